@@ -0,0 +1,76 @@
+//! Presence stream into a custom sink
+//!
+//! Integrators forwarding measurements to their own transport (a message bus, a database,
+//! a GPIO line) implement a small sink over the measurement types. This example ships two:
+//! JSON lines on stdout and an edge-triggered sink that only reports presence changes, fed
+//! from the presence stream of a simulated module.
+//!
+//! ```text
+//! cargo run --example custom_sink
+//! ```
+
+use futures::StreamExt;
+use std::pin::pin;
+use std::time::Duration;
+use xm125_radar_monitor::error::Result;
+use xm125_radar_monitor::gpio::XM125GpioPins;
+use xm125_radar_monitor::i2c::I2cDevice;
+use xm125_radar_monitor::radar::stream::StreamConfig;
+use xm125_radar_monitor::radar::{PresenceMeasurement, XM125Radar};
+use xm125_radar_monitor::xm125_protocol::Application;
+
+/// Destination for presence measurements
+trait Sink {
+    fn publish(&mut self, measurement: &PresenceMeasurement);
+}
+
+/// Every measurement as one JSON line
+struct JsonLines;
+
+impl Sink for JsonLines {
+    fn publish(&mut self, measurement: &PresenceMeasurement) {
+        match serde_json::to_string(measurement) {
+            Ok(line) => println!("{line}"),
+            Err(e) => eprintln!("measurement not serializable: {e}"),
+        }
+    }
+}
+
+/// Only the measurements where presence appears or disappears
+#[derive(Default)]
+struct Edges {
+    present: Option<bool>,
+}
+
+impl Sink for Edges {
+    fn publish(&mut self, measurement: &PresenceMeasurement) {
+        let present = measurement.presence_detected;
+        if self.present.replace(present) != Some(present) {
+            let change = if present { "ENTERED" } else { "LEFT" };
+            println!(
+                "{change} at {} ({:.2} m)",
+                measurement.timestamp.format("%H:%M:%S%.3f"),
+                measurement.presence_distance
+            );
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let i2c = I2cDevice::simulated(Application::Presence, 0x52);
+    let mut radar = XM125Radar::new(i2c, XM125GpioPins::default());
+
+    let mut sinks: Vec<Box<dyn Sink>> = vec![Box::new(JsonLines), Box::<Edges>::default()];
+    let mut stream = pin!(radar.presence_stream(StreamConfig {
+        interval: Duration::from_millis(100),
+        count: Some(30),
+    }));
+    while let Some(measurement) = stream.next().await {
+        let measurement = measurement?;
+        for sink in &mut sinks {
+            sink.publish(&measurement);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! Single distance measurement through the library API
+//!
+//! Connects to a simulated module running the distance detector firmware, configures the
+//! detector for 0.2-3 m and prints one measurement. On hardware, open the bus with
+//! `I2cDevice::new("/dev/i2c-2", 0x52)` instead; everything else stays the same.
+//!
+//! ```text
+//! cargo run --example distance_read
+//! ```
+
+use xm125_radar_monitor::error::Result;
+use xm125_radar_monitor::gpio::XM125GpioPins;
+use xm125_radar_monitor::i2c::I2cDevice;
+use xm125_radar_monitor::radar::{DetectorMode, XM125Radar};
+use xm125_radar_monitor::xm125_protocol::Application;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let i2c = I2cDevice::simulated(Application::Distance, 0x52);
+    let mut radar = XM125Radar::new(i2c, XM125GpioPins::default());
+    radar.connect()?;
+    radar.set_detector_mode(DetectorMode::Distance);
+    radar.config.start_m = 0.2;
+    radar.config.length_m = 2.8;
+
+    let result = radar.measure_distance().await?;
+    if result.measure_error {
+        println!("No target (module temperature {}°C)", result.temperature);
+    } else {
+        println!(
+            "Target at {:.3} m, {:.1} dB (module temperature {}°C)",
+            result.distance, result.strength_db, result.temperature
+        );
+    }
+    Ok(())
+}
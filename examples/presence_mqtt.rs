@@ -0,0 +1,50 @@
+//! Presence stream published to MQTT
+//!
+//! Streams twenty presence measurements from a simulated module and publishes each as a
+//! JSON record (the FIFO JSON layout the monitor uses) on `xm125/presence`. The broker
+//! defaults to `localhost:1883`; pass another as `[mqtt://][user:password@]host[:port]`.
+//! Records are dropped, not queued forever, while the broker is unreachable, so the example
+//! also runs without one.
+//!
+//! ```text
+//! cargo run --example presence_mqtt --features mqtt -- mqtt://broker.local
+//! mosquitto_sub -h broker.local -t xm125/presence
+//! ```
+
+use futures::StreamExt;
+use std::pin::pin;
+use std::time::Duration;
+use xm125_radar_monitor::error::{RadarError, Result};
+use xm125_radar_monitor::gpio::XM125GpioPins;
+use xm125_radar_monitor::i2c::I2cDevice;
+use xm125_radar_monitor::mqtt::{parse_broker, MqttSink};
+use xm125_radar_monitor::radar::stream::StreamConfig;
+use xm125_radar_monitor::radar::XM125Radar;
+use xm125_radar_monitor::xm125_protocol::Application;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let broker = std::env::args().nth(1).unwrap_or("localhost".to_string());
+    let broker = parse_broker(&broker).map_err(RadarError::InvalidParameters)?;
+    let sink = MqttSink::connect(&broker, "xm125/presence", None, None);
+
+    let i2c = I2cDevice::simulated(Application::Presence, 0x52);
+    let mut radar = XM125Radar::new(i2c, XM125GpioPins::default());
+
+    let mut stream = pin!(radar.presence_stream(StreamConfig {
+        interval: Duration::from_millis(250),
+        count: Some(20),
+    }));
+    while let Some(measurement) = stream.next().await {
+        let measurement = measurement?;
+        println!(
+            "presence: {} at {:.2} m",
+            measurement.presence_detected, measurement.presence_distance
+        );
+        sink.publish_presence(&measurement);
+    }
+
+    // Give the background connection time to deliver the last records
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    Ok(())
+}
@@ -0,0 +1,296 @@
+//! Windowed aggregation of measurements
+//!
+//! Occupancy analytics ingest one record per fixed time window rather than every frame.
+//! With `--window`, the monitoring loops feed each admitted measurement into an aggregator
+//! and emit a min/max/mean summary whenever a window closes. Windows are aligned to the
+//! first sample and advance in whole steps, so gaps in the measurement stream never shift
+//! later window boundaries.
+
+use crate::radar::{DistanceMeasurement, PresenceMeasurement};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Minimum, maximum and mean of a value over a window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Stats {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+/// Running min/max/sum for one value
+#[derive(Debug, Clone, Copy, Default)]
+struct Accumulator {
+    count: u32,
+    sum: f64,
+    min: f32,
+    max: f32,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: f32) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.count += 1;
+        self.sum += f64::from(value);
+    }
+
+    fn stats(&self) -> Option<Stats> {
+        (self.count > 0).then(|| Stats {
+            min: self.min,
+            max: self.max,
+            mean: (self.sum / f64::from(self.count)) as f32,
+        })
+    }
+}
+
+/// Window boundaries anchored to the first sample
+#[derive(Debug, Clone)]
+struct WindowClock {
+    length_ms: i64,
+    start: Option<DateTime<Utc>>,
+}
+
+impl WindowClock {
+    fn new(length: Duration) -> Self {
+        Self {
+            length_ms: i64::try_from(length.as_millis()).unwrap_or(i64::MAX).max(1),
+            start: None,
+        }
+    }
+
+    /// Place a sample; returns the bounds of the window it closes, if any
+    fn advance(&mut self, timestamp: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let start = *self.start.get_or_insert(timestamp);
+        let elapsed_ms = (timestamp - start).num_milliseconds();
+        if elapsed_ms < self.length_ms {
+            return None;
+        }
+
+        // Skip windows without samples so boundaries stay on the original grid
+        let steps = elapsed_ms / self.length_ms;
+        self.start = Some(start + chrono::Duration::milliseconds(steps * self.length_ms));
+        Some((
+            start,
+            start + chrono::Duration::milliseconds(self.length_ms),
+        ))
+    }
+
+    /// Bounds of the current window, closed at `now`
+    fn current(&self, now: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        self.start.map(|start| {
+            let end = start + chrono::Duration::milliseconds(self.length_ms);
+            (start, now.min(end))
+        })
+    }
+}
+
+/// Aggregated presence scores for one window
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenceWindow {
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub samples: u32,
+    /// Samples with presence detected
+    pub detections: u32,
+    pub intra_score: Stats,
+    pub inter_score: Stats,
+    /// Presence distance over detected samples only
+    pub presence_distance_m: Option<Stats>,
+}
+
+impl PresenceWindow {
+    /// Fraction of samples with presence detected
+    pub fn presence_ratio(&self) -> f32 {
+        self.detections as f32 / self.samples.max(1) as f32
+    }
+}
+
+/// Aggregated distance readings for one window
+#[derive(Debug, Clone, Serialize)]
+pub struct DistanceWindow {
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub samples: u32,
+    /// Samples with a valid target
+    pub targets: u32,
+    /// Distance over samples with a valid target only
+    pub distance_m: Option<Stats>,
+    pub signal_strength: Option<Stats>,
+}
+
+/// Running totals for the open presence window
+#[derive(Debug, Clone, Default)]
+struct PresenceTotals {
+    samples: u32,
+    detections: u32,
+    intra: Accumulator,
+    inter: Accumulator,
+    distance: Accumulator,
+}
+
+/// Collects presence measurements into fixed windows
+#[derive(Debug, Clone)]
+pub struct PresenceAggregator {
+    clock: WindowClock,
+    totals: PresenceTotals,
+}
+
+impl PresenceAggregator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            clock: WindowClock::new(window),
+            totals: PresenceTotals::default(),
+        }
+    }
+
+    /// Add a measurement; returns the previous window when this sample closes it
+    pub fn push(&mut self, result: &PresenceMeasurement) -> Option<PresenceWindow> {
+        let closed = self
+            .clock
+            .advance(result.timestamp)
+            .and_then(|(start, end)| self.take(start, end));
+
+        let totals = &mut self.totals;
+        totals.samples += 1;
+        totals.intra.add(result.intra_presence_score);
+        totals.inter.add(result.inter_presence_score);
+        if result.presence_detected {
+            totals.detections += 1;
+            totals.distance.add(result.presence_distance);
+        }
+        closed
+    }
+
+    /// Emit the partial window at the end of monitoring
+    pub fn flush(&mut self) -> Option<PresenceWindow> {
+        let (start, end) = self.clock.current(Utc::now())?;
+        self.take(start, end)
+    }
+
+    fn take(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<PresenceWindow> {
+        let totals = std::mem::take(&mut self.totals);
+        Some(PresenceWindow {
+            window_start: start,
+            window_end: end,
+            samples: totals.samples,
+            detections: totals.detections,
+            intra_score: totals.intra.stats()?,
+            inter_score: totals.inter.stats()?,
+            presence_distance_m: totals.distance.stats(),
+        })
+    }
+}
+
+/// Running totals for the open distance window
+#[derive(Debug, Clone, Default)]
+struct DistanceTotals {
+    samples: u32,
+    distance: Accumulator,
+    strength: Accumulator,
+}
+
+/// Collects distance measurements into fixed windows
+#[derive(Debug, Clone)]
+pub struct DistanceAggregator {
+    clock: WindowClock,
+    totals: DistanceTotals,
+}
+
+impl DistanceAggregator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            clock: WindowClock::new(window),
+            totals: DistanceTotals::default(),
+        }
+    }
+
+    /// Add a measurement; returns the previous window when this sample closes it
+    pub fn push(&mut self, result: &DistanceMeasurement) -> Option<DistanceWindow> {
+        let closed = self
+            .clock
+            .advance(result.timestamp)
+            .and_then(|(start, end)| self.take(start, end));
+
+        self.totals.samples += 1;
+        if !result.measure_error && result.distance > 0.0 {
+            self.totals.distance.add(result.distance);
+            self.totals.strength.add(result.strength);
+        }
+        closed
+    }
+
+    /// Emit the partial window at the end of monitoring
+    pub fn flush(&mut self) -> Option<DistanceWindow> {
+        let (start, end) = self.clock.current(Utc::now())?;
+        self.take(start, end)
+    }
+
+    fn take(&mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Option<DistanceWindow> {
+        let totals = std::mem::take(&mut self.totals);
+        (totals.samples > 0).then(|| DistanceWindow {
+            window_start: start,
+            window_end: end,
+            samples: totals.samples,
+            targets: totals.distance.count,
+            distance_m: totals.distance.stats(),
+            signal_strength: totals.strength.stats(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn presence(ms: i64, detected: bool, intra: f32) -> PresenceMeasurement {
+        PresenceMeasurement {
+            presence_detected: detected,
+            presence_distance: 1.5,
+            intra_presence_score: intra,
+            inter_presence_score: 0.5,
+            timestamp: Utc.timestamp_millis_opt(1_700_000_000_000 + ms).unwrap(),
+            config_generation: None,
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn test_presence_windows() {
+        let mut aggregator = PresenceAggregator::new(Duration::from_secs(1));
+        assert!(aggregator.push(&presence(0, false, 1.0)).is_none());
+        assert!(aggregator.push(&presence(500, true, 3.0)).is_none());
+
+        // A sample after a gap closes the first window and lands on the 1s grid
+        let window = aggregator.push(&presence(2_200, true, 2.0)).unwrap();
+        assert_eq!((window.samples, window.detections), (2, 1));
+        assert_eq!(window.presence_ratio(), 0.5);
+        assert_eq!(
+            window.intra_score,
+            Stats {
+                min: 1.0,
+                max: 3.0,
+                mean: 2.0
+            }
+        );
+        assert_eq!(window.presence_distance_m.unwrap().mean, 1.5);
+
+        let window = aggregator.push(&presence(3_000, false, 1.0)).unwrap();
+        assert_eq!(window.samples, 1);
+        assert_eq!(
+            (window.window_end - window.window_start).num_milliseconds(),
+            1000
+        );
+        assert_eq!(
+            window.window_start,
+            Utc.timestamp_millis_opt(1_700_000_002_000).unwrap()
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Command aliases and default command from the settings file
+//!
+//! The `--config` file may define `aliases`, extra subcommand names that expand to a preset
+//! argument list (`occupancy = ["presence", "--range", "long", "--continuous"]`), and a
+//! `default_command` run when no subcommand is given. Expansion happens on the raw arguments
+//! before they are parsed, so everything after the alias is appended to its preset and
+//! global options before it still apply. Built-in subcommands cannot be shadowed.
+//! `--print-effective-command` shows the command line that results.
+
+use crate::cli::Cli;
+use crate::error::RadarError;
+use crate::settings::Settings;
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{CommandFactory, Parser};
+use std::ffi::OsString;
+
+/// Values of every `--config` in raw arguments
+fn config_paths(args: &[OsString]) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut args = args.iter().skip(1).map(|arg| arg.to_string_lossy());
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            paths.extend(args.next().map(|path| path.into_owned()));
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
+/// Refuse aliases that would be unreachable behind a built-in subcommand
+pub fn check_aliases(settings: &Settings) -> Result<(), RadarError> {
+    let command = Cli::command();
+    for name in settings.aliases.keys() {
+        if command.find_subcommand(name).is_some() {
+            return Err(RadarError::InvalidParameters(format!(
+                "alias '{name}' in the settings file shadows the built-in '{name}' subcommand"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Unknown subcommand clap stopped at, if that is why parsing failed
+fn unknown_subcommand(error: &clap::Error) -> Option<String> {
+    if error.kind() != ErrorKind::InvalidSubcommand {
+        return None;
+    }
+    match error.get(ContextKind::InvalidSubcommand)? {
+        ContextValue::String(name) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// Expand an alias or supply the default command from the `--config` file
+///
+/// Arguments are returned unchanged without a settings file, or when they already parse
+/// with a subcommand (or fail for a reason aliases cannot fix).
+pub fn expand(mut args: Vec<OsString>) -> Result<Vec<OsString>, RadarError> {
+    let paths = config_paths(&args);
+    if paths.is_empty() {
+        return Ok(args);
+    }
+    let settings = Settings::load_layers(&paths)?;
+    check_aliases(&settings)?;
+
+    match Cli::try_parse_from(&args) {
+        Ok(cli) => {
+            if cli.command.is_none() && !cli.version {
+                if let Some(default) = &settings.default_command {
+                    args.extend(default.iter().map(OsString::from));
+                }
+            }
+        }
+        Err(error) => {
+            let Some(name) = unknown_subcommand(&error) else {
+                return Ok(args);
+            };
+            let Some(preset) = settings.aliases.get(&name) else {
+                return Ok(args);
+            };
+            if let Some(position) = args.iter().skip(1).position(|arg| *arg == *name) {
+                args.splice(
+                    position + 1..=position + 1,
+                    preset.iter().map(OsString::from),
+                );
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Arguments joined for display, quoting any that the shell would split or expand
+pub fn shell_join(args: &[OsString]) -> String {
+    args.iter()
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            let plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./:=,@+%".contains(c));
+            if plain {
+                arg.into_owned()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<OsString> {
+        line.split_whitespace().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn test_alias_and_default_command_expansion() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xm125.toml");
+        std::fs::write(
+            &path,
+            r#"
+            default_command = ["status"]
+
+            [aliases]
+            occupancy = ["presence", "--range", "long", "--continuous"]
+            "#,
+        )
+        .unwrap();
+        let config = path.to_str().unwrap();
+
+        let expanded = expand(args(&format!(
+            "xm125 -q --config {config} occupancy --count 5"
+        )))
+        .unwrap();
+        assert_eq!(
+            shell_join(&expanded),
+            format!("xm125 -q --config {config} presence --range long --continuous --count 5")
+        );
+        let expanded = expand(args(&format!("xm125 --config={config}"))).unwrap();
+        assert_eq!(expanded.last().unwrap(), "status");
+        // Built-in subcommands and runs without a settings file are left alone
+        let untouched = args(&format!("xm125 --config {config} info"));
+        assert_eq!(expand(untouched.clone()).unwrap(), untouched);
+        assert_eq!(
+            expand(args("xm125 occupancy")).unwrap(),
+            args("xm125 occupancy")
+        );
+
+        std::fs::write(&path, "[aliases]\ninfo = [\"status\"]\n").unwrap();
+        assert!(expand(args(&format!("xm125 --config {config} status"))).is_err());
+        assert_eq!(shell_join(&args("a")), "a");
+        assert_eq!(shell_join(&[OsString::from("it's")]), r"'it'\''s'");
+    }
+}
@@ -0,0 +1,311 @@
+//! Command-line application behind the `xm125-radar-monitor` binary
+
+use crate::cli::{Cli, Commands, FirmwareAction, WaitMode};
+use crate::commands::{
+    execute_command, execute_local_command, needs_hardware, required_firmware, writable_paths,
+    Context,
+};
+use crate::error::RadarError;
+use crate::fifo::FifoWriter;
+use crate::mqtt::MqttSink;
+use crate::radar::{QuirkTable, XM125Radar};
+use crate::shm::ShmWriter;
+use crate::{
+    aliases, app_id_cache, console, device_lock, heartbeat, i2c, i2c_errors, log_file, log_level,
+    manifest, paths, plain, settings, stats, telemetry, timestamps, version,
+};
+use clap::{CommandFactory, FromArgMatches};
+use log::{error, info, warn};
+use std::env;
+use std::ffi::OsString;
+use std::io::Write;
+use std::process;
+
+/// Why a command line could not be turned into a [`Cli`]
+pub(crate) enum ArgsError {
+    /// Usage error, help or version request, rendered by clap
+    Usage(clap::Error),
+    /// The settings file behind aliases or `--config-profile` could not be used
+    Settings(RadarError),
+}
+
+/// Expand aliases and the default command in `args`, parse them and apply `--config-profile`
+///
+/// The command line and every interactive shell line go through here, so both see the same
+/// aliases and profile values.
+pub(crate) fn parse_args(args: Vec<OsString>) -> Result<Cli, ArgsError> {
+    let args = aliases::expand(args).map_err(ArgsError::Settings)?;
+    let matches = Cli::command()
+        .try_get_matches_from(&args)
+        .map_err(ArgsError::Usage)?;
+    let mut cli = Cli::from_arg_matches(&matches).map_err(ArgsError::Usage)?;
+    settings::apply_profile(&mut cli, &matches).map_err(ArgsError::Settings)?;
+    cli.effective_args = args;
+    Ok(cli)
+}
+
+/// Application entry point
+pub async fn main() {
+    let cli = match parse_args(env::args_os().collect()) {
+        Ok(cli) => cli,
+        Err(ArgsError::Usage(e)) => e.exit(),
+        Err(ArgsError::Settings(e)) => {
+            eprintln!("Error: {e}");
+            process::exit(e.exit_code());
+        }
+    };
+    if cli.print_effective_command {
+        println!("{}", aliases::shell_join(&cli.effective_args));
+        return;
+    }
+    if cli.output.plain {
+        plain::enable();
+    }
+    timestamps::set_format(cli.output.timestamps);
+
+    // Version report honours --format and needs no subcommand
+    if cli.version {
+        if let Err(e) = version::print_version(&cli.output.format) {
+            eprintln!("Failed to print version: {e}");
+            process::exit(1);
+        }
+        return;
+    }
+    if cli.command.is_none() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingSubcommand,
+                "a subcommand is required (see --help)",
+            )
+            .exit();
+    }
+
+    // Initialize logging
+    if let Some(path) = &cli.logging.log_file {
+        if let Err(e) = log_file::open(path) {
+            eprintln!("Failed to open log file {path}: {e}");
+            process::exit(1);
+        }
+    }
+    let mut logger = env_logger::Builder::from_default_env();
+    // The global max level filters records, so log_level can change it at runtime
+    logger.filter_level(log::LevelFilter::Trace);
+    logger.target(env_logger::Target::Pipe(Box::new(log_file::Diagnostics)));
+    if cli.output.plain {
+        logger.format(|buf, record| {
+            writeln!(
+                buf,
+                "[{} {} {}] {}",
+                buf.timestamp(),
+                record.level(),
+                record.target(),
+                plain::sanitize(&record.args().to_string())
+            )
+        });
+    }
+    logger.init();
+    log::set_max_level(if cli.logging.verbose {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    });
+    log_level::cycle_on_sigusr1();
+
+    // Run the application; optional OTLP export is flushed when the guard goes out of scope
+    console::set_flush_interval(cli.flush_interval());
+    if let Some(path) = &cli.output.heartbeat_file {
+        heartbeat::init(path, cli.output.heartbeat_max_age);
+    }
+    let result = {
+        let _telemetry = telemetry::init(cli.otel_endpoint());
+        run(cli).await
+    };
+    heartbeat::stop();
+    console::flush();
+    if let Err(e) = result {
+        error!("Application error: {e}");
+        i2c_errors::log_recent();
+        process::exit(e.exit_code());
+    }
+}
+
+/// Main application logic
+async fn run(cli: Cli) -> Result<(), RadarError> {
+    // Catch missing/corrupt firmware binaries before they are needed mid-update
+    if !matches!(
+        cli.subcommand(),
+        Commands::Firmware {
+            action: FirmwareAction::Manifest { .. }
+        }
+    ) {
+        manifest::startup_check(&cli.firmware_path);
+    }
+    // A simulated module is never flashed
+    if cli.simulate.is_none() {
+        cli.firmware_paths()
+            .ensure_exist(&required_firmware(cli.subcommand()))?;
+    }
+
+    let gpio_problems: Vec<String> = cli
+        .get_gpio_pins()
+        .signals
+        .problems()
+        .into_iter()
+        .map(|(field, problem)| format!("--gpio-{} {problem}", field.replace('_', "-")))
+        .collect();
+    if !gpio_problems.is_empty() {
+        return Err(RadarError::InvalidParameters(gpio_problems.join("; ")));
+    }
+    if cli.wait_mode == WaitMode::Interrupt
+        && (cli.simulate.is_some() || cli.i2c_playback.is_some())
+    {
+        return Err(RadarError::InvalidParameters(
+            "--wait-mode interrupt needs the MCU_INT line of a real module".to_string(),
+        ));
+    }
+    if cli.simulate.is_some() && needs_hardware(cli.subcommand()) {
+        return Err(RadarError::InvalidParameters(
+            "--simulate does not emulate the bootloader or GPIO lines; run this command against the module".to_string(),
+        ));
+    }
+
+    // Handle commands that don't need I2C connection first
+    if let Some(result) = execute_local_command(&cli).await {
+        return result;
+    }
+
+    // Print startup banner unless quiet mode; it is a diagnostic, not measurement data
+    if !cli.output.quiet {
+        eprintln!("xm125-radar-monitor v{}", env!("CARGO_PKG_VERSION"));
+        eprintln!("Copyright (c) 2025 Dynamic Devices Ltd. All rights reserved.");
+        eprintln!("XM125 Radar Module Monitor");
+        eprintln!(
+            "I2C: {} @ 0x{:02X} | Auto-reconnect: ON",
+            cli.get_i2c_device_path(),
+            cli.i2c_address
+        );
+        eprintln!();
+    }
+
+    // Fail now rather than mid-session if a file this run writes is on a read-only mount
+    for (path, option) in writable_paths(&cli) {
+        paths::ensure_writable(&path, option)?;
+    }
+    #[cfg(feature = "sign")]
+    if let Some(path) = &cli.output.sign_key {
+        crate::sign::init(path)?;
+    }
+    #[cfg(feature = "fault-injection")]
+    if let Some(plan) = cli.inject_failure.clone() {
+        crate::fault::init(plan, cli.inject_seed);
+    }
+
+    // Refuse to share the module with another instance; a simulated module is never shared
+    let _device_lock = match cli.simulate {
+        Some(_) => None,
+        None => Some(device_lock::DeviceLock::acquire(
+            cli.lock_dir_path(),
+            &cli.get_i2c_device_path(),
+            cli.i2c_address,
+            cli.takeover,
+        )?),
+    };
+
+    // Initialize I2C and radar with GPIO pins
+    let mut i2c_device = match (&cli.i2c_playback, cli.simulate) {
+        (Some(trace), _) => {
+            i2c::I2cDevice::playback(trace, cli.i2c_address, cli.i2c_playback_speed)?
+        }
+        (None, Some(firmware)) => i2c::I2cDevice::simulated(firmware.into(), cli.i2c_address),
+        (None, None) => i2c::I2cDevice::new(&cli.get_i2c_device_path(), cli.i2c_address)?,
+    };
+    if let Some(trace) = &cli.i2c_record {
+        i2c_device.record_to(trace)?;
+    }
+    i2c_device.set_transaction_delay(cli.i2c_delay);
+    let gpio_pins = cli.get_gpio_pins();
+    let mut radar = XM125Radar::new(i2c_device, gpio_pins);
+    radar.config.timeouts = cli.timeouts();
+    radar.set_strict_protocol(cli.strict_protocol);
+    radar.set_write_verify(cli.write_verify);
+    radar.set_interrupt_wait(cli.wait_mode == WaitMode::Interrupt)?;
+    radar.set_include_raw(cli.output.include_raw);
+    radar.set_privacy(cli.local_privacy_bucket());
+    let quirk_table = cli
+        .quirk_table
+        .as_deref()
+        .map(QuirkTable::load)
+        .transpose()?
+        .unwrap_or_default();
+    radar.set_quirks(quirk_table, cli.force_quirk.as_deref())?;
+    radar.set_app_id_cache(app_id_cache::AppIdCache::new(
+        cli.app_id_cache_path().as_deref(),
+        &cli.get_i2c_device_path(),
+        cli.i2c_address,
+    ));
+    radar.set_stats(stats::StatsRecorder::new(
+        cli.stats_path().as_deref(),
+        &cli.get_i2c_device_path(),
+        cli.i2c_address,
+    ));
+
+    // Initialize FIFO writer if enabled
+    let mut fifo_writer = if cli.output.fifo_output {
+        match FifoWriter::new(&cli.output.fifo_path, cli.output.fifo_interval) {
+            Ok(writer) => {
+                let writer = writer.with_privacy(cli.privacy_bucket());
+                if cli.output.fifo_interval > 0.0 {
+                    info!("FIFO output enabled: {} (format: {:?}, interval: {:.1}s - spi-lib compatible)", 
+                          cli.output.fifo_path, cli.output.fifo_format, cli.output.fifo_interval);
+                } else {
+                    info!(
+                        "FIFO output enabled: {} (format: {:?}, real-time mode)",
+                        cli.output.fifo_path, cli.output.fifo_format
+                    );
+                }
+                // Send startup status (same as spi-lib)
+                let _ = writer.write_status("Starting up");
+                Some(writer)
+            }
+            Err(e) => {
+                warn!("Failed to initialize FIFO writer: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Shared-memory output was asked for explicitly, so failing to create it is fatal
+    let shm_writer = cli.output.shm.as_deref().map(ShmWriter::open).transpose()?;
+
+    #[cfg(feature = "mqtt")]
+    let mqtt_sink = cli.output.mqtt_broker.as_ref().map(|broker| {
+        let topic = cli
+            .output
+            .mqtt_topic
+            .clone()
+            .unwrap_or_else(|| format!("xm125/{}", crate::discovery::device_id()));
+        MqttSink::connect(
+            broker,
+            &topic,
+            cli.output.mqtt_discovery_prefix.as_deref(),
+            cli.privacy_bucket(),
+        )
+    });
+    #[cfg(not(feature = "mqtt"))]
+    let mqtt_sink: Option<MqttSink> = None;
+
+    // Execute the command
+    let mut ctx = Context::new(&cli, &mut radar, fifo_writer.as_mut())
+        .with_shm(shm_writer.as_ref())
+        .with_mqtt(mqtt_sink.as_ref());
+    execute_command(&mut ctx).await?;
+
+    // Send exit status if FIFO is enabled
+    if let Some(ref writer) = fifo_writer {
+        let _ = writer.write_status("App exit");
+    }
+
+    Ok(())
+}
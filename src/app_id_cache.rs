@@ -0,0 +1,169 @@
+//! Cache of the firmware identity read at connect time
+//!
+//! Every connect reads the application ID (to select the register protocol and refuse the
+//! wrong firmware) and the RSS version. The result is kept in memory for the life of the
+//! radar and written to a small file so repeated CLI invocations can skip those reads.
+//! Anything that resets, erases or reflashes the module removes the file; the default
+//! location under `/run` is also cleared on reboot.
+
+use crate::journal::write_atomic;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Identity registers read from the module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirmwareIdentity {
+    pub app_id: u32,
+    /// Raw RSS version register
+    pub rss_version: u32,
+}
+
+/// On-disk cache entry, tied to the bus and address it was read from
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    i2c_device: String,
+    i2c_address: u16,
+    app_id: u32,
+    rss_version: u32,
+    cached_at: DateTime<Utc>,
+}
+
+/// In-process and on-disk firmware identity cache for one module
+#[derive(Debug, Clone, Default)]
+pub struct AppIdCache {
+    path: Option<PathBuf>,
+    i2c_device: String,
+    i2c_address: u16,
+    identity: Option<FirmwareIdentity>,
+}
+
+impl AppIdCache {
+    /// Cache for the module at `i2c_device`/`i2c_address`; `path: None` keeps it in memory only
+    pub fn new(path: Option<&str>, i2c_device: &str, i2c_address: u16) -> Self {
+        Self {
+            path: path.map(PathBuf::from),
+            i2c_device: i2c_device.to_string(),
+            i2c_address,
+            identity: None,
+        }
+    }
+
+    /// Cached identity, loading the cache file on first use
+    pub fn get(&mut self) -> Option<FirmwareIdentity> {
+        if self.identity.is_none() {
+            self.identity = self.load();
+        }
+        self.identity
+    }
+
+    /// Remember a freshly read identity
+    pub fn store(&mut self, identity: FirmwareIdentity) {
+        self.identity = Some(identity);
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let entry = CacheEntry {
+            i2c_device: self.i2c_device.clone(),
+            i2c_address: self.i2c_address,
+            app_id: identity.app_id,
+            rss_version: identity.rss_version,
+            cached_at: Utc::now(),
+        };
+        let saved = toml::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                write_atomic(path, contents.as_bytes()).map_err(|e| e.to_string())
+            });
+        if let Err(e) = saved {
+            warn!(
+                "Failed to write application ID cache {}: {e}",
+                path.display()
+            );
+        }
+    }
+
+    /// Forget the identity after the module was reset or reflashed
+    pub fn invalidate(&mut self) {
+        self.identity = None;
+        if let Some(path) = &self.path {
+            invalidate_file(path);
+        }
+    }
+
+    fn load(&self) -> Option<FirmwareIdentity> {
+        let path = self.path.as_ref()?;
+        let contents = fs::read_to_string(path).ok()?;
+        let entry: CacheEntry = match toml::from_str(&contents) {
+            Ok(entry) => entry,
+            Err(e) => {
+                debug!(
+                    "Ignoring unreadable application ID cache {}: {e}",
+                    path.display()
+                );
+                return None;
+            }
+        };
+        if entry.i2c_device != self.i2c_device || entry.i2c_address != self.i2c_address {
+            debug!(
+                "Application ID cache is for {} @ 0x{:02X}, ignoring",
+                entry.i2c_device, entry.i2c_address
+            );
+            return None;
+        }
+        Some(FirmwareIdentity {
+            app_id: entry.app_id,
+            rss_version: entry.rss_version,
+        })
+    }
+}
+
+/// Remove a cache file, for commands that reset the module without a radar connection
+pub fn invalidate_file(path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    match fs::remove_file(path) {
+        Ok(()) => debug!("Application ID cache {} invalidated", path.display()),
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => warn!(
+            "Failed to remove application ID cache {}: {e}",
+            path.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_roundtrip_and_invalidation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run/app-id.toml");
+        let path = path.to_str().unwrap();
+        let identity = FirmwareIdentity {
+            app_id: 2,
+            rss_version: 0x0001_0000,
+        };
+
+        AppIdCache::new(Some(path), "/dev/i2c-2", 0x52).store(identity);
+        assert_eq!(
+            AppIdCache::new(Some(path), "/dev/i2c-2", 0x52).get(),
+            Some(identity)
+        );
+        // Another module on the same host must not reuse the entry
+        assert_eq!(AppIdCache::new(Some(path), "/dev/i2c-1", 0x52).get(), None);
+
+        let mut cache = AppIdCache::new(Some(path), "/dev/i2c-2", 0x52);
+        assert!(cache.get().is_some());
+        cache.invalidate();
+        assert_eq!(cache.get(), None);
+        assert!(!Path::new(path).exists());
+    }
+}
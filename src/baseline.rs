@@ -0,0 +1,205 @@
+//! Empty-room false-positive survey for site acceptance testing
+//!
+//! With the room guaranteed empty every presence detection is a false positive. The
+//! survey records each one with its scores and distance and summarizes them as a rate,
+//! a distance histogram and threshold suggestions that would have suppressed them.
+
+use crate::radar::PresenceMeasurement;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Width of the distance histogram bins in meters
+const HISTOGRAM_BIN_M: f32 = 0.5;
+/// Margin applied above the highest empty-room score when suggesting thresholds
+const THRESHOLD_MARGIN: f32 = 1.2;
+
+/// A detection recorded while the room was empty
+#[derive(Debug, Clone, Serialize)]
+pub struct FalsePositive {
+    pub timestamp: DateTime<Utc>,
+    pub distance_m: f32,
+    pub intra_score: f32,
+    pub inter_score: f32,
+}
+
+/// Number of false positives within a distance band
+#[derive(Debug, Clone, Serialize)]
+pub struct HistogramBin {
+    pub start_m: f32,
+    pub end_m: f32,
+    pub count: u32,
+}
+
+/// Survey summary
+#[derive(Debug, Clone, Serialize)]
+pub struct BaselineReport {
+    pub started_at: DateTime<Utc>,
+    pub duration_s: f64,
+    pub samples: u32,
+    pub false_positives: u32,
+    pub false_positives_per_hour: f64,
+    pub max_intra_score: f32,
+    pub max_inter_score: f32,
+    pub current_intra_threshold: f32,
+    pub current_inter_threshold: f32,
+    /// Suggested thresholds (None when the current threshold produced no false positives)
+    pub suggested_intra_threshold: Option<f32>,
+    pub suggested_inter_threshold: Option<f32>,
+    pub distance_histogram: Vec<HistogramBin>,
+    pub detections: Vec<FalsePositive>,
+}
+
+/// Accumulates presence measurements taken in an empty room
+#[derive(Debug)]
+pub struct BaselineSurvey {
+    started_at: DateTime<Utc>,
+    intra_threshold: f32,
+    inter_threshold: f32,
+    samples: u32,
+    max_intra: f32,
+    max_inter: f32,
+    detections: Vec<FalsePositive>,
+}
+
+impl BaselineSurvey {
+    /// Start a survey against the configured detection thresholds
+    pub fn new(intra_threshold: f32, inter_threshold: f32) -> Self {
+        Self {
+            started_at: Utc::now(),
+            intra_threshold,
+            inter_threshold,
+            samples: 0,
+            max_intra: 0.0,
+            max_inter: 0.0,
+            detections: Vec::new(),
+        }
+    }
+
+    /// Record a measurement; returns true if it was a false positive
+    pub fn record(&mut self, measurement: &PresenceMeasurement) -> bool {
+        self.samples += 1;
+        self.max_intra = self.max_intra.max(measurement.intra_presence_score);
+        self.max_inter = self.max_inter.max(measurement.inter_presence_score);
+
+        if measurement.presence_detected {
+            self.detections.push(FalsePositive {
+                timestamp: measurement.timestamp,
+                distance_m: measurement.presence_distance,
+                intra_score: measurement.intra_presence_score,
+                inter_score: measurement.inter_presence_score,
+            });
+        }
+        measurement.presence_detected
+    }
+
+    /// Number of false positives recorded so far
+    pub fn false_positives(&self) -> usize {
+        self.detections.len()
+    }
+
+    /// Build the survey report for the elapsed duration
+    pub fn report(&self) -> BaselineReport {
+        let duration_s = (Utc::now() - self.started_at).num_milliseconds() as f64 / 1000.0;
+        #[allow(clippy::cast_possible_truncation)] // Detection counts are small
+        let false_positives = self.detections.len() as u32;
+        let false_positives_per_hour = if duration_s > 0.0 {
+            f64::from(false_positives) * 3600.0 / duration_s
+        } else {
+            0.0
+        };
+
+        // A threshold only needs raising if that motion path produced false positives
+        let suggest = |score: fn(&FalsePositive) -> f32, max: f32, current: f32| {
+            self.detections
+                .iter()
+                .any(|d| score(d) >= current)
+                .then(|| (max * THRESHOLD_MARGIN).max(current))
+        };
+
+        BaselineReport {
+            started_at: self.started_at,
+            duration_s,
+            samples: self.samples,
+            false_positives,
+            false_positives_per_hour,
+            max_intra_score: self.max_intra,
+            max_inter_score: self.max_inter,
+            current_intra_threshold: self.intra_threshold,
+            current_inter_threshold: self.inter_threshold,
+            suggested_intra_threshold: suggest(
+                |d| d.intra_score,
+                self.max_intra,
+                self.intra_threshold,
+            ),
+            suggested_inter_threshold: suggest(
+                |d| d.inter_score,
+                self.max_inter,
+                self.inter_threshold,
+            ),
+            distance_histogram: self.histogram(),
+            detections: self.detections.clone(),
+        }
+    }
+
+    /// Histogram of false-positive distances in fixed-width bins
+    fn histogram(&self) -> Vec<HistogramBin> {
+        let max_distance = self
+            .detections
+            .iter()
+            .map(|d| d.distance_m)
+            .fold(0.0f32, f32::max);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Bounded by range
+        let bins = (max_distance / HISTOGRAM_BIN_M).floor() as usize + 1;
+
+        let mut histogram: Vec<HistogramBin> = (0..bins)
+            .map(|i| {
+                #[allow(clippy::cast_precision_loss)]
+                let start_m = i as f32 * HISTOGRAM_BIN_M;
+                HistogramBin {
+                    start_m,
+                    end_m: start_m + HISTOGRAM_BIN_M,
+                    count: 0,
+                }
+            })
+            .collect();
+        for detection in &self.detections {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let bin = (detection.distance_m.max(0.0) / HISTOGRAM_BIN_M).floor() as usize;
+            histogram[bin.min(bins - 1)].count += 1;
+        }
+        histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(detected: bool, distance: f32, intra: f32, inter: f32) -> PresenceMeasurement {
+        PresenceMeasurement {
+            presence_detected: detected,
+            presence_distance: distance,
+            intra_presence_score: intra,
+            inter_presence_score: inter,
+            timestamp: Utc::now(),
+            config_generation: None,
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn test_false_positive_report() {
+        let mut survey = BaselineSurvey::new(1.3, 1.0);
+        survey.record(&measurement(false, 0.0, 0.2, 0.3));
+        survey.record(&measurement(true, 1.2, 0.4, 1.5));
+        survey.record(&measurement(true, 1.4, 0.5, 2.0));
+
+        let report = survey.report();
+        assert_eq!(report.samples, 3);
+        assert_eq!(report.false_positives, 2);
+        assert_eq!(report.distance_histogram.len(), 3);
+        assert_eq!(report.distance_histogram[2].count, 2);
+        assert!(report.suggested_intra_threshold.is_none());
+        assert!((report.suggested_inter_threshold.unwrap() - 2.4).abs() < 1e-4);
+    }
+}
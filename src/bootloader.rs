@@ -0,0 +1,393 @@
+//! STM32 system bootloader over I2C (AN4221)
+//!
+//! Firmware update, readback and erase talk to the bootloader at 0x48 directly instead of
+//! running `stm32flash`, so constrained images need no external flashing tool. Every command
+//! is the command byte and its complement, answered by ACK (0x79) or NACK (0x1F); addresses
+//! and data frames carry an XOR checksum. When the bootloader lists the no-stretch variants
+//! of WRITE MEMORY and ERASE they are preferred: the bootloader answers BUSY (0x76) while the
+//! flash operation runs instead of holding SCL low, which some I2C controllers time out on.
+
+use crate::error::{RadarError, Result};
+use crate::i2c::I2cBackend;
+use log::debug;
+use std::time::{Duration, Instant};
+
+/// Acknowledge
+const ACK: u8 = 0x79;
+/// Not acknowledged (unknown command, protection, bad checksum)
+const NACK: u8 = 0x1F;
+/// No-stretch command still running
+const BUSY: u8 = 0x76;
+
+const CMD_GET: u8 = 0x00;
+const CMD_READ_MEMORY: u8 = 0x11;
+const CMD_GO: u8 = 0x21;
+const CMD_WRITE_MEMORY: u8 = 0x31;
+const CMD_WRITE_MEMORY_NO_STRETCH: u8 = 0x32;
+const CMD_ERASE: u8 = 0x44;
+const CMD_ERASE_NO_STRETCH: u8 = 0x45;
+
+/// Largest READ/WRITE MEMORY transfer
+pub const MAX_TRANSFER: usize = 256;
+/// Flash page size of the module's STM32L431
+const FLASH_PAGE_SIZE: u32 = 2048;
+/// Pages erased per ERASE command
+const ERASE_PAGES_PER_COMMAND: usize = 16;
+/// ERASE page count selecting a global mass erase
+const MASS_ERASE: u16 = 0xFFFF;
+/// Writes are padded with erased bytes to whole flash double words
+const WRITE_ALIGNMENT: usize = 8;
+/// Delay between polls for an ACK
+const ACK_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// RAM location for the software reset stub, above the bootloader's own RAM
+const RESET_STUB_ADDRESS: u32 = 0x2000_4000;
+
+/// Vector table and Thumb code requesting a system reset through AIRCR.SYSRESETREQ
+///
+/// GO loads the stack pointer from the first word and jumps to the second. The system reset
+/// reloads the option bytes like `stm32flash -R`, clearing the empty-flash flag that would
+/// otherwise keep the module in the bootloader after programming.
+fn reset_stub() -> Vec<u8> {
+    [
+        RESET_STUB_ADDRESS + 0x400, // Stack pointer
+        RESET_STUB_ADDRESS + 8 + 1, // Entry point (Thumb)
+        0x4902_4801,                // ldr r0, [pc, #4]; ldr r1, [pc, #8]
+        0xE7FE_6001,                // str r1, [r0]; b .
+        0xE000_ED0C,                // AIRCR
+        0x05FA_0004,                // VECTKEY | SYSRESETREQ
+    ]
+    .iter()
+    .flat_map(|word: &u32| word.to_le_bytes())
+    .collect()
+}
+
+fn xor(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |acc, b| acc ^ b)
+}
+
+/// Address frame: big-endian address followed by its XOR checksum
+fn address_frame(address: u32) -> [u8; 5] {
+    let [a, b, c, d] = address.to_be_bytes();
+    [a, b, c, d, a ^ b ^ c ^ d]
+}
+
+/// WRITE MEMORY data frame: byte count - 1, the data and the XOR checksum of both
+fn data_frame(data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(data.len() + 2);
+    #[allow(clippy::cast_possible_truncation)] // At most MAX_TRANSFER bytes
+    frame.push((data.len() - 1) as u8);
+    frame.extend_from_slice(data);
+    frame.push(xor(&frame));
+    frame
+}
+
+/// Big-endian 16-bit values followed by the XOR checksum of their bytes
+fn words_frame(words: &[u16]) -> Vec<u8> {
+    let mut frame: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+    frame.push(xor(&frame));
+    frame
+}
+
+/// Connection to the STM32 bootloader
+pub struct Bootloader {
+    bus: Box<dyn I2cBackend>,
+    address: u8,
+    version: u8,
+    commands: Vec<u8>,
+    /// Limit for each erase, write, read or reset
+    timeout: Duration,
+    deadline: Instant,
+}
+
+impl Bootloader {
+    /// Open `device_path` and ask the bootloader at `address` for its commands
+    pub fn open(device_path: &str, address: u16, timeout: Duration) -> Result<Self> {
+        let bus = linux_embedded_hal::I2cdev::new(device_path).map_err(|e| {
+            RadarError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Cannot open I2C device {device_path}: {e}"),
+            ))
+        })?;
+        #[allow(clippy::cast_possible_truncation)] // I2C addresses are 7-bit
+        Self::connect(Box::new(bus), address as u8, timeout)
+    }
+
+    fn connect(bus: Box<dyn I2cBackend>, address: u8, timeout: Duration) -> Result<Self> {
+        let mut bootloader = Self {
+            bus,
+            address,
+            version: 0,
+            commands: Vec::new(),
+            timeout,
+            deadline: Instant::now() + timeout,
+        };
+        bootloader.get()?;
+        debug!(
+            "STM32 bootloader v{}.{} commands: {:02X?}",
+            bootloader.version >> 4,
+            bootloader.version & 0x0F,
+            bootloader.commands
+        );
+        Ok(bootloader)
+    }
+
+    /// Bootloader protocol version, e.g. `0x11` for v1.1
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn supports(&self, command: u8) -> bool {
+        self.commands.contains(&command)
+    }
+
+    fn error(&self, message: String) -> RadarError {
+        RadarError::FirmwareError {
+            message: format!("STM32 bootloader at 0x{:02X}: {message}", self.address),
+        }
+    }
+
+    /// Allow the next operation the full timeout
+    fn start(&mut self) {
+        self.deadline = Instant::now() + self.timeout;
+    }
+
+    fn send(&mut self, frame: &[u8]) -> Result<()> {
+        self.bus.write(self.address, frame)
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<()> {
+        self.bus.read(self.address, buffer)
+    }
+
+    /// Wait for the ACK to the last frame, polling through BUSY answers and stretched reads
+    fn wait_ack(&mut self, what: &str) -> Result<()> {
+        loop {
+            let mut answer = [0u8];
+            match self.receive(&mut answer) {
+                Ok(()) if answer[0] == ACK => return Ok(()),
+                Ok(()) if answer[0] == NACK => return Err(self.error(format!("{what} refused"))),
+                Ok(()) if answer[0] == BUSY => {}
+                Ok(()) => {
+                    return Err(
+                        self.error(format!("unexpected answer 0x{:02X} to {what}", answer[0]))
+                    )
+                }
+                Err(e) => debug!("Bootloader not answering {what} yet: {e}"),
+            }
+            if Instant::now() >= self.deadline {
+                return Err(crate::firmware::flash_timeout_error(self.timeout));
+            }
+            std::thread::sleep(ACK_POLL_INTERVAL);
+        }
+    }
+
+    fn command(&mut self, command: u8) -> Result<()> {
+        self.send(&[command, !command])?;
+        self.wait_ack(&format!("command 0x{command:02X}"))
+    }
+
+    /// GET: protocol version and supported commands
+    fn get(&mut self) -> Result<()> {
+        self.command(CMD_GET)?;
+        let mut count = [0u8];
+        self.receive(&mut count)?;
+        let mut reply = vec![0u8; usize::from(count[0]) + 1];
+        self.receive(&mut reply)?;
+        self.wait_ack("GET")?;
+        self.version = reply[0];
+        self.commands = reply[1..].to_vec();
+        Ok(())
+    }
+
+    /// Erase `count` pages from `first`, or the whole flash for [`MASS_ERASE`]
+    fn erase(&mut self, first: u16, count: u16) -> Result<()> {
+        let command = if self.supports(CMD_ERASE_NO_STRETCH) {
+            CMD_ERASE_NO_STRETCH
+        } else {
+            CMD_ERASE
+        };
+        self.command(command)?;
+        if count == MASS_ERASE {
+            self.send(&words_frame(&[MASS_ERASE]))?;
+            return self.wait_ack("mass erase");
+        }
+        self.send(&words_frame(&[count - 1]))?;
+        self.wait_ack("erase page count")?;
+        let pages: Vec<u16> = (first..first + count).collect();
+        self.send(&words_frame(&pages))?;
+        self.wait_ack(&format!("erase of pages {first}-{}", first + count - 1))
+    }
+
+    /// Erase the whole flash
+    pub fn mass_erase(&mut self) -> Result<()> {
+        self.start();
+        self.erase(0, MASS_ERASE)
+    }
+
+    /// Erase the flash pages covering `length` bytes from `address`
+    pub fn erase_range(&mut self, address: u32, length: usize) -> Result<()> {
+        self.start();
+        let offset = address - crate::firmware::FLASH_BASE_ADDRESS;
+        #[allow(clippy::cast_possible_truncation)] // Flash is far below 4 GiB
+        let end = offset + length as u32;
+        #[allow(clippy::cast_possible_truncation)] // At most 65535 pages
+        let pages: Vec<u16> = (offset / FLASH_PAGE_SIZE..end.div_ceil(FLASH_PAGE_SIZE))
+            .map(|page| page as u16)
+            .collect();
+        for chunk in pages.chunks(ERASE_PAGES_PER_COMMAND) {
+            #[allow(clippy::cast_possible_truncation)] // At most ERASE_PAGES_PER_COMMAND
+            self.erase(chunk[0], chunk.len() as u16)?;
+        }
+        Ok(())
+    }
+
+    /// WRITE MEMORY of up to [`MAX_TRANSFER`] bytes
+    fn write_block(&mut self, address: u32, data: &[u8]) -> Result<()> {
+        let command = if self.supports(CMD_WRITE_MEMORY_NO_STRETCH) {
+            CMD_WRITE_MEMORY_NO_STRETCH
+        } else {
+            CMD_WRITE_MEMORY
+        };
+        self.command(command)?;
+        self.send(&address_frame(address))?;
+        self.wait_ack(&format!("write address 0x{address:08X}"))?;
+        self.send(&data_frame(data))?;
+        self.wait_ack(&format!("write at 0x{address:08X}"))
+    }
+
+    /// READ MEMORY of up to [`MAX_TRANSFER`] bytes
+    fn read_block(&mut self, address: u32, buffer: &mut [u8]) -> Result<()> {
+        self.command(CMD_READ_MEMORY)?;
+        self.send(&address_frame(address))?;
+        self.wait_ack(&format!("read address 0x{address:08X}"))?;
+        #[allow(clippy::cast_possible_truncation)] // At most MAX_TRANSFER bytes
+        let count = (buffer.len() - 1) as u8;
+        self.send(&[count, !count])?;
+        self.wait_ack("read length")?;
+        self.receive(buffer)
+    }
+
+    /// Write `data` from `address` in [`MAX_TRANSFER`] blocks, reporting bytes written
+    ///
+    /// The last block is padded with erased bytes to a whole flash double word.
+    pub fn write(
+        &mut self,
+        address: u32,
+        data: &[u8],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<()> {
+        self.start();
+        let mut written = 0;
+        for block in data.chunks(MAX_TRANSFER) {
+            let mut padded = block.to_vec();
+            padded.resize(block.len().next_multiple_of(WRITE_ALIGNMENT), 0xFF);
+            #[allow(clippy::cast_possible_truncation)] // Flash is far below 4 GiB
+            self.write_block(address + written as u32, &padded)?;
+            written += block.len();
+            progress(written, data.len());
+        }
+        Ok(())
+    }
+
+    /// Read `length` bytes from `address`, reporting bytes read
+    pub fn read(
+        &mut self,
+        address: u32,
+        length: usize,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<u8>> {
+        self.start();
+        let mut data = vec![0u8; length];
+        let mut read = 0;
+        for block in data.chunks_mut(MAX_TRANSFER) {
+            #[allow(clippy::cast_possible_truncation)] // Flash is far below 4 GiB
+            self.read_block(address + read as u32, block)?;
+            read += block.len();
+            progress(read, length);
+        }
+        Ok(data)
+    }
+
+    /// GO: start executing the vector table at `address`
+    fn go(&mut self, address: u32) -> Result<()> {
+        self.command(CMD_GO)?;
+        self.send(&address_frame(address))?;
+        self.wait_ack(&format!("go to 0x{address:08X}"))
+    }
+
+    /// Reset the module from software, leaving the bootloader
+    pub fn reset(&mut self) -> Result<()> {
+        self.start();
+        self.write_block(RESET_STUB_ADDRESS, &reset_stub())?;
+        self.go(RESET_STUB_ADDRESS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// Bus answering reads from a script and recording every write
+    struct Scripted {
+        answers: VecDeque<Vec<u8>>,
+        writes: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl I2cBackend for Scripted {
+        fn write(&mut self, _address: u8, data: &[u8]) -> Result<()> {
+            self.writes.lock().unwrap().push(data.to_vec());
+            Ok(())
+        }
+
+        fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<()> {
+            let answer = self.answers.pop_front().expect("unscripted read");
+            buffer.copy_from_slice(&answer);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_uses_no_stretch_frames_and_waits_out_busy() {
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let answers = [
+            // GET: ACK, count, version 1.1 and commands, ACK
+            vec![ACK],
+            vec![3],
+            vec![0x11, CMD_GET, CMD_WRITE_MEMORY_NO_STRETCH, CMD_ERASE],
+            vec![ACK],
+            // WRITE MEMORY: command, address, data (busy twice while programming)
+            vec![ACK],
+            vec![ACK],
+            vec![BUSY],
+            vec![BUSY],
+            vec![ACK],
+        ];
+        let bus = Scripted {
+            answers: answers.into_iter().collect(),
+            writes: Arc::clone(&writes),
+        };
+        let mut bootloader =
+            Bootloader::connect(Box::new(bus), 0x48, Duration::from_secs(1)).unwrap();
+        assert_eq!(bootloader.version(), 0x11);
+
+        let mut reported = Vec::new();
+        bootloader
+            .write(0x0800_0100, &[0xAA, 0x55, 0x01], |done, total| {
+                reported.push((done, total));
+            })
+            .unwrap();
+        assert_eq!(reported, [(3, 3)]);
+
+        let writes = writes.lock().unwrap();
+        assert_eq!(writes[0], [CMD_GET, 0xFF]);
+        assert_eq!(writes[1], [0x32, 0xCD]);
+        assert_eq!(writes[2], [0x08, 0x00, 0x01, 0x00, 0x09]);
+        let data = &writes[3];
+        assert_eq!(data.len(), 1 + WRITE_ALIGNMENT + 1);
+        assert_eq!(data[..5], [7, 0xAA, 0x55, 0x01, 0xFF]);
+        assert_eq!(xor(data), 0);
+    }
+}
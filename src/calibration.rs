@@ -0,0 +1,235 @@
+//! Distance calibration against a reference target
+//!
+//! Mounting the XM125 behind a plastic fascia introduces a consistent bias in the
+//! reported distance. This module computes a fixed offset (or scale) correction from
+//! repeated measurements of a target at a known distance and persists it so that
+//! subsequent distance measurements are corrected automatically.
+//!
+//! It also keeps the metadata of a recorded detection threshold: the detector learns the
+//! background of a cleared scene, and the range and profile it was recorded for tell later
+//! runs when the threshold no longer matches their configuration.
+
+use crate::error::RadarError;
+use crate::journal::write_atomic;
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Persisted distance correction: `corrected = measured * scale + offset_m`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DistanceCalibration {
+    /// Known distance to the reference target in meters
+    pub reference_m: f32,
+    /// Mean of the raw measurements taken during calibration in meters
+    pub measured_mean_m: f32,
+    /// Additive correction in meters
+    pub offset_m: f32,
+    /// Multiplicative correction (1.0 = none)
+    pub scale: f32,
+    /// Number of valid measurements used to compute the correction
+    pub samples: u32,
+    /// When the calibration was performed
+    pub calibrated_at: DateTime<Utc>,
+}
+
+impl DistanceCalibration {
+    /// Compute a correction from raw measurements of a target at `reference_m`
+    ///
+    /// Zero readings (no target detected) are ignored. With `use_scale` the bias is
+    /// corrected multiplicatively, otherwise as a fixed offset.
+    pub fn from_measurements(
+        reference_m: f32,
+        measurements: &[f32],
+        use_scale: bool,
+    ) -> Result<Self, RadarError> {
+        if reference_m <= 0.0 {
+            return Err(RadarError::InvalidParameters(format!(
+                "Reference distance must be positive (got {reference_m:.3}m)"
+            )));
+        }
+
+        let valid: Vec<f32> = measurements
+            .iter()
+            .copied()
+            .filter(|d| d.is_finite() && *d > 0.0)
+            .collect();
+        if valid.is_empty() {
+            return Err(RadarError::MeasurementFailed(
+                "No valid distance readings during calibration - check the reference target"
+                    .to_string(),
+            ));
+        }
+
+        #[allow(clippy::cast_precision_loss)] // Sample counts are small
+        let mean = valid.iter().sum::<f32>() / valid.len() as f32;
+
+        let (offset_m, scale) = if use_scale {
+            (0.0, reference_m / mean)
+        } else {
+            (reference_m - mean, 1.0)
+        };
+
+        #[allow(clippy::cast_possible_truncation)] // Sample counts are small
+        Ok(Self {
+            reference_m,
+            measured_mean_m: mean,
+            offset_m,
+            scale,
+            samples: valid.len() as u32,
+            calibrated_at: Utc::now(),
+        })
+    }
+
+    /// Apply the correction to a raw distance in meters (zero means no target and is kept)
+    pub fn apply(&self, distance_m: f32) -> f32 {
+        if distance_m <= 0.0 {
+            return distance_m;
+        }
+        distance_m * self.scale + self.offset_m
+    }
+}
+
+/// Remove thermal drift from a distance in meters
+///
+/// Readings creep as the module warms or cools after detector calibration;
+/// `coefficient_mm_per_c` is the drift per degree of `temperature_delta_c`. Zero (no target)
+/// is kept.
+pub fn correct_thermal_drift(
+    distance_m: f32,
+    coefficient_mm_per_c: f32,
+    temperature_delta_c: i16,
+) -> f32 {
+    if distance_m <= 0.0 {
+        return distance_m;
+    }
+    distance_m - coefficient_mm_per_c * f32::from(temperature_delta_c) / 1000.0
+}
+
+/// Detection threshold recorded from background frames (RECORDED threshold method)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordedThreshold {
+    /// Background frames the threshold is computed from
+    pub frames: u32,
+    /// Measurement range the background was recorded over, in meters
+    pub start_m: f32,
+    pub end_m: f32,
+    /// Highest profile the detector used
+    pub max_profile: u32,
+    /// Module temperature after recording
+    pub temperature_c: Option<i16>,
+    /// When the background was recorded
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl RecordedThreshold {
+    /// Why a detector configured for `start_m..end_m` with `max_profile` cannot use this
+    /// threshold; empty when it still applies
+    pub fn recalibration_reasons(&self, start_m: f32, end_m: f32, max_profile: u32) -> Vec<String> {
+        let mut reasons = Vec::new();
+        // Register values are whole millimetres
+        if (self.start_m - start_m).abs() >= 0.001 || (self.end_m - end_m).abs() >= 0.001 {
+            reasons.push(format!(
+                "range is {start_m:.3}-{end_m:.3}m, recorded for {:.3}-{:.3}m",
+                self.start_m, self.end_m
+            ));
+        }
+        if self.max_profile != max_profile {
+            reasons.push(format!(
+                "max profile is {max_profile}, recorded with {}",
+                self.max_profile
+            ));
+        }
+        reasons
+    }
+}
+
+/// On-disk calibration profile
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CalibrationProfile {
+    pub distance: Option<DistanceCalibration>,
+    pub threshold: Option<RecordedThreshold>,
+}
+
+impl CalibrationProfile {
+    /// Load the calibration profile, returning an empty profile if the file does not exist
+    pub fn load(path: &str) -> Result<Self, RadarError> {
+        if !Path::new(path).exists() {
+            debug!("No calibration profile at {path}");
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|e| RadarError::DeviceError {
+            message: format!("Failed to parse calibration profile {path}: {e}"),
+        })
+    }
+
+    /// Save the calibration profile, creating the parent directory if required
+    pub fn save(&self, path: &str) -> Result<(), RadarError> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let contents = toml::to_string_pretty(self).map_err(|e| RadarError::DeviceError {
+            message: format!("Failed to serialize calibration profile: {e}"),
+        })?;
+        write_atomic(path, contents.as_bytes())?;
+        info!("💾 Calibration profile saved to {path}");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_calibration() {
+        let cal =
+            DistanceCalibration::from_measurements(1.0, &[1.04, 1.06, 0.0, 1.05], false).unwrap();
+        assert_eq!(cal.samples, 3);
+        assert!((cal.offset_m + 0.05).abs() < 1e-4);
+        assert!((cal.apply(1.05) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_scale_calibration() {
+        let cal = DistanceCalibration::from_measurements(2.0, &[2.2, 2.2], true).unwrap();
+        assert!((cal.scale - 2.0 / 2.2).abs() < 1e-4);
+        assert!((cal.apply(2.2) - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_thermal_drift_correction() {
+        // 0.5mm/°C creep, 10°C warmer than at calibration
+        assert!((correct_thermal_drift(1.505, 0.5, 10) - 1.5).abs() < 1e-4);
+        assert!((correct_thermal_drift(1.495, 0.5, -10) - 1.5).abs() < 1e-4);
+        assert_eq!(correct_thermal_drift(0.0, 0.5, 10), 0.0);
+    }
+
+    #[test]
+    fn test_recorded_threshold_mismatch() {
+        let threshold = RecordedThreshold {
+            frames: 100,
+            start_m: 0.2,
+            end_m: 3.0,
+            max_profile: 5,
+            temperature_c: Some(25),
+            recorded_at: Utc::now(),
+        };
+        assert!(threshold.recalibration_reasons(0.2, 3.0, 5).is_empty());
+        let reasons = threshold.recalibration_reasons(0.2, 4.0, 3);
+        assert_eq!(reasons.len(), 2);
+        assert!(reasons[0].contains("recorded for 0.200-3.000m"));
+    }
+
+    #[test]
+    fn test_calibration_requires_valid_readings() {
+        assert!(DistanceCalibration::from_measurements(1.0, &[0.0, 0.0], false).is_err());
+        assert!(DistanceCalibration::from_measurements(0.0, &[1.0], false).is_err());
+    }
+}
@@ -1,7 +1,44 @@
 use clap::{Parser, Subcommand, ValueEnum};
 
+use crate::error::RadarError;
 use crate::fifo;
-use crate::firmware;
+use crate::firmware::{self, FirmwarePaths, FirmwareType};
+use crate::i2c::WriteVerify;
+use crate::journal::JournaledFile;
+use crate::limits::RunLimits;
+use crate::realtime::RealtimeArgs;
+use crate::settings::Settings;
+use crate::timeouts::Timeouts;
+use std::time::Duration;
+
+/// Per-phase timeouts for device operations (seconds)
+#[derive(Parser, Debug, Clone)]
+pub struct TimeoutArgs {
+    /// Timeout for device responses: connect, calibration and measurement phases
+    #[arg(
+        short = 't',
+        long,
+        value_name = "SECS",
+        help = "Timeout in seconds for device responses (connect, calibration, measurement)"
+    )]
+    pub timeout: Option<u64>,
+
+    /// Wait for the module to answer after a reset (default 5)
+    #[arg(long, value_name = "SECS", help = "Connect timeout in seconds")]
+    pub connect_timeout: Option<u64>,
+
+    /// Wait for configuration and calibration to complete (default 5)
+    #[arg(long, value_name = "SECS", help = "Calibration timeout in seconds")]
+    pub calibration_timeout: Option<u64>,
+
+    /// Wait for a single measurement to complete (default 5)
+    #[arg(long, value_name = "SECS", help = "Measurement timeout in seconds")]
+    pub measurement_timeout: Option<u64>,
+
+    /// Limit for each flash write, readback or erase during update/verify/erase (default 120)
+    #[arg(long, value_name = "SECS", help = "Firmware flash timeout in seconds")]
+    pub flash_timeout: Option<u64>,
+}
 
 /// Logging and debug configuration
 #[derive(Parser, Debug, Clone)]
@@ -13,6 +50,23 @@ pub struct LoggingArgs {
     /// Log all register values after configuration for comparison with evaluation tools
     #[arg(long, help = "Debug register configuration (global option)")]
     pub debug_registers: bool,
+
+    /// File receiving log records and diagnostics instead of stderr
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Append log records and diagnostics to this file instead of stderr; stdout carries only measurement data"
+    )]
+    pub log_file: Option<String>,
+
+    /// OTLP/gRPC endpoint for traces and metrics (e.g., http://collector:4317)
+    #[cfg(feature = "otel")]
+    #[arg(
+        long,
+        env = "OTEL_EXPORTER_OTLP_ENDPOINT",
+        help = "Export traces and metrics to this OTLP/gRPC endpoint"
+    )]
+    pub otel_endpoint: Option<String>,
 }
 
 /// Output configuration and formatting
@@ -26,6 +80,22 @@ pub struct OutputArgs {
     #[arg(short = 'q', long, help = "Suppress startup messages")]
     pub quiet: bool,
 
+    /// ASCII-only output for serial consoles and legacy log collectors
+    #[arg(
+        long,
+        env = "XM125_PLAIN",
+        help = "Plain ASCII output: no emoji or box-drawing characters"
+    )]
+    pub plain: bool,
+
+    /// Timestamp format of CSV rows, FIFO and MQTT records
+    #[arg(
+        long,
+        default_value = "iso",
+        help = "Record timestamps: iso (calendar time), realtime or monotonic (integer nanoseconds, cheaper at high frame rates)"
+    )]
+    pub timestamps: TimestampFormat,
+
     /// Enable FIFO output (compatible with spi-lib readers)
     #[arg(
         long,
@@ -56,6 +126,265 @@ pub struct OutputArgs {
         help = "FIFO output interval in seconds (5.0=spi-lib compatible, 0=every measurement)"
     )]
     pub fifo_interval: f32,
+
+    /// POSIX shared-memory segment for the latest measurement
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Publish the latest measurement to a shared-memory segment (e.g. /xm125) under a sequence lock"
+    )]
+    pub shm: Option<String>,
+
+    /// MQTT broker receiving the measurement records
+    #[cfg(feature = "mqtt")]
+    #[arg(
+        long,
+        value_name = "[mqtt://][USER:PASSWORD@]HOST[:PORT]",
+        value_parser = crate::mqtt::parse_broker,
+        help = "Publish every measurement as a JSON record to this MQTT broker"
+    )]
+    pub mqtt_broker: Option<crate::mqtt::BrokerAddress>,
+
+    /// MQTT topic for the measurement records
+    #[cfg(feature = "mqtt")]
+    #[arg(
+        long,
+        value_name = "TOPIC",
+        requires = "mqtt_broker",
+        help = "MQTT topic for the measurement records (default: xm125/<device id>)"
+    )]
+    pub mqtt_topic: Option<String>,
+
+    /// Home Assistant discovery prefix for the MQTT sink
+    #[cfg(feature = "mqtt")]
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        requires = "mqtt_broker",
+        help = "Also publish Home Assistant discovery config messages, retained, under this prefix (e.g. homeassistant)"
+    )]
+    pub mqtt_discovery_prefix: Option<String>,
+
+    /// Raw register words in JSON measurement output
+    #[arg(
+        long,
+        help = "Add the raw 32-bit register words (result, distance, scores) behind each measurement to JSON output"
+    )]
+    pub include_raw: bool,
+
+    /// Anonymized privacy mode
+    #[arg(
+        long,
+        help = "Quantize distances, drop score details and cut timestamps to minutes in FIFO output (and locally unless --privacy-keep-local)"
+    )]
+    pub privacy: bool,
+
+    /// Distance bucket for privacy mode
+    #[arg(
+        long,
+        value_name = "METRES",
+        default_value = "0.5",
+        requires = "privacy",
+        help = "Distance bucket size in metres for --privacy"
+    )]
+    pub privacy_bucket: f32,
+
+    /// Keep stdout and the CSV export at full fidelity in privacy mode
+    #[arg(
+        long,
+        requires = "privacy",
+        help = "With --privacy, keep stdout and the CSV export at full fidelity; only external sinks are anonymized"
+    )]
+    pub privacy_keep_local: bool,
+
+    /// Interval between fsyncs of data files
+    #[arg(
+        long,
+        default_value = "1.0",
+        value_parser = parse_seconds,
+        help = "Seconds between fsyncs of CSV data files (0=every record, limits data lost on power failure)"
+    )]
+    pub fsync_interval: f32,
+
+    /// Recipient the saved data files are encrypted to
+    #[cfg(feature = "encrypt")]
+    #[arg(
+        long,
+        value_name = "age:RECIPIENT",
+        value_parser = crate::encrypt::parse_encrypt_target,
+        help = "Encrypt the CSV export and rejected measurements file to this age recipient (e.g. age:age1...)"
+    )]
+    pub encrypt: Option<crate::encrypt::EncryptTarget>,
+
+    /// Ed25519 key the saved data files are signed with
+    #[cfg(feature = "sign")]
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Sign the CSV export and rejected measurements file with the Ed25519 key in FILE (created if missing), writing <file>.sig on close"
+    )]
+    pub sign_key: Option<String>,
+
+    /// When buffered stdout and CSV output is written out
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "every",
+        help = "Flush stdout and data files after every record, every --flush-interval, or only at the end"
+    )]
+    pub flush: FlushPolicy,
+
+    /// Interval for `--flush interval`
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value = "1.0",
+        value_parser = parse_seconds,
+        help = "Seconds between flushes with --flush interval"
+    )]
+    pub flush_interval: f32,
+
+    /// Spool for measurements no data sink accepted
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Spool measurements to this file while all data sinks fail, replaying them (marked delayed) on recovery"
+    )]
+    pub spool: Option<String>,
+
+    /// Spool size limit
+    #[arg(
+        long,
+        default_value = "4096",
+        help = "Maximum spool size in KiB; further measurements are dropped while it is full"
+    )]
+    pub spool_max_kb: u64,
+
+    /// Health status file for continuous runs
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Keep an atomically replaced JSON health status (last measurement, state, counts, errors) at this path during continuous runs"
+    )]
+    pub status_file: Option<String>,
+
+    /// Interval between status file updates
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value = "5.0",
+        value_parser = parse_seconds,
+        help = "Seconds between status file updates"
+    )]
+    pub status_interval: f32,
+
+    /// Dead-man file touched after every successful measurement
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Touch this file after every successful measurement; removed on exit (e.g. /run/xm125.alive)"
+    )]
+    pub heartbeat_file: Option<String>,
+
+    /// Age after which the heartbeat file counts as stale
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "30s",
+        value_parser = parse_duration,
+        requires = "heartbeat_file",
+        help = "Maximum heartbeat age supervisors should accept, recorded in the file (e.g. 10s, 2m)"
+    )]
+    pub heartbeat_max_age: Duration,
+
+    /// Measurement quality gating
+    #[arg(
+        long,
+        default_value = "off",
+        help = "Quality gate for invalid measurements: off, flag (warn but keep) or reject (drop from output)"
+    )]
+    pub quality_gate: QualityGateMode,
+
+    /// File receiving flagged/rejected measurements
+    #[arg(
+        long,
+        help = "Write flagged/rejected measurements to this file as JSON lines for later analysis"
+    )]
+    pub rejected_file: Option<String>,
+
+    /// Destination completed sessions are uploaded to
+    #[arg(
+        long,
+        value_name = "URL",
+        value_parser = crate::upload::parse_destination,
+        help = "When a continuous run with --save-to or a capture completes, upload its files and a completion marker to s3://BUCKET[/PREFIX] or an http(s):// URL"
+    )]
+    pub upload: Option<crate::upload::UploadDestination>,
+
+    /// Retries of a failed upload
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "3",
+        requires = "upload",
+        help = "Retry each failed transfer up to N times with exponential backoff"
+    )]
+    pub upload_retries: u32,
+}
+
+/// Parse a duration such as `500ms`, `5s`, `10m`, `8h` or `7d` (bare numbers are seconds)
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (value, scale_ms) = [
+        ("ms", 1.0),
+        ("s", 1000.0),
+        ("m", 60_000.0),
+        ("h", 3_600_000.0),
+        ("d", 86_400_000.0),
+    ]
+    .into_iter()
+    .find_map(|(suffix, scale)| s.strip_suffix(suffix).map(|value| (value, scale)))
+    .unwrap_or((s, 1000.0));
+    let value: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid duration '{s}' (expected e.g. 500ms, 5s, 1m, 8h)"))?;
+    let millis = value * scale_ms;
+    if !millis.is_finite() || millis < 1.0 {
+        return Err(format!("Duration must be at least 1ms, got '{s}'"));
+    }
+    Ok(Duration::from_millis(millis.round() as u64))
+}
+
+/// Parse a number of seconds between 0 and one day, such as `0.5` or `60`
+pub fn parse_seconds(s: &str) -> Result<f32, String> {
+    let value: f32 = s
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid number of seconds '{s}' (expected e.g. 0.5, 60)"))?;
+    if !(0.0..=86_400.0).contains(&value) {
+        return Err(format!("Seconds must be between 0 and 86400, got '{s}'"));
+    }
+    Ok(value)
+}
+
+/// Parse a size such as `500M`, `2G`, `64K` or a bare number of bytes
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let upper = s.to_ascii_uppercase();
+    let digits = upper.trim_end_matches('B').trim_end_matches('I');
+    let (value, scale) = [("K", 1u64 << 10), ("M", 1 << 20), ("G", 1 << 30)]
+        .into_iter()
+        .find_map(|(suffix, scale)| digits.strip_suffix(suffix).map(|value| (value, scale)))
+        .unwrap_or((digits, 1));
+    let value: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid size '{s}' (expected e.g. 64K, 500M, 2G)"))?;
+    if !value.is_finite() || value < 1.0 {
+        return Err(format!("Size must be at least 1 byte, got '{s}'"));
+    }
+    Ok((value * scale as f64) as u64)
 }
 
 /// Parse I2C address from string, supporting both decimal and hex formats
@@ -68,7 +397,151 @@ fn parse_i2c_address(s: &str) -> Result<u16, String> {
     }
 }
 
+/// `explicit` if given, otherwise `name` inside `dir`
+fn in_dir(dir: &str, explicit: Option<&String>, name: &str) -> String {
+    explicit.cloned().unwrap_or_else(|| {
+        std::path::Path::new(dir)
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
 impl Cli {
+    /// Get the subcommand to execute (presence is validated at startup)
+    pub fn subcommand(&self) -> &Commands {
+        self.command
+            .as_ref()
+            .expect("subcommand presence is validated at startup")
+    }
+
+    /// Per-phase timeouts from `--timeout` and the per-phase flags
+    pub fn timeouts(&self) -> Timeouts {
+        let args = &self.timeouts;
+        Timeouts::from_overrides(
+            args.timeout,
+            args.connect_timeout,
+            args.calibration_timeout,
+            args.measurement_timeout,
+            args.flash_timeout,
+        )
+    }
+
+    /// Minimum time between flushes of buffered output (None = only at the end)
+    ///
+    /// Spooling needs to know whether each record reached the CSV export, so `--spool`
+    /// always flushes every record.
+    pub fn flush_interval(&self) -> Option<Duration> {
+        if self.output.spool.is_some() {
+            return Some(Duration::ZERO);
+        }
+        match self.output.flush {
+            FlushPolicy::Every => Some(Duration::ZERO),
+            FlushPolicy::Interval => Some(Duration::from_secs_f32(self.output.flush_interval)),
+            FlushPolicy::End => None,
+        }
+    }
+
+    /// Distance bucket for anonymized external sinks (None without `--privacy`)
+    pub fn privacy_bucket(&self) -> Option<f32> {
+        self.output.privacy.then_some(self.output.privacy_bucket)
+    }
+
+    /// Distance bucket for anonymized local output (None when it keeps full fidelity)
+    pub fn local_privacy_bucket(&self) -> Option<f32> {
+        self.privacy_bucket()
+            .filter(|_| !self.output.privacy_keep_local)
+    }
+
+    /// Create a data file honouring `--fsync-interval`, `--flush`, `--encrypt` and `--sign-key`
+    pub fn create_data_file(&self, path: &str) -> std::io::Result<JournaledFile> {
+        let sync_interval = Duration::from_secs_f32(self.output.fsync_interval);
+        let file =
+            JournaledFile::create(path, sync_interval)?.with_commit_interval(self.flush_interval());
+        #[cfg(feature = "encrypt")]
+        let file = file.with_encryption(self.output.encrypt.as_ref())?;
+        #[cfg(feature = "sign")]
+        let file = file.with_signature(std::path::Path::new(path));
+        Ok(file)
+    }
+
+    /// Detector settings of the `--config` layers with `--config-profile` applied
+    pub fn settings(&self) -> Result<Settings, RadarError> {
+        Settings::load_effective(&self.config, self.config_profile.as_deref())
+    }
+
+    /// OTLP endpoint for telemetry export (always None without the `otel` feature)
+    pub fn otel_endpoint(&self) -> Option<&str> {
+        #[cfg(feature = "otel")]
+        return self.logging.otel_endpoint.as_deref();
+        #[cfg(not(feature = "otel"))]
+        None
+    }
+
+    /// Calibration profile file
+    pub fn calibration_path(&self) -> String {
+        in_dir(
+            &self.state_dir,
+            self.calibration_file.as_ref(),
+            "calibration.toml",
+        )
+    }
+
+    /// Record of the last flashed firmware
+    pub fn firmware_state_path(&self) -> String {
+        in_dir(
+            &self.state_dir,
+            self.firmware_state.as_ref(),
+            "firmware-state.toml",
+        )
+    }
+
+    /// Device statistics file, whether or not recording is enabled
+    pub fn stats_file_path(&self) -> String {
+        in_dir(&self.state_dir, self.stats_file.as_ref(), "stats.toml")
+    }
+
+    /// Directory for the per-device session locks
+    pub fn lock_dir_path(&self) -> String {
+        self.lock_dir
+            .clone()
+            .unwrap_or_else(|| self.runtime_dir.clone())
+    }
+
+    /// Application ID cache file (None when disabled with `--no-app-id-cache`)
+    ///
+    /// Traced sessions always read the ID from the module, so recording and playback see
+    /// the same transactions. A simulated module never shares the real module's cache.
+    pub fn app_id_cache_path(&self) -> Option<String> {
+        let traced = self.i2c_record.is_some() || self.i2c_playback.is_some();
+        (!self.no_app_id_cache && !traced && self.simulate.is_none())
+            .then(|| in_dir(&self.runtime_dir, self.app_id_cache.as_ref(), "app-id.toml"))
+    }
+
+    /// Firmware binary locations: `--firmware-path` plus any per-type overrides
+    pub fn firmware_paths(&self) -> FirmwarePaths {
+        FirmwarePaths::new(&self.firmware_path)
+            .with_override(FirmwareType::Distance, self.firmware_distance.as_deref())
+            .with_override(FirmwareType::Presence, self.firmware_presence.as_deref())
+            .with_override(FirmwareType::Breathing, self.firmware_breathing.as_deref())
+    }
+
+    /// Bootloader link from `--flash-transport`, `--serial` and `--serial-baud`
+    pub fn flash_transport(&self) -> firmware::FlashTransport {
+        match (&self.flash_transport, &self.serial) {
+            (FlashTransportKind::Uart, Some(device)) => firmware::FlashTransport::Uart {
+                device: device.clone(),
+                baud: self.serial_baud,
+            },
+            _ => firmware::FlashTransport::I2c,
+        }
+    }
+
+    /// Device statistics file (None when disabled with `--no-stats` or simulating)
+    pub fn stats_path(&self) -> Option<String> {
+        (!self.no_stats && self.simulate.is_none()).then(|| self.stats_file_path())
+    }
+
     /// Get the I2C device path, using bus number if device path not specified
     pub fn get_i2c_device_path(&self) -> String {
         if let Some(device) = &self.i2c_device {
@@ -85,6 +558,13 @@ impl Cli {
             mcu_interrupt: self.gpio_mcu_int,
             wake_up: self.gpio_wake,
             boot: self.gpio_boot,
+            signals: crate::gpio::GpioSignals {
+                reset_active: self.gpio_reset_active.into(),
+                reset_pulse: self.gpio_reset_pulse,
+                startup_delay: self.gpio_startup_delay,
+                wake_delay: self.gpio_wake_delay,
+                settle_delay: self.gpio_settle_delay,
+            },
         }
     }
 }
@@ -93,6 +573,8 @@ impl Cli {
 #[command(
     author = "Dynamic Devices Ltd",
     version,
+    disable_version_flag = true,
+    arg_required_else_help = true,
     about = "XM125 Radar Module Monitor v2.0.0 - Clean CLI for Acconeer XM125 radar modules",
     long_about = "XM125 Radar Module Monitor v2.0.0
 
@@ -121,6 +603,9 @@ DISTANCE MEASUREMENT:
   # Custom range configuration
   xm125-radar-monitor distance --range 0.1:3.0
 
+  # Calibrate against a reference target at exactly 1m (stored and applied henceforth)
+  xm125-radar-monitor distance calibrate-offset --actual 1.000
+
 PRESENCE DETECTION:
   # Single presence detection (default long range: 0.5m - 7.0m)
   xm125-radar-monitor presence
@@ -180,14 +665,106 @@ pub struct Cli {
     #[arg(short = 'a', long, default_value = "0x52", value_parser = parse_i2c_address, help = "I2C address of XM125 module")]
     pub i2c_address: u16,
 
-    /// Command timeout in seconds (how long to wait for device responses)
+    /// Pause after each I2C transaction for the module to process it
     #[arg(
-        short = 't',
         long,
-        default_value = "3",
-        help = "Command timeout in seconds"
+        value_name = "DELAY",
+        default_value = "1ms",
+        value_parser = crate::i2c::parse_transaction_delay,
+        help = "Pause after each I2C transaction: 500us, 2ms or bare ms, 0 for none (default 1ms)"
+    )]
+    pub i2c_delay: Duration,
+
+    /// Record raw I2C transactions of this session to a trace file
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Record every I2C transaction of this session to a JSON-lines trace"
+    )]
+    pub i2c_record: Option<String>,
+
+    /// Replay a recorded I2C trace instead of talking to the bus
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "i2c_record",
+        help = "Answer I2C transactions from a trace recorded with --i2c-record instead of the bus"
+    )]
+    pub i2c_playback: Option<String>,
+
+    /// Time warp factor for playback
+    #[arg(
+        long,
+        value_name = "FACTOR",
+        default_value = "1.0",
+        requires = "i2c_playback",
+        help = "Replay recorded timing this many times faster (0 = no waiting)"
+    )]
+    pub i2c_playback_speed: f64,
+
+    /// Emulate a module instead of using the I2C bus
+    #[arg(
+        long,
+        value_name = "FIRMWARE",
+        conflicts_with = "i2c_playback",
+        help = "Talk to a simulated module running this firmware (distance, presence, breathing) instead of the I2C bus"
+    )]
+    pub simulate: Option<FirmwareType>,
+
+    /// Check busy and error status before every register write
+    #[arg(
+        long,
+        help = "Read the detector status before every register write and fail on writes while busy or in error"
+    )]
+    pub strict_protocol: bool,
+
+    /// Which register writes to read back and compare
+    #[arg(
+        long,
+        value_enum,
+        default_value = "off",
+        help = "Read register writes back and retry on mismatch: off, critical (detector configuration) or all"
+    )]
+    pub write_verify: WriteVerify,
+
+    /// How to wait for the module to finish a detector command
+    #[arg(
+        long,
+        value_enum,
+        default_value = "poll",
+        help = "Wait for detector commands by polling the status register or on the MCU_INT edge"
+    )]
+    pub wait_mode: WaitMode,
+
+    /// Failure rates for robustness testing, e.g. `i2c-read:0.01,calibration-timeout:0.001`
+    #[cfg(feature = "fault-injection")]
+    #[arg(long, hide = true, value_name = "POINT:RATE,...", value_parser = crate::fault::parse_plan)]
+    pub inject_failure: Option<crate::fault::FaultPlan>,
+
+    /// Seed of the `--inject-failure` draws
+    #[cfg(feature = "fault-injection")]
+    #[arg(long, hide = true, value_name = "SEED", default_value = "1")]
+    pub inject_seed: u64,
+
+    /// Per-firmware result decoding corrections
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "TOML table of firmware quirks (scale factors, byte order, bit positions) keyed by application ID and RSS version"
+    )]
+    pub quirk_table: Option<String>,
+
+    /// Quirk entry to use regardless of the detected firmware
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Decode results with this quirk table entry whatever firmware is detected ('standard' = as documented)"
     )]
-    pub timeout: u64,
+    pub force_quirk: Option<String>,
+
+    /// Device operation timeouts
+    #[command(flatten)]
+    pub timeouts: TimeoutArgs,
 
     /// Logging and debug configuration
     #[command(flatten)]
@@ -229,6 +806,55 @@ pub struct Cli {
     )]
     pub gpio_boot: u32,
 
+    /// Level that holds the module in reset
+    #[arg(
+        long,
+        value_enum,
+        default_value = "low",
+        help = "Level that holds the XM125 in reset (high for inverting level shifters)"
+    )]
+    pub gpio_reset_active: ActiveLevel,
+
+    /// How long reset is held asserted
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "100ms",
+        value_parser = parse_duration,
+        help = "How long to hold reset asserted, 1ms-5s"
+    )]
+    pub gpio_reset_pulse: Duration,
+
+    /// Wait after releasing reset for the application to start
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "100ms",
+        value_parser = parse_duration,
+        help = "Wait after releasing reset for the module application to start, 10ms-10s"
+    )]
+    pub gpio_startup_delay: Duration,
+
+    /// Wait after driving WAKE_UP high at the end of a reset
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "100ms",
+        value_parser = parse_duration,
+        help = "Wait after driving WAKE_UP high at the end of a reset, 0-5s"
+    )]
+    pub gpio_wake_delay: Duration,
+
+    /// Wait for BOOT0 and WAKE_UP to settle before asserting reset
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "10ms",
+        value_parser = parse_duration,
+        help = "Wait for BOOT0 and WAKE_UP to settle before asserting reset, 0-1s"
+    )]
+    pub gpio_settle_delay: Duration,
+
     /// Firmware directory path (contains .bin files)
     #[arg(
         long,
@@ -237,29 +863,202 @@ pub struct Cli {
     )]
     pub firmware_path: String,
 
-    #[command(subcommand)]
-    pub command: Commands,
-}
+    /// Distance firmware binary overriding the one in --firmware-path
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Distance detector firmware binary (overrides --firmware-path)"
+    )]
+    pub firmware_distance: Option<String>,
 
-#[derive(Subcommand)]
-pub enum Commands {
-    /// Check XM125 radar status and initialization state
-    ///
-    /// Shows device status flags, initialization progress, and error conditions.
-    /// Use this first to verify the device is responding and properly initialized.
-    Status,
+    /// Presence firmware binary overriding the one in --firmware-path
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Presence detector firmware binary (overrides --firmware-path)"
+    )]
+    pub firmware_presence: Option<String>,
 
-    /// Get XM125 device information and firmware details
-    ///
-    /// Displays sensor ID, firmware version, application type, and current configuration.
-    /// Useful for verifying correct firmware is loaded and device capabilities.
-    Info,
+    /// Breathing firmware binary overriding the one in --firmware-path
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Breathing monitor firmware binary (overrides --firmware-path)"
+    )]
+    pub firmware_breathing: Option<String>,
 
-    /// Perform distance measurement
-    ///
+    /// Link used to reach the STM32 bootloader for update, verify and erase
+    #[arg(
+        long,
+        value_name = "TRANSPORT",
+        default_value = "i2c",
+        help = "Bootloader link for firmware update/verify/erase: i2c, or uart for boards exposing the module UART"
+    )]
+    pub flash_transport: FlashTransportKind,
+
+    /// Serial port of the module UART for `--flash-transport uart`
+    #[arg(
+        long,
+        value_name = "DEV",
+        required_if_eq("flash_transport", "uart"),
+        help = "Serial port wired to the XM125 UART (e.g. /dev/ttymxc1), used with --flash-transport uart"
+    )]
+    pub serial: Option<String>,
+
+    /// Baud rate for the UART bootloader
+    #[arg(
+        long,
+        value_name = "BAUD",
+        default_value = "115200",
+        help = "Baud rate for UART firmware flashing"
+    )]
+    pub serial_baud: u32,
+
+    /// Flash the firmware a measurement command needs when the module runs another one
+    #[arg(
+        long,
+        conflicts_with_all = ["simulate", "i2c_playback"],
+        help = "Flash the matching firmware when a command needs a different detector than the one loaded"
+    )]
+    pub auto_flash: bool,
+
+    /// Directory for persistent state (calibration, firmware state, statistics)
+    #[arg(
+        long,
+        value_name = "DIR",
+        env = "STATE_DIRECTORY",
+        default_value = "/var/lib/xm125-radar-monitor",
+        help = "Directory for persistent state files (set by systemd StateDirectory=)"
+    )]
+    pub state_dir: String,
+
+    /// Directory for runtime files (locks, application ID cache)
+    #[arg(
+        long,
+        value_name = "DIR",
+        env = "RUNTIME_DIRECTORY",
+        default_value = "/run/xm125-radar-monitor",
+        help = "Directory for lock and cache files cleared on reboot (set by systemd RuntimeDirectory=)"
+    )]
+    pub runtime_dir: String,
+
+    /// Calibration profile path (distance offset/scale correction)
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Calibration profile file used to store and apply distance corrections [default: <state-dir>/calibration.toml]"
+    )]
+    pub calibration_file: Option<String>,
+
+    /// Firmware state file recording the digests of the last flashed image
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "File recording the size, MD5 and SHA256 of the last flashed firmware [default: <state-dir>/firmware-state.toml]"
+    )]
+    pub firmware_state: Option<String>,
+
+    /// Cache of the application ID and RSS version read at connect time
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "File caching the detected application ID between runs, cleared on reset/flash [default: <runtime-dir>/app-id.toml]"
+    )]
+    pub app_id_cache: Option<String>,
+
+    /// Always read the application ID from the module
+    #[arg(long, help = "Do not cache the detected application ID on disk")]
+    pub no_app_id_cache: bool,
+
+    /// Directory holding the per-device session locks
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory for the lock files that stop two instances driving the same module [default: <runtime-dir>]"
+    )]
+    pub lock_dir: Option<String>,
+
+    /// Stop the instance currently using the module and take over
+    #[arg(
+        long,
+        help = "If another instance holds the module, ask it to stop (SIGTERM) and take over"
+    )]
+    pub takeover: bool,
+
+    /// Cumulative device statistics kept across sessions
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "File accumulating measurement, detection, reset, flash and error counts per module [default: <state-dir>/stats.toml]"
+    )]
+    pub stats_file: Option<String>,
+
+    /// Do not record device statistics
+    #[arg(long, help = "Do not update the device statistics file")]
+    pub no_stats: bool,
+
+    /// Detector settings files (TOML) reloaded on SIGHUP during continuous monitoring
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "TOML file with [presence]/[distance] detector settings, aliases and a default command; repeat to layer files (later ones win), command-line options take precedence, SIGHUP reloads them while monitoring"
+    )]
+    pub config: Vec<String>,
+
+    /// Named profile of the --config files to apply
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "config",
+        help = "Apply the [profiles.NAME] setup of the --config files (I2C bus/address, GPIO pins, FIFO and detector settings); command-line options still take precedence"
+    )]
+    pub config_profile: Option<String>,
+
+    /// Print the command line after alias and default-command expansion
+    #[arg(
+        long,
+        help = "Print the command line after expanding aliases and the default command from --config, then exit"
+    )]
+    pub print_effective_command: bool,
+
+    /// Arguments after alias and default command expansion, as they were parsed
+    #[arg(skip)]
+    pub effective_args: Vec<std::ffi::OsString>,
+
+    /// Print version and build information
+    #[arg(
+        short = 'V',
+        long,
+        help = "Print version and build metadata (use --format json for a structured report)"
+    )]
+    pub version: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Check XM125 radar status and initialization state
+    ///
+    /// Shows device status flags, initialization progress, and error conditions.
+    /// Use this first to verify the device is responding and properly initialized.
+    Status,
+
+    /// Get XM125 device information and firmware details
+    ///
+    /// Displays sensor ID, firmware version, application type, and current configuration.
+    /// Useful for verifying correct firmware is loaded and device capabilities.
+    Info,
+
+    /// Perform distance measurement
+    ///
     /// Measures distance to objects with high precision. Automatically configures
     /// the device for distance detection mode and handles firmware updates if needed.
     Distance {
+        #[command(subcommand)]
+        action: Option<DistanceAction>,
+
         /// Detection range in meters (format: start:end, e.g., 0.1:3.0)
         #[arg(long, help = "Detection range in meters (start:end, e.g., 0.1:3.0)")]
         range: Option<String>,
@@ -275,13 +1074,29 @@ pub enum Commands {
         )]
         count: Option<u32>,
 
-        /// Measurement interval in milliseconds for continuous mode
+        /// Measurement interval for continuous mode (bare numbers are milliseconds)
         #[arg(
             long,
             default_value = "1000",
-            help = "Time between measurements in ms (requires --continuous)"
+            value_parser = crate::pacing::parse_interval,
+            help = "Time between measurements: ms, or with a unit such as 12.5ms or 0.05s (requires --continuous)"
         )]
-        interval: u64,
+        interval: Duration,
+
+        /// Number of initial measurements to discard in continuous mode
+        #[arg(
+            long,
+            default_value = "0",
+            help = "Discard the first N measurements after calibration (requires --continuous)"
+        )]
+        warmup: u32,
+
+        /// Correlate the device measure counter with host timestamps
+        #[arg(
+            long,
+            help = "Report host/module clock drift and sampling jitter (requires --continuous)"
+        )]
+        clock_drift: bool,
 
         /// Save measurements to CSV file (continuous mode only)
         #[arg(
@@ -289,6 +1104,107 @@ pub enum Commands {
             help = "Output CSV file path (e.g., distance_data.csv, requires --continuous)"
         )]
         save_to: Option<String>,
+
+        /// Distance zones as ascending upper bounds, last zone unbounded
+        #[arg(
+            long,
+            value_name = "SPEC",
+            requires = "continuous",
+            help = "Emit zone-change events, e.g. near:0.5,mid:2.0,far (requires --continuous)"
+        )]
+        zones: Option<String>,
+
+        /// Distance past a zone boundary required before the zone changes
+        #[arg(
+            long,
+            default_value = "0.05",
+            value_name = "METERS",
+            help = "Zone boundary hysteresis in meters"
+        )]
+        zone_hysteresis: f32,
+
+        /// Only output zone-change events, not every measurement
+        #[arg(long, requires = "zones", help = "Only output zone-change events")]
+        zone_events_only: bool,
+
+        /// Thermal drift of the distance reading per degree from the calibration temperature
+        #[arg(
+            long,
+            value_name = "MM_PER_C",
+            allow_negative_numbers = true,
+            help = "Correct thermal drift by this many mm per °C away from the temperature at detector calibration"
+        )]
+        temperature_coefficient: Option<f32>,
+
+        /// Reference level for peak strengths reported in dB
+        #[arg(
+            long,
+            value_name = "DB",
+            default_value = "0",
+            allow_negative_numbers = true,
+            help = "Report peak strengths in dB relative to this level"
+        )]
+        strength_reference: f32,
+
+        /// Number of peaks to read per measurement
+        #[arg(
+            long,
+            value_name = "1-10",
+            default_value = "1",
+            value_parser = clap::value_parser!(u8).range(1..=10),
+            help = "Read up to this many peaks per measurement and report them all, in the module's peak order"
+        )]
+        max_peaks: u8,
+
+        /// Highest profile the detector may use for its far subsweeps
+        #[arg(
+            long,
+            value_name = "1-5",
+            value_parser = clap::value_parser!(u32).range(1..=5),
+            help = "Highest profile for the detector's subsweeps: low keeps fine resolution, 5 reaches furthest [default: 5]"
+        )]
+        max_profile: Option<u32>,
+
+        /// Longest step length the detector may use (0 = automatic)
+        #[arg(
+            long,
+            value_name = "STEPS",
+            help = "Longest step length for the detector's subsweeps, in 2.5mm steps [default: 0 = automatic]"
+        )]
+        max_step_length: Option<u32>,
+
+        /// Shape of the target
+        #[arg(
+            long,
+            value_enum,
+            help = "Tune the detector for the target's shape: planar for liquid levels and walls [default: generic]"
+        )]
+        reflector: Option<Reflector>,
+
+        /// What the target is made of
+        #[arg(
+            long,
+            value_enum,
+            help = "Tune signal quality and threshold for the target: liquid for tank levels [default: solid]"
+        )]
+        material: Option<Material>,
+
+        /// Aggregate measurements over fixed time windows
+        #[arg(
+            long,
+            value_name = "DURATION",
+            value_parser = parse_duration,
+            requires = "continuous",
+            conflicts_with = "zone_events_only",
+            help = "Emit one min/max/mean record per window, e.g. 5s or 500ms (requires --continuous)"
+        )]
+        window: Option<Duration>,
+
+        #[command(flatten)]
+        limits: RunLimits,
+
+        #[command(flatten)]
+        realtime: RealtimeArgs,
     },
 
     /// Perform presence detection
@@ -344,6 +1260,14 @@ pub enum Commands {
         )]
         profile: ProfileMode,
 
+        /// Motion the detector reacts to
+        #[arg(
+            long,
+            default_value = "both",
+            help = "Motion detection: fast (intra-frame, quick entry detection), slow (inter-frame, seated/micro-motion) or both"
+        )]
+        detection: DetectionMotion,
+
         /// Enable continuous monitoring mode
         #[arg(long, help = "Continuously monitor presence detection")]
         continuous: bool,
@@ -355,13 +1279,108 @@ pub enum Commands {
         )]
         count: Option<u32>,
 
-        /// Measurement interval in milliseconds for continuous mode
+        /// Measurement interval for continuous mode (bare numbers are milliseconds)
         #[arg(
             long,
             default_value = "1000",
-            help = "Time between measurements in ms (requires --continuous)"
+            value_parser = crate::pacing::parse_interval,
+            help = "Time between measurements: ms, or with a unit such as 12.5ms or 0.05s (requires --continuous)"
         )]
-        interval: u64,
+        interval: Duration,
+
+        /// Number of initial measurements to discard in continuous mode
+        #[arg(
+            long,
+            default_value = "0",
+            help = "Discard the first N measurements after calibration (requires --continuous)"
+        )]
+        warmup: u32,
+
+        /// Correlate the device measure counter with host timestamps
+        #[arg(
+            long,
+            help = "Report host/module clock drift and sampling jitter (requires --continuous)"
+        )]
+        clock_drift: bool,
+
+        /// Minimum time presence stays asserted once detected (PIR emulation)
+        #[arg(
+            long,
+            default_value = "0",
+            help = "Keep detected presence asserted for at least N ms even if scores drop (requires --continuous)"
+        )]
+        latch_ms: u64,
+
+        /// Host GPIO wired to the module's detection output (MISC_GPIO0)
+        #[arg(
+            long,
+            value_name = "GPIO",
+            requires = "continuous",
+            help = "Sleep on this host GPIO and only read results while the module signals presence (requires --continuous)"
+        )]
+        gpio_wakeup: Option<u32>,
+
+        /// Drive the module's detection output (MISC_GPIO0) while presence is detected
+        #[arg(
+            long,
+            help = "Have the module drive its detection output pin (MISC_GPIO0) high while presence is detected"
+        )]
+        gpio_output: bool,
+
+        /// Aggregate measurements over fixed time windows
+        #[arg(
+            long,
+            value_name = "DURATION",
+            value_parser = parse_duration,
+            requires = "continuous",
+            help = "Emit one min/max/mean record per window, e.g. 5s or 500ms (requires --continuous)"
+        )]
+        window: Option<Duration>,
+
+        /// Report presence transitions instead of every measurement
+        #[arg(
+            long,
+            requires = "continuous",
+            conflicts_with = "window",
+            help = "Output ENTERED/LEFT/STILL_PRESENT events instead of measurements on stdout, FIFO and MQTT (requires --continuous)"
+        )]
+        events: bool,
+
+        /// Time without detection before LEFT is reported
+        #[arg(
+            long,
+            value_name = "DURATION",
+            default_value = "5s",
+            value_parser = parse_duration,
+            help = "Report LEFT after no detection for this long, e.g. 5s or 2m (with --events)"
+        )]
+        hold_time: Duration,
+
+        /// Continuous detection needed before ENTERED is reported
+        #[arg(
+            long,
+            value_name = "DURATION",
+            value_parser = parse_duration,
+            requires = "events",
+            help = "Report ENTERED only after presence was detected for this long (default: first detection)"
+        )]
+        debounce: Option<Duration>,
+
+        /// Time between STILL_PRESENT events
+        #[arg(
+            long,
+            value_name = "DURATION",
+            default_value = "60s",
+            value_parser = parse_duration,
+            help = "Repeat STILL_PRESENT at this interval while presence lasts (with --events)"
+        )]
+        still_interval: Duration,
+
+        #[command(flatten)]
+        limits: RunLimits,
+
+        #[command(flatten)]
+        realtime: RealtimeArgs,
 
         /// Save measurements to CSV file (continuous mode only)
         #[arg(
@@ -369,6 +1388,94 @@ pub enum Commands {
             help = "Output CSV file path (e.g., presence_data.csv, requires --continuous)"
         )]
         save_to: Option<String>,
+
+        /// Empty-room false-positive survey for site acceptance testing
+        #[arg(
+            long,
+            conflicts_with = "continuous",
+            help = "Survey false positives with the room guaranteed empty and report rate, distances and suggested thresholds"
+        )]
+        baseline_capture: bool,
+
+        /// Duration of the baseline capture in seconds
+        #[arg(
+            long,
+            default_value = "3600",
+            help = "Baseline capture duration in seconds (requires --baseline-capture)"
+        )]
+        duration: u64,
+    },
+
+    /// Measure breathing rate
+    ///
+    /// Runs the breathing reference application, which locates a still person in range
+    /// and estimates their breathing rate once enough frames have been collected.
+    Breathing {
+        /// Start of the measured interval in meters
+        #[arg(
+            long,
+            default_value = "0.3",
+            help = "Closest distance to look for a person in meters (0.1-3.0)"
+        )]
+        min_range: f32,
+
+        /// End of the measured interval in meters
+        #[arg(
+            long,
+            default_value = "1.5",
+            help = "Farthest distance to look for a person in meters (0.1-3.0)"
+        )]
+        max_range: f32,
+
+        /// Lowest breathing rate to report
+        #[arg(
+            long,
+            default_value = "6",
+            help = "Lowest breathing rate in breaths per minute"
+        )]
+        min_bpm: u32,
+
+        /// Highest breathing rate to report
+        #[arg(
+            long,
+            default_value = "60",
+            help = "Highest breathing rate in breaths per minute"
+        )]
+        max_bpm: u32,
+
+        /// Frame rate for the breathing application in Hz
+        #[arg(long, default_value = "10.0", help = "Sensor frame rate in Hz (1-60)")]
+        frame_rate: f32,
+
+        /// Enable continuous monitoring mode
+        #[arg(long, help = "Continuously monitor the breathing rate")]
+        continuous: bool,
+
+        /// Number of measurements in continuous mode (omit for infinite)
+        #[arg(
+            long,
+            help = "Number of measurements to take (omit for infinite, requires --continuous)"
+        )]
+        count: Option<u32>,
+
+        /// Measurement interval for continuous mode (bare numbers are milliseconds)
+        #[arg(
+            long,
+            default_value = "1000",
+            value_parser = crate::pacing::parse_interval,
+            help = "Time between measurements: ms, or with a unit such as 500ms or 2s (requires --continuous)"
+        )]
+        interval: Duration,
+
+        #[command(flatten)]
+        limits: RunLimits,
+
+        /// Save measurements to CSV file (continuous mode only)
+        #[arg(
+            long,
+            help = "Output CSV file path (e.g., breathing_data.csv, requires --continuous)"
+        )]
+        save_to: Option<String>,
     },
 
     /// Firmware management commands
@@ -388,6 +1495,459 @@ pub enum Commands {
         #[command(subcommand)]
         action: GpioAction,
     },
+
+    /// Module power control
+    ///
+    /// Sleep and wake the XM125 through the WAKE_UP pin, verified against MCU_INT.
+    /// Does not require I2C, so it also works while the module is asleep.
+    Power {
+        #[command(subcommand)]
+        action: PowerAction,
+    },
+
+    /// Record labeled presence measurements for model training
+    ///
+    /// Tags every measurement with the ground-truth label and writes it to
+    /// <dataset-dir>/<label>/<timestamp>[-<scenario>]/ as measurements.csv plus
+    /// metadata.json (label, scenario, timing and detector configuration).
+    Capture {
+        /// Ground truth for the whole capture
+        #[arg(long, help = "Ground-truth label: occupied or empty")]
+        label: crate::dataset::CaptureLabel,
+
+        /// Capture duration in seconds
+        #[arg(long, default_value = "300", help = "Capture duration in seconds")]
+        duration: u64,
+
+        /// Time between measurements (bare numbers are milliseconds)
+        #[arg(
+            long,
+            default_value = "100",
+            value_parser = crate::pacing::parse_interval,
+            help = "Time between measurements: ms, or with a unit such as 12.5ms or 0.05s"
+        )]
+        interval: Duration,
+
+        /// Dataset root directory
+        #[arg(
+            long,
+            default_value = "dataset",
+            help = "Dataset root directory (captures go under <label>/)"
+        )]
+        dataset_dir: String,
+
+        /// Scenario name, appended to the capture directory
+        #[arg(long, help = "Scenario name (e.g., desk-seated-1m)")]
+        scenario: Option<String>,
+
+        /// Free-form notes stored in the metadata
+        #[arg(long, help = "Notes stored in metadata.json")]
+        notes: Option<String>,
+
+        /// Presence detection range preset
+        #[arg(
+            long,
+            help = "Detection range: short (6-70cm), medium (20cm-2m), long (50cm-7m)"
+        )]
+        range: Option<PresenceRange>,
+
+        /// Detection sensitivity threshold
+        #[arg(
+            long,
+            help = "Detection sensitivity: lower = less sensitive, higher = more sensitive"
+        )]
+        sensitivity: Option<f32>,
+    },
+
+    /// Serve measurements to multiple clients over a Unix socket
+    ///
+    /// Clients send one JSON request per line, e.g. {"kind":"presence","max_age_ms":250},
+    /// and receive one JSON response per line. Concurrent requests are batched, detector
+    /// mode switches are serialized, and recent results are reused up to the max age.
+    Serve {
+        /// Unix socket path
+        #[arg(
+            long,
+            default_value = "/run/xm125-radar-monitor.sock",
+            help = "Unix socket path to listen on"
+        )]
+        socket: String,
+
+        /// Default maximum age of a cached result
+        #[arg(
+            long,
+            default_value = "500",
+            help = "Serve cached results younger than this many ms (0 = always measure)"
+        )]
+        max_age_ms: u64,
+    },
+
+    /// Run continuously in the background, e.g. as a systemd service
+    ///
+    /// Configures the detector from --config (re-read on SIGHUP), then measures every
+    /// --interval into the global sinks (--fifo-output, --format on stdout, --shm) and, with
+    /// --save-dir, one CSV file per session. After an I2C or device failure the module is
+    /// reconnected and the detector configuration re-applied, backing off up to
+    /// --max-retry-delay. SIGTERM finishes the current measurement and exits. Under a
+    /// `Type=notify` unit, readiness, status and watchdog pings go to systemd.
+    Daemon {
+        /// Detector to run
+        #[arg(long, value_enum, default_value = "presence", help = "Detector to run")]
+        detector: DaemonDetector,
+
+        /// Time between measurements (bare numbers are milliseconds)
+        #[arg(
+            long,
+            default_value = "1000",
+            value_parser = crate::pacing::parse_interval,
+            help = "Time between measurements: ms, or with a unit such as 12.5ms or 0.05s"
+        )]
+        interval: Duration,
+
+        /// Directory receiving one CSV file per session
+        #[arg(
+            long,
+            help = "Write each session to <DIR>/<detector>-<timestamp>.csv (with session metadata)"
+        )]
+        save_dir: Option<String>,
+
+        /// First delay before recovering from a failure
+        #[arg(
+            long,
+            default_value = "2s",
+            value_parser = parse_duration,
+            help = "Delay before reconnecting after a failure, doubled on each consecutive failure"
+        )]
+        retry_delay: Duration,
+
+        /// Upper bound of the recovery back-off
+        #[arg(
+            long,
+            default_value = "60s",
+            value_parser = parse_duration,
+            help = "Longest delay between recovery attempts"
+        )]
+        max_retry_delay: Duration,
+    },
+
+    /// Low-power presence/distance hybrid for battery-powered sensors
+    ///
+    /// Watches for an approach with the presence firmware at a low frame rate, flashes the
+    /// distance firmware once presence has lasted --approach-time, and flashes the presence
+    /// firmware back after --clear-time without a distance target. Detector settings come
+    /// from --config, measurements and mode changes go to the global sinks. Every switch is
+    /// a flash cycle, so --min-dwell bounds how often it can happen.
+    Sentry {
+        /// Presence frame rate while waiting for an approach
+        #[arg(
+            long,
+            value_name = "HZ",
+            default_value = "1.0",
+            help = "Presence frame rate while waiting for an approach, Hz"
+        )]
+        presence_frame_rate: f32,
+
+        /// Distance range while ranging, overriding --config
+        #[arg(
+            long,
+            value_name = "START:END",
+            help = "Distance detector range in metres while ranging, e.g. 0.2:2.5"
+        )]
+        distance_range: Option<String>,
+
+        /// Time between distance measurements (bare numbers are milliseconds)
+        #[arg(
+            long,
+            default_value = "1000",
+            value_parser = crate::pacing::parse_interval,
+            help = "Time between distance measurements while ranging: ms, or with a unit such as 0.5s"
+        )]
+        interval: Duration,
+
+        /// Presence needed before switching to distance
+        #[arg(
+            long,
+            default_value = "2s",
+            value_parser = parse_duration,
+            help = "Continuous presence needed before switching to the distance firmware"
+        )]
+        approach_time: Duration,
+
+        /// Time without a target before switching back to presence
+        #[arg(
+            long,
+            default_value = "30s",
+            value_parser = parse_duration,
+            help = "Time without a distance target before switching back to the presence firmware"
+        )]
+        clear_time: Duration,
+
+        /// Minimum time in either mode
+        #[arg(
+            long,
+            default_value = "60s",
+            value_parser = parse_duration,
+            help = "Minimum time in either mode, limiting flash cycles when a target lingers at the edge of the range"
+        )]
+        min_dwell: Duration,
+
+        /// Stop after this long
+        #[arg(
+            long,
+            value_parser = parse_duration,
+            help = "Stop after this long (default: run until Ctrl+C or SIGTERM)"
+        )]
+        duration: Option<Duration>,
+    },
+
+    /// Interactive shell against a persistent radar connection
+    ///
+    /// Each line is parsed as a subcommand (e.g. `presence --range long`) and run on the
+    /// already connected radar, avoiding reconnect and calibration costs between manual
+    /// commands during bring-up. Global options given before `shell` apply to every line.
+    Shell {
+        /// Command history file
+        #[arg(long, help = "History file (default: ~/.xm125-radar-monitor_history)")]
+        history_file: Option<String>,
+    },
+
+    /// Collect a diagnostic snapshot as one JSON document
+    ///
+    /// Includes tool version, status and decoded status bits, firmware identity,
+    /// configuration registers, GPIO levels and one measurement of the running firmware's
+    /// mode. This is the report to attach to support requests.
+    Snapshot {
+        /// Write the snapshot to a file instead of stdout
+        #[arg(
+            short,
+            long,
+            help = "Write the snapshot to this file instead of stdout"
+        )]
+        output: Option<String>,
+    },
+
+    /// Characterize what this host and bus can sustain
+    Bench {
+        #[command(subcommand)]
+        action: BenchAction,
+    },
+
+    /// Check a data file against its detached signature
+    ///
+    /// Verifies the `<file>.sig` written with --sign-key: the file must be unchanged since it
+    /// was signed and the signature must match the public key it names. Pass the device's
+    /// fingerprint to also require that key. Needs a build with the `sign` feature.
+    VerifySignature {
+        /// Data file to check
+        #[arg(value_name = "FILE")]
+        file: String,
+
+        /// Fingerprint the signing key must have
+        #[arg(
+            long,
+            value_name = "SHA256:...",
+            help = "Require the file to be signed by the key with this fingerprint (from the session metadata)"
+        )]
+        fingerprint: Option<String>,
+    },
+
+    /// Triage a module that does not answer
+    ///
+    /// Checks the I2C device node and its permissions, the device lock, both the run and
+    /// bootloader addresses, the control GPIO exports and levels, firmware identity and
+    /// status, and resets a silent module to see whether it comes back. Ends with the
+    /// probable causes, most likely first, and the commands to try next.
+    Diagnose {
+        /// Do not reset the module when it does not answer
+        #[arg(long, help = "Never reset the module, even when it does not answer")]
+        no_reset: bool,
+    },
+
+    /// Show cumulative device statistics
+    ///
+    /// Lifetime measurement, detection, reset, flash and error counts for this module,
+    /// accumulated across sessions in --stats-file. No I2C access is needed.
+    Stats {
+        /// Show every module recorded in the statistics file
+        #[arg(
+            long,
+            help = "Show all modules in the statistics file, not just this one"
+        )]
+        all: bool,
+    },
+
+    /// Detector configuration register tools
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Print Home Assistant MQTT discovery config messages
+    ///
+    /// Prints one retained config message per entity (presence binary_sensor, distance and
+    /// temperature sensors) as `<topic> <payload>`, or as JSON lines with --format json.
+    /// Publish them once with retain so the radar appears in Home Assistant as a device.
+    HaDiscovery {
+        /// Topic the JSON measurement records are published on
+        #[arg(
+            long,
+            value_name = "TOPIC",
+            help = "MQTT topic carrying the JSON measurement records (default: xm125/<device id>)"
+        )]
+        state_topic: Option<String>,
+
+        /// Home Assistant discovery prefix
+        #[arg(
+            long,
+            default_value = "homeassistant",
+            help = "Home Assistant MQTT discovery prefix"
+        )]
+        discovery_prefix: String,
+    },
+
+    /// Generate shell completion scripts
+    ///
+    /// Example: xm125-radar-monitor completions bash > /etc/bash_completion.d/xm125-radar-monitor
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BenchAction {
+    /// Find the highest presence frame rate the host reads without missing frames
+    ///
+    /// Steps the presence frame rate up from --start to --max. At each rate it reads every
+    /// frame as the module's measure counter advances, counting missed frames, I2C errors and
+    /// the frame rate actually achieved. The sweep stops at the first rate that is not
+    /// sustained and reports the last one that was.
+    FrameRate {
+        /// First frame rate tried in Hz
+        #[arg(long, default_value = "5.0", help = "First frame rate to try in Hz")]
+        start: f32,
+
+        /// Highest frame rate tried in Hz
+        #[arg(long, default_value = "60.0", help = "Highest frame rate to try in Hz")]
+        max: f32,
+
+        /// Frame rate increment in Hz
+        #[arg(
+            long,
+            default_value = "5.0",
+            help = "Frame rate increment per step in Hz"
+        )]
+        step: f32,
+
+        /// Time spent at each frame rate
+        #[arg(
+            long,
+            default_value = "5s",
+            value_parser = parse_duration,
+            help = "Time spent measuring at each frame rate (e.g. 5s, 500ms)"
+        )]
+        duration: Duration,
+
+        /// Missed frames tolerated at a sustainable rate, in percent
+        #[arg(
+            long,
+            default_value = "1.0",
+            help = "Percentage of missed frames still counted as sustainable"
+        )]
+        max_missed: f32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Show the effective settings of the --config layers and where each value came from
+    ///
+    /// Layers are merged in the order given, later ones overriding earlier ones, e.g.
+    /// `--config base.toml --config site-london.toml --config device-42.toml config explain`.
+    Explain,
+
+    /// Print the effective configuration after --config, --config-profile and command line
+    ///
+    /// Shows the I2C device, GPIO pins, FIFO output and detector settings a measurement
+    /// command would use, e.g. `--config /etc/xm125.toml --config-profile lobby config show`.
+    Show,
+
+    /// Check the --config files without touching the module
+    ///
+    /// Reports unreadable files, unknown keys, aliases shadowing subcommands, out-of-range
+    /// detector values and an unknown --config-profile, and exits non-zero if any are found.
+    Validate,
+
+    /// Print configuration register changes made by other tools
+    ///
+    /// Snapshots the configuration registers of the running firmware periodically and
+    /// prints every register whose value changed since the previous snapshot, e.g. when
+    /// the Acconeer Exploration Tool reconfigures the module over UART on a shared rig.
+    Watch {
+        /// Time between snapshots in milliseconds
+        #[arg(
+            short,
+            long,
+            default_value = "1000",
+            help = "Time between register snapshots in milliseconds"
+        )]
+        interval: u64,
+
+        /// Number of snapshots to take
+        #[arg(
+            short = 'n',
+            long,
+            help = "Number of snapshots to take (default: until interrupted)"
+        )]
+        count: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DistanceAction {
+    /// Calibrate distance offset against a target at a known distance
+    ///
+    /// Measures the reference target repeatedly, computes a fixed offset (or scale)
+    /// correction and stores it in the calibration profile. The correction is applied
+    /// to all subsequent distance measurements, compensating for consistent bias such
+    /// as mounting behind a plastic fascia.
+    CalibrateOffset {
+        /// Actual distance to the reference target in meters
+        #[arg(
+            long,
+            help = "Known distance to the reference target in meters (e.g., 1.000)"
+        )]
+        actual: f32,
+
+        /// Number of measurements to average
+        #[arg(long, default_value = "20", help = "Number of measurements to average")]
+        samples: u32,
+
+        /// Compute a scale factor instead of a fixed offset
+        #[arg(long, help = "Correct with a scale factor instead of a fixed offset")]
+        scale: bool,
+    },
+
+    /// Record the detection threshold from the background of a cleared scene
+    ///
+    /// Switches the detector to the recorded threshold method, records the background
+    /// while nothing is in range, recalibrates and stores the recording's range and
+    /// profile in the calibration profile. Later distance runs use the recorded threshold
+    /// and warn when their configuration no longer matches it.
+    CalibrateThreshold {
+        /// Background frames to record
+        #[arg(
+            long,
+            default_value = "100",
+            value_parser = clap::value_parser!(u32).range(1..),
+            help = "Number of background frames to record the threshold from"
+        )]
+        frames: u32,
+
+        /// Do not wait for confirmation that the scene is clear
+        #[arg(long, help = "Start recording without waiting for Enter")]
+        yes: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -401,7 +1961,7 @@ pub enum FirmwareAction {
     /// Update firmware to match the specified detector mode
     ///
     /// Automatically flashes the correct firmware binary for the selected mode.
-    /// Uses stm32flash and GPIO control for safe firmware updates.
+    /// Uses the STM32 bootloader and GPIO control for safe firmware updates.
     Update {
         /// Target firmware type (distance or presence)
         firmware_type: firmware::FirmwareType,
@@ -415,10 +1975,36 @@ pub enum FirmwareAction {
         verify: bool,
     },
 
-    /// Verify firmware integrity using checksums
+    /// Update every module configured in the settings profiles
     ///
-    /// Compares the loaded firmware against known good checksums to detect
-    /// corruption or version mismatches.
+    /// Each `[profiles.<name>]` table with an I2C bus or address is one module. Modules
+    /// are flashed and verified one by one through their own GPIO pins; a module whose
+    /// update fails is flashed back to the firmware it was running.
+    UpdateAll {
+        /// Target firmware type
+        firmware_type: firmware::FirmwareType,
+
+        /// Modules flashed at the same time
+        #[arg(
+            long,
+            value_name = "N",
+            default_value = "1",
+            value_parser = clap::value_parser!(u8).range(1..),
+            help = "Flash up to N modules at once; modules sharing a bus or reset/BOOT0 line never overlap"
+        )]
+        parallel: u8,
+
+        /// Flash modules already running the firmware too
+        #[arg(short, long, help = "Also flash modules already running this firmware")]
+        force: bool,
+    },
+
+    /// Verify firmware integrity by reading back device flash
+    ///
+    /// Reads the programmed flash back through the bootloader and compares it
+    /// (CRC32 and byte-for-byte) with the firmware binary, detecting bit-rot or
+    /// partial flashes even when the application ID matches. Defaults to the
+    /// firmware type currently running on the device.
     Verify {
         /// Firmware type to verify against
         firmware_type: Option<firmware::FirmwareType>,
@@ -448,10 +2034,19 @@ pub enum FirmwareAction {
         verbose: bool,
     },
 
+    /// Manage the firmware staging area manifest
+    ///
+    /// The manifest records the size and CRC32 of each binary in --firmware-path.
+    /// Binaries are checked against it at startup and before every update.
+    Manifest {
+        #[command(subcommand)]
+        action: ManifestAction,
+    },
+
     /// Put XM125 module into bootloader mode for firmware programming
     ///
     /// Uses GPIO control to reset the module into bootloader mode (I2C address 0x48).
-    /// This is required for firmware programming with stm32flash.
+    /// This is required for firmware programming, e.g. with external tools.
     Bootloader {
         /// Reset to run mode after entering bootloader (for testing)
         #[arg(long, help = "Reset back to run mode after bootloader test")]
@@ -490,6 +2085,54 @@ pub enum GpioAction {
     /// Tests the bootloader pin control by cycling between bootloader and run modes.
     /// Useful for verifying GPIO hardware connections.
     Test,
+
+    /// Wait until the module signals readiness on MCU_INT
+    ///
+    /// Blocks until MCU_INT goes HIGH without driving any pins, so boot scripts can
+    /// sequence dependent services. Exits 0 when ready and 2 on timeout.
+    WaitReady {
+        /// Maximum time to wait in seconds
+        #[arg(long, default_value = "10", help = "Seconds to wait for MCU_INT HIGH")]
+        timeout: u32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ManifestAction {
+    /// Record the binaries currently in --firmware-path as known good
+    Generate,
+
+    /// Check the binaries in --firmware-path against the manifest
+    Check,
+}
+
+#[derive(Subcommand)]
+pub enum PowerAction {
+    /// Put the module to sleep
+    ///
+    /// Drives WAKE_UP low and waits for MCU_INT to go low. With --hold-reset the
+    /// module is also held in reset for true minimum power.
+    Sleep {
+        /// Hold the module in reset after it has gone to sleep
+        #[arg(long, help = "Also hold the module in reset for minimum power")]
+        hold_reset: bool,
+
+        /// Time to wait for MCU_INT in milliseconds
+        #[arg(long, default_value = "2000", help = "MCU_INT timeout in ms")]
+        timeout_ms: u64,
+    },
+
+    /// Wake the module
+    ///
+    /// Releases reset if held, drives WAKE_UP high and waits for MCU_INT to signal ready.
+    Wake {
+        /// Time to wait for MCU_INT in milliseconds
+        #[arg(long, default_value = "2000", help = "MCU_INT timeout in ms")]
+        timeout_ms: u64,
+    },
+
+    /// Show the module power state
+    Status,
 }
 
 #[derive(Clone, Debug, ValueEnum)]
@@ -502,7 +2145,46 @@ pub enum OutputFormat {
     Csv,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum WaitMode {
+    /// Read the status register every 10 ms (default)
+    Poll,
+    /// Sleep until MCU_INT rises, then confirm with one status read
+    Interrupt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TimestampFormat {
+    /// Calendar time, e.g. 2024-05-01 12:00:00.250 (default)
+    Iso,
+    /// CLOCK_REALTIME in integer nanoseconds
+    Realtime,
+    /// CLOCK_MONOTONIC in integer nanoseconds
+    Monotonic,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum FlushPolicy {
+    /// Flush after every record (default)
+    Every,
+    /// Flush at most every --flush-interval seconds
+    Interval,
+    /// Flush only when the run ends
+    End,
+}
+
 #[derive(Clone, Debug, ValueEnum)]
+pub enum QualityGateMode {
+    /// No quality checks (default)
+    Off,
+    /// Warn about invalid measurements but keep them in the output
+    Flag,
+    /// Drop invalid measurements from display, CSV and FIFO output
+    Reject,
+}
+
+#[derive(Clone, Debug, PartialEq, ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PresenceRange {
     /// Short range: 6cm to 70cm (good for close proximity detection)
     Short,
@@ -512,6 +2194,42 @@ pub enum PresenceRange {
     Long,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActiveLevel {
+    /// Asserted when driven low (default)
+    Low,
+    /// Asserted when driven high
+    High,
+}
+
+impl From<ActiveLevel> for crate::gpio::GpioValue {
+    fn from(level: ActiveLevel) -> Self {
+        match level {
+            ActiveLevel::Low => crate::gpio::GpioValue::Low,
+            ActiveLevel::High => crate::gpio::GpioValue::High,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reflector {
+    /// Objects of any shape (default)
+    Generic,
+    /// Flat surfaces facing the sensor: liquid levels, walls, lids
+    Planar,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Material {
+    /// Solid objects (default)
+    Solid,
+    /// Liquid surfaces: stricter signal quality, lower threshold sensitivity
+    Liquid,
+}
+
 #[derive(Clone, Debug, ValueEnum)]
 pub enum ProfileMode {
     /// Automatic profile selection based on range (default, recommended)
@@ -520,6 +2238,77 @@ pub enum ProfileMode {
     Manual,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum FlashTransportKind {
+    /// STM32 I2C bootloader on the module I2C bus (default)
+    I2c,
+    /// STM32 UART bootloader on the serial port given with --serial
+    Uart,
+}
+
+/// Detector run by `daemon`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DaemonDetector {
+    Presence,
+    Distance,
+}
+
+impl DaemonDetector {
+    pub fn name(self) -> &'static str {
+        match self {
+            DaemonDetector::Presence => "presence",
+            DaemonDetector::Distance => "distance",
+        }
+    }
+}
+
+impl From<DaemonDetector> for crate::radar::DetectorMode {
+    fn from(detector: DaemonDetector) -> Self {
+        match detector {
+            DaemonDetector::Presence => crate::radar::DetectorMode::Presence,
+            DaemonDetector::Distance => crate::radar::DetectorMode::Distance,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum DetectionMotion {
+    /// Intra-frame detection only: fast reaction to large movements
+    Fast,
+    /// Inter-frame detection only: slow micro-motion, fewer false positives from drafts
+    Slow,
+    /// Both detectors (default)
+    Both,
+}
+
+impl From<DetectionMotion> for crate::radar::MotionDetection {
+    fn from(motion: DetectionMotion) -> Self {
+        match motion {
+            DetectionMotion::Fast => crate::radar::MotionDetection::Fast,
+            DetectionMotion::Slow => crate::radar::MotionDetection::Slow,
+            DetectionMotion::Both => crate::radar::MotionDetection::Both,
+        }
+    }
+}
+
+impl From<Reflector> for crate::radar::ReflectorShape {
+    fn from(reflector: Reflector) -> Self {
+        match reflector {
+            Reflector::Generic => crate::radar::ReflectorShape::Generic,
+            Reflector::Planar => crate::radar::ReflectorShape::Planar,
+        }
+    }
+}
+
+impl From<Material> for crate::radar::TargetMaterial {
+    fn from(material: Material) -> Self {
+        match material {
+            Material::Solid => crate::radar::TargetMaterial::Solid,
+            Material::Liquid => crate::radar::TargetMaterial::Liquid,
+        }
+    }
+}
+
 impl From<PresenceRange> for crate::radar::PresenceRange {
     fn from(cli_range: PresenceRange) -> Self {
         match cli_range {
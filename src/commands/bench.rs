@@ -0,0 +1,256 @@
+//! Host and bus characterization (frame-rate sweep)
+
+use super::Context;
+use crate::cli::{BenchAction, OutputFormat};
+use crate::error::RadarError;
+use crate::radar::{DetectorMode, XM125Radar};
+use log::debug;
+use serde::Serialize;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// Frame rate range the presence detector accepts
+const MIN_FRAME_RATE: f32 = 1.0;
+const MAX_FRAME_RATE: f32 = 60.0;
+/// Fraction of the requested rate the module must reach for a step to count as sustained
+const MIN_ACHIEVED_RATIO: f64 = 0.9;
+
+/// Outcome of running at one frame rate
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepResult {
+    pub requested_hz: f32,
+    /// Frames counted by the module per second of the step
+    pub achieved_hz: f64,
+    /// Frames the module produced
+    pub frames: u32,
+    /// Frames produced but never read
+    pub missed: u32,
+    /// Failed register reads
+    pub i2c_errors: u32,
+    /// Configuration error that ended the step before measuring
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub sustained: bool,
+}
+
+/// Rates from `start` to `max` (inclusive) in `step` increments
+fn frame_rates(start: f32, max: f32, step: f32) -> Result<Vec<f32>, RadarError> {
+    if !(MIN_FRAME_RATE..=MAX_FRAME_RATE).contains(&start)
+        || !(start..=MAX_FRAME_RATE).contains(&max)
+        || step <= 0.0
+    {
+        return Err(RadarError::InvalidParameters(format!(
+            "frame rate sweep {start}-{max} Hz in {step} Hz steps must stay within \
+             {MIN_FRAME_RATE}-{MAX_FRAME_RATE} Hz with a positive step"
+        )));
+    }
+    let count = ((max - start) / step + 1e-3).floor() as u32;
+    Ok((0..=count).map(|i| start + i as f32 * step).collect())
+}
+
+/// Judge one step from the frames the module counted and the frames read in `elapsed`
+fn evaluate(
+    requested_hz: f32,
+    elapsed: Duration,
+    frames: u32,
+    read: u32,
+    i2c_errors: u32,
+    max_missed_percent: f32,
+) -> StepResult {
+    let achieved_hz = f64::from(frames) / elapsed.as_secs_f64().max(f64::EPSILON);
+    let missed = frames.saturating_sub(read);
+    let sustained = frames > 0
+        && i2c_errors == 0
+        && f64::from(missed) * 100.0 <= f64::from(max_missed_percent) * f64::from(frames)
+        && achieved_hz >= f64::from(requested_hz) * MIN_ACHIEVED_RATIO;
+    StepResult {
+        requested_hz,
+        achieved_hz,
+        frames,
+        missed,
+        i2c_errors,
+        error: None,
+        sustained,
+    }
+}
+
+/// Read every frame for `duration` as the measure counter advances
+async fn run_step(
+    radar: &mut XM125Radar,
+    requested_hz: f32,
+    duration: Duration,
+    max_missed_percent: f32,
+) -> StepResult {
+    // Poll several times per frame so a frame is read well before the next one replaces it
+    let poll = Duration::from_secs_f32(0.25 / requested_hz).max(Duration::from_millis(1));
+    let (mut frames, mut read, mut i2c_errors) = (0u32, 0u32, 0u32);
+    let mut last_counter = None;
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        match radar.read_measure_counter() {
+            Ok(counter) => {
+                let advanced = counter.wrapping_sub(*last_counter.get_or_insert(counter));
+                if advanced > 0 {
+                    last_counter = Some(counter);
+                    frames = frames.saturating_add(advanced);
+                    match radar.measure_presence().await {
+                        Ok(_) => read += 1,
+                        Err(e) => {
+                            debug!("Frame read failed at {requested_hz} Hz: {e}");
+                            i2c_errors += 1;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                debug!("Measure counter read failed at {requested_hz} Hz: {e}");
+                i2c_errors += 1;
+            }
+        }
+        sleep(poll).await;
+    }
+    evaluate(
+        requested_hz,
+        start.elapsed(),
+        frames,
+        read,
+        i2c_errors,
+        max_missed_percent,
+    )
+}
+
+fn print_step(step: &StepResult, format: &OutputFormat) {
+    match format {
+        OutputFormat::Json => {}
+        OutputFormat::Csv => println!(
+            "{:.1},{:.2},{},{},{},{}",
+            step.requested_hz,
+            step.achieved_hz,
+            step.frames,
+            step.missed,
+            step.i2c_errors,
+            step.sustained
+        ),
+        OutputFormat::Human => match &step.error {
+            Some(error) => println!("   {:>5.1} Hz  ❌ {error}", step.requested_hz),
+            None => println!(
+                "   {:>5.1} Hz  {:>8.2} Hz  {:>7}  {:>7}  {:>10}  {}",
+                step.requested_hz,
+                step.achieved_hz,
+                step.frames,
+                step.missed,
+                step.i2c_errors,
+                if step.sustained { "✅" } else { "❌" }
+            ),
+        },
+    }
+}
+
+/// Handle bench commands
+pub async fn handle_bench_command(
+    ctx: &mut Context<'_>,
+    action: &BenchAction,
+) -> Result<(), RadarError> {
+    let BenchAction::FrameRate {
+        start,
+        max,
+        step,
+        duration,
+        max_missed,
+    } = action;
+    let rates = frame_rates(*start, *max, *step)?;
+    let cli = ctx.cli;
+    let format = &cli.output.format;
+    let radar = &mut *ctx.radar;
+
+    super::firmware::ensure_firmware(cli, radar, DetectorMode::Presence).await?;
+    radar.set_detector_mode(DetectorMode::Presence);
+
+    match format {
+        OutputFormat::Csv => {
+            println!("requested_hz,achieved_hz,frames,missed,i2c_errors,sustained");
+        }
+        OutputFormat::Human => {
+            println!(
+                "🏁 Presence frame rate sweep: {start:.1} → {max:.1} Hz in {step:.1} Hz steps, {duration:?} each"
+            );
+            println!("   Requested   Achieved   Frames   Missed  I2C errors");
+        }
+        OutputFormat::Json => {}
+    }
+
+    let mut steps = Vec::new();
+    for rate in rates {
+        radar.config.frame_rate = rate;
+        let result = match radar.configure_presence_range().await {
+            Ok(()) => run_step(radar, rate, *duration, *max_missed).await,
+            Err(e) => StepResult {
+                requested_hz: rate,
+                achieved_hz: 0.0,
+                frames: 0,
+                missed: 0,
+                i2c_errors: 0,
+                error: Some(e.to_string()),
+                sustained: false,
+            },
+        };
+        print_step(&result, format);
+        let sustained = result.sustained;
+        steps.push(result);
+        if !sustained {
+            break;
+        }
+    }
+
+    let best = steps
+        .iter()
+        .rev()
+        .find(|step| step.sustained)
+        .map(|step| step.requested_hz);
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({
+                "max_sustainable_hz": best,
+                "i2c_device": cli.get_i2c_device_path(),
+                "steps": steps,
+            }))
+            .unwrap_or_default()
+        ),
+        OutputFormat::Csv => {}
+        OutputFormat::Human => match best {
+            Some(rate) => println!(
+                "✅ Maximum sustainable presence frame rate: {rate:.1} Hz on {}",
+                cli.get_i2c_device_path()
+            ),
+            None => println!("❌ Not even {start:.1} Hz was sustained"),
+        },
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sweep_rates_and_step_verdict() {
+        assert_eq!(
+            frame_rates(5.0, 20.0, 5.0).unwrap(),
+            [5.0, 10.0, 15.0, 20.0]
+        );
+        assert_eq!(frame_rates(10.0, 12.0, 5.0).unwrap(), [10.0]);
+        assert!(frame_rates(5.0, 80.0, 5.0).is_err());
+        assert!(frame_rates(5.0, 20.0, 0.0).is_err());
+
+        let second = Duration::from_secs(5);
+        assert!(evaluate(20.0, second, 100, 100, 0, 1.0).sustained);
+        let missing = evaluate(20.0, second, 100, 95, 0, 1.0);
+        assert_eq!(missing.missed, 5);
+        assert!(!missing.sustained);
+        assert!(!evaluate(20.0, second, 100, 100, 1, 1.0).sustained);
+        // Module capped well below the requested rate
+        assert!(!evaluate(40.0, second, 100, 100, 0, 1.0).sustained);
+    }
+}
@@ -0,0 +1,59 @@
+//! Breathing rate command
+
+use super::Context;
+use crate::display::{display_breathing_result, write_breathing_to_fifo};
+use crate::error::RadarError;
+use crate::limits::RunLimits;
+use crate::monitoring::monitor_breathing_continuous;
+use crate::pacing::frame_rate_warning;
+use crate::radar::{BreathingConfig, DetectorMode};
+use log::warn;
+use std::time::Duration;
+
+/// Parameters for the breathing command
+pub struct BreathingParams<'a> {
+    pub config: BreathingConfig,
+    pub continuous: bool,
+    pub count: Option<u32>,
+    pub interval: Duration,
+    pub limits: RunLimits,
+    pub save_to: Option<&'a str>,
+}
+
+/// Handle breathing command
+pub async fn handle_breathing_command(
+    ctx: &mut Context<'_>,
+    params: BreathingParams<'_>,
+) -> Result<(), RadarError> {
+    let cli = ctx.cli;
+    let radar = &mut *ctx.radar;
+
+    params.config.validate()?;
+    super::firmware::ensure_firmware(cli, radar, DetectorMode::Breathing).await?;
+    radar.config.breathing = params.config;
+    radar.configure_breathing_detector().await?;
+
+    if params.continuous {
+        if let Some(warning) = frame_rate_warning(params.interval, params.config.frame_rate) {
+            warn!("⚠️  {warning}");
+        }
+        monitor_breathing_continuous(
+            radar,
+            cli,
+            params.count,
+            params.interval,
+            params.save_to,
+            params.limits,
+            ctx.fifo_writer.as_deref_mut(),
+        )
+        .await?;
+        super::upload_export(cli, params.save_to).await?;
+    } else {
+        let result = radar.measure_breathing().await?;
+        display_breathing_result(&result, &cli.output.format);
+        if let Some(writer) = ctx.fifo_writer.as_deref_mut() {
+            write_breathing_to_fifo(writer, &result, &cli.output.fifo_format);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Labeled dataset capture command
+
+use super::Context;
+use crate::cli::{OutputFormat, PresenceRange, ProfileMode};
+use crate::config::configure_presence_parameters;
+use crate::dataset::{CaptureLabel, CaptureOptions, LabeledCapture};
+use crate::error::RadarError;
+use crate::monitoring::run_labeled_capture;
+use crate::pacing::{frame_rate_warning, interval_ms};
+use crate::radar::DetectorMode;
+use crate::upload::{self, UploadSession};
+use log::{info, warn};
+use std::time::Duration;
+
+/// Parameters for the capture command
+pub struct CaptureParams<'a> {
+    pub label: CaptureLabel,
+    pub duration: u64,
+    pub interval: Duration,
+    pub dataset_dir: &'a str,
+    pub scenario: Option<&'a str>,
+    pub notes: Option<&'a str>,
+    pub range: &'a Option<PresenceRange>,
+    pub sensitivity: Option<f32>,
+}
+
+/// Handle labeled capture command
+pub async fn handle_capture_command(
+    ctx: &mut Context<'_>,
+    params: CaptureParams<'_>,
+) -> Result<(), RadarError> {
+    let cli = ctx.cli;
+    let radar = &mut *ctx.radar;
+
+    super::firmware::ensure_firmware(cli, radar, DetectorMode::Presence).await?;
+    radar.set_detector_mode(DetectorMode::Presence);
+    configure_presence_parameters(
+        radar,
+        params.range.as_ref(),
+        None,
+        None,
+        params.sensitivity,
+        None,
+        &ProfileMode::Auto,
+    )
+    .await?;
+
+    if let Some(warning) = frame_rate_warning(params.interval, radar.config.frame_rate) {
+        warn!("⚠️  {warning}");
+    }
+
+    let options = CaptureOptions {
+        dataset_dir: params.dataset_dir,
+        label: params.label,
+        scenario: params.scenario,
+        notes: params.notes,
+        duration_s: params.duration,
+        interval_ms: interval_ms(params.interval),
+        sync_interval: Duration::from_secs_f32(cli.output.fsync_interval),
+    };
+    let capture = LabeledCapture::create(
+        &options,
+        radar.config.clone(),
+        radar.rss_version().map(|v| v.to_string()),
+    )?;
+    info!(
+        "🏷️  Capturing '{}' for {}s - keep the ground truth unchanged until the capture ends",
+        params.label, params.duration
+    );
+
+    let (dir, metadata) =
+        run_labeled_capture(radar, cli, capture, params.duration, params.interval).await?;
+
+    match cli.output.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&metadata)?),
+        OutputFormat::Csv => {
+            println!("directory,label,samples,detector_agreement");
+            println!(
+                "{},{},{},{}",
+                dir.display(),
+                metadata.label,
+                metadata.samples,
+                metadata.detector_agreement
+            );
+        }
+        OutputFormat::Human => {
+            let agreement = if metadata.samples > 0 {
+                100.0 * f64::from(metadata.detector_agreement) / f64::from(metadata.samples)
+            } else {
+                0.0
+            };
+            println!(
+                "🏷️  {} '{}' samples captured (detector agreed with label on {agreement:.1}%)",
+                metadata.samples, metadata.label
+            );
+            println!("💾 Saved to: {}", dir.display());
+        }
+    }
+
+    if let Some(destination) = &cli.output.upload {
+        let session = UploadSession::capture(&dir)?;
+        upload::upload(destination, &session, cli.output.upload_retries).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,329 @@
+//! Configuration commands (watch register changes made by other tools, explain, show and
+//! validate settings layers)
+
+use super::Context;
+use crate::aliases;
+use crate::cli::{Cli, ConfigAction, OutputFormat};
+use crate::error::RadarError;
+use crate::radar::RegisterInfo;
+use crate::settings::{self, Settings};
+use chrono::Utc;
+use serde_json::json;
+use tokio::time::{sleep, Duration};
+
+/// A configuration register whose value differs between two snapshots
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RegisterChange {
+    register: RegisterInfo,
+    old: u32,
+    new: u32,
+}
+
+/// Registers that changed from `previous` to `current` (snapshots of the same register list)
+fn diff_snapshots(
+    previous: &[(RegisterInfo, u32)],
+    current: &[(RegisterInfo, u32)],
+) -> Vec<RegisterChange> {
+    previous
+        .iter()
+        .zip(current)
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|(&(register, old), &(_, new))| RegisterChange { register, old, new })
+        .collect()
+}
+
+fn print_change(change: &RegisterChange, format: &OutputFormat) {
+    let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S%.3f");
+    let RegisterChange { register, old, new } = change;
+    match format {
+        OutputFormat::Json => {
+            let event = json!({
+                "event": "register_change",
+                "timestamp": timestamp.to_string(),
+                "address": register.address,
+                "name": register.name,
+                "old": old,
+                "new": new
+            });
+            println!("{event}");
+        }
+        OutputFormat::Csv => {
+            println!(
+                "{timestamp},0x{:04X},{},{old},{new}",
+                register.address, register.name
+            );
+        }
+        OutputFormat::Human => {
+            println!(
+                "{timestamp} ✏️  0x{:04X} {:<24} 0x{old:08X} → 0x{new:08X} ({old} → {new})",
+                register.address, register.name
+            );
+        }
+    }
+}
+
+/// Show the effective `--config` settings and the layer each value came from
+pub fn handle_config_explain(cli: &Cli) -> Result<(), RadarError> {
+    if cli.config.is_empty() {
+        return Err(RadarError::InvalidParameters(
+            "config explain needs at least one --config file".to_string(),
+        ));
+    }
+    let explained = settings::explain(&cli.config)?;
+    match cli.output.format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&explained).unwrap_or_default()
+        ),
+        OutputFormat::Csv => {
+            println!("key,value,source");
+            for value in &explained {
+                println!("{},{},{}", value.key, value.value, value.source);
+            }
+        }
+        OutputFormat::Human => {
+            println!(
+                "📚 Layers (later override earlier): {}",
+                cli.config.join(" → ")
+            );
+            for value in &explained {
+                let overrides = if value.overrides.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (overrides {})", value.overrides.join(", "))
+                };
+                println!(
+                    "   {:<28} = {:<20} ← {}{overrides}",
+                    value.key,
+                    value.value.to_string(),
+                    value.source
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Show the configuration a measurement command would run with
+pub fn handle_config_show(cli: &Cli) -> Result<(), RadarError> {
+    let settings = cli.settings()?;
+    let mut values: Vec<(String, serde_json::Value)> = vec![
+        ("i2c.device".to_string(), json!(cli.get_i2c_device_path())),
+        (
+            "i2c.address".to_string(),
+            json!(format!("0x{:02X}", cli.i2c_address)),
+        ),
+        ("gpio.reset".to_string(), json!(cli.gpio_reset)),
+        ("gpio.mcu_int".to_string(), json!(cli.gpio_mcu_int)),
+        ("gpio.wake".to_string(), json!(cli.gpio_wake)),
+        ("gpio.boot".to_string(), json!(cli.gpio_boot)),
+        (
+            "gpio.reset_active".to_string(),
+            json!(cli.gpio_reset_active),
+        ),
+        (
+            "gpio.reset_pulse_ms".to_string(),
+            json!(cli.gpio_reset_pulse.as_millis()),
+        ),
+        (
+            "gpio.startup_delay_ms".to_string(),
+            json!(cli.gpio_startup_delay.as_millis()),
+        ),
+        (
+            "gpio.wake_delay_ms".to_string(),
+            json!(cli.gpio_wake_delay.as_millis()),
+        ),
+        (
+            "gpio.settle_delay_ms".to_string(),
+            json!(cli.gpio_settle_delay.as_millis()),
+        ),
+        ("fifo.output".to_string(), json!(cli.output.fifo_output)),
+        ("fifo.path".to_string(), json!(cli.output.fifo_path)),
+        (
+            "fifo.format".to_string(),
+            json!(format!("{:?}", cli.output.fifo_format).to_lowercase()),
+        ),
+        ("fifo.interval".to_string(), json!(cli.output.fifo_interval)),
+    ];
+    values.extend(settings.detector_values());
+
+    match cli.output.format {
+        OutputFormat::Json => {
+            let object: serde_json::Map<String, serde_json::Value> = values.into_iter().collect();
+            let report = json!({
+                "layers": cli.config,
+                "profile": cli.config_profile,
+                "settings": object,
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_default()
+            );
+        }
+        OutputFormat::Csv => {
+            println!("key,value");
+            for (key, value) in &values {
+                println!("{key},{value}");
+            }
+        }
+        OutputFormat::Human => {
+            let layers = if cli.config.is_empty() {
+                "none".to_string()
+            } else {
+                cli.config.join(" → ")
+            };
+            println!("📋 Effective configuration");
+            println!("   Layers:  {layers}");
+            println!(
+                "   Profile: {}",
+                cli.config_profile.as_deref().unwrap_or("none")
+            );
+            for (key, value) in &values {
+                println!("   {key:<28} = {value}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check the `--config` layers, reporting every problem found
+pub fn handle_config_validate(cli: &Cli) -> Result<(), RadarError> {
+    if cli.config.is_empty() {
+        return Err(RadarError::InvalidParameters(
+            "config validate needs at least one --config file".to_string(),
+        ));
+    }
+    let settings = Settings::load_layers(&cli.config)?;
+    let mut problems = settings.problems();
+    if let Err(e) = aliases::check_aliases(&settings) {
+        problems.push(e.to_string());
+    }
+    let profiles: Vec<&String> = settings.profiles.keys().collect();
+
+    match cli.output.format {
+        OutputFormat::Json => {
+            let report = json!({
+                "valid": problems.is_empty(),
+                "layers": cli.config,
+                "profiles": profiles,
+                "problems": problems,
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_default()
+            );
+        }
+        OutputFormat::Csv => {
+            println!("problem");
+            for problem in &problems {
+                println!("\"{}\"", problem.replace('"', "\"\""));
+            }
+        }
+        OutputFormat::Human => {
+            if problems.is_empty() {
+                println!(
+                    "✅ {} valid ({} profile(s){})",
+                    cli.config.join(", "),
+                    profiles.len(),
+                    if profiles.is_empty() {
+                        String::new()
+                    } else {
+                        format!(
+                            ": {}",
+                            profiles
+                                .iter()
+                                .map(|name| name.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    }
+                );
+            } else {
+                println!("❌ {}", cli.config.join(", "));
+                for problem in &problems {
+                    println!("   • {problem}");
+                }
+            }
+        }
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(RadarError::InvalidParameters(format!(
+            "{} problem(s) in the --config files",
+            problems.len()
+        )))
+    }
+}
+
+/// Handle configuration register commands
+pub async fn handle_config_action(
+    ctx: &mut Context<'_>,
+    action: &ConfigAction,
+) -> Result<(), RadarError> {
+    let ConfigAction::Watch { interval, count } = action else {
+        unreachable!("config explain, show and validate are handled before I2C initialization");
+    };
+    let format = &ctx.cli.output.format;
+
+    let mut previous = ctx.radar.read_config_registers()?;
+    match format {
+        OutputFormat::Csv => println!("timestamp,address,name,old,new"),
+        OutputFormat::Human => {
+            println!(
+                "👀 Watching {} {} configuration registers every {interval}ms (Ctrl+C to stop)",
+                previous.len(),
+                ctx.radar.protocol().name()
+            );
+            for (register, value) in &previous {
+                println!(
+                    "   0x{:04X} {:<24} 0x{value:08X} ({value})",
+                    register.address, register.name
+                );
+            }
+        }
+        OutputFormat::Json => {}
+    }
+
+    let mut snapshots = 1u32;
+    while count.is_none_or(|count| snapshots < count) {
+        sleep(Duration::from_millis(*interval)).await;
+        let current = ctx.radar.read_config_registers()?;
+        for change in diff_snapshots(&previous, &current) {
+            print_change(&change, format);
+        }
+        previous = current;
+        snapshots += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let start = RegisterInfo {
+            address: 0x40,
+            name: "Start",
+            description: "",
+        };
+        let end = RegisterInfo {
+            address: 0x41,
+            name: "End",
+            description: "",
+        };
+
+        let previous = [(start, 250), (end, 3000)];
+        assert!(diff_snapshots(&previous, &previous).is_empty());
+        assert_eq!(
+            diff_snapshots(&previous, &[(start, 250), (end, 5000)]),
+            [RegisterChange {
+                register: end,
+                old: 3000,
+                new: 5000
+            }]
+        );
+    }
+}
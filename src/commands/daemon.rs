@@ -0,0 +1,180 @@
+//! Long-running daemon command
+//!
+//! Runs one detector continuously through the regular monitoring loop, wrapped in a
+//! supervisor: a session that fails after startup (I2C errors, device timeouts, a module
+//! that reset underneath us) is ended, the module reconnected and the detector
+//! configuration from `--config` applied again, with exponential back-off between attempts.
+//! Errors before the first session is running are returned, so misconfiguration fails the
+//! unit instead of retrying forever.
+
+use super::Context;
+use crate::cli::{Cli, DaemonDetector};
+use crate::device_lock;
+use crate::error::RadarError;
+use crate::limits::RunLimits;
+use crate::monitoring::{monitor_distance_continuous, monitor_presence_continuous, MonitorOptions};
+use crate::sd_notify;
+use crate::settings::{self, apply_distance, apply_presence, Reloader, Settings};
+use chrono::Utc;
+use log::{info, warn};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// Parameters for the daemon command
+pub struct DaemonParams<'a> {
+    pub detector: DaemonDetector,
+    pub interval: Duration,
+    pub save_dir: Option<&'a str>,
+    pub retry_delay: Duration,
+    pub max_retry_delay: Duration,
+}
+
+/// Settings from `--config`, keeping `last` in effect if the files can no longer be read
+fn current_settings(cli: &Cli, last: &Settings) -> Settings {
+    cli.settings().unwrap_or_else(|e| {
+        warn!("⚠️  Failed to re-read settings, keeping the previous ones: {e}");
+        last.clone()
+    })
+}
+
+/// Write `settings` to the detector, connecting first if needed
+async fn configure(
+    ctx: &mut Context<'_>,
+    detector: DaemonDetector,
+    settings: &Settings,
+) -> Result<(), RadarError> {
+    let radar = &mut *ctx.radar;
+    super::firmware::ensure_firmware(ctx.cli, radar, detector.into()).await?;
+    radar.set_detector_mode(detector.into());
+    match detector {
+        DaemonDetector::Presence => apply_presence(radar, &settings.presence).await,
+        DaemonDetector::Distance => apply_distance(radar, &settings.distance).await,
+    }
+}
+
+/// Measure until SIGTERM or a failure
+async fn run_session(
+    ctx: &mut Context<'_>,
+    params: &DaemonParams<'_>,
+    settings: &Settings,
+) -> Result<(), RadarError> {
+    let cli = ctx.cli;
+    let save_to = params
+        .save_dir
+        .map(|dir| -> Result<String, RadarError> {
+            std::fs::create_dir_all(dir)?;
+            let name = format!(
+                "{}-{}.csv",
+                params.detector.name(),
+                Utc::now().format("%Y%m%dT%H%M%S")
+            );
+            Ok(Path::new(dir).join(name).to_string_lossy().into_owned())
+        })
+        .transpose()?;
+    if let Some(path) = &save_to {
+        info!("💾 Recording session to {path}");
+    }
+
+    let options = MonitorOptions {
+        count: None,
+        interval: params.interval,
+        warmup: 0,
+        clock_drift: false,
+        latch_ms: 0,
+        gpio_wakeup: None,
+        save_to: save_to.as_deref(),
+        zones: None,
+        zone_events_only: false,
+        window: None,
+        presence_events: None,
+        limits: RunLimits::default(),
+        shm: ctx.shm,
+        mqtt: ctx.mqtt,
+        reloader: (!cli.config.is_empty())
+            .then(|| Reloader::new(cli, Settings::default(), settings.clone())),
+    };
+    let fifo_writer = ctx.fifo_writer.as_deref_mut();
+    match params.detector {
+        DaemonDetector::Presence => {
+            monitor_presence_continuous(ctx.radar, cli, options, fifo_writer).await
+        }
+        DaemonDetector::Distance => {
+            monitor_distance_continuous(ctx.radar, cli, options, fifo_writer).await
+        }
+    }
+}
+
+/// Wait `delay`, returning early on SIGTERM; the watchdog keeps being fed meanwhile
+async fn wait_for_retry(delay: Duration) {
+    let deadline = Instant::now() + delay;
+    while Instant::now() < deadline && !device_lock::stop_requested() {
+        sd_notify::watchdog();
+        sleep(Duration::from_millis(100).min(deadline - Instant::now())).await;
+    }
+}
+
+/// Handle the daemon command
+pub async fn handle_daemon_command(
+    ctx: &mut Context<'_>,
+    params: DaemonParams<'_>,
+) -> Result<(), RadarError> {
+    let cli = ctx.cli;
+    device_lock::catch_terminate();
+    // SIGHUP must not end the daemon even without --config to reload
+    settings::catch_reload();
+
+    let mut settings = cli.settings()?;
+    configure(ctx, params.detector, &settings).await?;
+    let running = format!(
+        "Monitoring {} every {:?}",
+        params.detector.name(),
+        params.interval
+    );
+    info!("🛰️  Daemon started: {running}");
+    sd_notify::ready(&running);
+
+    let mut retry_delay = params.retry_delay;
+    'sessions: loop {
+        let started = Instant::now();
+        let mut error = match run_session(ctx, &params, &settings).await {
+            Err(e) if !device_lock::stop_requested() => e,
+            _ => break,
+        };
+
+        // A session that ran for a while was a fresh failure, not the previous one recurring
+        if started.elapsed() > params.max_retry_delay {
+            retry_delay = params.retry_delay;
+        }
+        warn!("⚠️  Session failed: {error}");
+        if let Some(writer) = ctx.fifo_writer.as_deref() {
+            let _ = writer.write_status("Recovering");
+        }
+        loop {
+            info!("🔁 Recovering in {retry_delay:?}");
+            sd_notify::status(&format!("Recovering in {retry_delay:?}: {error}"));
+            wait_for_retry(retry_delay).await;
+            if device_lock::stop_requested() {
+                break 'sessions;
+            }
+            retry_delay = (retry_delay * 2).min(params.max_retry_delay);
+
+            // Start from a clean connection: the module may have reset or been reflashed
+            ctx.radar.forget_firmware();
+            settings = current_settings(cli, &settings);
+            match configure(ctx, params.detector, &settings).await {
+                Ok(()) => break,
+                Err(e) => {
+                    warn!("⚠️  Recovery failed: {e}");
+                    error = e;
+                }
+            }
+        }
+        info!("✅ Detector configuration re-applied");
+        sd_notify::status(&running);
+    }
+
+    info!("⏹️  Daemon stopping");
+    sd_notify::stopping();
+    Ok(())
+}
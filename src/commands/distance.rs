@@ -0,0 +1,334 @@
+//! Distance measurement and calibration commands
+
+use super::Context;
+use crate::calibration::{CalibrationProfile, DistanceCalibration, RecordedThreshold};
+use crate::cli::{Cli, Material, OutputFormat, Reflector};
+use crate::config::{configure_distance_range, debug_registers_if_connected};
+use crate::display::{display_distance_result, write_distance_to_fifo};
+use crate::error::RadarError;
+use crate::limits::RunLimits;
+use crate::monitoring::{monitor_distance_continuous, MonitorOptions};
+use crate::quality::{distance_issues, QualityGate};
+use crate::radar::{DetectorMode, XM125Radar};
+use crate::realtime::RealtimeArgs;
+use crate::settings::{DistanceSettings, Reloader, Settings};
+use crate::zones::ZoneSet;
+use chrono::Utc;
+use log::{info, warn};
+use std::io::IsTerminal;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::time::{sleep, Duration};
+
+/// Parameters for distance measurement command
+pub struct DistanceParams<'a> {
+    pub range: &'a Option<String>,
+    pub continuous: bool,
+    pub count: Option<u32>,
+    pub interval: Duration,
+    pub warmup: u32,
+    pub clock_drift: bool,
+    pub save_to: &'a Option<String>,
+    pub zones: Option<&'a str>,
+    pub zone_hysteresis: f32,
+    pub zone_events_only: bool,
+    pub temperature_coefficient: Option<f32>,
+    pub strength_reference: f32,
+    pub max_peaks: u8,
+    pub max_profile: Option<u32>,
+    pub max_step_length: Option<u32>,
+    pub reflector: Option<Reflector>,
+    pub material: Option<Material>,
+    pub window: Option<Duration>,
+    pub limits: RunLimits,
+    pub realtime: &'a RealtimeArgs,
+}
+
+/// Load the stored distance calibration and recorded threshold (if any) into the radar
+/// configuration
+fn load_distance_calibration(radar: &mut XM125Radar, cli: &Cli) -> Result<(), RadarError> {
+    let profile = CalibrationProfile::load(&cli.calibration_path())?;
+    if let Some(threshold) = profile.threshold {
+        info!(
+            "📐 Using the threshold recorded from {} background frames ({})",
+            threshold.frames,
+            threshold.recorded_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        let config = &radar.config;
+        let reasons = threshold.recalibration_reasons(
+            config.start_m,
+            config.start_m + config.length_m,
+            config.max_profile,
+        );
+        if !reasons.is_empty() {
+            warn!(
+                "⚠️  Recorded threshold needs recalibration ({}); clear the scene and run 'distance calibrate-threshold'",
+                reasons.join(", ")
+            );
+        }
+        radar.config.recorded_threshold_frames = Some(threshold.frames);
+    }
+    if let Some(calibration) = profile.distance {
+        info!(
+            "📐 Applying distance calibration: offset {:+.3}m, scale {:.4} (reference {:.3}m, {})",
+            calibration.offset_m,
+            calibration.scale,
+            calibration.reference_m,
+            calibration.calibrated_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        radar.config.distance_calibration = Some(calibration);
+    }
+    Ok(())
+}
+
+/// Handle distance offset calibration against a reference target
+pub async fn handle_distance_calibrate_offset(
+    ctx: &mut Context<'_>,
+    range: &Option<String>,
+    actual: f32,
+    samples: u32,
+    use_scale: bool,
+) -> Result<(), RadarError> {
+    if samples == 0 {
+        return Err(RadarError::InvalidParameters(
+            "Calibration requires at least one sample".to_string(),
+        ));
+    }
+
+    let cli = ctx.cli;
+    let radar = &mut *ctx.radar;
+    super::firmware::ensure_firmware(cli, radar, DetectorMode::Distance).await?;
+    radar.set_detector_mode(DetectorMode::Distance);
+    if let Some(range_str) = range {
+        configure_distance_range(radar, range_str)?;
+    }
+
+    // Measure uncorrected distances
+    radar.config.distance_calibration = None;
+
+    info!("📐 Calibrating distance against reference target at {actual:.3}m ({samples} samples)");
+    let mut readings = Vec::with_capacity(samples as usize);
+    for _ in 0..samples {
+        let result = radar.measure_distance().await?;
+        readings.push(result.distance);
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    let calibration = DistanceCalibration::from_measurements(actual, &readings, use_scale)?;
+
+    let mut profile = CalibrationProfile::load(&cli.calibration_path())?;
+    profile.distance = Some(calibration.clone());
+    profile.save(&cli.calibration_path())?;
+
+    match cli.output.format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&calibration)?);
+        }
+        OutputFormat::Csv => {
+            println!("reference_m,measured_mean_m,offset_m,scale,samples");
+            println!(
+                "{:.3},{:.4},{:.4},{:.5},{}",
+                calibration.reference_m,
+                calibration.measured_mean_m,
+                calibration.offset_m,
+                calibration.scale,
+                calibration.samples
+            );
+        }
+        OutputFormat::Human => {
+            println!("📐 Distance calibration complete");
+            println!(
+                "   Reference: {:.3}m | Measured mean: {:.4}m ({} valid samples)",
+                calibration.reference_m, calibration.measured_mean_m, calibration.samples
+            );
+            println!(
+                "   Correction: offset {:+.4}m, scale {:.5}",
+                calibration.offset_m, calibration.scale
+            );
+            println!("💾 Saved to: {}", cli.calibration_path());
+        }
+    }
+    Ok(())
+}
+
+/// Record the detection threshold from a cleared scene and store its metadata
+pub async fn handle_distance_calibrate_threshold(
+    ctx: &mut Context<'_>,
+    range: &Option<String>,
+    frames: u32,
+    yes: bool,
+) -> Result<(), RadarError> {
+    let cli = ctx.cli;
+    let radar = &mut *ctx.radar;
+    super::firmware::ensure_firmware(cli, radar, DetectorMode::Distance).await?;
+    radar.set_detector_mode(DetectorMode::Distance);
+    if let Some(range_str) = range.as_ref().or(cli.settings()?.distance.range.as_ref()) {
+        configure_distance_range(radar, range_str)?;
+    }
+    let start_m = radar.config.start_m;
+    let end_m = start_m + radar.config.length_m;
+
+    eprintln!("🧹 Clear the scene: nothing may be within {start_m:.2}-{end_m:.2}m of the sensor while the background is recorded.");
+    if !yes && std::io::stdin().is_terminal() {
+        eprintln!("   Press Enter to start recording...");
+        let mut line = String::new();
+        BufReader::new(tokio::io::stdin())
+            .read_line(&mut line)
+            .await?;
+    }
+
+    info!("📐 Recording the detection threshold from {frames} background frames");
+    radar.config.recorded_threshold_frames = Some(frames);
+    radar.configure_distance_detector().await?;
+    radar.recalibrate_distance_detector().await?;
+    let temperature_c = radar.measure_distance().await?.temperature;
+
+    let threshold = RecordedThreshold {
+        frames,
+        start_m,
+        end_m,
+        max_profile: radar.config.max_profile,
+        temperature_c: Some(temperature_c),
+        recorded_at: Utc::now(),
+    };
+    let mut profile = CalibrationProfile::load(&cli.calibration_path())?;
+    profile.threshold = Some(threshold.clone());
+    profile.save(&cli.calibration_path())?;
+
+    match cli.output.format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&threshold)?);
+        }
+        OutputFormat::Csv => {
+            println!("frames,start_m,end_m,max_profile,temperature_c");
+            println!(
+                "{},{:.3},{:.3},{},{temperature_c}",
+                threshold.frames, threshold.start_m, threshold.end_m, threshold.max_profile
+            );
+        }
+        OutputFormat::Human => {
+            println!("📐 Threshold recorded");
+            println!(
+                "   Frames: {} | Range: {:.3}-{:.3}m | Max profile: {} | Temperature: {temperature_c}°C",
+                threshold.frames, threshold.start_m, threshold.end_m, threshold.max_profile
+            );
+            println!("💾 Saved to: {}", cli.calibration_path());
+        }
+    }
+    Ok(())
+}
+
+/// Handle distance measurement command
+pub async fn handle_distance_command(
+    ctx: &mut Context<'_>,
+    params: DistanceParams<'_>,
+) -> Result<(), RadarError> {
+    let cli = ctx.cli;
+    let radar = &mut *ctx.radar;
+
+    // Validate zones before touching the device
+    let zones = params
+        .zones
+        .map(|spec| ZoneSet::parse(spec, params.zone_hysteresis))
+        .transpose()?;
+
+    // Refuse to drive the wrong firmware
+    super::firmware::ensure_firmware(cli, radar, DetectorMode::Distance).await?;
+
+    // Ensure device is in distance mode
+    radar.set_detector_mode(DetectorMode::Distance);
+
+    // Command-line options take precedence over the settings file
+    let overrides = Settings {
+        distance: DistanceSettings {
+            range: params.range.clone(),
+            max_profile: params.max_profile,
+            max_step_length: params.max_step_length,
+            reflector: params.reflector,
+            material: params.material,
+        },
+        ..Settings::default()
+    };
+    let effective = overrides.or(&cli.settings()?);
+    let distance = &effective.distance;
+
+    // Configure range if specified
+    if let Some(range_str) = &distance.range {
+        configure_distance_range(radar, range_str)?;
+    }
+
+    if let Some(coefficient) = params.temperature_coefficient {
+        info!("🌡️  Correcting thermal drift of {coefficient:+.2}mm/°C from the calibration temperature");
+        radar.config.temperature_coefficient = coefficient;
+    }
+    radar.config.strength_reference_db = params.strength_reference;
+    radar.config.max_peaks = usize::from(params.max_peaks);
+    if let Some(max_profile) = distance.max_profile {
+        radar.config.max_profile = max_profile;
+    }
+    if let Some(max_step_length) = distance.max_step_length {
+        radar.config.max_step_length = max_step_length;
+    }
+    if let Some(reflector) = distance.reflector {
+        radar.config.reflector_shape = reflector.into();
+    }
+    if let Some(material) = distance.material {
+        radar.config.target_material = material.into();
+    }
+    // Apply stored reference-target calibration and recorded threshold
+    load_distance_calibration(radar, cli)?;
+    if !cli.config.is_empty() {
+        radar.set_config_generation(Some(0));
+    }
+
+    // Debug registers if requested (global option)
+    if cli.logging.debug_registers {
+        debug_registers_if_connected(radar, &cli.output.format);
+    }
+
+    if params.continuous {
+        let options = MonitorOptions {
+            count: params.count,
+            interval: params.interval,
+            warmup: params.warmup,
+            clock_drift: params.clock_drift,
+            latch_ms: 0,
+            gpio_wakeup: None,
+            save_to: params.save_to.as_deref(),
+            zones,
+            zone_events_only: params.zone_events_only,
+            window: params.window,
+            presence_events: None,
+            limits: params.limits,
+            shm: ctx.shm,
+            mqtt: ctx.mqtt,
+            reloader: (!cli.config.is_empty())
+                .then(|| Reloader::new(cli, overrides, effective.clone())),
+        };
+        params.realtime.apply()?;
+        monitor_distance_continuous(radar, cli, options, ctx.fifo_writer.as_deref_mut()).await?;
+        super::upload_export(cli, params.save_to.as_deref()).await?;
+    } else {
+        let result = radar.measure_distance().await?;
+        let issues = distance_issues(&result);
+        if !QualityGate::from_cli(cli)?.admit("distance", &issues, &result)? {
+            return Err(RadarError::MeasurementFailed(format!(
+                "rejected by quality gate: {}",
+                issues.join(", ")
+            )));
+        }
+        display_distance_result(&result, &cli.output.format);
+
+        if let Some(shm) = ctx.shm {
+            shm.publish_distance(&result);
+        }
+        if let Some(mqtt) = ctx.mqtt {
+            mqtt.publish_distance(&result);
+        }
+
+        // Single measurement FIFO output
+        if let Some(writer) = ctx.fifo_writer.as_deref_mut() {
+            write_distance_to_fifo(writer, &result, &cli.output.fifo_format);
+        }
+    }
+    Ok(())
+}
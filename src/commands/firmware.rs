@@ -0,0 +1,370 @@
+//! Firmware management commands (update, update-all, verify, manifest, erase, bootloader)
+
+use super::Context;
+use crate::app_id_cache;
+use crate::cli::{Cli, FirmwareAction, ManifestAction, OutputFormat};
+use crate::error::RadarError;
+use crate::firmware::{self, FirmwarePaths, FirmwareType, FlashTransport};
+use crate::flash_state::FlashState;
+use crate::fleet::{self, Outcome};
+use crate::manifest::{BinaryStatus, FirmwareManifest};
+use crate::radar::{DetectorMode, XM125Radar};
+use crate::telemetry;
+use log::info;
+use std::time::Duration;
+
+/// Firmware manager for the binaries, timeout and bootloader link selected on the command line
+fn firmware_manager(cli: &Cli) -> firmware::FirmwareManager {
+    firmware::FirmwareManager::new(cli.firmware_paths(), "/usr/bin/xm125-control.sh", 0x52)
+        .with_flash_timeout(cli.timeouts().firmware_flash)
+        .with_transport(cli.flash_transport())
+}
+
+/// Flash `firmware_type` and record it, forgetting the identity of the previous firmware
+async fn flash(
+    cli: &Cli,
+    radar: &mut XM125Radar,
+    firmware_type: FirmwareType,
+    operation: &'static str,
+) -> Result<(), RadarError> {
+    // Whatever ends up on the module, the cached identity is stale
+    radar.forget_firmware();
+    let manager = firmware_manager(cli).with_state_file(&cli.firmware_state_path());
+    let operation = telemetry::start_firmware(operation);
+    let result = manager.update_firmware(firmware_type).await;
+    operation.finish(&result);
+    result?;
+    radar.stats().flash();
+    Ok(())
+}
+
+/// Fail fast if the module cannot serve `mode`, or flash the firmware it needs with `--auto-flash`
+pub async fn ensure_firmware(
+    cli: &Cli,
+    radar: &mut XM125Radar,
+    mode: DetectorMode,
+) -> Result<(), RadarError> {
+    match radar.ensure_firmware_for(mode) {
+        // Neither a simulated module nor a recorded trace can be flashed
+        Err(RadarError::FirmwareMismatch { required, detected })
+            if cli.auto_flash && cli.simulate.is_none() && cli.i2c_playback.is_none() =>
+        {
+            info!(
+                "🔁 {} firmware loaded but {} needed, flashing it (--auto-flash)",
+                detected.display_name(),
+                required.display_name()
+            );
+            flash(cli, radar, required, "auto-flash").await?;
+            radar.ensure_firmware_for(mode)
+        }
+        result => result,
+    }
+}
+
+/// Load the firmware serving `mode`, flashing it if another one is running
+///
+/// A simulated module switches its emulated application instead.
+pub async fn switch_firmware(
+    cli: &Cli,
+    radar: &mut XM125Radar,
+    mode: DetectorMode,
+) -> Result<(), RadarError> {
+    if radar.load_simulated_firmware(mode) {
+        return radar.ensure_firmware_for(mode);
+    }
+    match radar.ensure_firmware_for(mode) {
+        // A recorded trace cannot be flashed
+        Err(RadarError::FirmwareMismatch { required, .. }) if cli.i2c_playback.is_none() => {
+            flash(cli, radar, required, "switch").await?;
+            radar.ensure_firmware_for(mode)
+        }
+        result => result,
+    }
+}
+
+/// Handle firmware-related commands
+pub async fn handle_firmware_action(
+    ctx: &mut Context<'_>,
+    action: &FirmwareAction,
+) -> Result<(), RadarError> {
+    let radar = &mut *ctx.radar;
+    match action {
+        FirmwareAction::Check => {
+            let info = radar.get_info()?;
+            println!("📦 Current Firmware:");
+            println!("{info}");
+            if let Some(record) = FlashState::load(&ctx.cli.firmware_state_path())?.last_flash {
+                println!(
+                    "💾 Last flashed: {} ({} bytes) at {}",
+                    record.firmware_type,
+                    record.size,
+                    record.flashed_at.format("%Y-%m-%d %H:%M:%S UTC")
+                );
+                println!("   SHA256: {}", record.sha256);
+            }
+        }
+
+        FirmwareAction::Update { firmware_type, .. } => {
+            flash(ctx.cli, radar, *firmware_type, "update").await?;
+        }
+
+        FirmwareAction::Verify { firmware_type } => {
+            // Default to the firmware the device reports running
+            let fw_type = match firmware_type.or(radar.detected_firmware()) {
+                Some(fw_type) => fw_type,
+                None => FirmwareType::from_app_id(radar.application_id()?),
+            };
+
+            let operation = telemetry::start_firmware("verify");
+            let result = firmware_manager(ctx.cli)
+                .verify_flash_contents(fw_type)
+                .await;
+            operation.finish(&result);
+            let verification = result?;
+
+            println!(
+                "🔍 Flash verification ({}, {} bytes):",
+                fw_type.display_name(),
+                verification.length
+            );
+            println!("   Binary CRC32: 0x{:08X}", verification.expected_crc);
+            println!("   Device CRC32: 0x{:08X}", verification.device_crc);
+            if let Some(offset) = verification.first_mismatch {
+                println!("❌ Flash differs from binary starting at offset 0x{offset:06X}");
+                return Err(RadarError::FirmwareError {
+                    message: format!(
+                        "Device flash does not match {} binary",
+                        fw_type.display_name()
+                    ),
+                });
+            }
+            println!("✅ Device flash matches binary");
+        }
+
+        // These are handled earlier in the flow
+        FirmwareAction::Checksum { .. }
+        | FirmwareAction::UpdateAll { .. }
+        | FirmwareAction::Manifest { .. }
+        | FirmwareAction::Erase { .. }
+        | FirmwareAction::Bootloader { .. } => {
+            unreachable!("These actions should be handled before I2C initialization");
+        }
+    }
+    Ok(())
+}
+
+/// Handle `firmware update-all`: update every configured module and report each result
+pub async fn handle_update_all_command(
+    cli: &Cli,
+    firmware_type: FirmwareType,
+    parallel: usize,
+    force: bool,
+) -> Result<(), RadarError> {
+    let results = fleet::update_all(cli, firmware_type, parallel, force).await?;
+    // Every module may have been reset
+    if let Some(path) = cli.app_id_cache_path() {
+        app_id_cache::invalidate_file(path);
+    }
+
+    if matches!(cli.output.format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        println!(
+            "📦 {} firmware update of {} modules:",
+            firmware_type.display_name(),
+            results.len()
+        );
+        for result in &results {
+            let (icon, summary) = match result.outcome {
+                Outcome::Updated => ("✅", "updated and verified".to_string()),
+                Outcome::Unchanged => ("➖", "already up to date".to_string()),
+                Outcome::RolledBack => (
+                    "↩️ ",
+                    format!("failed, restored {}", result.previous.unwrap_or("previous")),
+                ),
+                Outcome::Failed => ("❌", "failed".to_string()),
+            };
+            println!(
+                "{icon} {} ({} @ 0x{:02X}): {summary}",
+                result.device, result.i2c_device, result.address
+            );
+            if let Some(error) = &result.error {
+                println!("     {error}");
+            }
+        }
+    }
+
+    let failed = results
+        .iter()
+        .filter(|result| matches!(result.outcome, Outcome::RolledBack | Outcome::Failed))
+        .count();
+    if failed > 0 {
+        return Err(RadarError::FirmwareError {
+            message: format!(
+                "{failed} of {} modules were not updated to {} firmware",
+                results.len(),
+                firmware_type.display_name()
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Handle firmware manifest generate/check
+pub fn handle_firmware_manifest_command(
+    action: &ManifestAction,
+    firmware_path: &str,
+) -> Result<(), RadarError> {
+    match action {
+        ManifestAction::Generate => {
+            let manifest = FirmwareManifest::generate(firmware_path)?;
+            let path = manifest.save(firmware_path)?;
+            for entry in &manifest.binaries {
+                println!(
+                    "{}: {} bytes, crc32 {}",
+                    entry.file, entry.size, entry.crc32
+                );
+            }
+            println!("✅ Firmware manifest written to {}", path.display());
+        }
+        ManifestAction::Check => {
+            let manifest =
+                FirmwareManifest::load(firmware_path)?.ok_or_else(|| RadarError::FirmwareError {
+                    message: format!(
+                        "No firmware manifest in {firmware_path} (create one with 'firmware manifest generate')"
+                    ),
+                })?;
+            let results = manifest.check(firmware_path)?;
+            for (file, status) in &results {
+                let icon = if *status == BinaryStatus::Ok {
+                    "✅"
+                } else {
+                    "❌"
+                };
+                println!("{icon} {file}: {status}");
+            }
+            if results
+                .iter()
+                .any(|(_, status)| *status != BinaryStatus::Ok)
+            {
+                return Err(RadarError::FirmwareError {
+                    message: "Firmware staging area does not match manifest".to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handle firmware erase command
+pub async fn handle_firmware_erase_command(
+    confirm: bool,
+    flash_timeout: Duration,
+    transport: &FlashTransport,
+) -> Result<(), RadarError> {
+    if !confirm {
+        eprintln!("❌ Chip erase requires --confirm flag for safety");
+        eprintln!("   This will completely erase all firmware from the XM125 module.");
+        eprintln!("   Use: xm125-radar-monitor firmware erase --confirm");
+        return Err(RadarError::DeviceError {
+            message: "Erase operation not confirmed".to_string(),
+        });
+    }
+
+    println!("⚠️  WARNING: This will completely erase the XM125 firmware!");
+    println!("🔄 Starting chip erase over {transport}...");
+
+    firmware::erase_flash(transport, flash_timeout)?;
+
+    println!("✅ Chip erase completed successfully");
+    println!("   The XM125 module now needs firmware to be programmed before use.");
+    Ok(())
+}
+
+/// Handle firmware checksum command
+pub fn handle_firmware_checksum_command(
+    firmware_type: Option<&FirmwareType>,
+    verbose: bool,
+    firmware_paths: &FirmwarePaths,
+) -> Result<(), RadarError> {
+    if let Some(fw_type) = firmware_type {
+        let manager = firmware::FirmwareManager::new(
+            firmware_paths.clone(),
+            "/usr/bin/xm125-control.sh",
+            0x52,
+        );
+        let digest = manager.binary_digest(*fw_type)?;
+        if verbose {
+            println!(
+                "Firmware: {} ({})",
+                fw_type.display_name(),
+                fw_type.binary_filename()
+            );
+            println!("Path: {}", firmware_paths.binary(*fw_type).display());
+            println!("Size: {} bytes", digest.size);
+            println!("MD5: {}", digest.md5);
+            println!("SHA256: {}", digest.sha256);
+        } else {
+            println!("{}: {}", fw_type.display_name(), digest.md5);
+        }
+        Ok(())
+    } else {
+        for fw_type in [
+            firmware::FirmwareType::Distance,
+            firmware::FirmwareType::Presence,
+        ] {
+            let manager = firmware::FirmwareManager::new(
+                firmware_paths.clone(),
+                "/usr/bin/xm125-control.sh",
+                0x52,
+            );
+            match manager.binary_digest(fw_type) {
+                Ok(digest) => {
+                    if verbose {
+                        println!(
+                            "Firmware: {} ({})",
+                            fw_type.display_name(),
+                            fw_type.binary_filename()
+                        );
+                        println!("Path: {}", firmware_paths.binary(fw_type).display());
+                        println!("Size: {} bytes", digest.size);
+                        println!("MD5: {}", digest.md5);
+                        println!("SHA256: {}", digest.sha256);
+                        println!();
+                    } else {
+                        println!("{}: {}", fw_type.display_name(), digest.md5);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("❌ {}: {e}", fw_type.display_name());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Handle bootloader command
+pub async fn handle_bootloader_command(cli: &Cli, test_mode: bool) -> Result<(), RadarError> {
+    let mut gpio_controller = super::gpio::gpio_controller(cli);
+    gpio_controller.initialize()?;
+
+    if test_mode {
+        println!("🧪 Testing bootloader mode (will reset back to run mode)");
+        gpio_controller.reset_to_bootloader_mode()?;
+
+        // Wait a moment for the device to enter bootloader mode
+        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+        // Reset back to run mode
+        gpio_controller.reset_to_run_mode()?;
+        println!("✅ Bootloader test completed");
+    } else {
+        println!("🔄 Putting XM125 into bootloader mode...");
+        gpio_controller.reset_to_bootloader_mode()?;
+        println!("✅ XM125 is now in bootloader mode (I2C address 0x48)");
+        println!("   Ready for firmware programming");
+        println!("   Use 'xm125-radar-monitor gpio reset-run' to return to normal mode");
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! GPIO and module power commands (no I2C connection required)
+
+use crate::cli::{Cli, GpioAction, OutputFormat, PowerAction};
+use crate::error::RadarError;
+use crate::gpio::{PowerState, XM125GpioController};
+use log::info;
+use serde_json::json;
+use std::time::Duration;
+
+/// Controller for the pins and reset timing configured on the command line
+pub(super) fn gpio_controller(cli: &Cli) -> XM125GpioController {
+    XM125GpioController::with_pins(cli.get_gpio_pins())
+}
+
+/// Handle GPIO commands
+pub fn handle_gpio_command(cli: &Cli, action: &GpioAction) -> Result<(), RadarError> {
+    // Waiting must not disturb a module that is already booting
+    if let GpioAction::WaitReady { timeout } = action {
+        let mut gpio_controller = gpio_controller(cli);
+        gpio_controller.attach()?;
+        gpio_controller.wait_for_module_ready(*timeout)?;
+        println!("✅ XM125 ready (MCU_INT HIGH)");
+        return Ok(());
+    }
+
+    let mut gpio_controller = gpio_controller(cli);
+    gpio_controller.initialize()?;
+
+    match action {
+        GpioAction::Init => {
+            gpio_controller.initialize()?;
+            println!("✅ GPIO pins initialized successfully");
+        }
+        GpioAction::Status => {
+            gpio_controller.show_gpio_status()?;
+        }
+        GpioAction::ResetRun => {
+            gpio_controller.initialize()?;
+            gpio_controller.reset_to_run_mode()?;
+            println!("✅ XM125 reset to run mode (I2C address 0x52)");
+        }
+        GpioAction::ResetBootloader => {
+            gpio_controller.initialize()?;
+            gpio_controller.reset_to_bootloader_mode()?;
+            println!("✅ XM125 reset to bootloader mode (I2C address 0x48)");
+        }
+        GpioAction::Test => {
+            gpio_controller.initialize()?;
+            gpio_controller.test_bootloader_control()?;
+        }
+        GpioAction::WaitReady { .. } => unreachable!("handled before pin initialization"),
+    }
+    Ok(())
+}
+
+/// Print a power state in the requested output format
+fn print_power_state(state: PowerState, format: &OutputFormat) -> Result<(), RadarError> {
+    match format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&json!({ "power_state": state }))?
+            );
+        }
+        OutputFormat::Csv => {
+            println!("power_state");
+            println!("{state}");
+        }
+        OutputFormat::Human => {
+            println!("🔋 XM125 power state: {state}");
+        }
+    }
+    Ok(())
+}
+
+/// Handle module power commands (no I2C required)
+pub fn handle_power_command(cli: &Cli, action: &PowerAction) -> Result<(), RadarError> {
+    let mut gpio_controller = gpio_controller(cli);
+    gpio_controller.attach()?;
+
+    match action {
+        PowerAction::Sleep {
+            hold_reset,
+            timeout_ms,
+        } => {
+            gpio_controller.sleep(*hold_reset, Duration::from_millis(*timeout_ms))?;
+        }
+        PowerAction::Wake { timeout_ms } => {
+            let elapsed = gpio_controller.wake(Duration::from_millis(*timeout_ms))?;
+            info!("✅ XM125 ready after {}ms", elapsed.as_millis());
+        }
+        PowerAction::Status => {}
+    }
+
+    print_power_state(gpio_controller.power_state()?, &cli.output.format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::GpioValue;
+    use clap::Parser;
+
+    #[test]
+    fn test_gpio_controller_uses_configured_pins_and_signals() {
+        let cli = Cli::try_parse_from([
+            "xm125-radar-monitor",
+            "--gpio-reset",
+            "20",
+            "--gpio-reset-active",
+            "high",
+            "--gpio-reset-pulse",
+            "250ms",
+            "--gpio-startup-delay",
+            "2s",
+            "gpio",
+            "reset-run",
+        ])
+        .unwrap();
+        let controller = gpio_controller(&cli);
+        let pins = controller.pins();
+        assert_eq!(pins.reset, 20);
+        assert_eq!(pins.signals.reset_active, GpioValue::High);
+        assert_eq!(pins.signals.reset_released(), GpioValue::Low);
+        assert_eq!(pins.signals.reset_pulse, Duration::from_millis(250));
+        assert_eq!(pins.signals.startup_delay, Duration::from_secs(2));
+    }
+}
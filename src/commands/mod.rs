@@ -0,0 +1,704 @@
+//! Command execution logic
+//!
+//! This module handles the main command dispatch and execution logic. Each command family
+//! has its own handler module; handlers that talk to the radar share a [`Context`] so the
+//! same code can be driven from the CLI, the measurement service or other front-ends.
+
+pub mod bench;
+pub mod breathing;
+pub mod capture;
+pub mod config;
+pub mod daemon;
+pub mod distance;
+pub mod firmware;
+pub mod gpio;
+pub mod presence;
+pub mod sentry;
+pub mod shell;
+pub mod stats;
+
+use crate::app_id_cache;
+use crate::cli::{
+    Cli, Commands, ConfigAction, DistanceAction, FirmwareAction, GpioAction, OutputFormat,
+};
+use crate::diagnose::{self, Report};
+use crate::discovery;
+use crate::error::RadarError;
+use crate::fifo::FifoWriter;
+use crate::firmware::FirmwareType;
+use crate::mqtt::MqttSink;
+use crate::presence_events::PresenceEventConfig;
+use crate::radar::{BreathingConfig, XM125Radar};
+use crate::sentry::SentryConfig;
+use crate::service;
+use crate::shm::ShmWriter;
+use crate::sign;
+use crate::snapshot::Snapshot;
+use crate::stats::StatsRecorder;
+use crate::telemetry;
+use crate::upload::{self, UploadSession};
+use log::warn;
+use serde_json::json;
+use tokio::time::Duration;
+
+/// Shared state for command handlers that need a connected radar
+pub struct Context<'a> {
+    pub cli: &'a Cli,
+    pub radar: &'a mut XM125Radar,
+    pub fifo_writer: Option<&'a mut FifoWriter>,
+    pub shm: Option<&'a ShmWriter>,
+    pub mqtt: Option<&'a MqttSink>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(
+        cli: &'a Cli,
+        radar: &'a mut XM125Radar,
+        fifo_writer: Option<&'a mut FifoWriter>,
+    ) -> Self {
+        Self {
+            cli,
+            radar,
+            fifo_writer,
+            shm: None,
+            mqtt: None,
+        }
+    }
+
+    /// Publish measurements to a shared-memory segment as well
+    pub fn with_shm(mut self, shm: Option<&'a ShmWriter>) -> Self {
+        self.shm = shm;
+        self
+    }
+
+    /// Publish measurements to an MQTT broker as well
+    pub fn with_mqtt(mut self, mqtt: Option<&'a MqttSink>) -> Self {
+        self.mqtt = mqtt;
+        self
+    }
+
+    /// Output format selected on the command line
+    pub fn format(&self) -> &OutputFormat {
+        &self.cli.output.format
+    }
+}
+
+/// Handle status command output in different formats
+fn handle_status_command(status: &str, format: &OutputFormat) -> Result<(), RadarError> {
+    match format {
+        OutputFormat::Json => {
+            let status_obj = json!({ "status": status });
+            println!("{}", serde_json::to_string_pretty(&status_obj)?);
+        }
+        OutputFormat::Csv => {
+            println!("status");
+            println!("{status}");
+        }
+        OutputFormat::Human => {
+            println!("📡 XM125 Status: {status}");
+        }
+    }
+    Ok(())
+}
+
+/// Upload the CSV export of a completed continuous run to `--upload`, if set
+async fn upload_export(cli: &Cli, save_to: Option<&str>) -> Result<(), RadarError> {
+    let Some(destination) = &cli.output.upload else {
+        return Ok(());
+    };
+    let Some(path) = save_to else {
+        warn!("⚠️  --upload has nothing to send without --save-to");
+        return Ok(());
+    };
+    let session = UploadSession::export(path, chrono::Utc::now());
+    upload::upload(destination, &session, cli.output.upload_retries).await
+}
+
+/// Handle info command output in different formats
+fn handle_info_command(info: &str, format: &OutputFormat) -> Result<(), RadarError> {
+    match format {
+        OutputFormat::Json => {
+            let info_obj = json!({ "info": info });
+            println!("{}", serde_json::to_string_pretty(&info_obj)?);
+        }
+        OutputFormat::Csv => {
+            println!("info");
+            println!("{info}");
+        }
+        OutputFormat::Human => {
+            println!("🔍 XM125 Device Information:");
+            println!("{info}");
+        }
+    }
+    Ok(())
+}
+
+/// Print the Home Assistant discovery config messages, one per line
+fn handle_ha_discovery_command(
+    state_topic: Option<&str>,
+    discovery_prefix: &str,
+    format: &OutputFormat,
+) -> Result<(), RadarError> {
+    let device_id = discovery::device_id();
+    let state_topic = state_topic.map_or_else(|| format!("xm125/{device_id}"), str::to_string);
+    for message in discovery::discovery_messages(discovery_prefix, &state_topic, &device_id) {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string(&message)?),
+            // Topic, then the payload: `while read -r topic payload; do mosquitto_pub ...`
+            OutputFormat::Csv | OutputFormat::Human => {
+                println!("{} {}", message.topic, message.payload);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check a data file's signature and print who signed it
+fn handle_verify_signature_command(
+    file: &str,
+    fingerprint: Option<&str>,
+    format: &OutputFormat,
+) -> Result<(), RadarError> {
+    let signature = sign::verify_file(file, fingerprint)?;
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&signature)?),
+        OutputFormat::Csv => println!(
+            "{},{},{},{}",
+            file, signature.bytes, signature.sha256, signature.fingerprint
+        ),
+        OutputFormat::Human => println!(
+            "✅ {file}: {} bytes unchanged, signed by {}",
+            signature.bytes, signature.fingerprint
+        ),
+    }
+    Ok(())
+}
+
+/// Print the triage checks and the ranked probable causes
+fn handle_diagnose_command(report: &Report, format: &OutputFormat) -> Result<(), RadarError> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        OutputFormat::Csv => {
+            println!("check,outcome,detail");
+            for check in &report.checks {
+                println!(
+                    "{},{},\"{}\"",
+                    check.name,
+                    format!("{:?}", check.outcome).to_lowercase(),
+                    check.detail.replace('"', "\"\"")
+                );
+            }
+        }
+        OutputFormat::Human => {
+            println!("🩺 XM125 connectivity triage");
+            for check in &report.checks {
+                println!(
+                    "  {} {:<18} {}",
+                    check.outcome.symbol(),
+                    check.name,
+                    check.detail
+                );
+            }
+            println!();
+            if report.causes.is_empty() {
+                println!("✅ No problems found");
+            } else {
+                println!("Probable causes (most likely first):");
+                for (rank, cause) in report.causes.iter().enumerate() {
+                    println!("  {}. {}", rank + 1, cause.summary);
+                    for step in &cause.next_steps {
+                        println!("       → {step}");
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Execute commands that don't need an I2C connection to the radar
+///
+/// Returns `None` when the command needs the radar and should go through [`execute_command`].
+pub async fn execute_local_command(cli: &Cli) -> Option<Result<(), RadarError>> {
+    if resets_module(cli.subcommand()) {
+        if let Some(path) = cli.app_id_cache_path() {
+            app_id_cache::invalidate_file(path);
+        }
+    }
+
+    let result = match cli.subcommand() {
+        Commands::Firmware { action } => match action {
+            FirmwareAction::Manifest { action } => {
+                firmware::handle_firmware_manifest_command(action, &cli.firmware_path)
+            }
+            FirmwareAction::Checksum {
+                firmware_type,
+                verbose,
+            } => firmware::handle_firmware_checksum_command(
+                firmware_type.as_ref(),
+                *verbose,
+                &cli.firmware_paths(),
+            ),
+            FirmwareAction::Erase { confirm } => {
+                let operation = telemetry::start_firmware("erase");
+                let result = firmware::handle_firmware_erase_command(
+                    *confirm,
+                    cli.timeouts().firmware_flash,
+                    &cli.flash_transport(),
+                )
+                .await;
+                operation.finish(&result);
+                result
+            }
+            FirmwareAction::UpdateAll {
+                firmware_type,
+                parallel,
+                force,
+            } => {
+                let operation = telemetry::start_firmware("update-all");
+                let result = firmware::handle_update_all_command(
+                    cli,
+                    *firmware_type,
+                    usize::from(*parallel),
+                    *force,
+                )
+                .await;
+                operation.finish(&result);
+                result
+            }
+            FirmwareAction::Bootloader { test_mode } => {
+                let operation = telemetry::start_firmware("bootloader");
+                let result = firmware::handle_bootloader_command(cli, *test_mode).await;
+                operation.finish(&result);
+                result
+            }
+            _ => return None, // Other firmware commands need I2C connection
+        },
+        Commands::Completions { shell } => shell::handle_completions_command(*shell),
+        Commands::HaDiscovery {
+            state_topic,
+            discovery_prefix,
+        } => handle_ha_discovery_command(
+            state_topic.as_deref(),
+            discovery_prefix,
+            &cli.output.format,
+        ),
+        Commands::Config {
+            action: ConfigAction::Explain,
+        } => config::handle_config_explain(cli),
+        Commands::Config {
+            action: ConfigAction::Show,
+        } => config::handle_config_show(cli),
+        Commands::Config {
+            action: ConfigAction::Validate,
+        } => config::handle_config_validate(cli),
+        Commands::Stats { all } => stats::handle_stats_command(cli, *all),
+        Commands::Diagnose { no_reset } => {
+            handle_diagnose_command(&diagnose::run(cli, !*no_reset), &cli.output.format)
+        }
+        Commands::VerifySignature { file, fingerprint } => {
+            handle_verify_signature_command(file, fingerprint.as_deref(), &cli.output.format)
+        }
+        Commands::Gpio { action } => gpio::handle_gpio_command(cli, action),
+        Commands::Power { action } => gpio::handle_power_command(cli, action),
+        _ => return None, // Other commands need I2C connection
+    };
+    if resets_module(cli.subcommand()) && result.is_ok() {
+        StatsRecorder::new(
+            cli.stats_path().as_deref(),
+            &cli.get_i2c_device_path(),
+            cli.i2c_address,
+        )
+        .reset();
+    }
+    Some(result)
+}
+
+/// Local commands after which the cached application ID can no longer be trusted
+fn resets_module(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Firmware {
+            action: FirmwareAction::Erase { confirm: true } | FirmwareAction::Bootloader { .. }
+        } | Commands::Gpio {
+            action: GpioAction::ResetRun | GpioAction::ResetBootloader
+        }
+    )
+}
+
+/// Commands that drive the bootloader or GPIO lines, which `--simulate` does not emulate
+pub fn needs_hardware(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Firmware {
+            action: FirmwareAction::Update { .. }
+                | FirmwareAction::UpdateAll { .. }
+                | FirmwareAction::Verify { .. }
+                | FirmwareAction::Erase { .. }
+                | FirmwareAction::Bootloader { .. }
+        } | Commands::Gpio { .. }
+    )
+}
+
+/// Firmware binaries `command` reads from disk, checked before it starts
+pub fn required_firmware(command: &Commands) -> Vec<FirmwareType> {
+    match command {
+        Commands::Firmware {
+            action:
+                FirmwareAction::Update { firmware_type, .. }
+                | FirmwareAction::UpdateAll { firmware_type, .. },
+        } => vec![*firmware_type],
+        Commands::Firmware {
+            action:
+                FirmwareAction::Verify {
+                    firmware_type: Some(firmware_type),
+                }
+                | FirmwareAction::Checksum {
+                    firmware_type: Some(firmware_type),
+                    ..
+                },
+        } => vec![*firmware_type],
+        Commands::Sentry { .. } => vec![FirmwareType::Presence, FirmwareType::Distance],
+        _ => Vec::new(),
+    }
+}
+
+/// Files and directories a radar command writes, with the option that relocates each
+pub fn writable_paths(cli: &Cli) -> Vec<(String, &'static str)> {
+    let mut paths = vec![(cli.lock_dir_path(), "--lock-dir or --runtime-dir")];
+    if let Some(path) = cli.app_id_cache_path() {
+        paths.push((path, "--app-id-cache or --runtime-dir"));
+    }
+    if let Some(path) = cli.stats_path() {
+        paths.push((path, "--stats-file or --state-dir"));
+    }
+    if let Some(path) = &cli.i2c_record {
+        paths.push((path.clone(), "--i2c-record"));
+    }
+    if let Some(path) = &cli.output.spool {
+        paths.push((path.clone(), "--spool"));
+    }
+    if let Some(path) = &cli.output.status_file {
+        paths.push((path.clone(), "--status-file"));
+    }
+    if let Some(path) = &cli.output.heartbeat_file {
+        paths.push((path.clone(), "--heartbeat-file"));
+    }
+    if cli.output.fifo_output {
+        paths.push((cli.output.fifo_path.clone(), "--fifo-path"));
+    }
+    match cli.subcommand() {
+        Commands::Distance {
+            action: Some(DistanceAction::CalibrateOffset { .. }),
+            ..
+        } => paths.push((cli.calibration_path(), "--calibration-file or --state-dir")),
+        Commands::Distance {
+            save_to: Some(path),
+            ..
+        }
+        | Commands::Presence {
+            save_to: Some(path),
+            ..
+        }
+        | Commands::Breathing {
+            save_to: Some(path),
+            ..
+        } => paths.push((path.clone(), "--save-to")),
+        Commands::Capture { dataset_dir, .. } => paths.push((dataset_dir.clone(), "--dataset-dir")),
+        Commands::Daemon {
+            save_dir: Some(dir),
+            ..
+        } => paths.push((dir.clone(), "--save-dir")),
+        Commands::Firmware {
+            action: FirmwareAction::Update { .. },
+        } => paths.push((cli.firmware_state_path(), "--firmware-state or --state-dir")),
+        _ => {}
+    }
+    paths
+}
+
+/// Execute the main command logic
+pub async fn execute_command(ctx: &mut Context<'_>) -> Result<(), RadarError> {
+    let cli = ctx.cli;
+    match cli.subcommand() {
+        Commands::Serve { socket, max_age_ms } => {
+            service::serve(ctx.radar, socket, Duration::from_millis(*max_age_ms)).await?;
+        }
+
+        Commands::Daemon {
+            detector,
+            interval,
+            save_dir,
+            retry_delay,
+            max_retry_delay,
+        } => {
+            let params = daemon::DaemonParams {
+                detector: *detector,
+                interval: *interval,
+                save_dir: save_dir.as_deref(),
+                retry_delay: *retry_delay,
+                max_retry_delay: *max_retry_delay,
+            };
+            daemon::handle_daemon_command(ctx, params).await?;
+        }
+
+        Commands::Sentry {
+            presence_frame_rate,
+            distance_range,
+            interval,
+            approach_time,
+            clear_time,
+            min_dwell,
+            duration,
+        } => {
+            let params = sentry::SentryParams {
+                presence_frame_rate: *presence_frame_rate,
+                distance_range: distance_range.as_deref(),
+                interval: *interval,
+                config: SentryConfig {
+                    approach: *approach_time,
+                    clear: *clear_time,
+                    min_dwell: *min_dwell,
+                },
+                duration: *duration,
+            };
+            sentry::handle_sentry_command(ctx, params).await?;
+        }
+
+        Commands::Status => {
+            let status = ctx.radar.get_status()?;
+            handle_status_command(&status, ctx.format())?;
+        }
+
+        Commands::Info => {
+            let info = ctx.radar.get_info()?;
+            handle_info_command(&info, ctx.format())?;
+        }
+
+        Commands::Distance {
+            action:
+                Some(DistanceAction::CalibrateOffset {
+                    actual,
+                    samples,
+                    scale,
+                }),
+            range,
+            ..
+        } => {
+            distance::handle_distance_calibrate_offset(ctx, range, *actual, *samples, *scale)
+                .await?;
+        }
+
+        Commands::Distance {
+            action: Some(DistanceAction::CalibrateThreshold { frames, yes }),
+            range,
+            ..
+        } => {
+            distance::handle_distance_calibrate_threshold(ctx, range, *frames, *yes).await?;
+        }
+
+        Commands::Distance {
+            action: None,
+            range,
+            continuous,
+            count,
+            interval,
+            warmup,
+            clock_drift,
+            save_to,
+            zones,
+            zone_hysteresis,
+            zone_events_only,
+            temperature_coefficient,
+            strength_reference,
+            max_peaks,
+            max_profile,
+            max_step_length,
+            reflector,
+            material,
+            window,
+            limits,
+            realtime,
+        } => {
+            let params = distance::DistanceParams {
+                range,
+                continuous: *continuous,
+                count: *count,
+                interval: *interval,
+                warmup: *warmup,
+                clock_drift: *clock_drift,
+                save_to,
+                zones: zones.as_deref(),
+                zone_hysteresis: *zone_hysteresis,
+                zone_events_only: *zone_events_only,
+                temperature_coefficient: *temperature_coefficient,
+                strength_reference: *strength_reference,
+                max_peaks: *max_peaks,
+                max_profile: *max_profile,
+                max_step_length: *max_step_length,
+                reflector: *reflector,
+                material: *material,
+                window: *window,
+                limits: *limits,
+                realtime,
+            };
+            distance::handle_distance_command(ctx, params).await?;
+        }
+
+        Commands::Presence {
+            range,
+            min_range,
+            max_range,
+            sensitivity,
+            frame_rate,
+            profile,
+            detection,
+            continuous,
+            count,
+            interval,
+            warmup,
+            clock_drift,
+            latch_ms,
+            gpio_wakeup,
+            gpio_output,
+            window,
+            events,
+            hold_time,
+            debounce,
+            still_interval,
+            save_to,
+            baseline_capture,
+            duration,
+            limits,
+            realtime,
+        } => {
+            let params = presence::PresenceParams {
+                range,
+                min_range: *min_range,
+                max_range: *max_range,
+                sensitivity: *sensitivity,
+                frame_rate: *frame_rate,
+                profile,
+                detection: *detection,
+                baseline_capture: *baseline_capture,
+                duration: *duration,
+                continuous: *continuous,
+                count: *count,
+                interval: *interval,
+                warmup: *warmup,
+                clock_drift: *clock_drift,
+                latch_ms: *latch_ms,
+                gpio_wakeup: *gpio_wakeup,
+                gpio_output: *gpio_output,
+                window: *window,
+                events: events.then_some(PresenceEventConfig {
+                    debounce: debounce.unwrap_or_default(),
+                    hold: *hold_time,
+                    still_interval: *still_interval,
+                }),
+                limits: *limits,
+                realtime,
+                save_to,
+            };
+            presence::handle_presence_command(ctx, params).await?;
+        }
+
+        Commands::Breathing {
+            min_range,
+            max_range,
+            min_bpm,
+            max_bpm,
+            frame_rate,
+            continuous,
+            count,
+            interval,
+            limits,
+            save_to,
+        } => {
+            let params = breathing::BreathingParams {
+                config: BreathingConfig {
+                    start_m: *min_range,
+                    end_m: *max_range,
+                    lowest_bpm: *min_bpm,
+                    highest_bpm: *max_bpm,
+                    frame_rate: *frame_rate,
+                },
+                continuous: *continuous,
+                count: *count,
+                interval: *interval,
+                limits: *limits,
+                save_to: save_to.as_deref(),
+            };
+            breathing::handle_breathing_command(ctx, params).await?;
+        }
+
+        Commands::Capture {
+            label,
+            duration,
+            interval,
+            dataset_dir,
+            scenario,
+            notes,
+            range,
+            sensitivity,
+        } => {
+            let params = capture::CaptureParams {
+                label: *label,
+                duration: *duration,
+                interval: *interval,
+                dataset_dir,
+                scenario: scenario.as_deref(),
+                notes: notes.as_deref(),
+                range,
+                sensitivity: *sensitivity,
+            };
+            capture::handle_capture_command(ctx, params).await?;
+        }
+
+        Commands::Firmware { action } => {
+            firmware::handle_firmware_action(ctx, action).await?;
+        }
+
+        Commands::Snapshot { output } => {
+            let snapshot = Snapshot::collect(
+                ctx.radar,
+                cli.get_i2c_device_path(),
+                cli.i2c_address,
+                cli.get_gpio_pins(),
+            )
+            .await;
+            let json = serde_json::to_string_pretty(&snapshot)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(path, json + "\n")?;
+                    println!("📋 Snapshot written to {path}");
+                }
+                None => println!("{json}"),
+            }
+        }
+
+        Commands::Config { action } => {
+            config::handle_config_action(ctx, action).await?;
+        }
+
+        Commands::Bench { action } => {
+            bench::handle_bench_command(ctx, action).await?;
+        }
+
+        Commands::Shell { history_file } => {
+            shell::run_shell(ctx, history_file.as_deref()).await?;
+        }
+
+        Commands::Gpio { .. }
+        | Commands::Power { .. }
+        | Commands::Completions { .. }
+        | Commands::HaDiscovery { .. }
+        | Commands::Diagnose { .. }
+        | Commands::VerifySignature { .. }
+        | Commands::Stats { .. } => {
+            // Local commands are handled earlier, this should not be reached
+            unreachable!("Local commands should be handled before I2C initialization");
+        }
+    }
+    Ok(())
+}
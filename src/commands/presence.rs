@@ -0,0 +1,150 @@
+//! Presence detection and baseline capture commands
+
+use super::Context;
+use crate::config::{configure_presence_parameters, debug_registers_if_connected};
+use crate::display::{display_presence_result, write_presence_to_fifo};
+use crate::error::RadarError;
+use crate::limits::RunLimits;
+use crate::monitoring::{monitor_presence_continuous, run_baseline_capture, MonitorOptions};
+use crate::pacing::frame_rate_warning;
+use crate::presence_events::PresenceEventConfig;
+use crate::quality::{presence_issues, QualityGate};
+use crate::radar::DetectorMode;
+use crate::realtime::RealtimeArgs;
+use crate::settings::{PresenceSettings, Reloader, Settings};
+use log::warn;
+use std::time::Duration;
+
+/// Parameters for presence detection command
+pub struct PresenceParams<'a> {
+    pub range: &'a Option<crate::cli::PresenceRange>,
+    pub min_range: Option<f32>,
+    pub max_range: Option<f32>,
+    pub sensitivity: Option<f32>,
+    pub frame_rate: Option<f32>,
+    pub profile: &'a crate::cli::ProfileMode,
+    pub detection: crate::cli::DetectionMotion,
+    pub baseline_capture: bool,
+    pub duration: u64,
+    pub continuous: bool,
+    pub count: Option<u32>,
+    pub interval: Duration,
+    pub warmup: u32,
+    pub clock_drift: bool,
+    pub latch_ms: u64,
+    pub gpio_wakeup: Option<u32>,
+    pub gpio_output: bool,
+    pub window: Option<std::time::Duration>,
+    pub events: Option<PresenceEventConfig>,
+    pub limits: RunLimits,
+    pub realtime: &'a RealtimeArgs,
+    pub save_to: &'a Option<String>,
+}
+
+/// Handle presence detection command
+pub async fn handle_presence_command(
+    ctx: &mut Context<'_>,
+    params: PresenceParams<'_>,
+) -> Result<(), RadarError> {
+    let cli = ctx.cli;
+    let radar = &mut *ctx.radar;
+
+    // Refuse to drive the wrong firmware
+    super::firmware::ensure_firmware(cli, radar, DetectorMode::Presence).await?;
+
+    // Ensure device is in presence mode
+    radar.set_detector_mode(DetectorMode::Presence);
+    radar.config.motion_detection = params.detection.into();
+
+    // Command-line options take precedence over the settings file
+    let overrides = Settings {
+        presence: PresenceSettings {
+            range: params.range.clone(),
+            min_range: params.min_range,
+            max_range: params.max_range,
+            sensitivity: params.sensitivity,
+            frame_rate: params.frame_rate,
+            // Waking on the detection GPIO needs the module to drive it
+            gpio_output: (params.gpio_output || params.gpio_wakeup.is_some()).then_some(true),
+        },
+        ..Settings::default()
+    };
+    let effective = overrides.or(&cli.settings()?);
+    let presence = &effective.presence;
+    radar.config.detection_on_gpio = presence.gpio_output.unwrap_or(false);
+
+    // Configure presence parameters
+    configure_presence_parameters(
+        radar,
+        presence.range.as_ref(),
+        presence.min_range,
+        presence.max_range,
+        presence.sensitivity,
+        presence.frame_rate,
+        params.profile,
+    )
+    .await?;
+    if !cli.config.is_empty() {
+        radar.set_config_generation(Some(0));
+    }
+
+    // Debug registers if requested (global option)
+    if cli.logging.debug_registers {
+        debug_registers_if_connected(radar, &cli.output.format);
+    }
+
+    if params.baseline_capture || params.continuous {
+        if let Some(warning) = frame_rate_warning(params.interval, radar.config.frame_rate) {
+            warn!("⚠️  {warning}");
+        }
+    }
+
+    if params.baseline_capture {
+        run_baseline_capture(radar, cli, params.duration, params.interval).await?;
+    } else if params.continuous {
+        let options = MonitorOptions {
+            count: params.count,
+            interval: params.interval,
+            warmup: params.warmup,
+            clock_drift: params.clock_drift,
+            latch_ms: params.latch_ms,
+            gpio_wakeup: params.gpio_wakeup,
+            save_to: params.save_to.as_deref(),
+            zones: None,
+            zone_events_only: false,
+            window: params.window,
+            presence_events: params.events,
+            limits: params.limits,
+            shm: ctx.shm,
+            mqtt: ctx.mqtt,
+            reloader: (!cli.config.is_empty())
+                .then(|| Reloader::new(cli, overrides, effective.clone())),
+        };
+        params.realtime.apply()?;
+        monitor_presence_continuous(radar, cli, options, ctx.fifo_writer.as_deref_mut()).await?;
+        super::upload_export(cli, params.save_to.as_deref()).await?;
+    } else {
+        let result = radar.measure_presence().await?;
+        let issues = presence_issues(&result);
+        if !QualityGate::from_cli(cli)?.admit("presence", &issues, &result)? {
+            return Err(RadarError::MeasurementFailed(format!(
+                "rejected by quality gate: {}",
+                issues.join(", ")
+            )));
+        }
+        display_presence_result(&result, &cli.output.format);
+
+        if let Some(shm) = ctx.shm {
+            shm.publish_presence(&result);
+        }
+        if let Some(mqtt) = ctx.mqtt {
+            mqtt.publish_presence(&result);
+        }
+
+        // Single measurement FIFO output
+        if let Some(writer) = ctx.fifo_writer.as_deref_mut() {
+            write_presence_to_fifo(writer, &result, &cli.output.fifo_format);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Presence/distance hybrid sentry command
+
+use super::Context;
+use crate::cli::OutputFormat;
+use crate::device_lock;
+use crate::display::{
+    display_distance_result, display_mode_change, display_presence_result, display_sentry_csv_row,
+    write_distance_to_fifo, write_mode_change_to_fifo, write_presence_to_fifo, SENTRY_CSV_HEADER,
+};
+use crate::error::RadarError;
+use crate::pacing::Pacer;
+use crate::radar::{DetectorMode, XM125Config};
+use crate::sentry::{ModeChange, Sentry, SentryConfig, SentryMode};
+use crate::settings::{apply_distance, apply_presence, Settings};
+use chrono::Utc;
+use log::info;
+use std::time::{Duration, Instant};
+
+/// Parameters for the sentry command
+pub struct SentryParams<'a> {
+    pub presence_frame_rate: f32,
+    pub distance_range: Option<&'a str>,
+    pub interval: Duration,
+    pub config: SentryConfig,
+    pub duration: Option<Duration>,
+}
+
+impl From<SentryMode> for DetectorMode {
+    fn from(mode: SentryMode) -> Self {
+        match mode {
+            SentryMode::Presence => DetectorMode::Presence,
+            SentryMode::Distance => DetectorMode::Distance,
+        }
+    }
+}
+
+/// Load the firmware for `mode` and configure its detector from `base` and `settings`
+///
+/// Both detectors share the range fields of the configuration, so each switch starts from
+/// the configuration the sentry was started with.
+async fn enter(
+    ctx: &mut Context<'_>,
+    mode: SentryMode,
+    base: &XM125Config,
+    settings: &Settings,
+) -> Result<(), RadarError> {
+    let radar = &mut *ctx.radar;
+    radar.config = base.clone();
+    super::firmware::switch_firmware(ctx.cli, radar, mode.into()).await?;
+    radar.set_detector_mode(mode.into());
+    match mode {
+        SentryMode::Presence => apply_presence(radar, &settings.presence).await,
+        SentryMode::Distance => apply_distance(radar, &settings.distance).await,
+    }
+}
+
+/// Take one measurement with the running detector and emit it; true if it found a target
+async fn measure(ctx: &mut Context<'_>, mode: SentryMode) -> Result<bool, RadarError> {
+    let format = &ctx.cli.output.format;
+    let fifo_format = &ctx.cli.output.fifo_format;
+    match mode {
+        SentryMode::Presence => {
+            let result = ctx.radar.measure_presence().await?;
+            let target = result.presence_detected;
+            match format {
+                OutputFormat::Csv => {
+                    display_sentry_csv_row(
+                        result.timestamp,
+                        mode,
+                        target,
+                        result.presence_distance,
+                    );
+                }
+                _ => display_presence_result(&result, format),
+            }
+            if let Some(shm) = ctx.shm {
+                shm.publish_presence(&result);
+            }
+            if let Some(mqtt) = ctx.mqtt {
+                mqtt.publish_presence(&result);
+            }
+            if let Some(writer) = ctx.fifo_writer.as_deref_mut() {
+                write_presence_to_fifo(writer, &result, fifo_format);
+            }
+            Ok(target)
+        }
+        SentryMode::Distance => {
+            let result = ctx.radar.measure_distance().await?;
+            let target = !result.measure_error && result.distance > 0.0;
+            match format {
+                OutputFormat::Csv => {
+                    display_sentry_csv_row(result.timestamp, mode, target, result.distance);
+                }
+                _ => display_distance_result(&result, format),
+            }
+            if let Some(shm) = ctx.shm {
+                shm.publish_distance(&result);
+            }
+            if let Some(mqtt) = ctx.mqtt {
+                mqtt.publish_distance(&result);
+            }
+            if let Some(writer) = ctx.fifo_writer.as_deref_mut() {
+                write_distance_to_fifo(writer, &result, fifo_format);
+            }
+            Ok(target)
+        }
+    }
+}
+
+fn emit_mode_change(ctx: &Context<'_>, change: &ModeChange) {
+    display_mode_change(change, &ctx.cli.output.format);
+    if let Some(mqtt) = ctx.mqtt {
+        mqtt.publish_mode_change(change);
+    }
+    if let Some(writer) = ctx.fifo_writer.as_deref() {
+        write_mode_change_to_fifo(writer, change, &ctx.cli.output.fifo_format);
+    }
+}
+
+/// Handle the sentry command
+pub async fn handle_sentry_command(
+    ctx: &mut Context<'_>,
+    params: SentryParams<'_>,
+) -> Result<(), RadarError> {
+    if !params.presence_frame_rate.is_finite() || params.presence_frame_rate <= 0.0 {
+        return Err(RadarError::InvalidParameters(format!(
+            "--presence-frame-rate must be above 0 Hz (got {})",
+            params.presence_frame_rate
+        )));
+    }
+    device_lock::catch_terminate();
+
+    let mut settings = ctx.cli.settings()?;
+    settings.presence.frame_rate = Some(params.presence_frame_rate);
+    if let Some(range) = params.distance_range {
+        settings.distance.range = Some(range.to_string());
+    }
+    let poll_interval = |mode| match mode {
+        SentryMode::Presence => Duration::from_secs_f32(1.0 / params.presence_frame_rate),
+        SentryMode::Distance => params.interval,
+    };
+
+    let base = ctx.radar.config.clone();
+    enter(ctx, SentryMode::Presence, &base, &settings).await?;
+    if matches!(ctx.cli.output.format, OutputFormat::Csv) {
+        println!("{SENTRY_CSV_HEADER}");
+    }
+    info!(
+        "🛡️  Sentry watching for an approach at {:.1} Hz",
+        params.presence_frame_rate
+    );
+
+    let started = Instant::now();
+    let mut sentry = Sentry::new(params.config, started);
+    let mut pacer = Pacer::new(poll_interval(SentryMode::Presence));
+    let mut switches = 0u32;
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+    loop {
+        let mode = sentry.mode();
+        let target = measure(ctx, mode).await?;
+
+        let now = Instant::now();
+        if let Some(next) = sentry.update(target, now) {
+            let dwell = sentry.dwell(now);
+            enter(ctx, next, &base, &settings).await?;
+            let switched = Instant::now();
+            sentry.switched(next, switched);
+            switches += 1;
+            emit_mode_change(
+                ctx,
+                &ModeChange {
+                    timestamp: Utc::now(),
+                    from: mode,
+                    to: next,
+                    dwell_s: dwell.as_secs_f64(),
+                    switch_ms: u64::try_from(switched.duration_since(now).as_millis())
+                        .unwrap_or(u64::MAX),
+                },
+            );
+            pacer = Pacer::new(poll_interval(next));
+        }
+
+        if device_lock::stop_requested()
+            || params
+                .duration
+                .is_some_and(|duration| started.elapsed() >= duration)
+        {
+            break;
+        }
+        tokio::select! {
+            () = pacer.wait() => {}
+            _ = &mut interrupted => break,
+        }
+    }
+
+    info!(
+        "🛡️  Sentry stopped in {} mode after {switches} mode changes",
+        sentry.mode().name()
+    );
+    Ok(())
+}
@@ -0,0 +1,235 @@
+//! Interactive shell and completion script generation
+
+use super::{execute_command, execute_local_command, Context};
+use crate::app::{parse_args, ArgsError};
+use crate::cli::{Cli, Commands};
+use crate::error::RadarError;
+use clap::{CommandFactory, Parser};
+use log::warn;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::PathBuf;
+
+const PROMPT: &str = "xm125> ";
+
+/// Print a completion script for `shell` to stdout
+pub fn handle_completions_command(shell: clap_complete::Shell) -> Result<(), RadarError> {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    // Render into a buffer: clap_complete panics if stdout is closed early (e.g. `| head`)
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    std::io::stdout().write_all(&script)?;
+    Ok(())
+}
+
+/// Split a command line into arguments, honouring single/double quotes and backslashes
+fn split_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (Some('\''), c) => current.push(c),
+            (_, '\\') => {
+                current.push(chars.next().ok_or("trailing backslash")?);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (_, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Global options the shell was started with (everything before the `shell` subcommand)
+///
+/// `args` is the expanded command line; the subcommand is the first `shell` word that
+/// parses as one, not an option value that happens to read `shell`.
+fn session_args(args: &[OsString]) -> Vec<OsString> {
+    let end = (1..args.len())
+        .find(|&index| {
+            args[index] == "shell"
+                && Cli::try_parse_from(&args[..=index])
+                    .is_ok_and(|cli| matches!(cli.command, Some(Commands::Shell { .. })))
+        })
+        .unwrap_or(args.len());
+    args[..end].to_vec()
+}
+
+fn default_history_file() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".xm125-radar-monitor_history"))
+}
+
+/// Run one shell line; `Ok(false)` ends the session
+async fn run_line(
+    ctx: &mut Context<'_>,
+    base_args: &[OsString],
+    line: &str,
+) -> Result<bool, RadarError> {
+    let words = split_line(line).map_err(RadarError::InvalidParameters)?;
+    match words.first().map(String::as_str) {
+        None => return Ok(true),
+        Some("exit" | "quit") => return Ok(false),
+        Some("help") => {
+            Cli::command().print_help()?;
+            return Ok(true);
+        }
+        Some(_) => {}
+    }
+
+    let args = base_args
+        .iter()
+        .cloned()
+        .chain(words.into_iter().map(OsString::from))
+        .collect();
+    let cli = match parse_args(args) {
+        Ok(cli) => cli,
+        Err(ArgsError::Usage(e)) => {
+            // Help and usage errors are rendered by clap; keep the session alive
+            let _ = e.print();
+            return Ok(true);
+        }
+        Err(ArgsError::Settings(e)) => return Err(e),
+    };
+
+    match cli.command {
+        None => {
+            println!("Enter a subcommand, `help` or `exit`");
+            return Ok(true);
+        }
+        Some(Commands::Shell { .. }) => {
+            println!("Already in the shell");
+            return Ok(true);
+        }
+        Some(_) => {}
+    }
+
+    if let Some(result) = execute_local_command(&cli).await {
+        return result.map(|()| true);
+    }
+    let mut line_ctx = Context::new(&cli, ctx.radar, ctx.fifo_writer.as_deref_mut())
+        .with_shm(ctx.shm)
+        .with_mqtt(ctx.mqtt);
+    Box::pin(execute_command(&mut line_ctx)).await?;
+    Ok(true)
+}
+
+/// Read-eval loop over the connected radar until `exit` or end of input
+pub async fn run_shell(
+    ctx: &mut Context<'_>,
+    history_file: Option<&str>,
+) -> Result<(), RadarError> {
+    let mut editor = DefaultEditor::new().map_err(|e| RadarError::DeviceError {
+        message: format!("Failed to start interactive shell: {e}"),
+    })?;
+    let history = history_file
+        .map(PathBuf::from)
+        .or_else(default_history_file);
+    if let Some(path) = &history {
+        // A missing history file just means a first session
+        let _ = editor.load_history(path);
+    }
+
+    let base_args = session_args(&ctx.cli.effective_args);
+    println!("Interactive shell - enter subcommands, `help` for usage, `exit` to leave");
+
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                return Err(RadarError::DeviceError {
+                    message: format!("Failed to read input: {e}"),
+                })
+            }
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.as_str());
+        }
+
+        match run_line(ctx, &base_args, &line).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("❌ {e}"),
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Err(e) = editor.save_history(path) {
+            warn!("Failed to save shell history to {}: {e}", path.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(
+            split_line(r#"presence --save-to "my file.csv" --range long"#).unwrap(),
+            ["presence", "--save-to", "my file.csv", "--range", "long"]
+        );
+        assert_eq!(
+            split_line(r"gpio set 'a b' c\ d").unwrap(),
+            ["gpio", "set", "a b", "c d"]
+        );
+        assert_eq!(split_line("  ").unwrap(), Vec::<String>::new());
+        assert!(split_line("info 'open").is_err());
+    }
+
+    #[test]
+    fn test_shell_lines_expand_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("c.toml");
+        std::fs::write(
+            &config,
+            "[aliases]\nroom = [\"presence\", \"--range\", \"long\"]\n",
+        )
+        .unwrap();
+        let config = config.to_str().unwrap();
+
+        let started = parse_args(
+            ["xm125", "--config", config, "--log-file", "shell", "shell"]
+                .map(OsString::from)
+                .to_vec(),
+        )
+        .ok()
+        .unwrap();
+        let base = session_args(&started.effective_args);
+        assert_eq!(base, ["xm125", "--config", config, "--log-file", "shell"]);
+
+        let line = base.into_iter().chain([OsString::from("room")]).collect();
+        let cli = parse_args(line).ok().unwrap();
+        assert!(matches!(cli.command, Some(Commands::Presence { .. })));
+    }
+}
@@ -0,0 +1,68 @@
+//! Cumulative device statistics command
+
+use crate::cli::{Cli, OutputFormat};
+use crate::error::RadarError;
+use crate::stats::{self, device_key, DeviceStats};
+use std::collections::BTreeMap;
+
+fn print_human(device: &str, stats: &DeviceStats) {
+    let counters = &stats.counters;
+    println!("📊 {device}");
+    println!("   Measurements: {}", counters.measurements);
+    println!("   Detections:   {}", counters.detections);
+    println!("   Errors:       {}", counters.errors);
+    println!("   Resets:       {}", counters.resets);
+    println!("   Flashes:      {}", counters.flashes);
+    println!(
+        "   Recorded:     {} to {}",
+        stats.first_seen.format("%Y-%m-%d %H:%M:%S UTC"),
+        stats.last_updated.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+}
+
+/// Handle `stats`: this module's lifetime counts, or every module's with `--all`
+pub fn handle_stats_command(cli: &Cli, all: bool) -> Result<(), RadarError> {
+    let mut devices = stats::load(cli.stats_file_path())?;
+    if !all {
+        let key = device_key(&cli.get_i2c_device_path(), cli.i2c_address);
+        devices = devices
+            .remove_entry(&key)
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        if devices.is_empty() && matches!(cli.output.format, OutputFormat::Human) {
+            println!(
+                "📊 No statistics recorded for {key} in {}",
+                cli.stats_file_path()
+            );
+            return Ok(());
+        }
+    }
+
+    match cli.output.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&devices)?),
+        OutputFormat::Csv => {
+            println!(
+                "device,measurements,detections,errors,resets,flashes,first_seen,last_updated"
+            );
+            for (device, stats) in &devices {
+                let counters = &stats.counters;
+                println!(
+                    "{device},{},{},{},{},{},{},{}",
+                    counters.measurements,
+                    counters.detections,
+                    counters.errors,
+                    counters.resets,
+                    counters.flashes,
+                    stats.first_seen.to_rfc3339(),
+                    stats.last_updated.to_rfc3339()
+                );
+            }
+        }
+        OutputFormat::Human => {
+            for (device, stats) in &devices {
+                print_human(device, stats);
+            }
+        }
+    }
+    Ok(())
+}
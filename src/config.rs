@@ -2,7 +2,8 @@
 //!
 //! This module handles device configuration, range setup, and debugging utilities.
 
-use crate::cli::{PresenceRange, ProfileMode};
+use crate::cli::{OutputFormat, PresenceRange, ProfileMode};
+use crate::display::display_register_dump;
 use crate::error::RadarError;
 use crate::radar::{presence::PresenceRange as RadarPresenceRange, XM125Radar};
 use log::{info, warn};
@@ -40,7 +41,7 @@ pub fn configure_distance_range(radar: &mut XM125Radar, range_str: &str) -> Resu
 
 /// Configure presence parameters for the radar
 #[allow(unused_assignments)]
-pub fn configure_presence_parameters(
+pub async fn configure_presence_parameters(
     radar: &mut XM125Radar,
     presence_range: Option<&PresenceRange>,
     min_range: Option<f32>,
@@ -116,7 +117,7 @@ pub fn configure_presence_parameters(
     config_changed = true;
 
     if config_changed || (presence_range.is_none() && min_range.is_none() && max_range.is_none()) {
-        radar.configure_presence_range()?;
+        radar.configure_presence_range().await?;
         if config_changed {
             info!("✅ Presence parameters configured successfully");
         } else {
@@ -126,13 +127,16 @@ pub fn configure_presence_parameters(
     Ok(())
 }
 
-/// Debug registers if radar is connected, with automatic connection attempt
-pub fn debug_registers_if_connected(radar: &mut XM125Radar, mode: &str) {
+/// Debug registers if radar is connected (register table follows the detected firmware)
+pub fn debug_registers_if_connected(radar: &mut XM125Radar, format: &OutputFormat) {
     if radar.is_connected() {
-        match radar.debug_registers(mode) {
-            Ok(()) => info!("✅ Register debugging completed successfully"),
+        match radar.register_dump() {
+            Ok(dump) => {
+                display_register_dump(&dump, format);
+                info!("✅ Register debugging completed successfully");
+            }
             Err(e) => {
-                eprintln!("❌ Failed to debug registers: {e}");
+                warn!("❌ Failed to debug registers: {e}");
                 warn!("Register debugging failed, but continuing with measurement");
             }
         }
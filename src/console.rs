@@ -0,0 +1,61 @@
+//! Buffered measurement output on stdout
+//!
+//! Standard output is line buffered, so at high frame rates every record (and every line
+//! of pretty JSON) costs a write to the terminal or pipe. With `--flush interval` or
+//! `--flush end`, console output collects in a buffer that is written out once the flush
+//! interval has passed, when it fills, and when the run ends, always on record boundaries.
+
+use std::io::{self, BufWriter, Stdout, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Buffer size for batched stdout writes
+const BUFFER_SIZE: usize = 64 * 1024;
+
+struct Buffered {
+    writer: BufWriter<Stdout>,
+    /// Minimum time between flushes (None = only on [`flush`])
+    interval: Option<Duration>,
+    last_flush: Instant,
+}
+
+static BUFFER: Mutex<Option<Buffered>> = Mutex::new(None);
+
+/// Buffer stdout, flushing at most every `interval` (None = only at the end)
+///
+/// An interval of zero keeps the default line-buffered behaviour.
+pub fn set_flush_interval(interval: Option<Duration>) {
+    if interval == Some(Duration::ZERO) {
+        return;
+    }
+    *BUFFER.lock().unwrap_or_else(|e| e.into_inner()) = Some(Buffered {
+        writer: BufWriter::with_capacity(BUFFER_SIZE, io::stdout()),
+        interval,
+        last_flush: Instant::now(),
+    });
+}
+
+/// Print one line of output
+pub fn print_line(line: &str) {
+    let mut buffer = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(buffered) = buffer.as_mut() else {
+        std::println!("{line}");
+        return;
+    };
+    let _ = writeln!(buffered.writer, "{line}");
+    if buffered
+        .interval
+        .is_some_and(|interval| buffered.last_flush.elapsed() >= interval)
+    {
+        let _ = buffered.writer.flush();
+        buffered.last_flush = Instant::now();
+    }
+}
+
+/// Write out anything still buffered
+pub fn flush() {
+    if let Some(buffered) = BUFFER.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        let _ = buffered.writer.flush();
+        buffered.last_flush = Instant::now();
+    }
+}
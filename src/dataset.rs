@@ -0,0 +1,255 @@
+//! Labeled presence captures for training classification models
+//!
+//! A capture records presence measurements while the ground truth is known (someone in
+//! the detection zone, or the zone verified empty) and stores them in a dataset tree:
+//!
+//! ```text
+//! <dataset>/<label>/<YYYYmmddTHHMMSSZ>[-<scenario>]/measurements.csv
+//!                                                  /metadata.json
+//! ```
+//!
+//! The metadata carries the label, scenario, timing and the full detector configuration
+//! so captures from different sites and firmware builds can be pooled and filtered.
+
+use crate::error::RadarError;
+use crate::journal::{write_atomic, JournaledFile};
+use crate::radar::{PresenceMeasurement, XM125Config};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Measurement file within a capture directory
+pub const MEASUREMENTS_FILE: &str = "measurements.csv";
+/// Metadata file within a capture directory
+pub const METADATA_FILE: &str = "metadata.json";
+
+const CSV_HEADER: &str =
+    "timestamp,label,presence_detected,presence_distance_m,intra_score,inter_score";
+
+/// Ground-truth label for a capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureLabel {
+    /// Someone is present in the detection zone for the whole capture
+    Occupied,
+    /// The detection zone is verified empty for the whole capture
+    Empty,
+}
+
+impl CaptureLabel {
+    /// Detector output that agrees with this label
+    fn expects_presence(self) -> bool {
+        self == CaptureLabel::Occupied
+    }
+}
+
+impl fmt::Display for CaptureLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CaptureLabel::Occupied => "occupied",
+            CaptureLabel::Empty => "empty",
+        })
+    }
+}
+
+/// Capture description written to `metadata.json`
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureMetadata {
+    pub label: CaptureLabel,
+    pub scenario: Option<String>,
+    pub notes: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Requested capture duration in seconds
+    pub duration_s: u64,
+    pub interval_ms: f64,
+    pub samples: u32,
+    /// Samples where the detector output matched the label
+    pub detector_agreement: u32,
+    /// RSS version reported by the module, when known
+    pub rss_version: Option<String>,
+    pub tool_version: String,
+    /// Detector configuration in effect during the capture
+    pub detector_config: XM125Config,
+}
+
+/// Options describing a capture
+pub struct CaptureOptions<'a> {
+    pub dataset_dir: &'a str,
+    pub label: CaptureLabel,
+    pub scenario: Option<&'a str>,
+    pub notes: Option<&'a str>,
+    pub duration_s: u64,
+    pub interval_ms: f64,
+    /// Fsync interval for the measurement file
+    pub sync_interval: Duration,
+}
+
+/// Keep scenario names usable as directory names
+fn sanitize_scenario(scenario: &str) -> String {
+    scenario
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Directory for a capture started at `started_at`
+pub fn capture_dir(
+    dataset_dir: &Path,
+    label: CaptureLabel,
+    scenario: Option<&str>,
+    started_at: DateTime<Utc>,
+) -> PathBuf {
+    let mut name = started_at.format("%Y%m%dT%H%M%SZ").to_string();
+    if let Some(scenario) = scenario.filter(|s| !s.is_empty()) {
+        name.push('-');
+        name.push_str(&sanitize_scenario(scenario));
+    }
+    dataset_dir.join(label.to_string()).join(name)
+}
+
+/// An in-progress labeled capture
+pub struct LabeledCapture {
+    dir: PathBuf,
+    writer: JournaledFile,
+    metadata: CaptureMetadata,
+}
+
+impl LabeledCapture {
+    /// Create the capture directory and measurement file
+    pub fn create(
+        options: &CaptureOptions<'_>,
+        detector_config: XM125Config,
+        rss_version: Option<String>,
+    ) -> Result<Self, RadarError> {
+        let started_at = Utc::now();
+        let dir = capture_dir(
+            Path::new(options.dataset_dir),
+            options.label,
+            options.scenario,
+            started_at,
+        );
+        std::fs::create_dir_all(&dir)?;
+        let mut writer = JournaledFile::create(dir.join(MEASUREMENTS_FILE), options.sync_interval)?;
+        writeln!(writer, "{CSV_HEADER}")?;
+        writer.flush()?;
+
+        Ok(Self {
+            dir,
+            writer,
+            metadata: CaptureMetadata {
+                label: options.label,
+                scenario: options.scenario.map(str::to_string),
+                notes: options.notes.map(str::to_string),
+                started_at,
+                finished_at: None,
+                duration_s: options.duration_s,
+                interval_ms: options.interval_ms,
+                samples: 0,
+                detector_agreement: 0,
+                rss_version,
+                tool_version: env!("CARGO_PKG_VERSION").to_string(),
+                detector_config,
+            },
+        })
+    }
+
+    /// Append a labeled measurement
+    pub fn record(&mut self, result: &PresenceMeasurement) -> Result<(), RadarError> {
+        writeln!(
+            self.writer,
+            "{},{},{},{:.3},{:.2},{:.2}",
+            result.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+            self.metadata.label,
+            result.presence_detected,
+            result.presence_distance,
+            result.intra_presence_score,
+            result.inter_presence_score
+        )?;
+        self.writer.flush()?;
+
+        self.metadata.samples += 1;
+        if result.presence_detected == self.metadata.label.expects_presence() {
+            self.metadata.detector_agreement += 1;
+        }
+        Ok(())
+    }
+
+    /// Samples recorded so far
+    pub fn samples(&self) -> u32 {
+        self.metadata.samples
+    }
+
+    /// Close the measurement file and write the metadata
+    pub fn finish(mut self) -> Result<(PathBuf, CaptureMetadata), RadarError> {
+        self.writer.sync()?;
+        self.metadata.finished_at = Some(Utc::now());
+        write_atomic(
+            self.dir.join(METADATA_FILE),
+            serde_json::to_string_pretty(&self.metadata)?.as_bytes(),
+        )?;
+        Ok((self.dir, self.metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_capture_layout_and_agreement() {
+        let started_at = Utc.with_ymd_and_hms(2025, 3, 1, 12, 30, 5).unwrap();
+        assert_eq!(
+            capture_dir(
+                Path::new("data"),
+                CaptureLabel::Occupied,
+                Some("desk/seated 1m"),
+                started_at
+            ),
+            Path::new("data/occupied/20250301T123005Z-desk_seated_1m")
+        );
+
+        let root = tempfile::tempdir().unwrap();
+        let options = CaptureOptions {
+            dataset_dir: root.path().to_str().unwrap(),
+            label: CaptureLabel::Empty,
+            scenario: None,
+            notes: None,
+            duration_s: 1,
+            interval_ms: 100.0,
+            sync_interval: Duration::ZERO,
+        };
+        let mut capture = LabeledCapture::create(&options, XM125Config::default(), None).unwrap();
+        for detected in [false, true, false] {
+            capture
+                .record(&PresenceMeasurement {
+                    presence_detected: detected,
+                    presence_distance: 1.0,
+                    intra_presence_score: 0.5,
+                    inter_presence_score: 0.5,
+                    timestamp: Utc::now(),
+                    config_generation: None,
+                    raw: None,
+                })
+                .unwrap();
+        }
+        let (dir, metadata) = capture.finish().unwrap();
+        assert_eq!((metadata.samples, metadata.detector_agreement), (3, 2));
+
+        let csv = std::fs::read_to_string(dir.join(MEASUREMENTS_FILE)).unwrap();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(2).unwrap().contains(",empty,true,"));
+        assert!(dir.join(METADATA_FILE).exists());
+    }
+}
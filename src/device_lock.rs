@@ -0,0 +1,160 @@
+//! Per-device lock against overlapping sessions
+//!
+//! Two processes driving the same module interleave register writes and read each other's
+//! results. Every command that talks to the radar first takes an exclusive `flock` on a lock
+//! file named after the I2C bus and address; a second instance is refused with the PID of the
+//! holder. With `--takeover` the holder is sent SIGTERM instead: continuous monitoring stops
+//! after the current measurement, finishing its CSV export and session sidecar, and the lock
+//! passes to the new instance.
+
+use crate::error::RadarError;
+use log::{debug, info};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// How long a taken-over instance gets to finish its session
+const TAKEOVER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Set by SIGTERM once [`catch_terminate`] is installed
+static TERMINATE: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_terminate(_signal: libc::c_int) {
+    TERMINATE.store(true, Ordering::SeqCst);
+}
+
+/// Turn SIGTERM into a stop request for long-running loops (see [`stop_requested`])
+pub fn catch_terminate() {
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        libc::signal(
+            libc::SIGTERM,
+            on_terminate as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// True once SIGTERM asked a continuous session to finish
+pub fn stop_requested() -> bool {
+    TERMINATE.load(Ordering::SeqCst)
+}
+
+/// Exclusive claim on one module, released when dropped
+#[derive(Debug)]
+pub struct DeviceLock {
+    path: PathBuf,
+    _file: File,
+}
+
+/// Lock file for the module at `i2c_device`/`i2c_address`
+fn lock_path(dir: &Path, i2c_device: &str, i2c_address: u16) -> PathBuf {
+    let device = i2c_device.trim_start_matches("/dev/").replace('/', "-");
+    dir.join(format!("{device}-0x{i2c_address:02x}.lock"))
+}
+
+fn try_lock(file: &File) -> Result<bool, RadarError> {
+    // SAFETY: flock on a file descriptor we own
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let err = std::io::Error::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        Ok(false)
+    } else {
+        Err(err.into())
+    }
+}
+
+/// PID recorded by the current holder, if readable
+fn holder_pid(file: &mut File) -> Option<i32> {
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+impl DeviceLock {
+    /// Claim the module, or take it over from the running instance with `takeover`
+    pub fn acquire(
+        dir: impl AsRef<Path>,
+        i2c_device: &str,
+        i2c_address: u16,
+        takeover: bool,
+    ) -> Result<Self, RadarError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let path = lock_path(dir, i2c_device, i2c_address);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        if !try_lock(&file)? {
+            let pid = holder_pid(&mut file);
+            let busy = || RadarError::DeviceBusy {
+                device: format!("{i2c_device} @ 0x{i2c_address:02X}"),
+                pid,
+            };
+            let Some(pid) = pid.filter(|_| takeover) else {
+                return Err(busy());
+            };
+
+            info!("🔁 Taking over {i2c_device} @ 0x{i2c_address:02X} from PID {pid}");
+            // SAFETY: kill(2) has no memory-safety preconditions
+            if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+                debug!(
+                    "Failed to signal PID {pid}: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            let deadline = Instant::now() + TAKEOVER_TIMEOUT;
+            while !try_lock(&file)? {
+                if Instant::now() >= deadline {
+                    return Err(busy());
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        writeln!(file, "{}", std::process::id())?;
+        debug!("Device lock {} acquired", path.display());
+        Ok(Self { path, _file: file })
+    }
+}
+
+impl Drop for DeviceLock {
+    fn drop(&mut self) {
+        debug!("Device lock {} released", self.path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_instance_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = DeviceLock::acquire(dir.path(), "/dev/i2c-2", 0x52, false).unwrap();
+        assert_eq!(lock.path, dir.path().join("i2c-2-0x52.lock"));
+
+        match DeviceLock::acquire(dir.path(), "/dev/i2c-2", 0x52, false) {
+            Err(RadarError::DeviceBusy { pid, .. }) => {
+                assert_eq!(pid, i32::try_from(std::process::id()).ok());
+            }
+            other => panic!("expected DeviceBusy, got {other:?}"),
+        }
+        // Other modules are independent
+        DeviceLock::acquire(dir.path(), "/dev/i2c-2", 0x53, false).unwrap();
+
+        drop(lock);
+        DeviceLock::acquire(dir.path(), "/dev/i2c-2", 0x52, false).unwrap();
+    }
+}
@@ -0,0 +1,572 @@
+//! Automated connectivity triage
+//!
+//! `diagnose` walks the checklist support uses for a module that does not answer: I2C
+//! device node and permissions, the device lock, which of the run and bootloader addresses
+//! ACK, how the control GPIOs are exported and driven, the firmware identity and status,
+//! and, if the module is silent, whether a reset brings it back. The observations are
+//! turned into a ranked list of probable causes, each with the commands to try next.
+
+use crate::cli::Cli;
+use crate::device_lock::DeviceLock;
+use crate::error::RadarError;
+use crate::firmware::BOOTLOADER_I2C_ADDRESS;
+use crate::gpio::{GpioValue, XM125GpioController};
+use crate::i2c::{probe_address, I2cDevice};
+use crate::radar::protocol::protocol_for_app_id;
+use crate::radar::{REG_APPLICATION_ID, REG_DETECTOR_STATUS};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How long the module gets to answer on I2C after a diagnostic reset
+const RESET_RECOVERY: Duration = Duration::from_secs(3);
+
+/// Result of one triage step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Pass,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+impl Outcome {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Outcome::Pass => "✅",
+            Outcome::Warn => "⚠️ ",
+            Outcome::Fail => "❌",
+            Outcome::Skipped => "⏭️ ",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub detail: String,
+}
+
+/// A probable cause with the commands to try next, most likely first
+#[derive(Debug, Clone, Serialize)]
+pub struct Cause {
+    /// Relative likelihood, 0-100
+    pub likelihood: u8,
+    pub summary: String,
+    pub next_steps: Vec<String>,
+}
+
+/// Access to the I2C device node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeAccess {
+    Ok,
+    Missing,
+    PermissionDenied,
+    Other(String),
+}
+
+/// sysfs state of one control GPIO
+#[derive(Debug, Clone)]
+pub struct PinState {
+    pub line: &'static str,
+    pub gpio: u32,
+    pub exported: bool,
+    pub output: bool,
+    pub value: Option<GpioValue>,
+}
+
+/// Everything observed, from which the causes are ranked
+#[derive(Debug, Clone, Default)]
+pub struct Findings {
+    pub device: String,
+    pub address: u16,
+    pub node: Option<NodeAccess>,
+    /// PID holding the device lock, when another instance owns the module
+    pub busy: Option<Option<i32>>,
+    pub run_ack: Option<bool>,
+    pub bootloader_ack: Option<bool>,
+    pub pins: Vec<PinState>,
+    /// Level that holds the module in reset (`--gpio-reset-active`)
+    pub reset_active: GpioValue,
+    /// Application ID, or why it could not be read
+    pub app_id: Option<Result<u32, String>>,
+    pub status_errors: Vec<&'static str>,
+    /// Whether the module answered after a diagnostic reset, or why the reset failed
+    pub reset: Option<Result<bool, String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub checks: Vec<Check>,
+    pub causes: Vec<Cause>,
+}
+
+fn check(checks: &mut Vec<Check>, name: &'static str, outcome: Outcome, detail: String) {
+    checks.push(Check {
+        name,
+        outcome,
+        detail,
+    });
+}
+
+fn node_access(device: &str) -> NodeAccess {
+    match OpenOptions::new().read(true).write(true).open(device) {
+        Ok(_) => NodeAccess::Ok,
+        Err(e) if e.kind() == ErrorKind::NotFound => NodeAccess::Missing,
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => NodeAccess::PermissionDenied,
+        Err(e) => NodeAccess::Other(e.to_string()),
+    }
+}
+
+fn pin_state(line: &'static str, gpio: u32) -> PinState {
+    let base = format!("/sys/class/gpio/gpio{gpio}");
+    let read = |file: &str| fs::read_to_string(format!("{base}/{file}")).ok();
+    PinState {
+        line,
+        gpio,
+        exported: Path::new(&base).exists(),
+        output: read("direction").is_some_and(|direction| direction.trim() == "out"),
+        value: read("value").and_then(|value| match value.trim() {
+            "0" => Some(GpioValue::Low),
+            "1" => Some(GpioValue::High),
+            _ => None,
+        }),
+    }
+}
+
+fn read_word(i2c: &mut I2cDevice, register: u16) -> Result<u32, RadarError> {
+    let bytes = i2c.read_register(register, 4)?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Reset the module into run mode and wait for it to ACK again
+fn reset_and_probe(cli: &Cli, device: &str) -> Result<bool, RadarError> {
+    let mut gpio = XM125GpioController::with_pins(cli.get_gpio_pins());
+    gpio.attach()?;
+    gpio.reset_to_run_mode()?;
+    let started = Instant::now();
+    while started.elapsed() < RESET_RECOVERY {
+        if probe_address(device, cli.i2c_address)? {
+            return Ok(true);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Ok(false)
+}
+
+/// Run the triage; `allow_reset` permits resetting a module that does not answer
+pub fn run(cli: &Cli, allow_reset: bool) -> Report {
+    let mut checks = Vec::new();
+    let device = cli.get_i2c_device_path();
+    let address = cli.i2c_address;
+    let mut findings = Findings {
+        device: device.clone(),
+        address,
+        reset_active: cli.get_gpio_pins().signals.reset_active,
+        ..Findings::default()
+    };
+
+    let node = node_access(&device);
+    let (outcome, detail) = match &node {
+        NodeAccess::Ok => (Outcome::Pass, format!("{device} opened read/write")),
+        NodeAccess::Missing => (Outcome::Fail, format!("{device} does not exist")),
+        NodeAccess::PermissionDenied => (Outcome::Fail, format!("{device}: permission denied")),
+        NodeAccess::Other(e) => (Outcome::Fail, format!("{device}: {e}")),
+    };
+    check(&mut checks, "i2c_device", outcome, detail);
+    let node_ok = node == NodeAccess::Ok;
+    findings.node = Some(node);
+
+    // Held for the rest of the triage so nothing else talks to the module meanwhile
+    let _lock = match DeviceLock::acquire(cli.lock_dir_path(), &device, address, false) {
+        Ok(lock) => {
+            check(
+                &mut checks,
+                "device_lock",
+                Outcome::Pass,
+                "no other instance is using the module".to_string(),
+            );
+            Some(lock)
+        }
+        Err(RadarError::DeviceBusy { pid, .. }) => {
+            let holder =
+                pid.map_or_else(|| "another process".to_string(), |pid| format!("PID {pid}"));
+            check(
+                &mut checks,
+                "device_lock",
+                Outcome::Warn,
+                format!("module in use by {holder}; register reads and reset skipped"),
+            );
+            findings.busy = Some(pid);
+            None
+        }
+        Err(e) => {
+            check(&mut checks, "device_lock", Outcome::Warn, e.to_string());
+            None
+        }
+    };
+    let exclusive = findings.busy.is_none();
+
+    if node_ok {
+        for (name, target, slot) in [
+            ("run_address", address, &mut findings.run_ack),
+            (
+                "bootloader_address",
+                BOOTLOADER_I2C_ADDRESS,
+                &mut findings.bootloader_ack,
+            ),
+        ] {
+            match probe_address(&device, target) {
+                Ok(acked) => {
+                    // Silence at the bootloader address is the normal case
+                    let outcome = if acked || name == "bootloader_address" {
+                        Outcome::Pass
+                    } else {
+                        Outcome::Fail
+                    };
+                    let answer = if acked { "ACK" } else { "no response" };
+                    check(
+                        &mut checks,
+                        name,
+                        outcome,
+                        format!("0x{target:02X}: {answer}"),
+                    );
+                    *slot = Some(acked);
+                }
+                Err(e) => check(&mut checks, name, Outcome::Fail, e.to_string()),
+            }
+        }
+    } else {
+        check(
+            &mut checks,
+            "run_address",
+            Outcome::Skipped,
+            "I2C device unavailable".to_string(),
+        );
+    }
+
+    let pins = cli.get_gpio_pins();
+    for (line, gpio) in [
+        ("reset", pins.reset),
+        ("mcu_interrupt", pins.mcu_interrupt),
+        ("wake_up", pins.wake_up),
+        ("boot", pins.boot),
+    ] {
+        let state = pin_state(line, gpio);
+        let (outcome, detail) = if !state.exported {
+            (Outcome::Warn, format!("{line} (GPIO{gpio}) not exported"))
+        } else {
+            let level = state
+                .value
+                .map_or("?".to_string(), |value| value.to_string());
+            let direction = if state.output { "out" } else { "in" };
+            let wrong_level = match line {
+                "reset" => state.value == Some(pins.signals.reset_active),
+                "wake_up" => state.value == Some(GpioValue::Low),
+                "boot" => state.value == Some(GpioValue::High),
+                _ => false,
+            };
+            (
+                if wrong_level {
+                    Outcome::Fail
+                } else {
+                    Outcome::Pass
+                },
+                format!("{line} (GPIO{gpio}) {direction}={level}"),
+            )
+        };
+        check(&mut checks, "gpio", outcome, detail);
+        findings.pins.push(state);
+    }
+
+    if findings.run_ack == Some(true) && exclusive {
+        let identity = I2cDevice::new(&device, address).and_then(|mut i2c| {
+            let app_id = read_word(&mut i2c, REG_APPLICATION_ID)?;
+            let status = read_word(&mut i2c, REG_DETECTOR_STATUS)?;
+            Ok((app_id, status))
+        });
+        match identity {
+            Ok((app_id, status)) => match protocol_for_app_id(app_id) {
+                Some(protocol) => {
+                    check(
+                        &mut checks,
+                        "firmware",
+                        Outcome::Pass,
+                        format!("{} firmware (application ID {app_id})", protocol.name()),
+                    );
+                    findings.status_errors = protocol
+                        .decode_status(status)
+                        .iter()
+                        .filter(|flag| flag.is_error())
+                        .map(|flag| flag.name)
+                        .collect();
+                    let (outcome, detail) = if findings.status_errors.is_empty() {
+                        (Outcome::Pass, format!("0x{status:08X}, no error flags"))
+                    } else {
+                        (
+                            Outcome::Fail,
+                            format!("0x{status:08X}: {}", findings.status_errors.join(", ")),
+                        )
+                    };
+                    check(&mut checks, "status", outcome, detail);
+                    findings.app_id = Some(Ok(app_id));
+                }
+                None => {
+                    check(
+                        &mut checks,
+                        "firmware",
+                        Outcome::Fail,
+                        format!("unknown application ID {app_id}"),
+                    );
+                    findings.app_id = Some(Ok(app_id));
+                }
+            },
+            Err(e) => {
+                check(&mut checks, "firmware", Outcome::Fail, e.to_string());
+                findings.app_id = Some(Err(e.to_string()));
+            }
+        }
+    }
+
+    let unresponsive =
+        findings.run_ack == Some(false) || findings.app_id.as_ref().is_some_and(Result::is_err);
+    if unresponsive && node_ok && exclusive {
+        if allow_reset {
+            let reset = reset_and_probe(cli, &device).map_err(|e| e.to_string());
+            let (outcome, detail) = match &reset {
+                Ok(true) => (Outcome::Pass, "module answered after reset".to_string()),
+                Ok(false) => (
+                    Outcome::Fail,
+                    format!("no answer within {RESET_RECOVERY:?} of reset"),
+                ),
+                Err(e) => (Outcome::Fail, format!("reset failed: {e}")),
+            };
+            check(&mut checks, "reset", outcome, detail);
+            findings.reset = Some(reset);
+        } else {
+            check(
+                &mut checks,
+                "reset",
+                Outcome::Skipped,
+                "--no-reset given".to_string(),
+            );
+        }
+    }
+
+    Report {
+        checks,
+        causes: rank(&findings),
+    }
+}
+
+/// Probable causes for `findings`, most likely first
+pub fn rank(findings: &Findings) -> Vec<Cause> {
+    let tool = env!("CARGO_PKG_NAME");
+    let device = &findings.device;
+    let bus = device.trim_start_matches("/dev/i2c-");
+    let mut causes = Vec::new();
+    let mut cause = |likelihood: u8, summary: String, next_steps: &[String]| {
+        causes.push(Cause {
+            likelihood,
+            summary,
+            next_steps: next_steps.to_vec(),
+        });
+    };
+
+    match &findings.node {
+        Some(NodeAccess::Missing) => cause(
+            95,
+            format!("I2C bus {device} does not exist: wrong bus number or the bus is not enabled"),
+            &[
+                "ls /dev/i2c-*".to_string(),
+                format!("{tool} --i2c-bus <N> diagnose"),
+            ],
+        ),
+        Some(NodeAccess::PermissionDenied) => cause(
+            95,
+            format!("No permission to open {device}"),
+            &[format!("sudo {tool} diagnose"), format!("ls -l {device}")],
+        ),
+        Some(NodeAccess::Other(e)) => cause(
+            80,
+            format!("{device} cannot be opened: {e}"),
+            &["dmesg | grep -i i2c".to_string()],
+        ),
+        _ => {}
+    }
+
+    if let Some(pid) = findings.busy {
+        let holder = pid.map_or_else(
+            || "Another instance".to_string(),
+            |pid| format!("PID {pid}"),
+        );
+        cause(
+            90,
+            format!("{holder} is using the module, so this run cannot"),
+            &[
+                "systemctl status xm125-radar-monitor".to_string(),
+                format!("{tool} --takeover <command>"),
+            ],
+        );
+    }
+
+    let pin = |line: &str| findings.pins.iter().find(|pin| pin.line == line);
+    let driven = |line: &str, level: GpioValue| {
+        pin(line).is_some_and(|pin| pin.output && pin.value == Some(level))
+    };
+    if driven("reset", findings.reset_active) {
+        let level = match findings.reset_active {
+            GpioValue::Low => "low",
+            GpioValue::High => "high",
+        };
+        cause(
+            85,
+            format!("Module held in reset (RESET line driven {level})"),
+            &[
+                format!("{tool} gpio reset-run"),
+                format!("{tool} power wake"),
+            ],
+        );
+    }
+    if findings.bootloader_ack == Some(true) && findings.run_ack == Some(false) {
+        cause(
+            85,
+            format!(
+                "Module is in the bootloader (0x{BOOTLOADER_I2C_ADDRESS:02X} answers, 0x{:02X} does not): no application firmware or BOOT0 left high",
+                findings.address
+            ),
+            &[
+                format!("{tool} gpio reset-run"),
+                format!("{tool} firmware update presence"),
+            ],
+        );
+    } else if driven("boot", GpioValue::High) {
+        cause(
+            80,
+            "BOOT0 driven high: the module starts the bootloader on the next reset".to_string(),
+            &[format!("{tool} gpio reset-run")],
+        );
+    }
+    if driven("wake_up", GpioValue::Low) {
+        cause(
+            70,
+            "Module sleeping (WAKE_UP driven low)".to_string(),
+            &[format!("{tool} power wake")],
+        );
+    }
+    if findings.pins.iter().any(|pin| !pin.exported) {
+        let missing: Vec<String> = findings
+            .pins
+            .iter()
+            .filter(|pin| !pin.exported)
+            .map(|pin| format!("{} (GPIO{})", pin.line, pin.gpio))
+            .collect();
+        cause(
+            60,
+            format!("Control GPIOs not exported: {}", missing.join(", ")),
+            &[format!("{tool} gpio init"), format!("{tool} gpio status")],
+        );
+    }
+
+    if findings.run_ack == Some(false) && findings.bootloader_ack != Some(true) {
+        match &findings.reset {
+            Some(Ok(true)) => cause(
+                75,
+                "Module had stopped responding and came back after a reset: firmware hang or brown-out"
+                    .to_string(),
+                &[
+                    format!("{tool} stats"),
+                    "check the 3.3V supply under load".to_string(),
+                ],
+            ),
+            _ => cause(
+                80,
+                format!(
+                    "Nothing answers at 0x{:02X}: module unpowered, wiring fault or wrong address",
+                    findings.address
+                ),
+                &[
+                    format!("i2cdetect -y {bus}"),
+                    format!("{tool} --i2c-address <ADDR> diagnose"),
+                    format!("{tool} gpio status"),
+                ],
+            ),
+        }
+    }
+
+    match &findings.app_id {
+        Some(Ok(app_id)) if protocol_for_app_id(*app_id).is_none() => cause(
+            70,
+            format!("Unrecognised firmware (application ID {app_id})"),
+            &[
+                format!("{tool} firmware update presence"),
+                format!("{tool} firmware update distance"),
+            ],
+        ),
+        Some(Err(e)) => cause(
+            65,
+            format!("Module ACKs but register reads fail ({e}): bus noise, pull-ups or clock stretching"),
+            &[
+                "dmesg | grep -i i2c".to_string(),
+                format!("{tool} --i2c-record /tmp/xm125-trace.jsonl status"),
+            ],
+        ),
+        _ => {}
+    }
+    if !findings.status_errors.is_empty() {
+        cause(
+            65,
+            format!(
+                "Detector reports errors: {}",
+                findings.status_errors.join(", ")
+            ),
+            &[
+                format!("{tool} gpio reset-run"),
+                format!("{tool} --debug-registers status"),
+            ],
+        );
+    }
+
+    causes.sort_by_key(|cause| std::cmp::Reverse(cause.likelihood));
+    causes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_bootloader_before_unexported_pins() {
+        let findings = Findings {
+            device: "/dev/i2c-2".to_string(),
+            address: 0x52,
+            node: Some(NodeAccess::Ok),
+            run_ack: Some(false),
+            bootloader_ack: Some(true),
+            pins: vec![PinState {
+                line: "boot",
+                gpio: 141,
+                exported: false,
+                output: false,
+                value: None,
+            }],
+            ..Findings::default()
+        };
+        let causes = rank(&findings);
+        assert_eq!(causes.len(), 2);
+        assert!(causes[0].summary.contains("bootloader"));
+        assert!(causes[1].summary.contains("GPIO141"));
+
+        let healthy = Findings {
+            node: Some(NodeAccess::Ok),
+            run_ack: Some(true),
+            app_id: Some(Ok(2)),
+            ..Findings::default()
+        };
+        assert!(rank(&healthy).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! Home Assistant MQTT discovery
+//!
+//! Home Assistant creates entities for retained config messages under its discovery prefix
+//! (`homeassistant/<component>/<node>/<object>/config`). The radar is described as one
+//! device with a `binary_sensor` for presence and `sensor`s for distance and temperature,
+//! all reading the JSON measurement records published on one state topic. `ha-discovery`
+//! prints the config messages so they can be published with any MQTT client.
+
+use serde::Serialize;
+use serde_json::json;
+
+/// Device identifier: `XM125_DEVICE_ID`, falling back to the hostname
+pub fn device_id() -> String {
+    std::env::var("XM125_DEVICE_ID")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Node ID Home Assistant accepts in discovery topics (`[a-zA-Z0-9_-]`)
+fn node_id(device_id: &str) -> String {
+    let id: String = device_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("xm125_{id}")
+}
+
+/// One retained discovery config message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiscoveryMessage {
+    pub topic: String,
+    pub payload: serde_json::Value,
+    pub retain: bool,
+}
+
+/// Config messages for a radar publishing its measurements on `state_topic`
+pub fn discovery_messages(
+    discovery_prefix: &str,
+    state_topic: &str,
+    device_id: &str,
+) -> Vec<DiscoveryMessage> {
+    let node = node_id(device_id);
+    let device = json!({
+        "identifiers": [node],
+        "name": format!("XM125 {device_id}"),
+        "manufacturer": "Acconeer",
+        "model": "XM125",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    // Presence records carry presence_distance_m, distance records distance_m and
+    // temperature_c; a record without the value keeps the entity's previous state
+    let entities = [
+        (
+            "binary_sensor",
+            "presence",
+            json!({
+                "name": "Presence",
+                "device_class": "occupancy",
+                "value_template":
+                    "{{ ('ON' if value_json.presence_detected else 'OFF') if value_json.presence_detected is defined else this.state }}",
+            }),
+        ),
+        (
+            "sensor",
+            "distance",
+            json!({
+                "name": "Distance",
+                "device_class": "distance",
+                "unit_of_measurement": "m",
+                "state_class": "measurement",
+                "value_template":
+                    "{{ value_json.distance_m if value_json.distance_m is defined else value_json.presence_distance_m | default(this.state) }}",
+            }),
+        ),
+        (
+            "sensor",
+            "temperature",
+            json!({
+                "name": "Temperature",
+                "device_class": "temperature",
+                "unit_of_measurement": "°C",
+                "state_class": "measurement",
+                "value_template": "{{ value_json.temperature_c | default(this.state) }}",
+            }),
+        ),
+    ];
+
+    entities
+        .into_iter()
+        .map(|(component, object, mut payload)| {
+            payload["unique_id"] = format!("{node}_{object}").into();
+            payload["object_id"] = format!("{node}_{object}").into();
+            payload["state_topic"] = state_topic.into();
+            payload["device"] = device.clone();
+            DiscoveryMessage {
+                topic: format!("{discovery_prefix}/{component}/{node}/{object}/config"),
+                payload,
+                retain: true,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_messages_describe_one_device() {
+        let messages = discovery_messages("homeassistant", "xm125/lobby.1", "lobby.1");
+        let topics: Vec<&str> = messages.iter().map(|m| m.topic.as_str()).collect();
+        assert_eq!(
+            topics,
+            [
+                "homeassistant/binary_sensor/xm125_lobby_1/presence/config",
+                "homeassistant/sensor/xm125_lobby_1/distance/config",
+                "homeassistant/sensor/xm125_lobby_1/temperature/config",
+            ]
+        );
+        for message in &messages {
+            assert!(message.retain);
+            assert_eq!(message.payload["state_topic"], "xm125/lobby.1");
+            assert_eq!(message.payload["device"]["identifiers"][0], "xm125_lobby_1");
+        }
+        assert_eq!(messages[0].payload["unique_id"], "xm125_lobby_1_presence");
+    }
+}
@@ -3,48 +3,398 @@
 //! This module handles all output formatting and display logic for measurements,
 //! including console output and FIFO writing for system integration.
 
+use crate::aggregate::{DistanceWindow, PresenceWindow, Stats};
+use crate::baseline::BaselineReport;
 use crate::cli::OutputFormat;
+use crate::error::RadarError;
 use crate::fifo::{FifoFormat, FifoWriter};
-use crate::radar::{DistanceMeasurement, PresenceMeasurement};
-use chrono::Utc;
+use crate::presence_events::PresenceEvent;
+use crate::radar::debug::{DecodedField, RegisterDump, RegisterRecord, RegisterSection};
+use crate::radar::{
+    BreathingMeasurement, BreathingState, DistanceMeasurement, DistancePeak, PresenceMeasurement,
+    RawRegisters,
+};
+use crate::sentry::{ModeChange, SentryMode};
+use crate::timeline::HardwareEvent;
+use crate::timestamps;
+use crate::zones::ZoneEvent;
+
+/// CSV header for distance measurements on stdout
+pub const DISTANCE_CSV_HEADER: &str =
+    "timestamp,distance_m,signal_strength,temperature_c,signal_strength_db";
+
+/// CSV header for zone-change events on stdout (`--zone-events-only`)
+pub const ZONE_EVENT_CSV_HEADER: &str = "timestamp,from_zone,to_zone,distance_m";
+
+/// CSV header for presence measurements on stdout
+pub const PRESENCE_CSV_HEADER: &str =
+    "timestamp,presence_detected,presence_distance_m,intra_score,inter_score,signal_quality,confidence";
+
+/// CSV header for presence transitions on stdout (`--events`)
+pub const PRESENCE_EVENT_CSV_HEADER: &str = "timestamp,event,duration_s,distance_m";
+
+/// CSV header for `sentry` measurements and mode changes on stdout
+pub const SENTRY_CSV_HEADER: &str = "timestamp,mode,target,distance_m,switched_from,switch_ms";
+
+/// CSV header for breathing measurements on stdout
+pub const BREATHING_CSV_HEADER: &str = "timestamp,breathing_rate_bpm,state,temperature_c";
+
+/// CSV header for register dumps (`--debug-registers`)
+pub const REGISTER_CSV_HEADER: &str = "section,address,name,value,value_hex,error,fields";
+
+/// CSV header for windowed presence aggregates
+pub const PRESENCE_WINDOW_CSV_HEADER: &str = "window_start,window_end,samples,presence_ratio,intra_min,intra_max,intra_mean,inter_min,inter_max,inter_mean,distance_min_m,distance_max_m,distance_mean_m";
+
+/// CSV header for windowed distance aggregates
+pub const DISTANCE_WINDOW_CSV_HEADER: &str = "window_start,window_end,samples,targets,distance_min_m,distance_max_m,distance_mean_m,strength_min,strength_max,strength_mean";
+
+/// CSV header for distance measurements, with the `peaks` column when several are read
+pub fn distance_csv_header(multi_peak: bool) -> String {
+    if multi_peak {
+        format!("{DISTANCE_CSV_HEADER},peaks")
+    } else {
+        DISTANCE_CSV_HEADER.to_string()
+    }
+}
+
+/// `peaks` CSV column: `distance_m:strength_db` per peak, separated by semicolons
+pub fn peaks_csv_field(peaks: &[DistancePeak]) -> String {
+    peaks
+        .iter()
+        .map(|peak| format!("{:.3}:{:.2}", peak.distance, peak.strength_db))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Format a distance measurement as a CSV row matching [`distance_csv_header`]
+pub fn distance_csv_row(result: &DistanceMeasurement) -> String {
+    let mut row = format!(
+        "{},{:.3},{:.1},{:.1},{:.2}",
+        timestamps::now(),
+        result.distance,
+        result.strength,
+        result.temperature,
+        result.strength_db
+    );
+    if let Some(peaks) = &result.peaks {
+        row.push(',');
+        row.push_str(&peaks_csv_field(peaks));
+    }
+    row
+}
+
+/// Format a presence measurement as a CSV row matching `PRESENCE_CSV_HEADER`
+pub fn presence_csv_row(result: &PresenceMeasurement) -> String {
+    let max_score = result.intra_presence_score.max(result.inter_presence_score);
+    let signal_quality = if max_score > 2.0 {
+        "STRONG"
+    } else if max_score > 1.0 {
+        "MEDIUM"
+    } else if max_score > 0.5 {
+        "WEAK"
+    } else {
+        "NONE"
+    };
+    let confidence = if result.presence_detected {
+        if max_score > 3.0 {
+            "HIGH"
+        } else if max_score > 1.5 {
+            "MEDIUM"
+        } else {
+            "LOW"
+        }
+    } else {
+        "NONE"
+    };
+    format!(
+        "{},{},{:.3},{:.2},{:.2},{},{}",
+        timestamps::now(),
+        result.presence_detected,
+        result.presence_distance,
+        result.intra_presence_score,
+        result.inter_presence_score,
+        signal_quality,
+        confidence
+    )
+}
 
 /// Display distance measurement result in the specified format
 pub fn display_distance_result(result: &DistanceMeasurement, format: &OutputFormat) {
+    crate::journald::log_distance(result);
+
     match format {
         OutputFormat::Json => {
-            let json_result = serde_json::json!({
-                "timestamp": Utc::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            let mut json_result = serde_json::json!({
+                "timestamp": timestamps::now(),
                 "distance_m": result.distance,
                 "signal_strength": result.strength,
+                "signal_strength_db": result.strength_db,
                 "temperature_c": result.temperature
             });
+            add_correction_fields(&mut json_result, result);
             println!("{}", serde_json::to_string_pretty(&json_result).unwrap());
         }
         OutputFormat::Csv => {
-            println!("timestamp,distance_m,signal_strength,temperature_c");
+            println!("{}", distance_csv_header(result.peaks.is_some()));
+            println!("{}", distance_csv_row(result));
+        }
+        OutputFormat::Human => {
+            let correction = match (result.uncorrected_distance, result.temperature_delta) {
+                (Some(uncorrected), Some(delta)) => {
+                    format!(" (uncorrected {uncorrected:.3}m, ΔT {delta:+}°C)")
+                }
+                (Some(uncorrected), None) => format!(" (uncorrected {uncorrected:.3}m)"),
+                _ => String::new(),
+            };
+            let peaks = match result.peaks.as_deref() {
+                Some(peaks) if peaks.len() > 1 => format!(
+                    " | Peaks: {}",
+                    peaks
+                        .iter()
+                        .map(|peak| format!("{:.3}m {:.1}dB", peak.distance, peak.strength_db))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                _ => String::new(),
+            };
             println!(
-                "{},{:.3},{:.1},{:.1}",
-                Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
-                result.distance,
-                result.strength,
-                result.temperature
+                "📏 Distance: {:.3}m | Signal: {:.1}dB (raw {:.0}) | Temp: {:.1}°C{correction}{peaks}",
+                result.distance, result.strength_db, result.strength, result.temperature
             );
         }
+    }
+}
+
+/// Text of a decoded field value (strings unquoted)
+fn field_value(field: &DecodedField) -> String {
+    field
+        .value
+        .as_str()
+        .map_or_else(|| field.value.to_string(), String::from)
+}
+
+/// Decoded fields as `name=value` pairs separated by `;`
+fn register_fields_csv(record: &RegisterRecord) -> String {
+    record
+        .fields
+        .iter()
+        .map(|field| format!("{}={}", field.name, field_value(field)))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Per-field breakdown printed under a register row
+fn print_register_fields(record: &RegisterRecord) {
+    for field in &record.fields {
+        let (msb, lsb) = field.bits;
+        let bits = if msb == lsb {
+            format!("[{msb}]")
+        } else {
+            format!("[{msb}:{lsb}]")
+        };
+        let value = match &field.value {
+            serde_json::Value::Bool(true) => "✓".to_string(),
+            serde_json::Value::Bool(false) => "·".to_string(),
+            _ => field_value(field),
+        };
+        let unit = field.unit.unwrap_or("");
+        println!(
+            "                │   {bits:>7} {:<25} {value}{unit}",
+            field.name
+        );
+    }
+}
+
+pub fn register_csv_row(record: &RegisterRecord) -> String {
+    let error = record
+        .error
+        .as_deref()
+        .map_or_else(String::new, |e| format!("\"{}\"", e.replace('"', "\"\"")));
+    format!(
+        "{},{},{},{},{},{error},{}",
+        record.section.as_str(),
+        record.address,
+        record.name,
+        record
+            .value
+            .map_or_else(String::new, |value| value.to_string()),
+        record
+            .value
+            .map_or_else(String::new, |value| format!("0x{value:08X}")),
+        register_fields_csv(record)
+    )
+}
+
+fn register_section_title(dump: &RegisterDump, section: RegisterSection) -> String {
+    match section {
+        RegisterSection::Common => "📊 Common Status & Control Registers:".to_string(),
+        RegisterSection::Configuration => format!("⚙️  {} Configuration:", dump.protocol),
+        RegisterSection::Results => format!("📊 {} Results:", dump.protocol),
+        RegisterSection::Application => "🆔 Application Information:".to_string(),
+    }
+}
+
+/// Display a register dump as a table, JSON document or CSV records
+pub fn display_register_dump(dump: &RegisterDump, format: &OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(dump).unwrap());
+        }
+        OutputFormat::Csv => {
+            println!("{REGISTER_CSV_HEADER}");
+            for record in &dump.registers {
+                println!("{}", register_csv_row(record));
+            }
+        }
         OutputFormat::Human => {
+            let rule = "─".repeat(80);
+            println!("{}", "=".repeat(80));
+            println!("XM125 Register Dump - {}", dump.protocol);
+            println!("{}", "=".repeat(80));
+
+            let mut section = None;
+            for record in &dump.registers {
+                if section != Some(record.section) {
+                    if section.is_some() {
+                        println!();
+                    }
+                    section = Some(record.section);
+                    println!("{}", register_section_title(dump, record.section));
+                    println!("{rule}");
+                    println!(
+                        "  Addr   (Dec) │ Register Name             │ Value (Hex)  (Decimal) │ Description"
+                    );
+                    println!("{rule}");
+                }
+                match (record.value, &record.error) {
+                    (Some(value), _) => println!(
+                        "  0x{:04X} ({:3}) │ {:<25} │ 0x{value:08X} ({value:10}) │ {}",
+                        record.address, record.address, record.name, record.description
+                    ),
+                    (None, error) => println!(
+                        "  0x{:04X} ({:3}) │ {:<25} │ ERROR: {} │ {}",
+                        record.address,
+                        record.address,
+                        record.name,
+                        error.as_deref().unwrap_or("unreadable"),
+                        record.description
+                    ),
+                }
+                print_register_fields(record);
+            }
+
+            if let Some(status) = dump.status() {
+                let flags = status
+                    .fields
+                    .iter()
+                    .filter(|field| field.value == serde_json::Value::Bool(true))
+                    .map(|field| field.name)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("\n🚦 Status Flags (0x{:08X}):", status.value.unwrap_or(0));
+                if flags.is_empty() {
+                    println!("  (none set)");
+                } else {
+                    println!("  {flags}");
+                }
+            }
+            println!("{}", "=".repeat(80));
+        }
+    }
+}
+
+/// Format a breathing measurement as a CSV row matching `BREATHING_CSV_HEADER`
+///
+/// The rate column is empty while the application has no estimate.
+pub fn breathing_csv_row(result: &BreathingMeasurement) -> String {
+    format!(
+        "{},{},{},{}",
+        timestamps::format_at(result.timestamp),
+        result
+            .breathing_rate
+            .map(|rate| format!("{rate:.1}"))
+            .unwrap_or_default(),
+        result.state.name(),
+        result.temperature
+    )
+}
+
+/// Display breathing measurement result in the specified format
+pub fn display_breathing_result(result: &BreathingMeasurement, format: &OutputFormat) {
+    match format {
+        OutputFormat::Json => {
+            let mut json_result = breathing_fifo_json(result);
+            add_raw(&mut json_result, result.raw.as_ref());
+            println!("{}", serde_json::to_string_pretty(&json_result).unwrap());
+        }
+        OutputFormat::Csv => {
+            println!("{BREATHING_CSV_HEADER}");
+            println!("{}", breathing_csv_row(result));
+        }
+        OutputFormat::Human => {
+            let status = match result.state {
+                BreathingState::NoPresence => "🔴 NO PRESENCE",
+                BreathingState::IntraPresence => "🟡 MOVING",
+                BreathingState::EstimateBreathingRate if result.breathing_rate.is_some() => {
+                    "🫁 BREATHING"
+                }
+                _ => "⏳ SETTLING",
+            };
+            let rate = result
+                .breathing_rate
+                .map_or_else(|| "--".to_string(), |rate| format!("{rate:.1} bpm"));
             println!(
-                "📏 Distance: {:.3}m | Signal: {:.1} | Temp: {:.1}°C",
-                result.distance, result.strength, result.temperature
+                "{} | Rate: {} | State: {} | Temperature: {}°C",
+                status,
+                rate,
+                result.state.name(),
+                result.temperature
             );
         }
     }
 }
 
+/// Write breathing measurement to FIFO with timing control
+pub fn write_breathing_to_fifo(
+    writer: &mut FifoWriter,
+    result: &BreathingMeasurement,
+    format: &FifoFormat,
+) {
+    if !writer.should_write() {
+        return;
+    }
+    let _ = match format {
+        // Simple format: presence_state (0/1) and breathing rate (0 while unknown)
+        FifoFormat::Simple => writer.write_data(&format!(
+            "{} {:.1}\n",
+            i32::from(result.state.presence()),
+            result.breathing_rate.unwrap_or(0.0)
+        )),
+        FifoFormat::Json => writer.write_json(&breathing_fifo_json(result)),
+    };
+}
+
+/// JSON FIFO record for a breathing measurement
+pub fn breathing_fifo_json(result: &BreathingMeasurement) -> serde_json::Value {
+    serde_json::json!({
+        "timestamp": timestamps::format_at(result.timestamp),
+        "sensor_type": "XM125",
+        "detection_mode": "breathing",
+        "breathing_rate_bpm": result.breathing_rate,
+        "state": result.state,
+        "presence_detected": result.state.presence(),
+        "temperature_c": result.temperature
+    })
+}
+
 /// Display presence measurement result in the specified format
 pub fn display_presence_result(result: &PresenceMeasurement, format: &OutputFormat) {
+    crate::journald::log_presence(result);
+
     match format {
         OutputFormat::Json => {
-            let json_result = serde_json::json!({
-                "timestamp": Utc::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            let mut json_result = serde_json::json!({
+                "timestamp": timestamps::now(),
                 "presence_detected": result.presence_detected,
                 "presence_distance_m": result.presence_distance,
                 "intra_score": result.intra_presence_score,
@@ -65,41 +415,13 @@ pub fn display_presence_result(result: &PresenceMeasurement, format: &OutputForm
                     "NONE"
                 }
             });
+            add_config_generation(&mut json_result, result.config_generation);
+            add_raw(&mut json_result, result.raw.as_ref());
             println!("{}", serde_json::to_string_pretty(&json_result).unwrap());
         }
         OutputFormat::Csv => {
-            println!("timestamp,presence_detected,presence_distance_m,intra_score,inter_score,signal_quality,confidence");
-            let max_score = result.intra_presence_score.max(result.inter_presence_score);
-            let signal_quality = if max_score > 2.0 {
-                "STRONG"
-            } else if max_score > 1.0 {
-                "MEDIUM"
-            } else if max_score > 0.5 {
-                "WEAK"
-            } else {
-                "NONE"
-            };
-            let confidence = if result.presence_detected {
-                if max_score > 3.0 {
-                    "HIGH"
-                } else if max_score > 1.5 {
-                    "MEDIUM"
-                } else {
-                    "LOW"
-                }
-            } else {
-                "NONE"
-            };
-            println!(
-                "{},{},{:.3},{:.2},{:.2},{},{}",
-                Utc::now().format("%Y-%m-%d %H:%M:%S%.3f"),
-                result.presence_detected,
-                result.presence_distance,
-                result.intra_presence_score,
-                result.inter_presence_score,
-                signal_quality,
-                confidence
-            );
+            println!("{PRESENCE_CSV_HEADER}");
+            println!("{}", presence_csv_row(result));
         }
         OutputFormat::Human => {
             let status = if result.presence_detected {
@@ -144,19 +466,256 @@ pub fn write_distance_to_fifo(
             let _ = writer.write_timed_simple(1, result.distance);
         }
         FifoFormat::Json => {
-            let json_data = serde_json::json!({
-                "timestamp": Utc::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
-                "sensor_type": "XM125",
-                "detection_mode": "distance",
-                "distance_m": result.distance,
-                "signal_strength": result.strength,
-                "temperature_c": result.temperature
+            let timestamp = timestamps::now();
+            let _ = writer.write_timed_json(&distance_fifo_json(result, &timestamp));
+        }
+    }
+}
+
+/// JSON FIFO record for a distance measurement
+pub fn distance_fifo_json(result: &DistanceMeasurement, timestamp: &str) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "timestamp": timestamp,
+        "sensor_type": "XM125",
+        "detection_mode": "distance",
+        "distance_m": result.distance,
+        "signal_strength": result.strength,
+        "signal_strength_db": result.strength_db,
+        "temperature_c": result.temperature
+    });
+    add_correction_fields(&mut json, result);
+    json
+}
+
+/// Add the settings file generation a measurement was taken with, when `--config` is used
+fn add_config_generation(json: &mut serde_json::Value, generation: Option<u32>) {
+    if let Some(generation) = generation {
+        json["config_generation"] = generation.into();
+    }
+}
+
+/// Add the register words a measurement was decoded from, when `--include-raw` is used
+fn add_raw(json: &mut serde_json::Value, raw: Option<&RawRegisters>) {
+    if let Some(raw) = raw {
+        json["raw"] = serde_json::json!(raw);
+    }
+}
+
+/// Add the module-reported distance and temperature change when a correction was applied
+fn add_correction_fields(json: &mut serde_json::Value, result: &DistanceMeasurement) {
+    if let Some(uncorrected) = result.uncorrected_distance {
+        json["uncorrected_distance_m"] = uncorrected.into();
+    }
+    if let Some(delta) = result.temperature_delta {
+        json["temperature_delta_c"] = delta.into();
+    }
+    if let Some(peaks) = &result.peaks {
+        json["peaks"] = peaks
+            .iter()
+            .map(|peak| {
+                serde_json::json!({
+                    "distance_m": peak.distance,
+                    "signal_strength_db": peak.strength_db,
+                })
+            })
+            .collect();
+    }
+    add_config_generation(json, result.config_generation);
+    add_raw(json, result.raw.as_ref());
+}
+
+/// Display a zone-change event in the requested format
+/// Show a GPIO write or module reset in the output stream
+///
+/// CSV on stdout carries measurement rows only, so events go to the diagnostic log there.
+pub fn display_hardware_event(event: &HardwareEvent, format: &OutputFormat) {
+    let timestamp = event.timestamp().format("%Y-%m-%d %H:%M:%S%.3f");
+    let description = match event {
+        HardwareEvent::Gpio {
+            gpio, line, value, ..
+        } => format!("🔌 GPIO{gpio} ({line}) → {value}"),
+        HardwareEvent::Reset { mode, .. } => format!("🔄 Module reset ({mode:?} mode)"),
+    };
+    match format {
+        OutputFormat::Json => match serde_json::to_value(event) {
+            Ok(mut json) => {
+                json["timestamp"] = timestamp.to_string().into();
+                println!("{json}");
+            }
+            Err(e) => log::warn!("Failed to serialize hardware event: {e}"),
+        },
+        OutputFormat::Csv => log::info!("{timestamp} {description}"),
+        OutputFormat::Human => println!("{timestamp} {description}"),
+    }
+}
+
+pub fn display_zone_event(event: &ZoneEvent, format: &OutputFormat) {
+    let timestamp = timestamps::format_at(event.timestamp);
+    match format {
+        OutputFormat::Json => {
+            let json_event = serde_json::json!({
+                "event": "zone_change",
+                "timestamp": timestamp,
+                "from": event.from,
+                "to": event.to,
+                "distance_m": event.distance_m
             });
-            let _ = writer.write_timed_json(&json_data);
+            println!("{json_event}");
+        }
+        OutputFormat::Csv => {
+            println!(
+                "{timestamp},{},{},{:.3}",
+                event.from.as_deref().unwrap_or(""),
+                event.to,
+                event.distance_m
+            );
+        }
+        OutputFormat::Human => {
+            println!(
+                "🚪 Zone: {} → {} ({:.2}m)",
+                event.from.as_deref().unwrap_or("-"),
+                event.to,
+                event.distance_m
+            );
         }
     }
 }
 
+/// Write a zone-change event to FIFO (events are never rate limited)
+pub fn write_zone_event_to_fifo(writer: &FifoWriter, event: &ZoneEvent, format: &FifoFormat) {
+    let _ = match format {
+        FifoFormat::Simple => writer.write_data(&format!(
+            "ZONE {} {} {:.2}\n",
+            event.from.as_deref().unwrap_or("-"),
+            event.to,
+            writer.distance(event.distance_m)
+        )),
+        FifoFormat::Json => writer.write_json(&serde_json::json!({
+            "timestamp": timestamps::format_at(event.timestamp),
+            "sensor_type": "XM125",
+            "event": "zone_change",
+            "from": event.from,
+            "to": event.to,
+            "distance_m": event.distance_m
+        })),
+    };
+}
+
+/// JSON record of a presence transition (stdout, FIFO and MQTT)
+pub fn presence_event_json(event: &PresenceEvent) -> serde_json::Value {
+    serde_json::json!({
+        "timestamp": timestamps::format_at(event.timestamp),
+        "sensor_type": "XM125",
+        "event": event.event,
+        "duration_s": event.duration_s,
+        "distance_m": event.distance_m
+    })
+}
+
+pub fn display_presence_event(event: &PresenceEvent, format: &OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("{}", presence_event_json(event)),
+        OutputFormat::Csv => {
+            println!(
+                "{},{},{},{:.3}",
+                timestamps::format_at(event.timestamp),
+                event.event.name(),
+                event
+                    .duration_s
+                    .map(|duration| format!("{duration:.1}"))
+                    .unwrap_or_default(),
+                event.distance_m
+            );
+        }
+        OutputFormat::Human => {
+            let duration = match event.duration_s {
+                Some(duration) => format!(" after {duration:.1}s"),
+                None => String::new(),
+            };
+            println!(
+                "{} 👤 {}{duration} ({:.2}m)",
+                event.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+                event.event.name(),
+                event.distance_m
+            );
+        }
+    }
+}
+
+/// Write a presence transition to FIFO (events are never rate limited)
+pub fn write_presence_event_to_fifo(
+    writer: &FifoWriter,
+    event: &PresenceEvent,
+    format: &FifoFormat,
+) {
+    let _ = match format {
+        FifoFormat::Simple => writer.write_data(&format!(
+            "EVENT {} {} {:.2}\n",
+            event.event.name(),
+            event
+                .duration_s
+                .map_or_else(|| "-".to_string(), |duration| format!("{duration:.1}")),
+            writer.distance(event.distance_m)
+        )),
+        FifoFormat::Json => writer.write_json(&presence_event_json(event)),
+    };
+}
+
+pub fn mode_change_json(change: &ModeChange) -> serde_json::Value {
+    serde_json::json!({
+        "timestamp": timestamps::format_at(change.timestamp),
+        "sensor_type": "XM125",
+        "event": "MODE_CHANGE",
+        "from": change.from,
+        "to": change.to,
+        "dwell_s": change.dwell_s,
+        "switch_ms": change.switch_ms
+    })
+}
+
+/// Sentry measurement row: whether the running detector sees a target, and where
+pub fn display_sentry_csv_row(
+    timestamp: chrono::DateTime<chrono::Utc>,
+    mode: SentryMode,
+    target: bool,
+    distance: f32,
+) {
+    println!(
+        "{},{},{target},{distance:.3},,",
+        timestamps::format_at(timestamp),
+        mode.name()
+    );
+}
+
+pub fn display_mode_change(change: &ModeChange, format: &OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("{}", mode_change_json(change)),
+        OutputFormat::Csv => println!(
+            "{},{},,,{},{}",
+            timestamps::format_at(change.timestamp),
+            change.to.name(),
+            change.from.name(),
+            change.switch_ms
+        ),
+        OutputFormat::Human => println!(
+            "{} 🔀 {} → {} after {:.1}s (switch took {}ms)",
+            change.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+            change.from.name(),
+            change.to.name(),
+            change.dwell_s,
+            change.switch_ms
+        ),
+    }
+}
+
+/// Write a sentry mode change to the FIFO
+pub fn write_mode_change_to_fifo(writer: &FifoWriter, change: &ModeChange, format: &FifoFormat) {
+    let _ = match format {
+        FifoFormat::Simple => writer.write_data(&format!("MODE {}\n", change.to.name())),
+        FifoFormat::Json => writer.write_json(&mode_change_json(change)),
+    };
+}
+
 /// Write presence measurement to FIFO with timing control
 pub fn write_presence_to_fifo(
     writer: &mut FifoWriter,
@@ -170,31 +729,242 @@ pub fn write_presence_to_fifo(
             let _ = writer.write_timed_simple(presence_state, result.presence_distance);
         }
         FifoFormat::Json => {
-            let json_data = serde_json::json!({
-                "timestamp": Utc::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
-                "sensor_type": "XM125",
-                "detection_mode": "presence",
-                "presence_detected": result.presence_detected,
-                "presence_distance_m": result.presence_distance,
-                "intra_score": result.intra_presence_score,
-                "inter_score": result.inter_presence_score,
-                "signal_quality": if result.intra_presence_score.max(result.inter_presence_score) > 2.0 {
-                    "STRONG"
-                } else if result.intra_presence_score.max(result.inter_presence_score) > 1.0 {
-                    "MEDIUM"
-                } else if result.intra_presence_score.max(result.inter_presence_score) > 0.5 {
-                    "WEAK"
-                } else {
-                    "NONE"
-                },
-                "confidence": if result.presence_detected {
-                    let max_score = result.intra_presence_score.max(result.inter_presence_score);
-                    if max_score > 3.0 { "HIGH" } else if max_score > 1.5 { "MEDIUM" } else { "LOW" }
-                } else {
-                    "NONE"
+            let timestamp = timestamps::now();
+            let _ = writer.write_timed_json(&presence_fifo_json(result, &timestamp));
+        }
+    }
+}
+
+/// JSON FIFO record for a presence measurement
+pub fn presence_fifo_json(result: &PresenceMeasurement, timestamp: &str) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "timestamp": timestamp,
+        "sensor_type": "XM125",
+        "detection_mode": "presence",
+        "presence_detected": result.presence_detected,
+        "presence_distance_m": result.presence_distance,
+        "intra_score": result.intra_presence_score,
+        "inter_score": result.inter_presence_score,
+        "signal_quality": if result.intra_presence_score.max(result.inter_presence_score) > 2.0 {
+            "STRONG"
+        } else if result.intra_presence_score.max(result.inter_presence_score) > 1.0 {
+            "MEDIUM"
+        } else if result.intra_presence_score.max(result.inter_presence_score) > 0.5 {
+            "WEAK"
+        } else {
+            "NONE"
+        },
+        "confidence": if result.presence_detected {
+            let max_score = result.intra_presence_score.max(result.inter_presence_score);
+            if max_score > 3.0 { "HIGH" } else if max_score > 1.5 { "MEDIUM" } else { "LOW" }
+        } else {
+            "NONE"
+        }
+    });
+    add_config_generation(&mut json, result.config_generation);
+    add_raw(&mut json, result.raw.as_ref());
+    json
+}
+
+/// Display an empty-room baseline survey report
+pub fn display_baseline_report(
+    report: &BaselineReport,
+    format: &OutputFormat,
+) -> Result<(), RadarError> {
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(report)?);
+        }
+        OutputFormat::Csv => {
+            println!("bin_start_m,bin_end_m,false_positives");
+            for bin in &report.distance_histogram {
+                println!("{:.2},{:.2},{}", bin.start_m, bin.end_m, bin.count);
+            }
+        }
+        OutputFormat::Human => {
+            println!("🧪 Baseline Capture Report");
+            println!(
+                "   Duration: {:.0}s, {} samples",
+                report.duration_s, report.samples
+            );
+            println!(
+                "   False positives: {} ({:.1}/hour)",
+                report.false_positives, report.false_positives_per_hour
+            );
+            println!(
+                "   Max scores: intra {:.2} (threshold {:.2}), inter {:.2} (threshold {:.2})",
+                report.max_intra_score,
+                report.current_intra_threshold,
+                report.max_inter_score,
+                report.current_inter_threshold
+            );
+            if report.false_positives > 0 {
+                println!("   Distance histogram:");
+                for bin in report.distance_histogram.iter().filter(|bin| bin.count > 0) {
+                    println!("     {:.1}-{:.1}m: {}", bin.start_m, bin.end_m, bin.count);
                 }
-            });
-            let _ = writer.write_timed_json(&json_data);
+            }
+            match (
+                report.suggested_intra_threshold,
+                report.suggested_inter_threshold,
+            ) {
+                (None, None) => println!("   ✅ Current thresholds produced no false positives"),
+                (intra, inter) => {
+                    if let Some(threshold) = intra {
+                        println!("   💡 Suggested intra (fast motion) threshold: {threshold:.2}");
+                    }
+                    if let Some(threshold) = inter {
+                        println!("   💡 Suggested inter (slow motion) threshold: {threshold:.2}");
+                    }
+                }
+            }
         }
     }
+    Ok(())
+}
+
+/// `min,max,mean` CSV fields, empty when the window had no qualifying samples
+fn stats_csv_fields(stats: Option<Stats>, precision: usize) -> String {
+    stats.map_or_else(
+        || ",,".to_string(),
+        |s| format!("{:.p$},{:.p$},{:.p$}", s.min, s.max, s.mean, p = precision),
+    )
+}
+
+/// `min/max/mean` for human output
+fn stats_human(stats: Option<Stats>, precision: usize) -> String {
+    stats.map_or_else(
+        || "-".to_string(),
+        |s| format!("{:.p$}/{:.p$}/{:.p$}", s.min, s.max, s.mean, p = precision),
+    )
+}
+
+/// Format a presence window as a CSV row matching `PRESENCE_WINDOW_CSV_HEADER`
+pub fn presence_window_csv_row(window: &PresenceWindow) -> String {
+    format!(
+        "{},{},{},{:.3},{},{},{}",
+        timestamps::format_at(window.window_start),
+        timestamps::format_at(window.window_end),
+        window.samples,
+        window.presence_ratio(),
+        stats_csv_fields(Some(window.intra_score), 2),
+        stats_csv_fields(Some(window.inter_score), 2),
+        stats_csv_fields(window.presence_distance_m, 3)
+    )
+}
+
+/// Format a distance window as a CSV row matching `DISTANCE_WINDOW_CSV_HEADER`
+pub fn distance_window_csv_row(window: &DistanceWindow) -> String {
+    format!(
+        "{},{},{},{},{},{}",
+        timestamps::format_at(window.window_start),
+        timestamps::format_at(window.window_end),
+        window.samples,
+        window.targets,
+        stats_csv_fields(window.distance_m, 3),
+        stats_csv_fields(window.signal_strength, 1)
+    )
+}
+
+fn presence_window_json(window: &PresenceWindow) -> serde_json::Value {
+    serde_json::json!({
+        "timestamp": timestamps::format_at(window.window_end),
+        "window_start": timestamps::format_at(window.window_start),
+        "samples": window.samples,
+        "presence_ratio": window.presence_ratio(),
+        "intra_score": window.intra_score,
+        "inter_score": window.inter_score,
+        "presence_distance_m": window.presence_distance_m
+    })
+}
+
+fn distance_window_json(window: &DistanceWindow) -> serde_json::Value {
+    serde_json::json!({
+        "timestamp": timestamps::format_at(window.window_end),
+        "window_start": timestamps::format_at(window.window_start),
+        "samples": window.samples,
+        "targets": window.targets,
+        "distance_m": window.distance_m,
+        "signal_strength": window.signal_strength
+    })
+}
+
+/// Display a presence window aggregate (CSV rows are streamed by the caller)
+pub fn display_presence_window(window: &PresenceWindow, format: &OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("{}", presence_window_json(window)),
+        OutputFormat::Csv => println!("{}", presence_window_csv_row(window)),
+        OutputFormat::Human => {
+            println!(
+                "📊 {}–{} | {} samples | Presence: {:.0}% | Intra: {} | Inter: {} | Distance: {}m",
+                window.window_start.format("%H:%M:%S%.3f"),
+                window.window_end.format("%H:%M:%S%.3f"),
+                window.samples,
+                window.presence_ratio() * 100.0,
+                stats_human(Some(window.intra_score), 2),
+                stats_human(Some(window.inter_score), 2),
+                stats_human(window.presence_distance_m, 2)
+            );
+        }
+    }
+}
+
+/// Display a distance window aggregate (CSV rows are streamed by the caller)
+pub fn display_distance_window(window: &DistanceWindow, format: &OutputFormat) {
+    match format {
+        OutputFormat::Json => println!("{}", distance_window_json(window)),
+        OutputFormat::Csv => println!("{}", distance_window_csv_row(window)),
+        OutputFormat::Human => {
+            println!(
+                "📊 {}–{} | {} samples, {} with target | Distance: {}m | Signal: {}",
+                window.window_start.format("%H:%M:%S%.3f"),
+                window.window_end.format("%H:%M:%S%.3f"),
+                window.samples,
+                window.targets,
+                stats_human(window.distance_m, 3),
+                stats_human(window.signal_strength, 1)
+            );
+        }
+    }
+}
+
+/// Write a presence window aggregate to the FIFO (not rate limited; one per window)
+pub fn write_presence_window_to_fifo(
+    writer: &FifoWriter,
+    window: &PresenceWindow,
+    format: &FifoFormat,
+) {
+    let _ = match format {
+        // BGT60TR13C compatible: presence if any sample in the window detected it
+        FifoFormat::Simple => writer.write_simple(
+            i32::from(window.detections > 0),
+            window.presence_distance_m.map_or(0.0, |d| d.mean),
+        ),
+        FifoFormat::Json => {
+            let mut json_data = presence_window_json(window);
+            json_data["sensor_type"] = "XM125".into();
+            json_data["detection_mode"] = "presence_window".into();
+            writer.write_json(&json_data)
+        }
+    };
+}
+
+/// Write a distance window aggregate to the FIFO (not rate limited; one per window)
+pub fn write_distance_window_to_fifo(
+    writer: &FifoWriter,
+    window: &DistanceWindow,
+    format: &FifoFormat,
+) {
+    let _ = match format {
+        FifoFormat::Simple => writer.write_simple(
+            i32::from(window.targets > 0),
+            window.distance_m.map_or(0.0, |d| d.mean),
+        ),
+        FifoFormat::Json => {
+            let mut json_data = distance_window_json(window);
+            json_data["sensor_type"] = "XM125".into();
+            json_data["detection_mode"] = "distance_window".into();
+            writer.write_json(&json_data)
+        }
+    };
 }
@@ -0,0 +1,131 @@
+//! Encryption of saved measurement files (`encrypt` feature)
+//!
+//! Some deployments record presence data in privacy-sensitive places where plaintext at rest
+//! is not acceptable. `--encrypt age:<recipient>` streams the CSV export and the rejected
+//! measurements file through age (X25519) encryption, so only the holder of the matching
+//! identity can read them (`age -d -i key.txt data.csv.age`). Records are still staged and
+//! committed by [`JournaledFile`](crate::journal::JournaledFile), but age seals data in
+//! 64 KiB chunks and authenticates the end of the stream: a file is only decryptable once it
+//! has been closed cleanly, and a power cut loses it rather than just the last interval.
+
+use age::stream::StreamWriter;
+use age::x25519::Recipient;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+/// Recipient of encrypted data files, given as `age:<age1...>`
+#[derive(Clone, PartialEq)]
+pub struct EncryptTarget(Recipient);
+
+impl fmt::Debug for EncryptTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "age:{}", self.0)
+    }
+}
+
+/// Parse `--encrypt age:<recipient>`
+pub fn parse_encrypt_target(s: &str) -> Result<EncryptTarget, String> {
+    let recipient = s
+        .strip_prefix("age:")
+        .ok_or_else(|| format!("Unsupported encryption '{s}' (expected age:<recipient>)"))?;
+    Recipient::from_str(recipient.trim())
+        .map(EncryptTarget)
+        .map_err(|e| format!("Invalid age recipient '{recipient}': {e}"))
+}
+
+/// Ciphertext produced by the age stream, waiting to be written to the file
+#[derive(Clone, Default)]
+struct Ciphertext(Arc<Mutex<Vec<u8>>>);
+
+impl Ciphertext {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl Write for Ciphertext {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Turns the plaintext records of one file into an age stream
+pub struct StreamEncryptor {
+    stream: StreamWriter<Ciphertext>,
+    ciphertext: Ciphertext,
+}
+
+impl fmt::Debug for StreamEncryptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamEncryptor").finish_non_exhaustive()
+    }
+}
+
+impl StreamEncryptor {
+    /// Start an age stream to `target`; its header is returned by the first [`Self::encrypt`]
+    pub fn new(target: &EncryptTarget) -> io::Result<Self> {
+        let encryptor = age::Encryptor::with_recipients(std::iter::once(&target.0 as _))
+            .map_err(io::Error::other)?;
+        let ciphertext = Ciphertext::default();
+        let stream = encryptor.wrap_output(ciphertext.clone())?;
+        Ok(Self { stream, ciphertext })
+    }
+
+    /// Encrypt `plaintext`, returning the ciphertext of every chunk completed so far
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        self.stream.write_all(plaintext)?;
+        Ok(self.ciphertext.take())
+    }
+
+    /// Encrypt the last `plaintext` and seal the stream, returning the remaining ciphertext
+    pub fn finish(mut self, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        self.stream.write_all(plaintext)?;
+        self.stream.finish()?;
+        Ok(self.ciphertext.take())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::JournaledFile;
+    use std::time::Duration;
+
+    #[test]
+    fn test_encrypted_data_file_decrypts_with_identity() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+        let identity = age::x25519::Identity::generate();
+        let target = parse_encrypt_target(&format!("age:{}", identity.to_public())).unwrap();
+        assert!(parse_encrypt_target("age:age1nope").is_err());
+        assert!(parse_encrypt_target(&identity.to_public().to_string()).is_err());
+
+        let mut file = JournaledFile::create(&path, Duration::ZERO)
+            .unwrap()
+            .with_encryption(Some(&target))
+            .unwrap();
+        file.write_all(b"timestamp,distance_m\n").unwrap();
+        file.flush().unwrap();
+        file.write_all(b"2024-01-01 00:00:00.000,1.25\n").unwrap();
+        file.flush().unwrap();
+        drop(file);
+
+        let ciphertext = std::fs::read(&path).unwrap();
+        assert!(!ciphertext.windows(4).any(|w| w == b"1.25"));
+        let plaintext = age::decrypt(&identity, &ciphertext).unwrap();
+        assert_eq!(
+            plaintext,
+            b"timestamp,distance_m\n2024-01-01 00:00:00.000,1.25\n"
+        );
+    }
+}
@@ -1,5 +1,10 @@
+//! Error type shared by every driver operation
+
+use crate::firmware::FirmwareType;
+use crate::timeouts::TimeoutPhase;
 use thiserror::Error;
 
+/// Failure of a driver, firmware or command operation
 #[derive(Error, Debug)]
 pub enum RadarError {
     #[error("I2C communication error: {0}")]
@@ -8,8 +13,11 @@ pub enum RadarError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("Command timeout after {timeout}s")]
-    Timeout { timeout: u64 },
+    #[error("Timed out during {phase} after {:.1}s", timeout.as_secs_f64())]
+    Timeout {
+        phase: TimeoutPhase,
+        timeout: std::time::Duration,
+    },
 
     #[error("Invalid response from XM125: {response}")]
     #[allow(dead_code)] // Reserved for future protocol validation
@@ -45,9 +53,57 @@ pub enum RadarError {
     #[allow(dead_code)] // Reserved for firmware management error handling
     FirmwareError { message: String },
 
+    #[error(
+        "`{}` needs {} firmware but the module is running {} firmware \
+         (flash it with `xm125-radar-monitor firmware update {}` or pass --auto-flash, or run `xm125-radar-monitor {}` instead)",
+        required.cli_name(),
+        required.display_name(),
+        detected.display_name(),
+        required.cli_name(),
+        detected.cli_name()
+    )]
+    FirmwareMismatch {
+        required: FirmwareType,
+        detected: FirmwareType,
+    },
+
+    #[error(
+        "{device} is in use by another xm125-radar-monitor instance{} (use --takeover to stop it)",
+        pid.map(|pid| format!(" (PID {pid})")).unwrap_or_default()
+    )]
+    DeviceBusy { device: String, pid: Option<i32> },
+
+    #[error("{path} is on a read-only filesystem (set {option} to a writable location, e.g. under /run or /var/lib)")]
+    ReadOnlyPath { path: String, option: &'static str },
+
+    #[error("Protocol violation: write to register 0x{register:04X} while the detector is {reason} (status 0x{status:08X})")]
+    ProtocolViolation {
+        register: u16,
+        status: u32,
+        reason: &'static str,
+    },
+
+    #[error("Register 0x{register:04X} did not read back as written after {attempts} attempts")]
+    WriteVerifyFailed { register: u16, attempts: u32 },
+
+    #[error("Signature check failed for {path}: {reason}")]
+    #[cfg_attr(not(feature = "sign"), allow(dead_code))]
+    SignatureInvalid { path: String, reason: String },
+
     #[error("XM125 module not programmed or not responding")]
     #[allow(dead_code)] // Reserved for unprogrammed module detection
     ModuleNotProgrammed,
 }
 
+impl RadarError {
+    /// Process exit code: 2 for timeouts (e.g. `gpio wait-ready`), 1 for everything else
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RadarError::Timeout { .. } => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Result of a driver operation
 pub type Result<T> = std::result::Result<T, RadarError>;
@@ -0,0 +1,198 @@
+//! Failure injection for robustness testing (`fault-injection` feature)
+//!
+//! QA builds accept the hidden `--inject-failure i2c-read:0.01,calibration-timeout:0.001`
+//! option: each listed point fails at the given rate, so the recovery ladder and daemon
+//! supervision can be exercised without a flaky bus. Failures are drawn from a generator
+//! seeded with `--inject-seed`, so a run with the same seed and the same sequence of
+//! operations fails at the same places. Injected I2C failures look like a NACK from the
+//! module (EREMOTEIO) and go through I2C recording like real ones; injected timeouts end the
+//! wait before polling the module. Without the feature nothing is ever injected.
+
+use crate::error::RadarError;
+use crate::timeouts::TimeoutPhase;
+
+/// Place a failure can be injected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    I2cRead,
+    I2cWrite,
+    CalibrationTimeout,
+    MeasurementTimeout,
+}
+
+impl Fault {
+    const ALL: [Fault; 4] = [
+        Fault::I2cRead,
+        Fault::I2cWrite,
+        Fault::CalibrationTimeout,
+        Fault::MeasurementTimeout,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Fault::I2cRead => "i2c-read",
+            Fault::I2cWrite => "i2c-write",
+            Fault::CalibrationTimeout => "calibration-timeout",
+            Fault::MeasurementTimeout => "measurement-timeout",
+        }
+    }
+
+    /// Injected timeout for waits limited by `phase`, if any
+    pub fn timeout(phase: TimeoutPhase) -> Option<Fault> {
+        match phase {
+            TimeoutPhase::Calibration => Some(Fault::CalibrationTimeout),
+            TimeoutPhase::Measurement => Some(Fault::MeasurementTimeout),
+            _ => None,
+        }
+    }
+}
+
+/// Failure rates by injection point, from `--inject-failure`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultPlan(Vec<(Fault, f64)>);
+
+/// Parse `point:rate[,point:rate...]` with rates between 0 and 1
+pub fn parse_plan(s: &str) -> Result<FaultPlan, String> {
+    let known = || {
+        Fault::ALL
+            .iter()
+            .map(|fault| fault.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    s.split(',')
+        .map(|entry| {
+            let (name, rate) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("'{entry}' is not <point>:<rate>"))?;
+            let fault = Fault::ALL
+                .into_iter()
+                .find(|fault| fault.name() == name.trim())
+                .ok_or_else(|| format!("unknown injection point '{name}' (known: {})", known()))?;
+            let rate: f64 = rate
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid rate '{rate}' for {name}"))?;
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(format!(
+                    "rate for {name} must be between 0 and 1 (got {rate})"
+                ));
+            }
+            Ok((fault, rate))
+        })
+        .collect::<Result<_, _>>()
+        .map(FaultPlan)
+}
+
+/// I2C error reported for an injected bus failure
+pub fn i2c_error() -> RadarError {
+    use linux_embedded_hal::i2cdev::linux::LinuxI2CError;
+    RadarError::I2c(LinuxI2CError::Errno(libc::EREMOTEIO).into())
+}
+
+/// Whether to fail at `fault` this time
+#[cfg(not(feature = "fault-injection"))]
+pub fn inject(_fault: Fault) -> bool {
+    false
+}
+
+/// xorshift64* generator, reproducible from its seed
+#[cfg(feature = "fault-injection")]
+struct Injector {
+    plan: FaultPlan,
+    state: u64,
+}
+
+#[cfg(feature = "fault-injection")]
+impl Injector {
+    fn new(plan: FaultPlan, seed: u64) -> Self {
+        Self {
+            plan,
+            // Zero is a fixed point of xorshift
+            state: seed.max(1),
+        }
+    }
+
+    /// Uniform draw in [0, 1)
+    fn next(&mut self) -> f64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let bits = self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 11;
+        bits as f64 / (1u64 << 53) as f64
+    }
+
+    fn fails(&mut self, fault: Fault) -> bool {
+        let Some(&(_, rate)) = self.plan.0.iter().find(|(point, _)| *point == fault) else {
+            return false;
+        };
+        self.next() < rate
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+static INJECTOR: std::sync::Mutex<Option<Injector>> = std::sync::Mutex::new(None);
+
+/// Inject failures per `plan` for the rest of the run
+#[cfg(feature = "fault-injection")]
+pub fn init(plan: FaultPlan, seed: u64) {
+    let points: Vec<String> = plan
+        .0
+        .iter()
+        .map(|(fault, rate)| format!("{}:{rate}", fault.name()))
+        .collect();
+    log::warn!("💉 Injecting failures: {} (seed {seed})", points.join(", "));
+    *INJECTOR
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(Injector::new(plan, seed));
+}
+
+/// Whether to fail at `fault` this time
+#[cfg(feature = "fault-injection")]
+pub fn inject(fault: Fault) -> bool {
+    let fails = INJECTOR
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .as_mut()
+        .is_some_and(|injector| injector.fails(fault));
+    if fails {
+        log::warn!("💉 Injected {} failure", fault.name());
+    }
+    fails
+}
+
+#[cfg(all(test, feature = "fault-injection"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_parsing_and_seeded_injection() {
+        let plan = parse_plan("i2c-read:0.25, calibration-timeout:0").unwrap();
+        assert_eq!(
+            plan,
+            FaultPlan(vec![
+                (Fault::I2cRead, 0.25),
+                (Fault::CalibrationTimeout, 0.0)
+            ])
+        );
+        assert!(parse_plan("i2c-read").is_err());
+        assert!(parse_plan("bus-fire:0.1").is_err());
+        assert!(parse_plan("i2c-write:1.5").is_err());
+
+        let draws = |seed| {
+            let mut injector = Injector::new(plan.clone(), seed);
+            (0..1000)
+                .map(|_| injector.fails(Fault::I2cRead))
+                .collect::<Vec<_>>()
+        };
+        let first = draws(7);
+        assert_eq!(first, draws(7));
+        assert_ne!(first, draws(8));
+        let failures = first.iter().filter(|fails| **fails).count();
+        assert!((200..300).contains(&failures), "{failures} failures");
+
+        let mut injector = Injector::new(plan, 7);
+        assert!(!(0..1000).any(|_| injector.fails(Fault::CalibrationTimeout)));
+        assert!(!injector.fails(Fault::I2cWrite));
+    }
+}
@@ -1,6 +1,7 @@
 // FIFO Writer Implementation
 // Based on spi-lib pattern: open-write-close with O_NONBLOCK
 
+use crate::privacy;
 use libc::{EEXIST, O_NONBLOCK, O_WRONLY};
 use log::{debug, error, info};
 use std::ffi::CString;
@@ -10,6 +11,8 @@ pub struct FifoWriter {
     path: CString,
     interval_secs: f32,
     last_write: Option<Instant>,
+    /// Bucket size for anonymized records (`--privacy`)
+    privacy_bucket: Option<f32>,
 }
 
 impl FifoWriter {
@@ -41,11 +44,51 @@ impl FifoWriter {
             path: path_cstring,
             interval_secs,
             last_write: None,
+            privacy_bucket: None,
         })
     }
 
+    /// Anonymize every record written, with distances quantized to `bucket_m`
+    pub fn with_privacy(mut self, bucket_m: Option<f32>) -> Self {
+        self.privacy_bucket = bucket_m;
+        self
+    }
+
+    /// `distance` as it may leave the device
+    pub fn distance(&self, distance: f32) -> f32 {
+        self.privacy_bucket.map_or(distance, |bucket| {
+            privacy::quantize_distance(distance, bucket)
+        })
+    }
+
+    /// `json_data` as it may leave the device
+    fn record(&self, json_data: &serde_json::Value) -> String {
+        match self.privacy_bucket {
+            Some(bucket) => {
+                let mut json_data = json_data.clone();
+                privacy::anonymize_json(&mut json_data, bucket);
+                format!("{json_data}\n")
+            }
+            None => format!("{json_data}\n"),
+        }
+    }
+
     /// Write data using spi-lib pattern: open-write-close with `O_NONBLOCK`
     pub fn write_data(&self, data: &str) -> Result<(), std::io::Error> {
+        self.deliver(data).map(|_| ())
+    }
+
+    /// True if the FIFO gets every record rather than one per interval
+    pub fn is_realtime(&self) -> bool {
+        self.interval_secs <= 0.0
+    }
+
+    /// Write a JSON record, returning whether a reader was connected to receive it
+    pub fn deliver_json(&self, json_data: &serde_json::Value) -> Result<bool, std::io::Error> {
+        self.deliver(&self.record(json_data))
+    }
+
+    fn deliver(&self, data: &str) -> Result<bool, std::io::Error> {
         unsafe {
             // CRITICAL: Same pattern as spi-lib - O_WRONLY | O_NONBLOCK
             let fd = libc::open(self.path.as_ptr(), O_WRONLY | O_NONBLOCK);
@@ -64,26 +107,35 @@ impl FifoWriter {
                     return Err(std::io::Error::last_os_error());
                 }
 
+                // Records are written in a single write(), which the kernel keeps atomic
+                // up to PIPE_BUF bytes; never leave a torn record for the reader unnoticed
+                #[allow(clippy::cast_sign_loss)] // Checked non-negative above
+                if (written as usize) < data_bytes.len() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::WriteZero,
+                        format!("Partial FIFO write: {written}/{} bytes", data_bytes.len()),
+                    ));
+                }
+
                 debug!("FIFO write successful: {written} bytes");
-                Ok(())
+                Ok(true)
             } else {
                 // No reader connected - this is normal, don't treat as error
                 // (spi-lib silently continues in this case)
                 debug!("FIFO write skipped: no reader connected");
-                Ok(())
+                Ok(false)
             }
         }
     }
 
     /// Write JSON data (enhanced format)
     pub fn write_json(&self, json_data: &serde_json::Value) -> Result<(), std::io::Error> {
-        let json_string = format!("{json_data}\n");
-        self.write_data(&json_string)
+        self.write_data(&self.record(json_data))
     }
 
     /// Write simple format for BGT60TR13C compatibility
     pub fn write_simple(&self, presence_state: i32, distance: f32) -> Result<(), std::io::Error> {
-        let simple_data = format!("{presence_state} {distance:.2}\n");
+        let simple_data = format!("{presence_state} {:.2}\n", self.distance(distance));
         self.write_data(&simple_data)
     }
 
@@ -1,9 +1,25 @@
+//! Firmware identification, flashing and verification
+//!
+//! [`FirmwareManager`] puts the module into its STM32 bootloader through the reset and BOOT0
+//! lines, writes one of the reference applications with read-back verification and checks
+//! the application ID once the module is back in run mode.
+
+use crate::bootloader::Bootloader;
 use crate::error::{RadarError, Result};
+use crate::flash_state::{FlashRecord, FlashState};
+use crate::gpio::{XM125GpioController, XM125GpioPins};
+use crate::timeline::{self, ResetMode};
+use crate::timeouts::{TimeoutPhase, Timeouts};
 use clap::ValueEnum;
 use log::{debug, info, warn};
-use std::path::Path;
-use std::process::Command;
-use std::time::Duration;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 /// Firmware types supported by XM125
 #[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
@@ -16,11 +32,7 @@ pub enum FirmwareType {
 impl FirmwareType {
     /// Get the application ID expected for this firmware type
     pub fn application_id(self) -> u32 {
-        match self {
-            FirmwareType::Distance => 1,
-            FirmwareType::Presence => 2,
-            FirmwareType::Breathing => 3,
-        }
+        xm125_protocol::Application::from(self).app_id()
     }
 
     /// Get the firmware binary filename
@@ -41,37 +53,378 @@ impl FirmwareType {
         }
     }
 
+    /// Name used on the command line (`firmware update <name>`, measurement subcommand)
+    pub fn cli_name(self) -> &'static str {
+        match self {
+            FirmwareType::Distance => "distance",
+            FirmwareType::Presence => "presence",
+            FirmwareType::Breathing => "breathing",
+        }
+    }
+
     /// Convert application ID to firmware type
     #[allow(clippy::match_same_arms)] // Default fallback is intentional
     pub fn from_app_id(app_id: u32) -> Self {
-        match app_id {
-            1 => FirmwareType::Distance,
-            2 => FirmwareType::Presence,
-            3 => FirmwareType::Breathing,
-            _ => FirmwareType::Distance, // Default fallback
+        xm125_protocol::Application::from_app_id(app_id).map_or(FirmwareType::Distance, Self::from)
+        // Default fallback
+    }
+}
+
+/// STM32 flash base address the XM125 application is programmed at
+pub const FLASH_BASE_ADDRESS: u32 = 0x0800_0000;
+
+/// I2C bus the XM125 is flashed over
+const FLASH_I2C_DEVICE: &str = "/dev/i2c-2";
+/// I2C address of the STM32 system bootloader
+pub const BOOTLOADER_I2C_ADDRESS: u16 = 0x48;
+/// Reset sequences to try before giving up on bootloader entry
+const BOOTLOADER_ENTRY_ATTEMPTS: u32 = 3;
+/// Probes per attempt while the bootloader starts up
+const BOOTLOADER_PROBE_RETRIES: u32 = 10;
+/// Delay between bootloader probes
+const BOOTLOADER_PROBE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Link to the STM32 system bootloader
+///
+/// The I2C bootloader is driven natively (`bootloader` module); UART flashing runs
+/// stm32flash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FlashTransport {
+    /// I2C bootloader at 0x48 on the module's I2C bus
+    #[default]
+    I2c,
+    /// UART bootloader on a serial port (8E1, autobaud sync at `baud`)
+    Uart { device: String, baud: u32 },
+}
+
+impl FlashTransport {
+    /// stm32flash arguments selecting this link, device last
+    pub fn stm32flash_args(&self) -> Vec<String> {
+        match self {
+            FlashTransport::I2c => vec![
+                "-a".to_string(),
+                format!("0x{BOOTLOADER_I2C_ADDRESS:02x}"),
+                FLASH_I2C_DEVICE.to_string(),
+            ],
+            FlashTransport::Uart { device, baud } => {
+                vec!["-b".to_string(), baud.to_string(), device.clone()]
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for FlashTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlashTransport::I2c => write!(f, "I2C {FLASH_I2C_DEVICE}"),
+            FlashTransport::Uart { device, baud } => write!(f, "UART {device} at {baud} baud"),
+        }
+    }
+}
+
+/// Result of comparing device flash (read back via the bootloader) with a firmware binary
+#[derive(Debug, Clone)]
+pub struct FlashVerification {
+    pub firmware_type: FirmwareType,
+    pub length: usize,
+    pub expected_crc: u32,
+    pub device_crc: u32,
+    /// Offset of the first differing byte, if any
+    pub first_mismatch: Option<usize>,
+}
+
+impl FlashVerification {
+    /// True if device flash matches the binary byte for byte
+    pub fn matches(&self) -> bool {
+        self.first_mismatch.is_none()
+    }
+}
+
+/// CRC-32 (IEEE 802.3, as used by zlib and `crc32`)
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
         }
     }
+    !crc
+}
+
+/// Location of each firmware binary: `<dir>/<standard filename>` unless overridden per type
+#[derive(Debug, Clone, Default)]
+pub struct FirmwarePaths {
+    dir: String,
+    overrides: Vec<(FirmwareType, String)>,
+}
+
+impl FirmwarePaths {
+    /// Binaries under `dir` with their standard filenames
+    pub fn new(dir: &str) -> Self {
+        Self {
+            dir: dir.to_string(),
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Use `path` for `firmware_type` instead of the directory convention
+    pub fn with_override(mut self, firmware_type: FirmwareType, path: Option<&str>) -> Self {
+        if let Some(path) = path {
+            self.overrides.push((firmware_type, path.to_string()));
+        }
+        self
+    }
+
+    /// Firmware directory (holds the staging manifest)
+    pub fn dir(&self) -> &str {
+        &self.dir
+    }
+
+    /// True if `firmware_type` comes from an explicit path rather than the directory
+    pub fn is_overridden(&self, firmware_type: FirmwareType) -> bool {
+        self.overrides.iter().any(|(fw, _)| *fw == firmware_type)
+    }
+
+    /// Path of the binary for `firmware_type`
+    pub fn binary(&self, firmware_type: FirmwareType) -> PathBuf {
+        match self.overrides.iter().find(|(fw, _)| *fw == firmware_type) {
+            Some((_, path)) => PathBuf::from(path),
+            None => Path::new(&self.dir).join(firmware_type.binary_filename()),
+        }
+    }
+
+    /// Fail early, naming every missing binary, before a command that needs them starts
+    pub fn ensure_exist(&self, firmware_types: &[FirmwareType]) -> Result<()> {
+        let missing: Vec<String> = firmware_types
+            .iter()
+            .map(|&fw| (fw, self.binary(fw)))
+            .filter(|(_, path)| !path.is_file())
+            .map(|(fw, path)| format!("{} ({})", path.display(), fw.cli_name()))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(RadarError::FirmwareError {
+            message: format!(
+                "Firmware binary not found: {} (set --firmware-path or --firmware-<type>)",
+                missing.join(", ")
+            ),
+        })
+    }
 }
 
 /// XM125 Firmware Manager
 pub struct FirmwareManager {
-    firmware_path: String,
+    paths: FirmwarePaths,
     control_script: String,
     i2c_address: u16,
+    flash_timeout: Duration,
+    transport: FlashTransport,
+    /// Where to record the digests of each flashed image
+    state_file: Option<String>,
+    /// Bus of the module, for I2C flashing and run mode checks
+    i2c_device: String,
+    /// Control pins to reset through instead of the control script's defaults
+    gpio_pins: Option<XM125GpioPins>,
+}
+
+/// Run a command to completion, killing it after `timeout`
+///
+/// Returns `Ok(None)` on timeout. Output is drained on helper threads so a chatty child
+/// (stm32flash progress) cannot stall on a full pipe while we wait.
+pub fn run_with_timeout(
+    command: &mut Command,
+    timeout: Duration,
+) -> std::io::Result<Option<Output>> {
+    fn drain(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = pipe.read_to_end(&mut buffer);
+            buffer
+        })
+    }
+
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().map(drain);
+    let stderr = child.stderr.take().map(drain);
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    let collect = |handle: Option<JoinHandle<Vec<u8>>>| {
+        handle
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default()
+    };
+    Ok(Some(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    }))
+}
+
+/// Size, MD5 and SHA-256 of a firmware image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirmwareDigest {
+    pub size: u64,
+    pub md5: String,
+    pub sha256: String,
+}
+
+/// Copy `source` into `dest` while hashing it, so the digest covers exactly the bytes written
+fn copy_hashed(source: &Path, dest: &mut impl Write) -> std::io::Result<FirmwareDigest> {
+    let mut file = std::fs::File::open(source)?;
+    let mut md5 = Md5::new();
+    let mut sha256 = Sha256::new();
+    let mut size = 0u64;
+    let mut chunk = [0u8; 4096];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        md5.update(&chunk[..read]);
+        sha256.update(&chunk[..read]);
+        size += read as u64;
+        dest.write_all(&chunk[..read])?;
+    }
+    dest.flush()?;
+    Ok(FirmwareDigest {
+        size,
+        md5: format!("{:x}", md5.finalize()),
+        sha256: format!("{:x}", sha256.finalize()),
+    })
+}
+
+/// Error for a flash write, readback or erase that exceeded the firmware flash timeout
+pub fn flash_timeout_error(timeout: Duration) -> RadarError {
+    RadarError::Timeout {
+        phase: TimeoutPhase::FirmwareFlash,
+        timeout,
+    }
+}
+
+/// Probe `address` until it ACKs or the bootloader probe retries run out
+fn wait_for_address(device_path: &str, address: u16) -> Result<bool> {
+    for _ in 0..BOOTLOADER_PROBE_RETRIES {
+        if crate::i2c::probe_address(device_path, address)? {
+            return Ok(true);
+        }
+        std::thread::sleep(BOOTLOADER_PROBE_INTERVAL);
+    }
+    Ok(false)
+}
+
+/// Progress callback logging `action` every 10%
+fn log_progress(action: &'static str) -> impl FnMut(usize, usize) {
+    let mut last_decile = None;
+    move |done, total| {
+        let decile = (done * 10).checked_div(total).unwrap_or(10);
+        if last_decile != Some(decile) {
+            last_decile = Some(decile);
+            info!("{action}: {}% ({done}/{total} bytes)", decile * 10);
+        }
+    }
+}
+
+/// Offset of the first byte where `device` differs from `expected` or ends early
+fn first_mismatch(expected: &[u8], device: &[u8]) -> Option<usize> {
+    expected
+        .iter()
+        .zip(device)
+        .position(|(a, b)| a != b)
+        .or_else(|| (device.len() < expected.len()).then_some(device.len()))
+}
+
+/// Mass erase the flash of a module that is already in bootloader mode
+pub fn erase_flash(transport: &FlashTransport, timeout: Duration) -> Result<()> {
+    if *transport == FlashTransport::I2c {
+        let mut bootloader = Bootloader::open(FLASH_I2C_DEVICE, BOOTLOADER_I2C_ADDRESS, timeout)?;
+        return bootloader.mass_erase();
+    }
+
+    let output = run_with_timeout(
+        Command::new("stm32flash")
+            .arg("-o") // Erase only
+            .args(transport.stm32flash_args()),
+        timeout,
+    )
+    .map_err(|e| RadarError::DeviceError {
+        message: format!("Failed to execute stm32flash: {e}"),
+    })?
+    .ok_or_else(|| flash_timeout_error(timeout))?;
+
+    if !output.status.success() {
+        return Err(RadarError::DeviceError {
+            message: format!(
+                "Chip erase failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        });
+    }
+    Ok(())
 }
 
 impl FirmwareManager {
     /// Create new firmware manager
-    pub fn new(firmware_path: &str, control_script: &str, i2c_address: u16) -> Self {
+    pub fn new(paths: FirmwarePaths, control_script: &str, i2c_address: u16) -> Self {
         Self {
-            firmware_path: firmware_path.to_string(),
+            paths,
             control_script: control_script.to_string(),
             i2c_address,
+            flash_timeout: Timeouts::default().firmware_flash,
+            transport: FlashTransport::default(),
+            state_file: None,
+            i2c_device: FLASH_I2C_DEVICE.to_string(),
+            gpio_pins: None,
         }
     }
 
+    /// Flash the module on `i2c_device`, resetting it through `pins` instead of the control script
+    pub fn for_device(mut self, i2c_device: &str, pins: XM125GpioPins) -> Self {
+        self.i2c_device = i2c_device.to_string();
+        self.gpio_pins = Some(pins);
+        self
+    }
+
+    /// Record the digests of flashed images in `path`
+    pub fn with_state_file(mut self, path: &str) -> Self {
+        self.state_file = Some(path.to_string());
+        self
+    }
+
+    /// Limit each flash write, readback and erase to `timeout`
+    pub fn with_flash_timeout(mut self, timeout: Duration) -> Self {
+        self.flash_timeout = timeout;
+        self
+    }
+
+    /// Talk to the bootloader over `transport` instead of I2C
+    pub fn with_transport(mut self, transport: FlashTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
     /// Check if the control script exists and is accessible
     pub fn check_control_script(&self) -> Result<()> {
+        if self.gpio_pins.is_some() {
+            return Ok(());
+        }
         let path = std::path::Path::new(&self.control_script);
 
         if !path.exists() {
@@ -119,11 +472,10 @@ impl FirmwareManager {
         firmware_type: FirmwareType,
         verify: bool,
     ) -> Result<()> {
-        let binary_filename = firmware_type.binary_filename();
-        let binary_path = format!("{}/{binary_filename}", self.firmware_path);
+        let binary_path = self.get_firmware_path(firmware_type);
 
         info!(
-            "Updating XM125 firmware to {} ({binary_filename})",
+            "Updating XM125 firmware to {} ({binary_path})",
             firmware_type.display_name()
         );
 
@@ -131,17 +483,29 @@ impl FirmwareManager {
         self.check_control_script()?;
 
         // Verify firmware binary exists
-        if !Path::new(&binary_path).exists() {
-            return Err(RadarError::DeviceError {
-                message: format!("Firmware binary not found: {binary_path}"),
-            });
+        self.paths.ensure_exist(&[firmware_type])?;
+
+        // Refuse to start an update with a binary that fails the staging manifest, which
+        // only describes the standard binaries in the firmware directory
+        if !self.paths.is_overridden(firmware_type) {
+            crate::manifest::ensure_binary_intact(self.paths.dir(), firmware_type)?;
         }
 
-        // Step 1: Put device into bootloader mode
-        self.enter_bootloader_mode()?;
+        // Stage the image, hashing it as it is copied; the staged copy is what gets programmed
+        let (staged_path, digest) = self.stage_binary(&binary_path)?;
+        info!(
+            "Firmware image: {} bytes, SHA256 {}",
+            digest.size, digest.sha256
+        );
 
-        // Step 2: Flash firmware using stm32flash
-        self.flash_firmware(&binary_path)?;
+        // Step 1: Put device into bootloader mode
+        // Step 2: Flash firmware through the bootloader
+        let flashed = self
+            .enter_bootloader_mode()
+            .and_then(|()| self.flash_firmware(&staged_path.to_string_lossy()));
+        let _ = std::fs::remove_file(&staged_path);
+        flashed?;
+        self.record_flash(firmware_type, &binary_path, digest);
 
         // Step 3: Reset to run mode (includes verification and timing)
         self.reset_to_run_mode().await?;
@@ -163,10 +527,52 @@ impl FirmwareManager {
         Ok(())
     }
 
-    /// Put XM125 into bootloader mode
+    /// Put XM125 into bootloader mode and confirm the bootloader answers on I2C
+    ///
+    /// A missed BOOT0 setup otherwise only surfaces later as an obscure flashing
+    /// failure, so the reset sequence is repeated until the bootloader ACKs at 0x48.
+    ///
+    /// Over UART there is nothing to probe without consuming the bootloader's one-time
+    /// autobaud sync, so the module is reset once and stm32flash performs the sync itself.
     fn enter_bootloader_mode(&self) -> Result<()> {
-        info!("Entering XM125 bootloader mode...");
+        if let FlashTransport::Uart { device, .. } = &self.transport {
+            info!("Entering XM125 bootloader mode for UART flashing on {device}...");
+            self.run_bootloader_reset()?;
+            std::thread::sleep(BOOTLOADER_PROBE_INTERVAL);
+            return Ok(());
+        }
+        for attempt in 1..=BOOTLOADER_ENTRY_ATTEMPTS {
+            info!(
+                "Entering XM125 bootloader mode (attempt {attempt}/{BOOTLOADER_ENTRY_ATTEMPTS})..."
+            );
+            self.run_bootloader_reset()?;
 
+            if wait_for_address(&self.i2c_device, BOOTLOADER_I2C_ADDRESS)? {
+                info!("Bootloader responding at 0x{BOOTLOADER_I2C_ADDRESS:02X}");
+                return Ok(());
+            }
+            warn!(
+                "Bootloader not responding at 0x{BOOTLOADER_I2C_ADDRESS:02X} after reset, retrying"
+            );
+        }
+
+        Err(RadarError::FirmwareError {
+            message: format!(
+                "XM125 bootloader did not respond at 0x{BOOTLOADER_I2C_ADDRESS:02X} on {} \
+                after {BOOTLOADER_ENTRY_ATTEMPTS} reset attempts.\n\
+                Check the BOOT0 and reset GPIO wiring and that {} --reset-bootloader works.",
+                self.i2c_device, self.control_script
+            ),
+        })
+    }
+
+    /// Drive BOOT0 high and reset the module via the control script
+    fn run_bootloader_reset(&self) -> Result<()> {
+        if let Some(pins) = self.gpio_pins {
+            let mut gpio = XM125GpioController::with_pins(pins);
+            gpio.attach()?;
+            return gpio.reset_to_bootloader_mode();
+        }
         let output = Command::new(&self.control_script)
             .arg("--reset-bootloader")
             .output()
@@ -183,15 +589,95 @@ impl FirmwareManager {
 
         let stdout = String::from_utf8_lossy(&output.stdout);
         debug!("XM125 bootloader mode output: {stdout}");
+        timeline::reset(ResetMode::Bootloader);
         Ok(())
     }
 
-    /// Flash firmware using stm32flash
+    /// Copy a firmware binary to a private staging file, computing its digests on the way
     #[allow(clippy::unused_self)] // Self needed for future enhancements
+    fn stage_binary(&self, binary_path: &str) -> Result<(PathBuf, FirmwareDigest)> {
+        // Modules on different buses may be flashed at the same time
+        static STAGED: AtomicU32 = AtomicU32::new(0);
+        let staged_path = std::env::temp_dir().join(format!(
+            "xm125-flash-stage-{}-{}.bin",
+            std::process::id(),
+            STAGED.fetch_add(1, Ordering::Relaxed)
+        ));
+        let staged = std::fs::File::create(&staged_path).and_then(|mut file| {
+            let digest = copy_hashed(Path::new(binary_path), &mut file)?;
+            file.sync_all()?;
+            Ok(digest)
+        });
+        match staged {
+            Ok(digest) => Ok((staged_path, digest)),
+            Err(e) => {
+                let _ = std::fs::remove_file(&staged_path);
+                Err(RadarError::FirmwareError {
+                    message: format!("Failed to stage firmware binary {binary_path}: {e}"),
+                })
+            }
+        }
+    }
+
+    /// Store the digests of a successfully written image in the state file
+    ///
+    /// The flash itself has succeeded at this point, so failing to save is only logged.
+    fn record_flash(&self, firmware_type: FirmwareType, binary_path: &str, digest: FirmwareDigest) {
+        let Some(path) = self.state_file.as_deref() else {
+            return;
+        };
+        let state = FlashState {
+            last_flash: Some(FlashRecord::new(firmware_type, binary_path, digest)),
+        };
+        if let Err(e) = state.save(path) {
+            warn!("Failed to record flashed firmware in {path}: {e}");
+        }
+    }
+
+    /// Erase, program, read back and verify the image, then reset out of the bootloader
     fn flash_firmware(&self, binary_path: &str) -> Result<()> {
-        info!("Flashing firmware: {binary_path}");
+        info!("Flashing firmware over {}: {binary_path}", self.transport);
+        if self.transport != FlashTransport::I2c {
+            return self.flash_with_stm32flash(binary_path);
+        }
 
-        // Use stm32flash to program the firmware via I2C
+        let image = std::fs::read(binary_path).map_err(|e| RadarError::FirmwareError {
+            message: format!("Failed to read firmware image {binary_path}: {e}"),
+        })?;
+        let mut bootloader =
+            Bootloader::open(&self.i2c_device, BOOTLOADER_I2C_ADDRESS, self.flash_timeout)?;
+        info!(
+            "Bootloader v{}.{}: erasing {} bytes at 0x{FLASH_BASE_ADDRESS:08X}",
+            bootloader.version() >> 4,
+            bootloader.version() & 0x0F,
+            image.len()
+        );
+        bootloader.erase_range(FLASH_BASE_ADDRESS, image.len())?;
+        bootloader.write(FLASH_BASE_ADDRESS, &image, log_progress("Writing flash"))?;
+        let device = bootloader.read(
+            FLASH_BASE_ADDRESS,
+            image.len(),
+            log_progress("Verifying flash"),
+        )?;
+        if let Some(offset) = first_mismatch(&image, &device) {
+            return Err(RadarError::FirmwareError {
+                message: format!(
+                    "Flash verification failed: device differs from {binary_path} at offset 0x{offset:06X}"
+                ),
+            });
+        }
+        info!("Firmware flashing completed successfully");
+
+        // Software reset reloads the option bytes and clears the "empty flash" flag (see
+        // flash_with_stm32flash); the GPIO reset in reset_to_run_mode() follows regardless
+        bootloader.reset()?;
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        Ok(())
+    }
+
+    /// Flash firmware over UART using stm32flash
+    fn flash_with_stm32flash(&self, binary_path: &str) -> Result<()> {
+        // Use stm32flash to program the firmware via the bootloader
         // -R flag performs software reset after programming
         //
         // IMPORTANT: The -R flag forces a software reset which triggers OBL_LAUNCH
@@ -210,20 +696,21 @@ impl FirmwareManager {
         // Note: Software reset via -R may not properly sample BOOT0 pin state during
         // the reset sequence, so we still perform hardware reset via GPIO after this
         // to ensure BOOT0 is sampled correctly for proper boot mode selection.
-        let output = Command::new("stm32flash")
-            .args([
-                "-w",
-                binary_path, // Write binary file
-                "-v",        // Verify after write
-                "-R", // Reset device after programming (software reset - triggers OBL_LAUNCH)
-                "-a",
-                "0x48",       // I2C bus address (bootloader mode)
-                "/dev/i2c-2", // I2C device
-            ])
-            .output()
-            .map_err(|e| RadarError::DeviceError {
-                message: format!("Failed to execute stm32flash: {e}"),
-            })?;
+        let output = run_with_timeout(
+            Command::new("stm32flash")
+                .args([
+                    "-w",
+                    binary_path, // Write binary file
+                    "-v",        // Verify after write
+                    "-R", // Reset device after programming (software reset - triggers OBL_LAUNCH)
+                ])
+                .args(self.transport.stm32flash_args()), // Bootloader link and device
+            self.flash_timeout,
+        )
+        .map_err(|e| RadarError::DeviceError {
+            message: format!("Failed to execute stm32flash: {e}"),
+        })?
+        .ok_or_else(|| flash_timeout_error(self.flash_timeout))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -262,23 +749,30 @@ impl FirmwareManager {
     pub async fn reset_to_run_mode(&self) -> Result<()> {
         info!("Resetting XM125 to run mode...");
 
-        let output = Command::new(&self.control_script)
-            .arg("--reset-run")
-            .output()
-            .map_err(|e| RadarError::DeviceError {
-                message: format!("Failed to execute control script: {e}"),
-            })?;
+        if let Some(pins) = self.gpio_pins {
+            let mut gpio = XM125GpioController::with_pins(pins);
+            gpio.attach()?;
+            gpio.reset_to_run_mode()?;
+        } else {
+            let output = Command::new(&self.control_script)
+                .arg("--reset-run")
+                .output()
+                .map_err(|e| RadarError::DeviceError {
+                    message: format!("Failed to execute control script: {e}"),
+                })?;
+
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(RadarError::DeviceError {
+                    message: format!("Failed to reset to run mode: {stderr}"),
+                });
+            }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(RadarError::DeviceError {
-                message: format!("Failed to reset to run mode: {stderr}"),
-            });
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            debug!("XM125 run mode output: {stdout}");
+            timeline::reset(ResetMode::Run);
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        debug!("XM125 run mode output: {stdout}");
-
         // Give the device time to fully initialize in run mode
         // Hardware reset samples BOOT0 pin, so device should boot from flash
         tokio::time::sleep(Duration::from_millis(1500)).await;
@@ -291,22 +785,9 @@ impl FirmwareManager {
         Ok(())
     }
 
-    /// Verify device is in run mode by checking I2C bus
-    #[allow(clippy::unused_self)] // May use self for future enhancements
+    /// Verify device is in run mode by checking it ACKs at the run mode address
     fn verify_device_in_run_mode(&self) -> bool {
-        use std::process::Command;
-
-        // Check if device is present at run mode address (0x52)
-        let output = Command::new("i2cdetect").args(["-y", "2"]).output();
-
-        match output {
-            Ok(output) if output.status.success() => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                // Look for address 52 (hex) in the i2cdetect output
-                stdout.contains(" 52 ")
-            }
-            _ => false,
-        }
+        crate::i2c::probe_address(&self.i2c_device, self.i2c_address).unwrap_or(false)
     }
 
     /// Verify firmware was flashed correctly
@@ -317,9 +798,9 @@ impl FirmwareManager {
         tokio::time::sleep(Duration::from_millis(1000)).await;
 
         // Create a temporary radar instance to read the application ID
-        let i2c_device_path = "/dev/i2c-2".to_string();
-        let i2c_device = crate::i2c::I2cDevice::new(&i2c_device_path, self.i2c_address)?;
-        let gpio_pins = crate::gpio::XM125GpioPins::default(); // Use default pins for firmware operations
+        let i2c_device = crate::i2c::I2cDevice::new(&self.i2c_device, self.i2c_address)?;
+        // The control script drives the default pins
+        let gpio_pins = self.gpio_pins.unwrap_or_default();
         let mut radar = crate::radar::XM125Radar::new(i2c_device, gpio_pins);
 
         // Try to connect and read application ID using our radar interface
@@ -350,8 +831,7 @@ impl FirmwareManager {
 
     /// Get full path to firmware binary
     fn get_firmware_path(&self, firmware_type: FirmwareType) -> String {
-        let binary_filename = firmware_type.binary_filename();
-        format!("{}/{}", self.firmware_path, binary_filename)
+        self.paths.binary(firmware_type).display().to_string()
     }
 
     /// Get MD5 checksum of currently flashed firmware
@@ -390,32 +870,97 @@ impl FirmwareManager {
         })
     }
 
+    /// Size, MD5 and SHA256 of a firmware binary file
+    pub fn binary_digest(&self, firmware_type: FirmwareType) -> Result<FirmwareDigest> {
+        let binary_path = self.get_firmware_path(firmware_type);
+        copy_hashed(Path::new(&binary_path), &mut std::io::sink()).map_err(|e| {
+            RadarError::DeviceError {
+                message: format!("Failed to hash firmware binary {binary_path}: {e}"),
+            }
+        })
+    }
+
     /// Calculate MD5 checksum of a firmware binary file
     pub fn calculate_binary_checksum(&self, firmware_type: FirmwareType) -> Result<String> {
-        let binary_filename = firmware_type.binary_filename();
-        let binary_path = format!("{}/{binary_filename}", self.firmware_path);
+        Ok(self.binary_digest(firmware_type)?.md5)
+    }
 
-        let output = Command::new("md5sum")
-            .arg(&binary_path)
-            .output()
-            .map_err(|e| RadarError::DeviceError {
-                message: format!("Failed to calculate MD5: {e}"),
-            })?;
+    /// Verify device flash against a firmware binary by reading it back via the bootloader
+    ///
+    /// Unlike the application ID check this detects bit-rot and partial flashes. The device
+    /// is left in run mode afterwards, even if the readback fails.
+    pub async fn verify_flash_contents(
+        &self,
+        firmware_type: FirmwareType,
+    ) -> Result<FlashVerification> {
+        self.check_control_script()?;
+
+        let binary_path = self.get_firmware_path(firmware_type);
+        let expected = std::fs::read(&binary_path).map_err(|e| RadarError::FirmwareError {
+            message: format!("Failed to read firmware binary {binary_path}: {e}"),
+        })?;
+
+        info!(
+            "Reading back {} bytes of device flash to verify against {binary_path}",
+            expected.len()
+        );
+        self.enter_bootloader_mode()?;
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+
+        let readback = self.read_flash(expected.len());
+        self.reset_to_run_mode().await?;
+        let device = readback?;
+
+        Ok(FlashVerification {
+            firmware_type,
+            length: expected.len(),
+            expected_crc: crc32(&expected),
+            device_crc: crc32(&device),
+            first_mismatch: first_mismatch(&expected, &device),
+        })
+    }
+
+    /// Read `length` bytes of flash from the application base address
+    fn read_flash(&self, length: usize) -> Result<Vec<u8>> {
+        if self.transport == FlashTransport::I2c {
+            let mut bootloader =
+                Bootloader::open(&self.i2c_device, BOOTLOADER_I2C_ADDRESS, self.flash_timeout)?;
+            return bootloader.read(FLASH_BASE_ADDRESS, length, log_progress("Reading flash"));
+        }
+
+        let readback_path =
+            std::env::temp_dir().join(format!("xm125-flash-{}.bin", std::process::id()));
+        let region = format!("0x{FLASH_BASE_ADDRESS:08X}:{length}");
+
+        let output = run_with_timeout(
+            Command::new("stm32flash")
+                .args(["-S", &region, "-r"])
+                .arg(&readback_path)
+                .args(self.transport.stm32flash_args()),
+            self.flash_timeout,
+        )
+        .map_err(|e| RadarError::FirmwareError {
+            message: format!("Failed to execute stm32flash for readback: {e}"),
+        })?
+        .ok_or_else(|| flash_timeout_error(self.flash_timeout))?;
 
         if !output.status.success() {
-            return Err(RadarError::DeviceError {
-                message: "Failed to calculate binary MD5 checksum".to_string(),
+            let _ = std::fs::remove_file(&readback_path);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(RadarError::FirmwareError {
+                message: format!("Flash readback failed: {stderr}"),
             });
         }
+        debug!(
+            "stm32flash readback output: {}",
+            String::from_utf8_lossy(&output.stdout)
+        );
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if let Some(checksum) = stdout.split_whitespace().next() {
-            Ok(checksum.to_string())
-        } else {
-            Err(RadarError::DeviceError {
-                message: "Could not parse MD5 checksum output".to_string(),
-            })
-        }
+        let data = std::fs::read(&readback_path);
+        let _ = std::fs::remove_file(&readback_path);
+        data.map_err(|e| RadarError::FirmwareError {
+            message: format!("Failed to read flash readback file: {e}"),
+        })
     }
 
     /// Check if firmware update is needed
@@ -434,6 +979,25 @@ impl FirmwareManager {
             return Ok(true);
         }
 
+        // The state file records what was last written, so no device checksum pass is needed
+        if let Some(record) = self
+            .state_file
+            .as_deref()
+            .and_then(|path| FlashState::load(path).ok())
+            .and_then(|state| state.last_flash)
+        {
+            let digest = self.binary_digest(desired_type)?;
+            if record.matches(desired_type, &digest) {
+                info!("Firmware matches last flash record - no update needed");
+                return Ok(false);
+            }
+            info!(
+                "Firmware differs from last flash record - update needed\nFlashed: {}\nBinary:  {}",
+                record.sha256, digest.sha256
+            );
+            return Ok(true);
+        }
+
         // Optionally verify checksum for additional validation
         if let Ok(device_checksum) = self.get_firmware_checksum(desired_type) {
             if let Ok(binary_checksum) = self.calculate_binary_checksum(desired_type) {
@@ -470,29 +1034,9 @@ impl FirmwareManager {
         // Step 2: Wait for bootloader to be ready
         tokio::time::sleep(Duration::from_millis(1000)).await;
 
-        // Step 3: Erase chip using stm32flash
-        info!("Step 2: Erasing chip using stm32flash...");
-        let output = Command::new("stm32flash")
-            .args([
-                "-i",
-                "rts,-dtr,dtr:-rts,dtr", // Reset sequence
-                "-E",                    // Erase command
-                "/dev/i2c-2",            // I2C device
-                "-a",
-                "0x48", // I2C address (bootloader mode)
-            ])
-            .output()
-            .map_err(|e| RadarError::DeviceError {
-                message: format!("Failed to execute stm32flash for erase: {e}"),
-            })?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            return Err(RadarError::DeviceError {
-                message: format!("Chip erase failed:\nstdout: {stdout}\nstderr: {stderr}"),
-            });
-        }
+        // Step 3: Mass erase through the bootloader
+        info!("Step 2: Erasing chip over {}...", self.transport);
+        erase_flash(&self.transport, self.flash_timeout)?;
 
         info!("✅ Chip erase completed successfully");
 
@@ -515,6 +1059,80 @@ impl FirmwareManager {
 
 impl Default for FirmwareManager {
     fn default() -> Self {
-        Self::new("/lib/firmware/acconeer", "/usr/bin/xm125-control.sh", 0x52)
+        Self::new(
+            FirmwarePaths::new("/lib/firmware/acconeer"),
+            "/usr/bin/xm125-control.sh",
+            0x52,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_hashed_digests() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("image.bin");
+        std::fs::write(&path, b"The quick brown fox jumps over the lazy dog").unwrap();
+        let mut copy = Vec::new();
+        let digest = copy_hashed(&path, &mut copy).unwrap();
+        assert_eq!(copy, b"The quick brown fox jumps over the lazy dog");
+        assert_eq!(
+            digest,
+            FirmwareDigest {
+                size: 43,
+                md5: "9e107d9d372bb6826bd81d3542a419d6".to_string(),
+                sha256: "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592"
+                    .to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn test_flash_transport_args_end_with_device() {
+        assert_eq!(
+            FlashTransport::I2c.stm32flash_args(),
+            ["-a", "0x48", "/dev/i2c-2"]
+        );
+        let uart = FlashTransport::Uart {
+            device: "/dev/ttymxc1".to_string(),
+            baud: 57600,
+        };
+        assert_eq!(uart.stm32flash_args(), ["-b", "57600", "/dev/ttymxc1"]);
+    }
+
+    #[test]
+    fn test_firmware_path_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let presence = dir.path().join("custom-presence.bin");
+        std::fs::write(&presence, b"fw").unwrap();
+        let dir_str = dir.path().to_str().unwrap();
+
+        let paths = FirmwarePaths::new(dir_str)
+            .with_override(FirmwareType::Presence, presence.to_str())
+            .with_override(FirmwareType::Breathing, None);
+        assert_eq!(paths.binary(FirmwareType::Presence), presence);
+        assert_eq!(
+            paths.binary(FirmwareType::Distance),
+            dir.path().join(FirmwareType::Distance.binary_filename())
+        );
+        assert!(paths.is_overridden(FirmwareType::Presence));
+        assert!(!paths.is_overridden(FirmwareType::Breathing));
+
+        paths.ensure_exist(&[FirmwareType::Presence]).unwrap();
+        let err = paths
+            .ensure_exist(&[FirmwareType::Distance, FirmwareType::Presence])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(FirmwareType::Distance.binary_filename()));
+        assert!(!err.contains("custom-presence.bin"));
     }
 }
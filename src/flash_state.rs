@@ -0,0 +1,84 @@
+//! Record of the last firmware flash
+//!
+//! `firmware update` hashes the image while staging it for flashing (which verifies the
+//! written flash by read-back) and stores the digests here. Later checks compare a binary
+//! against this record instead of running a separate checksum pass over the device.
+
+use crate::error::RadarError;
+use crate::firmware::{FirmwareDigest, FirmwareType};
+use crate::journal::write_atomic;
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Digests of an image written to the module
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FlashRecord {
+    /// Firmware type (`distance`, `presence` or `breathing`)
+    pub firmware_type: String,
+    /// Binary the image was staged from
+    pub file: String,
+    pub size: u64,
+    pub md5: String,
+    pub sha256: String,
+    pub flashed_at: DateTime<Utc>,
+}
+
+impl FlashRecord {
+    pub fn new(firmware_type: FirmwareType, file: &str, digest: FirmwareDigest) -> Self {
+        Self {
+            firmware_type: firmware_type.cli_name().to_string(),
+            file: file.to_string(),
+            size: digest.size,
+            md5: digest.md5,
+            sha256: digest.sha256,
+            flashed_at: Utc::now(),
+        }
+    }
+
+    /// True if this record describes `digest` flashed as `firmware_type`
+    pub fn matches(&self, firmware_type: FirmwareType, digest: &FirmwareDigest) -> bool {
+        self.firmware_type == firmware_type.cli_name()
+            && self.size == digest.size
+            && self.sha256 == digest.sha256
+    }
+}
+
+/// Persisted firmware state
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlashState {
+    pub last_flash: Option<FlashRecord>,
+}
+
+impl FlashState {
+    /// Load the state file, returning an empty state if it does not exist
+    pub fn load(path: &str) -> Result<Self, RadarError> {
+        if !Path::new(path).exists() {
+            debug!("No firmware state file at {path}");
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|e| RadarError::FirmwareError {
+            message: format!("Failed to parse firmware state file {path}: {e}"),
+        })
+    }
+
+    /// Save the state file, creating the parent directory if required
+    pub fn save(&self, path: &str) -> Result<(), RadarError> {
+        if let Some(parent) = Path::new(path).parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let contents = toml::to_string_pretty(self).map_err(|e| RadarError::FirmwareError {
+            message: format!("Failed to serialize firmware state: {e}"),
+        })?;
+        write_atomic(path, contents.as_bytes())?;
+        info!("💾 Firmware state saved to {path}");
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+//! Firmware updates across every configured module
+//!
+//! Hosts with several modules describe each one as a `[profiles.<name>]` table with its I2C
+//! bus or address and GPIO pins. `firmware update-all <type>` updates every such module in
+//! turn: it identifies the running firmware, enters the bootloader through the module's own
+//! pins, flashes with read-back verification, returns to run mode and checks the application
+//! ID. A module that fails is flashed back to the firmware it was running, so a bad update
+//! leaves the fleet as it was rather than with a dead sensor.
+//!
+//! Modules sharing a reset or BOOT0 line are reset together, and every module's bootloader
+//! answers at the same address, so two modules are never flashed at once if they share a bus
+//! or a control line. `--parallel <N>` lets up to N modules without anything in common
+//! flash at the same time; the default flashes one at a time.
+
+use crate::cli::Cli;
+use crate::device_lock::DeviceLock;
+use crate::error::RadarError;
+use crate::firmware::{FirmwareManager, FirmwareType};
+use crate::gpio::XM125GpioPins;
+use crate::i2c::I2cDevice;
+use crate::radar::XM125Radar;
+use crate::settings::Settings;
+use log::{info, warn};
+use serde::Serialize;
+
+/// Module described by a settings profile
+#[derive(Debug, Clone)]
+pub struct FleetDevice {
+    /// Profile name
+    pub name: String,
+    pub i2c_device: String,
+    pub address: u16,
+    pub pins: XM125GpioPins,
+}
+
+impl FleetDevice {
+    fn control_lines(&self) -> [u32; 2] {
+        [self.pins.reset, self.pins.boot]
+    }
+
+    /// Whether flashing `other` at the same time would disturb this module
+    fn conflicts_with(&self, other: &FleetDevice) -> bool {
+        self.i2c_device == other.i2c_device
+            || self
+                .control_lines()
+                .iter()
+                .any(|pin| other.control_lines().contains(pin))
+    }
+
+    /// Whether this module's bootloader can only be entered together with `other` on the same bus
+    fn inseparable_from(&self, other: &FleetDevice) -> bool {
+        self.i2c_device == other.i2c_device && self.control_lines() == other.control_lines()
+    }
+}
+
+/// Modules of the profiles that name an I2C bus or address, in profile order
+pub fn devices(cli: &Cli) -> Result<Vec<FleetDevice>, RadarError> {
+    let settings = Settings::load_layers(&cli.config)?;
+    let devices: Vec<FleetDevice> = settings
+        .profiles
+        .iter()
+        .filter(|(_, profile)| profile.i2c_bus.is_some() || profile.i2c_address.is_some())
+        .map(|(name, profile)| {
+            let gpio = &profile.gpio;
+            FleetDevice {
+                name: name.clone(),
+                i2c_device: profile.i2c_bus.map_or_else(
+                    || cli.get_i2c_device_path(),
+                    |bus| format!("/dev/i2c-{bus}"),
+                ),
+                address: profile.i2c_address.unwrap_or(cli.i2c_address),
+                pins: XM125GpioPins {
+                    reset: gpio.reset.unwrap_or(cli.gpio_reset),
+                    mcu_interrupt: gpio.mcu_int.unwrap_or(cli.gpio_mcu_int),
+                    wake_up: gpio.wake.unwrap_or(cli.gpio_wake),
+                    boot: gpio.boot.unwrap_or(cli.gpio_boot),
+                    signals: gpio.signals(cli.get_gpio_pins().signals),
+                },
+            }
+        })
+        .collect();
+    if devices.is_empty() {
+        return Err(RadarError::InvalidParameters(
+            "update-all needs [profiles.<name>] tables with i2c_bus or i2c_address in the --config files"
+                .to_string(),
+        ));
+    }
+    Ok(devices)
+}
+
+/// Split `devices` into rounds of at most `parallel` modules that can be flashed together
+pub fn rounds(devices: &[FleetDevice], parallel: usize) -> Vec<Vec<usize>> {
+    let mut pending: Vec<usize> = (0..devices.len()).collect();
+    let mut rounds = Vec::new();
+    while !pending.is_empty() {
+        let mut round: Vec<usize> = Vec::new();
+        pending.retain(|&candidate| {
+            let fits = round.len() < parallel.max(1)
+                && round
+                    .iter()
+                    .all(|&taken| !devices[taken].conflicts_with(&devices[candidate]));
+            if fits {
+                round.push(candidate);
+            }
+            !fits
+        });
+        rounds.push(round);
+    }
+    rounds
+}
+
+/// How the update of one module ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    /// Flashed and verified
+    Updated,
+    /// Already running the firmware (no `--force`)
+    Unchanged,
+    /// The update failed and the previous firmware was restored
+    RolledBack,
+    /// The update failed and the module could not be restored
+    Failed,
+}
+
+/// Result of one module's update
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceResult {
+    pub device: String,
+    pub i2c_device: String,
+    pub address: u16,
+    pub outcome: Outcome,
+    /// Firmware running before the update, if it could be identified
+    pub previous: Option<&'static str>,
+    pub error: Option<String>,
+}
+
+/// Firmware the module is running, if it answers
+fn running_firmware(device: &FleetDevice) -> Option<FirmwareType> {
+    let i2c = I2cDevice::new(&device.i2c_device, device.address).ok()?;
+    let mut radar = XM125Radar::new(i2c, device.pins);
+    radar.connect().ok()?;
+    let app_id = radar.read_application_id().ok()?;
+    xm125_protocol::Application::from_app_id(app_id).map(FirmwareType::from)
+}
+
+/// Update one module, restoring its previous firmware if the update fails
+async fn update_device(
+    device: &FleetDevice,
+    manager: &FirmwareManager,
+    firmware_type: FirmwareType,
+    force: bool,
+    peers: &[FleetDevice],
+) -> (Outcome, Option<FirmwareType>, Option<String>) {
+    if let Some(peer) = peers
+        .iter()
+        .find(|peer| peer.name != device.name && device.inseparable_from(peer))
+    {
+        let error = format!(
+            "shares its bus, reset and BOOT0 lines with {}, so its bootloader cannot be reached alone",
+            peer.name
+        );
+        return (Outcome::Failed, None, Some(error));
+    }
+
+    let previous = running_firmware(device);
+    if previous == Some(firmware_type) && !force {
+        info!(
+            "{}: already running {} firmware",
+            device.name,
+            firmware_type.display_name()
+        );
+        return (Outcome::Unchanged, previous, None);
+    }
+
+    let Err(e) = manager
+        .update_firmware_with_verification(firmware_type, true)
+        .await
+    else {
+        return (Outcome::Updated, previous, None);
+    };
+    let Some(previous_type) = previous else {
+        warn!(
+            "{}: update failed and the previous firmware is unknown: {e}",
+            device.name
+        );
+        return (Outcome::Failed, previous, Some(e.to_string()));
+    };
+    warn!(
+        "{}: update failed, restoring {} firmware: {e}",
+        device.name,
+        previous_type.display_name()
+    );
+    match manager
+        .update_firmware_with_verification(previous_type, true)
+        .await
+    {
+        Ok(()) => (Outcome::RolledBack, previous, Some(e.to_string())),
+        Err(rollback) => (
+            Outcome::Failed,
+            previous,
+            Some(format!("{e}; rollback failed: {rollback}")),
+        ),
+    }
+}
+
+/// Update every configured module to `firmware_type`, `parallel` modules at a time at most
+pub async fn update_all(
+    cli: &Cli,
+    firmware_type: FirmwareType,
+    parallel: usize,
+    force: bool,
+) -> Result<Vec<DeviceResult>, RadarError> {
+    if cli.flash_transport() != crate::firmware::FlashTransport::I2c {
+        return Err(RadarError::InvalidParameters(
+            "update-all flashes each module over its own I2C bus and cannot use --flash-transport uart"
+                .to_string(),
+        ));
+    }
+    let devices = devices(cli)?;
+    let mut results: Vec<Option<DeviceResult>> = vec![None; devices.len()];
+    for round in rounds(&devices, parallel) {
+        let names: Vec<&str> = round.iter().map(|&i| devices[i].name.as_str()).collect();
+        info!("📦 Updating {}", names.join(", "));
+        let tasks: Vec<_> = round
+            .iter()
+            .map(|&i| {
+                let device = devices[i].clone();
+                let peers = devices.clone();
+                let lock_dir = cli.lock_dir_path();
+                let manager = FirmwareManager::new(
+                    cli.firmware_paths(),
+                    "/usr/bin/xm125-control.sh",
+                    device.address,
+                )
+                .with_flash_timeout(cli.timeouts().firmware_flash)
+                .for_device(&device.i2c_device, device.pins);
+                let runtime = tokio::runtime::Handle::current();
+                // Flashing blocks on the bus, so each module gets its own thread
+                tokio::task::spawn_blocking(move || {
+                    let result =
+                        DeviceLock::acquire(&lock_dir, &device.i2c_device, device.address, false)
+                            .map(|_lock| {
+                                runtime.block_on(update_device(
+                                    &device,
+                                    &manager,
+                                    firmware_type,
+                                    force,
+                                    &peers,
+                                ))
+                            });
+                    let (outcome, previous, error) =
+                        result.unwrap_or_else(|e| (Outcome::Failed, None, Some(e.to_string())));
+                    DeviceResult {
+                        device: device.name,
+                        i2c_device: device.i2c_device,
+                        address: device.address,
+                        outcome,
+                        previous: previous.map(FirmwareType::display_name),
+                        error,
+                    }
+                })
+            })
+            .collect();
+        for (&i, task) in round.iter().zip(tasks) {
+            results[i] = Some(task.await.map_err(|e| RadarError::FirmwareError {
+                message: format!("update of {} panicked: {e}", devices[i].name),
+            })?);
+        }
+    }
+    Ok(results.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, bus: u8, reset: u32, boot: u32) -> FleetDevice {
+        FleetDevice {
+            name: name.to_string(),
+            i2c_device: format!("/dev/i2c-{bus}"),
+            address: 0x52,
+            pins: XM125GpioPins {
+                reset,
+                mcu_interrupt: 0,
+                wake_up: 0,
+                boot,
+                ..XM125GpioPins::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_rounds_never_share_a_bus_or_control_line() {
+        let devices = [
+            device("a", 1, 10, 11),
+            device("b", 1, 20, 21),
+            device("c", 2, 30, 31),
+            device("d", 3, 10, 41),
+            device("e", 4, 50, 51),
+        ];
+        assert_eq!(
+            rounds(&devices, 1),
+            vec![vec![0], vec![1], vec![2], vec![3], vec![4]]
+        );
+        assert_eq!(rounds(&devices, 3), vec![vec![0, 2, 4], vec![1, 3]]);
+        assert_eq!(rounds(&devices, 0).len(), devices.len());
+        assert!(devices[0].inseparable_from(&device("f", 1, 10, 11)));
+        assert!(!devices[0].inseparable_from(&devices[1]));
+    }
+}
@@ -6,10 +6,15 @@
 // Internal GPIO control implementation to replace external script dependencies.
 // Provides robust, cross-platform GPIO operations for XM125 radar module control.
 
+//! Reset, wake-up, interrupt and BOOT0 lines of the module
+
 use crate::error::RadarError;
+use crate::timeline::{self, ResetMode};
+use crate::timeouts::TimeoutPhase;
 use log::{debug, info, warn};
 use std::fs::File;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
+use std::os::unix::io::AsRawFd;
 use std::path::Path;
 use std::thread;
 use std::time::Duration;
@@ -17,7 +22,7 @@ use std::time::Duration;
 /// XM125 GPIO pin definitions for i.MX8MM platform
 #[derive(Debug, Clone, Copy)]
 pub struct XM125GpioPins {
-    /// Reset pin - `GPIO4_IO28` (96+28=124) - Active-low reset
+    /// Reset pin - `GPIO4_IO28` (96+28=124) - Reset, active-low by default
     pub reset: u32,
     /// MCU interrupt pin - `GPIO4_IO29` (96+29=125) - Module ready signal
     pub mcu_interrupt: u32,
@@ -25,6 +30,8 @@ pub struct XM125GpioPins {
     pub wake_up: u32,
     /// Boot pin - `GPIO5_IO13` (128+13=141) - Bootloader control
     pub boot: u32,
+    /// Reset polarity and control line timing
+    pub signals: GpioSignals,
 }
 
 impl Default for XM125GpioPins {
@@ -34,10 +41,71 @@ impl Default for XM125GpioPins {
             mcu_interrupt: 125, // GPIO4_IO29 - SAI3_RXC
             wake_up: 139,       // GPIO5_IO11 - ECSPI2_MOSI
             boot: 141,          // GPIO5_IO13 - ECSPI2_SS0
+            signals: GpioSignals::default(),
         }
     }
 }
 
+/// Reset polarity and timing of the control lines
+///
+/// The defaults suit the Sentai carrier. Boards with level shifters or inverting buffers
+/// between the host and the module may need the opposite reset polarity or longer pulses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpioSignals {
+    /// Level that holds the module in reset
+    pub reset_active: GpioValue,
+    /// How long reset is held asserted
+    pub reset_pulse: Duration,
+    /// Wait after releasing reset for the application to start
+    pub startup_delay: Duration,
+    /// Wait after driving WAKE_UP high at the end of a reset
+    pub wake_delay: Duration,
+    /// Wait for BOOT0 and WAKE_UP to settle before asserting reset
+    pub settle_delay: Duration,
+}
+
+impl Default for GpioSignals {
+    fn default() -> Self {
+        Self {
+            reset_active: GpioValue::Low,
+            reset_pulse: Duration::from_millis(100),
+            startup_delay: Duration::from_millis(100),
+            wake_delay: Duration::from_millis(100),
+            settle_delay: Duration::from_millis(10),
+        }
+    }
+}
+
+impl GpioSignals {
+    /// Level that lets the module run
+    pub fn reset_released(&self) -> GpioValue {
+        match self.reset_active {
+            GpioValue::Low => GpioValue::High,
+            GpioValue::High => GpioValue::Low,
+        }
+    }
+
+    /// Durations outside the range the module and this tool accept, as (field, problem)
+    pub fn problems(&self) -> Vec<(&'static str, String)> {
+        [
+            ("reset_pulse", self.reset_pulse, 1, 5_000),
+            ("startup_delay", self.startup_delay, 10, 10_000),
+            ("wake_delay", self.wake_delay, 0, 5_000),
+            ("settle_delay", self.settle_delay, 0, 1_000),
+        ]
+        .into_iter()
+        .filter(|(_, value, min_ms, max_ms)| !(*min_ms..=*max_ms).contains(&value.as_millis()))
+        .map(|(name, value, min_ms, max_ms)| {
+            let problem = format!(
+                "must be between {min_ms} and {max_ms} ms (got {} ms)",
+                value.as_millis()
+            );
+            (name, problem)
+        })
+        .collect()
+    }
+}
+
 /// GPIO direction enumeration
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum GpioDirection {
@@ -47,6 +115,16 @@ pub enum GpioDirection {
     OutputHigh, // Output with initial value HIGH (atomic operation)
 }
 
+impl GpioDirection {
+    /// Output starting at `level`
+    fn output(level: GpioValue) -> Self {
+        match level {
+            GpioValue::Low => GpioDirection::OutputLow,
+            GpioValue::High => GpioDirection::OutputHigh,
+        }
+    }
+}
+
 impl std::fmt::Display for GpioDirection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -59,8 +137,9 @@ impl std::fmt::Display for GpioDirection {
 }
 
 /// GPIO value enumeration
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum GpioValue {
+    #[default]
     Low = 0,
     High = 1,
 }
@@ -71,6 +150,43 @@ impl std::fmt::Display for GpioValue {
     }
 }
 
+/// XM125 power state derived from the RESET, WAKE_UP and MCU_INT pins
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerState {
+    /// Reset released, WAKE_UP high and MCU_INT high
+    Awake,
+    /// Reset released and WAKE_UP high, MCU_INT not yet high
+    Waking,
+    /// WAKE_UP low - module in low-power sleep
+    Sleeping,
+    /// Reset held asserted - minimum power, module not running
+    HeldInReset,
+}
+
+impl std::fmt::Display for PowerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerState::Awake => write!(f, "awake"),
+            PowerState::Waking => write!(f, "waking"),
+            PowerState::Sleeping => write!(f, "sleeping"),
+            PowerState::HeldInReset => write!(f, "held in reset"),
+        }
+    }
+}
+
+impl PowerState {
+    /// Derive the power state from pin levels
+    pub fn from_pins(reset: GpioValue, wake_up: GpioValue, mcu_interrupt: GpioValue) -> Self {
+        match (reset, wake_up, mcu_interrupt) {
+            (GpioValue::Low, _, _) => PowerState::HeldInReset,
+            (GpioValue::High, GpioValue::Low, _) => PowerState::Sleeping,
+            (GpioValue::High, GpioValue::High, GpioValue::High) => PowerState::Awake,
+            (GpioValue::High, GpioValue::High, GpioValue::Low) => PowerState::Waking,
+        }
+    }
+}
+
 /// XM125 GPIO Controller
 pub struct XM125GpioController {
     pins: XM125GpioPins,
@@ -96,6 +212,7 @@ impl XM125GpioController {
 
     /// Initialize all XM125 GPIO pins
     pub fn initialize(&mut self) -> Result<(), RadarError> {
+        let reset_released = GpioDirection::output(self.pins.signals.reset_released());
         info!("🔧 Initializing XM125 GPIO pins...");
 
         // Fix GPIO141 bootloader pin first (Foundries.io workaround)
@@ -111,7 +228,7 @@ impl XM125GpioController {
         // Using "low" or "high" direction sets both direction and value in one operation
         // This prevents the brief microsecond pulse that occurs when setting direction
         // and value separately
-        self.set_gpio_direction(self.pins.reset, GpioDirection::OutputHigh, "Reset")?;
+        self.set_gpio_direction(self.pins.reset, reset_released, "Reset")?;
         self.set_gpio_direction(
             self.pins.mcu_interrupt,
             GpioDirection::Input,
@@ -127,6 +244,44 @@ impl XM125GpioController {
         Ok(())
     }
 
+    /// Attach to the XM125 GPIO pins without disturbing outputs that are already driven
+    ///
+    /// Unlike [`Self::initialize`], pins already configured as outputs keep their level, so
+    /// power state can be inspected or changed without waking or resetting the module.
+    pub fn attach(&mut self) -> Result<(), RadarError> {
+        self.fix_gpio141_bootloader_pin()?;
+
+        self.export_gpio(self.pins.reset, "Reset")?;
+        self.export_gpio(self.pins.mcu_interrupt, "MCU Interrupt")?;
+        self.export_gpio(self.pins.wake_up, "Wake Up")?;
+        self.export_gpio(self.pins.boot, "Bootloader")?;
+
+        let reset_released = GpioDirection::output(self.pins.signals.reset_released());
+        for (pin, default, name) in [
+            (self.pins.reset, reset_released, "Reset"),
+            (self.pins.wake_up, GpioDirection::OutputHigh, "Wake Up"),
+            (self.pins.boot, GpioDirection::OutputLow, "Bootloader"),
+        ] {
+            if !Self::is_output(pin) {
+                self.set_gpio_direction(pin, default, name)?;
+            }
+        }
+        self.set_gpio_direction(
+            self.pins.mcu_interrupt,
+            GpioDirection::Input,
+            "MCU Interrupt",
+        )?;
+
+        self.initialized = true;
+        Ok(())
+    }
+
+    /// Check whether a GPIO is currently configured as an output
+    fn is_output(gpio_num: u32) -> bool {
+        std::fs::read_to_string(format!("/sys/class/gpio/gpio{gpio_num}/direction"))
+            .is_ok_and(|direction| direction.trim() == "out")
+    }
+
     /// Fix GPIO141 bootloader pin (Foundries.io workaround)
     /// This resolves the SPI controller conflict that prevents GPIO141 access
     fn fix_gpio141_bootloader_pin(&self) -> Result<(), RadarError> {
@@ -305,6 +460,7 @@ impl XM125GpioController {
         std::fs::write(&value_path, value.to_string()).map_err(|e| RadarError::DeviceError {
             message: format!("Failed to set GPIO{gpio_num} value: {e}"),
         })?;
+        timeline::gpio(gpio_num, gpio_name, value == GpioValue::High);
 
         Ok(())
     }
@@ -349,10 +505,11 @@ impl XM125GpioController {
         self.set_gpio_value(self.pins.wake_up, GpioValue::High, "Wake Up (awake)")?;
 
         // Small delay for pin to stabilize
-        thread::sleep(Duration::from_millis(10));
+        thread::sleep(self.pins.signals.settle_delay);
 
         // Perform reset sequence
         self.perform_reset_sequence()?;
+        timeline::reset(ResetMode::Run);
 
         info!("✅ Reset to RUN mode completed - ready for normal operation");
         Ok(())
@@ -373,10 +530,11 @@ impl XM125GpioController {
         self.set_gpio_value(self.pins.wake_up, GpioValue::High, "Wake Up (awake)")?;
 
         // Small delay for pin to stabilize
-        thread::sleep(Duration::from_millis(10));
+        thread::sleep(self.pins.signals.settle_delay);
 
         // Perform reset sequence
         self.perform_reset_sequence()?;
+        timeline::reset(ResetMode::Bootloader);
 
         info!("✅ Reset to BOOTLOADER mode completed - ready for firmware programming");
         Ok(())
@@ -384,19 +542,25 @@ impl XM125GpioController {
 
     /// Perform the actual reset sequence (common for both modes)
     fn perform_reset_sequence(&self) -> Result<(), RadarError> {
-        // Assert reset (active-low)
-        debug!("Asserting reset (LOW)");
-        self.set_gpio_value(self.pins.reset, GpioValue::Low, "Reset (asserted)")?;
-        thread::sleep(Duration::from_millis(100)); // 100ms reset assertion
+        let signals = &self.pins.signals;
+
+        // Assert reset
+        debug!("Asserting reset ({})", signals.reset_active);
+        self.set_gpio_value(self.pins.reset, signals.reset_active, "Reset (asserted)")?;
+        thread::sleep(signals.reset_pulse);
 
         // Deassert reset
-        debug!("Deasserting reset (HIGH)");
-        self.set_gpio_value(self.pins.reset, GpioValue::High, "Reset (released)")?;
-        thread::sleep(Duration::from_millis(100)); // 100ms for application startup
+        debug!("Deasserting reset ({})", signals.reset_released());
+        self.set_gpio_value(
+            self.pins.reset,
+            signals.reset_released(),
+            "Reset (released)",
+        )?;
+        thread::sleep(signals.startup_delay); // Application startup
 
         // Ensure wake pin is HIGH
         self.set_gpio_value(self.pins.wake_up, GpioValue::High, "Wake Up (awake)")?;
-        thread::sleep(Duration::from_millis(100)); // Additional time for wake-up
+        thread::sleep(signals.wake_delay); // Additional time for wake-up
 
         Ok(())
     }
@@ -436,7 +600,6 @@ impl XM125GpioController {
     }
 
     /// Wait for MCU interrupt to go HIGH (module ready)
-    #[allow(dead_code)] // Public API method
     pub fn wait_for_module_ready(&self, timeout_seconds: u32) -> Result<(), RadarError> {
         info!("⏳ Waiting for XM125 to become ready (MCU_INT HIGH)...");
 
@@ -458,11 +621,94 @@ impl XM125GpioController {
             thread::sleep(Duration::from_secs(1));
         }
 
-        Err(RadarError::DeviceError {
-            message: format!("Timeout waiting for XM125 ready signal after {timeout_seconds}s"),
+        warn!("Timeout waiting for XM125 ready signal after {timeout_seconds}s");
+        Err(RadarError::Timeout {
+            phase: TimeoutPhase::Connect,
+            timeout: Duration::from_secs(u64::from(timeout_seconds)),
         })
     }
 
+    /// Wait for MCU_INT to reach `level`, polling every 10ms
+    pub fn wait_for_mcu_interrupt(
+        &self,
+        level: GpioValue,
+        timeout: Duration,
+    ) -> Result<Duration, RadarError> {
+        let start = std::time::Instant::now();
+        loop {
+            if self.get_gpio_value(self.pins.mcu_interrupt)? == level {
+                return Ok(start.elapsed());
+            }
+            if start.elapsed() >= timeout {
+                return Err(RadarError::DeviceError {
+                    message: format!(
+                        "Timeout waiting for MCU_INT {} after {}ms",
+                        if level == GpioValue::High {
+                            "HIGH"
+                        } else {
+                            "LOW"
+                        },
+                        timeout.as_millis()
+                    ),
+                });
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Read the current power state of the module
+    pub fn power_state(&self) -> Result<PowerState, RadarError> {
+        // Reset is reported as the active-low level of the reference carrier
+        let held = self.get_gpio_value(self.pins.reset)? == self.pins.signals.reset_active;
+        Ok(PowerState::from_pins(
+            if held {
+                GpioValue::Low
+            } else {
+                GpioValue::High
+            },
+            self.get_gpio_value(self.pins.wake_up)?,
+            self.get_gpio_value(self.pins.mcu_interrupt)?,
+        ))
+    }
+
+    /// Put the module to sleep by driving WAKE_UP low and waiting for MCU_INT to drop
+    ///
+    /// With `hold_reset` the module is additionally held in reset for minimum power; it
+    /// then needs a full reset (and re-configuration) on wake.
+    pub fn sleep(&self, hold_reset: bool, timeout: Duration) -> Result<(), RadarError> {
+        info!("😴 Putting XM125 to sleep (WAKE_UP LOW)...");
+        self.set_gpio_value(self.pins.wake_up, GpioValue::Low, "Wake Up (sleep)")?;
+        let elapsed = self.wait_for_mcu_interrupt(GpioValue::Low, timeout)?;
+        debug!("MCU_INT went LOW after {}ms", elapsed.as_millis());
+
+        if hold_reset {
+            info!("🔌 Holding XM125 in reset for minimum power");
+            let active = self.pins.signals.reset_active;
+            self.set_gpio_value(self.pins.reset, active, "Reset (held)")?;
+        }
+        Ok(())
+    }
+
+    /// Wake the module by driving WAKE_UP high and waiting for MCU_INT (module ready)
+    ///
+    /// A module held in reset is released first and boots into run mode.
+    pub fn wake(&self, timeout: Duration) -> Result<Duration, RadarError> {
+        let signals = self.pins.signals;
+        if self.get_gpio_value(self.pins.reset)? == signals.reset_active {
+            info!("🔄 Releasing XM125 from reset...");
+            self.set_gpio_value(self.pins.boot, GpioValue::Low, "Bootloader (run mode)")?;
+            self.set_gpio_value(
+                self.pins.reset,
+                signals.reset_released(),
+                "Reset (released)",
+            )?;
+        }
+
+        info!("⏰ Waking XM125 (WAKE_UP HIGH)...");
+        self.set_gpio_value(self.pins.wake_up, GpioValue::High, "Wake Up (awake)")?;
+        self.wait_for_mcu_interrupt(GpioValue::High, timeout)
+    }
+
     /// Show current GPIO status
     #[allow(clippy::unnecessary_wraps)]
     pub fn show_gpio_status(&self) -> Result<(), RadarError> {
@@ -528,6 +774,29 @@ impl XM125GpioController {
     }
 
     /// Check if GPIO controller is initialized
+    /// Configure `gpio_num` as an input reporting both edges, for blocking waits
+    pub fn open_edge_input(
+        &self,
+        gpio_num: u32,
+        gpio_name: &str,
+    ) -> Result<GpioEdgeInput, RadarError> {
+        self.export_gpio(gpio_num, gpio_name)?;
+        self.set_gpio_direction(gpio_num, GpioDirection::Input, gpio_name)?;
+        std::fs::write(format!("/sys/class/gpio/gpio{gpio_num}/edge"), "both").map_err(|e| {
+            RadarError::DeviceError {
+                message: format!("Failed to enable edge events on GPIO{gpio_num}: {e}"),
+            }
+        })?;
+
+        let file = File::open(format!("/sys/class/gpio/gpio{gpio_num}/value")).map_err(|e| {
+            RadarError::DeviceError {
+                message: format!("Failed to open GPIO{gpio_num} value file: {e}"),
+            }
+        })?;
+        debug!("GPIO{gpio_num} ({gpio_name}) armed for edge events");
+        Ok(GpioEdgeInput { gpio_num, file })
+    }
+
     #[allow(dead_code)] // Public API method
     pub fn is_initialized(&self) -> bool {
         self.initialized
@@ -540,6 +809,80 @@ impl XM125GpioController {
     }
 }
 
+/// Input pin held open so the kernel can signal edges through `poll(2)`
+///
+/// The sysfs value file reports `POLLPRI` once per edge after it has been read, so the
+/// process sleeps in the kernel until the pin changes instead of polling the level.
+#[derive(Debug)]
+pub struct GpioEdgeInput {
+    gpio_num: u32,
+    file: File,
+}
+
+impl GpioEdgeInput {
+    /// Current level; reading also re-arms the edge notification
+    pub fn level(&mut self) -> Result<GpioValue, RadarError> {
+        let mut contents = [0u8; 2];
+        let read = self
+            .file
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.file.read(&mut contents))
+            .map_err(|e| RadarError::DeviceError {
+                message: format!("Failed to read GPIO{} value: {e}", self.gpio_num),
+            })?;
+        match &contents[..read.min(1)] {
+            b"0" => Ok(GpioValue::Low),
+            b"1" => Ok(GpioValue::High),
+            other => Err(RadarError::DeviceError {
+                message: format!(
+                    "Invalid GPIO{} value: {}",
+                    self.gpio_num,
+                    String::from_utf8_lossy(other)
+                ),
+            }),
+        }
+    }
+
+    /// Block until the pin reaches `level` or `timeout` passes; returns whether it did
+    pub fn wait_for(&mut self, level: GpioValue, timeout: Duration) -> Result<bool, RadarError> {
+        // A timeout too large to represent waits forever
+        let deadline = std::time::Instant::now().checked_add(timeout);
+        loop {
+            if self.level()? == level {
+                return Ok(true);
+            }
+            let timeout_ms = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                    if remaining.is_zero() {
+                        return Ok(false);
+                    }
+                    i32::try_from(remaining.as_millis()).unwrap_or(i32::MAX)
+                }
+                None => -1,
+            };
+
+            let mut pollfd = libc::pollfd {
+                fd: self.file.as_raw_fd(),
+                events: libc::POLLPRI | libc::POLLERR,
+                revents: 0,
+            };
+            // SAFETY: `pollfd` is a valid pollfd for the open value file
+            let ready = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+            if ready < 0 {
+                let err = std::io::Error::last_os_error();
+                // A signal (e.g. SIGTERM on takeover) ends the wait so the caller can stop
+                if err.kind() == std::io::ErrorKind::Interrupted {
+                    return Ok(false);
+                }
+                return Err(RadarError::DeviceError {
+                    message: format!("Failed to wait for GPIO{} edge: {err}", self.gpio_num),
+                });
+            }
+        }
+    }
+}
+
 impl Default for XM125GpioController {
     fn default() -> Self {
         Self::new()
@@ -567,6 +910,31 @@ mod tests {
         assert_eq!(pins.boot, 141);
     }
 
+    #[test]
+    fn test_gpio_signal_ranges() {
+        assert!(GpioSignals::default().problems().is_empty());
+        let signals = GpioSignals {
+            reset_active: GpioValue::High,
+            reset_pulse: Duration::from_secs(10),
+            startup_delay: Duration::from_millis(5),
+            ..GpioSignals::default()
+        };
+        assert_eq!(signals.reset_released(), GpioValue::Low);
+        assert_eq!(
+            signals.problems(),
+            vec![
+                (
+                    "reset_pulse",
+                    "must be between 1 and 5000 ms (got 10000 ms)".to_string()
+                ),
+                (
+                    "startup_delay",
+                    "must be between 10 and 10000 ms (got 5 ms)".to_string()
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_gpio_direction_display() {
         assert_eq!(GpioDirection::Input.to_string(), "in");
@@ -581,6 +949,18 @@ mod tests {
         assert_eq!(GpioValue::High.to_string(), "1");
     }
 
+    #[test]
+    fn test_power_state_from_pins() {
+        use GpioValue::{High, Low};
+        assert_eq!(
+            PowerState::from_pins(Low, High, High),
+            PowerState::HeldInReset
+        );
+        assert_eq!(PowerState::from_pins(High, Low, High), PowerState::Sleeping);
+        assert_eq!(PowerState::from_pins(High, High, Low), PowerState::Waking);
+        assert_eq!(PowerState::from_pins(High, High, High), PowerState::Awake);
+    }
+
     #[test]
     fn test_gpio_controller_creation() {
         let controller = XM125GpioController::new();
@@ -0,0 +1,199 @@
+//! Self-diagnostics of the measurement service
+//!
+//! `{"kind":"health"}` on the `serve` socket reports how the service itself is doing:
+//! uptime, percentiles of measurement latency and of the time requests waited in the queue,
+//! current queue depths and client count, and the last error of each subsystem. A slow
+//! radar shows up as high measurement latency, while a starved host shows up as requests
+//! waiting long in the queue with normal measurement latency. Health requests are answered
+//! by the client task directly, so they still answer while a measurement is in progress.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Latency samples kept per series
+const WINDOW: usize = 1024;
+
+/// Rolling window of recent durations
+#[derive(Debug, Default)]
+struct LatencyWindow {
+    samples: VecDeque<Duration>,
+}
+
+impl LatencyWindow {
+    fn record(&mut self, duration: Duration) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(duration);
+    }
+
+    fn percentiles(&self) -> Option<Percentiles> {
+        let mut sorted: Vec<f64> = self
+            .samples
+            .iter()
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .collect();
+        sorted.sort_by(f64::total_cmp);
+        let at = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+        let max = *sorted.last()?;
+        Some(Percentiles {
+            samples: sorted.len(),
+            p50_ms: at(0.50),
+            p95_ms: at(0.95),
+            p99_ms: at(0.99),
+            max_ms: max,
+        })
+    }
+}
+
+/// Latency percentiles over the most recent samples
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Most recent error of one subsystem
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemError {
+    pub error: String,
+    pub age_s: u64,
+}
+
+/// Health report returned by `{"kind":"health"}`
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceHealth {
+    pub uptime_s: u64,
+    pub clients: usize,
+    /// Requests waiting for the scheduler
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    pub measurements: u64,
+    pub measurement_latency: Option<Percentiles>,
+    /// Time from a request being queued to its batch starting
+    pub queue_wait: Option<Percentiles>,
+    pub last_errors: BTreeMap<&'static str, SubsystemError>,
+}
+
+#[derive(Debug)]
+struct State {
+    started: Instant,
+    clients: usize,
+    measurements: u64,
+    measurement_latency: LatencyWindow,
+    queue_wait: LatencyWindow,
+    last_errors: BTreeMap<&'static str, (String, Instant)>,
+}
+
+/// Health counters shared between the scheduler and client tasks
+#[derive(Debug, Clone)]
+pub struct HealthMonitor(Arc<Mutex<State>>);
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(State {
+            started: Instant::now(),
+            clients: 0,
+            measurements: 0,
+            measurement_latency: LatencyWindow::default(),
+            queue_wait: LatencyWindow::default(),
+            last_errors: BTreeMap::new(),
+        })))
+    }
+}
+
+impl HealthMonitor {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// A measurement cycle finished after `duration`
+    pub fn measured(&self, duration: Duration) {
+        let mut state = self.state();
+        state.measurements += 1;
+        state.measurement_latency.record(duration);
+    }
+
+    /// A request waited `duration` before its batch started
+    pub fn waited(&self, duration: Duration) {
+        self.state().queue_wait.record(duration);
+    }
+
+    /// Record the latest error of `subsystem` (e.g. `"radar"`, `"socket"`)
+    pub fn error(&self, subsystem: &'static str, error: impl ToString) {
+        self.state()
+            .last_errors
+            .insert(subsystem, (error.to_string(), Instant::now()));
+    }
+
+    pub fn client_connected(&self) {
+        self.state().clients += 1;
+    }
+
+    pub fn client_disconnected(&self) {
+        let mut state = self.state();
+        state.clients = state.clients.saturating_sub(1);
+    }
+
+    /// Current report, given the scheduler queue's depth and capacity
+    pub fn report(&self, queue_depth: usize, queue_capacity: usize) -> ServiceHealth {
+        let state = self.state();
+        ServiceHealth {
+            uptime_s: state.started.elapsed().as_secs(),
+            clients: state.clients,
+            queue_depth,
+            queue_capacity,
+            measurements: state.measurements,
+            measurement_latency: state.measurement_latency.percentiles(),
+            queue_wait: state.queue_wait.percentiles(),
+            last_errors: state
+                .last_errors
+                .iter()
+                .map(|(&subsystem, (error, at))| {
+                    let error = SubsystemError {
+                        error: error.clone(),
+                        age_s: at.elapsed().as_secs(),
+                    };
+                    (subsystem, error)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_percentiles_over_rolling_window() {
+        let health = HealthMonitor::default();
+        assert!(health.report(0, 64).measurement_latency.is_none());
+
+        for ms in 1..=100 {
+            health.measured(Duration::from_millis(ms));
+        }
+        health.error("radar", "Device timeout");
+        let report = health.report(3, 64);
+        let latency = report.measurement_latency.unwrap();
+        assert_eq!(latency.samples, 100);
+        assert_eq!(latency.p50_ms, 51.0);
+        assert_eq!(latency.p99_ms, 99.0);
+        assert_eq!(latency.max_ms, 100.0);
+        assert_eq!(report.measurements, 100);
+        assert_eq!(report.last_errors["radar"].error, "Device timeout");
+
+        for _ in 0..WINDOW {
+            health.measured(Duration::from_millis(5));
+        }
+        assert_eq!(
+            health.report(0, 64).measurement_latency.unwrap().max_ms,
+            5.0
+        );
+    }
+}
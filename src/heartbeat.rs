@@ -0,0 +1,115 @@
+//! Dead-man heartbeat file
+//!
+//! With `--heartbeat-file`, every successful measurement touches the file. It is created
+//! with the first measurement and holds the maximum age the run promises
+//! (`--heartbeat-max-age`), so an external supervisor only has to compare the file's
+//! modification time against it: a stale or missing file means the measurement loop has
+//! stalled or ended. The file is removed when the process exits.
+
+use log::{debug, warn};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+struct Heartbeat {
+    path: PathBuf,
+    max_age: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+static HEARTBEAT: OnceLock<Heartbeat> = OnceLock::new();
+
+/// Touch `path` after every successful measurement from now on
+pub fn init(path: &str, max_age: Duration) {
+    let _ = HEARTBEAT.set(Heartbeat {
+        path: PathBuf::from(path),
+        max_age,
+        last: Mutex::new(None),
+    });
+}
+
+/// A measurement succeeded
+pub fn beat() {
+    let Some(heartbeat) = HEARTBEAT.get() else {
+        return;
+    };
+    let mut last = heartbeat.last.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(gap) = last.map(|at| at.elapsed()) {
+        if gap > heartbeat.max_age {
+            warn!(
+                "⚠️  {:.1}s since the previous measurement exceeds the heartbeat max age of {:?}",
+                gap.as_secs_f64(),
+                heartbeat.max_age
+            );
+        }
+    }
+    *last = Some(Instant::now());
+
+    let touched = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&heartbeat.path)
+        .and_then(|mut file| {
+            if file.metadata()?.len() == 0 {
+                writeln!(
+                    file,
+                    "pid={}\nmax_age_s={}",
+                    std::process::id(),
+                    heartbeat.max_age.as_secs_f64()
+                )?;
+            }
+            file.set_modified(SystemTime::now())
+        });
+    if let Err(e) = touched {
+        warn!(
+            "Failed to touch heartbeat file {}: {e}",
+            heartbeat.path.display()
+        );
+    }
+}
+
+/// Remove the heartbeat file, so supervisors see the run has ended
+pub fn stop() {
+    if let Some(heartbeat) = HEARTBEAT.get() {
+        if let Err(e) = fs::remove_file(&heartbeat.path) {
+            debug!(
+                "Heartbeat file {} not removed: {e}",
+                heartbeat.path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beat_creates_touches_and_stop_removes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xm125.alive");
+        init(path.to_str().unwrap(), Duration::from_secs(15));
+
+        beat();
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("max_age_s=15"));
+
+        let old = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        beat();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        assert!(modified > old + Duration::from_secs(30));
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+
+        stop();
+        assert!(!path.exists());
+    }
+}
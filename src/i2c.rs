@@ -1,16 +1,128 @@
+//! I2C transport to the module
+//!
+//! [`I2cDevice`] carries register reads and writes to the module on a Linux I2C bus, replays
+//! a trace recorded with `--i2c-record`, or answers from a simulated module for development
+//! without the hardware.
+//!
+//! Bus transfers block, so every register access runs through [`blocking`]: on the
+//! multi-threaded tokio runtime the worker hands its other tasks to another thread for the
+//! duration, and timers, signal handling and the MQTT connection keep running while the
+//! module is polled. The pause the module needs between transactions is configurable
+//! with `--i2c-delay`, and `--write-verify` reads register writes back to catch bus
+//! corruption that would otherwise leave the module silently mis-configured. With
+//! `--wait-mode interrupt` the detectors sleep on the MCU_INT edge between status reads
+//! instead of polling the status register every 10 ms.
+
 use crate::error::{RadarError, Result};
+use crate::fault::{self, Fault};
+use crate::gpio::{GpioEdgeInput, GpioValue};
+use crate::i2c_errors;
+use crate::i2c_sim::SimulatedXm125;
+use crate::i2c_trace::{TracePlayer, TraceRecorder};
+use clap::ValueEnum;
 use embedded_hal::i2c::I2c;
 use linux_embedded_hal::I2cdev;
 use log::{debug, info, warn};
 use std::io::Read;
 use std::thread;
 use std::time::{Duration, Instant};
+use xm125_protocol::detector::DetectorProtocol;
+
+/// Default pause after each transaction for the module to process it
+pub const DEFAULT_TRANSACTION_DELAY: Duration = Duration::from_millis(1);
+
+/// Writes of a register that keeps reading back differently before giving up
+const WRITE_VERIFY_ATTEMPTS: u32 = 3;
+
+/// Which register writes are read back and compared (`--write-verify`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum WriteVerify {
+    /// Trust the bus
+    #[default]
+    Off,
+    /// Detector configuration registers (range, thresholds, frame rate, ...)
+    Critical,
+    /// Every register except the command register
+    All,
+}
+
+/// Longest accepted `--i2c-delay`
+const MAX_TRANSACTION_DELAY: Duration = Duration::from_millis(100);
+
+/// Run blocking work without stalling the async executor
+///
+/// On the multi-threaded runtime this is `block_in_place`; outside a runtime or on the
+/// current-thread runtime, where `block_in_place` is not allowed, `f` simply runs.
+pub fn blocking<R>(f: impl FnOnce() -> R) -> R {
+    use tokio::runtime::{Handle, RuntimeFlavor};
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// Parse `--i2c-delay`: `500us`, `2ms` or bare milliseconds, 0 for none
+pub fn parse_transaction_delay(s: &str) -> std::result::Result<Duration, String> {
+    let s = s.trim();
+    let (value, scale_us) = [("us", 1.0), ("ms", 1000.0)]
+        .into_iter()
+        .find_map(|(suffix, scale)| s.strip_suffix(suffix).map(|value| (value, scale)))
+        .unwrap_or((s, 1000.0));
+    let micros = value
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid delay '{s}' (expected e.g. 0, 500us, 2ms)"))?
+        * scale_us;
+    if !(0.0..=MAX_TRANSACTION_DELAY.as_micros() as f64).contains(&micros) {
+        return Err(format!(
+            "Delay must be between 0 and {}ms, got '{s}'",
+            MAX_TRANSACTION_DELAY.as_millis()
+        ));
+    }
+    Ok(Duration::from_micros(micros.round() as u64))
+}
+
+/// Where transactions go: the real bus, a recorded trace or the simulated module
+pub trait I2cBackend: Send {
+    fn write(&mut self, address: u8, data: &[u8]) -> Result<()>;
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<()>;
+
+    /// Start `application` as flashing its firmware would; only a simulated module can
+    fn load_application(&mut self, _application: xm125_protocol::Application) -> bool {
+        false
+    }
+}
+
+impl I2cBackend for I2cdev {
+    fn write(&mut self, address: u8, data: &[u8]) -> Result<()> {
+        I2c::write(self, address, data).map_err(RadarError::I2c)
+    }
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<()> {
+        I2c::read(self, address, buffer).map_err(RadarError::I2c)
+    }
+}
 
+/// Register-level connection to one module
 pub struct I2cDevice {
-    device: I2cdev,
+    bus: Box<dyn I2cBackend>,
+    /// Copy of every transaction for `--i2c-record`
+    recorder: Option<TraceRecorder>,
     address: u16,
+    /// Register addressed by the last write, the context of a following read
+    selected: Option<u16>,
     wakeup_pin: Option<u32>,
     int_pin: Option<u32>,
+    /// Protocol whose status is checked before every register write (`--strict-protocol`)
+    strict: Option<&'static dyn DetectorProtocol>,
+    /// Read-back level and the protocol whose registers it applies to (`--write-verify`)
+    verify: Option<(WriteVerify, &'static dyn DetectorProtocol)>,
+    /// Pause after each transaction (`--i2c-delay`)
+    delay: Duration,
+    /// MCU_INT armed for edge events (`--wait-mode interrupt`)
+    ready_interrupt: Option<GpioEdgeInput>,
 }
 
 impl I2cDevice {
@@ -25,15 +137,191 @@ impl I2cDevice {
             ))
         })?;
 
-        Ok(Self {
-            device,
+        Ok(Self::with_backend(Box::new(device), address))
+    }
+
+    /// Device answering from the trace at `path` instead of the bus (`--i2c-playback`)
+    pub fn playback(path: &str, address: u16, speed: f64) -> Result<Self> {
+        Ok(Self::with_backend(
+            Box::new(TracePlayer::open(path, speed)?),
             address,
+        ))
+    }
+
+    /// Device answered by an emulated module running `application` (`--simulate`)
+    pub fn simulated(application: xm125_protocol::Application, address: u16) -> Self {
+        Self::with_backend(Box::new(SimulatedXm125::new(application)), address)
+    }
+
+    fn with_backend(bus: Box<dyn I2cBackend>, address: u16) -> Self {
+        Self {
+            bus,
+            recorder: None,
+            address,
+            selected: None,
             wakeup_pin: None,
             int_pin: None,
+            strict: None,
+            verify: None,
+            delay: DEFAULT_TRANSACTION_DELAY,
+            ready_interrupt: None,
+        }
+    }
+
+    /// Pause for `delay` after each transaction instead of [`DEFAULT_TRANSACTION_DELAY`]
+    pub fn set_transaction_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Wait for command completion on `pin` (MCU_INT) instead of polling the status register
+    pub fn set_ready_interrupt(&mut self, pin: Option<GpioEdgeInput>) {
+        self.ready_interrupt = pin;
+    }
+
+    /// Sleep until MCU_INT signals that the module finished its command, at most `timeout`
+    ///
+    /// MCU_INT drops while the module works on a command and rises when it is done. A pin
+    /// still high has not seen the command yet, so the falling edge is awaited briefly
+    /// first. Returns false without waiting when no interrupt is armed; callers then poll.
+    pub fn wait_for_ready_interrupt(&mut self, timeout: Duration) -> Result<bool> {
+        let Some(pin) = self.ready_interrupt.as_mut() else {
+            return Ok(false);
+        };
+        blocking(|| {
+            if pin.level()? == GpioValue::High {
+                pin.wait_for(GpioValue::Low, timeout.min(Duration::from_millis(10)))?;
+            }
+            pin.wait_for(GpioValue::High, timeout)
+        })?;
+        Ok(true)
+    }
+
+    /// Switch a simulated module to `application`; false on a real bus or a recorded trace
+    pub fn load_simulated_application(&mut self, application: xm125_protocol::Application) -> bool {
+        self.bus.load_application(application)
+    }
+
+    /// Transfer `data` and give the module time to process it
+    fn transact_write(&mut self, data: &[u8]) -> Result<()> {
+        let delay = self.delay;
+        blocking(|| {
+            self.bus_write(data)?;
+            thread::sleep(delay);
+            Ok(())
+        })
+    }
+
+    /// Record every transaction to `path` (`--i2c-record`)
+    pub fn record_to(&mut self, path: &str) -> Result<()> {
+        self.recorder = Some(TraceRecorder::create(path)?);
+        Ok(())
+    }
+
+    /// Check busy and error status under `protocol` before every register write
+    ///
+    /// Register access rules are otherwise only enforced around commands; in strict mode a
+    /// write while the detector is busy, or anything but RESET MODULE while it reports an
+    /// error, fails instead of being sent.
+    pub fn set_strict_protocol(&mut self, protocol: Option<&'static dyn DetectorProtocol>) {
+        self.strict = protocol;
+    }
+
+    /// Read back register writes covered by `level` under `protocol` and retry on mismatch
+    ///
+    /// Commands are never read back: the command register does not hold the last command.
+    pub fn set_write_verify(
+        &mut self,
+        level: WriteVerify,
+        protocol: &'static dyn DetectorProtocol,
+    ) {
+        self.verify = (level != WriteVerify::Off).then_some((level, protocol));
+    }
+
+    fn verifies(&self, register: u16) -> bool {
+        match self.verify {
+            None => false,
+            Some((_, protocol)) if register == protocol.command_register() => false,
+            Some((WriteVerify::All, _)) => true,
+            Some((_, protocol)) => protocol
+                .config_registers()
+                .iter()
+                .any(|info| info.address == register),
+        }
+    }
+
+    fn check_sequencing(
+        &mut self,
+        protocol: &'static dyn DetectorProtocol,
+        register: u16,
+        data: &[u8],
+    ) -> Result<()> {
+        let raw = self.read_register(protocol.status_register(), 4)?;
+        let status = u32::from_be_bytes([raw[0], raw[1], raw[2], raw[3]]);
+        let reset = register == protocol.command_register()
+            && data == protocol.cmd_reset_module().to_be_bytes();
+        let reason = if protocol.is_busy(status) {
+            "busy"
+        } else if protocol.has_error(status) && !reset {
+            "reporting an error"
+        } else {
+            return Ok(());
+        };
+        warn!(
+            "❌ Protocol violation: write to register 0x{register:04X} while the {} is {reason} (status 0x{status:08X})",
+            protocol.name()
+        );
+        Err(RadarError::ProtocolViolation {
+            register,
+            status,
+            reason,
         })
     }
 
+    fn bus_write(&mut self, data: &[u8]) -> Result<()> {
+        #[allow(clippy::cast_possible_truncation)] // I2C addresses are 7-bit, safe to cast
+        let result = if fault::inject(Fault::I2cWrite) {
+            Err(fault::i2c_error())
+        } else {
+            self.bus.write(self.address as u8, data)
+        };
+        if let Some(recorder) = &mut self.recorder {
+            recorder.write(data, result.as_ref().err().map(ToString::to_string));
+        }
+        self.selected = match data {
+            [high, low, ..] => Some(u16::from_be_bytes([*high, *low])),
+            _ => None,
+        };
+        if let Err(e) = &result {
+            i2c_errors::record("write", self.address, self.selected, data.len(), e);
+        }
+        result
+    }
+
+    fn bus_read(&mut self, buffer: &mut [u8]) -> Result<()> {
+        #[allow(clippy::cast_possible_truncation)] // I2C addresses are 7-bit, safe to cast
+        let result = if fault::inject(Fault::I2cRead) {
+            Err(fault::i2c_error())
+        } else {
+            self.bus.read(self.address as u8, buffer)
+        };
+        if let Some(recorder) = &mut self.recorder {
+            let data: &[u8] = if result.is_ok() { buffer } else { &[] };
+            recorder.read(
+                buffer.len(),
+                data,
+                result.as_ref().err().map(ToString::to_string),
+            );
+        }
+        if let Err(e) = &result {
+            i2c_errors::record("read", self.address, self.selected, buffer.len(), e);
+        }
+        result
+    }
+
     pub fn write_register(&mut self, register: u16, data: &[u8]) -> Result<()> {
+        if let Some(protocol) = self.strict {
+            self.check_sequencing(protocol, register, data)?;
+        }
         debug!("Writing to register 0x{register:04X}: {data:?}");
 
         // XM125 register protocol: [reg_high, reg_low, data...]
@@ -45,15 +333,23 @@ impl I2cDevice {
         }
         buffer.extend_from_slice(data);
 
-        #[allow(clippy::cast_possible_truncation)] // I2C addresses are 7-bit, safe to cast
-        self.device
-            .write(self.address as u8, &buffer)
-            .map_err(RadarError::I2c)?;
-
-        // Small delay for XM125 processing
-        thread::sleep(Duration::from_millis(1));
-
-        Ok(())
+        if data.is_empty() || !self.verifies(register) {
+            return self.transact_write(&buffer);
+        }
+        for attempt in 1..=WRITE_VERIFY_ATTEMPTS {
+            self.transact_write(&buffer)?;
+            let read = self.read_register(register, data.len())?;
+            if read == data {
+                return Ok(());
+            }
+            warn!(
+                "⚠️  Register 0x{register:04X} read back {read:02X?} after writing {data:02X?} (attempt {attempt}/{WRITE_VERIFY_ATTEMPTS})"
+            );
+        }
+        Err(RadarError::WriteVerifyFailed {
+            register,
+            attempts: WRITE_VERIFY_ATTEMPTS,
+        })
     }
 
     pub fn read_register(&mut self, register: u16, length: usize) -> Result<Vec<u8>> {
@@ -62,20 +358,11 @@ impl I2cDevice {
         // First, write the register address
         #[allow(clippy::cast_possible_truncation)] // Register addresses are 16-bit, safe to cast
         let reg_bytes = [(register >> 8) as u8, register as u8];
-        #[allow(clippy::cast_possible_truncation)] // I2C addresses are 7-bit, safe to cast
-        self.device
-            .write(self.address as u8, &reg_bytes)
-            .map_err(RadarError::I2c)?;
-
-        // Small delay for XM125 processing
-        thread::sleep(Duration::from_millis(1));
+        self.transact_write(&reg_bytes)?;
 
         // Then read the data
         let mut buffer = vec![0u8; length];
-        #[allow(clippy::cast_possible_truncation)] // I2C addresses are 7-bit, safe to cast
-        self.device
-            .read(self.address as u8, &mut buffer)
-            .map_err(RadarError::I2c)?;
+        blocking(|| self.bus_read(&mut buffer))?;
 
         debug!("Read data: {buffer:?}");
         Ok(buffer)
@@ -94,7 +381,7 @@ impl I2cDevice {
         self.write_register(register, write_data)?;
 
         // Wait a bit for processing
-        thread::sleep(Duration::from_millis(5));
+        blocking(|| thread::sleep(Duration::from_millis(5)));
 
         // Read response
         self.read_register(register, read_length)
@@ -307,3 +594,89 @@ impl I2cDevice {
         })
     }
 }
+
+/// Check whether a device ACKs `address` on the bus
+///
+/// Uses an SMBus quick write (empty write), the same probe `i2cdetect` uses for the
+/// STM32 bootloader and XM125 address ranges, so no data reaches the device.
+pub fn probe_address(device_path: &str, address: u16) -> Result<bool> {
+    let mut bus = I2cdev::new(device_path).map_err(|e| {
+        RadarError::Io(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Cannot open I2C device {device_path}: {e}"),
+        ))
+    })?;
+
+    #[allow(clippy::cast_possible_truncation)] // I2C addresses are 7-bit, safe to cast
+    let acked = I2c::write(&mut bus, address as u8, &[]).is_ok();
+    debug!(
+        "I2C probe 0x{address:02X} on {device_path}: {}",
+        if acked { "ACK" } else { "no response" }
+    );
+    Ok(acked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xm125_protocol::detector::PresenceProtocol;
+
+    #[test]
+    fn test_strict_protocol_rejects_writes_while_busy_or_in_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("strict.jsonl");
+        let status = |value: u32| -> [u8; 4] { value.to_be_bytes() };
+        let reset = xm125_protocol::registers::CMD_RESET_MODULE.to_be_bytes();
+
+        let mut recorder = TraceRecorder::create(&path).unwrap();
+        // Busy: the write is refused before it reaches the bus
+        recorder.write(&[0x00, 0x03], None);
+        recorder.read(4, &status(0x8000_0000), None);
+        // Error: only RESET MODULE goes through
+        recorder.write(&[0x00, 0x03], None);
+        recorder.read(4, &status(0x0080_0000), None);
+        recorder.write(&[0x00, 0x03], None);
+        recorder.read(4, &status(0x0080_0000), None);
+        recorder.write(&[[0x01, 0x00].as_slice(), &reset].concat(), None);
+        drop(recorder);
+
+        let mut device = I2cDevice::playback(path.to_str().unwrap(), 0x52, 0.0).unwrap();
+        device.set_strict_protocol(Some(&PresenceProtocol));
+        let busy = device.write_register(0x40, &[0, 0, 0, 1]).unwrap_err();
+        assert!(busy.to_string().contains("busy"), "{busy}");
+        let error = device.write_register(0x40, &[0, 0, 0, 1]).unwrap_err();
+        assert!(error.to_string().contains("reporting an error"), "{error}");
+        device.write_register(0x100, &reset).unwrap();
+    }
+
+    #[test]
+    fn test_write_verify_retries_mismatched_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("verify.jsonl");
+        let start = xm125_protocol::registers::PRESENCE_REG_START_ADDRESS.to_be_bytes();
+        let write = [start.as_slice(), &[0, 0, 1, 0]].concat();
+
+        let mut recorder = TraceRecorder::create(&path).unwrap();
+        // A corrupted write is repeated until it reads back
+        recorder.write(&write, None);
+        recorder.write(&start, None);
+        recorder.read(4, &[0, 0, 3, 0], None);
+        recorder.write(&write, None);
+        recorder.write(&start, None);
+        recorder.read(4, &[0, 0, 1, 0], None);
+        // Commands are not read back
+        recorder.write(&[0x01, 0x00, 0, 0, 0, 1], None);
+        drop(recorder);
+
+        let mut device = I2cDevice::playback(path.to_str().unwrap(), 0x52, 0.0).unwrap();
+        device.set_write_verify(WriteVerify::Critical, &PresenceProtocol);
+        device
+            .write_register(u16::from_be_bytes(start), &[0, 0, 1, 0])
+            .unwrap();
+        device.write_register(0x100, &[0, 0, 0, 1]).unwrap();
+
+        let mut device = I2cDevice::simulated(xm125_protocol::Application::Presence, 0x52);
+        device.set_write_verify(WriteVerify::All, &PresenceProtocol);
+        device.write_register(0x200, &[0, 0, 0, 7]).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+//! Ring of recent I2C errors for postmortem
+//!
+//! A field failure that happens once a week is rarely caught with trace logging enabled.
+//! Every failed bus transfer is therefore kept in a small in-memory ring with its time,
+//! direction, bus address, the register it addressed and the error, whatever the log level.
+//! The ring is printed after a fatal error and included in `snapshot`, so the bus history
+//! leading up to a failure is available after the fact. Only the last [`CAPACITY`] errors
+//! are kept.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+
+/// Errors kept in the ring
+pub const CAPACITY: usize = 32;
+
+/// One failed bus transfer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct I2cErrorRecord {
+    pub at: DateTime<Utc>,
+    /// `read` or `write`
+    pub operation: &'static str,
+    pub address: u16,
+    /// Register the transfer addressed, if known
+    pub register: Option<u16>,
+    pub length: usize,
+    pub error: String,
+}
+
+impl fmt::Display for I2cErrorRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} of {} bytes at 0x{:02X}",
+            self.at.format("%Y-%m-%d %H:%M:%S%.3f"),
+            self.operation,
+            self.length,
+            self.address
+        )?;
+        if let Some(register) = self.register {
+            write!(f, " register 0x{register:04X}")?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+#[derive(Debug)]
+struct Ring {
+    records: VecDeque<I2cErrorRecord>,
+    /// Errors dropped from the front since the start of the run
+    dropped: u64,
+}
+
+impl Ring {
+    const fn new() -> Self {
+        Self {
+            records: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, record: I2cErrorRecord) {
+        if self.records.len() == CAPACITY {
+            self.records.pop_front();
+            self.dropped += 1;
+        }
+        self.records.push_back(record);
+    }
+}
+
+static RING: Mutex<Ring> = Mutex::new(Ring::new());
+
+/// Remember a failed transfer
+pub fn record(
+    operation: &'static str,
+    address: u16,
+    register: Option<u16>,
+    length: usize,
+    error: &impl ToString,
+) {
+    RING.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(I2cErrorRecord {
+            at: Utc::now(),
+            operation,
+            address,
+            register,
+            length,
+            error: error.to_string(),
+        });
+}
+
+/// Recent I2C errors for a report, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecentI2cErrors {
+    /// Older errors no longer in the ring
+    pub dropped: u64,
+    pub errors: Vec<I2cErrorRecord>,
+}
+
+/// The errors in the ring, oldest first
+pub fn recent() -> RecentI2cErrors {
+    let ring = RING.lock().unwrap_or_else(PoisonError::into_inner);
+    RecentI2cErrors {
+        dropped: ring.dropped,
+        errors: ring.records.iter().cloned().collect(),
+    }
+}
+
+/// Log the ring after a fatal error, if any I2C errors happened
+pub fn log_recent() {
+    let recent = recent();
+    if recent.errors.is_empty() {
+        return;
+    }
+    let dropped = match recent.dropped {
+        0 => String::new(),
+        n => format!(" ({n} older ones dropped)"),
+    };
+    log::error!("Last {} I2C errors{dropped}:", recent.errors.len());
+    for record in &recent.errors {
+        log::error!("  {record}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_keeps_the_latest_errors_in_order() {
+        let mut ring = Ring::new();
+        for n in 0..CAPACITY + 3 {
+            ring.push(I2cErrorRecord {
+                at: Utc::now(),
+                operation: "read",
+                address: 0x52,
+                register: Some(n as u16),
+                length: 4,
+                error: "Remote I/O error".to_string(),
+            });
+        }
+        assert_eq!(ring.dropped, 3);
+        assert_eq!(ring.records.len(), CAPACITY);
+        assert_eq!(ring.records.front().unwrap().register, Some(3));
+        assert_eq!(
+            ring.records.back().unwrap().register,
+            Some(CAPACITY as u16 + 2)
+        );
+        let line = ring.records.back().unwrap().to_string();
+        assert!(line.ends_with("read of 4 bytes at 0x52 register 0x0022: Remote I/O error"));
+    }
+}
@@ -0,0 +1,300 @@
+//! Simulated XM125 for development and CI
+//!
+//! `--simulate <distance|presence|breathing>` replaces the I2C bus with an emulation of the
+//! module running that reference application, so every run-mode command can be exercised on
+//! a machine without the hardware. The emulation follows the register protocol: writes to
+//! configuration registers are stored and read back, commands set the busy bit for a short
+//! while, the detector status reports the OK bits once a configuration has been applied, and
+//! the measure counter advances with the configured frame rate. Results come from a scripted
+//! scene: a target drifting between roughly 0.7 m and 2.3 m, a person who is present for 20 s
+//! out of every 30 s, and a breathing rate around 15 breaths per minute. The bootloader is not
+//! emulated, so firmware flashing still needs the module; `sentry` switches the emulated
+//! application in place instead, keeping the scene clock running.
+
+use crate::error::{RadarError, Result};
+use crate::i2c::I2cBackend;
+use log::info;
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::time::{Duration, Instant};
+use xm125_protocol::detector::{protocol_for_application, Application, DetectorProtocol, FlagKind};
+use xm125_protocol::registers::{
+    BREATHING_APP_STATE_DETERMINE_DISTANCE, BREATHING_APP_STATE_ESTIMATE_BREATHING_RATE,
+    BREATHING_APP_STATE_INIT, BREATHING_APP_STATE_NO_PRESENCE, BREATHING_REG_FRAME_RATE_ADDRESS,
+    CMD_MEASURE_DISTANCE, PRESENCE_REG_FRAME_RATE_ADDRESS, REG_APPLICATION_ID, REG_COMMAND,
+    REG_DETECTOR_STATUS, REG_END_CONFIG, REG_MEASURE_COUNTER, REG_PROTOCOL_STATUS,
+    REG_START_CONFIG, REG_VERSION, STATUS_BUSY_MASK,
+};
+
+/// RSS version the simulated firmware reports (1.2.0)
+const RSS_VERSION: u32 = (1 << 16) | (2 << 8);
+/// Time a command keeps the busy bit set
+const COMMAND_TIME: Duration = Duration::from_millis(20);
+/// Module temperature in the result words, °C
+const TEMPERATURE_C: u32 = 24;
+/// Frame rate of a started application before one is configured, Hz
+const DEFAULT_FRAME_RATE_HZ: f64 = 10.0;
+/// Time the breathing application spends locating the chest before estimating
+const BREATHING_SETTLE: Duration = Duration::from_secs(3);
+
+/// Emulated module running one reference application
+#[derive(Debug)]
+pub struct SimulatedXm125 {
+    application: Application,
+    /// Configuration registers as written by the host
+    registers: HashMap<u16, u32>,
+    /// Register addressed by the last address-only write
+    selected: u16,
+    busy_until: Option<Instant>,
+    configured: bool,
+    /// When the continuously running application was started
+    started: Option<Instant>,
+    /// Measurements triggered one at a time (distance)
+    triggered: u32,
+    epoch: Instant,
+}
+
+impl SimulatedXm125 {
+    pub fn new(application: Application) -> Self {
+        info!(
+            "🧪 Simulating an XM125 running the {} (no I2C bus is used)",
+            protocol_for_application(application).name()
+        );
+        Self {
+            application,
+            registers: HashMap::new(),
+            selected: 0,
+            busy_until: None,
+            configured: false,
+            started: None,
+            triggered: 0,
+            epoch: Instant::now(),
+        }
+    }
+
+    fn protocol(&self) -> &'static dyn DetectorProtocol {
+        protocol_for_application(self.application)
+    }
+
+    fn busy(&self) -> bool {
+        self.busy_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    fn command(&mut self, command: u32) {
+        let protocol = self.protocol();
+        self.busy_until = Some(Instant::now() + COMMAND_TIME);
+        if command == protocol.cmd_reset_module() {
+            self.registers.clear();
+            self.configured = false;
+            self.started = None;
+            self.busy_until = None;
+        } else if command == protocol.cmd_apply_configuration() {
+            self.configured = true;
+        } else if self.application == Application::Distance && command == CMD_MEASURE_DISTANCE {
+            self.triggered = self.triggered.wrapping_add(1);
+        } else if Some(command) == protocol.cmd_start() {
+            self.started = Some(Instant::now());
+        } else if Some(command) == protocol.cmd_stop() {
+            self.started = None;
+        }
+    }
+
+    fn status(&self) -> u32 {
+        let flags = self.protocol().status_flags().iter();
+        let ok = if self.configured {
+            flags
+                .filter(|flag| flag.kind == FlagKind::Ok)
+                .fold(0, |mask, flag| mask | flag.mask)
+        } else {
+            // Only the RSS registration has happened after reset
+            self.protocol()
+                .status_flags()
+                .first()
+                .map_or(0, |flag| flag.mask)
+        };
+        if self.busy() {
+            ok | STATUS_BUSY_MASK
+        } else {
+            ok
+        }
+    }
+
+    fn frame_rate_hz(&self) -> f64 {
+        let register = match self.application {
+            Application::Presence => PRESENCE_REG_FRAME_RATE_ADDRESS,
+            Application::Breathing => BREATHING_REG_FRAME_RATE_ADDRESS,
+            Application::Distance => return 0.0,
+        };
+        match self.registers.get(&register) {
+            Some(&mhz) if mhz > 0 => f64::from(mhz) / 1000.0,
+            _ => DEFAULT_FRAME_RATE_HZ,
+        }
+    }
+
+    fn measure_counter(&self) -> u32 {
+        match (self.application, self.started) {
+            (Application::Distance, _) => self.triggered,
+            (_, Some(started)) => (started.elapsed().as_secs_f64() * self.frame_rate_hz()) as u32,
+            (_, None) => 0,
+        }
+    }
+
+    /// Scene time in seconds
+    fn t(&self) -> f64 {
+        self.epoch.elapsed().as_secs_f64()
+    }
+
+    fn distance_result(&self, register: u16) -> u32 {
+        let target_mm = (1500.0 + 800.0 * (TAU * self.t() / 20.0).sin()) as u32;
+        let start = self
+            .registers
+            .get(&REG_START_CONFIG)
+            .copied()
+            .unwrap_or(100);
+        let end = self.registers.get(&REG_END_CONFIG).copied().unwrap_or(3000);
+        let in_range = (start..=end).contains(&target_mm);
+        match register {
+            // Result word: number of distances, temperature in the upper half
+            16 => (TEMPERATURE_C << 16) | u32::from(in_range),
+            17 => target_mm,
+            // Peak strength in dB * 1000, weaker further away
+            27 => (-(20_000 + 10 * target_mm as i32)) as u32,
+            _ => 0,
+        }
+    }
+
+    fn presence_result(&self, register: u16) -> u32 {
+        let t = self.t();
+        let present = self.started.is_some() && t % 30.0 < 20.0;
+        let sway = (TAU * t / 7.0).sin();
+        match register {
+            16 => (TEMPERATURE_C << 16) | if present { 0b11 } else { 0 },
+            17 if present => (1200.0 + 300.0 * sway) as u32,
+            18 if present => (2200.0 + 800.0 * sway) as u32,
+            19 if present => (1600.0 - 400.0 * sway) as u32,
+            18 | 19 => 150,
+            _ => 0,
+        }
+    }
+
+    fn breathing_result(&self, register: u16) -> u32 {
+        let since_start = self.started.map(|started| started.elapsed());
+        let state = match since_start {
+            None => BREATHING_APP_STATE_INIT,
+            Some(elapsed) if elapsed < BREATHING_SETTLE / 3 => BREATHING_APP_STATE_NO_PRESENCE,
+            Some(elapsed) if elapsed < BREATHING_SETTLE => BREATHING_APP_STATE_DETERMINE_DISTANCE,
+            Some(_) => BREATHING_APP_STATE_ESTIMATE_BREATHING_RATE,
+        };
+        let ready = state == BREATHING_APP_STATE_ESTIMATE_BREATHING_RATE;
+        match register {
+            16 => (TEMPERATURE_C << 16) | if ready { 0b11 } else { 0 },
+            17 if ready => (15_000.0 + 1_500.0 * (TAU * self.t() / 60.0).sin()) as u32,
+            18 => state,
+            _ => 0,
+        }
+    }
+
+    fn read_register(&self, register: u16) -> u32 {
+        match register {
+            REG_VERSION => RSS_VERSION,
+            REG_PROTOCOL_STATUS => 0,
+            REG_MEASURE_COUNTER => self.measure_counter(),
+            REG_DETECTOR_STATUS => self.status(),
+            REG_APPLICATION_ID => self.application.app_id(),
+            16..=63 => match self.application {
+                Application::Distance => self.distance_result(register),
+                Application::Presence => self.presence_result(register),
+                Application::Breathing => self.breathing_result(register),
+            },
+            _ => self.registers.get(&register).copied().unwrap_or(0),
+        }
+    }
+}
+
+impl I2cBackend for SimulatedXm125 {
+    fn write(&mut self, _address: u8, data: &[u8]) -> Result<()> {
+        let [high, low, value @ ..] = data else {
+            return Err(RadarError::InvalidParameters(format!(
+                "simulated module: write of {} bytes has no register address",
+                data.len()
+            )));
+        };
+        let register = u16::from_be_bytes([*high, *low]);
+        self.selected = register;
+        if value.is_empty() {
+            return Ok(());
+        }
+        let mut word = [0u8; 4];
+        let len = value.len().min(4);
+        word[4 - len..].copy_from_slice(&value[..len]);
+        let value = u32::from_be_bytes(word);
+        if register == REG_COMMAND {
+            self.command(value);
+        } else {
+            self.registers.insert(register, value);
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<()> {
+        let word = self.read_register(self.selected).to_be_bytes();
+        for (byte, value) in buffer.iter_mut().zip(word.iter().cycle()) {
+            *byte = *value;
+        }
+        Ok(())
+    }
+
+    fn load_application(&mut self, application: Application) -> bool {
+        info!(
+            "🧪 Simulated module now running the {}",
+            protocol_for_application(application).name()
+        );
+        self.application = application;
+        self.registers.clear();
+        self.busy_until = None;
+        self.configured = false;
+        self.started = None;
+        self.triggered = 0;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xm125_protocol::registers::CMD_PRESENCE_START_DETECTOR;
+
+    fn read(sim: &mut SimulatedXm125, register: u16) -> u32 {
+        sim.write(0x52, &register.to_be_bytes()).unwrap();
+        let mut buffer = [0u8; 4];
+        sim.read(0x52, &mut buffer).unwrap();
+        u32::from_be_bytes(buffer)
+    }
+
+    fn write(sim: &mut SimulatedXm125, register: u16, value: u32) {
+        let mut data = register.to_be_bytes().to_vec();
+        data.extend_from_slice(&value.to_be_bytes());
+        sim.write(0x52, &data).unwrap();
+    }
+
+    #[test]
+    fn test_simulated_presence_follows_register_protocol() {
+        let mut sim = SimulatedXm125::new(Application::Presence);
+        let protocol = protocol_for_application(Application::Presence);
+        assert_eq!(read(&mut sim, REG_APPLICATION_ID), 2);
+        assert!(!protocol.is_ready(read(&mut sim, REG_DETECTOR_STATUS)));
+
+        write(&mut sim, PRESENCE_REG_FRAME_RATE_ADDRESS, 50_000);
+        assert_eq!(read(&mut sim, PRESENCE_REG_FRAME_RATE_ADDRESS), 50_000);
+        write(&mut sim, REG_COMMAND, protocol.cmd_apply_configuration());
+        assert!(protocol.is_busy(read(&mut sim, REG_DETECTOR_STATUS)));
+        std::thread::sleep(COMMAND_TIME);
+        let status = read(&mut sim, REG_DETECTOR_STATUS);
+        assert!(protocol.is_ready(status) && !protocol.has_error(status));
+
+        write(&mut sim, REG_COMMAND, CMD_PRESENCE_START_DETECTOR);
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(read(&mut sim, REG_MEASURE_COUNTER) >= 4);
+        assert_eq!(read(&mut sim, 16) >> 16, TEMPERATURE_C);
+    }
+}
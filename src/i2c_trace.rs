@@ -0,0 +1,262 @@
+//! Record and replay of raw I2C transactions
+//!
+//! `--i2c-record` appends every bus write and read of a real session, with its timing and
+//! any bus error, to a JSON-lines trace. `--i2c-playback` answers the same transactions from
+//! that trace instead of the bus, so a session reported from the field can be reproduced
+//! exactly on a desk without the hardware. Playback checks that every write matches the
+//! recording and fails at the first divergence. Recorded gaps between transactions are
+//! replayed scaled by `--i2c-playback-speed` (`0` = no waiting).
+
+use crate::error::RadarError;
+use crate::i2c::I2cBackend;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One bus transaction as recorded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Transaction {
+    Write {
+        /// Milliseconds since the recording started
+        t_ms: f64,
+        data: Vec<u8>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    Read {
+        t_ms: f64,
+        len: usize,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        data: Vec<u8>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+impl Transaction {
+    fn t_ms(&self) -> f64 {
+        match self {
+            Transaction::Write { t_ms, .. } | Transaction::Read { t_ms, .. } => *t_ms,
+        }
+    }
+}
+
+fn replayed_error(message: &str) -> RadarError {
+    RadarError::Io(std::io::Error::other(format!(
+        "I2C error (replayed): {message}"
+    )))
+}
+
+/// Appends the transactions of a live session to a trace file
+#[derive(Debug)]
+pub struct TraceRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl TraceRecorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, RadarError> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        info!("⏺️  Recording I2C transactions to {}", path.display());
+        Ok(Self {
+            writer: BufWriter::new(file),
+            start: Instant::now(),
+        })
+    }
+
+    fn elapsed_ms(&self) -> f64 {
+        self.start.elapsed().as_secs_f64() * 1000.0
+    }
+
+    pub fn write(&mut self, data: &[u8], error: Option<String>) {
+        let transaction = Transaction::Write {
+            t_ms: self.elapsed_ms(),
+            data: data.to_vec(),
+            error,
+        };
+        self.append(&transaction);
+    }
+
+    pub fn read(&mut self, len: usize, data: &[u8], error: Option<String>) {
+        let transaction = Transaction::Read {
+            t_ms: self.elapsed_ms(),
+            len,
+            data: data.to_vec(),
+            error,
+        };
+        self.append(&transaction);
+    }
+
+    fn append(&mut self, transaction: &Transaction) {
+        // A failing trace must not take the session down with it
+        let line = serde_json::to_string(transaction).unwrap_or_default();
+        if let Err(e) = writeln!(self.writer, "{line}").and_then(|()| self.writer.flush()) {
+            debug!("Failed to record I2C transaction: {e}");
+        }
+    }
+}
+
+/// Answers bus transactions from a recorded trace
+#[derive(Debug)]
+pub struct TracePlayer {
+    transactions: VecDeque<Transaction>,
+    /// Position in the trace, for divergence reports
+    index: usize,
+    speed: f64,
+    start: Instant,
+}
+
+impl TracePlayer {
+    /// Load `path`, replaying recorded gaps divided by `speed` (0 = no waiting)
+    pub fn open(path: impl AsRef<Path>, speed: f64) -> Result<Self, RadarError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        let transactions = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(number, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    RadarError::InvalidParameters(format!(
+                        "Invalid I2C trace {} line {}: {e}",
+                        path.display(),
+                        number + 1
+                    ))
+                })
+            })
+            .collect::<Result<VecDeque<Transaction>, _>>()?;
+        info!(
+            "⏯️  Replaying {} I2C transactions from {}",
+            transactions.len(),
+            path.display()
+        );
+        Ok(Self {
+            transactions,
+            index: 0,
+            speed,
+            start: Instant::now(),
+        })
+    }
+
+    /// Next transaction, after waiting until its (time-warped) recorded moment
+    fn next(&mut self, expected: &str) -> Result<Transaction, RadarError> {
+        let transaction = self.transactions.pop_front().ok_or_else(|| {
+            RadarError::InvalidParameters(format!(
+                "I2C trace exhausted after {} transactions (next was a {expected})",
+                self.index
+            ))
+        })?;
+        self.index += 1;
+        if self.speed > 0.0 {
+            let due = Duration::from_secs_f64(transaction.t_ms().max(0.0) / 1000.0 / self.speed);
+            if let Some(wait) = due.checked_sub(self.start.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+        Ok(transaction)
+    }
+
+    fn diverged(&self, message: String) -> RadarError {
+        RadarError::InvalidParameters(format!(
+            "I2C playback diverged at transaction {}: {message}",
+            self.index
+        ))
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> Result<(), RadarError> {
+        match self.next("write")? {
+            Transaction::Write {
+                data: recorded,
+                error,
+                ..
+            } => {
+                if recorded != data {
+                    return Err(self.diverged(format!("wrote {data:?}, recorded {recorded:?}")));
+                }
+                error.map_or(Ok(()), |message| Err(replayed_error(&message)))
+            }
+            Transaction::Read { .. } => {
+                Err(self.diverged(format!("wrote {data:?}, recorded a read")))
+            }
+        }
+    }
+
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<(), RadarError> {
+        match self.next("read")? {
+            Transaction::Read {
+                len, data, error, ..
+            } => {
+                if len != buffer.len() {
+                    return Err(
+                        self.diverged(format!("read {} bytes, recorded {len}", buffer.len()))
+                    );
+                }
+                if let Some(message) = error {
+                    return Err(replayed_error(&message));
+                }
+                if data.len() != len {
+                    return Err(self.diverged(format!(
+                        "recorded read of {len} bytes holds {} bytes",
+                        data.len()
+                    )));
+                }
+                buffer.copy_from_slice(&data);
+                Ok(())
+            }
+            Transaction::Write { data, .. } => Err(self.diverged(format!(
+                "read {} bytes, recorded a write of {data:?}",
+                buffer.len()
+            ))),
+        }
+    }
+}
+
+impl I2cBackend for TracePlayer {
+    fn write(&mut self, _address: u8, data: &[u8]) -> Result<(), RadarError> {
+        TracePlayer::write(self, data)
+    }
+
+    fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), RadarError> {
+        TracePlayer::read(self, buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorded_session_replays_and_detects_divergence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+
+        let mut recorder = TraceRecorder::create(&path).unwrap();
+        recorder.write(&[0x00, 0x03], None);
+        recorder.read(4, &[0x00, 0x00, 0x00, 0x80], None);
+        recorder.write(&[0x01, 0x00], Some("Remote I/O error".to_string()));
+        drop(recorder);
+
+        let mut player = TracePlayer::open(&path, 0.0).unwrap();
+        player.write(&[0x00, 0x03]).unwrap();
+        let mut buffer = [0u8; 4];
+        player.read(&mut buffer).unwrap();
+        assert_eq!(buffer, [0x00, 0x00, 0x00, 0x80]);
+        assert!(player.write(&[0x01, 0x00]).is_err());
+        assert!(player.read(&mut buffer).is_err());
+
+        let mut player = TracePlayer::open(&path, 0.0).unwrap();
+        let error = player.write(&[0x00, 0x04]).unwrap_err().to_string();
+        assert!(error.contains("diverged at transaction 1"), "{error}");
+    }
+}
@@ -0,0 +1,299 @@
+//! Crash-safe file writing
+//!
+//! Battery-powered units can lose power at any moment. Data files are therefore created
+//! atomically (write to a temporary file, fsync, rename), every record reaches the kernel
+//! in a single write, and file contents are fsynced at a configurable interval so that at
+//! most one interval of data is lost and the file never ends in a torn record.
+
+use log::{debug, warn};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Temporary path used while a file is being created atomically
+fn temp_path(path: &Path) -> std::path::PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Fsync the directory containing `path` so that a rename is durable
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()
+}
+
+/// Atomically replace `path` with `contents` (write temp, fsync, rename, fsync dir)
+pub fn write_atomic(path: impl AsRef<Path>, contents: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    let temp = temp_path(path);
+    {
+        let mut file = File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp, path)?;
+    sync_parent_dir(path)
+}
+
+/// Append-only data file with record-atomic writes and periodic fsync
+///
+/// Bytes written through [`Write::write`] are staged in memory and only reach the file
+/// on [`Write::flush`], as a single write, so flushing once per record keeps records
+/// whole. A failed write is rolled back and the staged record discarded, so the file never
+/// holds a torn record and a later flush cannot resurrect it. A `sync_interval` of zero
+/// fsyncs on every flush. With a commit interval (see [`JournaledFile::with_commit_interval`])
+/// flushes between records only reach the file once the interval has passed, batching many
+/// records into one write. With `--encrypt` (see [`JournaledFile::with_encryption`]) staged
+/// records pass through an age stream on commit and the stream is sealed on close; the
+/// stream cannot skip a lost chunk, so after a failed encrypted write the file takes no
+/// further records. With
+/// `--sign-key` (see [`JournaledFile::with_signature`]) the closed file is signed.
+#[derive(Debug)]
+pub struct JournaledFile {
+    file: File,
+    pending: Vec<u8>,
+    /// File length after the last complete write
+    committed: u64,
+    sync_interval: Duration,
+    last_sync: Instant,
+    dirty: bool,
+    /// Minimum time between writes of staged records (None = only on sync or close)
+    commit_interval: Option<Duration>,
+    last_commit: Instant,
+    /// Encrypts staged records on their way to the file
+    #[cfg(feature = "encrypt")]
+    encryptor: Option<crate::encrypt::StreamEncryptor>,
+    /// An encrypted write failed; later chunks would not decrypt after the lost one
+    #[cfg(feature = "encrypt")]
+    encryption_broken: bool,
+    /// File to sign once it is closed
+    #[cfg(feature = "sign")]
+    sign_on_close: Option<std::path::PathBuf>,
+}
+
+impl JournaledFile {
+    /// Create (or replace) `path` atomically as an empty file and open it for appending
+    pub fn create(path: impl AsRef<Path>, sync_interval: Duration) -> io::Result<Self> {
+        let path = path.as_ref();
+        write_atomic(path, b"")?;
+        let file = OpenOptions::new().append(true).open(path)?;
+        debug!(
+            "Journaled file {} opened (fsync every {:.1}s)",
+            path.display(),
+            sync_interval.as_secs_f32()
+        );
+
+        Ok(Self {
+            file,
+            pending: Vec::new(),
+            committed: 0,
+            sync_interval,
+            last_sync: Instant::now(),
+            dirty: false,
+            commit_interval: Some(Duration::ZERO),
+            last_commit: Instant::now(),
+            #[cfg(feature = "encrypt")]
+            encryptor: None,
+            #[cfg(feature = "encrypt")]
+            encryption_broken: false,
+            #[cfg(feature = "sign")]
+            sign_on_close: None,
+        })
+    }
+
+    /// Write staged records at most every `interval` (None = only on sync or close)
+    pub fn with_commit_interval(mut self, interval: Option<Duration>) -> Self {
+        self.commit_interval = interval;
+        self
+    }
+
+    /// Encrypt the file to `target`, if given
+    #[cfg(feature = "encrypt")]
+    pub fn with_encryption(
+        mut self,
+        target: Option<&crate::encrypt::EncryptTarget>,
+    ) -> io::Result<Self> {
+        self.encryptor = target
+            .map(crate::encrypt::StreamEncryptor::new)
+            .transpose()?;
+        Ok(self)
+    }
+
+    /// Sign `path` (this file) with the device key on close, if one is loaded
+    #[cfg(feature = "sign")]
+    pub fn with_signature(mut self, path: &Path) -> Self {
+        self.sign_on_close = crate::sign::device_key().map(|_| path.to_path_buf());
+        self
+    }
+
+    /// Bytes of complete records written, including those staged for the next commit
+    pub fn len(&self) -> u64 {
+        self.committed + self.pending.len() as u64
+    }
+
+    /// Fsync everything written so far
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.commit()?;
+        if self.dirty {
+            self.file.sync_data()?;
+            self.dirty = false;
+        }
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+
+    /// Write all staged records to the file, fsyncing if the sync interval has passed
+    fn commit(&mut self) -> io::Result<()> {
+        self.last_commit = Instant::now();
+        #[cfg(feature = "encrypt")]
+        if self.encryption_broken {
+            if !self.pending.is_empty() {
+                self.pending.clear();
+                return Err(io::Error::other(
+                    "encrypted data file takes no more records after a failed write",
+                ));
+            }
+        } else if let Some(encryptor) = &mut self.encryptor {
+            let plaintext = std::mem::take(&mut self.pending);
+            self.pending = encryptor.encrypt(&plaintext).inspect_err(|_| {
+                self.encryption_broken = true;
+            })?;
+        }
+        if !self.pending.is_empty() {
+            let written = self.file.write_all(&self.pending);
+            let len = self.pending.len() as u64;
+            self.pending.clear();
+            if let Err(e) = written {
+                let _ = self.file.set_len(self.committed);
+                #[cfg(feature = "encrypt")]
+                if self.encryptor.is_some() {
+                    warn!("Encrypted data file write failed; no further records are written to it");
+                    self.encryption_broken = true;
+                }
+                return Err(e);
+            }
+            self.committed += len;
+            self.dirty = true;
+        }
+        if self.dirty && self.last_sync.elapsed() >= self.sync_interval {
+            self.file.sync_data()?;
+            self.dirty = false;
+            self.last_sync = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+impl Write for JournaledFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.commit_interval {
+            Some(interval) if self.last_commit.elapsed() >= interval => self.commit(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for JournaledFile {
+    fn drop(&mut self) {
+        #[cfg(feature = "encrypt")]
+        if let Some(encryptor) = self.encryptor.take() {
+            if self.encryption_broken {
+                self.pending.clear();
+            } else {
+                match encryptor.finish(&self.pending) {
+                    Ok(ciphertext) => self.pending = ciphertext,
+                    Err(e) => {
+                        warn!("Failed to seal encrypted data file: {e}");
+                        // Never let the staged plaintext reach the encrypted file
+                        self.pending.clear();
+                    }
+                }
+            }
+        }
+        if let Err(e) = self.sync() {
+            warn!("Failed to sync data file on close: {e}");
+        }
+        #[cfg(feature = "sign")]
+        if let (Some(path), Some(key)) = (&self.sign_on_close, crate::sign::device_key()) {
+            if let Err(e) = key.sign_file(path) {
+                warn!("Failed to sign data file {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_reach_file_on_flush_only() {
+        let dir = std::env::temp_dir().join(format!("xm125-journal-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.csv");
+
+        fs::write(&path, "stale").unwrap();
+        let mut file = JournaledFile::create(&path, Duration::ZERO).unwrap();
+        assert!(!temp_path(&path).exists());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        file.write_all(b"1,2\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        file.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1,2\n");
+
+        drop(file);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_commit_interval_batches_records_until_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.csv");
+
+        let mut file = JournaledFile::create(&path, Duration::ZERO)
+            .unwrap()
+            .with_commit_interval(None);
+        file.write_all(b"1,2\n").unwrap();
+        file.flush().unwrap();
+        file.write_all(b"3,4\n").unwrap();
+        file.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        assert_eq!(file.len(), 8);
+
+        file.sync().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "1,2\n3,4\n");
+    }
+
+    #[cfg(feature = "encrypt")]
+    #[test]
+    fn test_failed_encrypted_write_stops_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let identity = age::x25519::Identity::generate();
+        let target =
+            crate::encrypt::parse_encrypt_target(&format!("age:{}", identity.to_public())).unwrap();
+        let mut file = JournaledFile::create(dir.path().join("data.csv"), Duration::ZERO)
+            .unwrap()
+            .with_encryption(Some(&target))
+            .unwrap();
+        // A full disk: the first chunk of the age stream is lost
+        file.file = OpenOptions::new().append(true).open("/dev/full").unwrap();
+
+        file.write_all(b"timestamp,distance_m\n").unwrap();
+        assert!(file.flush().is_err());
+        file.write_all(b"2024-01-01 00:00:00.000,1.25\n").unwrap();
+        let error = file.flush().unwrap_err();
+        assert!(error.to_string().contains("no more records"), "{error}");
+        assert_eq!(file.len(), 0);
+        drop(file);
+    }
+}
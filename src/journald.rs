@@ -0,0 +1,162 @@
+//! Structured measurement fields for systemd-journald (`journald` feature)
+//!
+//! When stderr is connected to the journal (systemd sets `JOURNAL_STREAM` for services),
+//! every measurement is also sent as a native journal entry carrying `MEASUREMENT_TYPE`,
+//! `DISTANCE_M`, `PRESENCE` and `DEVICE_ID` fields, so ad-hoc queries such as
+//! `journalctl MEASUREMENT_TYPE=presence PRESENCE=1` work on devices without the full
+//! data pipeline. Without the feature, or when not running under journald, these calls
+//! do nothing.
+
+use crate::radar::{DistanceMeasurement, PresenceMeasurement};
+
+/// Send a distance measurement to the journal
+pub fn log_distance(result: &DistanceMeasurement) {
+    #[cfg(feature = "journald")]
+    native::send(&[
+        (
+            "MESSAGE",
+            format!(
+                "distance {:.3}m strength {:.1}",
+                result.distance, result.strength
+            ),
+        ),
+        ("MEASUREMENT_TYPE", "distance".to_string()),
+        ("DISTANCE_M", format!("{:.3}", result.distance)),
+        ("SIGNAL_STRENGTH", format!("{:.1}", result.strength)),
+        ("TEMPERATURE_C", result.temperature.to_string()),
+    ]);
+    #[cfg(not(feature = "journald"))]
+    let _ = result;
+}
+
+/// Send a presence measurement to the journal
+pub fn log_presence(result: &PresenceMeasurement) {
+    #[cfg(feature = "journald")]
+    native::send(&[
+        (
+            "MESSAGE",
+            format!(
+                "presence {} at {:.3}m",
+                if result.presence_detected {
+                    "detected"
+                } else {
+                    "clear"
+                },
+                result.presence_distance
+            ),
+        ),
+        ("MEASUREMENT_TYPE", "presence".to_string()),
+        ("PRESENCE", u8::from(result.presence_detected).to_string()),
+        ("DISTANCE_M", format!("{:.3}", result.presence_distance)),
+        ("INTRA_SCORE", format!("{:.2}", result.intra_presence_score)),
+        ("INTER_SCORE", format!("{:.2}", result.inter_presence_score)),
+    ]);
+    #[cfg(not(feature = "journald"))]
+    let _ = result;
+}
+
+#[cfg(feature = "journald")]
+mod native {
+    use crate::discovery::device_id;
+    use log::debug;
+    use std::os::fd::AsFd;
+    use std::os::unix::fs::MetadataExt;
+    use std::os::unix::net::UnixDatagram;
+    use std::sync::OnceLock;
+
+    /// journald native protocol socket
+    const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+    const IDENTIFIER: &str = "xm125-radar-monitor";
+
+    struct Journal {
+        socket: UnixDatagram,
+        device_id: String,
+    }
+
+    /// Journal connection, opened on first use when stderr goes to journald
+    fn journal() -> Option<&'static Journal> {
+        static JOURNAL: OnceLock<Option<Journal>> = OnceLock::new();
+        JOURNAL
+            .get_or_init(|| {
+                if !stderr_is_journal() {
+                    return None;
+                }
+                let socket = UnixDatagram::unbound().ok()?;
+                socket.connect(JOURNAL_SOCKET).ok()?;
+                debug!("Sending structured measurement fields to journald");
+                Some(Journal {
+                    socket,
+                    device_id: device_id(),
+                })
+            })
+            .as_ref()
+    }
+
+    /// True when stderr is the stream systemd connected to the journal (`JOURNAL_STREAM`)
+    fn stderr_is_journal() -> bool {
+        let Ok(stream) = std::env::var("JOURNAL_STREAM") else {
+            return false;
+        };
+        let Some((dev, ino)) = stream.split_once(':') else {
+            return false;
+        };
+        let Ok(fd) = std::io::stderr().as_fd().try_clone_to_owned() else {
+            return false;
+        };
+        match std::fs::File::from(fd).metadata() {
+            Ok(meta) => dev.parse() == Ok(meta.dev()) && ino.parse() == Ok(meta.ino()),
+            Err(_) => false,
+        }
+    }
+
+    /// Serialize fields in the journal native protocol
+    ///
+    /// Values containing a newline use the binary form (name, newline, little-endian length).
+    pub(super) fn encode(fields: &[(&str, String)]) -> Vec<u8> {
+        let mut datagram = Vec::new();
+        for (name, value) in fields {
+            datagram.extend_from_slice(name.as_bytes());
+            if value.contains('\n') {
+                datagram.push(b'\n');
+                datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            } else {
+                datagram.push(b'=');
+            }
+            datagram.extend_from_slice(value.as_bytes());
+            datagram.push(b'\n');
+        }
+        datagram
+    }
+
+    pub(super) fn send(fields: &[(&str, String)]) {
+        let Some(journal) = journal() else {
+            return;
+        };
+        let mut entry = vec![
+            ("PRIORITY", "6".to_string()),
+            ("SYSLOG_IDENTIFIER", IDENTIFIER.to_string()),
+            ("DEVICE_ID", journal.device_id.clone()),
+        ];
+        entry.extend(fields.iter().map(|(name, value)| (*name, value.clone())));
+        if let Err(e) = journal.socket.send(&encode(&entry)) {
+            debug!("Failed to send journal entry: {e}");
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::encode;
+
+        #[test]
+        fn test_encode_plain_and_binary_fields() {
+            let datagram = encode(&[
+                ("PRESENCE", "1".to_string()),
+                ("MESSAGE", "a\nb".to_string()),
+            ]);
+            let mut expected = b"PRESENCE=1\nMESSAGE\n".to_vec();
+            expected.extend_from_slice(&3u64.to_le_bytes());
+            expected.extend_from_slice(b"a\nb\n");
+            assert_eq!(datagram, expected);
+        }
+    }
+}
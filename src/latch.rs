@@ -0,0 +1,66 @@
+//! Presence latching for PLC-style consumers
+//!
+//! Building-automation controllers expect PIR-like behaviour: once presence is reported
+//! it stays asserted for a minimum hold time even if the motion scores drop in between.
+//! While latched, the last detected distance is reported as well.
+
+use crate::radar::PresenceMeasurement;
+use std::time::{Duration, Instant};
+
+/// Holds a detected presence state for at least `hold`
+#[derive(Debug)]
+pub struct PresenceLatch {
+    hold: Duration,
+    asserted_until: Option<Instant>,
+    last_distance: f32,
+}
+
+impl PresenceLatch {
+    pub fn new(hold: Duration) -> Self {
+        Self {
+            hold,
+            asserted_until: None,
+            last_distance: 0.0,
+        }
+    }
+
+    /// Latched presence state at `now` given the raw detection
+    fn update(&mut self, detected: bool, now: Instant) -> bool {
+        if detected {
+            self.asserted_until = Some(now + self.hold);
+            return true;
+        }
+        self.asserted_until.is_some_and(|until| now < until)
+    }
+
+    /// Replace the measurement's presence state (and distance) with the latched values
+    pub fn apply(&mut self, measurement: &mut PresenceMeasurement) {
+        let detected = measurement.presence_detected;
+        if detected {
+            self.last_distance = measurement.presence_distance;
+        }
+        if self.update(detected, Instant::now()) && !detected {
+            measurement.presence_detected = true;
+            measurement.presence_distance = self.last_distance;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latch_holds_presence() {
+        let mut latch = PresenceLatch::new(Duration::from_millis(500));
+        let start = Instant::now();
+        assert!(!latch.update(false, start));
+        assert!(latch.update(true, start));
+        assert!(latch.update(false, start + Duration::from_millis(499)));
+        assert!(!latch.update(false, start + Duration::from_millis(500)));
+
+        // A new detection restarts the hold time
+        assert!(latch.update(true, start + Duration::from_millis(600)));
+        assert!(latch.update(false, start + Duration::from_millis(1000)));
+    }
+}
@@ -0,0 +1,113 @@
+//! Driver for the Acconeer XM125 radar module
+//!
+//! The `xm125-radar-monitor` binary is a command-line front end over this library; services
+//! that want to drive the module themselves can depend on the crate instead of running the
+//! binary. [`radar::XM125Radar`] owns the connection: it resets the module through its GPIO
+//! lines, checks the running firmware, configures the presence, distance or breathing
+//! detector and takes measurements. The detectors themselves are [`radar::presence::PresenceDetector`]
+//! and [`radar::distance::DistanceDetector`]; [`firmware::FirmwareManager`] flashes and
+//! verifies the reference applications, and [`i2c::I2cDevice`] can talk to the real bus, replay
+//! a recorded trace or simulate a module.
+//!
+//! ```
+//! use xm125_radar_monitor::gpio::XM125GpioPins;
+//! use xm125_radar_monitor::i2c::I2cDevice;
+//! use xm125_radar_monitor::radar::{DetectorMode, XM125Radar};
+//! use xm125_radar_monitor::xm125_protocol::Application;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> xm125_radar_monitor::error::Result<()> {
+//! // On hardware: I2cDevice::new("/dev/i2c-2", 0x52)?
+//! let i2c = I2cDevice::simulated(Application::Presence, 0x52);
+//! let mut radar = XM125Radar::new(i2c, XM125GpioPins::default());
+//! radar.connect()?;
+//! radar.set_detector_mode(DetectorMode::Presence);
+//! let result = radar.measure_presence().await?;
+//! println!("present: {} at {:.2}m", result.presence_detected, result.presence_distance);
+//! # Ok(())
+//! # }
+//! ```
+
+#![allow(dead_code)] // Allow dead code during restructure
+
+// Route all console output through `--plain` sanitization (textually scoped to every module);
+// measurement output follows `--flush` and diagnostics follow `--log-file`
+macro_rules! println {
+    () => { $crate::console::print_line("") };
+    ($($arg:tt)*) => { $crate::console::print_line(&$crate::plain::sanitize(&format!($($arg)*))) };
+}
+macro_rules! eprintln {
+    () => { $crate::log_file::write_line("") };
+    ($($arg:tt)*) => { $crate::log_file::write_line(&$crate::plain::sanitize(&format!($($arg)*))) };
+}
+
+pub use xm125_protocol;
+
+mod aggregate;
+mod aliases;
+#[doc(hidden)]
+pub mod app;
+mod app_id_cache;
+mod baseline;
+mod bootloader;
+mod calibration;
+mod cli;
+mod commands;
+mod config;
+mod console;
+mod dataset;
+mod device_lock;
+mod diagnose;
+mod discovery;
+mod display;
+#[cfg(feature = "encrypt")]
+mod encrypt;
+pub mod error;
+mod fault;
+mod fifo;
+pub mod firmware;
+mod flash_state;
+mod fleet;
+pub mod gpio;
+mod health;
+mod heartbeat;
+pub mod i2c;
+mod i2c_errors;
+mod i2c_sim;
+mod i2c_trace;
+mod journal;
+mod journald;
+mod latch;
+mod limits;
+mod log_file;
+mod log_level;
+mod manifest;
+mod monitoring;
+pub mod mqtt;
+mod pacing;
+mod paths;
+mod plain;
+mod presence_events;
+mod privacy;
+mod quality;
+pub mod radar;
+mod realtime;
+mod sd_notify;
+mod sentry;
+mod service;
+mod session;
+mod settings;
+mod shm;
+mod sign;
+mod snapshot;
+mod spool;
+mod stats;
+mod status_file;
+mod telemetry;
+mod timeline;
+pub mod timeouts;
+mod timestamps;
+mod timing;
+mod upload;
+mod version;
+mod zones;
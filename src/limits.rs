@@ -0,0 +1,118 @@
+//! Failsafe limits for continuous monitoring
+//!
+//! An unattended `--continuous` run left without `--count` would otherwise record until the
+//! eMMC fills. These limits end the run cleanly at the end of a measurement cycle, so the
+//! CSV export is flushed and the session sidecar records why the run stopped.
+
+use crate::cli::{parse_duration, parse_size};
+use clap::Args;
+use std::fmt;
+use std::time::Duration;
+
+/// Safety limits shared by the continuous monitoring commands
+#[derive(Args, Debug, Clone, Copy, Default)]
+pub struct RunLimits {
+    /// Stop after running this long
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = parse_duration,
+        requires = "continuous",
+        help = "Stop continuous monitoring after this long, e.g. 8h or 7d"
+    )]
+    pub max_duration: Option<Duration>,
+
+    /// Stop after recording this many measurements
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "continuous",
+        help = "Stop continuous monitoring after this many recorded measurements"
+    )]
+    pub max_records: Option<u64>,
+
+    /// Stop once the CSV export reaches this size
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        requires = "save_to",
+        help = "Stop when the CSV export reaches this size, e.g. 500M"
+    )]
+    pub max_file_size: Option<u64>,
+}
+
+/// The limit that ended a run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitReached {
+    Duration(Duration),
+    Records(u64),
+    FileSize(u64),
+}
+
+impl fmt::Display for LimitReached {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitReached::Duration(limit) => {
+                write!(f, "max duration of {}s reached", limit.as_secs())
+            }
+            LimitReached::Records(limit) => write!(f, "max records of {limit} reached"),
+            LimitReached::FileSize(limit) => write!(f, "max file size of {limit} bytes reached"),
+        }
+    }
+}
+
+impl RunLimits {
+    /// First limit reached after `elapsed` with `records` recorded and a `file_size` byte export
+    pub fn check(
+        &self,
+        elapsed: Duration,
+        records: u64,
+        file_size: Option<u64>,
+    ) -> Option<LimitReached> {
+        if let Some(limit) = self.max_duration.filter(|limit| elapsed >= *limit) {
+            return Some(LimitReached::Duration(limit));
+        }
+        if let Some(limit) = self.max_records.filter(|limit| records >= *limit) {
+            return Some(LimitReached::Records(limit));
+        }
+        self.max_file_size
+            .filter(|limit| file_size.is_some_and(|size| size >= *limit))
+            .map(LimitReached::FileSize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(parse_duration("8h"), Ok(Duration::from_secs(8 * 3600)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1m"), Ok(Duration::from_secs(60)));
+        assert!(parse_duration("0s").is_err());
+        assert_eq!(parse_size("500M"), Ok(500 << 20));
+        assert_eq!(parse_size("64KiB"), Ok(64 << 10));
+        assert_eq!(parse_size("1024"), Ok(1024));
+
+        let limits = RunLimits {
+            max_duration: Some(Duration::from_secs(3600)),
+            max_records: Some(100),
+            max_file_size: Some(1 << 20),
+        };
+        assert_eq!(limits.check(Duration::from_secs(10), 5, Some(1024)), None);
+        assert_eq!(
+            limits.check(Duration::from_secs(10), 100, None),
+            Some(LimitReached::Records(100))
+        );
+        assert_eq!(
+            limits.check(Duration::from_secs(10), 5, Some(2 << 20)),
+            Some(LimitReached::FileSize(1 << 20))
+        );
+        assert_eq!(
+            RunLimits::default().check(Duration::MAX, u64::MAX, Some(u64::MAX)),
+            None
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Destination for diagnostics
+//!
+//! Measurement data is the only thing written to stdout, so a pipeline reading it never sees
+//! a status or error line. Log records and the console diagnostics printed with `eprintln!`
+//! go to stderr, or with `--log-file` are appended to that file instead, keeping them apart
+//! from both the data and anything else the service manager collects from stderr.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
+
+static LOG_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Send all further diagnostics to `path`, appending to it
+pub fn open(path: &str) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    // Only set once at startup; a second call keeps the first file
+    let _ = LOG_FILE.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Write one diagnostic line to the log file, or stderr without one
+pub fn write_line(line: &str) {
+    let _ = writeln!(Diagnostics, "{line}");
+}
+
+/// Writer for the diagnostics destination, usable as a logger target
+pub struct Diagnostics;
+
+impl Write for Diagnostics {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match LOG_FILE.get() {
+            Some(file) => file.lock().unwrap_or_else(|e| e.into_inner()).write(buf),
+            None => io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match LOG_FILE.get() {
+            Some(file) => file.lock().unwrap_or_else(|e| e.into_inner()).flush(),
+            None => io::stderr().flush(),
+        }
+    }
+}
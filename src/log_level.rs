@@ -0,0 +1,59 @@
+//! Runtime log verbosity
+//!
+//! The logger passes every record on and the global maximum level alone decides what is
+//! logged, so verbosity can change mid-session: restarting a long continuous run to get debug
+//! logs would destroy the conditions that triggered the problem. SIGUSR1 cycles
+//! info → debug → trace → info, and the `serve` API sets a level with
+//! `{"kind":"log_level","level":"debug"}`.
+
+use log::{info, LevelFilter};
+use tokio::signal::unix::{signal, SignalKind};
+
+/// Level SIGUSR1 switches to from `level`
+fn next(level: LevelFilter) -> LevelFilter {
+    match level {
+        LevelFilter::Debug => LevelFilter::Trace,
+        LevelFilter::Trace => LevelFilter::Info,
+        _ => LevelFilter::Debug,
+    }
+}
+
+/// Log records up to `level` from now on
+pub fn set(level: LevelFilter) {
+    // Announced even when switching below info
+    log::set_max_level(level.max(LevelFilter::Info));
+    info!("🔊 Log level now {level}");
+    log::set_max_level(level);
+}
+
+/// Current log level
+pub fn current() -> LevelFilter {
+    log::max_level()
+}
+
+/// Cycle the log level on every SIGUSR1 instead of terminating the process
+pub fn cycle_on_sigusr1() {
+    match signal(SignalKind::user_defined1()) {
+        Ok(mut signals) => {
+            tokio::spawn(async move {
+                while signals.recv().await.is_some() {
+                    set(next(current()));
+                }
+            });
+        }
+        Err(e) => log::warn!("SIGUSR1 log level switching unavailable: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sigusr1_cycles_info_debug_trace() {
+        assert_eq!(next(LevelFilter::Info), LevelFilter::Debug);
+        assert_eq!(next(LevelFilter::Debug), LevelFilter::Trace);
+        assert_eq!(next(LevelFilter::Trace), LevelFilter::Info);
+        assert_eq!(next(LevelFilter::Warn), LevelFilter::Debug);
+    }
+}
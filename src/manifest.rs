@@ -0,0 +1,248 @@
+//! Firmware staging area manifest
+//!
+//! `firmware manifest generate` records the size and CRC32 of every firmware binary in
+//! `--firmware-path` in `manifest.toml`. At startup the binaries are checked against it
+//! so that a missing or corrupt binary is reported long before it is needed mid-update
+//! in the field; `firmware update` refuses to flash a binary that fails the check.
+
+use crate::error::RadarError;
+use crate::firmware::{crc32, FirmwareType};
+use crate::journal::write_atomic;
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Manifest file name inside the firmware directory
+pub const MANIFEST_FILENAME: &str = "manifest.toml";
+
+/// Firmware types whose binaries are staged
+const STAGED_FIRMWARE: [FirmwareType; 3] = [
+    FirmwareType::Distance,
+    FirmwareType::Presence,
+    FirmwareType::Breathing,
+];
+
+/// Recorded size and checksum of one binary
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ManifestEntry {
+    pub file: String,
+    pub size: u64,
+    /// CRC32 as 8 hex digits
+    pub crc32: String,
+}
+
+/// Manifest of the firmware staging area
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirmwareManifest {
+    pub generated_at: DateTime<Utc>,
+    #[serde(rename = "binary", default)]
+    pub binaries: Vec<ManifestEntry>,
+}
+
+/// Result of checking one binary against the manifest
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinaryStatus {
+    Ok,
+    /// Listed in the manifest but not on disk
+    Missing,
+    /// On disk but not listed in the manifest
+    Unlisted,
+    /// Size or checksum differs from the manifest
+    Corrupt {
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for BinaryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryStatus::Ok => write!(f, "ok"),
+            BinaryStatus::Missing => write!(f, "missing"),
+            BinaryStatus::Unlisted => write!(f, "not in manifest"),
+            BinaryStatus::Corrupt { expected, actual } => {
+                write!(f, "corrupt (expected {expected}, found {actual})")
+            }
+        }
+    }
+}
+
+/// Path of the manifest for a firmware directory
+pub fn manifest_path(firmware_path: &str) -> PathBuf {
+    Path::new(firmware_path).join(MANIFEST_FILENAME)
+}
+
+/// Size and checksum of a binary on disk (None if it doesn't exist)
+fn describe(firmware_path: &str, file: &str) -> Result<Option<ManifestEntry>, RadarError> {
+    let path = Path::new(firmware_path).join(file);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read(&path)?;
+    Ok(Some(ManifestEntry {
+        file: file.to_string(),
+        size: data.len() as u64,
+        crc32: format!("{:08x}", crc32(&data)),
+    }))
+}
+
+impl FirmwareManifest {
+    /// Describe the binaries currently staged in `firmware_path`
+    pub fn generate(firmware_path: &str) -> Result<Self, RadarError> {
+        let mut binaries = Vec::new();
+        for firmware in STAGED_FIRMWARE {
+            match describe(firmware_path, firmware.binary_filename())? {
+                Some(entry) => binaries.push(entry),
+                None => warn!(
+                    "⚠️  {} binary not found, not added to manifest",
+                    firmware.binary_filename()
+                ),
+            }
+        }
+        Ok(Self {
+            generated_at: Utc::now(),
+            binaries,
+        })
+    }
+
+    /// Load the manifest for `firmware_path` (None if there isn't one)
+    pub fn load(firmware_path: &str) -> Result<Option<Self>, RadarError> {
+        let path = manifest_path(firmware_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&path)?;
+        toml::from_str(&contents)
+            .map(Some)
+            .map_err(|e| RadarError::FirmwareError {
+                message: format!("Invalid firmware manifest {}: {e}", path.display()),
+            })
+    }
+
+    /// Write the manifest atomically into `firmware_path`
+    pub fn save(&self, firmware_path: &str) -> Result<PathBuf, RadarError> {
+        let path = manifest_path(firmware_path);
+        let contents = toml::to_string_pretty(self).map_err(|e| RadarError::FirmwareError {
+            message: format!("Failed to serialize firmware manifest: {e}"),
+        })?;
+        write_atomic(&path, contents.as_bytes())?;
+        Ok(path)
+    }
+
+    /// Check a single binary against its manifest entry
+    pub fn check_file(&self, firmware_path: &str, file: &str) -> Result<BinaryStatus, RadarError> {
+        let actual = describe(firmware_path, file)?;
+        let expected = self.binaries.iter().find(|entry| entry.file == file);
+        Ok(match (expected, actual) {
+            (None, None) => BinaryStatus::Missing,
+            (None, Some(_)) => BinaryStatus::Unlisted,
+            (Some(_), None) => BinaryStatus::Missing,
+            (Some(expected), Some(actual)) if *expected == actual => BinaryStatus::Ok,
+            (Some(expected), Some(actual)) => BinaryStatus::Corrupt {
+                expected: format!("{} bytes crc32 {}", expected.size, expected.crc32),
+                actual: format!("{} bytes crc32 {}", actual.size, actual.crc32),
+            },
+        })
+    }
+
+    /// Check every binary listed in the manifest or staged on disk
+    pub fn check(&self, firmware_path: &str) -> Result<Vec<(String, BinaryStatus)>, RadarError> {
+        let mut files: Vec<String> = self.binaries.iter().map(|e| e.file.clone()).collect();
+        for firmware in STAGED_FIRMWARE {
+            let file = firmware.binary_filename().to_string();
+            if !files.contains(&file) && Path::new(firmware_path).join(&file).exists() {
+                files.push(file);
+            }
+        }
+        files
+            .into_iter()
+            .map(|file| {
+                let status = self.check_file(firmware_path, &file)?;
+                Ok((file, status))
+            })
+            .collect()
+    }
+}
+
+/// Startup check of the staging area: warns about missing or corrupt binaries
+pub fn startup_check(firmware_path: &str) {
+    let manifest = match FirmwareManifest::load(firmware_path) {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => {
+            debug!("No firmware manifest in {firmware_path}, skipping integrity check");
+            return;
+        }
+        Err(e) => {
+            warn!("⚠️  {e}");
+            return;
+        }
+    };
+
+    match manifest.check(firmware_path) {
+        Ok(results) => {
+            let mut healthy = true;
+            for (file, status) in results.iter().filter(|(_, s)| *s != BinaryStatus::Ok) {
+                healthy = false;
+                warn!("⚠️  Firmware binary {file}: {status}");
+            }
+            if healthy {
+                debug!(
+                    "Firmware staging area matches manifest ({} binaries)",
+                    results.len()
+                );
+            }
+        }
+        Err(e) => warn!("⚠️  Firmware integrity check failed: {e}"),
+    }
+}
+
+/// Refuse to flash a binary that fails the manifest check (no manifest = not checked)
+pub fn ensure_binary_intact(firmware_path: &str, firmware: FirmwareType) -> Result<(), RadarError> {
+    let Some(manifest) = FirmwareManifest::load(firmware_path)? else {
+        return Ok(());
+    };
+    match manifest.check_file(firmware_path, firmware.binary_filename())? {
+        BinaryStatus::Ok => {
+            info!("✅ {} matches firmware manifest", firmware.binary_filename());
+            Ok(())
+        }
+        status => Err(RadarError::FirmwareError {
+            message: format!(
+                "{} failed manifest check: {status} (regenerate with 'firmware manifest generate' if intended)",
+                firmware.binary_filename()
+            ),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_detects_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let firmware_path = dir.path().to_str().unwrap();
+        let binary = dir.path().join(FirmwareType::Presence.binary_filename());
+        fs::write(&binary, b"firmware image").unwrap();
+
+        FirmwareManifest::generate(firmware_path)
+            .unwrap()
+            .save(firmware_path)
+            .unwrap();
+        let manifest = FirmwareManifest::load(firmware_path).unwrap().unwrap();
+        assert!(ensure_binary_intact(firmware_path, FirmwareType::Presence).is_ok());
+
+        fs::write(&binary, b"firmware imagf").unwrap();
+        assert!(matches!(
+            manifest
+                .check_file(firmware_path, FirmwareType::Presence.binary_filename())
+                .unwrap(),
+            BinaryStatus::Corrupt { .. }
+        ));
+        assert!(ensure_binary_intact(firmware_path, FirmwareType::Presence).is_err());
+    }
+}
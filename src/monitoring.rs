@@ -3,29 +3,369 @@
 //! This module handles continuous measurement operations for both distance and presence
 //! detection, including CSV export and FIFO output integration.
 
-use crate::cli::Cli;
+use crate::aggregate::{DistanceAggregator, DistanceWindow, PresenceAggregator, PresenceWindow};
+use crate::baseline::BaselineSurvey;
+use crate::cli::{Cli, OutputFormat};
+use crate::dataset::{CaptureMetadata, LabeledCapture};
+use crate::device_lock;
 use crate::display::{
-    display_distance_result, display_presence_result, write_distance_to_fifo,
-    write_presence_to_fifo,
+    breathing_csv_row, display_baseline_report, display_breathing_result, display_distance_result,
+    display_distance_window, display_hardware_event, display_presence_event,
+    display_presence_result, display_presence_window, display_zone_event, distance_csv_header,
+    distance_csv_row, distance_fifo_json, distance_window_csv_row, peaks_csv_field,
+    presence_csv_row, presence_fifo_json, presence_window_csv_row, write_breathing_to_fifo,
+    write_distance_to_fifo, write_distance_window_to_fifo, write_presence_event_to_fifo,
+    write_presence_to_fifo, write_presence_window_to_fifo, write_zone_event_to_fifo,
+    BREATHING_CSV_HEADER, DISTANCE_WINDOW_CSV_HEADER, PRESENCE_CSV_HEADER,
+    PRESENCE_EVENT_CSV_HEADER, PRESENCE_WINDOW_CSV_HEADER, ZONE_EVENT_CSV_HEADER,
 };
 use crate::error::RadarError;
-use crate::fifo::FifoWriter;
-use crate::radar::{PresenceMeasurement, XM125Radar};
-use chrono::Utc;
+use crate::fifo::{FifoFormat, FifoWriter};
+use crate::gpio::{GpioEdgeInput, GpioValue, XM125GpioController};
+use crate::journal::JournaledFile;
+use crate::latch::PresenceLatch;
+use crate::limits::RunLimits;
+use crate::mqtt::MqttSink;
+use crate::pacing::{interval_ms, Pacer};
+use crate::presence_events::{PresenceEventConfig, PresenceEventEngine};
+use crate::quality::{distance_issues, presence_issues, QualityGate};
+use crate::radar::{DistanceMeasurement, PresenceMeasurement, XM125Radar};
+use crate::sd_notify;
+use crate::session::SessionMetadata;
+use crate::settings::Reloader;
+use crate::shm::ShmWriter;
+use crate::spool::{Spool, SpooledMeasurement};
+use crate::status_file::StatusReporter;
+use crate::timeline;
+use crate::timestamps;
+use crate::timing::ClockDriftTracker;
+use crate::zones::{ZoneSet, ZoneTracker};
 use indicatif::{ProgressBar, ProgressStyle};
-use log::info;
-use std::fs::File;
-use tokio::time::{sleep, Duration};
+use log::{debug, info, warn};
+use std::path::PathBuf;
+use std::time::Instant;
+use tokio::time::Duration;
+
+/// Options shared by the continuous monitoring loops
+pub struct MonitorOptions<'a> {
+    /// Number of measurements to record (None = until interrupted)
+    pub count: Option<u32>,
+    /// Time between measurements
+    pub interval: Duration,
+    /// Measurements to take and discard before recording starts
+    pub warmup: u32,
+    /// Correlate the device measure counter with host timestamps
+    pub clock_drift: bool,
+    /// Presence hold time in milliseconds (0 = no latching, presence only)
+    pub latch_ms: u64,
+    /// Host GPIO carrying the module's detection output (presence only)
+    pub gpio_wakeup: Option<u32>,
+    /// CSV export path
+    pub save_to: Option<&'a str>,
+    /// Distance zones for zone-change events (distance only)
+    pub zones: Option<ZoneSet>,
+    /// Output zone-change events instead of every measurement
+    pub zone_events_only: bool,
+    /// Emit one min/max/mean aggregate per window instead of every measurement
+    pub window: Option<Duration>,
+    /// Report presence transitions instead of every measurement (presence only)
+    pub presence_events: Option<PresenceEventConfig>,
+    /// Failsafe limits ending the run early
+    pub limits: RunLimits,
+    /// Shared-memory segment receiving every admitted measurement
+    pub shm: Option<&'a ShmWriter>,
+    /// MQTT broker receiving every admitted measurement
+    pub mqtt: Option<&'a MqttSink>,
+    /// Applies the settings file again on SIGHUP (`--config`)
+    pub reloader: Option<Reloader>,
+}
+
+/// Write one row of pre-formatted CSV fields to a data file
+fn write_csv_line(writer: &mut csv::Writer<JournaledFile>, line: &str) -> Result<(), RadarError> {
+    writer
+        .write_record(line.split(','))
+        .map_err(|e| RadarError::DeviceError {
+            message: format!("Failed to write CSV record: {e}"),
+        })?;
+    writer.flush().map_err(|e| RadarError::DeviceError {
+        message: format!("Failed to flush CSV writer: {e}"),
+    })
+}
+
+/// Create a crash-safe CSV data file
+fn create_data_file(cli: &Cli, filename: &str) -> Result<JournaledFile, RadarError> {
+    cli.create_data_file(filename)
+        .map_err(|e| RadarError::DeviceError {
+            message: format!("Failed to create CSV file: {e}"),
+        })
+}
+
+/// Open the `--spool` file, if configured
+fn open_spool(cli: &Cli) -> Result<Option<Spool>, RadarError> {
+    cli.output
+        .spool
+        .as_deref()
+        .map(|path| Spool::open(path, cli.output.spool_max_kb.saturating_mul(1024)))
+        .transpose()
+}
+
+/// Write one measurement row to the CSV export
+///
+/// With `--spool`, rows carry a `delayed` column and a failed write is reported as
+/// undelivered instead of ending monitoring.
+fn write_csv_record(
+    writer: &mut csv::Writer<JournaledFile>,
+    mut fields: Vec<String>,
+    delayed: bool,
+    cli: &Cli,
+) -> Result<bool, RadarError> {
+    let spooling = cli.output.spool.is_some();
+    if spooling {
+        fields.push(delayed.to_string());
+    }
+    let written = writer
+        .write_record(&fields)
+        .and_then(|()| writer.flush().map_err(csv::Error::from));
+    match written {
+        Ok(()) => Ok(true),
+        Err(e) if spooling => {
+            warn!("Failed to write CSV record: {e}");
+            Ok(false)
+        }
+        Err(e) => Err(RadarError::DeviceError {
+            message: format!("Failed to write CSV record: {e}"),
+        }),
+    }
+}
+
+/// Send one measurement to the FIFO
+///
+/// With `--spool`, a real-time JSON FIFO is a record sink: every record is written (replayed
+/// ones marked `delayed`) and `Some(false)` reports that no reader took it. Otherwise the
+/// FIFO keeps its rate-limited behaviour, skips replayed records and returns `None`.
+fn write_fifo_record(
+    writer: &mut FifoWriter,
+    mut json: serde_json::Value,
+    delayed: bool,
+    cli: &Cli,
+    write_live: impl FnOnce(&mut FifoWriter),
+) -> Option<bool> {
+    let record_sink = cli.output.spool.is_some()
+        && writer.is_realtime()
+        && matches!(cli.output.fifo_format, FifoFormat::Json);
+    if !record_sink {
+        if !delayed {
+            write_live(writer);
+        }
+        return None;
+    }
+
+    if delayed {
+        json["delayed"] = serde_json::Value::Bool(true);
+    }
+    Some(writer.deliver_json(&json).unwrap_or_else(|e| {
+        warn!("Failed to write FIFO record: {e}");
+        false
+    }))
+}
+
+/// True if at least one record sink took the measurement, or none is configured
+fn record_delivered(csv: Option<bool>, fifo: Option<bool>) -> bool {
+    match (csv, fifo) {
+        (None, None) => true,
+        (csv, fifo) => csv.unwrap_or(false) || fifo.unwrap_or(false),
+    }
+}
+
+/// Write one distance measurement to the CSV export and FIFO; false if no sink took it
+fn write_distance_record(
+    result: &DistanceMeasurement,
+    timestamp: &str,
+    delayed: bool,
+    cli: &Cli,
+    csv_writer: &mut Option<csv::Writer<JournaledFile>>,
+    fifo_writer: Option<&mut FifoWriter>,
+) -> Result<bool, RadarError> {
+    let csv = csv_writer
+        .as_mut()
+        .map(|writer| {
+            let mut fields = vec![
+                timestamp.to_string(),
+                format!("{:.3}", result.distance),
+                format!("{:.1}", result.strength),
+                format!("{:.1}", result.temperature),
+                format!("{:.2}", result.strength_db),
+            ];
+            fields.extend(result.peaks.as_deref().map(peaks_csv_field));
+            fields.extend(
+                result
+                    .config_generation
+                    .map(|generation| generation.to_string()),
+            );
+            write_csv_record(writer, fields, delayed, cli)
+        })
+        .transpose()?;
+    let fifo = fifo_writer.and_then(|writer| {
+        let json = distance_fifo_json(result, timestamp);
+        write_fifo_record(writer, json, delayed, cli, |writer| {
+            write_distance_to_fifo(writer, result, &cli.output.fifo_format);
+        })
+    });
+    Ok(record_delivered(csv, fifo))
+}
+
+/// Write one presence measurement to the CSV export and FIFO; false if no sink took it
+fn write_presence_record(
+    result: &PresenceMeasurement,
+    measurement_count: u32,
+    timestamp: &str,
+    delayed: bool,
+    cli: &Cli,
+    csv_writer: &mut Option<csv::Writer<JournaledFile>>,
+    fifo_writer: Option<&mut FifoWriter>,
+) -> Result<bool, RadarError> {
+    let csv = csv_writer
+        .as_mut()
+        .map(|writer| {
+            let (signal_quality, confidence) = calculate_signal_metrics(result);
+            let mut fields = vec![
+                timestamp.to_string(),
+                measurement_count.to_string(),
+                result.presence_detected.to_string(),
+                format!("{:.3}", result.presence_distance),
+                format!("{:.2}", result.intra_presence_score),
+                format!("{:.2}", result.inter_presence_score),
+                signal_quality.to_string(),
+                confidence.to_string(),
+            ];
+            fields.extend(
+                result
+                    .config_generation
+                    .map(|generation| generation.to_string()),
+            );
+            write_csv_record(writer, fields, delayed, cli)
+        })
+        .transpose()?;
+    let fifo = fifo_writer.and_then(|writer| {
+        let json = presence_fifo_json(result, timestamp);
+        write_fifo_record(writer, json, delayed, cli, |writer| {
+            write_presence_to_fifo(writer, result, &cli.output.fifo_format);
+        })
+    });
+    Ok(record_delivered(csv, fifo))
+}
+
+/// Spool an undelivered measurement, or replay the spool once a measurement got through
+fn spool_or_replay(
+    spool: &mut Option<Spool>,
+    delivered: bool,
+    record: impl FnOnce() -> SpooledMeasurement,
+    replay: impl FnMut(&SpooledMeasurement) -> Result<bool, RadarError>,
+) -> Result<(), RadarError> {
+    match spool {
+        Some(spool) if delivered => spool.replay(replay).map(|_| ()),
+        Some(spool) => spool.push(&record()),
+        None => Ok(()),
+    }
+}
+
+/// Log the warm-up phase before it starts
+fn log_warmup(warmup: u32) {
+    if warmup > 0 {
+        info!("🔥 Discarding {warmup} warm-up measurement(s) before recording");
+    }
+}
+
+/// Correlate the device measure counter with the host clock after a measurement
+fn track_clock(
+    radar: &mut XM125Radar,
+    tracker: &mut Option<ClockDriftTracker>,
+) -> Result<(), RadarError> {
+    if let Some(tracker) = tracker {
+        let host_time = Instant::now();
+        tracker.record(host_time, radar.read_measure_counter()?);
+    }
+    Ok(())
+}
+
+/// Log the clock drift summary and attach it to the session metadata
+fn report_clock_drift(tracker: Option<&ClockDriftTracker>, session: &mut SessionMetadata) {
+    let Some(report) = tracker.and_then(ClockDriftTracker::report) else {
+        return;
+    };
+
+    info!(
+        "⏱️  Host interval {:.1}ms ± {:.1}ms (max {:.1}ms), device {:.2} Hz over {} frames",
+        report.host_interval_mean_ms,
+        report.host_jitter_ms,
+        report.host_interval_max_ms,
+        report.device_rate_hz,
+        report.device_frames
+    );
+    if let Some(drift_ppm) = report.drift_ppm {
+        info!("⏱️  Device frame clock drift vs host: {drift_ppm:+.0} ppm");
+    }
+    if report.degraded {
+        warn!(
+            "⚠️  Sampling regularity degraded ({} late samples) - check host CPU load",
+            report.late_samples
+        );
+    }
+    session.clock = Some(report);
+}
+
+/// Emit the GPIO writes and resets recorded since the last call and add them to the session
+fn emit_hardware_events(cli: &Cli, session: &mut SessionMetadata) {
+    for event in timeline::drain() {
+        if !cli.output.quiet {
+            display_hardware_event(&event, &cli.output.format);
+        }
+        session.hardware_events.push(event);
+    }
+}
+
+/// Write the session metadata sidecar next to the CSV export, if any
+fn finish_session(session: &mut SessionMetadata, save_to: Option<&str>) -> Result<(), RadarError> {
+    session.finish();
+    if let Some(filename) = save_to {
+        session.write_sidecar(filename)?;
+    }
+    Ok(())
+}
+
+/// Status file reporter for a continuous `mode` run (inactive without `--status-file`)
+fn status_reporter(cli: &Cli, mode: &str) -> StatusReporter {
+    StatusReporter::new(
+        cli.output.status_file.as_deref(),
+        Duration::from_secs_f32(cli.output.status_interval),
+        mode,
+    )
+}
 
 /// Monitor distance detection continuously
 pub async fn monitor_distance_continuous(
     radar: &mut XM125Radar,
     cli: &Cli,
-    count: Option<u32>,
-    interval: u64,
-    save_to: Option<&str>,
+    options: MonitorOptions<'_>,
     mut fifo_writer: Option<&mut FifoWriter>,
 ) -> Result<(), RadarError> {
+    let MonitorOptions {
+        count,
+        interval,
+        warmup,
+        clock_drift,
+        latch_ms: _,
+        gpio_wakeup: _,
+        save_to,
+        ref zones,
+        zone_events_only,
+        window,
+        presence_events: _,
+        limits,
+        shm,
+        mqtt,
+        mut reloader,
+    } = options;
     let total_measurements = count.unwrap_or(u32::MAX);
     let mut measurement_count = 0u32;
 
@@ -47,91 +387,319 @@ pub async fn monitor_distance_continuous(
 
     // Setup CSV writer if requested
     let mut csv_writer = if let Some(filename) = save_to {
-        let file = File::create(filename).map_err(|e| RadarError::DeviceError {
-            message: format!("Failed to create CSV file: {e}"),
-        })?;
+        let file = create_data_file(cli, filename)?;
         let mut writer = csv::Writer::from_writer(file);
 
         // Write CSV header
-        writer
-            .write_record([
+        if window.is_some() {
+            write_csv_line(&mut writer, DISTANCE_WINDOW_CSV_HEADER)?;
+        } else {
+            let mut header = vec![
                 "timestamp",
                 "distance_m",
                 "signal_strength",
                 "temperature_c",
-            ])
-            .map_err(|e| RadarError::DeviceError {
-                message: format!("Failed to write CSV header: {e}"),
-            })?;
+                "signal_strength_db",
+            ];
+            if radar.config.max_peaks > 1 {
+                header.push("peaks");
+            }
+            if !cli.config.is_empty() {
+                header.push("config_generation");
+            }
+            if cli.output.spool.is_some() {
+                header.push("delayed");
+            }
+            writer
+                .write_record(&header)
+                .map_err(|e| RadarError::DeviceError {
+                    message: format!("Failed to write CSV header: {e}"),
+                })?;
+        }
         Some(writer)
     } else {
         None
     };
 
+    // CSV on stdout is streamed: header once, then one row per measurement (or event)
+    let stream_csv = matches!(cli.output.format, OutputFormat::Csv);
+    if stream_csv {
+        if zone_events_only {
+            println!("{ZONE_EVENT_CSV_HEADER}");
+        } else if window.is_some() {
+            println!("{DISTANCE_WINDOW_CSV_HEADER}");
+        } else {
+            println!("{}", distance_csv_header(radar.config.max_peaks > 1));
+        }
+    }
+    let mut zone_tracker = zones.clone().map(ZoneTracker::new);
+    let mut aggregator = window.map(DistanceAggregator::new);
+
     info!("🚀 Starting continuous distance monitoring...");
     if let Some(count) = count {
-        info!("📊 Taking {count} measurements every {interval}ms");
+        info!(
+            "📊 Taking {count} measurements every {:.1}ms",
+            interval_ms(interval)
+        );
     } else {
-        info!("📊 Continuous monitoring every {interval}ms (Ctrl+C to stop)");
+        info!(
+            "📊 Continuous monitoring every {:.1}ms (Ctrl+C to stop)",
+            interval_ms(interval)
+        );
+    }
+
+    // Measurements right after calibration are frequently unreliable
+    let mut session = SessionMetadata::new("distance", count, interval);
+    emit_hardware_events(cli, &mut session);
+    let mut pacer = Pacer::new(interval);
+    log_warmup(warmup);
+    for _ in 0..warmup {
+        radar.measure_distance().await?;
+        session.warmup_discarded += 1;
+        pacer.wait().await;
     }
 
+    let mut quality_gate = QualityGate::from_cli(cli)?;
+    let mut spool = open_spool(cli)?;
+
+    // Distance measurements are host-triggered, so no nominal device frame rate
+    let mut clock_tracker = clock_drift.then(|| ClockDriftTracker::new(None));
+
+    device_lock::catch_terminate();
+    let started = Instant::now();
+    let mut status = status_reporter(cli, "distance");
     while measurement_count < total_measurements {
-        let result = radar.measure_distance().await?;
-        let timestamp_full = Utc::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string();
+        if let Some(reloader) = reloader.as_mut() {
+            reloader.reload_if_requested(radar).await;
+        }
+        let result = radar
+            .measure_distance()
+            .await
+            .inspect_err(|e| status.error(e))?;
+        track_clock(radar, &mut clock_tracker)?;
+        let timestamp_full = timestamps::now();
+        // Measurements rejected by the quality gate are kept out of all outputs
+        let admitted = quality_gate.admit("distance", &distance_issues(&result), &result)?;
 
-        // Display result unless quiet mode
-        if !cli.output.quiet {
+        // Stream CSV rows, otherwise display result unless quiet mode
+        if zone_events_only || aggregator.is_some() {
+            // Only zone changes and window aggregates are reported below
+        } else if admitted && stream_csv {
+            println!("{}", distance_csv_row(&result));
+        } else if admitted && !cli.output.quiet {
             display_distance_result(&result, &cli.output.format);
         }
 
-        // CSV output
-        if let Some(ref mut writer) = csv_writer {
-            writer
-                .write_record([
-                    &timestamp_full,
-                    &format!("{:.3}", result.distance),
-                    &format!("{:.1}", result.strength),
-                    &format!("{:.1}", result.temperature),
-                ])
-                .map_err(|e| RadarError::DeviceError {
-                    message: format!("Failed to write CSV record: {e}"),
-                })?;
-            writer.flush().map_err(|e| RadarError::DeviceError {
-                message: format!("Failed to flush CSV writer: {e}"),
-            })?;
+        // Zone transitions; readings without a target leave the zone unchanged
+        let has_target = admitted && !result.measure_error && result.distance > 0.0;
+        if let Some(event) = zone_tracker
+            .as_mut()
+            .filter(|_| has_target)
+            .and_then(|tracker| tracker.update(result.distance))
+        {
+            if zone_events_only || !stream_csv {
+                display_zone_event(&event, &cli.output.format);
+            } else {
+                info!(
+                    "🚪 Zone: {} → {} ({:.2}m)",
+                    event.from.as_deref().unwrap_or("-"),
+                    event.to,
+                    event.distance_m
+                );
+            }
+            if let Some(writer) = fifo_writer.as_deref() {
+                write_zone_event_to_fifo(writer, &event, &cli.output.fifo_format);
+            }
         }
 
-        // FIFO output
-        if let Some(ref mut writer) = fifo_writer {
-            write_distance_to_fifo(writer, &result, &cli.output.fifo_format);
+        // Window aggregates replace per-measurement records in every sink
+        if let Some(aggregator) = aggregator.as_mut() {
+            if let Some(closed) = admitted.then(|| aggregator.push(&result)).flatten() {
+                emit_distance_window(&closed, cli, &mut csv_writer, fifo_writer.as_deref())?;
+            }
+        } else if admitted {
+            let delivered = write_distance_record(
+                &result,
+                &timestamp_full,
+                false,
+                cli,
+                &mut csv_writer,
+                fifo_writer.as_deref_mut(),
+            )?;
+            spool_or_replay(
+                &mut spool,
+                delivered,
+                || SpooledMeasurement::Distance {
+                    sequence: measurement_count + 1,
+                    measurement: result.clone(),
+                },
+                |record| match record {
+                    SpooledMeasurement::Distance { measurement, .. } => write_distance_record(
+                        measurement,
+                        &timestamps::format_at(measurement.timestamp),
+                        true,
+                        cli,
+                        &mut csv_writer,
+                        fifo_writer.as_deref_mut(),
+                    ),
+                    SpooledMeasurement::Presence { .. } => {
+                        warn!("Discarding spooled presence measurement during distance monitoring");
+                        Ok(true)
+                    }
+                },
+            )?;
+        }
+
+        // Shared memory and MQTT always carry the latest reading, even when aggregating
+        if let Some(shm) = shm.filter(|_| admitted) {
+            shm.publish_distance(&result);
+        }
+        if let Some(mqtt) = mqtt.filter(|_| admitted) {
+            mqtt.publish_distance(&result);
         }
 
         measurement_count += 1;
+        session.measurements = measurement_count;
+        session.quality_failed = quality_gate.failed;
+        emit_hardware_events(cli, &mut session);
+        status.measured(radar.state(), &session);
+        sd_notify::watchdog();
 
         // Update progress bar
         if let Some(ref pb) = progress {
             pb.set_position(u64::from(measurement_count));
         }
 
+        if should_stop(
+            &limits,
+            started,
+            measurement_count,
+            &csv_writer,
+            &mut session,
+        ) {
+            break;
+        }
+
         // Break if we've reached the count
         if count.is_some() && measurement_count >= total_measurements {
             break;
         }
 
         // Wait for next measurement
-        sleep(Duration::from_millis(interval)).await;
+        pacer.wait().await;
+    }
+
+    // Report the partial last window
+    if let Some(closed) = aggregator.as_mut().and_then(DistanceAggregator::flush) {
+        emit_distance_window(&closed, cli, &mut csv_writer, fifo_writer.as_deref())?;
     }
 
     // Finish progress bar
     if let Some(pb) = progress {
         pb.finish_with_message("✅ Distance monitoring completed");
     }
+    session.late_cycles = pacer.late_cycles();
+    report_clock_drift(clock_tracker.as_ref(), &mut session);
+    status.finish(&session);
+    finish_session(&mut session, save_to)?;
 
     // Print summary
     if let Some(filename) = save_to {
-        println!("💾 Results saved to: {filename}");
+        eprintln!("💾 Results saved to: {filename}");
+    }
+
+    Ok(())
+}
+
+/// Longest single wait on the detection GPIO before checking for SIGTERM again
+///
+/// SIGTERM may be delivered to any runtime thread, so the blocked poll cannot rely on being
+/// interrupted by it.
+const DETECTION_WAIT_SLICE: Duration = Duration::from_millis(500);
+
+/// Sleep on the detection GPIO until the module signals presence, SIGTERM asks the session
+/// to stop or `--max-duration` passes
+fn wait_for_detection(
+    pin: &mut GpioEdgeInput,
+    limits: &RunLimits,
+    started: Instant,
+) -> Result<(), RadarError> {
+    let remaining = || {
+        limits.max_duration.map_or(DETECTION_WAIT_SLICE, |max| {
+            max.saturating_sub(started.elapsed())
+        })
+    };
+    debug!("💤 Waiting for the detection GPIO");
+    loop {
+        let slice = remaining().min(DETECTION_WAIT_SLICE);
+        if crate::i2c::blocking(|| pin.wait_for(GpioValue::High, slice))? {
+            debug!("⚡ Detection GPIO asserted, reading results");
+            return Ok(());
+        }
+        if device_lock::stop_requested() || remaining().is_zero() {
+            return Ok(());
+        }
+    }
+}
+
+/// Check for a takeover or the failsafe limits after a measurement, recording why the run stops
+fn should_stop(
+    limits: &RunLimits,
+    started: Instant,
+    measurement_count: u32,
+    csv_writer: &Option<csv::Writer<JournaledFile>>,
+    session: &mut SessionMetadata,
+) -> bool {
+    if device_lock::stop_requested() {
+        warn!("⏹️  Stopping continuous monitoring: terminated");
+        session.stop_reason = Some("terminated".to_string());
+        return true;
+    }
+    let file_size = csv_writer.as_ref().map(|writer| writer.get_ref().len());
+    let Some(reached) = limits.check(started.elapsed(), u64::from(measurement_count), file_size)
+    else {
+        return false;
+    };
+    warn!("⏹️  Stopping continuous monitoring: {reached}");
+    session.stop_reason = Some(reached.to_string());
+    true
+}
+
+/// Send a distance window aggregate to stdout, the CSV export and the FIFO
+fn emit_distance_window(
+    window: &DistanceWindow,
+    cli: &Cli,
+    csv_writer: &mut Option<csv::Writer<JournaledFile>>,
+    fifo_writer: Option<&FifoWriter>,
+) -> Result<(), RadarError> {
+    if matches!(cli.output.format, OutputFormat::Csv) || !cli.output.quiet {
+        display_distance_window(window, &cli.output.format);
+    }
+    if let Some(writer) = csv_writer {
+        write_csv_line(writer, &distance_window_csv_row(window))?;
     }
+    if let Some(writer) = fifo_writer {
+        write_distance_window_to_fifo(writer, window, &cli.output.fifo_format);
+    }
+    Ok(())
+}
 
+/// Send a presence window aggregate to stdout, the CSV export and the FIFO
+fn emit_presence_window(
+    window: &PresenceWindow,
+    cli: &Cli,
+    csv_writer: &mut Option<csv::Writer<JournaledFile>>,
+    fifo_writer: Option<&FifoWriter>,
+) -> Result<(), RadarError> {
+    if matches!(cli.output.format, OutputFormat::Csv) || !cli.output.quiet {
+        display_presence_window(window, &cli.output.format);
+    }
+    if let Some(writer) = csv_writer {
+        write_csv_line(writer, &presence_window_csv_row(window))?;
+    }
+    if let Some(writer) = fifo_writer {
+        write_presence_window_to_fifo(writer, window, &cli.output.fifo_format);
+    }
     Ok(())
 }
 
@@ -155,24 +723,37 @@ fn setup_progress_bar(cli: &Cli, count: Option<u32>) -> Option<ProgressBar> {
 }
 
 /// Setup CSV writer for presence monitoring
-fn setup_presence_csv_writer(filename: &str) -> Result<csv::Writer<File>, RadarError> {
-    let file = File::create(filename).map_err(|e| RadarError::DeviceError {
-        message: format!("Failed to create CSV file: {e}"),
-    })?;
+fn setup_presence_csv_writer(
+    cli: &Cli,
+    filename: &str,
+    windowed: bool,
+) -> Result<csv::Writer<JournaledFile>, RadarError> {
+    let file = create_data_file(cli, filename)?;
     let mut writer = csv::Writer::from_writer(file);
+    if windowed {
+        write_csv_line(&mut writer, PRESENCE_WINDOW_CSV_HEADER)?;
+        return Ok(writer);
+    }
 
     // Write CSV header
+    let mut header = vec![
+        "timestamp",
+        "measurement_number",
+        "presence_detected",
+        "presence_distance_m",
+        "intra_score",
+        "inter_score",
+        "signal_quality",
+        "confidence",
+    ];
+    if !cli.config.is_empty() {
+        header.push("config_generation");
+    }
+    if cli.output.spool.is_some() {
+        header.push("delayed");
+    }
     writer
-        .write_record([
-            "timestamp",
-            "measurement_number",
-            "presence_detected",
-            "presence_distance_m",
-            "intra_score",
-            "inter_score",
-            "signal_quality",
-            "confidence",
-        ])
+        .write_record(&header)
         .map_err(|e| RadarError::DeviceError {
             message: format!("Failed to write CSV header: {e}"),
         })?;
@@ -210,116 +791,479 @@ fn calculate_signal_metrics(result: &PresenceMeasurement) -> (&'static str, &'st
 }
 
 /// Process a single presence measurement (display, CSV, FIFO output)
+///
+/// Returns false when no record sink took the measurement.
 fn process_presence_measurement(
     result: &PresenceMeasurement,
     measurement_count: u32,
     timestamp: &str,
     cli: &Cli,
-    csv_writer: &mut Option<csv::Writer<File>>,
-    fifo_writer: &mut Option<&mut FifoWriter>,
-) -> Result<(), RadarError> {
-    // Display result unless quiet mode
-    if !cli.output.quiet {
+    csv_writer: &mut Option<csv::Writer<JournaledFile>>,
+    fifo_writer: Option<&mut FifoWriter>,
+) -> Result<bool, RadarError> {
+    // Stream CSV rows (header printed once at start), otherwise display unless quiet mode
+    if matches!(cli.output.format, OutputFormat::Csv) {
+        println!("{}", presence_csv_row(result));
+    } else if !cli.output.quiet {
         display_presence_result(result, &cli.output.format);
     }
 
-    // CSV output
-    if let Some(ref mut writer) = csv_writer {
-        let (signal_quality, confidence) = calculate_signal_metrics(result);
-
-        writer
-            .write_record([
-                timestamp,
-                &measurement_count.to_string(),
-                &result.presence_detected.to_string(),
-                &format!("{:.3}", result.presence_distance),
-                &format!("{:.2}", result.intra_presence_score),
-                &format!("{:.2}", result.inter_presence_score),
-                signal_quality,
-                confidence,
-            ])
-            .map_err(|e| RadarError::DeviceError {
-                message: format!("Failed to write CSV record: {e}"),
-            })?;
-        writer.flush().map_err(|e| RadarError::DeviceError {
-            message: format!("Failed to flush CSV writer: {e}"),
-        })?;
-    }
-
-    // FIFO output
-    if let Some(ref mut writer) = fifo_writer {
-        write_presence_to_fifo(writer, result, &cli.output.fifo_format);
-    }
-
-    Ok(())
+    write_presence_record(
+        result,
+        measurement_count,
+        timestamp,
+        false,
+        cli,
+        csv_writer,
+        fifo_writer,
+    )
 }
 
 /// Monitor presence detection continuously
 pub async fn monitor_presence_continuous(
     radar: &mut XM125Radar,
     cli: &Cli,
-    count: Option<u32>,
-    interval: u64,
-    save_to: Option<&str>,
+    options: MonitorOptions<'_>,
     mut fifo_writer: Option<&mut FifoWriter>,
 ) -> Result<(), RadarError> {
+    let MonitorOptions {
+        count,
+        interval,
+        warmup,
+        clock_drift,
+        latch_ms,
+        gpio_wakeup,
+        save_to,
+        zones: _,
+        zone_events_only: _,
+        window,
+        presence_events,
+        limits,
+        shm,
+        mqtt,
+        mut reloader,
+    } = options;
     let total_measurements = count.unwrap_or(u32::MAX);
     let mut measurement_count = 0u32;
 
     // Setup components
     let progress = setup_progress_bar(cli, count);
     let mut csv_writer = if let Some(filename) = save_to {
-        Some(setup_presence_csv_writer(filename)?)
+        Some(setup_presence_csv_writer(cli, filename, window.is_some())?)
     } else {
         None
     };
 
+    // CSV on stdout is streamed: header once, then one row per measurement (or event)
+    if matches!(cli.output.format, OutputFormat::Csv) {
+        if presence_events.is_some() {
+            println!("{PRESENCE_EVENT_CSV_HEADER}");
+        } else if window.is_some() {
+            println!("{PRESENCE_WINDOW_CSV_HEADER}");
+        } else {
+            println!("{PRESENCE_CSV_HEADER}");
+        }
+    }
+    let mut aggregator = window.map(PresenceAggregator::new);
+    let mut events = presence_events.map(PresenceEventEngine::new);
+
     // Log startup info
     info!("🚀 Starting continuous presence monitoring...");
     if let Some(count) = count {
-        info!("📊 Taking {count} measurements every {interval}ms");
+        info!(
+            "📊 Taking {count} measurements every {:.1}ms",
+            interval_ms(interval)
+        );
     } else {
-        info!("📊 Continuous monitoring every {interval}ms (Ctrl+C to stop)");
+        info!(
+            "📊 Continuous monitoring every {:.1}ms (Ctrl+C to stop)",
+            interval_ms(interval)
+        );
     }
 
+    // Measurements right after calibration are frequently unreliable
+    let mut session = SessionMetadata::new("presence", count, interval);
+    emit_hardware_events(cli, &mut session);
+    let mut pacer = Pacer::new(interval);
+    log_warmup(warmup);
+    for _ in 0..warmup {
+        radar.measure_presence().await?;
+        session.warmup_discarded += 1;
+        pacer.wait().await;
+    }
+
+    let mut quality_gate = QualityGate::from_cli(cli)?;
+    let mut spool = open_spool(cli)?;
+    let mut latch = (latch_ms > 0).then(|| PresenceLatch::new(Duration::from_millis(latch_ms)));
+
+    // The presence detector free-runs at the configured frame rate
+    let nominal_rate = f64::from(radar.config.frame_rate);
+    let mut clock_tracker = clock_drift.then(|| ClockDriftTracker::new(Some(nominal_rate)));
+
+    // With a detection GPIO, results are only read while the module signals presence
+    let mut wakeup = gpio_wakeup
+        .map(|gpio| XM125GpioController::new().open_edge_input(gpio, "Detection"))
+        .transpose()?;
+    let mut detected = false;
+
     // Main monitoring loop
+    device_lock::catch_terminate();
+    let started = Instant::now();
+    let mut status = status_reporter(cli, "presence");
     while measurement_count < total_measurements {
-        let result = radar.measure_presence().await?;
-        let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string();
+        if let Some(reloader) = reloader.as_mut() {
+            reloader.reload_if_requested(radar).await;
+        }
+        if let Some(pin) = wakeup.as_mut().filter(|_| !detected) {
+            wait_for_detection(pin, &limits, started)?;
+        }
+        let mut result = radar
+            .measure_presence()
+            .await
+            .inspect_err(|e| status.error(e))?;
+        track_clock(radar, &mut clock_tracker)?;
+        let timestamp = timestamps::now();
         measurement_count += 1;
+        session.measurements = measurement_count;
 
-        // Process the measurement (display, CSV, FIFO)
-        process_presence_measurement(
-            &result,
-            measurement_count,
-            &timestamp,
-            cli,
-            &mut csv_writer,
-            &mut fifo_writer,
-        )?;
+        // Process the measurement (display, CSV, FIFO) unless the quality gate rejects it
+        if quality_gate.admit("presence", &presence_issues(&result), &result)? {
+            if let Some(latch) = latch.as_mut() {
+                latch.apply(&mut result);
+            }
+            if let Some(shm) = shm {
+                shm.publish_presence(&result);
+            }
+            if let Some(mqtt) = mqtt.filter(|_| events.is_none()) {
+                mqtt.publish_presence(&result);
+            }
+            if let Some(engine) = events.as_mut() {
+                // Transitions replace measurements on stdout, FIFO and MQTT; the CSV file
+                // still records every measurement
+                if let Some(event) = engine.update(
+                    result.presence_detected,
+                    result.presence_distance,
+                    Instant::now(),
+                ) {
+                    display_presence_event(&event, &cli.output.format);
+                    if let Some(writer) = fifo_writer.as_deref() {
+                        write_presence_event_to_fifo(writer, &event, &cli.output.fifo_format);
+                    }
+                    if let Some(mqtt) = mqtt {
+                        mqtt.publish_presence_event(&event);
+                    }
+                }
+                write_presence_record(
+                    &result,
+                    measurement_count,
+                    &timestamp,
+                    false,
+                    cli,
+                    &mut csv_writer,
+                    None,
+                )?;
+            } else if let Some(aggregator) = aggregator.as_mut() {
+                if let Some(closed) = aggregator.push(&result) {
+                    emit_presence_window(&closed, cli, &mut csv_writer, fifo_writer.as_deref())?;
+                }
+            } else {
+                let delivered = process_presence_measurement(
+                    &result,
+                    measurement_count,
+                    &timestamp,
+                    cli,
+                    &mut csv_writer,
+                    fifo_writer.as_deref_mut(),
+                )?;
+                spool_or_replay(
+                    &mut spool,
+                    delivered,
+                    || SpooledMeasurement::Presence {
+                        sequence: measurement_count,
+                        measurement: result.clone(),
+                    },
+                    |record| match record {
+                        SpooledMeasurement::Presence {
+                            sequence,
+                            measurement,
+                        } => write_presence_record(
+                            measurement,
+                            *sequence,
+                            &timestamps::format_at(measurement.timestamp),
+                            true,
+                            cli,
+                            &mut csv_writer,
+                            fifo_writer.as_deref_mut(),
+                        ),
+                        SpooledMeasurement::Distance { .. } => {
+                            warn!("Discarding spooled distance measurement during presence monitoring");
+                            Ok(true)
+                        }
+                    },
+                )?;
+            }
+        }
+        session.quality_failed = quality_gate.failed;
+        emit_hardware_events(cli, &mut session);
+        status.measured(radar.state(), &session);
+        sd_notify::watchdog();
+        // Keep reading while latched, so the release is reported before sleeping again
+        detected = result.presence_detected;
 
         // Update progress bar
         if let Some(ref pb) = progress {
             pb.set_position(u64::from(measurement_count));
         }
 
+        if should_stop(
+            &limits,
+            started,
+            measurement_count,
+            &csv_writer,
+            &mut session,
+        ) {
+            break;
+        }
+
         // Check if we should stop
         if count.is_some() && measurement_count >= total_measurements {
             break;
         }
 
         // Wait for next measurement
-        sleep(Duration::from_millis(interval)).await;
+        pacer.wait().await;
+    }
+
+    // Report the partial last window
+    if let Some(closed) = aggregator.as_mut().and_then(PresenceAggregator::flush) {
+        emit_presence_window(&closed, cli, &mut csv_writer, fifo_writer.as_deref())?;
     }
 
     // Cleanup and summary
     if let Some(pb) = progress {
         pb.finish_with_message("✅ Presence monitoring completed");
     }
+    session.late_cycles = pacer.late_cycles();
+    report_clock_drift(clock_tracker.as_ref(), &mut session);
+    status.finish(&session);
+    finish_session(&mut session, save_to)?;
 
     if let Some(filename) = save_to {
-        println!("💾 Results saved to: {filename}");
+        eprintln!("💾 Results saved to: {filename}");
     }
 
     Ok(())
 }
+
+/// Monitor the breathing rate continuously
+pub async fn monitor_breathing_continuous(
+    radar: &mut XM125Radar,
+    cli: &Cli,
+    count: Option<u32>,
+    interval: Duration,
+    save_to: Option<&str>,
+    limits: RunLimits,
+    mut fifo_writer: Option<&mut FifoWriter>,
+) -> Result<(), RadarError> {
+    let total_measurements = count.unwrap_or(u32::MAX);
+    let mut measurement_count = 0u32;
+
+    let progress = setup_progress_bar(cli, count);
+    let mut csv_writer = save_to
+        .map(|filename| -> Result<_, RadarError> {
+            let mut writer = csv::Writer::from_writer(create_data_file(cli, filename)?);
+            write_csv_line(
+                &mut writer,
+                "timestamp,measurement_number,breathing_rate_bpm,state,temperature_c",
+            )?;
+            Ok(writer)
+        })
+        .transpose()?;
+    if matches!(cli.output.format, OutputFormat::Csv) {
+        println!("{BREATHING_CSV_HEADER}");
+    }
+
+    info!("🚀 Starting continuous breathing monitoring...");
+    info!(
+        "📊 Reading the breathing rate every {:.1}ms{}",
+        interval_ms(interval),
+        if count.is_none() {
+            " (Ctrl+C to stop)"
+        } else {
+            ""
+        }
+    );
+
+    let mut session = SessionMetadata::new("breathing", count, interval);
+    emit_hardware_events(cli, &mut session);
+    let mut pacer = Pacer::new(interval);
+
+    device_lock::catch_terminate();
+    let started = Instant::now();
+    let mut status = status_reporter(cli, "breathing");
+    while measurement_count < total_measurements {
+        let result = radar
+            .measure_breathing()
+            .await
+            .inspect_err(|e| status.error(e))?;
+        measurement_count += 1;
+        session.measurements = measurement_count;
+
+        if matches!(cli.output.format, OutputFormat::Csv) {
+            println!("{}", breathing_csv_row(&result));
+        } else if !cli.output.quiet {
+            display_breathing_result(&result, &cli.output.format);
+        }
+        if let Some(writer) = csv_writer.as_mut() {
+            let row = breathing_csv_row(&result);
+            let (timestamp, fields) = row.split_once(',').unwrap_or_default();
+            write_csv_line(writer, &format!("{timestamp},{measurement_count},{fields}"))?;
+        }
+        if let Some(writer) = fifo_writer.as_deref_mut() {
+            write_breathing_to_fifo(writer, &result, &cli.output.fifo_format);
+        }
+
+        emit_hardware_events(cli, &mut session);
+        status.measured(radar.state(), &session);
+        sd_notify::watchdog();
+        if let Some(ref pb) = progress {
+            pb.set_position(u64::from(measurement_count));
+        }
+        if should_stop(
+            &limits,
+            started,
+            measurement_count,
+            &csv_writer,
+            &mut session,
+        ) || measurement_count >= total_measurements
+        {
+            break;
+        }
+        pacer.wait().await;
+    }
+
+    if let Some(pb) = progress {
+        pb.finish_with_message("✅ Breathing monitoring completed");
+    }
+    session.late_cycles = pacer.late_cycles();
+    status.finish(&session);
+    finish_session(&mut session, save_to)?;
+
+    if let Some(filename) = save_to {
+        eprintln!("💾 Results saved to: {filename}");
+    }
+
+    Ok(())
+}
+
+/// Run an empty-room false-positive survey for `duration` seconds and print the report
+pub async fn run_baseline_capture(
+    radar: &mut XM125Radar,
+    cli: &Cli,
+    duration: u64,
+    interval: Duration,
+) -> Result<(), RadarError> {
+    let mut survey = BaselineSurvey::new(
+        radar.config.intra_detection_threshold,
+        radar.config.inter_detection_threshold,
+    );
+    let deadline = Instant::now() + Duration::from_secs(duration);
+
+    info!("🧪 Starting baseline capture for {duration}s - the room must remain empty");
+    let progress = if cli.output.quiet {
+        None
+    } else {
+        let pb = ProgressBar::new(duration);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len}s {msg}",
+                )
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        Some(pb)
+    };
+
+    let mut pacer = Pacer::new(interval);
+    while Instant::now() < deadline {
+        let result = radar.measure_presence().await?;
+        if survey.record(&result) {
+            warn!(
+                "False positive at {:.2}m (intra {:.2}, inter {:.2})",
+                result.presence_distance, result.intra_presence_score, result.inter_presence_score
+            );
+        }
+
+        if let Some(ref pb) = progress {
+            let remaining = deadline.saturating_duration_since(Instant::now()).as_secs();
+            pb.set_position(duration.saturating_sub(remaining));
+            pb.set_message(format!("{} false positives", survey.false_positives()));
+        }
+        pacer.wait().await;
+    }
+
+    if let Some(pb) = progress {
+        pb.finish_with_message(format!(
+            "✅ Baseline capture completed ({} false positives)",
+            survey.false_positives()
+        ));
+    }
+
+    display_baseline_report(&survey.report(), &cli.output.format)
+}
+
+/// Record labeled presence measurements for `duration` seconds (Ctrl+C ends early)
+pub async fn run_labeled_capture(
+    radar: &mut XM125Radar,
+    cli: &Cli,
+    mut capture: LabeledCapture,
+    duration: u64,
+    interval: Duration,
+) -> Result<(PathBuf, CaptureMetadata), RadarError> {
+    let deadline = Instant::now() + Duration::from_secs(duration);
+    let progress = if cli.output.quiet {
+        None
+    } else {
+        let pb = ProgressBar::new(duration);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template(
+                    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len}s {msg}",
+                )
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        Some(pb)
+    };
+
+    let interrupted = tokio::signal::ctrl_c();
+    tokio::pin!(interrupted);
+    let mut pacer = Pacer::new(interval);
+    while Instant::now() < deadline {
+        let result = radar.measure_presence().await?;
+        capture.record(&result)?;
+
+        if let Some(ref pb) = progress {
+            let remaining = deadline.saturating_duration_since(Instant::now()).as_secs();
+            pb.set_position(duration.saturating_sub(remaining));
+            pb.set_message(format!("{} samples", capture.samples()));
+        }
+
+        tokio::select! {
+            () = pacer.wait() => {}
+            _ = &mut interrupted => {
+                warn!("Capture interrupted - keeping {} samples", capture.samples());
+                break;
+            }
+        }
+    }
+
+    if let Some(pb) = progress {
+        pb.finish_with_message(format!(
+            "✅ Capture completed ({} samples)",
+            capture.samples()
+        ));
+    }
+    capture.finish()
+}
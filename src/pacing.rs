@@ -0,0 +1,133 @@
+//! Measurement loop pacing
+//!
+//! Continuous loops measure on a fixed schedule instead of sleeping a full interval after
+//! each cycle, so the time spent measuring and writing records no longer stretches the
+//! period. When the cycle itself takes longer than the requested interval the loop runs
+//! back-to-back, and the pacer says so once, with the measured cycle cost, instead of
+//! silently lagging behind the requested rate.
+
+use log::warn;
+use std::time::{Duration, Instant};
+use tokio::time::sleep_until;
+
+/// Consecutive late cycles before the interval is reported as unattainable
+const LATE_STREAK: u32 = 5;
+
+/// Longest accepted measurement interval (one day), in ms
+const MAX_INTERVAL_MS: f64 = 86_400_000.0;
+
+/// Parse a measurement interval such as `500`, `12.5ms` or `0.05s` (bare numbers are ms)
+pub fn parse_interval(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (value, scale_ms) = [("ms", 1.0), ("s", 1000.0)]
+        .into_iter()
+        .find_map(|(suffix, scale)| s.strip_suffix(suffix).map(|value| (value, scale)))
+        .unwrap_or((s, 1.0));
+    let value: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid interval '{s}' (expected e.g. 500, 12.5ms, 0.05s)"))?;
+    let millis = value * scale_ms;
+    if !millis.is_finite() || millis < 1.0 {
+        return Err(format!("Interval must be at least 1ms, got '{s}'"));
+    }
+    if millis > MAX_INTERVAL_MS {
+        return Err(format!("Interval must be at most 24h, got '{s}'"));
+    }
+    Ok(Duration::from_secs_f64(millis / 1000.0))
+}
+
+/// Interval in milliseconds, for logs and metadata
+pub fn interval_ms(interval: Duration) -> f64 {
+    interval.as_secs_f64() * 1000.0
+}
+
+/// Warning when `interval` is shorter than one frame of a detector running at `frame_rate_hz`
+///
+/// The presence detector produces a new result once per frame; reading it more often
+/// returns the same frame again.
+pub fn frame_rate_warning(interval: Duration, frame_rate_hz: f32) -> Option<String> {
+    let frame_ms = 1000.0 / f64::from(frame_rate_hz);
+    (frame_rate_hz > 0.0 && interval_ms(interval) < frame_ms).then(|| {
+        format!(
+            "Interval {:.1}ms is shorter than one frame at {frame_rate_hz} Hz ({frame_ms:.1}ms) - \
+             consecutive readings will repeat frames; raise --frame-rate or the interval",
+            interval_ms(interval)
+        )
+    })
+}
+
+/// Schedules measurement cycles `interval` apart
+#[derive(Debug)]
+pub struct Pacer {
+    interval: Duration,
+    next: Instant,
+    cycle_started: Instant,
+    late_streak: u32,
+    late_cycles: u32,
+    warned: bool,
+}
+
+impl Pacer {
+    /// Start the schedule now; the first cycle runs immediately
+    pub fn new(interval: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            interval,
+            next: now,
+            cycle_started: now,
+            late_streak: 0,
+            late_cycles: 0,
+            warned: false,
+        }
+    }
+
+    /// Wait for the start of the next cycle
+    pub async fn wait(&mut self) {
+        let now = Instant::now();
+        let cost = now.duration_since(self.cycle_started);
+        self.next += self.interval;
+        if self.next > now {
+            self.late_streak = 0;
+            sleep_until(self.next.into()).await;
+        } else {
+            // Start over from now rather than bursting to catch up
+            self.late_cycles += 1;
+            self.late_streak += 1;
+            self.next = now;
+            if self.late_streak >= LATE_STREAK && !self.warned {
+                self.warned = true;
+                warn!(
+                    "⚠️  Requested interval {:.1}ms is unattainable: a measurement cycle takes {:.1}ms, measuring back-to-back instead",
+                    interval_ms(self.interval),
+                    interval_ms(cost)
+                );
+            }
+        }
+        self.cycle_started = Instant::now();
+    }
+
+    /// Cycles that overran the interval
+    pub fn late_cycles(&self) -> u32 {
+        self.late_cycles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval_and_frame_rate_check() {
+        assert_eq!(parse_interval("500"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_interval("12.5ms"), Ok(Duration::from_micros(12_500)));
+        assert_eq!(parse_interval("0.05s"), Ok(Duration::from_millis(50)));
+        assert!(parse_interval("0.5").is_err());
+        assert!(parse_interval("fast").is_err());
+        assert!(parse_interval("1e20s").is_err());
+        assert!(parse_interval("86400s").is_ok());
+
+        assert!(frame_rate_warning(Duration::from_millis(50), 12.0).is_some());
+        assert!(frame_rate_warning(Duration::from_millis(100), 12.0).is_none());
+    }
+}
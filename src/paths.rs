@@ -0,0 +1,56 @@
+//! Writable path checks for read-only root filesystems
+//!
+//! Production images mount the root filesystem read-only and keep writable state under
+//! `/var/lib` and `/run`. A default path that lands on the read-only mount would otherwise
+//! only fail when it is first written, possibly hours into a session. Every file a command
+//! will write is checked before the radar is touched, and a path on a read-only mount is
+//! reported together with the option that moves it.
+
+use crate::error::RadarError;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// Whether `path`, or the nearest existing directory above it, is on a read-only mount
+pub fn is_read_only(path: &Path) -> bool {
+    // A relative path with no existing parent is created in the working directory
+    let existing = path
+        .ancestors()
+        .find(|p| !p.as_os_str().is_empty() && p.exists())
+        .unwrap_or(Path::new("."));
+    let Ok(c_path) = CString::new(existing.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: statvfs is plain data filled in by the call
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is NUL-terminated and `stat` outlives the call
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return false;
+    }
+    stat.f_flag & libc::ST_RDONLY != 0
+}
+
+/// Refuse `path` if it is on a read-only mount, naming `option` as the way to move it
+pub fn ensure_writable(path: &str, option: &'static str) -> Result<(), RadarError> {
+    if is_read_only(Path::new(path)) {
+        return Err(RadarError::ReadOnlyPath {
+            path: path.to_string(),
+            option,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_path_is_checked_at_nearest_existing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("state/xm125/stats.toml");
+        assert!(!is_read_only(&nested));
+        ensure_writable(nested.to_str().unwrap(), "--state-dir").unwrap();
+        assert!(!is_read_only(Path::new("relative/file")));
+    }
+}
@@ -0,0 +1,85 @@
+//! Plain (ASCII-only) output mode
+//!
+//! Serial consoles and legacy log collectors mangle UTF-8, so `--plain` strips emoji and
+//! replaces box-drawing characters in everything printed to stdout/stderr and in log
+//! messages. Status emoji that carry meaning are replaced by bracketed ASCII tags.
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Enable plain output for the rest of the process
+pub fn enable() {
+    PLAIN.store(true, Ordering::Relaxed);
+}
+
+/// Whether plain output is enabled
+pub fn is_enabled() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// ASCII replacement for a character, `None` to keep it, `Some("")` to drop it
+fn replacement(c: char) -> Option<&'static str> {
+    match c {
+        '✅' | '✓' | '✔' => Some("[OK]"),
+        '❌' | '✗' | '✘' => Some("[ERROR]"),
+        '⚠' => Some("[WARN]"),
+        '→' => Some("->"),
+        '←' => Some("<-"),
+        '•' | '·' => Some("*"),
+        '°' => Some("deg"),
+        '─' | '━' | '═' | '┄' | '┈' => Some("-"),
+        '│' | '┃' | '║' | '┆' | '┊' => Some("|"),
+        '\u{2500}'..='\u{257F}' => Some("+"), // Remaining box drawing: corners and tees
+        '\u{2580}'..='\u{259F}' => Some("#"), // Block elements
+        '\u{FE0F}' | '\u{200D}' => Some(""),  // Variation selector and zero-width joiner
+        '\u{1F000}'..='\u{1FAFF}' | '\u{2600}'..='\u{27BF}' | '\u{2300}'..='\u{23FF}' => Some(""),
+        _ => None,
+    }
+}
+
+/// Strip emoji and box drawing from `text` when plain mode is enabled
+pub fn sanitize(text: &str) -> Cow<'_, str> {
+    if !is_enabled() || text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(to_ascii(text))
+}
+
+/// Unconditional ASCII conversion used by [`sanitize`]
+fn to_ascii(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut dropped = false;
+    for c in text.chars() {
+        match replacement(c) {
+            Some("") => dropped = true,
+            Some(ascii) => {
+                out.push_str(ascii);
+                dropped = false;
+            }
+            None => {
+                // Avoid doubled spaces where an emoji separated two words
+                if !(dropped && c == ' ' && (out.is_empty() || out.ends_with(' '))) {
+                    out.push(c);
+                }
+                dropped = false;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ascii() {
+        assert_eq!(to_ascii("✅ Connected"), "[OK] Connected");
+        assert_eq!(to_ascii("🚀 Starting monitoring"), "Starting monitoring");
+        assert_eq!(to_ascii("⚠️  Warning"), "[WARN]  Warning");
+        assert_eq!(to_ascii("┌──┐"), "+--+");
+        assert_eq!(to_ascii("distance 1.2m"), "distance 1.2m");
+    }
+}
@@ -0,0 +1,175 @@
+//! Presence state transitions
+//!
+//! Consumers such as lighting or occupancy dashboards want to know when someone arrives and
+//! leaves, not every sample. With `presence --continuous --events` the measurements are fed
+//! through a small state machine that reports `ENTERED` once presence has been detected for
+//! the debounce time, `LEFT` once nothing has been detected for the hold time, and
+//! `STILL_PRESENT` at a fixed interval in between. Each event carries a duration: how long
+//! the area was empty for `ENTERED`, and how long presence has lasted for the other two.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Timing of the event engine, from `--debounce`, `--hold-time` and `--still-interval`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresenceEventConfig {
+    /// Continuous detection needed before ENTERED
+    pub debounce: Duration,
+    /// Time without detection before LEFT
+    pub hold: Duration,
+    /// Time between STILL_PRESENT events
+    pub still_interval: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PresenceEventKind {
+    Entered,
+    Left,
+    StillPresent,
+}
+
+impl PresenceEventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            PresenceEventKind::Entered => "ENTERED",
+            PresenceEventKind::Left => "LEFT",
+            PresenceEventKind::StillPresent => "STILL_PRESENT",
+        }
+    }
+}
+
+/// A presence state transition
+#[derive(Debug, Clone, Serialize)]
+pub struct PresenceEvent {
+    pub timestamp: DateTime<Utc>,
+    pub event: PresenceEventKind,
+    /// Seconds the area was empty (ENTERED, None the first time) or presence has lasted
+    pub duration_s: Option<f64>,
+    /// Distance of the last detection
+    pub distance_m: f32,
+}
+
+/// Tracks presence with debounce and hold time and reports transitions
+#[derive(Debug, Clone)]
+pub struct PresenceEventEngine {
+    config: PresenceEventConfig,
+    /// Start of the current presence (after ENTERED)
+    entered: Option<Instant>,
+    /// Start of the current run of detections while absent
+    candidate: Option<Instant>,
+    last_seen: Option<Instant>,
+    last_left: Option<Instant>,
+    last_report: Option<Instant>,
+    last_distance: f32,
+}
+
+impl PresenceEventEngine {
+    pub fn new(config: PresenceEventConfig) -> Self {
+        Self {
+            config,
+            entered: None,
+            candidate: None,
+            last_seen: None,
+            last_left: None,
+            last_report: None,
+            last_distance: 0.0,
+        }
+    }
+
+    /// Feed one measurement taken at `now`; returns an event when the state changes or a
+    /// STILL_PRESENT report is due
+    pub fn update(&mut self, detected: bool, distance: f32, now: Instant) -> Option<PresenceEvent> {
+        if !detected {
+            self.candidate = None;
+            let entered = self.entered?;
+            let last_seen = self.last_seen.unwrap_or(entered);
+            if now.duration_since(last_seen) < self.config.hold {
+                return None;
+            }
+            self.entered = None;
+            self.last_left = Some(last_seen);
+            return Some(self.event(
+                PresenceEventKind::Left,
+                Some(last_seen.duration_since(entered)),
+            ));
+        }
+
+        self.last_seen = Some(now);
+        self.last_distance = distance;
+        if let Some(entered) = self.entered {
+            let last_report = self.last_report.unwrap_or(entered);
+            if now.duration_since(last_report) < self.config.still_interval {
+                return None;
+            }
+            self.last_report = Some(now);
+            return Some(self.event(
+                PresenceEventKind::StillPresent,
+                Some(now.duration_since(entered)),
+            ));
+        }
+
+        let candidate = *self.candidate.get_or_insert(now);
+        if now.duration_since(candidate) < self.config.debounce {
+            return None;
+        }
+        self.candidate = None;
+        self.entered = Some(candidate);
+        self.last_report = Some(now);
+        let absent = self
+            .last_left
+            .map(|left| candidate.saturating_duration_since(left));
+        Some(self.event(PresenceEventKind::Entered, absent))
+    }
+
+    fn event(&self, event: PresenceEventKind, duration: Option<Duration>) -> PresenceEvent {
+        PresenceEvent {
+            timestamp: Utc::now(),
+            event,
+            duration_s: duration.map(|duration| duration.as_secs_f64()),
+            distance_m: self.last_distance,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debounce_hold_and_still_present() {
+        let mut engine = PresenceEventEngine::new(PresenceEventConfig {
+            debounce: Duration::from_secs(1),
+            hold: Duration::from_secs(5),
+            still_interval: Duration::from_secs(10),
+        });
+        let start = Instant::now();
+        let mut feed = |secs: u64, detected: bool| {
+            engine
+                .update(detected, 1.2, start + Duration::from_secs(secs))
+                .map(|event| (event.event, event.duration_s))
+        };
+
+        // A single detection shorter than the debounce is ignored
+        assert_eq!(feed(0, true), None);
+        assert_eq!(feed(1, false), None);
+        assert_eq!(feed(2, true), None);
+        assert_eq!(feed(3, true), Some((PresenceEventKind::Entered, None)));
+        // Gaps shorter than the hold time keep presence
+        assert_eq!(feed(6, false), None);
+        assert_eq!(feed(7, true), None);
+        assert_eq!(
+            feed(13, true),
+            Some((PresenceEventKind::StillPresent, Some(11.0)))
+        );
+        assert_eq!(feed(17, false), None);
+        assert_eq!(feed(18, false), Some((PresenceEventKind::Left, Some(11.0))));
+        assert_eq!(feed(19, false), None);
+        assert_eq!(feed(30, true), None);
+        assert_eq!(
+            feed(31, true),
+            Some((PresenceEventKind::Entered, Some(17.0)))
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Anonymized privacy mode
+//!
+//! `--privacy` coarsens what leaves the device through external sinks (the FIFO, and any
+//! other sink that forwards measurement records): distances are reported as the centre of a
+//! `--privacy-bucket` sized bucket, presence scores, signal strengths and raw register words
+//! are dropped, and timestamps are cut to whole minutes. Local output (stdout and the CSV
+//! export) is coarsened the same way unless `--privacy-keep-local` explicitly keeps it at
+//! full fidelity; numeric score columns then read zero.
+
+use crate::radar::{DistanceMeasurement, PresenceMeasurement};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use serde_json::Value;
+
+/// Fields with score or signal detail, removed from anonymized records
+const DETAIL_FIELDS: &[&str] = &[
+    "intra_score",
+    "inter_score",
+    "signal_strength",
+    "signal_strength_db",
+    "uncorrected_distance_m",
+    "raw",
+];
+
+/// Centre of the `bucket_m` sized bucket holding `distance` (no target stays at zero)
+pub fn quantize_distance(distance: f32, bucket_m: f32) -> f32 {
+    if distance <= 0.0 || bucket_m <= 0.0 {
+        return distance;
+    }
+    ((distance / bucket_m).floor() + 0.5) * bucket_m
+}
+
+/// `timestamp` truncated to the start of its minute
+pub fn truncate_to_minute(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    timestamp
+        .duration_trunc(TimeDelta::minutes(1))
+        .unwrap_or(timestamp)
+}
+
+/// Anonymize a JSON record in place
+///
+/// Numbers under keys ending in `_m` (including window statistics) are quantized, detail
+/// fields removed and `timestamp`-like strings cut to `YYYY-MM-DD HH:MM`.
+pub fn anonymize_json(json: &mut Value, bucket_m: f32) {
+    let Value::Object(fields) = json else {
+        return;
+    };
+    fields.retain(|key, _| !DETAIL_FIELDS.contains(&key.as_str()));
+    for (key, value) in fields.iter_mut() {
+        if key.ends_with("_m") {
+            quantize_json(value, bucket_m);
+        } else if let (true, Value::Array(peaks)) = (key == "peaks", &mut *value) {
+            peaks
+                .iter_mut()
+                .for_each(|peak| anonymize_json(peak, bucket_m));
+        } else if key == "timestamp" || key.starts_with("window_") {
+            if let Value::String(timestamp) = value {
+                match timestamp.parse::<i64>() {
+                    // Integer nanoseconds (`--timestamps realtime|monotonic`)
+                    Ok(ns) => *timestamp = (ns - ns.rem_euclid(60_000_000_000)).to_string(),
+                    // "%Y-%m-%d %H:%M:%S%.3f" formatted timestamps
+                    Err(_) => timestamp.truncate(16),
+                }
+            }
+        }
+    }
+}
+
+fn quantize_json(value: &mut Value, bucket_m: f32) {
+    match value {
+        Value::Number(number) => {
+            if let Some(distance) = number.as_f64() {
+                *value = quantize_distance(distance as f32, bucket_m).into();
+            }
+        }
+        Value::Object(fields) => fields
+            .values_mut()
+            .for_each(|value| quantize_json(value, bucket_m)),
+        _ => {}
+    }
+}
+
+/// Measurements that can be coarsened for privacy mode
+pub trait Anonymize {
+    fn anonymize(&mut self, bucket_m: f32);
+}
+
+impl Anonymize for DistanceMeasurement {
+    fn anonymize(&mut self, bucket_m: f32) {
+        self.distance = quantize_distance(self.distance, bucket_m);
+        self.strength = 0.0;
+        self.strength_db = 0.0;
+        self.timestamp = truncate_to_minute(self.timestamp);
+        self.uncorrected_distance = None;
+        self.raw = None;
+        for peak in self.peaks.iter_mut().flatten() {
+            peak.distance = quantize_distance(peak.distance, bucket_m);
+            peak.strength_db = 0.0;
+        }
+    }
+}
+
+impl Anonymize for PresenceMeasurement {
+    fn anonymize(&mut self, bucket_m: f32) {
+        self.presence_distance = quantize_distance(self.presence_distance, bucket_m);
+        self.intra_presence_score = 0.0;
+        self.inter_presence_score = 0.0;
+        self.timestamp = truncate_to_minute(self.timestamp);
+        self.raw = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_record_is_coarsened() {
+        assert_eq!(quantize_distance(0.0, 0.5), 0.0);
+        assert_eq!(quantize_distance(0.3, 0.5), 0.25);
+        assert_eq!(quantize_distance(1.74, 0.5), 1.75);
+
+        let mut json = serde_json::json!({
+            "timestamp": "2024-05-01 12:34:56.789",
+            "presence_detected": true,
+            "presence_distance_m": 1.23,
+            "intra_score": 2.5,
+            "inter_score": 1.1,
+            "distance_m": {"min": 0.6, "max": 2.2, "mean": 1.4},
+        });
+        anonymize_json(&mut json, 0.5);
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "timestamp": "2024-05-01 12:34",
+                "presence_detected": true,
+                "presence_distance_m": 1.25,
+                "distance_m": {"min": 0.75, "max": 2.25, "mean": 1.25},
+            })
+        );
+    }
+}
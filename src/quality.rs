@@ -0,0 +1,152 @@
+//! Measurement quality gating
+//!
+//! Measurements the module reports as failed or that are physically implausible are
+//! flagged, or rejected before they reach the display, CSV and FIFO outputs. Either way
+//! they can be written to a separate "rejected" JSON-lines sink for later analysis.
+
+use crate::cli::{Cli, QualityGateMode};
+use crate::error::RadarError;
+use crate::journal::JournaledFile;
+use crate::radar::{DistanceMeasurement, PresenceMeasurement};
+use log::warn;
+use serde::Serialize;
+use serde_json::json;
+use std::io::Write;
+
+/// Plausible module temperature range in °C (XM125 operating range)
+const TEMPERATURE_RANGE_C: std::ops::RangeInclusive<i16> = -40..=85;
+/// Presence scores above this are treated as register overflow
+const MAX_PLAUSIBLE_SCORE: f32 = 100_000.0;
+
+/// Reasons a distance measurement is invalid (empty when it passes)
+pub fn distance_issues(measurement: &DistanceMeasurement) -> Vec<&'static str> {
+    let mut issues = Vec::new();
+    if measurement.measure_error {
+        issues.push(if measurement.distance == 0.0 {
+            "measure_error_no_distance"
+        } else {
+            "measure_error"
+        });
+    }
+    if !measurement.distance.is_finite() || measurement.distance < 0.0 {
+        issues.push("distance_invalid");
+    }
+    if !TEMPERATURE_RANGE_C.contains(&measurement.temperature) {
+        issues.push("temperature_implausible");
+    }
+    issues
+}
+
+/// Reasons a presence measurement is invalid (empty when it passes)
+pub fn presence_issues(measurement: &PresenceMeasurement) -> Vec<&'static str> {
+    let score_valid =
+        |score: f32| score.is_finite() && (0.0..=MAX_PLAUSIBLE_SCORE).contains(&score);
+
+    let mut issues = Vec::new();
+    if !score_valid(measurement.intra_presence_score) {
+        issues.push("intra_score_invalid");
+    }
+    if !score_valid(measurement.inter_presence_score) {
+        issues.push("inter_score_invalid");
+    }
+    if !measurement.presence_distance.is_finite() || measurement.presence_distance < 0.0 {
+        issues.push("distance_invalid");
+    }
+    issues
+}
+
+/// Applies `--quality-gate` and writes failing measurements to `--rejected-file`
+pub struct QualityGate {
+    mode: QualityGateMode,
+    sink: Option<JournaledFile>,
+    /// Measurements that failed the checks
+    pub failed: u32,
+}
+
+impl QualityGate {
+    /// Create the gate from the CLI options, opening the rejected sink if configured
+    pub fn from_cli(cli: &Cli) -> Result<Self, RadarError> {
+        let sink = match &cli.output.rejected_file {
+            Some(path) if !matches!(cli.output.quality_gate, QualityGateMode::Off) => Some(
+                cli.create_data_file(path)
+                    .map_err(|e| RadarError::DeviceError {
+                        message: format!("Failed to create rejected measurements file {path}: {e}"),
+                    })?,
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            mode: cli.output.quality_gate.clone(),
+            sink,
+            failed: 0,
+        })
+    }
+
+    /// Check a measurement; returns false if it must be dropped from normal output
+    pub fn admit<T: Serialize>(
+        &mut self,
+        mode: &str,
+        issues: &[&'static str],
+        measurement: &T,
+    ) -> Result<bool, RadarError> {
+        if issues.is_empty() || matches!(self.mode, QualityGateMode::Off) {
+            return Ok(true);
+        }
+
+        self.failed += 1;
+        let reject = matches!(self.mode, QualityGateMode::Reject);
+        warn!(
+            "⚠️  {} {mode} measurement: {}",
+            if reject { "Rejected" } else { "Flagged" },
+            issues.join(", ")
+        );
+
+        if let Some(sink) = self.sink.as_mut() {
+            let record = json!({
+                "mode": mode,
+                "action": if reject { "rejected" } else { "flagged" },
+                "issues": issues,
+                "measurement": measurement,
+            });
+            writeln!(sink, "{record}")
+                .and_then(|()| sink.flush())
+                .map_err(|e| RadarError::DeviceError {
+                    message: format!("Failed to write rejected measurement: {e}"),
+                })?;
+        }
+        Ok(!reject)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_distance_issues() {
+        let mut measurement = DistanceMeasurement {
+            distance: 1.2,
+            strength: 10.0,
+            strength_db: 0.01,
+            temperature: 25,
+            measure_error: false,
+            timestamp: Utc::now(),
+            uncorrected_distance: None,
+            temperature_delta: None,
+            config_generation: None,
+            raw: None,
+            peaks: None,
+        };
+        assert!(distance_issues(&measurement).is_empty());
+
+        measurement.distance = 0.0;
+        measurement.measure_error = true;
+        measurement.temperature = 200;
+        assert_eq!(
+            distance_issues(&measurement),
+            vec!["measure_error_no_distance", "temperature_implausible"]
+        );
+    }
+}
@@ -0,0 +1,304 @@
+// Breathing Monitor Module
+// Drives the breathing reference application (i2c_ref_app_breathing.bin)
+
+#![allow(clippy::pedantic)]
+
+use super::protocol::{describe_errors, BreathingProtocol, DetectorProtocol, ResultWord};
+use super::registers::{
+    BREATHING_APP_STATE_DETERMINE_DISTANCE, BREATHING_APP_STATE_ESTIMATE_BREATHING_RATE,
+    BREATHING_APP_STATE_INIT, BREATHING_APP_STATE_INTRA_PRESENCE, BREATHING_APP_STATE_NO_PRESENCE,
+    BREATHING_REG_APP_STATE_ADDRESS, BREATHING_REG_BREATHING_RATE_ADDRESS,
+    BREATHING_REG_BREATHING_RESULT_ADDRESS, BREATHING_REG_END_ADDRESS,
+    BREATHING_REG_FRAME_RATE_ADDRESS, BREATHING_REG_HIGHEST_BREATHING_RATE_ADDRESS,
+    BREATHING_REG_LOWEST_BREATHING_RATE_ADDRESS, BREATHING_REG_START_ADDRESS,
+};
+use super::RawRegisters;
+use crate::error::{RadarError, Result};
+use crate::fault::{self, Fault};
+use crate::i2c::I2cDevice;
+use crate::timeouts::{TimeoutPhase, Timeouts, POLL_INTERVAL};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+/// Closest start point supported by the breathing application in mm
+const MIN_START_MM: u32 = 100;
+/// Farthest end point supported by the breathing application in mm
+const MAX_END_MM: u32 = 3000;
+
+/// Breathing application configuration
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BreathingConfig {
+    /// Start of the measured interval in metres
+    pub start_m: f32,
+    /// End of the measured interval in metres
+    pub end_m: f32,
+    /// Lowest breathing rate reported in breaths per minute
+    pub lowest_bpm: u32,
+    /// Highest breathing rate reported in breaths per minute
+    pub highest_bpm: u32,
+    /// Sensor frame rate in Hz
+    pub frame_rate: f32,
+}
+
+impl Default for BreathingConfig {
+    fn default() -> Self {
+        // Defaults of the Acconeer breathing reference application
+        Self {
+            start_m: 0.3,
+            end_m: 1.5,
+            lowest_bpm: 6,
+            highest_bpm: 60,
+            frame_rate: 10.0,
+        }
+    }
+}
+
+impl BreathingConfig {
+    /// Check the configuration against the limits of the breathing application
+    pub fn validate(&self) -> Result<()> {
+        let (start_mm, end_mm) = self.range_mm();
+        if start_mm < MIN_START_MM || end_mm > MAX_END_MM || start_mm >= end_mm {
+            return Err(RadarError::InvalidParameters(format!(
+                "breathing range {:.2}m - {:.2}m is outside {:.1}m - {:.1}m or empty",
+                self.start_m,
+                self.end_m,
+                MIN_START_MM as f32 / 1000.0,
+                MAX_END_MM as f32 / 1000.0
+            )));
+        }
+        if self.lowest_bpm == 0 || self.lowest_bpm >= self.highest_bpm {
+            return Err(RadarError::InvalidParameters(format!(
+                "breathing rate bounds {}-{} bpm must be increasing and above 0",
+                self.lowest_bpm, self.highest_bpm
+            )));
+        }
+        if !(1.0..=60.0).contains(&self.frame_rate) {
+            return Err(RadarError::InvalidParameters(format!(
+                "frame rate must be between 1.0 and 60.0 Hz (got {:.1})",
+                self.frame_rate
+            )));
+        }
+        Ok(())
+    }
+
+    fn range_mm(&self) -> (u32, u32) {
+        (
+            (self.start_m * 1000.0).round() as u32,
+            (self.end_m * 1000.0).round() as u32,
+        )
+    }
+}
+
+/// Processing stage of the breathing application
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BreathingState {
+    Init,
+    /// Nobody in range
+    NoPresence,
+    /// Someone is moving too much for a breathing estimate
+    IntraPresence,
+    /// Locating the chest of a still person
+    DetermineDistance,
+    /// Rate estimate available once the time series is filled
+    EstimateBreathingRate,
+    Unknown,
+}
+
+impl BreathingState {
+    /// Name used in CSV and log output
+    pub fn name(&self) -> &'static str {
+        match self {
+            BreathingState::Init => "init",
+            BreathingState::NoPresence => "no_presence",
+            BreathingState::IntraPresence => "intra_presence",
+            BreathingState::DetermineDistance => "determine_distance",
+            BreathingState::EstimateBreathingRate => "estimate_breathing_rate",
+            BreathingState::Unknown => "unknown",
+        }
+    }
+
+    /// Someone is in range, whether or not a rate is available yet
+    pub fn presence(&self) -> bool {
+        matches!(
+            self,
+            BreathingState::IntraPresence
+                | BreathingState::DetermineDistance
+                | BreathingState::EstimateBreathingRate
+        )
+    }
+}
+
+impl From<u32> for BreathingState {
+    fn from(state: u32) -> Self {
+        match state {
+            BREATHING_APP_STATE_INIT => BreathingState::Init,
+            BREATHING_APP_STATE_NO_PRESENCE => BreathingState::NoPresence,
+            BREATHING_APP_STATE_INTRA_PRESENCE => BreathingState::IntraPresence,
+            BREATHING_APP_STATE_DETERMINE_DISTANCE => BreathingState::DetermineDistance,
+            BREATHING_APP_STATE_ESTIMATE_BREATHING_RATE => BreathingState::EstimateBreathingRate,
+            _ => BreathingState::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreathingMeasurement {
+    /// Breaths per minute, when the application has a fresh estimate
+    pub breathing_rate: Option<f32>,
+    pub state: BreathingState,
+    pub temperature: i16,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Register words the measurement was decoded from (see `--include-raw`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<RawRegisters>,
+}
+
+pub struct BreathingDetector<'a> {
+    i2c: &'a mut I2cDevice,
+    timeouts: Timeouts,
+}
+
+impl<'a> BreathingDetector<'a> {
+    pub fn new(i2c: &'a mut I2cDevice, timeouts: Timeouts) -> Self {
+        Self { i2c, timeouts }
+    }
+
+    fn read_u32(&mut self, register: u16) -> Result<u32> {
+        let bytes = self.i2c.read_register(register, 4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn write_u32(&mut self, register: u16, value: u32) -> Result<()> {
+        self.i2c.write_register(register, &value.to_be_bytes())
+    }
+
+    /// Write a command and wait for the application to finish it
+    async fn command(&mut self, command: u32) -> Result<()> {
+        let protocol = BreathingProtocol;
+        self.write_u32(protocol.command_register(), command)?;
+
+        let phase = TimeoutPhase::Calibration;
+        let timeout = self.timeouts.get(phase);
+        if Fault::timeout(phase).is_some_and(fault::inject) {
+            return Err(RadarError::Timeout { phase, timeout });
+        }
+        let start = std::time::Instant::now();
+        loop {
+            let status = self.read_u32(protocol.status_register())?;
+            if !protocol.is_busy(status) {
+                if protocol.has_error(status) {
+                    return Err(RadarError::DeviceError {
+                        message: format!(
+                            "breathing application rejected command {command}: {}",
+                            describe_errors(&protocol, status)
+                        ),
+                    });
+                }
+                return Ok(());
+            }
+            if start.elapsed() >= timeout {
+                return Err(RadarError::Timeout { phase, timeout });
+            }
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if !self.i2c.wait_for_ready_interrupt(remaining)? {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    /// Reset the application, write the configuration, apply it and start the application
+    pub async fn configure(&mut self, config: &BreathingConfig) -> Result<()> {
+        config.validate()?;
+        let protocol = BreathingProtocol;
+        let (start_mm, end_mm) = config.range_mm();
+
+        // A new configuration needs a module reset; the reset itself is not acknowledged
+        self.write_u32(protocol.command_register(), protocol.cmd_reset_module())?;
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        info!(
+            "Breathing range {start_mm}-{end_mm} mm, {}-{} bpm, {:.1} Hz",
+            config.lowest_bpm, config.highest_bpm, config.frame_rate
+        );
+        self.write_u32(BREATHING_REG_START_ADDRESS, start_mm)?;
+        self.write_u32(BREATHING_REG_END_ADDRESS, end_mm)?;
+        self.write_u32(
+            BREATHING_REG_LOWEST_BREATHING_RATE_ADDRESS,
+            config.lowest_bpm,
+        )?;
+        self.write_u32(
+            BREATHING_REG_HIGHEST_BREATHING_RATE_ADDRESS,
+            config.highest_bpm,
+        )?;
+        self.write_u32(
+            BREATHING_REG_FRAME_RATE_ADDRESS,
+            (config.frame_rate * 1000.0).round() as u32,
+        )?;
+
+        self.command(protocol.cmd_apply_configuration()).await?;
+        if let Some(start) = protocol.cmd_start() {
+            self.command(start).await?;
+        }
+        info!("✅ Breathing application configured and started");
+        Ok(())
+    }
+
+    /// Read the latest result
+    pub fn measure(&mut self) -> Result<BreathingMeasurement> {
+        let result = self.read_u32(BREATHING_REG_BREATHING_RESULT_ADDRESS)?;
+        let rate = self.read_u32(BREATHING_REG_BREATHING_RATE_ADDRESS)?;
+        let state = self.read_u32(BREATHING_REG_APP_STATE_ADDRESS)?;
+        Ok(decode_measurement(result, rate, state))
+    }
+}
+
+/// Measurement from the result, rate and application state registers
+fn decode_measurement(result: u32, rate: u32, state: u32) -> BreathingMeasurement {
+    let ResultWord::Breathing {
+        result_ready,
+        temperature,
+        ..
+    } = BreathingProtocol.parse_result(result)
+    else {
+        unreachable!("breathing protocol yields breathing result words");
+    };
+    BreathingMeasurement {
+        // Rate is in breaths per minute * 1000
+        breathing_rate: result_ready.then(|| rate as f32 / 1000.0),
+        state: BreathingState::from(state),
+        temperature,
+        timestamp: chrono::Utc::now(),
+        raw: Some(RawRegisters::from([
+            ("result".to_string(), result),
+            ("breathing_rate".to_string(), rate),
+            ("app_state".to_string(), state),
+        ])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_breathing_measurement() {
+        let measurement = decode_measurement((22 << 16) | 0b11, 14_500, 4);
+        assert_eq!(measurement.breathing_rate, Some(14.5));
+        assert_eq!(measurement.state, BreathingState::EstimateBreathingRate);
+        assert_eq!(measurement.temperature, 22);
+
+        let waiting = decode_measurement(22 << 16, 14_500, 3);
+        assert_eq!(waiting.breathing_rate, None);
+        assert_eq!(waiting.state, BreathingState::DetermineDistance);
+
+        assert!(BreathingConfig::default().validate().is_ok());
+        let inverted = BreathingConfig {
+            lowest_bpm: 30,
+            highest_bpm: 20,
+            ..BreathingConfig::default()
+        };
+        assert!(inverted.validate().is_err());
+    }
+}
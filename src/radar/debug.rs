@@ -1,8 +1,9 @@
 // Register Debug Module
-// Provides comprehensive register debugging functionality
+// Reads the register tables of the running firmware into structured records for display
 
 #![allow(clippy::pedantic)]
 
+use super::protocol::{DetectorProtocol, ProtocolFirmware, RegisterInfo, ResultWord, RssVersion};
 use super::registers::{
     REG_CLOSE_RANGE_LEAKAGE_CANCELLATION, REG_COMMAND, REG_DETECTOR_STATUS, REG_DISTANCE_RESULT,
     REG_END_CONFIG, REG_FIXED_AMPLITUDE_THRESHOLD_VALUE, REG_FIXED_STRENGTH_THRESHOLD_VALUE,
@@ -12,296 +13,495 @@ use super::registers::{
     REG_THRESHOLD_SENSITIVITY, REG_VERSION,
 };
 use crate::error::Result;
+use crate::firmware::FirmwareType;
 use crate::i2c::I2cDevice;
+use serde::Serialize;
+use serde_json::Value;
 
-pub struct RegisterDebugger<'a> {
-    i2c: &'a mut I2cDevice,
+const fn reg(address: u16, name: &'static str, description: &'static str) -> RegisterInfo {
+    RegisterInfo {
+        address,
+        name,
+        description,
+    }
 }
 
-impl<'a> RegisterDebugger<'a> {
-    pub fn new(i2c: &'a mut I2cDevice) -> Self {
-        Self { i2c }
+const COMMON_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        REG_VERSION,
+        "Module Version",
+        "Hardware/firmware version info",
+    ),
+    reg(
+        REG_PROTOCOL_STATUS,
+        "Protocol Status",
+        "Communication protocol status",
+    ),
+    reg(
+        REG_MEASURE_COUNTER,
+        "Measure Counter",
+        "Number of measurements performed",
+    ),
+    reg(
+        REG_DETECTOR_STATUS,
+        "Detector Status",
+        "Current detector state and flags",
+    ),
+    reg(
+        REG_COMMAND,
+        "Command Register",
+        "Last executed command code",
+    ),
+];
+
+const PRESENCE_CONFIG_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        64,
+        "Sweeps Per Frame",
+        "Number of sweeps per measurement frame",
+    ),
+    reg(
+        65,
+        "Inter Frame Timeout",
+        "Presence timeout in seconds (0-30)",
+    ),
+    reg(
+        66,
+        "Inter Phase Boost",
+        "Phase boost for slow motion detection",
+    ),
+    reg(67, "Intra Detection", "Fast motion detection enable (0/1)"),
+    reg(68, "Inter Detection", "Slow motion detection enable (0/1)"),
+    reg(69, "Frame Rate", "Frame rate in mHz (value * 1000)"),
+    reg(
+        70,
+        "Intra Threshold",
+        "Fast motion threshold (value * 1000)",
+    ),
+    reg(
+        71,
+        "Inter Threshold",
+        "Slow motion threshold (value * 1000)",
+    ),
+    reg(72, "Inter Dev Time", "Inter-frame deviation time constant"),
+    reg(73, "Inter Fast Cutoff", "Fast filter cutoff frequency"),
+    reg(74, "Inter Slow Cutoff", "Slow filter cutoff frequency"),
+    reg(75, "Intra Frame Time", "Intra-frame time constant"),
+    reg(76, "Intra Output Time", "Intra output time constant"),
+    reg(77, "Inter Output Time", "Inter output time constant"),
+    reg(78, "Auto Profile", "Auto profile selection enable (0/1)"),
+    reg(79, "Auto Step Length", "Auto step length enable (0/1)"),
+    reg(80, "Manual Profile", "Manual profile (1-5)"),
+    reg(81, "Manual Step Length", "Manual step length"),
+    reg(82, "Start Point", "Start distance in mm * 1000"),
+    reg(83, "End Point", "End distance in mm * 1000"),
+    reg(84, "Reset Filters", "Reset filters on prepare (0/1)"),
+    reg(85, "HWAAS", "Hardware accelerated average samples"),
+    reg(86, "Auto Subsweeps", "Automatic subsweeps enable (0/1)"),
+    reg(87, "Signal Quality", "Signal quality threshold"),
+    reg(128, "Detection GPIO", "Output detection on GPIO (0/1)"),
+];
+
+const PRESENCE_RESULT_REGISTERS: &[RegisterInfo] = &[
+    reg(0, "Version", "RSS version (major.minor.patch)"),
+    reg(1, "Protocol Status", "Protocol error flags"),
+    reg(2, "Measure Counter", "Number of measurements since restart"),
+    reg(3, "Detector Status", "Detector status flags"),
+    reg(
+        16,
+        "Presence Result",
+        "Presence detection result & temperature",
+    ),
+    reg(
+        17,
+        "Presence Distance",
+        "Distance to detected presence (mm)",
+    ),
+    reg(18, "Intra Score", "Fast motion detection score"),
+    reg(19, "Inter Score", "Slow motion detection score"),
+    reg(32, "Actual Frame Rate", "Actual frame rate in mHz"),
+];
+
+const APPLICATION_REGISTERS: &[RegisterInfo] = &[reg(
+    65535,
+    "Application ID",
+    "Firmware application identifier",
+)];
+
+const DISTANCE_CONFIG_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        REG_START_CONFIG,
+        "Start Config",
+        "Detection start point (mm)",
+    ),
+    reg(REG_END_CONFIG, "End Config", "Detection end point (mm)"),
+    reg(
+        REG_MAX_STEP_LENGTH,
+        "Max Step Length",
+        "Maximum step length",
+    ),
+    reg(
+        REG_CLOSE_RANGE_LEAKAGE_CANCELLATION,
+        "Leakage Cancel",
+        "Close range leakage cancellation",
+    ),
+    reg(
+        REG_SIGNAL_QUALITY,
+        "Signal Quality",
+        "Signal quality threshold",
+    ),
+    reg(REG_MAX_PROFILE, "Max Profile", "Maximum profile setting"),
+    reg(
+        REG_THRESHOLD_METHOD,
+        "Threshold Method",
+        "Threshold calculation method",
+    ),
+    reg(REG_PEAK_SORTING, "Peak Sorting", "Peak sorting method"),
+    reg(
+        REG_NUM_FRAMES_RECORDED_THRESHOLD,
+        "Frames Threshold",
+        "Number of frames for threshold",
+    ),
+    reg(
+        REG_FIXED_AMPLITUDE_THRESHOLD_VALUE,
+        "Fixed Amplitude",
+        "Fixed amplitude threshold value",
+    ),
+    reg(
+        REG_THRESHOLD_SENSITIVITY,
+        "Sensitivity",
+        "Detection sensitivity",
+    ),
+    reg(
+        REG_REFLECTOR_SHAPE,
+        "Reflector Shape",
+        "Expected reflector shape",
+    ),
+    reg(
+        REG_FIXED_STRENGTH_THRESHOLD_VALUE,
+        "Fixed Strength",
+        "Fixed strength threshold value",
+    ),
+];
+
+const DISTANCE_RESULT_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        REG_DISTANCE_RESULT,
+        "Distance Result",
+        "Measured distance (mm)",
+    ),
+    reg(
+        REG_PEAK0_DISTANCE,
+        "Peak 0 Distance",
+        "Peak 0 distance (mm)",
+    ),
+    reg(
+        REG_PEAK0_STRENGTH,
+        "Peak 0 Strength",
+        "Peak 0 signal strength",
+    ),
+];
+
+/// Register group a record belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterSection {
+    Common,
+    Configuration,
+    Results,
+    Application,
+}
+
+impl RegisterSection {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RegisterSection::Common => "common",
+            RegisterSection::Configuration => "configuration",
+            RegisterSection::Results => "results",
+            RegisterSection::Application => "application",
+        }
+    }
+}
+
+/// One register read for the dump
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterRecord {
+    pub section: RegisterSection,
+    pub address: u16,
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<u32>,
+    /// Why the register could not be read
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Fields decoded from the value (status bits, result word, RSS version)
+    #[serde(
+        serialize_with = "serialize_fields",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub fields: Vec<DecodedField>,
+}
+
+/// Every register of the running firmware, in dump order
+#[derive(Debug, Clone, Serialize)]
+pub struct RegisterDump {
+    pub protocol: &'static str,
+    pub firmware: &'static str,
+    pub registers: Vec<RegisterRecord>,
+}
+
+impl RegisterDump {
+    /// Decoded detector status record, if it was read
+    pub fn status(&self) -> Option<&RegisterRecord> {
+        self.registers.iter().find(|record| {
+            record.section == RegisterSection::Common && record.address == REG_DETECTOR_STATUS
+        })
     }
+}
+
+/// One field decoded from a register value
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedField {
+    pub name: &'static str,
+    /// Most and least significant bit of the field
+    pub bits: (u8, u8),
+    pub value: Value,
+    pub unit: Option<&'static str>,
+}
 
-    /// Debug all common registers
-    pub fn debug_common_registers(&mut self) -> Result<()> {
-        println!("📊 Common Status & Control Registers:");
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-        println!(
-            "  Addr   (Dec) │ Register Name             │ Value (Hex)  (Decimal) │ Description"
-        );
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-
-        self.debug_register(
-            REG_VERSION,
-            "Module Version",
-            "Hardware/firmware version info",
-        )?;
-        self.debug_register(
-            REG_PROTOCOL_STATUS,
-            "Protocol Status",
-            "Communication protocol status",
-        )?;
-        self.debug_register(
-            REG_MEASURE_COUNTER,
-            "Measure Counter",
-            "Number of measurements performed",
-        )?;
-        self.debug_register(
-            REG_DETECTOR_STATUS,
-            "Detector Status",
-            "Current detector state and flags",
-        )?;
-        self.debug_register(
-            REG_COMMAND,
-            "Command Register",
-            "Last executed command code",
-        )?;
-
-        Ok(())
+const fn bit(index: u8) -> (u8, u8) {
+    (index, index)
+}
+
+fn field(name: &'static str, bits: (u8, u8), value: impl Into<Value>) -> DecodedField {
+    DecodedField {
+        name,
+        bits,
+        value: value.into(),
+        unit: None,
     }
+}
 
-    /// Debug presence detector registers
-    pub fn debug_presence_registers(&mut self) -> Result<()> {
-        println!("\n👤 Presence Detector Configuration:");
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-        println!(
-            "  Addr   (Dec) │ Register Name             │ Value (Hex)  (Decimal) │ Description"
-        );
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-
-        // Configuration registers
-        self.debug_register(
-            64,
-            "Sweeps Per Frame",
-            "Number of sweeps per measurement frame",
-        )?;
-        self.debug_register(
-            65,
-            "Inter Frame Timeout",
-            "Presence timeout in seconds (0-30)",
-        )?;
-        self.debug_register(
-            66,
-            "Inter Phase Boost",
-            "Phase boost for slow motion detection",
-        )?;
-        self.debug_register(67, "Intra Detection", "Fast motion detection enable (0/1)")?;
-        self.debug_register(68, "Inter Detection", "Slow motion detection enable (0/1)")?;
-        self.debug_register(69, "Frame Rate", "Frame rate in mHz (value * 1000)")?;
-        self.debug_register(
-            70,
-            "Intra Threshold",
-            "Fast motion threshold (value * 1000)",
-        )?;
-        self.debug_register(
-            71,
-            "Inter Threshold",
-            "Slow motion threshold (value * 1000)",
-        )?;
-        self.debug_register(72, "Inter Dev Time", "Inter-frame deviation time constant")?;
-        self.debug_register(73, "Inter Fast Cutoff", "Fast filter cutoff frequency")?;
-        self.debug_register(74, "Inter Slow Cutoff", "Slow filter cutoff frequency")?;
-        self.debug_register(75, "Intra Frame Time", "Intra-frame time constant")?;
-        self.debug_register(76, "Intra Output Time", "Intra output time constant")?;
-        self.debug_register(77, "Inter Output Time", "Inter output time constant")?;
-        self.debug_register(78, "Auto Profile", "Auto profile selection enable (0/1)")?;
-        self.debug_register(79, "Auto Step Length", "Auto step length enable (0/1)")?;
-        self.debug_register(80, "Manual Profile", "Manual profile (1-5)")?;
-        self.debug_register(81, "Manual Step Length", "Manual step length")?;
-        self.debug_register(82, "Start Point", "Start distance in mm * 1000")?;
-        self.debug_register(83, "End Point", "End distance in mm * 1000")?;
-        self.debug_register(84, "Reset Filters", "Reset filters on prepare (0/1)")?;
-        self.debug_register(85, "HWAAS", "Hardware accelerated average samples")?;
-        self.debug_register(86, "Auto Subsweeps", "Automatic subsweeps enable (0/1)")?;
-        self.debug_register(87, "Signal Quality", "Signal quality threshold")?;
-        self.debug_register(128, "Detection GPIO", "Output detection on GPIO (0/1)")?;
-
-        // Result registers
-        println!("\n📊 Presence Detector Results:");
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-        println!(
-            "  Addr   (Dec) │ Register Name             │ Value (Hex)  (Decimal) │ Description"
-        );
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-
-        self.debug_register(0, "Version", "RSS version (major.minor.patch)")?;
-        self.debug_register(1, "Protocol Status", "Protocol error flags")?;
-        self.debug_register(2, "Measure Counter", "Number of measurements since restart")?;
-        self.debug_register(3, "Detector Status", "Detector status flags")?;
-        self.debug_register(
-            16,
-            "Presence Result",
-            "Presence detection result & temperature",
-        )?;
-        self.debug_register(
-            17,
-            "Presence Distance",
-            "Distance to detected presence (mm)",
-        )?;
-        self.debug_register(18, "Intra Score", "Fast motion detection score")?;
-        self.debug_register(19, "Inter Score", "Slow motion detection score")?;
-        self.debug_register(32, "Actual Frame Rate", "Actual frame rate in mHz")?;
-
-        // Application info
-        println!("\n🆔 Application Information:");
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-        self.debug_register(65535, "Application ID", "Firmware application identifier")?;
-
-        Ok(())
+fn temperature_field(temperature: i16) -> DecodedField {
+    DecodedField {
+        unit: Some("°C"),
+        ..field("temperature", (31, 16), temperature)
     }
+}
 
-    /// Debug distance detector registers
-    pub fn debug_distance_registers(&mut self) -> Result<()> {
-        println!("\n📏 Distance Detector Configuration:");
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-        println!(
-            "  Addr   (Dec) │ Register Name             │ Value (Hex)  (Decimal) │ Description"
-        );
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-
-        // Configuration registers
-        self.debug_register(
-            REG_START_CONFIG,
-            "Start Config",
-            "Detection start point (mm)",
-        )?;
-        self.debug_register(REG_END_CONFIG, "End Config", "Detection end point (mm)")?;
-        self.debug_register(
-            REG_MAX_STEP_LENGTH,
-            "Max Step Length",
-            "Maximum step length",
-        )?;
-        self.debug_register(
-            REG_CLOSE_RANGE_LEAKAGE_CANCELLATION,
-            "Leakage Cancel",
-            "Close range leakage cancellation",
-        )?;
-        self.debug_register(
-            REG_SIGNAL_QUALITY,
-            "Signal Quality",
-            "Signal quality threshold",
-        )?;
-        self.debug_register(REG_MAX_PROFILE, "Max Profile", "Maximum profile setting")?;
-        self.debug_register(
-            REG_THRESHOLD_METHOD,
-            "Threshold Method",
-            "Threshold calculation method",
-        )?;
-        self.debug_register(REG_PEAK_SORTING, "Peak Sorting", "Peak sorting method")?;
-        self.debug_register(
-            REG_NUM_FRAMES_RECORDED_THRESHOLD,
-            "Frames Threshold",
-            "Number of frames for threshold",
-        )?;
-        self.debug_register(
-            REG_FIXED_AMPLITUDE_THRESHOLD_VALUE,
-            "Fixed Amplitude",
-            "Fixed amplitude threshold value",
-        )?;
-        self.debug_register(
-            REG_THRESHOLD_SENSITIVITY,
-            "Sensitivity",
-            "Detection sensitivity",
-        )?;
-        self.debug_register(
-            REG_REFLECTOR_SHAPE,
-            "Reflector Shape",
-            "Expected reflector shape",
-        )?;
-        self.debug_register(
-            REG_FIXED_STRENGTH_THRESHOLD_VALUE,
-            "Fixed Strength",
-            "Fixed strength threshold value",
-        )?;
-
-        // Result registers
-        println!("\n📊 Distance Detector Results:");
-        println!(
-            "────────────────────────────────────────────────────────────────────────────────"
-        );
-        self.debug_register(
-            REG_DISTANCE_RESULT,
-            "Distance Result",
-            "Measured distance (mm)",
-        )?;
-        self.debug_register(
-            REG_PEAK0_DISTANCE,
-            "Peak 0 Distance",
-            "Peak 0 distance (mm)",
-        )?;
-        self.debug_register(
-            REG_PEAK0_STRENGTH,
-            "Peak 0 Strength",
-            "Peak 0 signal strength",
-        )?;
-
-        Ok(())
+/// Serialize decoded fields as a `name: value` object
+fn serialize_fields<S: serde::Serializer>(
+    fields: &[DecodedField],
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_map(fields.iter().map(|field| (field.name, &field.value)))
+}
+
+/// Decode the fields of `value` for the registers with a known layout
+fn decode_fields(protocol: &dyn DetectorProtocol, address: u16, value: u32) -> Vec<DecodedField> {
+    if address == protocol.status_register() {
+        return protocol
+            .status_flags()
+            .iter()
+            .map(|flag| field(flag.name, bit(flag.bit()), value & flag.mask != 0))
+            .collect();
+    }
+    if address == REG_VERSION {
+        return vec![field(
+            "rss_version",
+            (31, 0),
+            RssVersion::from_register(value).to_string(),
+        )];
+    }
+    if address != REG_DISTANCE_RESULT {
+        return Vec::new();
     }
 
-    /// Debug a single register
-    fn debug_register(&mut self, address: u16, name: &str, description: &str) -> Result<()> {
-        match self.i2c.read_register(address, 4) {
-            Ok(data) => {
-                let value = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
-                println!(
-                    "  0x{:04X} ({:3}) │ {:<25} │ 0x{:08X} ({:10}) │ {}",
-                    address, address, name, value, value, description
+    match protocol.parse_result(value) {
+        ResultWord::Distance {
+            num_distances,
+            near_start_edge,
+            calibration_needed,
+            measure_distance_error,
+            temperature,
+        } => vec![
+            field("num_distances", (3, 0), num_distances),
+            field("near_start_edge", bit(8), near_start_edge),
+            field("calibration_needed", bit(9), calibration_needed),
+            field("measure_distance_error", bit(10), measure_distance_error),
+            temperature_field(temperature),
+        ],
+        ResultWord::Presence {
+            presence_detected,
+            presence_detected_sticky,
+            detector_error,
+            temperature,
+        } => vec![
+            field("presence_detected", bit(0), presence_detected),
+            field("presence_detected_sticky", bit(1), presence_detected_sticky),
+            field("detector_error", bit(15), detector_error),
+            temperature_field(temperature),
+        ],
+        ResultWord::Breathing {
+            result_ready,
+            result_ready_sticky,
+            temperature,
+        } => vec![
+            field("result_ready", bit(0), result_ready),
+            field("result_ready_sticky", bit(1), result_ready_sticky),
+            temperature_field(temperature),
+        ],
+    }
+}
+pub struct RegisterDebugger<'a> {
+    i2c: &'a mut I2cDevice,
+    records: Vec<RegisterRecord>,
+}
+
+impl<'a> RegisterDebugger<'a> {
+    pub fn new(i2c: &'a mut I2cDevice) -> Self {
+        Self {
+            i2c,
+            records: Vec::new(),
+        }
+    }
+
+    /// Read every register in `table`; a failed read is recorded rather than aborting the dump
+    fn read_table(
+        &mut self,
+        protocol: &dyn DetectorProtocol,
+        section: RegisterSection,
+        table: &[RegisterInfo],
+    ) {
+        for info in table {
+            let (value, error) = match self.i2c.read_register(info.address, 4) {
+                Ok(data) => (
+                    Some(u32::from_be_bytes([data[0], data[1], data[2], data[3]])),
+                    None,
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            self.records.push(RegisterRecord {
+                section,
+                address: info.address,
+                name: info.name,
+                description: info.description,
+                value,
+                error,
+                fields: value
+                    .map(|value| decode_fields(protocol, info.address, value))
+                    .unwrap_or_default(),
+            });
+        }
+    }
+
+    /// Read all registers for the firmware implementing `protocol`
+    pub fn dump_all_registers(mut self, protocol: &dyn DetectorProtocol) -> Result<RegisterDump> {
+        let firmware = protocol.firmware_type();
+        self.read_table(protocol, RegisterSection::Common, COMMON_REGISTERS);
+        match firmware {
+            FirmwareType::Presence => {
+                self.read_table(
+                    protocol,
+                    RegisterSection::Configuration,
+                    PRESENCE_CONFIG_REGISTERS,
+                );
+                self.read_table(
+                    protocol,
+                    RegisterSection::Results,
+                    PRESENCE_RESULT_REGISTERS,
+                );
+                self.read_table(
+                    protocol,
+                    RegisterSection::Application,
+                    APPLICATION_REGISTERS,
                 );
             }
-            Err(e) => {
-                println!(
-                    "  0x{:04X} ({:3}) │ {:<25} │ ERROR: {:?} │ {}",
-                    address, address, name, e, description
+            FirmwareType::Distance => {
+                self.read_table(
+                    protocol,
+                    RegisterSection::Configuration,
+                    DISTANCE_CONFIG_REGISTERS,
+                );
+                self.read_table(
+                    protocol,
+                    RegisterSection::Results,
+                    DISTANCE_RESULT_REGISTERS,
+                );
+            }
+            FirmwareType::Breathing => {
+                self.read_table(
+                    protocol,
+                    RegisterSection::Configuration,
+                    protocol.config_registers(),
+                );
+                self.read_table(
+                    protocol,
+                    RegisterSection::Results,
+                    protocol.result_registers(),
                 );
             }
         }
-        Ok(())
-    }
 
-    /// Debug all registers based on detector mode
-    pub fn debug_all_registers(&mut self, detector_mode: &str) -> Result<()> {
-        println!(
-            "================================================================================"
-        );
-        println!("XM125 Register Dump - {} Mode", detector_mode);
-        println!(
-            "================================================================================"
-        );
-
-        self.debug_common_registers()?;
-
-        match detector_mode.to_lowercase().as_str() {
-            "presence" => self.debug_presence_registers()?,
-            "distance" => self.debug_distance_registers()?,
-            _ => {
-                // Debug both for unknown modes
-                self.debug_presence_registers()?;
-                self.debug_distance_registers()?;
-            }
+        // The status register is the one read that must succeed for the dump to mean anything
+        let status_read = self
+            .records
+            .iter()
+            .find(|record| record.address == protocol.status_register())
+            .and_then(|record| record.error.clone());
+        if let Some(error) = status_read {
+            return Err(crate::error::RadarError::DeviceError {
+                message: format!("Failed to read detector status: {error}"),
+            });
         }
 
-        println!(
-            "================================================================================"
-        );
-        Ok(())
+        Ok(RegisterDump {
+            protocol: protocol.name(),
+            firmware: firmware.cli_name(),
+            registers: self.records,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radar::protocol::PresenceProtocol;
+
+    fn value_of(fields: &[DecodedField], name: &str) -> Value {
+        fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| field.value.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_decode_fields() {
+        let result = decode_fields(&PresenceProtocol, REG_DISTANCE_RESULT, 0x0019_0001);
+        assert_eq!(value_of(&result, "presence_detected"), Value::Bool(true));
+        assert_eq!(value_of(&result, "temperature"), Value::from(25));
+
+        let status = decode_fields(&PresenceProtocol, REG_DETECTOR_STATUS, 0x8000_0080);
+        assert_eq!(value_of(&status, "CONFIG_APPLY_OK"), Value::Bool(true));
+        assert_eq!(value_of(&status, "BUSY"), Value::Bool(true));
+        assert_eq!(value_of(&status, "RSS_REGISTER_OK"), Value::Bool(false));
+
+        let version = decode_fields(&PresenceProtocol, REG_VERSION, 0x0001_0302);
+        assert_eq!(value_of(&version, "rss_version"), Value::from("1.3.2"));
+        assert!(decode_fields(&PresenceProtocol, REG_MEASURE_COUNTER, 7).is_empty());
+
+        let record = RegisterRecord {
+            section: RegisterSection::Results,
+            address: REG_DISTANCE_RESULT,
+            name: "Presence Result",
+            description: "",
+            value: Some(0x0019_0001),
+            error: None,
+            fields: result,
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["fields"]["temperature"], Value::from(25));
     }
 }
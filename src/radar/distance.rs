@@ -3,42 +3,127 @@
 
 #![allow(clippy::pedantic)]
 
+use super::protocol::{describe_errors, DetectorProtocol, DistanceProtocol, ResultWord};
 use super::registers::{
-    CALIBRATION_TIMEOUT, CMD_APPLY_CONFIG_AND_CALIBRATE, CMD_MEASURE_DISTANCE, CMD_RESET_MODULE,
+    CMD_APPLY_CONFIG_AND_CALIBRATE, CMD_MEASURE_DISTANCE, CMD_RECALIBRATE, CMD_RESET_MODULE,
     DISTANCE_CLOSE_RANGE_LEAKAGE_CANCELLATION_DEFAULT,
     DISTANCE_FIXED_AMPLITUDE_THRESHOLD_VALUE_DEFAULT,
-    DISTANCE_FIXED_STRENGTH_THRESHOLD_VALUE_DEFAULT, DISTANCE_MAX_PROFILE_DEFAULT,
-    DISTANCE_MAX_STEP_LENGTH_DEFAULT, DISTANCE_NUM_FRAMES_RECORDED_THRESHOLD_DEFAULT,
-    DISTANCE_PEAK_SORTING_DEFAULT, DISTANCE_REFLECTOR_SHAPE_DEFAULT,
-    DISTANCE_SIGNAL_QUALITY_DEFAULT, DISTANCE_THRESHOLD_METHOD_DEFAULT,
-    DISTANCE_THRESHOLD_SENSITIVITY_DEFAULT, MEASUREMENT_TIMEOUT,
+    DISTANCE_FIXED_STRENGTH_THRESHOLD_VALUE_DEFAULT, DISTANCE_MAX_PEAKS,
+    DISTANCE_MAX_PROFILE_DEFAULT, DISTANCE_MAX_STEP_LENGTH_DEFAULT,
+    DISTANCE_NUM_FRAMES_RECORDED_THRESHOLD_DEFAULT, DISTANCE_PEAK_SORTING_DEFAULT,
+    DISTANCE_REFLECTOR_SHAPE_DEFAULT, DISTANCE_REFLECTOR_SHAPE_PLANAR,
+    DISTANCE_SIGNAL_QUALITY_DEFAULT, DISTANCE_SIGNAL_QUALITY_LIQUID,
+    DISTANCE_THRESHOLD_METHOD_DEFAULT, DISTANCE_THRESHOLD_METHOD_RECORDED,
+    DISTANCE_THRESHOLD_SENSITIVITY_DEFAULT, DISTANCE_THRESHOLD_SENSITIVITY_LIQUID,
     REG_CLOSE_RANGE_LEAKAGE_CANCELLATION, REG_COMMAND, REG_DETECTOR_STATUS, REG_DISTANCE_RESULT,
     REG_END_CONFIG, REG_FIXED_AMPLITUDE_THRESHOLD_VALUE, REG_FIXED_STRENGTH_THRESHOLD_VALUE,
-    REG_MAX_PROFILE, REG_MAX_STEP_LENGTH, REG_NUM_FRAMES_RECORDED_THRESHOLD, REG_PEAK0_STRENGTH,
-    REG_PEAK_SORTING, REG_REFLECTOR_SHAPE, REG_SIGNAL_QUALITY, REG_START_CONFIG,
-    REG_THRESHOLD_METHOD, REG_THRESHOLD_SENSITIVITY, STATUS_BUSY_MASK, STATUS_ERROR_MASK,
+    REG_MAX_PROFILE, REG_MAX_STEP_LENGTH, REG_NUM_FRAMES_RECORDED_THRESHOLD, REG_PEAK0_DISTANCE,
+    REG_PEAK0_STRENGTH, REG_PEAK_SORTING, REG_REFLECTOR_SHAPE, REG_SIGNAL_QUALITY,
+    REG_START_CONFIG, REG_THRESHOLD_METHOD, REG_THRESHOLD_SENSITIVITY,
 };
+use super::RawRegisters;
 use crate::error::{RadarError, Result};
+use crate::fault::{self, Fault};
 use crate::i2c::I2cDevice;
+use crate::timeouts::{TimeoutPhase, Timeouts, POLL_INTERVAL};
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
+/// One distance detector result (the strongest peak)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DistanceMeasurement {
     pub distance: f32,
+    /// Raw peak strength register value
     pub strength: f32,
+    /// Peak strength in dB relative to the configured reference (the register holds dB × 1000)
+    #[serde(default)]
+    pub strength_db: f32,
     pub temperature: i16,
+    /// Measure distance error flag from the result word
+    pub measure_error: bool,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Distance reported by the module, when a host-side correction changed `distance`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uncorrected_distance: Option<f32>,
+    /// Temperature change since detector calibration, when thermal correction is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature_delta: Option<i16>,
+    /// Settings file generation in effect (see `--config`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_generation: Option<u32>,
+    /// Register words the measurement was decoded from (see `--include-raw`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<RawRegisters>,
+    /// Every peak the module reported, strongest or closest first (`--max-peaks` above 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peaks: Option<Vec<DistancePeak>>,
 }
 
+/// One of the distance detector's reported peaks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DistancePeak {
+    pub distance: f32,
+    /// Strength in dB relative to the configured reference
+    pub strength_db: f32,
+}
+
+/// Shape of the target the distance detector is tuned for
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum ReflectorShape {
+    /// Objects of any shape (default)
+    #[default]
+    Generic,
+    /// Flat surfaces facing the sensor, such as a liquid level or a wall
+    Planar,
+}
+
+impl ReflectorShape {
+    fn register_value(self) -> u32 {
+        match self {
+            ReflectorShape::Generic => DISTANCE_REFLECTOR_SHAPE_DEFAULT,
+            ReflectorShape::Planar => DISTANCE_REFLECTOR_SHAPE_PLANAR,
+        }
+    }
+}
+
+/// What the target is made of, which sets how clear an echo must be to count as a peak
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum TargetMaterial {
+    /// Solid objects (default)
+    #[default]
+    Solid,
+    /// Liquid surfaces: the surface echo is strong but ripples and echoes from the tank walls
+    /// and bottom are not, so a higher signal quality and a lower threshold sensitivity keep
+    /// the reading on the surface
+    Liquid,
+}
+
+impl TargetMaterial {
+    /// Signal quality and threshold sensitivity register values
+    fn register_values(self) -> (u32, u32) {
+        match self {
+            TargetMaterial::Solid => (
+                DISTANCE_SIGNAL_QUALITY_DEFAULT,
+                DISTANCE_THRESHOLD_SENSITIVITY_DEFAULT,
+            ),
+            TargetMaterial::Liquid => (
+                DISTANCE_SIGNAL_QUALITY_LIQUID,
+                DISTANCE_THRESHOLD_SENSITIVITY_LIQUID,
+            ),
+        }
+    }
+}
+
+/// Register-level driver of the distance detector application
 pub struct DistanceDetector<'a> {
     i2c: &'a mut I2cDevice,
+    timeouts: Timeouts,
 }
 
 impl<'a> DistanceDetector<'a> {
-    pub fn new(i2c: &'a mut I2cDevice) -> Self {
-        Self { i2c }
+    pub fn new(i2c: &'a mut I2cDevice, timeouts: Timeouts) -> Self {
+        Self { i2c, timeouts }
     }
 
     /// Configure distance range
@@ -62,36 +147,68 @@ impl<'a> DistanceDetector<'a> {
         Ok(())
     }
 
-    /// Configure distance detector with default settings
-    pub fn configure_detector(&mut self) -> Result<()> {
+    /// Configure distance detector with default settings, the subsweep planning limits and
+    /// the reflector and material presets
+    ///
+    /// The firmware splits the range into subsweeps itself, using shorter profiles (finer
+    /// resolution) near the sensor and longer ones further out, up to `max_profile`.
+    pub fn configure_detector(
+        &mut self,
+        max_step_length: u32,
+        max_profile: u32,
+        reflector: ReflectorShape,
+        material: TargetMaterial,
+        recorded_threshold_frames: Option<u32>,
+    ) -> Result<()> {
         info!("🔧 Configuring distance detector with default settings...");
+        if reflector != ReflectorShape::default() || material != TargetMaterial::default() {
+            info!("Tuned for a {reflector:?} reflector of {material:?} material");
+        }
+        let (signal_quality, threshold_sensitivity) = material.register_values();
+        if max_profile != DISTANCE_MAX_PROFILE_DEFAULT
+            || max_step_length != DISTANCE_MAX_STEP_LENGTH_DEFAULT
+        {
+            info!(
+                "Subsweep planning: max profile {max_profile}, max step length {}",
+                if max_step_length == 0 {
+                    "auto".to_string()
+                } else {
+                    max_step_length.to_string()
+                }
+            );
+        }
 
         // Write default configuration values
-        self.i2c.write_register(
-            REG_MAX_STEP_LENGTH,
-            &DISTANCE_MAX_STEP_LENGTH_DEFAULT.to_be_bytes(),
-        )?;
+        self.i2c
+            .write_register(REG_MAX_STEP_LENGTH, &max_step_length.to_be_bytes())?;
         self.i2c.write_register(
             REG_CLOSE_RANGE_LEAKAGE_CANCELLATION,
             &DISTANCE_CLOSE_RANGE_LEAKAGE_CANCELLATION_DEFAULT.to_be_bytes(),
         )?;
-        self.i2c.write_register(
-            REG_SIGNAL_QUALITY,
-            &DISTANCE_SIGNAL_QUALITY_DEFAULT.to_be_bytes(),
-        )?;
         self.i2c
-            .write_register(REG_MAX_PROFILE, &DISTANCE_MAX_PROFILE_DEFAULT.to_be_bytes())?;
-        self.i2c.write_register(
-            REG_THRESHOLD_METHOD,
-            &DISTANCE_THRESHOLD_METHOD_DEFAULT.to_be_bytes(),
-        )?;
+            .write_register(REG_SIGNAL_QUALITY, &signal_quality.to_be_bytes())?;
+        self.i2c
+            .write_register(REG_MAX_PROFILE, &max_profile.to_be_bytes())?;
+        // A recorded threshold is learnt from background frames during calibration
+        let (threshold_method, recorded_frames) = match recorded_threshold_frames {
+            Some(frames) => {
+                info!("Recording the detection threshold from {frames} background frames");
+                (DISTANCE_THRESHOLD_METHOD_RECORDED, frames)
+            }
+            None => (
+                DISTANCE_THRESHOLD_METHOD_DEFAULT,
+                DISTANCE_NUM_FRAMES_RECORDED_THRESHOLD_DEFAULT,
+            ),
+        };
+        self.i2c
+            .write_register(REG_THRESHOLD_METHOD, &threshold_method.to_be_bytes())?;
         self.i2c.write_register(
             REG_PEAK_SORTING,
             &DISTANCE_PEAK_SORTING_DEFAULT.to_be_bytes(),
         )?;
         self.i2c.write_register(
             REG_NUM_FRAMES_RECORDED_THRESHOLD,
-            &DISTANCE_NUM_FRAMES_RECORDED_THRESHOLD_DEFAULT.to_be_bytes(),
+            &recorded_frames.to_be_bytes(),
         )?;
         self.i2c.write_register(
             REG_FIXED_AMPLITUDE_THRESHOLD_VALUE,
@@ -99,11 +216,11 @@ impl<'a> DistanceDetector<'a> {
         )?;
         self.i2c.write_register(
             REG_THRESHOLD_SENSITIVITY,
-            &DISTANCE_THRESHOLD_SENSITIVITY_DEFAULT.to_be_bytes(),
+            &threshold_sensitivity.to_be_bytes(),
         )?;
         self.i2c.write_register(
             REG_REFLECTOR_SHAPE,
-            &DISTANCE_REFLECTOR_SHAPE_DEFAULT.to_be_bytes(),
+            &reflector.register_value().to_be_bytes(),
         )?;
         self.i2c.write_register(
             REG_FIXED_STRENGTH_THRESHOLD_VALUE,
@@ -114,39 +231,60 @@ impl<'a> DistanceDetector<'a> {
         Ok(())
     }
 
+    fn read_status(&mut self) -> Result<u32> {
+        self.read_word(REG_DETECTOR_STATUS)
+    }
+
+    fn read_word(&mut self, register: u16) -> Result<u32> {
+        let word = self.i2c.read_register(register, 4)?;
+        Ok(u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+    }
+
     /// Check if distance detector is busy
     pub fn is_busy(&mut self) -> Result<bool> {
-        let status = self.i2c.read_register(REG_DETECTOR_STATUS, 4)?;
-        let status_value = u32::from_be_bytes([status[0], status[1], status[2], status[3]]);
-        Ok((status_value & STATUS_BUSY_MASK) != 0)
+        Ok(DistanceProtocol.is_busy(self.read_status()?))
     }
 
     /// Check if distance detector has errors
     pub fn has_errors(&mut self) -> Result<bool> {
-        let status = self.i2c.read_register(REG_DETECTOR_STATUS, 4)?;
-        let status_value = u32::from_be_bytes([status[0], status[1], status[2], status[3]]);
-        Ok((status_value & STATUS_ERROR_MASK) != 0)
+        Ok(DistanceProtocol.has_error(self.read_status()?))
+    }
+
+    /// Fail with the decoded error flags if the detector reports an error
+    fn check_errors(&mut self, failure: &str) -> Result<()> {
+        let status = self.read_status()?;
+        if DistanceProtocol.has_error(status) {
+            return Err(RadarError::DeviceError {
+                message: format!("{failure}: {}", describe_errors(&DistanceProtocol, status)),
+            });
+        }
+        Ok(())
     }
 
     /// Wait for distance detector to not be busy
-    pub async fn wait_for_not_busy(&mut self, timeout: Duration) -> Result<()> {
+    pub async fn wait_for_not_busy(&mut self, phase: TimeoutPhase) -> Result<()> {
+        let timeout = self.timeouts.get(phase);
+        if Fault::timeout(phase).is_some_and(fault::inject) {
+            return Err(RadarError::Timeout { phase, timeout });
+        }
         let start = std::time::Instant::now();
         while start.elapsed() < timeout {
             if !self.is_busy()? {
                 return Ok(());
             }
-            tokio::time::sleep(Duration::from_millis(10)).await;
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if !self.i2c.wait_for_ready_interrupt(remaining)? {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
         }
-        Err(RadarError::Timeout {
-            timeout: timeout.as_secs(),
-        })
+        Err(RadarError::Timeout { phase, timeout })
     }
 
     /// Write command safely with busy/error checking
     pub async fn write_command_safe(&mut self, command: u32) -> Result<()> {
         // Check if detector is busy before writing command
         if self.is_busy()? {
-            self.wait_for_not_busy(Duration::from_secs(5)).await?;
+            self.wait_for_not_busy(TimeoutPhase::Measurement).await?;
         }
 
         // Check for errors - if present, only RESET MODULE command is allowed
@@ -183,59 +321,105 @@ impl<'a> DistanceDetector<'a> {
             .await?;
 
         // Wait for configuration and calibration to complete
-        self.wait_for_not_busy(CALIBRATION_TIMEOUT).await?;
+        self.wait_for_not_busy(TimeoutPhase::Calibration).await?;
 
-        // Check for configuration errors
-        if self.has_errors()? {
-            return Err(RadarError::DeviceError {
-                message: "Distance detector configuration/calibration failed".to_string(),
-            });
-        }
+        self.check_errors("Distance detector configuration/calibration failed")?;
 
         info!("✅ Distance detector configured and calibrated successfully");
         Ok(())
     }
 
-    /// Measure distance
-    pub async fn measure(&mut self) -> Result<DistanceMeasurement> {
+    /// Recalibrate the sensor and detector, recording the threshold again when the
+    /// recorded threshold method is configured
+    pub async fn recalibrate(&mut self) -> Result<()> {
+        info!("Recalibrating distance detector...");
+        self.write_command_safe(CMD_RECALIBRATE).await?;
+        self.wait_for_not_busy(TimeoutPhase::Calibration).await?;
+        self.check_errors("Distance detector recalibration failed")?;
+        info!("✅ Distance detector recalibrated");
+        Ok(())
+    }
+
+    /// Measure distance, reading up to `max_peaks` of the reported peaks
+    ///
+    /// Peak 0 always fills `distance` and `strength`; `peaks` lists all peaks read when more
+    /// than one is requested.
+    pub async fn measure(&mut self, max_peaks: usize) -> Result<DistanceMeasurement> {
         // Send measure command
         self.write_command_safe(CMD_MEASURE_DISTANCE).await?;
 
         // Wait for measurement to complete
-        self.wait_for_not_busy(MEASUREMENT_TIMEOUT).await?;
-
-        // Read measurement results
-        let distance_result = self.i2c.read_register(REG_DISTANCE_RESULT, 4)?;
-        let strength_result = self.i2c.read_register(REG_PEAK0_STRENGTH, 4)?;
-
-        // Parse results
-        let distance_value = u32::from_be_bytes([
-            distance_result[0],
-            distance_result[1],
-            distance_result[2],
-            distance_result[3],
-        ]);
-        let strength_value = u32::from_be_bytes([
-            strength_result[0],
-            strength_result[1],
-            strength_result[2],
-            strength_result[3],
-        ]);
-
-        // Convert distance from mm to meters
-        let distance = (distance_value as f32) / 1000.0;
-
-        // Convert strength (scaled appropriately)
+        self.wait_for_not_busy(TimeoutPhase::Measurement).await?;
+
+        // Read measurement results: result word (flags + temperature), then peak 0
+        let result_value = self.read_word(REG_DISTANCE_RESULT)?;
+        let distance_value = self.read_word(REG_PEAK0_DISTANCE)?;
+        let strength_value = self.read_word(REG_PEAK0_STRENGTH)?;
+
+        let (num_distances, measure_error, temperature) =
+            match DistanceProtocol.parse_result(result_value) {
+                ResultWord::Distance {
+                    num_distances,
+                    measure_distance_error,
+                    temperature,
+                    ..
+                } => (num_distances, measure_distance_error, temperature),
+                _ => unreachable!("distance protocol always yields a distance result word"),
+            };
+
+        // Convert distance from mm to meters (0 when no peak was found)
+        let distance = if num_distances > 0 {
+            (distance_value as f32) / 1000.0
+        } else {
+            0.0
+        };
+
+        // Strength is a signed fixed-point dB value; keep the raw register value alongside
         let strength = strength_value as f32;
+        let strength_db = (strength_value as i32) as f32 / 1000.0;
 
-        // Mock temperature for now (would need additional register read)
-        let temperature = 25i16;
+        let mut raw = RawRegisters::from([
+            ("result".to_string(), result_value),
+            ("peak0_distance".to_string(), distance_value),
+            ("peak0_strength".to_string(), strength_value),
+        ]);
+        let peaks = if max_peaks > 1 {
+            let count = usize::from(num_distances).min(max_peaks.min(DISTANCE_MAX_PEAKS));
+            let mut peaks = Vec::with_capacity(count);
+            for n in 0..count {
+                #[allow(clippy::cast_possible_truncation)] // At most DISTANCE_MAX_PEAKS
+                let offset = n as u16;
+                let (distance_word, strength_word) = if n == 0 {
+                    (distance_value, strength_value)
+                } else {
+                    let distance_word = self.read_word(REG_PEAK0_DISTANCE + offset)?;
+                    let strength_word = self.read_word(REG_PEAK0_STRENGTH + offset)?;
+                    raw.insert(format!("peak{n}_distance"), distance_word);
+                    raw.insert(format!("peak{n}_strength"), strength_word);
+                    (distance_word, strength_word)
+                };
+                peaks.push(DistancePeak {
+                    distance: distance_word as f32 / 1000.0,
+                    strength_db: strength_word as i32 as f32 / 1000.0,
+                });
+            }
+            Some(peaks)
+        } else {
+            None
+        };
 
         Ok(DistanceMeasurement {
             distance,
             strength,
+            strength_db,
             temperature,
+            measure_error,
             timestamp: chrono::Utc::now(),
+            uncorrected_distance: None,
+            temperature_delta: None,
+            config_generation: None,
+            raw: Some(raw),
+            peaks,
         })
     }
 }
@@ -1,25 +1,50 @@
-// XM125 Radar Module
+//! The XM125 driver: connection, detector configuration and measurements
 
 #![allow(clippy::pedantic)]
 // Main interface for XM125 radar functionality with modular design
 
+pub mod breathing;
 pub mod debug;
 pub mod distance;
 pub mod presence;
-pub mod registers;
-
+pub mod protocol;
+pub mod quirks;
+pub mod state;
+pub use xm125_protocol::registers;
+pub mod stream;
+
+use crate::app_id_cache::{AppIdCache, FirmwareIdentity};
+use crate::calibration::{correct_thermal_drift, DistanceCalibration};
 use crate::error::{RadarError, Result};
+use crate::firmware::FirmwareType;
 use crate::gpio::{XM125GpioController, XM125GpioPins};
-use crate::i2c::I2cDevice;
+use crate::heartbeat;
+use crate::i2c::{I2cDevice, WriteVerify};
+use crate::privacy::Anonymize;
+use crate::stats::StatsRecorder;
+use crate::telemetry;
+use crate::timeouts::{TimeoutPhase, Timeouts};
 use log::{debug, info, warn};
+use quirks::{QuirkEntry, Quirks};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::time::Instant;
 
 // Re-export public types
-pub use distance::DistanceMeasurement;
-pub use presence::{PresenceMeasurement, PresenceRange};
+pub use breathing::{BreathingConfig, BreathingMeasurement, BreathingState};
+pub use distance::{DistanceMeasurement, DistancePeak, ReflectorShape, TargetMaterial};
+pub use presence::{MotionDetection, PresenceMeasurement, PresenceRange};
+pub use protocol::{
+    DetectorProtocol, ProtocolFirmware, RegisterInfo, RssVersion, StatusFlag, MIN_RSS_VERSION,
+};
+pub use quirks::QuirkTable;
 pub use registers::*;
+pub use state::RadarState;
+
+/// Raw 32-bit register words behind a measurement, by register name
+pub type RawRegisters = BTreeMap<String, u32>;
 
+/// Reference application the driver configures and reads
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum DetectorMode {
     Distance,
@@ -28,14 +53,35 @@ pub enum DetectorMode {
     Breathing,
 }
 
+/// Detector configuration applied by [`XM125Radar`]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct XM125Config {
     pub detector_mode: DetectorMode,
     pub start_m: f32,
     pub length_m: f32,
+    /// Longest step the distance detector may use when planning subsweeps (0 = automatic)
     pub max_step_length: u32,
+    /// Highest profile the distance detector may use for its far subsweeps (1-5)
     pub max_profile: u32,
     pub threshold_sensitivity: f32,
+    // Distance correction from reference-target calibration
+    pub distance_calibration: Option<DistanceCalibration>,
+    // Thermal drift correction in mm per °C from the calibration temperature (0 = off)
+    pub temperature_coefficient: f32,
+    /// Reference level subtracted from peak strengths in dB
+    pub strength_reference_db: f32,
+    /// Distance peaks read per measurement (1-10)
+    #[serde(default = "default_max_peaks")]
+    pub max_peaks: usize,
+    /// Target shape the distance detector is tuned for
+    #[serde(default)]
+    pub reflector_shape: ReflectorShape,
+    /// Target material the distance detector's thresholds are tuned for
+    #[serde(default)]
+    pub target_material: TargetMaterial,
+    /// Background frames for a recorded detection threshold (CFAR threshold when unset)
+    #[serde(default)]
+    pub recorded_threshold_frames: Option<u32>,
     // Presence detection configuration
     pub presence_range: PresenceRange,
     pub intra_detection_threshold: f32,
@@ -43,20 +89,39 @@ pub struct XM125Config {
     pub frame_rate: f32,
     pub sweeps_per_frame: u32,
     pub auto_profile_enabled: bool,
+    /// Motion the presence detector reacts to (intra/inter detection enables)
+    pub motion_detection: MotionDetection,
+    /// Drive the module's detection GPIO while presence is detected
+    pub detection_on_gpio: bool,
+    /// Breathing application configuration
+    #[serde(default)]
+    pub breathing: BreathingConfig,
     // Connection settings
+    pub timeouts: Timeouts,
     pub auto_reconnect: bool,
     pub measurement_interval_ms: u64,
 }
 
+fn default_max_peaks() -> usize {
+    1
+}
+
 impl Default for XM125Config {
     fn default() -> Self {
         Self {
             detector_mode: DetectorMode::Distance,
             start_m: 0.10,  // 10 cm minimum distance
             length_m: 2.90, // 2.90m range (end at 3.0m total)
-            max_step_length: 24,
-            max_profile: 5,
+            max_step_length: DISTANCE_MAX_STEP_LENGTH_DEFAULT,
+            max_profile: DISTANCE_MAX_PROFILE_DEFAULT,
             threshold_sensitivity: 0.1,
+            distance_calibration: None,
+            temperature_coefficient: 0.0,
+            strength_reference_db: 0.0,
+            max_peaks: 1,
+            reflector_shape: ReflectorShape::Generic,
+            target_material: TargetMaterial::Solid,
+            recorded_threshold_frames: None,
             // Presence detection defaults
             presence_range: PresenceRange::Long,
             intra_detection_threshold: 1.3,
@@ -64,35 +129,78 @@ impl Default for XM125Config {
             frame_rate: 12.0,
             sweeps_per_frame: 16,
             auto_profile_enabled: true, // Default to auto profile (user-friendly)
+            motion_detection: MotionDetection::Both,
+            detection_on_gpio: false,
+            breathing: BreathingConfig::default(),
             // Connection settings
+            timeouts: Timeouts::default(),
             auto_reconnect: true,
             measurement_interval_ms: 1000,
         }
     }
 }
 
+/// Connection to one XM125 module
+///
+/// Handles reset and wake-up through the GPIO lines, identifies the running firmware,
+/// configures the detector for the selected [`DetectorMode`] and takes measurements,
+/// reconnecting after bus errors.
 pub struct XM125Radar {
     i2c: I2cDevice,
     pub config: XM125Config,
+    protocol: &'static dyn DetectorProtocol,
+    detected_firmware: Option<FirmwareType>,
+    rss_version: Option<RssVersion>,
+    app_id_cache: AppIdCache,
+    stats: StatsRecorder,
     gpio_pins: XM125GpioPins,
-    is_connected: bool,
-    is_calibrated: bool,
+    state: state::StateMachine,
     last_calibration: Option<Instant>,
-    continuous_mode: bool,
+    /// Module temperature at the last detector calibration
+    calibration_temperature: Option<i16>,
     last_measurement: Option<Instant>,
+    /// Settings file generation stamped on measurements (`--config`)
+    config_generation: Option<u32>,
+    /// Check busy/error status before every register write (`--strict-protocol`)
+    strict_protocol: bool,
+    /// Register writes read back and compared (`--write-verify`)
+    write_verify: WriteVerify,
+    /// Keep the raw register words on measurements (`--include-raw`)
+    include_raw: bool,
+    /// Bucket size for anonymized measurements (`--privacy` without `--privacy-keep-local`)
+    privacy_bucket: Option<f32>,
+    /// Firmware quirk entries (`--quirk-table`)
+    quirk_table: QuirkTable,
+    /// Entry used regardless of the detected firmware (`--force-quirk`)
+    forced_quirk: Option<QuirkEntry>,
+    /// Result decoding for the running firmware
+    quirks: Quirks,
 }
 
 impl XM125Radar {
     pub fn new(i2c: I2cDevice, gpio_pins: XM125GpioPins) -> Self {
+        let config = XM125Config::default();
         Self {
             i2c,
-            config: XM125Config::default(),
+            protocol: protocol::protocol_for_mode(config.detector_mode),
+            detected_firmware: None,
+            rss_version: None,
+            app_id_cache: AppIdCache::default(),
+            stats: StatsRecorder::default(),
+            config,
             gpio_pins,
-            is_connected: false,
-            is_calibrated: false,
+            state: state::StateMachine::default(),
             last_calibration: None,
-            continuous_mode: false,
+            calibration_temperature: None,
             last_measurement: None,
+            config_generation: None,
+            strict_protocol: false,
+            write_verify: WriteVerify::Off,
+            include_raw: false,
+            privacy_bucket: None,
+            quirk_table: QuirkTable::default(),
+            forced_quirk: None,
+            quirks: Quirks::default(),
         }
     }
 
@@ -103,8 +211,9 @@ impl XM125Radar {
         // First, try to connect without any warnings
         match self.get_status_raw() {
             Ok(_) => {
-                self.is_connected = true;
+                self.state.transition(RadarState::Connected)?;
                 info!("Successfully connected to XM125");
+                self.detect_firmware();
                 return Ok(());
             }
             Err(_) => {
@@ -114,26 +223,224 @@ impl XM125Radar {
         }
 
         // Try hardware reset to ensure module is in run mode
-        if let Err(reset_err) = self.reset_xm125_to_run_mode() {
+        if let Err(reset_err) = crate::i2c::blocking(|| self.reset_xm125_to_run_mode()) {
             debug!("Hardware reset failed: {reset_err}");
         } else {
-            // Give module time to initialize after reset
-            std::thread::sleep(std::time::Duration::from_millis(1000));
-
-            // Try connection again after reset
-            if self.get_status_raw().is_ok() {
-                self.is_connected = true;
-                info!("Successfully connected to XM125 after hardware initialization");
-                return Ok(());
+            self.state.transition(RadarState::Reset)?;
+            self.app_id_cache.invalidate();
+            self.stats.reset();
+
+            // Poll until the module answers or the connect timeout expires
+            let phase = TimeoutPhase::Connect;
+            let timeout = self.config.timeouts.get(phase);
+            let start = Instant::now();
+            while start.elapsed() < timeout {
+                crate::i2c::blocking(|| std::thread::sleep(std::time::Duration::from_millis(100)));
+                if self.get_status_raw().is_ok() {
+                    self.state.transition(RadarState::Connected)?;
+                    info!(
+                        "Successfully connected to XM125 after hardware initialization ({}ms)",
+                        start.elapsed().as_millis()
+                    );
+                    self.detect_firmware();
+                    return Ok(());
+                }
             }
+
+            warn!("XM125 did not answer on I2C within {timeout:?} of reset - check hardware connections and power");
+            self.state.transition(RadarState::Disconnected)?;
+            return Err(RadarError::Timeout { phase, timeout });
         }
 
         // Only issue warning after we've tried proper initialization
         warn!("Failed to connect to XM125: I2C communication error after hardware initialization");
         warn!("XM125 not detected on I2C bus - check hardware connections and power");
+        self.state.transition(RadarState::Disconnected)?;
         Err(RadarError::NotConnected)
     }
 
+    /// Identify the running firmware from its application ID and select its register protocol
+    ///
+    /// Status bits, error decoding and register dumps differ between firmwares, so they must
+    /// follow what is actually flashed rather than the requested measurement mode. The RSS
+    /// version is read alongside and checked against `MIN_RSS_VERSION`. Both come from the
+    /// application ID cache when it holds an entry for this module.
+    fn detect_firmware(&mut self) {
+        let (app_id, rss_version) = match self.app_id_cache.get() {
+            Some(identity) => {
+                debug!("Using cached application ID {}", identity.app_id);
+                (Some(identity.app_id), Some(identity.rss_version))
+            }
+            None => {
+                let app_id = self
+                    .read_application_id()
+                    .map_err(|e| warn!("Failed to read application ID: {}", e))
+                    .ok();
+                let rss_version = self
+                    .read_version_register()
+                    .map_err(|e| warn!("Failed to read RSS version: {}", e))
+                    .ok();
+                if let (Some(app_id), Some(rss_version)) = (app_id, rss_version) {
+                    self.app_id_cache.store(FirmwareIdentity {
+                        app_id,
+                        rss_version,
+                    });
+                }
+                (app_id, rss_version)
+            }
+        };
+
+        if let Some(app_id) = app_id {
+            match protocol::protocol_for_app_id(app_id) {
+                Some(detected) => {
+                    debug!(
+                        "Detected {} firmware (application ID {})",
+                        detected.name(),
+                        app_id
+                    );
+                    self.detected_firmware = Some(detected.firmware_type());
+                    self.protocol = detected;
+                    self.sync_write_checks();
+                }
+                None => warn!(
+                    "Unknown application ID {} - using {} protocol",
+                    app_id,
+                    self.protocol.name()
+                ),
+            }
+        }
+
+        if let Some(version) = rss_version.map(RssVersion::from_register) {
+            debug!("RSS version {}", version);
+            if version < MIN_RSS_VERSION {
+                warn!(
+                        "⚠️  RSS version {} is older than the minimum supported {} - update the module firmware",
+                        version, MIN_RSS_VERSION
+                    );
+            }
+            self.rss_version = Some(version);
+        }
+        self.select_quirks(app_id);
+    }
+
+    /// Pick the quirk entry for the running firmware, unless one is forced
+    fn select_quirks(&mut self, app_id: Option<u32>) {
+        let entry = match &self.forced_quirk {
+            Some(forced) => forced.clone(),
+            None => self.quirk_table.select(app_id, self.rss_version),
+        };
+        self.quirks = entry.quirks;
+    }
+
+    /// Use `table` to adjust result parsing per firmware, or always the entry named `forced`
+    pub fn set_quirks(&mut self, table: QuirkTable, forced: Option<&str>) -> Result<()> {
+        self.forced_quirk = forced
+            .map(|name| {
+                table.by_name(name).ok_or_else(|| {
+                    RadarError::InvalidParameters(format!(
+                        "Unknown quirk '{name}' (known: {})",
+                        table.names().join(", ")
+                    ))
+                })
+            })
+            .transpose()?;
+        if let Some(forced) = &self.forced_quirk {
+            info!("🩹 Forcing firmware quirk '{}'", forced.name);
+            self.quirks = forced.quirks;
+        }
+        self.quirk_table = table;
+        Ok(())
+    }
+
+    /// Use `cache` for the application ID and RSS version read at connect time
+    pub fn set_app_id_cache(&mut self, cache: AppIdCache) {
+        self.app_id_cache = cache;
+    }
+
+    /// Record measurements, resets and errors to `stats`
+    pub fn set_stats(&mut self, stats: StatsRecorder) {
+        self.stats = stats;
+    }
+
+    /// Device statistics of this run, merged into the statistics file on drop
+    pub fn stats(&mut self) -> &mut StatsRecorder {
+        &mut self.stats
+    }
+
+    /// Application ID of the running firmware, from the cache when available
+    pub fn application_id(&mut self) -> Result<u32> {
+        if let Some(identity) = self.app_id_cache.get() {
+            return Ok(identity.app_id);
+        }
+        self.read_application_id()
+    }
+
+    /// Drop everything known about the running firmware before it is reset or reflashed
+    ///
+    /// The next command reconnects and reads the identity from the module again.
+    pub fn forget_firmware(&mut self) {
+        self.app_id_cache.invalidate();
+        self.detected_firmware = None;
+        self.rss_version = None;
+        self.select_quirks(None);
+        self.state.fall_back(RadarState::Disconnected);
+    }
+
+    /// Switch a simulated module to the firmware serving `mode` (`--simulate`)
+    ///
+    /// Returns false, changing nothing, when talking to a real module or a recorded trace.
+    pub fn load_simulated_firmware(&mut self, mode: DetectorMode) -> bool {
+        let firmware = protocol::protocol_for_mode(mode).firmware_type();
+        let loaded = self.i2c.load_simulated_application(firmware.into());
+        if loaded {
+            self.forget_firmware();
+        }
+        loaded
+    }
+
+    /// Firmware detected on the module at connect time
+    pub fn detected_firmware(&self) -> Option<FirmwareType> {
+        self.detected_firmware
+    }
+
+    /// Fail fast if the flashed firmware cannot serve `mode`
+    ///
+    /// Other firmwares accept the writes but interpret the registers differently, so a
+    /// mismatch would otherwise show up as nonsense readings rather than an error. Modules
+    /// whose application ID could not be identified are allowed through.
+    pub fn ensure_firmware_for(&mut self, mode: DetectorMode) -> Result<()> {
+        if !self.is_connected() {
+            self.connect()?;
+        }
+        let required = protocol::protocol_for_mode(mode).firmware_type();
+        match self.detected_firmware {
+            Some(detected) if detected != required => {
+                Err(RadarError::FirmwareMismatch { required, detected })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// RSS version detected on the module at connect time
+    pub fn rss_version(&self) -> Option<RssVersion> {
+        self.rss_version
+    }
+
+    /// Refuse to configure firmware older than `MIN_RSS_VERSION`
+    ///
+    /// Unknown versions (read failure) are allowed through; connect has already warned.
+    fn ensure_supported_rss(&self) -> Result<()> {
+        match self.rss_version {
+            Some(version) if version < MIN_RSS_VERSION => Err(RadarError::FirmwareError {
+                message: format!(
+                    "RSS version {} is older than the minimum supported {}; update the module firmware",
+                    version, MIN_RSS_VERSION
+                ),
+            }),
+            _ => Ok(()),
+        }
+    }
+
     /// Reset XM125 to run mode using internal GPIO control
     fn reset_xm125_to_run_mode(&self) -> Result<()> {
         info!("Executing XM125 reset to run mode using internal GPIO control...");
@@ -161,7 +468,7 @@ impl XM125Radar {
 
     /// Get raw status from device
     fn get_status_raw(&mut self) -> Result<u32> {
-        let status_data = self.i2c.read_register(REG_DETECTOR_STATUS, 4)?;
+        let status_data = self.i2c.read_register(self.protocol.status_register(), 4)?;
         Ok(u32::from_be_bytes([
             status_data[0],
             status_data[1],
@@ -170,55 +477,61 @@ impl XM125Radar {
         ]))
     }
 
+    /// Read the raw status register of the running firmware
+    pub fn read_status(&mut self) -> Result<u32> {
+        if !self.is_connected() {
+            self.connect()?;
+        }
+        self.get_status_raw()
+    }
+
     /// Get formatted status string
     pub fn get_status(&mut self) -> Result<String> {
         // Ensure we're connected (this will trigger GPIO initialization if needed)
-        if !self.is_connected {
+        if !self.is_connected() {
             self.connect()?;
         }
 
         let status = self.get_status_raw()?;
-
-        let mut status_parts = Vec::new();
-
-        if status & STATUS_DETECTOR_READY != 0 {
-            status_parts.push("Detector Ready");
-        }
-        if status & STATUS_CALIBRATION_DONE != 0 {
-            status_parts.push("Calibrated");
-        }
-        if status & STATUS_MEASUREMENT_READY != 0 {
-            status_parts.push("Measurement Ready");
-        }
-        if status & STATUS_ERROR != 0 {
-            status_parts.push("ERROR");
-        }
-
-        if status_parts.is_empty() {
-            status_parts.push("Initializing");
-        }
+        let protocol = self.protocol;
+
+        let flags = protocol.decode_status(status);
+        let summary = if protocol.has_error(status) {
+            let errors: Vec<StatusFlag> = flags.into_iter().filter(StatusFlag::is_error).collect();
+            format!("ERROR ({})", protocol::flag_names(&errors))
+        } else if protocol.is_busy(status) {
+            "Busy".to_string()
+        } else if protocol.is_ready(status) {
+            "Detector Ready".to_string()
+        } else if flags.is_empty() {
+            "Initializing".to_string()
+        } else {
+            format!("Initializing ({})", protocol::flag_names(&flags))
+        };
 
         Ok(format!(
-            "Status: {} (0x{:08X})",
-            status_parts.join(", "),
-            status
+            "Status [{}]: {} (0x{:08X}) | State: {}",
+            protocol.name(),
+            summary,
+            status,
+            self.state()
         ))
     }
 
     /// Get device information
     pub fn get_info(&mut self) -> Result<String> {
         // Ensure we're connected (this will trigger GPIO initialization if needed)
-        if !self.is_connected {
+        if !self.is_connected() {
             self.connect()?;
         }
 
-        let version_data = self.i2c.read_register(REG_VERSION, 4)?;
-        let version = u32::from_be_bytes([
-            version_data[0],
-            version_data[1],
-            version_data[2],
-            version_data[3],
-        ]);
+        let raw_version = self.read_version_register()?;
+        let version = RssVersion::from_register(raw_version);
+        let support = if version < MIN_RSS_VERSION {
+            format!(" - older than minimum supported {}", MIN_RSS_VERSION)
+        } else {
+            String::new()
+        };
 
         let app_id_data = self.i2c.read_register(REG_APPLICATION_ID, 4)?;
         let app_id = u32::from_be_bytes([
@@ -228,10 +541,44 @@ impl XM125Radar {
             app_id_data[3],
         ]);
 
-        Ok(format!(
-            "XM125 Radar Module\nVersion: 0x{:08X}\nApplication ID: 0x{:08X}",
-            version, app_id
-        ))
+        let mut info = format!(
+            "XM125 Radar Module\nRSS Version: {} (0x{:08X}){}\nApplication ID: 0x{:08X}",
+            version, raw_version, support, app_id
+        );
+        if xm125_protocol::Application::from_app_id(app_id)
+            == Some(xm125_protocol::Application::Presence)
+        {
+            let detection_gpio = self
+                .i2c
+                .read_register(PRESENCE_REG_DETECTION_ON_GPIO_ADDRESS, 4)?;
+            let enabled = u32::from_be_bytes([
+                detection_gpio[0],
+                detection_gpio[1],
+                detection_gpio[2],
+                detection_gpio[3],
+            ]) != 0;
+            info.push_str(&format!(
+                "\nDetection GPIO: {}",
+                if enabled { "enabled" } else { "disabled" }
+            ));
+        }
+        Ok(info)
+    }
+
+    /// Read the raw version register
+    fn read_version_register(&mut self) -> Result<u32> {
+        let version_data = self.i2c.read_register(REG_VERSION, 4)?;
+        Ok(u32::from_be_bytes([
+            version_data[0],
+            version_data[1],
+            version_data[2],
+            version_data[3],
+        ]))
+    }
+
+    /// Read and decode the RSS version
+    pub fn read_rss_version(&mut self) -> Result<RssVersion> {
+        self.read_version_register().map(RssVersion::from_register)
     }
 
     /// Read application ID (for firmware compatibility)
@@ -245,9 +592,58 @@ impl XM125Radar {
         ]))
     }
 
-    /// Set detector mode
+    /// Read the measure counter (incremented by the module once per radar frame)
+    pub fn read_measure_counter(&mut self) -> Result<u32> {
+        let counter = self.i2c.read_register(REG_MEASURE_COUNTER, 4)?;
+        Ok(u32::from_be_bytes([
+            counter[0], counter[1], counter[2], counter[3],
+        ]))
+    }
+
+    /// Set detector mode (selects the matching register protocol unless firmware was detected)
     pub fn set_detector_mode(&mut self, mode: DetectorMode) {
         self.config.detector_mode = mode;
+        self.protocol = match self.detected_firmware {
+            Some(firmware) => protocol::protocol_for_firmware(firmware),
+            None => protocol::protocol_for_mode(mode),
+        };
+        self.sync_write_checks();
+    }
+
+    /// Fail any register write made while the detector is busy or in error
+    pub fn set_strict_protocol(&mut self, enabled: bool) {
+        self.strict_protocol = enabled;
+        self.sync_write_checks();
+    }
+
+    /// Read back register writes at `level` and retry the ones that do not match
+    pub fn set_write_verify(&mut self, level: WriteVerify) {
+        self.write_verify = level;
+        self.sync_write_checks();
+    }
+
+    /// Wait for detector commands on the MCU_INT edge (`--wait-mode interrupt`) or poll
+    pub fn set_interrupt_wait(&mut self, enabled: bool) -> Result<()> {
+        let pin = enabled
+            .then(|| {
+                XM125GpioController::with_pins(self.gpio_pins)
+                    .open_edge_input(self.gpio_pins.mcu_interrupt, "MCU Interrupt")
+            })
+            .transpose()?;
+        self.i2c.set_ready_interrupt(pin);
+        Ok(())
+    }
+
+    /// Keep the strict and read-back write checks on the protocol in use
+    fn sync_write_checks(&mut self) {
+        self.i2c
+            .set_strict_protocol(self.strict_protocol.then_some(self.protocol));
+        self.i2c.set_write_verify(self.write_verify, self.protocol);
+    }
+
+    /// Register protocol currently used to talk to the module
+    pub fn protocol(&self) -> &'static dyn DetectorProtocol {
+        self.protocol
     }
 
     /// Get detector mode
@@ -257,18 +653,44 @@ impl XM125Radar {
 
     /// Check if radar is connected
     pub fn is_connected(&self) -> bool {
-        self.is_connected
+        self.state.state() >= RadarState::Connected
+    }
+
+    /// Where the module is in its startup sequence
+    pub fn state(&self) -> RadarState {
+        self.state.state()
+    }
+
+    /// Keep the register words each measurement was decoded from
+    pub fn set_include_raw(&mut self, include_raw: bool) {
+        self.include_raw = include_raw;
+    }
+
+    /// Anonymize measurements, with distances quantized to `bucket_m`
+    pub fn set_privacy(&mut self, bucket_m: Option<f32>) {
+        self.privacy_bucket = bucket_m;
+    }
+
+    /// Settings file generation to record in subsequent measurements
+    pub fn set_config_generation(&mut self, generation: Option<u32>) {
+        self.config_generation = generation;
     }
 
     /// Configure presence detector
     pub async fn configure_presence_detector(&mut self) -> Result<()> {
         info!("🔧 Configuring presence detector...");
 
+        if !self.is_connected() {
+            self.connect()?;
+        }
+        self.ensure_supported_rss()?;
+
         // Set detector mode to presence
-        self.config.detector_mode = DetectorMode::Presence;
+        self.set_detector_mode(DetectorMode::Presence);
 
         // Create presence detector and configure it
-        let mut presence_detector = presence::PresenceDetector::new(&mut self.i2c);
+        let mut presence_detector =
+            presence::PresenceDetector::new(&mut self.i2c, self.config.timeouts);
 
         // Configure range (check for custom range override)
         let custom_start = if self.config.start_m > 0.0 {
@@ -282,59 +704,53 @@ impl XM125Radar {
             None
         };
 
-        let (profile, step_length) = presence_detector.configure_range(
+        let settings = presence_detector.configure_range(
             self.config.presence_range,
             custom_start,
             custom_length,
+            self.config.auto_profile_enabled,
         )?;
 
-        // Calculate final range values for hardware registers
-        let (final_start_mm, final_end_mm) =
-            if let (Some(start_m), Some(length_m)) = (custom_start, custom_length) {
-                // Use custom range values
-                let start_mm = (start_m * 1000.0) as u32;
-                let end_mm = ((start_m + length_m) * 1000.0) as u32;
-                (start_mm, end_mm)
-            } else {
-                // Use preset range values
-                match self.config.presence_range {
-                    presence::PresenceRange::Short => (60u32, 700u32), // 0.06m - 0.7m
-                    presence::PresenceRange::Medium => (200u32, 2000u32), // 0.2m - 2.0m
-                    presence::PresenceRange::Long => (300u32, 5500u32), // 0.3m - 5.5m
-                }
-            };
-
         presence_detector.configure_thresholds(
             self.config.intra_detection_threshold,
             self.config.inter_detection_threshold,
             self.config.frame_rate,
-            profile,
-            step_length,
+            &settings,
             self.config.auto_profile_enabled,
-            final_start_mm,
-            final_end_mm,
         )?;
+        self.state.transition(RadarState::Configured)?;
 
         // CRITICAL: Apply the complete configuration sequence (reset, apply, verify, start)
         info!("🔧 Applying complete presence detector configuration sequence...");
-        presence_detector.apply_complete_configuration(final_start_mm, final_end_mm)?;
+        presence_detector
+            .apply_complete_configuration(
+                &settings,
+                self.config.motion_detection,
+                self.config.detection_on_gpio,
+            )
+            .await?;
+        self.state.transition(RadarState::Calibrated)?;
+        self.state.transition(RadarState::Streaming)?;
 
         info!("✅ Presence detector configured successfully");
         Ok(())
     }
 
     /// Configure presence range and parameters (called from main.rs)
-    pub fn configure_presence_range(&mut self) -> Result<()> {
+    pub async fn configure_presence_range(&mut self) -> Result<()> {
         info!("🔧 Configuring presence range and parameters...");
 
         // Ensure connection before configuration
         self.connect()?;
 
+        self.ensure_supported_rss()?;
+
         // Set detector mode to presence
-        self.config.detector_mode = DetectorMode::Presence;
+        self.set_detector_mode(DetectorMode::Presence);
 
         // Create presence detector and configure it
-        let mut presence_detector = presence::PresenceDetector::new(&mut self.i2c);
+        let mut presence_detector =
+            presence::PresenceDetector::new(&mut self.i2c, self.config.timeouts);
 
         // Configure range (check for custom range override)
         let custom_start = if self.config.start_m > 0.0 {
@@ -348,43 +764,33 @@ impl XM125Radar {
             None
         };
 
-        let (profile, step_length) = presence_detector.configure_range(
+        let settings = presence_detector.configure_range(
             self.config.presence_range,
             custom_start,
             custom_length,
+            self.config.auto_profile_enabled,
         )?;
 
-        // Calculate final range values for hardware registers
-        let (final_start_mm, final_end_mm) =
-            if let (Some(start_m), Some(length_m)) = (custom_start, custom_length) {
-                // Use custom range values
-                let start_mm = (start_m * 1000.0) as u32;
-                let end_mm = ((start_m + length_m) * 1000.0) as u32;
-                (start_mm, end_mm)
-            } else {
-                // Use preset range values
-                match self.config.presence_range {
-                    presence::PresenceRange::Short => (60u32, 700u32), // 0.06m - 0.7m
-                    presence::PresenceRange::Medium => (200u32, 2000u32), // 0.2m - 2.0m
-                    presence::PresenceRange::Long => (300u32, 5500u32), // 0.3m - 5.5m
-                }
-            };
-
-        // Pass the auto_profile_enabled config and range values to configure_thresholds
         presence_detector.configure_thresholds(
             self.config.intra_detection_threshold,
             self.config.inter_detection_threshold,
             self.config.frame_rate,
-            profile,
-            step_length,
-            self.config.auto_profile_enabled, // Pass the profile mode
-            final_start_mm,
-            final_end_mm,
+            &settings,
+            self.config.auto_profile_enabled,
         )?;
+        self.state.transition(RadarState::Configured)?;
 
         // CRITICAL: Apply the complete configuration sequence (reset, apply, verify, start)
         info!("🔧 Applying complete presence detector configuration sequence...");
-        presence_detector.apply_complete_configuration(final_start_mm, final_end_mm)?;
+        presence_detector
+            .apply_complete_configuration(
+                &settings,
+                self.config.motion_detection,
+                self.config.detection_on_gpio,
+            )
+            .await?;
+        self.state.transition(RadarState::Calibrated)?;
+        self.state.transition(RadarState::Streaming)?;
 
         info!("✅ Presence range and parameters configured successfully");
         Ok(())
@@ -392,64 +798,257 @@ impl XM125Radar {
 
     /// Start presence detector
     pub async fn start_presence_detector(&mut self) -> Result<()> {
-        let mut presence_detector = presence::PresenceDetector::new(&mut self.i2c);
-        presence_detector.start_detector().await
+        let mut presence_detector =
+            presence::PresenceDetector::new(&mut self.i2c, self.config.timeouts);
+        presence_detector.start_detector().await?;
+        self.state.transition(RadarState::Streaming)
     }
 
     /// Stop presence detector
     pub async fn stop_presence_detector(&mut self) -> Result<()> {
-        let mut presence_detector = presence::PresenceDetector::new(&mut self.i2c);
-        presence_detector.stop_detector().await
+        let mut presence_detector =
+            presence::PresenceDetector::new(&mut self.i2c, self.config.timeouts);
+        presence_detector.stop_detector().await?;
+        self.state.fall_back(RadarState::Calibrated);
+        Ok(())
     }
 
     /// Measure presence
     pub async fn measure_presence(&mut self) -> Result<PresenceMeasurement> {
+        let operation = telemetry::start_measurement("presence");
+        let mut result = self.measure_presence_cycle().await;
+        operation.finish(&result);
+        if let Ok(measurement) = &mut result {
+            measurement.config_generation = self.config_generation;
+            if !self.include_raw {
+                measurement.raw = None;
+            }
+        }
+        match &result {
+            Ok(measurement) => {
+                self.stats.measurement(measurement.presence_detected);
+                heartbeat::beat();
+            }
+            Err(_) => self.stats.error(),
+        }
+        if let (Ok(measurement), Some(bucket)) = (&mut result, self.privacy_bucket) {
+            measurement.anonymize(bucket);
+        }
+        result
+    }
+
+    /// Single presence measurement cycle
+    async fn measure_presence_cycle(&mut self) -> Result<PresenceMeasurement> {
         // Ensure the detector is configured and started
         if self.config.detector_mode != DetectorMode::Presence {
             self.configure_presence_detector().await?;
             self.start_presence_detector().await?;
         }
 
-        let mut presence_detector = presence::PresenceDetector::new(&mut self.i2c);
-        presence_detector.measure().await
+        let mut presence_detector =
+            presence::PresenceDetector::new(&mut self.i2c, self.config.timeouts);
+        let mut measurement = presence_detector.measure().await?;
+        if self.quirks != Quirks::default() {
+            self.quirks.apply_presence(&mut measurement);
+        }
+        Ok(measurement)
     }
 
     /// Configure distance detector
     pub async fn configure_distance_detector(&mut self) -> Result<()> {
         info!("🔧 Configuring distance detector...");
 
+        if !self.is_connected() {
+            self.connect()?;
+        }
+
         // Set detector mode to distance
-        self.config.detector_mode = DetectorMode::Distance;
+        self.set_detector_mode(DetectorMode::Distance);
 
         // Create distance detector and configure it
-        let mut distance_detector = distance::DistanceDetector::new(&mut self.i2c);
+        let mut distance_detector =
+            distance::DistanceDetector::new(&mut self.i2c, self.config.timeouts);
 
         distance_detector.configure_range(self.config.start_m, self.config.length_m)?;
-        distance_detector.configure_detector()?;
+        distance_detector.configure_detector(
+            self.config.max_step_length,
+            self.config.max_profile,
+            self.config.reflector_shape,
+            self.config.target_material,
+            self.config.recorded_threshold_frames,
+        )?;
+        self.state.transition(RadarState::Configured)?;
         distance_detector.apply_config_and_calibrate().await?;
 
-        self.is_calibrated = true;
+        self.state.transition(RadarState::Calibrated)?;
         self.last_calibration = Some(Instant::now());
+        self.calibration_temperature = None;
 
         info!("✅ Distance detector configured successfully");
         Ok(())
     }
 
+    /// Recalibrate the configured distance detector, re-recording a recorded threshold
+    pub async fn recalibrate_distance_detector(&mut self) -> Result<()> {
+        let mut distance_detector =
+            distance::DistanceDetector::new(&mut self.i2c, self.config.timeouts);
+        distance_detector.recalibrate().await?;
+        self.last_calibration = Some(Instant::now());
+        self.calibration_temperature = None;
+        Ok(())
+    }
+
+    /// Configure and start the breathing application
+    pub async fn configure_breathing_detector(&mut self) -> Result<()> {
+        info!("🔧 Configuring breathing application...");
+
+        if !self.is_connected() {
+            self.connect()?;
+        }
+        self.ensure_supported_rss()?;
+        self.set_detector_mode(DetectorMode::Breathing);
+
+        let mut breathing_detector =
+            breathing::BreathingDetector::new(&mut self.i2c, self.config.timeouts);
+        self.state.transition(RadarState::Configured)?;
+        breathing_detector.configure(&self.config.breathing).await?;
+        self.state.transition(RadarState::Calibrated)?;
+        self.state.transition(RadarState::Streaming)?;
+        Ok(())
+    }
+
+    /// Measure breathing rate
+    pub async fn measure_breathing(&mut self) -> Result<BreathingMeasurement> {
+        let operation = telemetry::start_measurement("breathing");
+        if self.config.detector_mode != DetectorMode::Breathing
+            || self.state() < RadarState::Streaming
+        {
+            if let Err(e) = self.configure_breathing_detector().await {
+                let result = Err(e);
+                operation.finish(&result);
+                self.stats.error();
+                return result;
+            }
+        }
+        let mut result =
+            breathing::BreathingDetector::new(&mut self.i2c, self.config.timeouts).measure();
+        if let (Ok(measurement), true) = (&mut result, self.quirks != Quirks::default()) {
+            self.quirks.apply_breathing(measurement);
+        }
+        operation.finish(&result);
+        match &mut result {
+            Ok(measurement) => {
+                self.stats.measurement(measurement.breathing_rate.is_some());
+                heartbeat::beat();
+                if !self.include_raw {
+                    measurement.raw = None;
+                }
+            }
+            Err(_) => self.stats.error(),
+        }
+        result
+    }
+
     /// Measure distance
     pub async fn measure_distance(&mut self) -> Result<DistanceMeasurement> {
+        let operation = telemetry::start_measurement("distance");
+        let mut result = self.measure_distance_cycle().await;
+        operation.finish(&result);
+        if let Ok(measurement) = &mut result {
+            measurement.config_generation = self.config_generation;
+            if !self.include_raw {
+                measurement.raw = None;
+            }
+        }
+        match &result {
+            Ok(measurement) => {
+                self.stats.measurement(measurement.distance > 0.0);
+                heartbeat::beat();
+            }
+            Err(_) => self.stats.error(),
+        }
+        if let (Ok(measurement), Some(bucket)) = (&mut result, self.privacy_bucket) {
+            measurement.anonymize(bucket);
+        }
+        result
+    }
+
+    /// Single distance measurement cycle
+    async fn measure_distance_cycle(&mut self) -> Result<DistanceMeasurement> {
         // Ensure the detector is configured
-        if self.config.detector_mode != DetectorMode::Distance || !self.is_calibrated {
+        if self.config.detector_mode != DetectorMode::Distance
+            || self.state() < RadarState::Calibrated
+        {
             self.configure_distance_detector().await?;
         }
 
-        let mut distance_detector = distance::DistanceDetector::new(&mut self.i2c);
-        distance_detector.measure().await
+        let mut distance_detector =
+            distance::DistanceDetector::new(&mut self.i2c, self.config.timeouts);
+        let mut measurement = distance_detector.measure(self.config.max_peaks).await?;
+        if self.quirks != Quirks::default() {
+            self.quirks.apply_distance(&mut measurement);
+        }
+        let reported = measurement.distance;
+        measurement.strength_db -= self.config.strength_reference_db;
+
+        // The first reading after detector calibration fixes the reference temperature
+        let reference = *self
+            .calibration_temperature
+            .get_or_insert(measurement.temperature);
+        let coefficient = self.config.temperature_coefficient;
+        let delta = measurement.temperature - reference;
+        if coefficient != 0.0 {
+            measurement.temperature_delta = Some(delta);
+        }
+
+        // Thermal drift and reference-target corrections apply to every peak alike
+        let correct = |distance: f32| {
+            let distance = if coefficient == 0.0 {
+                distance
+            } else {
+                correct_thermal_drift(distance, coefficient, delta)
+            };
+            match &self.config.distance_calibration {
+                Some(calibration) => calibration.apply(distance),
+                None => distance,
+            }
+        };
+        measurement.distance = correct(measurement.distance);
+        for peak in measurement.peaks.iter_mut().flatten() {
+            peak.distance = correct(peak.distance);
+            peak.strength_db -= self.config.strength_reference_db;
+        }
+        if coefficient != 0.0 || self.config.distance_calibration.is_some() {
+            measurement.uncorrected_distance = Some(reported);
+        }
+
+        Ok(measurement)
     }
 
-    /// Debug registers
-    pub fn debug_registers(&mut self, mode: &str) -> Result<()> {
-        let mut debugger = debug::RegisterDebugger::new(&mut self.i2c);
-        debugger.debug_all_registers(mode)
+    /// Read every register in the table of the detected firmware
+    pub fn register_dump(&mut self) -> Result<debug::RegisterDump> {
+        if !self.is_connected() {
+            self.connect()?;
+        }
+        debug::RegisterDebugger::new(&mut self.i2c).dump_all_registers(self.protocol)
+    }
+
+    /// Read every configuration register of the running firmware
+    pub fn read_config_registers(&mut self) -> Result<Vec<(RegisterInfo, u32)>> {
+        if !self.is_connected() {
+            self.connect()?;
+        }
+        self.protocol
+            .config_registers()
+            .iter()
+            .map(|info| {
+                let data = self.i2c.read_register(info.address, 4)?;
+                Ok((
+                    *info,
+                    u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+                ))
+            })
+            .collect()
     }
 
     /// Configure distance range from string (e.g., "0.1:3.0")
@@ -3,23 +3,29 @@
 
 #![allow(clippy::pedantic)]
 
+use super::protocol::{describe_errors, DetectorProtocol, PresenceProtocol, ResultWord};
 use super::registers::{
     CMD_PRESENCE_APPLY_CONFIGURATION, CMD_PRESENCE_RESET_MODULE, CMD_PRESENCE_START_DETECTOR,
     CMD_PRESENCE_STOP_DETECTOR, CMD_RESET_MODULE, PRESENCE_REG_AUTO_PROFILE_ADDRESS,
     PRESENCE_REG_AUTO_STEP_LENGTH_ADDRESS, PRESENCE_REG_AUTO_SUBSWEEPS_ADDRESS,
-    PRESENCE_REG_COMMAND_ADDRESS, PRESENCE_REG_DETECTOR_STATUS_ADDRESS, PRESENCE_REG_END_ADDRESS,
+    PRESENCE_REG_COMMAND_ADDRESS, PRESENCE_REG_DETECTION_ON_GPIO_ADDRESS,
+    PRESENCE_REG_DETECTOR_STATUS_ADDRESS, PRESENCE_REG_END_ADDRESS,
     PRESENCE_REG_FRAME_RATE_ADDRESS, PRESENCE_REG_HWAAS_ADDRESS,
-    PRESENCE_REG_INTER_DETECTION_THRESHOLD_ADDRESS, PRESENCE_REG_INTRA_DETECTION_THRESHOLD_ADDRESS,
+    PRESENCE_REG_INTER_DETECTION_ENABLED_ADDRESS, PRESENCE_REG_INTER_DETECTION_THRESHOLD_ADDRESS,
+    PRESENCE_REG_INTRA_DETECTION_ENABLED_ADDRESS, PRESENCE_REG_INTRA_DETECTION_THRESHOLD_ADDRESS,
     PRESENCE_REG_MANUAL_PROFILE_ADDRESS, PRESENCE_REG_MANUAL_STEP_LENGTH_ADDRESS,
     PRESENCE_REG_SIGNAL_QUALITY_ADDRESS, PRESENCE_REG_START_ADDRESS, REG_INTER_PRESENCE_SCORE,
-    REG_INTRA_PRESENCE_SCORE, REG_PRESENCE_DISTANCE, REG_PRESENCE_RESULT, STATUS_BUSY_MASK,
-    STATUS_ERROR_MASK,
+    REG_INTRA_PRESENCE_SCORE, REG_PRESENCE_DISTANCE, REG_PRESENCE_RESULT,
 };
+use super::RawRegisters;
 use crate::error::{RadarError, Result};
+use crate::fault::{self, Fault};
 use crate::i2c::I2cDevice;
+use crate::timeouts::{TimeoutPhase, Timeouts, POLL_INTERVAL};
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 
+/// Presence detection range preset
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum PresenceRange {
     Short,  // 0.06m - 0.7m (6cm - 70cm)
@@ -27,6 +33,27 @@ pub enum PresenceRange {
     Long,   // 0.3m - 5.5m (30cm - 5.5m) - Updated to match Philip's working config
 }
 
+/// Which motion the presence detector reacts to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum MotionDetection {
+    /// Intra-frame only: quick reaction to large movements such as someone entering
+    Fast,
+    /// Inter-frame only: small, slow movements such as a seated person breathing
+    Slow,
+    Both,
+}
+
+impl MotionDetection {
+    fn intra_enabled(self) -> bool {
+        self != MotionDetection::Slow
+    }
+
+    fn inter_enabled(self) -> bool {
+        self != MotionDetection::Fast
+    }
+}
+
+/// One presence detector result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PresenceMeasurement {
     pub presence_detected: bool,
@@ -34,28 +61,89 @@ pub struct PresenceMeasurement {
     pub intra_presence_score: f32, // Fast motion score
     pub inter_presence_score: f32, // Slow motion score
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Settings file generation in effect (see `--config`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_generation: Option<u32>,
+    /// Register words the measurement was decoded from (see `--include-raw`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<RawRegisters>,
 }
 
-pub struct PresenceDetector<'a> {
-    i2c: &'a mut I2cDevice,
+/// Maximum usable end point per profile in mm (index 0 = Profile 1)
+const PROFILE_MAX_END_MM: [u32; 5] = [700, 2000, 3500, 6000, 7000];
+/// Closest start point supported by the presence detector (short preset start)
+const MIN_START_MM: u32 = 60;
+/// Profile forced when auto profile selection is disabled
+pub const MANUAL_PROFILE: u32 = 5;
+
+/// Range, profile and step length to be written to the detector
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeSettings {
+    pub start_mm: u32,
+    pub end_mm: u32,
+    pub profile: u32,
+    pub step_length: u32,
 }
 
-impl<'a> PresenceDetector<'a> {
-    pub fn new(i2c: &'a mut I2cDevice) -> Self {
-        Self { i2c }
+/// Select the profile for a range, validating the range against its physical limits
+///
+/// With auto profile the shortest profile that reaches `end_mm` is chosen; otherwise
+/// [`MANUAL_PROFILE`] is used and the range must fit within it.
+pub fn select_profile(start_mm: u32, end_mm: u32, auto_profile: bool) -> Result<u32> {
+    if start_mm < MIN_START_MM {
+        return Err(RadarError::InvalidParameters(format!(
+            "Presence range start {:.2}m is below the detector minimum of {:.2}m",
+            start_mm as f32 / 1000.0,
+            MIN_START_MM as f32 / 1000.0
+        )));
+    }
+    if start_mm >= end_mm {
+        return Err(RadarError::InvalidParameters(format!(
+            "Presence range start {:.2}m must be less than end {:.2}m",
+            start_mm as f32 / 1000.0,
+            end_mm as f32 / 1000.0
+        )));
     }
 
-    /// Calculate optimal profile based on detection range
-    fn calculate_optimal_profile(_start_mm: u32, end_mm: u32) -> u32 {
-        let max_range_m = end_mm as f32 / 1000.0;
-
-        match max_range_m {
-            r if r <= 0.7 => 1, // Profile 1: Short range (0.06m - 0.7m)
-            r if r <= 2.0 => 2, // Profile 2: Short-medium range (0.2m - 2.0m)
-            r if r <= 3.5 => 3, // Profile 3: Medium range (0.3m - 3.5m)
-            r if r <= 6.0 => 4, // Profile 4: Medium-long range (0.5m - 6.0m)
-            _ => 5,             // Profile 5: Long range (1.0m - 7.0m+)
+    let exceeds = |profile: u32| {
+        RadarError::InvalidParameters(format!(
+            "Presence range end {:.2}m exceeds the Profile {} maximum of {:.2}m",
+            end_mm as f32 / 1000.0,
+            profile,
+            PROFILE_MAX_END_MM[profile as usize - 1] as f32 / 1000.0
+        ))
+    };
+
+    if auto_profile {
+        PROFILE_MAX_END_MM
+            .iter()
+            .position(|&limit| end_mm <= limit)
+            .map(|index| index as u32 + 1)
+            .ok_or_else(|| exceeds(PROFILE_MAX_END_MM.len() as u32))
+    } else if end_mm > PROFILE_MAX_END_MM[MANUAL_PROFILE as usize - 1] {
+        Err(exceeds(MANUAL_PROFILE))
+    } else {
+        if end_mm <= PROFILE_MAX_END_MM[1] {
+            warn!(
+                "Manual Profile {} is coarse for a {:.2}m range; --profile auto would select Profile {}",
+                MANUAL_PROFILE,
+                end_mm as f32 / 1000.0,
+                select_profile(start_mm, end_mm, true)?
+            );
         }
+        Ok(MANUAL_PROFILE)
+    }
+}
+
+/// Register-level driver of the presence detector application
+pub struct PresenceDetector<'a> {
+    i2c: &'a mut I2cDevice,
+    timeouts: Timeouts,
+}
+
+impl<'a> PresenceDetector<'a> {
+    pub fn new(i2c: &'a mut I2cDevice, timeouts: Timeouts) -> Self {
+        Self { i2c, timeouts }
     }
 
     /// Calculate optimal step length based on maximum range
@@ -76,7 +164,8 @@ impl<'a> PresenceDetector<'a> {
         range: PresenceRange,
         custom_start_m: Option<f32>,
         custom_length_m: Option<f32>,
-    ) -> Result<(u32, u32)> {
+        auto_profile: bool,
+    ) -> Result<RangeSettings> {
         info!("🎯 Configuring presence range preset: {:?}", range);
 
         // Convert PresenceRange enum to millimeter values according to datasheet
@@ -119,13 +208,13 @@ impl<'a> PresenceDetector<'a> {
                 (start_mm, end_mm)
             };
 
-        // Calculate optimal profile based on actual range being used
-        let optimal_profile = Self::calculate_optimal_profile(final_start_mm, final_end_mm);
-        let optimal_step_length = Self::calculate_optimal_step_length(final_end_mm);
+        // Select and validate the profile for the range actually being used
+        let profile = select_profile(final_start_mm, final_end_mm, auto_profile)?;
+        let step_length = Self::calculate_optimal_step_length(final_end_mm);
 
         info!(
             "🎯 Selected Profile {} for range {}mm-{}mm ({:.1}m-{:.1}m)",
-            optimal_profile,
+            profile,
             final_start_mm,
             final_end_mm,
             final_start_mm as f32 / 1000.0,
@@ -133,7 +222,12 @@ impl<'a> PresenceDetector<'a> {
         );
 
         info!("✅ Presence range parameters calculated");
-        Ok((optimal_profile, optimal_step_length))
+        Ok(RangeSettings {
+            start_mm: final_start_mm,
+            end_mm: final_end_mm,
+            profile,
+            step_length,
+        })
     }
 
     /// Configure thresholds and frame rate
@@ -143,12 +237,15 @@ impl<'a> PresenceDetector<'a> {
         intra_threshold: f32,
         inter_threshold: f32,
         frame_rate: f32,
-        profile: u32,
-        step_length: u32,
+        settings: &RangeSettings,
         auto_profile_enabled: bool,
-        start_mm: u32,
-        end_mm: u32,
     ) -> Result<()> {
+        let RangeSettings {
+            start_mm,
+            end_mm,
+            profile,
+            step_length,
+        } = *settings;
         // Write threshold and frame rate configuration
         let intra_threshold_scaled = (intra_threshold * 1000.0) as u32;
         let inter_threshold_scaled = (inter_threshold * 1000.0) as u32;
@@ -182,7 +279,7 @@ impl<'a> PresenceDetector<'a> {
                 .write_register(PRESENCE_REG_AUTO_STEP_LENGTH_ADDRESS, &1u32.to_be_bytes())?;
         } else {
             info!(
-                "🔧 Disabling Auto Profile (using manual Profile {} for 7m range)",
+                "🔧 Disabling Auto Profile (using manual Profile {})",
                 profile
             );
             self.i2c
@@ -248,18 +345,22 @@ impl<'a> PresenceDetector<'a> {
     }
 
     /// Apply the complete configuration including range settings
-    pub fn apply_complete_configuration(
+    pub async fn apply_complete_configuration(
         &mut self,
-        final_start_mm: u32,
-        final_end_mm: u32,
+        settings: &RangeSettings,
+        motion: MotionDetection,
+        detection_on_gpio: bool,
     ) -> Result<()> {
+        let final_start_mm = settings.start_mm;
+        let final_end_mm = settings.end_mm;
+
         // CRITICAL: Reset module before applying new configuration (from datasheet requirement)
         info!("Resetting presence detector module before configuration...");
-        self.reset_module()?;
+        self.reset_module().await?;
 
         // Wait for reset to complete
         info!("Waiting for module reset to complete...");
-        self.wait_for_not_busy()?;
+        self.wait_for_not_busy().await?;
 
         // CRITICAL: Configure Auto Profile settings AFTER reset (reset wipes these settings)
         info!("Disabling Auto Profile and Auto Step Length AFTER reset");
@@ -268,22 +369,18 @@ impl<'a> PresenceDetector<'a> {
         self.i2c
             .write_register(PRESENCE_REG_AUTO_STEP_LENGTH_ADDRESS, &0u32.to_be_bytes())?;
 
-        // Calculate and set optimal profile for 7m range
-        let optimal_profile: u32 = if final_end_mm >= 6500 { 5 } else { 4 }; // Profile 5 for 7m
-        let optimal_step_length = ((final_end_mm as f32 / 1000.0) * 1000.0 / 60.0) as u32;
-        let optimal_step_length = optimal_step_length.clamp(12, 120);
-
+        // Use the profile selected (and validated) for the requested range
         info!(
             "Setting Manual Profile {} and Step Length {} for {}mm range",
-            optimal_profile, optimal_step_length, final_end_mm
+            settings.profile, settings.step_length, final_end_mm
         );
         self.i2c.write_register(
             PRESENCE_REG_MANUAL_PROFILE_ADDRESS,
-            &optimal_profile.to_be_bytes(),
+            &settings.profile.to_be_bytes(),
         )?;
         self.i2c.write_register(
             PRESENCE_REG_MANUAL_STEP_LENGTH_ADDRESS,
-            &optimal_step_length.to_be_bytes(),
+            &settings.step_length.to_be_bytes(),
         )?;
 
         // Set Signal Quality to 20000 for long range
@@ -314,6 +411,22 @@ impl<'a> PresenceDetector<'a> {
 
         info!("✅ Range configuration written to hardware registers");
 
+        info!("Motion detection: {motion:?}");
+        self.i2c.write_register(
+            PRESENCE_REG_INTRA_DETECTION_ENABLED_ADDRESS,
+            &u32::from(motion.intra_enabled()).to_be_bytes(),
+        )?;
+        self.i2c.write_register(
+            PRESENCE_REG_INTER_DETECTION_ENABLED_ADDRESS,
+            &u32::from(motion.inter_enabled()).to_be_bytes(),
+        )?;
+
+        // The detection output lets the host sleep on a GPIO edge instead of polling results
+        self.i2c.write_register(
+            PRESENCE_REG_DETECTION_ON_GPIO_ADDRESS,
+            &u32::from(detection_on_gpio).to_be_bytes(),
+        )?;
+
         // CRITICAL: Apply configuration by writing CMD_PRESENCE_APPLY_CONFIGURATION to command register 0x0100
         // Without this step, detector uses default values (end point = 2500mm)
         info!("Applying presence detector configuration (CMD_PRESENCE_APPLY_CONFIGURATION to register 0x0100)");
@@ -324,7 +437,7 @@ impl<'a> PresenceDetector<'a> {
 
         // CRITICAL: Wait for the configuration to be done (from example code)
         info!("Waiting for configuration to complete...");
-        self.wait_for_not_busy()?;
+        self.wait_for_not_busy().await?;
 
         // CRITICAL: Test if configuration of detector was OK (from example code)
         info!("Verifying configuration was applied successfully...");
@@ -349,42 +462,38 @@ impl<'a> PresenceDetector<'a> {
     }
 
     /// Wait for detector to not be busy (from example code)
-    fn wait_for_not_busy(&mut self) -> Result<()> {
-        let timeout = std::time::Duration::from_secs(5);
+    ///
+    /// Presence commands (reset, apply configuration, start/stop) all complete within the
+    /// calibration phase limit; measurements read results without waiting.
+    async fn wait_for_not_busy(&mut self) -> Result<()> {
+        let phase = TimeoutPhase::Calibration;
+        let timeout = self.timeouts.get(phase);
+        if Fault::timeout(phase).is_some_and(fault::inject) {
+            return Err(RadarError::Timeout { phase, timeout });
+        }
         let start = std::time::Instant::now();
 
         while start.elapsed() < timeout {
             if !self.is_busy()? {
                 return Ok(());
             }
-            std::thread::sleep(std::time::Duration::from_millis(10));
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if !self.i2c.wait_for_ready_interrupt(remaining)? {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
         }
 
-        Err(RadarError::Timeout {
-            timeout: timeout.as_secs(),
-        })
+        Err(RadarError::Timeout { phase, timeout })
     }
 
     /// Check if configuration was applied successfully (from example code)
     fn configuration_ok(&mut self) -> Result<bool> {
-        // Read detector status to check for configuration success
-        let status_data = self
-            .i2c
-            .read_register(PRESENCE_REG_DETECTOR_STATUS_ADDRESS, 4)?;
-        let status = u32::from_be_bytes([
-            status_data[0],
-            status_data[1],
-            status_data[2],
-            status_data[3],
-        ]);
-
-        // Check if there are any error bits set (bit 28 and others)
-        let has_errors = (status & 0x10000000) != 0; // Error bit
+        let status = self.read_status()?;
 
-        if has_errors {
+        if PresenceProtocol.has_error(status) {
             warn!(
-                "Configuration failed - detector status shows errors: 0x{:08X}",
-                status
+                "Configuration failed - {}",
+                describe_errors(&PresenceProtocol, status)
             );
             return Ok(false);
         }
@@ -393,7 +502,7 @@ impl<'a> PresenceDetector<'a> {
     }
 
     /// Reset the presence detector module (needed to make a new configuration)
-    fn reset_module(&mut self) -> Result<()> {
+    async fn reset_module(&mut self) -> Result<()> {
         info!(
             "Resetting presence detector module (CMD_PRESENCE_RESET_MODULE: {})...",
             CMD_PRESENCE_RESET_MODULE
@@ -404,40 +513,52 @@ impl<'a> PresenceDetector<'a> {
         )?;
 
         // Wait a moment for reset to take effect
-        std::thread::sleep(std::time::Duration::from_millis(200));
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
 
         Ok(())
     }
 
-    /// Check if presence detector is busy (section 2.3.1 compliance)
-    pub fn is_busy(&mut self) -> Result<bool> {
+    fn read_status(&mut self) -> Result<u32> {
         let status = self
             .i2c
             .read_register(PRESENCE_REG_DETECTOR_STATUS_ADDRESS, 4)?;
-        let status_value = u32::from_be_bytes([status[0], status[1], status[2], status[3]]);
-        Ok((status_value & STATUS_BUSY_MASK) != 0)
+        Ok(u32::from_be_bytes([
+            status[0], status[1], status[2], status[3],
+        ]))
+    }
+
+    /// Check if presence detector is busy (section 2.3.1 compliance)
+    pub fn is_busy(&mut self) -> Result<bool> {
+        Ok(PresenceProtocol.is_busy(self.read_status()?))
     }
 
     /// Check if presence detector has errors (section 2.3.1 compliance)
     pub fn has_errors(&mut self) -> Result<bool> {
-        let status = self
-            .i2c
-            .read_register(PRESENCE_REG_DETECTOR_STATUS_ADDRESS, 4)?;
-        let status_value = u32::from_be_bytes([status[0], status[1], status[2], status[3]]);
-        Ok((status_value & STATUS_ERROR_MASK) != 0)
+        Ok(PresenceProtocol.has_error(self.read_status()?))
+    }
+
+    /// Fail with the decoded error flags if the detector reports an error
+    fn check_errors(&mut self, failure: &str) -> Result<()> {
+        let status = self.read_status()?;
+        if PresenceProtocol.has_error(status) {
+            return Err(RadarError::DeviceError {
+                message: format!("{failure}: {}", describe_errors(&PresenceProtocol, status)),
+            });
+        }
+        Ok(())
     }
 
     /// Write command safely with busy/error checking (section 2.3.1 compliance)
     pub async fn write_command_safe(&mut self, command: u32) -> Result<()> {
         // Check if detector is busy before writing command
         if self.is_busy()? {
-            self.wait_for_not_busy()?;
+            self.wait_for_not_busy().await?;
         }
 
         // Check for errors - if present, only RESET MODULE command is allowed
         if self.has_errors()? && command != CMD_RESET_MODULE {
             warn!("Presence detector has errors, resetting module before command");
-            self.reset_module()?;
+            self.reset_module().await?;
         }
 
         // Write the command
@@ -453,15 +574,9 @@ impl<'a> PresenceDetector<'a> {
             .await?;
 
         // Wait for configuration to be applied and check status
-        self.wait_for_not_busy()?;
+        self.wait_for_not_busy().await?;
 
-        // Check for configuration errors
-        if self.has_errors()? {
-            return Err(RadarError::DeviceError {
-                message: "Presence detector configuration failed - check register settings"
-                    .to_string(),
-            });
-        }
+        self.check_errors("Presence detector configuration failed - check register settings")?;
 
         info!("✅ Presence detector configured successfully");
         Ok(())
@@ -475,14 +590,9 @@ impl<'a> PresenceDetector<'a> {
         self.write_command_safe(CMD_PRESENCE_START_DETECTOR).await?;
 
         // Wait for start command to complete
-        self.wait_for_not_busy()?;
+        self.wait_for_not_busy().await?;
 
-        // Check for start errors
-        if self.has_errors()? {
-            return Err(RadarError::DeviceError {
-                message: "Failed to start presence detector - check configuration".to_string(),
-            });
-        }
+        self.check_errors("Failed to start presence detector - check configuration")?;
 
         info!("✅ Presence detector started successfully");
         Ok(())
@@ -496,7 +606,7 @@ impl<'a> PresenceDetector<'a> {
         self.write_command_safe(CMD_PRESENCE_STOP_DETECTOR).await?;
 
         // Wait for stop command to complete
-        self.wait_for_not_busy()?;
+        self.wait_for_not_busy().await?;
 
         info!("✅ Presence detector stopped successfully");
         Ok(())
@@ -536,8 +646,14 @@ impl<'a> PresenceDetector<'a> {
             inter_score[3],
         ]);
 
-        // Extract presence detection (bit 0)
-        let presence_detected = (presence_value & 0x1) != 0;
+        // Decode presence result word (detected flag is bit 0)
+        let presence_detected = matches!(
+            PresenceProtocol.parse_result(presence_value),
+            ResultWord::Presence {
+                presence_detected: true,
+                ..
+            }
+        );
 
         // Convert distance from mm to meters
         let presence_distance = (distance_value as f32) / 1000.0;
@@ -552,6 +668,28 @@ impl<'a> PresenceDetector<'a> {
             intra_presence_score,
             inter_presence_score,
             timestamp: chrono::Utc::now(),
+            config_generation: None,
+            raw: Some(RawRegisters::from([
+                ("result".to_string(), presence_value),
+                ("presence_distance".to_string(), distance_value),
+                ("intra_score".to_string(), intra_value),
+                ("inter_score".to_string(), inter_value),
+            ])),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_profile_limits() {
+        assert_eq!(select_profile(60, 700, true).unwrap(), 1);
+        assert_eq!(select_profile(300, 5500, true).unwrap(), 4);
+        assert_eq!(select_profile(500, 7000, false).unwrap(), MANUAL_PROFILE);
+        assert!(select_profile(500, 7500, true).is_err());
+        assert!(select_profile(500, 7500, false).is_err());
+        assert!(select_profile(20, 1000, true).is_err());
+    }
+}
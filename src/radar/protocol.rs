@@ -0,0 +1,105 @@
+// Detector Protocol Module
+// Host-side glue for the register protocol definitions in the `xm125-protocol` crate
+
+#![allow(clippy::pedantic)]
+// The protocol crate is no_std and knows nothing about CLI firmware selection or detector
+// modes; this module maps between those and the per-firmware protocol implementations.
+
+pub use xm125_protocol::detector::*;
+
+use super::DetectorMode;
+use crate::firmware::FirmwareType;
+
+impl From<Application> for FirmwareType {
+    fn from(application: Application) -> Self {
+        match application {
+            Application::Distance => FirmwareType::Distance,
+            Application::Presence => FirmwareType::Presence,
+            Application::Breathing => FirmwareType::Breathing,
+        }
+    }
+}
+
+impl From<FirmwareType> for Application {
+    fn from(firmware_type: FirmwareType) -> Self {
+        match firmware_type {
+            FirmwareType::Distance => Application::Distance,
+            FirmwareType::Presence => Application::Presence,
+            FirmwareType::Breathing => Application::Breathing,
+        }
+    }
+}
+
+/// Firmware type of a protocol, as used for firmware selection and flashing
+pub trait ProtocolFirmware {
+    fn firmware_type(&self) -> FirmwareType;
+}
+
+impl<P: DetectorProtocol + ?Sized> ProtocolFirmware for P {
+    fn firmware_type(&self) -> FirmwareType {
+        self.application().into()
+    }
+}
+
+/// Protocol implemented by the given firmware
+pub fn protocol_for_firmware(firmware_type: FirmwareType) -> &'static dyn DetectorProtocol {
+    protocol_for_application(firmware_type.into())
+}
+
+/// Protocol required by the given detector mode
+pub fn protocol_for_mode(mode: DetectorMode) -> &'static dyn DetectorProtocol {
+    match mode {
+        DetectorMode::Distance | DetectorMode::Combined => &DistanceProtocol,
+        DetectorMode::Presence => &PresenceProtocol,
+        DetectorMode::Breathing => &BreathingProtocol,
+    }
+}
+
+/// Flag names joined for log lines, e.g. `CONFIG_APPLY_ERROR, DETECTOR_ERROR`
+pub fn flag_names(flags: &[StatusFlag]) -> String {
+    flags
+        .iter()
+        .map(|flag| flag.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Error flags set in `status` with their descriptions, for error messages
+pub fn describe_errors(protocol: &dyn DetectorProtocol, status: u32) -> String {
+    let errors: Vec<String> = protocol
+        .decode_status(status)
+        .iter()
+        .filter(|flag| flag.is_error())
+        .map(|flag| format!("{flag}: {}", flag.description))
+        .collect();
+    if errors.is_empty() {
+        format!("status 0x{status:08X}, no error flag set")
+    } else {
+        format!("status 0x{status:08X}, {}", errors.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_firmware_type_mapping() {
+        for firmware_type in [
+            FirmwareType::Distance,
+            FirmwareType::Presence,
+            FirmwareType::Breathing,
+        ] {
+            let protocol = protocol_for_firmware(firmware_type);
+            assert_eq!(protocol.firmware_type(), firmware_type);
+            assert_eq!(
+                protocol.application().app_id(),
+                firmware_type.application_id()
+            );
+        }
+        assert_eq!(
+            protocol_for_mode(DetectorMode::Combined).firmware_type(),
+            FirmwareType::Distance
+        );
+    }
+}
@@ -0,0 +1,321 @@
+// Firmware Quirk Module
+// Per-firmware corrections to result parsing, selected by application ID and RSS version
+
+#![allow(clippy::pedantic)]
+// Some firmware revisions in the field report result registers with a different byte order,
+// fixed-point scale or temperature position than the register protocol documents. Results
+// are decoded the documented way first; a matching quirk entry then decodes them again from
+// the raw register words, so detectors stay written against the documented protocol.
+
+use super::protocol::{DetectorProtocol, DistanceProtocol, ResultWord, RssVersion};
+use super::{BreathingMeasurement, DistanceMeasurement, PresenceMeasurement, RawRegisters};
+use crate::error::{RadarError, Result};
+use log::info;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::Path;
+
+/// Name of the built-in entry decoding results as documented
+pub const STANDARD: &str = "standard";
+
+/// How result registers are decoded
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Quirks {
+    /// Result words arrive least significant byte first
+    pub swap_bytes: bool,
+    /// Metres per distance register unit
+    pub distance_scale: f32,
+    /// Presence score per score register unit
+    pub score_scale: f32,
+    /// dB per peak strength register unit
+    pub strength_scale: f32,
+    /// Breaths per minute per breathing rate register unit
+    pub breathing_rate_scale: f32,
+    /// Lowest bit of the signed 16-bit temperature in the result word
+    pub temperature_shift: u8,
+    /// Bit of the presence result word flagging detected presence
+    pub presence_bit: u8,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            swap_bytes: false,
+            distance_scale: 0.001,
+            score_scale: 0.001,
+            strength_scale: 0.001,
+            breathing_rate_scale: 0.001,
+            temperature_shift: 16,
+            presence_bit: 0,
+        }
+    }
+}
+
+impl Quirks {
+    fn word(&self, raw: &RawRegisters, name: &str) -> Option<u32> {
+        raw.get(name).map(|&word| {
+            if self.swap_bytes {
+                word.swap_bytes()
+            } else {
+                word
+            }
+        })
+    }
+
+    fn temperature(&self, result: u32) -> i16 {
+        (result >> self.temperature_shift.min(16)) as u16 as i16
+    }
+
+    /// Decode a distance measurement again from its raw register words
+    pub fn apply_distance(&self, measurement: &mut DistanceMeasurement) {
+        let Some(raw) = measurement.raw.as_ref() else {
+            return;
+        };
+        if let Some(result) = self.word(raw, "result") {
+            if let ResultWord::Distance {
+                num_distances,
+                measure_distance_error,
+                ..
+            } = DistanceProtocol.parse_result(result)
+            {
+                measurement.measure_error = measure_distance_error;
+                if num_distances == 0 {
+                    measurement.distance = 0.0;
+                } else if let Some(distance) = self.word(raw, "peak0_distance") {
+                    measurement.distance = distance as f32 * self.distance_scale;
+                }
+            }
+            measurement.temperature = self.temperature(result);
+        }
+        if let Some(strength) = self.word(raw, "peak0_strength") {
+            measurement.strength = strength as f32;
+            measurement.strength_db = strength as i32 as f32 * self.strength_scale;
+        }
+        for (n, peak) in measurement.peaks.iter_mut().flatten().enumerate() {
+            if let Some(distance) = self.word(raw, &format!("peak{n}_distance")) {
+                peak.distance = distance as f32 * self.distance_scale;
+            }
+            if let Some(strength) = self.word(raw, &format!("peak{n}_strength")) {
+                peak.strength_db = strength as i32 as f32 * self.strength_scale;
+            }
+        }
+    }
+
+    /// Decode a presence measurement again from its raw register words
+    pub fn apply_presence(&self, measurement: &mut PresenceMeasurement) {
+        let Some(raw) = measurement.raw.as_ref() else {
+            return;
+        };
+        if let Some(result) = self.word(raw, "result") {
+            measurement.presence_detected = result & (1 << self.presence_bit.min(31)) != 0;
+        }
+        if let Some(distance) = self.word(raw, "presence_distance") {
+            measurement.presence_distance = distance as f32 * self.distance_scale;
+        }
+        if let Some(score) = self.word(raw, "intra_score") {
+            measurement.intra_presence_score = score as f32 * self.score_scale;
+        }
+        if let Some(score) = self.word(raw, "inter_score") {
+            measurement.inter_presence_score = score as f32 * self.score_scale;
+        }
+    }
+
+    /// Decode a breathing measurement again from its raw register words
+    pub fn apply_breathing(&self, measurement: &mut BreathingMeasurement) {
+        let Some(raw) = measurement.raw.as_ref() else {
+            return;
+        };
+        if let Some(result) = self.word(raw, "result") {
+            measurement.temperature = self.temperature(result);
+        }
+        if let (Some(_), Some(rate)) =
+            (measurement.breathing_rate, self.word(raw, "breathing_rate"))
+        {
+            measurement.breathing_rate = Some(rate as f32 * self.breathing_rate_scale);
+        }
+    }
+}
+
+/// One row of the quirk table
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuirkEntry {
+    /// Name used in logs and by `--force-quirk`
+    pub name: String,
+    /// Application ID the entry applies to (any when unset)
+    pub app_id: Option<u32>,
+    /// Oldest RSS version the entry applies to, inclusive
+    #[serde(default, deserialize_with = "deserialize_version")]
+    pub rss_min: Option<RssVersion>,
+    /// Newest RSS version the entry applies to, inclusive
+    #[serde(default, deserialize_with = "deserialize_version")]
+    pub rss_max: Option<RssVersion>,
+    #[serde(flatten)]
+    pub quirks: Quirks,
+}
+
+impl QuirkEntry {
+    fn matches(&self, app_id: Option<u32>, rss: Option<RssVersion>) -> bool {
+        let app_matches = self.app_id.is_none() || self.app_id == app_id;
+        let version_bounded = self.rss_min.is_some() || self.rss_max.is_some();
+        let version_matches = match rss {
+            Some(rss) => {
+                self.rss_min.is_none_or(|min| rss >= min)
+                    && self.rss_max.is_none_or(|max| rss <= max)
+            }
+            None => !version_bounded,
+        };
+        app_matches && version_matches
+    }
+}
+
+/// Parse a `major.minor.patch` RSS version
+fn parse_version(s: &str) -> std::result::Result<RssVersion, String> {
+    let parts: Vec<&str> = s.trim().split('.').collect();
+    let [major, minor, patch] = parts.as_slice() else {
+        return Err(format!("Invalid RSS version '{s}' (expected e.g. 1.2.0)"));
+    };
+    let invalid = |_| format!("Invalid RSS version '{s}' (expected e.g. 1.2.0)");
+    Ok(RssVersion::new(
+        major.parse().map_err(invalid)?,
+        minor.parse().map_err(invalid)?,
+        patch.parse().map_err(invalid)?,
+    ))
+}
+
+fn deserialize_version<'de, D>(deserializer: D) -> std::result::Result<Option<RssVersion>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| parse_version(&s).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Quirk entries, searched in order; the built-in standard entry matches last
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuirkTable {
+    #[serde(default, rename = "quirk")]
+    entries: Vec<QuirkEntry>,
+}
+
+impl QuirkTable {
+    /// Load a quirk table from a TOML file of `[[quirk]]` entries
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| {
+            RadarError::InvalidParameters(format!(
+                "Failed to read quirk table {}: {e}",
+                path.display()
+            ))
+        })?;
+        let table: Self = toml::from_str(&contents).map_err(|e| {
+            RadarError::InvalidParameters(format!(
+                "Failed to parse quirk table {}: {e}",
+                path.display()
+            ))
+        })?;
+        if let Some(entry) = table.entries.iter().find(|entry| entry.name == STANDARD) {
+            return Err(RadarError::InvalidParameters(format!(
+                "Quirk table {}: '{}' is reserved for the built-in entry",
+                path.display(),
+                entry.name
+            )));
+        }
+        Ok(table)
+    }
+
+    /// Entry called `name`, including the built-in standard entry
+    pub fn by_name(&self, name: &str) -> Option<QuirkEntry> {
+        if name == STANDARD {
+            return Some(standard_entry());
+        }
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .cloned()
+    }
+
+    /// Names of all entries, for error messages
+    pub fn names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .chain([STANDARD])
+            .collect()
+    }
+
+    /// First entry matching the firmware, or the standard entry
+    pub fn select(&self, app_id: Option<u32>, rss: Option<RssVersion>) -> QuirkEntry {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.matches(app_id, rss))
+            .cloned()
+            .unwrap_or_else(standard_entry);
+        if entry.name != STANDARD {
+            info!("🩹 Applying firmware quirk '{}'", entry.name);
+        }
+        entry
+    }
+}
+
+fn standard_entry() -> QuirkEntry {
+    QuirkEntry {
+        name: STANDARD.to_string(),
+        app_id: None,
+        rss_min: None,
+        rss_max: None,
+        quirks: Quirks::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quirk_selection_and_rescaling() {
+        let table: QuirkTable = toml::from_str(
+            r#"
+            [[quirk]]
+            name = "presence-centi"
+            app_id = 2
+            rss_max = "1.1.0"
+            score_scale = 0.01
+            swap_bytes = true
+            "#,
+        )
+        .unwrap();
+
+        let old = Some(RssVersion::new(1, 0, 3));
+        let new = Some(RssVersion::new(1, 2, 0));
+        assert_eq!(table.select(Some(2), old).name, "presence-centi");
+        assert_eq!(table.select(Some(2), new).name, STANDARD);
+        assert_eq!(table.select(Some(1), old).name, STANDARD);
+        assert_eq!(table.select(Some(2), None).name, STANDARD);
+        assert!(table.by_name("missing").is_none());
+        assert!(
+            toml::from_str::<QuirkTable>("[[quirk]]\nname = \"x\"\nscore_scael = 0.01\n").is_err()
+        );
+
+        let quirks = table.by_name("presence-centi").unwrap().quirks;
+        let mut measurement = PresenceMeasurement {
+            presence_detected: false,
+            presence_distance: 0.0,
+            intra_presence_score: 0.0,
+            inter_presence_score: 0.0,
+            timestamp: chrono::Utc::now(),
+            config_generation: None,
+            raw: Some(RawRegisters::from([
+                ("result".to_string(), 1u32.swap_bytes()),
+                ("intra_score".to_string(), 250u32.swap_bytes()),
+            ])),
+        };
+        quirks.apply_presence(&mut measurement);
+        assert!(measurement.presence_detected);
+        assert_eq!(measurement.intra_presence_score, 2.5);
+    }
+}
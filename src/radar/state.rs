@@ -0,0 +1,138 @@
+// Radar Lifecycle State Module
+// Orders the connect/reset/configure/calibrate/start sequence of the module
+
+#![allow(clippy::pedantic)]
+
+use crate::error::{RadarError, Result};
+use log::{debug, info};
+use serde::Serialize;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Where the module is in its startup sequence
+///
+/// The sequence only moves forward one step at a time. Any state may drop back to an
+/// earlier one, e.g. reconfiguring a streaming detector, and a reset or lost connection
+/// may happen at any point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RadarState {
+    Disconnected,
+    /// Hardware reset issued, waiting for the module to answer
+    Reset,
+    /// Answering on I2C, firmware identified
+    Connected,
+    /// Detector configuration written
+    Configured,
+    /// Configuration applied and the sensor/detector calibrated
+    Calibrated,
+    /// Detector started and producing frames
+    Streaming,
+}
+
+impl RadarState {
+    fn is_valid_transition(self, to: RadarState) -> bool {
+        match to {
+            RadarState::Disconnected | RadarState::Reset => true,
+            RadarState::Connected => self <= RadarState::Reset || self > to,
+            _ => to <= self || to as u8 == self as u8 + 1,
+        }
+    }
+}
+
+impl fmt::Display for RadarState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RadarState::Disconnected => "Disconnected",
+            RadarState::Reset => "Reset",
+            RadarState::Connected => "Connected",
+            RadarState::Configured => "Configured",
+            RadarState::Calibrated => "Calibrated",
+            RadarState::Streaming => "Streaming",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Current lifecycle state and when it was entered
+#[derive(Debug)]
+pub struct StateMachine {
+    state: RadarState,
+    entered: Instant,
+}
+
+impl Default for StateMachine {
+    fn default() -> Self {
+        Self {
+            state: RadarState::Disconnected,
+            entered: Instant::now(),
+        }
+    }
+}
+
+impl StateMachine {
+    pub fn state(&self) -> RadarState {
+        self.state
+    }
+
+    /// Time spent in the current state
+    pub fn elapsed(&self) -> Duration {
+        self.entered.elapsed()
+    }
+
+    /// Move to `to`, refusing steps that skip part of the sequence
+    pub fn transition(&mut self, to: RadarState) -> Result<()> {
+        let from = self.state;
+        if from == to {
+            return Ok(());
+        }
+        if !from.is_valid_transition(to) {
+            return Err(RadarError::DeviceError {
+                message: format!("Invalid radar state transition {from} → {to}"),
+            });
+        }
+        if to > from {
+            info!("🔀 Radar state {from} → {to}");
+        } else {
+            debug!(
+                "Radar state {from} → {to} after {}ms",
+                self.entered.elapsed().as_millis()
+            );
+        }
+        self.state = to;
+        self.entered = Instant::now();
+        Ok(())
+    }
+
+    /// Drop back to `to` if currently past it; a no-op otherwise
+    pub fn fall_back(&mut self, to: RadarState) {
+        if self.state > to {
+            // Moving backwards is always valid
+            let _ = self.transition(to);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_startup_sequence_is_ordered() {
+        let mut machine = StateMachine::default();
+        assert!(machine.transition(RadarState::Configured).is_err());
+        machine.transition(RadarState::Reset).unwrap();
+        machine.transition(RadarState::Connected).unwrap();
+        assert!(machine.transition(RadarState::Streaming).is_err());
+        machine.transition(RadarState::Configured).unwrap();
+        machine.transition(RadarState::Calibrated).unwrap();
+        machine.transition(RadarState::Streaming).unwrap();
+
+        // Reconfiguring drops back without passing through Connected
+        machine.transition(RadarState::Configured).unwrap();
+        machine.fall_back(RadarState::Streaming);
+        assert_eq!(machine.state(), RadarState::Configured);
+        machine.transition(RadarState::Disconnected).unwrap();
+        assert!(machine.transition(RadarState::Calibrated).is_err());
+    }
+}
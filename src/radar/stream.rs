@@ -0,0 +1,155 @@
+// Measurement Streams
+// Async Stream API over the measurement cycle, so consumers don't re-implement polling
+
+#![allow(clippy::pedantic)]
+
+use super::{BreathingMeasurement, DistanceMeasurement, PresenceMeasurement, XM125Radar};
+use crate::error::Result;
+use futures::future::LocalBoxFuture;
+use futures::stream::{self, Stream};
+use std::time::Duration;
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+/// Pacing and length of a measurement stream
+#[derive(Debug, Clone, Copy)]
+pub struct StreamConfig {
+    /// Time between measurements
+    pub interval: Duration,
+    /// Number of measurements before the stream ends (None = endless)
+    pub count: Option<u32>,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(1000),
+            count: None,
+        }
+    }
+}
+
+type MeasureFn<T> = for<'r> fn(&'r mut XM125Radar) -> LocalBoxFuture<'r, Result<T>>;
+
+struct StreamState<'a, T> {
+    radar: &'a mut XM125Radar,
+    ticker: Interval,
+    remaining: Option<u32>,
+    measure: MeasureFn<T>,
+}
+
+/// Build a stream that connects on first poll and then measures once per interval tick
+///
+/// Detector configuration is handled by the measurement cycle itself, which configures
+/// (and for presence, starts) the detector whenever the radar is not already in that mode.
+/// Errors are yielded as items and the stream continues; it ends after `count` items.
+fn measurement_stream<'a, T: 'a>(
+    radar: &'a mut XM125Radar,
+    config: StreamConfig,
+    measure: MeasureFn<T>,
+) -> impl Stream<Item = Result<T>> + 'a {
+    let mut ticker = interval(config.interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let state = StreamState {
+        radar,
+        ticker,
+        remaining: config.count,
+        measure,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if state.remaining == Some(0) {
+            return None;
+        }
+        state.ticker.tick().await;
+
+        let result = if state.radar.is_connected() {
+            Ok(())
+        } else {
+            state.radar.connect()
+        };
+        let item = match result {
+            Ok(()) => (state.measure)(state.radar).await,
+            Err(e) => Err(e),
+        };
+
+        state.remaining = state.remaining.map(|n| n - 1);
+        Some((item, state))
+    })
+}
+
+impl XM125Radar {
+    /// Stream presence measurements, configuring the presence detector on first use
+    ///
+    /// ```ignore
+    /// let mut stream = pin!(radar.presence_stream(StreamConfig::default()));
+    /// while let Some(measurement) = stream.next().await { ... }
+    /// ```
+    pub fn presence_stream(
+        &mut self,
+        config: StreamConfig,
+    ) -> impl Stream<Item = Result<PresenceMeasurement>> + '_ {
+        measurement_stream(self, config, |radar| Box::pin(radar.measure_presence()))
+    }
+
+    /// Stream distance measurements, configuring and calibrating the detector on first use
+    pub fn distance_stream(
+        &mut self,
+        config: StreamConfig,
+    ) -> impl Stream<Item = Result<DistanceMeasurement>> + '_ {
+        measurement_stream(self, config, |radar| Box::pin(radar.measure_distance()))
+    }
+
+    /// Stream breathing measurements, configuring and starting the detector on first use
+    ///
+    /// Items carry no rate until the application has located the chest and settled.
+    pub fn breathing_stream(
+        &mut self,
+        config: StreamConfig,
+    ) -> impl Stream<Item = Result<BreathingMeasurement>> + '_ {
+        measurement_stream(self, config, |radar| Box::pin(radar.measure_breathing()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gpio::XM125GpioPins;
+    use crate::i2c::I2cDevice;
+    use futures::StreamExt;
+    use xm125_protocol::Application;
+
+    fn simulated(application: Application) -> XM125Radar {
+        XM125Radar::new(
+            I2cDevice::simulated(application, 0x52),
+            XM125GpioPins::default(),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_streams_end_after_count() {
+        let config = StreamConfig {
+            interval: Duration::from_millis(1),
+            count: Some(3),
+        };
+
+        let mut radar = simulated(Application::Presence);
+        let presence: Vec<_> = radar.presence_stream(config).collect().await;
+        assert_eq!(presence.len(), 3);
+        assert!(presence.iter().all(Result::is_ok));
+
+        let mut radar = simulated(Application::Distance);
+        let distance: Vec<_> = radar.distance_stream(config).collect().await;
+        assert_eq!(distance.len(), 3);
+        assert!(distance.iter().all(Result::is_ok));
+
+        let mut radar = simulated(Application::Breathing);
+        let breathing: Vec<_> = radar.breathing_stream(config).collect().await;
+        assert_eq!(breathing.len(), 3);
+        assert!(radar.is_connected());
+        // The simulated application is still locating the chest
+        for measurement in breathing {
+            assert_eq!(measurement.unwrap().breathing_rate, None);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Realtime scheduling for continuous monitoring
+//!
+//! At high frame rates on a loaded system the measurement loop can be delayed by ordinary
+//! time-sharing, which shows up as late samples and host jitter in `--clock-drift` reports.
+//! `--rt-priority` moves the thread driving the measurement loop to `SCHED_FIFO` and
+//! `--cpu-affinity` pins it to chosen CPUs. Both apply to the calling thread only, so tokio
+//! worker threads and any sink I/O stay on normal scheduling.
+
+use crate::error::RadarError;
+use clap::Args;
+use log::info;
+
+/// Realtime scheduling options for the continuous monitoring commands
+#[derive(Args, Debug, Clone, Default)]
+pub struct RealtimeArgs {
+    /// SCHED_FIFO priority for the measurement loop
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(i32).range(1..=99),
+        requires = "continuous",
+        help = "Run the measurement loop under SCHED_FIFO at priority N (1-99, needs CAP_SYS_NICE or RLIMIT_RTPRIO)"
+    )]
+    pub rt_priority: Option<i32>,
+
+    /// CPUs the measurement loop may run on
+    #[arg(
+        long,
+        value_name = "CPUS",
+        value_parser = parse_cpu_list,
+        requires = "continuous",
+        help = "Pin the measurement loop to these CPUs, e.g. 3 or 2,3 or 0-1"
+    )]
+    pub cpu_affinity: Option<CpuList>,
+}
+
+/// Sorted, de-duplicated CPU numbers
+#[derive(Debug, Clone, PartialEq)]
+pub struct CpuList(pub Vec<usize>);
+
+/// Parse a CPU list such as `3`, `2,3` or `0-1,3`
+pub fn parse_cpu_list(s: &str) -> Result<CpuList, String> {
+    let mut cpus = Vec::new();
+    for part in s.split(',').map(str::trim) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let parse = |cpu: &str| {
+            cpu.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid CPU '{cpu}' in '{s}'"))
+        };
+        let (first, last) = (parse(first)?, parse(last)?);
+        if first > last {
+            return Err(format!("invalid CPU range '{part}'"));
+        }
+        cpus.extend(first..=last);
+    }
+    if let Some(cpu) = cpus.iter().find(|&&cpu| cpu >= libc::CPU_SETSIZE as usize) {
+        return Err(format!("CPU {cpu} is out of range"));
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(CpuList(cpus))
+}
+
+/// Whether this process may use SCHED_FIFO at `priority`
+fn may_use_priority(priority: i32) -> bool {
+    // SAFETY: geteuid has no preconditions
+    if unsafe { libc::geteuid() } == 0 {
+        return true;
+    }
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit writes into the rlimit we own
+    if unsafe { libc::getrlimit(libc::RLIMIT_RTPRIO, &mut limit) } != 0 {
+        return false;
+    }
+    limit.rlim_cur == libc::RLIM_INFINITY || limit.rlim_cur >= priority as libc::rlim_t
+}
+
+fn set_fifo_priority(priority: i32) -> Result<(), RadarError> {
+    if !may_use_priority(priority) {
+        return Err(RadarError::InvalidParameters(format!(
+            "--rt-priority {priority} needs root, CAP_SYS_NICE or an RLIMIT_RTPRIO of at least {priority} \
+             (e.g. LimitRTPRIO= in the systemd unit)"
+        )));
+    }
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    // SAFETY: pid 0 selects the calling thread; `param` outlives the call
+    if unsafe { libc::sched_setscheduler(0, libc::SCHED_FIFO, &param) } != 0 {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::EPERM) {
+            return Err(RadarError::InvalidParameters(format!(
+                "--rt-priority {priority} refused by the kernel: {err} (missing CAP_SYS_NICE?)"
+            )));
+        }
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+fn set_affinity(cpus: &[usize]) -> Result<(), RadarError> {
+    // SAFETY: cpu_set_t is plain data and all-zero is the empty set
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        // SAFETY: parse_cpu_list keeps every CPU below CPU_SETSIZE
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+    }
+    // SAFETY: pid 0 selects the calling thread; `set` outlives the call
+    if unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) } != 0 {
+        let err = std::io::Error::last_os_error();
+        return Err(RadarError::InvalidParameters(format!(
+            "--cpu-affinity {cpus:?} rejected: {err}"
+        )));
+    }
+    Ok(())
+}
+
+impl RealtimeArgs {
+    /// Apply the requested scheduling to the calling thread
+    pub fn apply(&self) -> Result<(), RadarError> {
+        if let Some(CpuList(cpus)) = &self.cpu_affinity {
+            set_affinity(cpus)?;
+            info!("📌 Measurement loop pinned to CPUs {cpus:?}");
+        }
+        if let Some(priority) = self.rt_priority {
+            set_fifo_priority(priority)?;
+            info!("⏱️  Measurement loop running under SCHED_FIFO priority {priority}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("3").unwrap(), CpuList(vec![3]));
+        assert_eq!(parse_cpu_list("0-1, 3,1").unwrap(), CpuList(vec![0, 1, 3]));
+        assert!(parse_cpu_list("2-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+        assert!(parse_cpu_list("100000").is_err());
+    }
+}
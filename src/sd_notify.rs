@@ -0,0 +1,104 @@
+//! systemd service notifications (`sd_notify(3)`)
+//!
+//! Under a `Type=notify` unit systemd passes a datagram socket in `NOTIFY_SOCKET`; the
+//! daemon reports `READY=1` once the detector is configured, a human-readable `STATUS=`
+//! while recovering, and `STOPPING=1` on shutdown. With `WatchdogSec=` set, systemd also
+//! passes `WATCHDOG_USEC` and restarts the service unless `WATCHDOG=1` arrives in time;
+//! monitoring loops ping it once per measurement, rate-limited to half the timeout, so a
+//! hung I2C transfer ends in a restart. Outside systemd every call is a no-op.
+
+use log::debug;
+use std::os::unix::net::UnixDatagram;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Send `state` (newline-separated `KEY=VALUE` assignments) to the service manager
+///
+/// Returns false when not running under systemd or the message could not be sent.
+pub fn notify(state: &str) -> bool {
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return false;
+    };
+    let sent = UnixDatagram::unbound().and_then(|socket| {
+        let path = path.to_string_lossy();
+        match path.strip_prefix('@') {
+            // Abstract namespace socket
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &address)
+            }
+            None => socket.send_to(state.as_bytes(), path.as_ref()),
+        }
+    });
+    if let Err(e) = &sent {
+        debug!("sd_notify({state}) failed: {e}");
+    }
+    sent.is_ok()
+}
+
+/// Startup finished, the service is up
+pub fn ready(status: &str) {
+    notify(&format!("READY=1\nSTATUS={status}"));
+}
+
+/// Free-form status shown by `systemctl status`
+pub fn status(status: &str) {
+    notify(&format!("STATUS={status}"));
+}
+
+/// Shutdown has begun
+pub fn stopping() {
+    notify("STOPPING=1");
+}
+
+/// Watchdog timeout requested by the unit, if it applies to this process
+fn watchdog_timeout() -> Option<Duration> {
+    static TIMEOUT: OnceLock<Option<Duration>> = OnceLock::new();
+    *TIMEOUT.get_or_init(|| {
+        let for_us = std::env::var("WATCHDOG_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .is_none_or(|pid| pid == std::process::id());
+        std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse().ok())
+            .filter(|_| for_us)
+            .map(Duration::from_micros)
+    })
+}
+
+/// Tell the watchdog the service is alive, at most every half timeout
+pub fn watchdog() {
+    static LAST: Mutex<Option<Instant>> = Mutex::new(None);
+    let Some(timeout) = watchdog_timeout() else {
+        return;
+    };
+    let mut last = LAST.lock().unwrap_or_else(|e| e.into_inner());
+    if last.is_none_or(|at| at.elapsed() >= timeout / 2) && notify("WATCHDOG=1") {
+        *last = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notify_sends_datagram() {
+        let dir = std::env::temp_dir().join(format!("xm125-notify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notify.sock");
+        let listener = UnixDatagram::bind(&path).unwrap();
+
+        std::env::set_var("NOTIFY_SOCKET", &path);
+        ready("Monitoring presence");
+        std::env::remove_var("NOTIFY_SOCKET");
+
+        let mut buf = [0u8; 128];
+        let len = listener.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1\nSTATUS=Monitoring presence");
+        assert!(!notify("STOPPING=1"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
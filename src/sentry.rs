@@ -0,0 +1,159 @@
+//! Presence/distance hybrid sentry
+//!
+//! Battery-powered installations such as parking sensors spend most of their time waiting
+//! for something to arrive, which the presence detector does at a low frame rate for little
+//! power, but they need the distance detector's precise ranging while a target is there.
+//! The two are separate firmwares, so `sentry` switches between them: it watches with the
+//! presence firmware, flashes the distance firmware once presence has been seen for the
+//! approach time, ranges until no target has been found for the clear time, then flashes
+//! the presence firmware back. Every firmware switch costs a flash cycle and a few seconds
+//! without measurements, so both detectors also stay active for a minimum dwell time, which
+//! bounds the switch rate when a target hovers at the edge of the range.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Detector the sentry is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SentryMode {
+    /// Low-power approach detection
+    Presence,
+    /// Precise ranging while a target is present
+    Distance,
+}
+
+impl SentryMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            SentryMode::Presence => "presence",
+            SentryMode::Distance => "distance",
+        }
+    }
+}
+
+/// Hysteresis of the sentry, from `--approach-time`, `--clear-time` and `--min-dwell`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SentryConfig {
+    /// Continuous presence needed before switching to distance
+    pub approach: Duration,
+    /// Time without a distance target before switching back to presence
+    pub clear: Duration,
+    /// Minimum time in either mode
+    pub min_dwell: Duration,
+}
+
+/// A switch between detectors
+#[derive(Debug, Clone, Serialize)]
+pub struct ModeChange {
+    pub timestamp: DateTime<Utc>,
+    pub from: SentryMode,
+    pub to: SentryMode,
+    /// Seconds spent in the previous mode
+    pub dwell_s: f64,
+    /// Milliseconds the firmware switch and reconfiguration took
+    pub switch_ms: u64,
+}
+
+/// Decides when to switch detectors
+#[derive(Debug, Clone)]
+pub struct Sentry {
+    config: SentryConfig,
+    mode: SentryMode,
+    entered: Instant,
+    /// Start of the current run of presence detections (presence mode)
+    approaching: Option<Instant>,
+    /// Last distance target (distance mode)
+    last_target: Instant,
+}
+
+impl Sentry {
+    /// Sentry watching for an approach since `now`
+    pub fn new(config: SentryConfig, now: Instant) -> Self {
+        Self {
+            config,
+            mode: SentryMode::Presence,
+            entered: now,
+            approaching: None,
+            last_target: now,
+        }
+    }
+
+    pub fn mode(&self) -> SentryMode {
+        self.mode
+    }
+
+    /// Time spent in the current mode
+    pub fn dwell(&self, now: Instant) -> Duration {
+        now.duration_since(self.entered)
+    }
+
+    /// Feed whether the running detector sees a target at `now`; returns the mode to switch
+    /// to when the hysteresis allows it
+    pub fn update(&mut self, target: bool, now: Instant) -> Option<SentryMode> {
+        let next = match self.mode {
+            SentryMode::Presence => {
+                if !target {
+                    self.approaching = None;
+                    return None;
+                }
+                let since = *self.approaching.get_or_insert(now);
+                (now.duration_since(since) >= self.config.approach).then_some(SentryMode::Distance)
+            }
+            SentryMode::Distance => {
+                if target {
+                    self.last_target = now;
+                }
+                (now.duration_since(self.last_target) >= self.config.clear)
+                    .then_some(SentryMode::Presence)
+            }
+        };
+        next.filter(|_| self.dwell(now) >= self.config.min_dwell)
+    }
+
+    /// Record that the detector switched to `mode` at `now`
+    pub fn switched(&mut self, mode: SentryMode, now: Instant) {
+        self.mode = mode;
+        self.entered = now;
+        self.approaching = None;
+        self.last_target = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hysteresis_and_minimum_dwell() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut sentry = Sentry::new(
+            SentryConfig {
+                approach: Duration::from_secs(2),
+                clear: Duration::from_secs(10),
+                min_dwell: Duration::from_secs(30),
+            },
+            start,
+        );
+
+        // A brief detection does not wake the distance detector
+        assert_eq!(sentry.update(true, at(0)), None);
+        assert_eq!(sentry.update(false, at(1)), None);
+        assert_eq!(sentry.update(true, at(2)), None);
+        // Sustained presence does, but not before the minimum dwell
+        assert_eq!(sentry.update(true, at(4)), None);
+        assert_eq!(sentry.update(true, at(30)), Some(SentryMode::Distance));
+        sentry.switched(SentryMode::Distance, at(35));
+
+        // Targets keep the distance detector running; losing them for the clear time ends it
+        assert_eq!(sentry.update(false, at(50)), None);
+        assert_eq!(sentry.update(true, at(55)), None);
+        assert_eq!(sentry.update(false, at(64)), None);
+        assert_eq!(sentry.update(false, at(65)), Some(SentryMode::Presence));
+        sentry.switched(SentryMode::Presence, at(67));
+        assert_eq!(sentry.mode(), SentryMode::Presence);
+        assert_eq!(sentry.dwell(at(70)), Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,458 @@
+//! Shared radar service (`serve` command)
+//!
+//! One process owns the radar; any number of clients connect to a Unix socket and send
+//! newline-delimited JSON requests such as `{"kind":"presence","max_age_ms":250}`. All
+//! requests go through a single scheduler which batches queued requests of the same
+//! kind into one measurement, serializes detector mode switches (current mode first),
+//! and answers from the most recent result when it is younger than the allowed age.
+//! `{"kind":"stats"}` returns the module's cumulative device statistics instead, and
+//! `{"kind":"health"}` the service's own diagnostics (see [`crate::health`]), and
+//! `{"kind":"log_level","level":"debug"}` changes the log verbosity (see [`crate::log_level`]).
+
+use crate::error::RadarError;
+use crate::health::{HealthMonitor, ServiceHealth};
+use crate::radar::{DetectorMode, DistanceMeasurement, PresenceMeasurement, XM125Radar};
+use crate::stats::DeviceStats;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::os::unix::fs::FileTypeExt;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{mpsc, oneshot};
+
+/// Queue depth between client connections and the scheduler
+const REQUEST_QUEUE: usize = 64;
+
+/// Measurement types served
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MeasurementKind {
+    Distance,
+    Presence,
+}
+
+impl MeasurementKind {
+    fn detector_mode(self) -> DetectorMode {
+        match self {
+            MeasurementKind::Distance => DetectorMode::Distance,
+            MeasurementKind::Presence => DetectorMode::Presence,
+        }
+    }
+}
+
+/// Client request
+#[derive(Debug, Clone, Deserialize)]
+pub struct MeasurementRequest {
+    pub kind: MeasurementKind,
+    /// Oldest acceptable cached result in ms (defaults to the service `--max-age-ms`)
+    pub max_age_ms: Option<u64>,
+}
+
+/// A measurement of either kind
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Measurement {
+    Distance(DistanceMeasurement),
+    Presence(PresenceMeasurement),
+}
+
+/// Response sent to the client, one JSON object per line
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum MeasurementResponse {
+    Ok {
+        kind: MeasurementKind,
+        /// True if served from the cache rather than a fresh measurement
+        cached: bool,
+        age_ms: u64,
+        measurement: Measurement,
+    },
+    Stats {
+        stats: DeviceStats,
+    },
+    Health {
+        health: ServiceHealth,
+    },
+    LogLevel {
+        log_level: String,
+    },
+    Error {
+        error: String,
+    },
+}
+
+struct Job {
+    request: MeasurementRequest,
+    queued: Instant,
+    reply: oneshot::Sender<MeasurementResponse>,
+}
+
+/// Most recent result of one kind
+struct Cached {
+    taken: Instant,
+    measurement: Measurement,
+}
+
+/// Order in which the kinds of a batch are measured: the detector's current mode first
+/// so a batch costs at most one mode switch per additional kind
+fn plan_batch(current: DetectorMode, kinds: &[MeasurementKind]) -> Vec<MeasurementKind> {
+    let mut plan: Vec<MeasurementKind> = Vec::new();
+    for &kind in kinds {
+        if !plan.contains(&kind) {
+            plan.push(kind);
+        }
+    }
+    plan.sort_by_key(|kind| kind.detector_mode() != current);
+    plan
+}
+
+/// Owns the radar and answers queued requests
+struct Scheduler<'a> {
+    radar: &'a mut XM125Radar,
+    default_max_age: Duration,
+    distance: Option<Cached>,
+    presence: Option<Cached>,
+    health: HealthMonitor,
+}
+
+impl Scheduler<'_> {
+    fn cache(&mut self, kind: MeasurementKind) -> &mut Option<Cached> {
+        match kind {
+            MeasurementKind::Distance => &mut self.distance,
+            MeasurementKind::Presence => &mut self.presence,
+        }
+    }
+
+    async fn measure(&mut self, kind: MeasurementKind) -> Result<Measurement, RadarError> {
+        let started = Instant::now();
+        let measurement = match kind {
+            MeasurementKind::Distance => self
+                .radar
+                .measure_distance()
+                .await
+                .map(Measurement::Distance),
+            MeasurementKind::Presence => self
+                .radar
+                .measure_presence()
+                .await
+                .map(Measurement::Presence),
+        };
+        match &measurement {
+            Ok(_) => self.health.measured(started.elapsed()),
+            Err(e) => self.health.error("radar", e),
+        }
+        measurement
+    }
+
+    /// Serve one batch: at most one fresh measurement per kind
+    async fn serve_batch(&mut self, jobs: Vec<Job>) {
+        for job in &jobs {
+            self.health.waited(job.queued.elapsed());
+        }
+        let kinds: Vec<MeasurementKind> = jobs.iter().map(|job| job.request.kind).collect();
+        let mut jobs: Vec<Option<Job>> = jobs.into_iter().map(Some).collect();
+
+        for kind in plan_batch(self.radar.get_detector_mode(), &kinds) {
+            let default_max_age = self.default_max_age;
+            let max_age = |job: &Job| {
+                job.request
+                    .max_age_ms
+                    .map_or(default_max_age, Duration::from_millis)
+            };
+
+            // Answer from the cache where it is fresh enough
+            let mut stale = Vec::new();
+            for slot in jobs
+                .iter_mut()
+                .filter(|slot| slot.as_ref().is_some_and(|job| job.request.kind == kind))
+            {
+                let job = slot.take().expect("filtered on Some");
+                match self.cache(kind) {
+                    Some(cached) if cached.taken.elapsed() <= max_age(&job) => {
+                        let response = MeasurementResponse::Ok {
+                            kind,
+                            cached: true,
+                            age_ms: duration_ms(cached.taken.elapsed()),
+                            measurement: cached.measurement.clone(),
+                        };
+                        let _ = job.reply.send(response);
+                    }
+                    _ => stale.push(job),
+                }
+            }
+            if stale.is_empty() {
+                continue;
+            }
+
+            // One fresh measurement shared by every remaining request of this kind
+            debug!("Measuring {kind:?} for {} queued request(s)", stale.len());
+            let response = match self.measure(kind).await {
+                Ok(measurement) => {
+                    *self.cache(kind) = Some(Cached {
+                        taken: Instant::now(),
+                        measurement: measurement.clone(),
+                    });
+                    MeasurementResponse::Ok {
+                        kind,
+                        cached: false,
+                        age_ms: 0,
+                        measurement,
+                    }
+                }
+                Err(e) => {
+                    warn!("{kind:?} measurement failed: {e}");
+                    MeasurementResponse::Error {
+                        error: e.to_string(),
+                    }
+                }
+            };
+            for job in stale {
+                let _ = job.reply.send(response.clone());
+            }
+        }
+    }
+}
+
+/// `{"kind":"stats"}` or `{"kind":"health"}` ask for something other than a measurement
+fn is_request_for(line: &str, kind: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(line).is_ok_and(|request| request["kind"] == kind)
+}
+
+/// Set the log level from `{"kind":"log_level","level":...}`, or report it without `level`
+fn log_level_response(line: &str) -> MeasurementResponse {
+    let request: serde_json::Value = serde_json::from_str(line).unwrap_or_default();
+    if let Some(level) = request["level"].as_str() {
+        match level.parse() {
+            Ok(level) => crate::log_level::set(level),
+            Err(_) => {
+                return MeasurementResponse::Error {
+                    error: format!(
+                    "Invalid log level '{level}' (expected off, error, warn, info, debug or trace)"
+                ),
+                }
+            }
+        }
+    }
+    MeasurementResponse::LogLevel {
+        log_level: crate::log_level::current().to_string().to_lowercase(),
+    }
+}
+
+fn stats_response(radar: &mut XM125Radar) -> MeasurementResponse {
+    match radar.stats().totals() {
+        Ok(stats) => MeasurementResponse::Stats { stats },
+        Err(e) => MeasurementResponse::Error {
+            error: e.to_string(),
+        },
+    }
+}
+
+fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Read requests from one client and write back responses until it disconnects
+async fn handle_client(
+    stream: UnixStream,
+    jobs: mpsc::Sender<Job>,
+    stats: mpsc::Sender<oneshot::Sender<MeasurementResponse>>,
+    health: HealthMonitor,
+) {
+    health.client_connected();
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = if is_request_for(&line, "health") {
+            let depth = jobs.max_capacity() - jobs.capacity();
+            MeasurementResponse::Health {
+                health: health.report(depth, jobs.max_capacity()),
+            }
+        } else if is_request_for(&line, "log_level") {
+            log_level_response(&line)
+        } else if is_request_for(&line, "stats") {
+            let (reply, response) = oneshot::channel();
+            if stats.send(reply).await.is_err() {
+                break;
+            }
+            match response.await {
+                Ok(response) => response,
+                Err(_) => break,
+            }
+        } else {
+            match serde_json::from_str::<MeasurementRequest>(&line) {
+                Ok(request) => {
+                    let (reply, response) = oneshot::channel();
+                    let job = Job {
+                        request,
+                        queued: Instant::now(),
+                        reply,
+                    };
+                    if jobs.send(job).await.is_err() {
+                        break; // Service shutting down
+                    }
+                    match response.await {
+                        Ok(response) => response,
+                        Err(_) => break,
+                    }
+                }
+                Err(e) => MeasurementResponse::Error {
+                    error: format!("Invalid request: {e}"),
+                },
+            }
+        };
+
+        let mut json = serde_json::to_string(&response).unwrap_or_default();
+        json.push('\n');
+        if let Err(e) = writer.write_all(json.as_bytes()).await {
+            health.error("client", e);
+            break;
+        }
+    }
+    health.client_disconnected();
+    debug!("Client disconnected");
+}
+
+/// Remove a socket left behind by an instance that is no longer running
+///
+/// A stale socket would make bind fail. Anything that is not a socket, or a socket another
+/// instance still accepts connections on, is left alone.
+fn remove_stale_socket(socket_path: &str) -> Result<(), RadarError> {
+    let Ok(metadata) = std::fs::symlink_metadata(socket_path) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(RadarError::InvalidParameters(format!(
+            "{socket_path} exists and is not a socket (choose another --socket)"
+        )));
+    }
+    if std::os::unix::net::UnixStream::connect(socket_path).is_ok() {
+        return Err(RadarError::InvalidParameters(format!(
+            "another instance is already serving on {socket_path} (choose another --socket)"
+        )));
+    }
+    std::fs::remove_file(socket_path)?;
+    Ok(())
+}
+
+/// Serve measurements on `socket_path` until Ctrl+C or SIGTERM
+pub async fn serve(
+    radar: &mut XM125Radar,
+    socket_path: &str,
+    default_max_age: Duration,
+) -> Result<(), RadarError> {
+    remove_stale_socket(socket_path)?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let listener = UnixListener::bind(socket_path)?;
+    info!(
+        "📡 Serving measurements on {socket_path} (max cached age {}ms)",
+        default_max_age.as_millis()
+    );
+
+    let (job_tx, mut job_rx) = mpsc::channel::<Job>(REQUEST_QUEUE);
+    let (stats_tx, mut stats_rx) = mpsc::channel(REQUEST_QUEUE);
+    let health = HealthMonitor::default();
+    let client_health = health.clone();
+    let acceptor = tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    debug!("Client connected");
+                    tokio::spawn(handle_client(
+                        stream,
+                        job_tx.clone(),
+                        stats_tx.clone(),
+                        client_health.clone(),
+                    ));
+                }
+                Err(e) => {
+                    warn!("Failed to accept client: {e}");
+                    client_health.error("socket", e);
+                }
+            }
+        }
+    });
+
+    let mut scheduler = Scheduler {
+        radar,
+        default_max_age,
+        distance: None,
+        presence: None,
+        health,
+    };
+
+    loop {
+        tokio::select! {
+            job = job_rx.recv() => {
+                let Some(job) = job else { break };
+                // Batch everything that queued up while the previous batch was measured
+                let mut batch = vec![job];
+                while let Ok(job) = job_rx.try_recv() {
+                    batch.push(job);
+                }
+                scheduler.serve_batch(batch).await;
+            }
+            reply = stats_rx.recv() => {
+                let Some(reply) = reply else { break };
+                let _ = reply.send(stats_response(scheduler.radar));
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("🛑 Shutting down measurement service");
+                break;
+            }
+            _ = terminate.recv() => {
+                info!("🛑 Shutting down measurement service (terminated)");
+                break;
+            }
+        }
+    }
+
+    acceptor.abort();
+    let _ = std::fs::remove_file(socket_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_stale_socket_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.csv");
+        std::fs::write(&file, "keep").unwrap();
+        assert!(remove_stale_socket(file.to_str().unwrap()).is_err());
+        assert!(file.exists());
+
+        let socket = dir.path().join("xm125.sock");
+        let path = socket.to_str().unwrap();
+        let live = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        assert!(remove_stale_socket(path).is_err());
+        drop(live);
+        remove_stale_socket(path).unwrap();
+        assert!(!socket.exists());
+        remove_stale_socket(path).unwrap();
+    }
+
+    #[test]
+    fn test_plan_batch_current_mode_first() {
+        let kinds = [
+            MeasurementKind::Distance,
+            MeasurementKind::Presence,
+            MeasurementKind::Distance,
+        ];
+        assert_eq!(
+            plan_batch(DetectorMode::Presence, &kinds),
+            vec![MeasurementKind::Presence, MeasurementKind::Distance]
+        );
+        assert_eq!(
+            plan_batch(DetectorMode::Distance, &kinds),
+            vec![MeasurementKind::Distance, MeasurementKind::Presence]
+        );
+    }
+}
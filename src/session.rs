@@ -0,0 +1,88 @@
+//! Continuous monitoring session metadata
+//!
+//! Describes how a recording was produced (mode, timing, discarded warm-up frames) and is
+//! written alongside the CSV export as `<file>.meta.json` so that datasets remain
+//! self-describing after they leave the device.
+
+use crate::error::RadarError;
+use crate::journal::write_atomic;
+use crate::pacing::interval_ms;
+use crate::timeline::HardwareEvent;
+use crate::timing::ClockDriftReport;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Metadata for a single continuous monitoring run
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMetadata {
+    /// Detector mode ("distance" or "presence")
+    pub mode: String,
+    /// Requested number of recorded measurements (None = unlimited)
+    pub requested_count: Option<u32>,
+    /// Requested interval between measurements in milliseconds
+    pub interval_ms: f64,
+    /// Cycles that took longer than the interval and started late
+    pub late_cycles: u32,
+    /// Measurements taken and discarded after calibration before recording started
+    pub warmup_discarded: u32,
+    /// Measurements recorded/emitted
+    pub measurements: u32,
+    /// Measurements that failed `--quality-gate` checks (flagged or rejected)
+    pub quality_failed: u32,
+    /// When the session started
+    pub started_at: DateTime<Utc>,
+    /// When the session finished
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Host/module clock drift and jitter, when tracked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock: Option<ClockDriftReport>,
+    /// GPIO writes and module resets performed during the session
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hardware_events: Vec<HardwareEvent>,
+    /// Failsafe limit that ended the run early, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+    /// Fingerprint of the key signing the data files (`--sign-key`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing_key: Option<String>,
+}
+
+impl SessionMetadata {
+    pub fn new(mode: &str, requested_count: Option<u32>, interval: Duration) -> Self {
+        Self {
+            mode: mode.to_string(),
+            requested_count,
+            interval_ms: interval_ms(interval),
+            late_cycles: 0,
+            warmup_discarded: 0,
+            measurements: 0,
+            quality_failed: 0,
+            started_at: Utc::now(),
+            finished_at: None,
+            clock: None,
+            hardware_events: Vec::new(),
+            stop_reason: None,
+            signing_key: crate::sign::fingerprint(),
+        }
+    }
+
+    /// Mark the session as finished
+    pub fn finish(&mut self) {
+        self.finished_at = Some(Utc::now());
+    }
+
+    /// Path of the metadata sidecar for a CSV export
+    pub fn sidecar_path(csv_path: &str) -> String {
+        format!("{csv_path}.meta.json")
+    }
+
+    /// Write the metadata next to the CSV export
+    pub fn write_sidecar(&self, csv_path: &str) -> Result<(), RadarError> {
+        let path = Self::sidecar_path(csv_path);
+        write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        info!("📝 Session metadata saved to {path}");
+        Ok(())
+    }
+}
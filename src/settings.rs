@@ -0,0 +1,837 @@
+//! Detector settings file with live reload
+//!
+//! `--config <PATH>` names a TOML file with `[presence]` and `[distance]` sections holding the
+//! same detector settings as the command-line options; an option given on the command line
+//! takes precedence over the file. During continuous monitoring SIGHUP re-reads the file and
+//! applies what changed without ending the session. If the module rejects the new settings,
+//! the previous ones are written back and the session carries on with them. Every applied
+//! reload bumps a configuration generation carried in output records, so each record can be
+//! matched to the settings that produced it.
+//!
+//! `--config` may be given several times to layer files, e.g. a base profile, a site overlay
+//! and a device overlay. Later layers override earlier ones value by value (aliases by
+//! name), and `config explain` shows which layer each effective value came from. SIGHUP
+//! re-reads every layer.
+//!
+//! `[profiles.<name>]` tables define named setups, selected with `--config-profile`: the I2C
+//! bus and address, GPIO pins, FIFO output, `auto_flash` and `presence`/`distance` detector
+//! settings that override the top-level sections. Profile values only fill options not
+//! given on the command line. `config show` prints the effective result and `config
+//! validate` checks the layers without touching the module.
+
+use crate::cli::{ActiveLevel, Cli, Material, PresenceRange, ProfileMode, Reflector};
+use crate::config::{configure_distance_range, configure_presence_parameters};
+use crate::error::RadarError;
+use crate::fifo::FifoFormat;
+use crate::gpio::GpioSignals;
+use crate::radar::{DetectorMode, XM125Radar};
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Set by SIGHUP once [`catch_reload`] is installed
+static RELOAD: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_hangup(_signal: libc::c_int) {
+    RELOAD.store(true, Ordering::SeqCst);
+}
+
+/// Turn SIGHUP into a settings reload request instead of terminating the process
+pub fn catch_reload() {
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        libc::signal(
+            libc::SIGHUP,
+            on_hangup as extern "C" fn(libc::c_int) as libc::sighandler_t,
+        );
+    }
+}
+
+/// Presence detector settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PresenceSettings {
+    pub range: Option<PresenceRange>,
+    pub min_range: Option<f32>,
+    pub max_range: Option<f32>,
+    pub sensitivity: Option<f32>,
+    pub frame_rate: Option<f32>,
+    /// Drive the detection output pin (MISC_GPIO0) while presence is detected
+    pub gpio_output: Option<bool>,
+}
+
+impl PresenceSettings {
+    /// These settings with unset values taken from `fallback`
+    pub fn or(&self, fallback: &PresenceSettings) -> PresenceSettings {
+        PresenceSettings {
+            range: self.range.clone().or_else(|| fallback.range.clone()),
+            min_range: self.min_range.or(fallback.min_range),
+            max_range: self.max_range.or(fallback.max_range),
+            sensitivity: self.sensitivity.or(fallback.sensitivity),
+            frame_rate: self.frame_rate.or(fallback.frame_rate),
+            gpio_output: self.gpio_output.or(fallback.gpio_output),
+        }
+    }
+
+    /// Problems the detector would reject these settings for
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let (Some(min), Some(max)) = (self.min_range, self.max_range) {
+            if min >= max {
+                problems.push(format!(
+                    "min_range ({min}) must be less than max_range ({max})"
+                ));
+            }
+        }
+        if let Some(sensitivity) = self.sensitivity.filter(|s| !(0.1..=5.0).contains(s)) {
+            problems.push(format!(
+                "sensitivity must be between 0.1 and 5.0 (got {sensitivity})"
+            ));
+        }
+        if let Some(rate) = self.frame_rate.filter(|r| !(1.0..=60.0).contains(r)) {
+            problems.push(format!(
+                "frame_rate must be between 1.0 and 60.0 Hz (got {rate})"
+            ));
+        }
+        problems
+    }
+}
+
+/// Distance detector settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DistanceSettings {
+    /// Measurement range as `start:end` in metres
+    pub range: Option<String>,
+    pub max_profile: Option<u32>,
+    pub max_step_length: Option<u32>,
+    /// `generic` or `planar`
+    pub reflector: Option<Reflector>,
+    /// `solid` or `liquid`
+    pub material: Option<Material>,
+}
+
+impl DistanceSettings {
+    /// These settings with unset values taken from `fallback`
+    pub fn or(&self, fallback: &DistanceSettings) -> DistanceSettings {
+        DistanceSettings {
+            range: self.range.clone().or_else(|| fallback.range.clone()),
+            max_profile: self.max_profile.or(fallback.max_profile),
+            max_step_length: self.max_step_length.or(fallback.max_step_length),
+            reflector: self.reflector.or(fallback.reflector),
+            material: self.material.or(fallback.material),
+        }
+    }
+
+    /// Problems the detector would reject these settings for
+    fn problems(&self) -> Vec<String> {
+        let Some(range) = &self.range else {
+            return Vec::new();
+        };
+        let bounds: Option<Vec<f32>> = range.split(':').map(|s| s.parse().ok()).collect();
+        match bounds.as_deref() {
+            Some(&[start, end]) if start < end => Vec::new(),
+            _ => vec![format!(
+                "range '{range}' must be 'start:end' with start < end (e.g. '0.1:3.0')"
+            )],
+        }
+    }
+}
+
+/// GPIO pin numbers, reset polarity and control line timing of the module
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GpioSettings {
+    pub reset: Option<u32>,
+    pub mcu_int: Option<u32>,
+    pub wake: Option<u32>,
+    pub boot: Option<u32>,
+    /// Level that holds the module in reset, `low` or `high`
+    pub reset_active: Option<ActiveLevel>,
+    pub reset_pulse_ms: Option<u64>,
+    pub startup_delay_ms: Option<u64>,
+    pub wake_delay_ms: Option<u64>,
+    pub settle_delay_ms: Option<u64>,
+}
+
+impl GpioSettings {
+    /// `base` with the polarity and timing set here
+    pub fn signals(&self, base: GpioSignals) -> GpioSignals {
+        let ms = |value: Option<u64>, default| value.map_or(default, Duration::from_millis);
+        GpioSignals {
+            reset_active: self.reset_active.map_or(base.reset_active, Into::into),
+            reset_pulse: ms(self.reset_pulse_ms, base.reset_pulse),
+            startup_delay: ms(self.startup_delay_ms, base.startup_delay),
+            wake_delay: ms(self.wake_delay_ms, base.wake_delay),
+            settle_delay: ms(self.settle_delay_ms, base.settle_delay),
+        }
+    }
+}
+
+/// FIFO output settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FifoSettings {
+    /// Enable FIFO output (`--fifo-output`)
+    pub output: Option<bool>,
+    pub path: Option<String>,
+    /// `simple` or `json`
+    pub format: Option<String>,
+    /// Seconds between FIFO records (0 = every measurement)
+    pub interval: Option<f32>,
+}
+
+/// Named setup selected with `--config-profile`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub description: Option<String>,
+    pub i2c_bus: Option<u8>,
+    pub i2c_address: Option<u16>,
+    pub gpio: GpioSettings,
+    pub fifo: FifoSettings,
+    /// Flash the firmware a command needs (`--auto-flash`)
+    pub auto_flash: Option<bool>,
+    /// Overrides the top-level `[presence]` section
+    pub presence: PresenceSettings,
+    /// Overrides the top-level `[distance]` section
+    pub distance: DistanceSettings,
+}
+
+impl Profile {
+    /// This profile with unset values taken from `fallback`
+    fn or(&self, fallback: &Profile) -> Profile {
+        let (gpio, other_gpio) = (&self.gpio, &fallback.gpio);
+        let (fifo, other_fifo) = (&self.fifo, &fallback.fifo);
+        Profile {
+            description: self
+                .description
+                .clone()
+                .or_else(|| fallback.description.clone()),
+            i2c_bus: self.i2c_bus.or(fallback.i2c_bus),
+            i2c_address: self.i2c_address.or(fallback.i2c_address),
+            gpio: GpioSettings {
+                reset: gpio.reset.or(other_gpio.reset),
+                mcu_int: gpio.mcu_int.or(other_gpio.mcu_int),
+                wake: gpio.wake.or(other_gpio.wake),
+                boot: gpio.boot.or(other_gpio.boot),
+                reset_active: gpio.reset_active.or(other_gpio.reset_active),
+                reset_pulse_ms: gpio.reset_pulse_ms.or(other_gpio.reset_pulse_ms),
+                startup_delay_ms: gpio.startup_delay_ms.or(other_gpio.startup_delay_ms),
+                wake_delay_ms: gpio.wake_delay_ms.or(other_gpio.wake_delay_ms),
+                settle_delay_ms: gpio.settle_delay_ms.or(other_gpio.settle_delay_ms),
+            },
+            fifo: FifoSettings {
+                output: fifo.output.or(other_fifo.output),
+                path: fifo.path.clone().or_else(|| other_fifo.path.clone()),
+                format: fifo.format.clone().or_else(|| other_fifo.format.clone()),
+                interval: fifo.interval.or(other_fifo.interval),
+            },
+            auto_flash: self.auto_flash.or(fallback.auto_flash),
+            presence: self.presence.or(&fallback.presence),
+            distance: self.distance.or(&fallback.distance),
+        }
+    }
+
+    fn problems(&self) -> Vec<String> {
+        let mut problems: Vec<String> = self
+            .presence
+            .problems()
+            .into_iter()
+            .map(|problem| format!("presence.{problem}"))
+            .chain(
+                self.distance
+                    .problems()
+                    .into_iter()
+                    .map(|problem| format!("distance.{problem}")),
+            )
+            .chain(
+                self.gpio
+                    .signals(GpioSignals::default())
+                    .problems()
+                    .into_iter()
+                    .map(|(field, problem)| format!("gpio.{field}_ms {problem}")),
+            )
+            .collect();
+        if let Some(Err(e)) = self.fifo.format.as_deref().map(str::parse::<FifoFormat>) {
+            problems.push(format!("fifo.format: {e}"));
+        }
+        if let Some(interval) = self.fifo.interval.filter(|i| *i < 0.0) {
+            problems.push(format!(
+                "fifo.interval must not be negative (got {interval})"
+            ));
+        }
+        if self.i2c_address.is_some_and(|address| address > 0x7F) {
+            problems.push("i2c_address must be a 7-bit address (0x00-0x7F)".to_string());
+        }
+        problems
+    }
+}
+
+/// Contents of the settings file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Arguments run when no subcommand is given, e.g. `["presence", "--continuous"]`
+    pub default_command: Option<Vec<String>>,
+    /// Extra subcommand names expanding to a preset argument list
+    pub aliases: BTreeMap<String, Vec<String>>,
+    pub presence: PresenceSettings,
+    pub distance: DistanceSettings,
+    /// Named setups, see [`Profile`]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Settings {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RadarError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|e| {
+            RadarError::InvalidParameters(format!(
+                "Failed to read settings file {}: {e}",
+                path.display()
+            ))
+        })?;
+        toml::from_str(&contents).map_err(|e| {
+            RadarError::InvalidParameters(format!(
+                "Failed to parse settings file {}: {e}",
+                path.display()
+            ))
+        })
+    }
+
+    /// Settings of the `--config` layers merged in order, later layers taking precedence
+    pub fn load_layers(paths: &[String]) -> Result<Self, RadarError> {
+        paths.iter().try_fold(Self::default(), |merged, path| {
+            Ok(Self::load(path)?.or(&merged))
+        })
+    }
+
+    /// Settings of the `--config` layers with the `profile` detector sections applied
+    pub fn load_effective(paths: &[String], profile: Option<&str>) -> Result<Self, RadarError> {
+        let settings = Self::load_layers(paths)?;
+        let Some(name) = profile else {
+            return Ok(settings);
+        };
+        let profile = settings.profile(name)?;
+        Ok(Settings {
+            presence: profile.presence.or(&settings.presence),
+            distance: profile.distance.or(&settings.distance),
+            ..settings.clone()
+        })
+    }
+
+    /// Profile called `name`
+    pub fn profile(&self, name: &str) -> Result<&Profile, RadarError> {
+        self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            RadarError::InvalidParameters(format!(
+                "profile '{name}' is not defined in the --config files (known: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ))
+        })
+    }
+
+    /// Dotted keys and values of the `presence` and `distance` settings that are set
+    pub fn detector_values(&self) -> BTreeMap<String, serde_json::Value> {
+        set_values(&Settings {
+            presence: self.presence.clone(),
+            distance: self.distance.clone(),
+            ..Settings::default()
+        })
+    }
+
+    /// Every value the detector or this tool would reject, as `<section>.<problem>`
+    pub fn problems(&self) -> Vec<String> {
+        let top_level = Profile {
+            presence: self.presence.clone(),
+            distance: self.distance.clone(),
+            ..Profile::default()
+        };
+        top_level
+            .problems()
+            .into_iter()
+            .chain(self.profiles.iter().flat_map(|(name, profile)| {
+                profile
+                    .problems()
+                    .into_iter()
+                    .map(move |problem| format!("profiles.{name}.{problem}"))
+            }))
+            .collect()
+    }
+
+    /// These settings with unset values taken from `fallback`
+    pub fn or(&self, fallback: &Settings) -> Settings {
+        Settings {
+            default_command: self
+                .default_command
+                .clone()
+                .or_else(|| fallback.default_command.clone()),
+            aliases: fallback
+                .aliases
+                .iter()
+                .chain(&self.aliases)
+                .map(|(name, preset)| (name.clone(), preset.clone()))
+                .collect(),
+            presence: self.presence.or(&fallback.presence),
+            distance: self.distance.or(&fallback.distance),
+            profiles: fallback.profiles.iter().chain(&self.profiles).fold(
+                BTreeMap::new(),
+                |mut profiles, (name, profile)| {
+                    let merged = profiles
+                        .get(name)
+                        .map_or_else(|| profile.clone(), |earlier| profile.or(earlier));
+                    profiles.insert(name.clone(), merged);
+                    profiles
+                },
+            ),
+        }
+    }
+}
+
+/// Fill the options not given on the command line from the `--config-profile` profile
+pub fn apply_profile(cli: &mut Cli, matches: &ArgMatches) -> Result<(), RadarError> {
+    let Some(name) = cli.config_profile.clone() else {
+        return Ok(());
+    };
+    let settings = Settings::load_layers(&cli.config)?;
+    let profile = settings.profile(&name)?;
+    let unset = |id: &str| {
+        !matches!(
+            matches.value_source(id),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        )
+    };
+
+    if let Some(bus) = profile.i2c_bus.filter(|_| unset("i2c_bus")) {
+        cli.i2c_bus = bus;
+    }
+    if let Some(address) = profile.i2c_address.filter(|_| unset("i2c_address")) {
+        cli.i2c_address = address;
+    }
+    let gpio = &profile.gpio;
+    for (pin, id, field) in [
+        (gpio.reset, "gpio_reset", &mut cli.gpio_reset),
+        (gpio.mcu_int, "gpio_mcu_int", &mut cli.gpio_mcu_int),
+        (gpio.wake, "gpio_wake", &mut cli.gpio_wake),
+        (gpio.boot, "gpio_boot", &mut cli.gpio_boot),
+    ] {
+        if let Some(pin) = pin.filter(|_| unset(id)) {
+            *field = pin;
+        }
+    }
+    if let Some(level) = gpio.reset_active.filter(|_| unset("gpio_reset_active")) {
+        cli.gpio_reset_active = level;
+    }
+    for (ms, id, field) in [
+        (
+            gpio.reset_pulse_ms,
+            "gpio_reset_pulse",
+            &mut cli.gpio_reset_pulse,
+        ),
+        (
+            gpio.startup_delay_ms,
+            "gpio_startup_delay",
+            &mut cli.gpio_startup_delay,
+        ),
+        (
+            gpio.wake_delay_ms,
+            "gpio_wake_delay",
+            &mut cli.gpio_wake_delay,
+        ),
+        (
+            gpio.settle_delay_ms,
+            "gpio_settle_delay",
+            &mut cli.gpio_settle_delay,
+        ),
+    ] {
+        if let Some(ms) = ms.filter(|_| unset(id)) {
+            *field = Duration::from_millis(ms);
+        }
+    }
+    let fifo = &profile.fifo;
+    if let Some(output) = fifo.output.filter(|_| unset("fifo_output")) {
+        cli.output.fifo_output = output;
+    }
+    if let Some(path) = fifo.path.clone().filter(|_| unset("fifo_path")) {
+        cli.output.fifo_path = path;
+    }
+    if let Some(format) = fifo.format.as_deref().filter(|_| unset("fifo_format")) {
+        cli.output.fifo_format = format.parse().map_err(|e| {
+            RadarError::InvalidParameters(format!("profile '{name}': fifo.format: {e}"))
+        })?;
+    }
+    if let Some(interval) = fifo.interval.filter(|_| unset("fifo_interval")) {
+        cli.output.fifo_interval = interval;
+    }
+    if let Some(auto_flash) = profile.auto_flash.filter(|_| unset("auto_flash")) {
+        cli.auto_flash = auto_flash;
+    }
+    Ok(())
+}
+
+/// Dotted keys of every value set in `settings`, e.g. `presence.sensitivity`
+fn set_values(settings: &Settings) -> BTreeMap<String, serde_json::Value> {
+    fn flatten(
+        prefix: &str,
+        value: serde_json::Value,
+        values: &mut BTreeMap<String, serde_json::Value>,
+    ) {
+        match value {
+            serde_json::Value::Null => {}
+            serde_json::Value::Object(fields) => {
+                for (key, value) in fields {
+                    let key = if prefix.is_empty() {
+                        key
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    flatten(&key, value, values);
+                }
+            }
+            value => {
+                values.insert(prefix.to_string(), value);
+            }
+        }
+    }
+    let mut values = BTreeMap::new();
+    if let Ok(value) = serde_json::to_value(settings) {
+        flatten("", value, &mut values);
+    }
+    values
+}
+
+/// An effective setting and the layer it came from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExplainedValue {
+    pub key: String,
+    pub value: serde_json::Value,
+    /// Layer providing the value
+    pub source: String,
+    /// Earlier layers whose value for this key was overridden
+    pub overrides: Vec<String>,
+}
+
+/// Every value set by the `--config` layers, with the layer it is taken from
+pub fn explain(paths: &[String]) -> Result<Vec<ExplainedValue>, RadarError> {
+    let mut explained: BTreeMap<String, ExplainedValue> = BTreeMap::new();
+    for path in paths {
+        for (key, value) in set_values(&Settings::load(path)?) {
+            let entry = explained
+                .entry(key.clone())
+                .or_insert_with(|| ExplainedValue {
+                    key,
+                    value: serde_json::Value::Null,
+                    source: String::new(),
+                    overrides: Vec::new(),
+                });
+            if !entry.source.is_empty() {
+                entry.overrides.push(std::mem::take(&mut entry.source));
+            }
+            entry.value = value;
+            entry.source.clone_from(path);
+        }
+    }
+    Ok(explained.into_values().collect())
+}
+
+/// Names of the top-level fields that differ between `old` and `new`
+fn changed_fields<T: Serialize>(old: &T, new: &T) -> Vec<String> {
+    let (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) =
+        (serde_json::to_value(old), serde_json::to_value(new))
+    else {
+        return Vec::new();
+    };
+    new.iter()
+        .filter(|(key, value)| old.get(*key) != Some(value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+/// Write the distance settings to the radar configuration and recalibrate the detector
+pub async fn apply_distance(
+    radar: &mut XM125Radar,
+    settings: &DistanceSettings,
+) -> Result<(), RadarError> {
+    if let Some(range) = &settings.range {
+        configure_distance_range(radar, range)?;
+    }
+    if let Some(max_profile) = settings.max_profile {
+        radar.config.max_profile = max_profile;
+    }
+    if let Some(max_step_length) = settings.max_step_length {
+        radar.config.max_step_length = max_step_length;
+    }
+    if let Some(reflector) = settings.reflector {
+        radar.config.reflector_shape = reflector.into();
+    }
+    if let Some(material) = settings.material {
+        radar.config.target_material = material.into();
+    }
+    radar.configure_distance_detector().await
+}
+
+/// Write the presence settings to the radar configuration and reconfigure the detector
+pub async fn apply_presence(
+    radar: &mut XM125Radar,
+    settings: &PresenceSettings,
+) -> Result<(), RadarError> {
+    if let Some(gpio_output) = settings.gpio_output {
+        radar.config.detection_on_gpio = gpio_output;
+    }
+    let profile = if radar.config.auto_profile_enabled {
+        ProfileMode::Auto
+    } else {
+        ProfileMode::Manual
+    };
+    configure_presence_parameters(
+        radar,
+        settings.range.as_ref(),
+        settings.min_range,
+        settings.max_range,
+        settings.sensitivity,
+        settings.frame_rate,
+        &profile,
+    )
+    .await
+}
+
+/// Re-reads the settings file on SIGHUP and applies it to a running session
+#[derive(Debug)]
+pub struct Reloader {
+    paths: Vec<String>,
+    profile: Option<String>,
+    /// Values given on the command line, which the file cannot override
+    overrides: Settings,
+    /// Settings in effect
+    current: Settings,
+    generation: u32,
+}
+
+impl Reloader {
+    /// Reload the `--config` layers on SIGHUP, starting from the settings in effect now
+    pub fn new(cli: &Cli, overrides: Settings, current: Settings) -> Self {
+        catch_reload();
+        Self {
+            paths: cli.config.clone(),
+            profile: cli.config_profile.clone(),
+            overrides,
+            current,
+            generation: 0,
+        }
+    }
+
+    /// Reload and apply the settings file if SIGHUP arrived since the last call
+    ///
+    /// Failures are logged and leave the previous settings in effect; the session goes on.
+    pub async fn reload_if_requested(&mut self, radar: &mut XM125Radar) {
+        if !RELOAD.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        info!(
+            "🔄 SIGHUP: reloading settings from {}",
+            self.paths.join(", ")
+        );
+        if let Err(e) = self.reload(radar).await {
+            warn!(
+                "⚠️  Settings reload failed, keeping generation {}: {e}",
+                self.generation
+            );
+        }
+    }
+
+    async fn reload(&mut self, radar: &mut XM125Radar) -> Result<(), RadarError> {
+        let settings = self.overrides.or(&Settings::load_effective(
+            &self.paths,
+            self.profile.as_deref(),
+        )?);
+        let mode = radar.get_detector_mode();
+        let changed = match mode {
+            DetectorMode::Presence => changed_fields(&self.current.presence, &settings.presence),
+            DetectorMode::Distance => changed_fields(&self.current.distance, &settings.distance),
+            other => {
+                return Err(RadarError::InvalidParameters(format!(
+                    "settings reload is not supported in {other:?} mode"
+                )))
+            }
+        };
+        if changed.is_empty() {
+            info!("Settings unchanged, nothing to apply");
+            return Ok(());
+        }
+        info!("Applying changed settings: {}", changed.join(", "));
+
+        let previous = radar.config.clone();
+        let applied = if mode == DetectorMode::Presence {
+            apply_presence(radar, &settings.presence).await
+        } else {
+            apply_distance(radar, &settings.distance).await
+        };
+        if let Err(e) = applied {
+            // Put the module back on the configuration it was running before the reload
+            radar.config = previous;
+            let restored = if mode == DetectorMode::Presence {
+                radar.configure_presence_range().await
+            } else {
+                radar.configure_distance_detector().await
+            };
+            if let Err(restore_error) = restored {
+                warn!("⚠️  Failed to restore the previous configuration: {restore_error}");
+            }
+            return Err(e);
+        }
+
+        self.current = settings;
+        self.generation += 1;
+        radar.set_config_generation(Some(self.generation));
+        info!("✅ Settings generation {} applied", self.generation);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line_overrides_file_and_changes_are_detected() {
+        let file: Settings = toml::from_str(
+            r#"
+            [presence]
+            range = "medium"
+            sensitivity = 1.5
+
+            [distance]
+            range = "0.2:4.0"
+            "#,
+        )
+        .unwrap();
+        let command_line = Settings {
+            presence: PresenceSettings {
+                sensitivity: Some(2.0),
+                ..PresenceSettings::default()
+            },
+            ..Settings::default()
+        };
+
+        let merged = command_line.or(&file);
+        assert_eq!(merged.presence.sensitivity, Some(2.0));
+        assert!(matches!(merged.presence.range, Some(PresenceRange::Medium)));
+        assert_eq!(merged.distance.range.as_deref(), Some("0.2:4.0"));
+
+        let mut edited = merged.clone();
+        edited.presence.frame_rate = Some(20.0);
+        assert_eq!(
+            changed_fields(&merged.presence, &edited.presence),
+            vec!["frame_rate".to_string()]
+        );
+        assert!(changed_fields(&merged.distance, &edited.distance).is_empty());
+
+        assert!(toml::from_str::<Settings>("[presence]\nsensitivty = 1.0").is_err());
+    }
+
+    #[test]
+    fn test_layers_merge_in_order_and_explain_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let layer = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let base = layer(
+            "base.toml",
+            "[aliases]\nroom = [\"presence\"]\n[presence]\nrange = \"long\"\nsensitivity = 1.0\n",
+        );
+        let site = layer("site.toml", "[presence]\nsensitivity = 1.5\n");
+        let device = layer(
+            "device.toml",
+            "[aliases]\ndesk = [\"distance\"]\n[presence]\nsensitivity = 2.0\n",
+        );
+        let paths = vec![base.clone(), site.clone(), device.clone()];
+
+        let merged = Settings::load_layers(&paths).unwrap();
+        assert_eq!(merged.presence.sensitivity, Some(2.0));
+        assert!(matches!(merged.presence.range, Some(PresenceRange::Long)));
+        assert_eq!(
+            merged.aliases.keys().collect::<Vec<_>>(),
+            vec!["desk", "room"]
+        );
+
+        let explained = explain(&paths).unwrap();
+        let sensitivity = explained
+            .iter()
+            .find(|value| value.key == "presence.sensitivity")
+            .unwrap();
+        assert_eq!(sensitivity.source, device);
+        assert_eq!(sensitivity.overrides, vec![base.clone(), site]);
+        let range = explained
+            .iter()
+            .find(|value| value.key == "presence.range")
+            .unwrap();
+        assert_eq!(range.source, base);
+        assert_eq!(range.value, "long");
+    }
+
+    #[test]
+    fn test_profile_fills_unset_options_and_overrides_sections() {
+        use clap::{CommandFactory, FromArgMatches};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xm125.toml");
+        fs::write(
+            &path,
+            r#"
+            [presence]
+            range = "long"
+            sensitivity = 1.0
+
+            [profiles.lobby]
+            i2c_bus = 3
+            gpio = { reset = 10 }
+            fifo = { output = true, format = "simple" }
+            presence = { sensitivity = 2.0 }
+
+            [profiles.broken]
+            fifo = { interval = -1.0 }
+            "#,
+        )
+        .unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        let args = [
+            "xm125-radar-monitor",
+            "--config",
+            &path,
+            "--config-profile",
+            "lobby",
+            "--gpio-reset",
+            "20",
+            "status",
+        ];
+        let matches = Cli::command().get_matches_from(args);
+        let mut cli = Cli::from_arg_matches(&matches).unwrap();
+        apply_profile(&mut cli, &matches).unwrap();
+        assert_eq!(cli.i2c_bus, 3);
+        assert_eq!(cli.gpio_reset, 20);
+        assert!(cli.output.fifo_output);
+        assert!(matches!(cli.output.fifo_format, FifoFormat::Simple));
+
+        let settings = cli.settings().unwrap();
+        assert_eq!(settings.presence.sensitivity, Some(2.0));
+        assert!(matches!(settings.presence.range, Some(PresenceRange::Long)));
+        assert!(settings.profile("missing").is_err());
+        assert_eq!(
+            settings.problems(),
+            vec!["profiles.broken.fifo.interval must not be negative (got -1)".to_string()]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Shared-memory output of the latest measurement
+//!
+//! `--shm /xm125` publishes every measurement into a POSIX shared-memory segment holding a
+//! single fixed-layout [`ShmRecord`], for local consumers that poll at frame rate and cannot
+//! afford parsing FIFO text. Updates are guarded by a sequence lock: `sequence` is odd while
+//! the record is being written and even once it is consistent. A reader copies the record
+//! and retries if the sequence was odd or changed during the copy:
+//!
+//! ```text
+//! do {
+//!     s1 = atomic_load_acquire(&rec->sequence);
+//!     copy = *rec;
+//!     atomic_thread_fence(acquire);
+//! } while ((s1 & 1) || s1 != atomic_load_relaxed(&rec->sequence));
+//! ```
+
+use crate::error::RadarError;
+use crate::radar::{DistanceMeasurement, PresenceMeasurement};
+use chrono::{DateTime, Utc};
+use log::info;
+use std::ffi::CString;
+use std::ptr::{addr_of_mut, NonNull};
+use std::sync::atomic::{fence, AtomicU32, Ordering};
+
+/// Segment magic, "XM12" in little-endian byte order
+pub const SHM_MAGIC: u32 = 0x3231_4D58;
+/// Layout version, bumped on any incompatible change to [`ShmRecord`]
+pub const SHM_VERSION: u16 = 1;
+
+/// No measurement published yet
+pub const KIND_NONE: u32 = 0;
+pub const KIND_DISTANCE: u32 = 1;
+pub const KIND_PRESENCE: u32 = 2;
+
+/// Presence detected (presence records)
+pub const FLAG_PRESENCE: u32 = 1 << 0;
+/// Measure error reported by the detector (distance records)
+pub const FLAG_MEASURE_ERROR: u32 = 1 << 1;
+
+/// Record layout in the segment (48 bytes, native endianness)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ShmRecord {
+    pub magic: u32,
+    pub version: u16,
+    /// Size of this struct in bytes
+    pub size: u16,
+    /// Sequence lock, odd while an update is in progress
+    pub sequence: u32,
+    /// `KIND_*` of the latest measurement
+    pub kind: u32,
+    /// Measurement time in microseconds since the Unix epoch
+    pub timestamp_us: i64,
+    /// Distance to the target or presence distance in metres
+    pub distance_m: f32,
+    /// Signal strength (distance records)
+    pub strength: f32,
+    /// Sensor temperature in °C (distance records)
+    pub temperature_c: f32,
+    /// Fast motion score (presence records)
+    pub intra_score: f32,
+    /// Slow motion score (presence records)
+    pub inter_score: f32,
+    /// `FLAG_*` bits
+    pub flags: u32,
+}
+
+impl ShmRecord {
+    fn new(kind: u32, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            magic: SHM_MAGIC,
+            version: SHM_VERSION,
+            size: std::mem::size_of::<Self>() as u16,
+            kind,
+            timestamp_us: timestamp.timestamp_micros(),
+            ..Self::default()
+        }
+    }
+
+    fn from_distance(result: &DistanceMeasurement) -> Self {
+        Self {
+            distance_m: result.distance,
+            strength: result.strength,
+            temperature_c: f32::from(result.temperature),
+            flags: if result.measure_error {
+                FLAG_MEASURE_ERROR
+            } else {
+                0
+            },
+            ..Self::new(KIND_DISTANCE, result.timestamp)
+        }
+    }
+
+    fn from_presence(result: &PresenceMeasurement) -> Self {
+        Self {
+            distance_m: result.presence_distance,
+            intra_score: result.intra_presence_score,
+            inter_score: result.inter_presence_score,
+            flags: if result.presence_detected {
+                FLAG_PRESENCE
+            } else {
+                0
+            },
+            ..Self::new(KIND_PRESENCE, result.timestamp)
+        }
+    }
+}
+
+/// Writer side of the shared-memory segment
+pub struct ShmWriter {
+    record: NonNull<ShmRecord>,
+}
+
+impl ShmWriter {
+    /// Create (or reuse) the segment `name` and mark it as holding no measurement
+    pub fn open(name: &str) -> Result<Self, RadarError> {
+        if !name.starts_with('/') || name[1..].is_empty() || name[1..].contains('/') {
+            return Err(RadarError::InvalidParameters(format!(
+                "Shared memory name must be of the form /name, got '{name}'"
+            )));
+        }
+        let c_name =
+            CString::new(name).map_err(|e| RadarError::InvalidParameters(e.to_string()))?;
+        let size = std::mem::size_of::<ShmRecord>();
+
+        let record = unsafe {
+            let fd = libc::shm_open(c_name.as_ptr(), libc::O_CREAT | libc::O_RDWR, 0o644);
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            if libc::ftruncate(fd, size as libc::off_t) != 0 {
+                let error = std::io::Error::last_os_error();
+                libc::close(fd);
+                return Err(error.into());
+            }
+            let addr = libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            );
+            // The mapping keeps the segment alive; the descriptor is no longer needed
+            libc::close(fd);
+            if addr == libc::MAP_FAILED {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            NonNull::new_unchecked(addr.cast::<ShmRecord>())
+        };
+
+        let writer = Self { record };
+        writer.write(&ShmRecord::new(KIND_NONE, Utc::now()));
+        info!("Shared memory output enabled: /dev/shm{name} ({size} bytes)");
+        Ok(writer)
+    }
+
+    pub fn publish_distance(&self, result: &DistanceMeasurement) {
+        self.write(&ShmRecord::from_distance(result));
+    }
+
+    pub fn publish_presence(&self, result: &PresenceMeasurement) {
+        self.write(&ShmRecord::from_presence(result));
+    }
+
+    fn sequence(&self) -> &AtomicU32 {
+        // AtomicU32 has the same layout as u32 and the mapping lives as long as `self`
+        unsafe { &*addr_of_mut!((*self.record.as_ptr()).sequence).cast::<AtomicU32>() }
+    }
+
+    /// Copy `record` into the segment under the sequence lock
+    fn write(&self, record: &ShmRecord) {
+        let sequence = self.sequence();
+        // Odd even if a previous writer died mid-update
+        let odd = sequence.load(Ordering::Relaxed) | 1;
+        sequence.store(odd, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        let target = self.record.as_ptr();
+        unsafe {
+            addr_of_mut!((*target).magic).write_volatile(record.magic);
+            addr_of_mut!((*target).version).write_volatile(record.version);
+            addr_of_mut!((*target).size).write_volatile(record.size);
+            addr_of_mut!((*target).kind).write_volatile(record.kind);
+            addr_of_mut!((*target).timestamp_us).write_volatile(record.timestamp_us);
+            addr_of_mut!((*target).distance_m).write_volatile(record.distance_m);
+            addr_of_mut!((*target).strength).write_volatile(record.strength);
+            addr_of_mut!((*target).temperature_c).write_volatile(record.temperature_c);
+            addr_of_mut!((*target).intra_score).write_volatile(record.intra_score);
+            addr_of_mut!((*target).inter_score).write_volatile(record.inter_score);
+            addr_of_mut!((*target).flags).write_volatile(record.flags);
+        }
+
+        sequence.store(odd.wrapping_add(1), Ordering::Release);
+    }
+}
+
+impl Drop for ShmWriter {
+    fn drop(&mut self) {
+        // The segment itself is left in place so consumers keep the last measurement
+        unsafe {
+            libc::munmap(
+                self.record.as_ptr().cast::<libc::c_void>(),
+                std::mem::size_of::<ShmRecord>(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_presence() {
+        assert_eq!(std::mem::size_of::<ShmRecord>(), 48);
+
+        let name = format!("/xm125-test-{}", std::process::id());
+        let writer = ShmWriter::open(&name).unwrap();
+        let read = || unsafe { writer.record.as_ptr().read_volatile() };
+        assert_eq!((read().kind, read().sequence), (KIND_NONE, 2));
+
+        writer.publish_presence(&PresenceMeasurement {
+            presence_detected: true,
+            presence_distance: 1.25,
+            intra_presence_score: 2.0,
+            inter_presence_score: 0.5,
+            timestamp: Utc::now(),
+            config_generation: None,
+            raw: None,
+        });
+        let record = read();
+        assert_eq!((record.magic, record.version), (SHM_MAGIC, SHM_VERSION));
+        assert_eq!((record.kind, record.flags), (KIND_PRESENCE, FLAG_PRESENCE));
+        assert_eq!((record.distance_m, record.sequence), (1.25, 4));
+
+        drop(writer);
+        let c_name = CString::new(name).unwrap();
+        unsafe { libc::shm_unlink(c_name.as_ptr()) };
+    }
+}
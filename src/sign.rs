@@ -0,0 +1,272 @@
+//! Signing of saved measurement files (`sign` feature)
+//!
+//! Access-control deployments need occupancy logs that cannot be edited unnoticed.
+//! `--sign-key <FILE>` loads the device's Ed25519 key (created on first use, readable by
+//! its owner only) and every data file written through [`JournaledFile`] gets a detached
+//! `<file>.sig` when it is closed: the SHA-256 and length of the file as written (after
+//! encryption) signed by the key. The key fingerprint is recorded in the session metadata, and
+//! `verify-signature` checks a file against its signature and, optionally, the expected
+//! fingerprint. A file that was never closed cleanly has no signature. Without the `sign`
+//! feature no key is loaded and nothing is signed.
+//!
+//! [`JournaledFile`]: crate::journal::JournaledFile
+
+use crate::error::RadarError;
+
+/// Detached signature written next to a data file
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SignatureFile {
+    pub algorithm: String,
+    /// Hex Ed25519 public key
+    pub public_key: String,
+    /// `SHA256:` and the hex SHA-256 of the public key
+    pub fingerprint: String,
+    pub bytes: u64,
+    /// Hex SHA-256 of the file
+    pub sha256: String,
+    /// Hex Ed25519 signature over the digest and length
+    pub signature: String,
+}
+
+/// Fingerprint of the key signing this run's data files, if any
+#[cfg(not(feature = "sign"))]
+pub fn fingerprint() -> Option<String> {
+    None
+}
+
+/// Check `path` against `<path>.sig`, requiring `expected_fingerprint` when given
+#[cfg(not(feature = "sign"))]
+pub fn verify_file(
+    _path: &str,
+    _expected_fingerprint: Option<&str>,
+) -> Result<SignatureFile, RadarError> {
+    Err(RadarError::InvalidParameters(
+        "verify-signature needs a build with the `sign` feature".to_string(),
+    ))
+}
+
+#[cfg(feature = "sign")]
+const ALGORITHM: &str = "ed25519";
+
+/// What the signature covers: the file's digest and length under a fixed context
+#[cfg(feature = "sign")]
+fn signed_message(sha256: &str, bytes: u64) -> String {
+    format!("xm125-radar-monitor file signature v1\nsha256={sha256}\nbytes={bytes}\n")
+}
+
+#[cfg(feature = "sign")]
+static DEVICE_KEY: std::sync::OnceLock<DeviceKey> = std::sync::OnceLock::new();
+
+/// Ed25519 key of this device
+#[cfg(feature = "sign")]
+pub struct DeviceKey(ed25519_dalek::SigningKey);
+
+#[cfg(feature = "sign")]
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(feature = "sign")]
+fn unhex<const N: usize>(s: &str) -> Option<[u8; N]> {
+    let s = s.trim();
+    if s.len() != 2 * N {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    for (byte, pair) in bytes.iter_mut().zip(s.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(feature = "sign")]
+fn fingerprint_of(public_key: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("SHA256:{:x}", Sha256::digest(public_key))
+}
+
+/// SHA-256 and length of the file at `path`
+#[cfg(feature = "sign")]
+fn digest_file(path: &std::path::Path) -> std::io::Result<(String, u64)> {
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 8192];
+    let mut bytes = 0u64;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok((format!("{:x}", hasher.finalize()), bytes));
+        }
+        hasher.update(&buffer[..read]);
+        bytes += read as u64;
+    }
+}
+
+#[cfg(feature = "sign")]
+fn signature_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut sig = path.as_os_str().to_owned();
+    sig.push(".sig");
+    sig.into()
+}
+
+#[cfg(feature = "sign")]
+impl DeviceKey {
+    /// Load the hex seed in `path`, creating a new key there if the file does not exist
+    pub fn load_or_create(path: &str) -> Result<Self, RadarError> {
+        use std::io::{ErrorKind, Read, Write};
+        use std::os::unix::fs::OpenOptionsExt;
+
+        match std::fs::read_to_string(path) {
+            Ok(contents) => unhex::<32>(&contents)
+                .map(|seed| Self(ed25519_dalek::SigningKey::from_bytes(&seed)))
+                .ok_or_else(|| {
+                    RadarError::InvalidParameters(format!(
+                        "{path} does not hold a signing key (expected 64 hex digits)"
+                    ))
+                }),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                let mut seed = [0u8; 32];
+                std::fs::File::open("/dev/urandom")?.read_exact(&mut seed)?;
+                let mut file = std::fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(0o600)
+                    .open(path)?;
+                writeln!(file, "{}", hex(&seed))?;
+                file.sync_all()?;
+                let key = Self(ed25519_dalek::SigningKey::from_bytes(&seed));
+                log::info!("🔑 Created signing key {path} ({})", key.fingerprint());
+                Ok(key)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn fingerprint(&self) -> String {
+        fingerprint_of(self.0.verifying_key().as_bytes())
+    }
+
+    /// Write `<path>.sig` for the file as it is now
+    pub fn sign_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        use ed25519_dalek::Signer;
+
+        let (sha256, bytes) = digest_file(path)?;
+        let signature = self.0.sign(signed_message(&sha256, bytes).as_bytes());
+        let record = SignatureFile {
+            algorithm: ALGORITHM.to_string(),
+            public_key: hex(self.0.verifying_key().as_bytes()),
+            fingerprint: self.fingerprint(),
+            bytes,
+            sha256,
+            signature: hex(&signature.to_bytes()),
+        };
+        let json = serde_json::to_string_pretty(&record).map_err(std::io::Error::other)?;
+        crate::journal::write_atomic(signature_path(path), json.as_bytes())
+    }
+}
+
+/// Sign data files with the key in `path` for the rest of the run
+#[cfg(feature = "sign")]
+pub fn init(path: &str) -> Result<(), RadarError> {
+    let key = DeviceKey::load_or_create(path)?;
+    log::info!("Signing data files with {}", key.fingerprint());
+    let _ = DEVICE_KEY.set(key);
+    Ok(())
+}
+
+/// Key signing data files, once [`init`] has loaded one
+#[cfg(feature = "sign")]
+pub fn device_key() -> Option<&'static DeviceKey> {
+    DEVICE_KEY.get()
+}
+
+/// Fingerprint of the key signing this run's data files, if any
+#[cfg(feature = "sign")]
+pub fn fingerprint() -> Option<String> {
+    DEVICE_KEY.get().map(DeviceKey::fingerprint)
+}
+
+/// Check `path` against `<path>.sig`, requiring `expected_fingerprint` when given
+#[cfg(feature = "sign")]
+pub fn verify_file(
+    path: &str,
+    expected_fingerprint: Option<&str>,
+) -> Result<SignatureFile, RadarError> {
+    use ed25519_dalek::{Signature, VerifyingKey};
+
+    let path = std::path::Path::new(path);
+    let sig_path = signature_path(path);
+    let invalid = |reason: String| RadarError::SignatureInvalid {
+        path: path.display().to_string(),
+        reason,
+    };
+    let record: SignatureFile = std::fs::read_to_string(&sig_path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+        .map_err(|e| invalid(format!("unreadable {}: {e}", sig_path.display())))?;
+    if record.algorithm != ALGORITHM {
+        return Err(invalid(format!(
+            "unsupported algorithm {}",
+            record.algorithm
+        )));
+    }
+    let public_key = unhex::<32>(&record.public_key)
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| invalid("malformed public key".to_string()))?;
+    let fingerprint = fingerprint_of(public_key.as_bytes());
+    if let Some(expected) = expected_fingerprint {
+        if fingerprint != expected {
+            return Err(invalid(format!(
+                "signed by {fingerprint}, expected {expected}"
+            )));
+        }
+    }
+    let signature = unhex::<64>(&record.signature)
+        .map(|bytes| Signature::from_bytes(&bytes))
+        .ok_or_else(|| invalid("malformed signature".to_string()))?;
+    let (sha256, bytes) = digest_file(path)?;
+    if (sha256.as_str(), bytes) != (record.sha256.as_str(), record.bytes) {
+        return Err(invalid(format!(
+            "contents changed since signing ({bytes} bytes now, {} signed)",
+            record.bytes
+        )));
+    }
+    public_key
+        .verify_strict(signed_message(&sha256, bytes).as_bytes(), &signature)
+        .map_err(|_| invalid("signature does not match".to_string()))?;
+    Ok(SignatureFile {
+        fingerprint,
+        ..record
+    })
+}
+
+#[cfg(all(test, feature = "sign"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_file_verifies_until_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("device.key");
+        let key = DeviceKey::load_or_create(key_path.to_str().unwrap()).unwrap();
+        let reloaded = DeviceKey::load_or_create(key_path.to_str().unwrap()).unwrap();
+        assert_eq!(key.fingerprint(), reloaded.fingerprint());
+
+        let data = dir.path().join("data.csv");
+        std::fs::write(
+            &data,
+            "timestamp,distance_m\n2024-01-01 00:00:00.000,1.25\n",
+        )
+        .unwrap();
+        key.sign_file(&data).unwrap();
+        let data = data.to_str().unwrap();
+        let verified = verify_file(data, Some(&key.fingerprint())).unwrap();
+        assert_eq!(verified.fingerprint, key.fingerprint());
+        assert!(verify_file(data, Some("SHA256:00")).is_err());
+
+        std::fs::write(data, "timestamp,distance_m\n2024-01-01 00:00:00.000,9.25\n").unwrap();
+        assert!(verify_file(data, None).is_err());
+    }
+}
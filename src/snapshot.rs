@@ -0,0 +1,247 @@
+//! One-shot diagnostic snapshot for remote support
+//!
+//! `snapshot` gathers everything support asks for into a single JSON document: tool build,
+//! status with decoded bits, firmware identity, configuration registers, GPIO levels and one
+//! measurement of the firmware's mode. A section that cannot be collected carries an
+//! `error` string instead, so a partly broken module still produces a complete report.
+
+use crate::error::RadarError;
+use crate::firmware::FirmwareType;
+use crate::gpio::{PowerState, XM125GpioController, XM125GpioPins};
+use crate::i2c_errors::{self, RecentI2cErrors};
+use crate::radar::{
+    DetectorMode, DistanceMeasurement, PresenceMeasurement, RadarState, XM125Radar,
+};
+use crate::version::VersionInfo;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A snapshot section, or why it could not be collected
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Section<T> {
+    Ok(T),
+    Failed { error: String },
+}
+
+impl<T> From<Result<T, RadarError>> for Section<T> {
+    fn from(result: Result<T, RadarError>) -> Self {
+        match result {
+            Ok(value) => Section::Ok(value),
+            Err(e) => Section::Failed {
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct I2cSnapshot {
+    pub device: String,
+    pub address: u16,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusSnapshot {
+    pub raw: u32,
+    pub summary: String,
+    /// Names of the status bits set, decoded for the running firmware
+    pub flags: Vec<&'static str>,
+    pub ready: bool,
+    pub busy: bool,
+    pub error: bool,
+    /// Startup sequence state of this connection
+    pub state: RadarState,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FirmwareSnapshot {
+    pub application_id: u32,
+    pub firmware: Option<&'static str>,
+    pub protocol: &'static str,
+    pub rss_version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegisterValue {
+    pub address: u16,
+    pub name: &'static str,
+    pub value: u32,
+}
+
+/// Control pin numbers and levels (1 = high)
+#[derive(Debug, Serialize)]
+pub struct GpioSnapshot {
+    pub reset: PinLevel,
+    pub mcu_interrupt: PinLevel,
+    pub wake_up: PinLevel,
+    pub boot: PinLevel,
+    pub power_state: PowerState,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PinLevel {
+    pub gpio: u32,
+    pub level: u8,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum MeasurementSnapshot {
+    Distance(DistanceMeasurement),
+    Presence(PresenceMeasurement),
+}
+
+/// Complete support snapshot
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    pub generated_at: DateTime<Utc>,
+    pub tool: VersionInfo,
+    pub i2c: I2cSnapshot,
+    pub status: Section<StatusSnapshot>,
+    pub firmware: Section<FirmwareSnapshot>,
+    pub config_registers: Section<Vec<RegisterValue>>,
+    pub gpio: Section<GpioSnapshot>,
+    pub measurement: Section<MeasurementSnapshot>,
+    /// I2C errors of this run, including any while collecting the snapshot
+    pub recent_i2c_errors: RecentI2cErrors,
+}
+
+fn collect_status(radar: &mut XM125Radar) -> Result<StatusSnapshot, RadarError> {
+    let summary = radar.get_status()?;
+    let raw = radar.read_status()?;
+    let protocol = radar.protocol();
+    Ok(StatusSnapshot {
+        raw,
+        summary,
+        flags: protocol
+            .decode_status(raw)
+            .iter()
+            .map(|flag| flag.name)
+            .collect(),
+        ready: protocol.is_ready(raw),
+        busy: protocol.is_busy(raw),
+        error: protocol.has_error(raw),
+        state: radar.state(),
+    })
+}
+
+fn collect_firmware(radar: &mut XM125Radar) -> Result<FirmwareSnapshot, RadarError> {
+    Ok(FirmwareSnapshot {
+        application_id: radar.application_id()?,
+        firmware: radar.detected_firmware().map(FirmwareType::display_name),
+        protocol: radar.protocol().name(),
+        rss_version: radar.rss_version().map(|version| version.to_string()),
+    })
+}
+
+fn collect_config_registers(radar: &mut XM125Radar) -> Result<Vec<RegisterValue>, RadarError> {
+    Ok(radar
+        .read_config_registers()?
+        .into_iter()
+        .map(|(register, value)| RegisterValue {
+            address: register.address,
+            name: register.name,
+            value,
+        })
+        .collect())
+}
+
+fn collect_gpio(pins: XM125GpioPins) -> Result<GpioSnapshot, RadarError> {
+    // Attaching leaves driven outputs alone, so the snapshot does not disturb the module
+    let mut controller = XM125GpioController::with_pins(pins);
+    controller.attach()?;
+    let level = |gpio| -> Result<PinLevel, RadarError> {
+        Ok(PinLevel {
+            gpio,
+            level: controller.get_gpio_value(gpio)? as u8,
+        })
+    };
+
+    Ok(GpioSnapshot {
+        reset: level(pins.reset)?,
+        mcu_interrupt: level(pins.mcu_interrupt)?,
+        wake_up: level(pins.wake_up)?,
+        boot: level(pins.boot)?,
+        power_state: controller.power_state()?,
+    })
+}
+
+async fn collect_measurement(radar: &mut XM125Radar) -> Result<MeasurementSnapshot, RadarError> {
+    match radar.detected_firmware() {
+        Some(FirmwareType::Distance) => {
+            radar.set_detector_mode(DetectorMode::Distance);
+            Ok(MeasurementSnapshot::Distance(
+                radar.measure_distance().await?,
+            ))
+        }
+        Some(FirmwareType::Presence) => {
+            radar.set_detector_mode(DetectorMode::Presence);
+            Ok(MeasurementSnapshot::Presence(
+                radar.measure_presence().await?,
+            ))
+        }
+        Some(firmware) => Err(RadarError::InvalidParameters(format!(
+            "no measurement support for {} firmware",
+            firmware.display_name()
+        ))),
+        None => Err(RadarError::InvalidParameters(
+            "running firmware not identified".to_string(),
+        )),
+    }
+}
+
+impl Snapshot {
+    /// Collect a snapshot; registers are read before the measurement reconfigures anything
+    pub async fn collect(
+        radar: &mut XM125Radar,
+        i2c_device: String,
+        i2c_address: u16,
+        pins: XM125GpioPins,
+    ) -> Self {
+        let status = collect_status(radar).into();
+        let firmware = collect_firmware(radar).into();
+        let config_registers = collect_config_registers(radar).into();
+        let gpio = collect_gpio(pins).into();
+        let measurement = collect_measurement(radar).await.into();
+
+        Self {
+            generated_at: Utc::now(),
+            tool: VersionInfo::current(),
+            i2c: I2cSnapshot {
+                device: i2c_device,
+                address: i2c_address,
+            },
+            status,
+            firmware,
+            config_registers,
+            gpio,
+            measurement,
+            recent_i2c_errors: i2c_errors::recent(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_section_serializes_error() {
+        let failed: Section<RegisterValue> = Err(RadarError::NotConnected).into();
+        assert_eq!(
+            serde_json::to_value(&failed).unwrap(),
+            serde_json::json!({ "error": "XM125 not connected or not responding" })
+        );
+
+        let ok: Section<PinLevel> = Ok(PinLevel {
+            gpio: 124,
+            level: 1,
+        })
+        .into();
+        assert_eq!(
+            serde_json::to_value(&ok).unwrap(),
+            serde_json::json!({ "gpio": 124, "level": 1 })
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Bounded on-disk spool for measurements during sink outages
+//!
+//! With `--spool`, a measurement that no data sink accepted (CSV export failing, e.g. disk
+//! full, and no reader on a real-time FIFO) is appended to a JSON-lines spool instead of
+//! being lost. Once a later measurement is delivered again, the spooled records are replayed
+//! in order, marked as delayed. The spool survives restarts and stops accepting records at
+//! its size limit. It should live on a different filesystem from the CSV export.
+
+use crate::error::RadarError;
+use crate::journal::write_atomic;
+use crate::radar::{DistanceMeasurement, PresenceMeasurement};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A measurement waiting for its sinks to recover
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpooledMeasurement {
+    Distance {
+        sequence: u32,
+        measurement: DistanceMeasurement,
+    },
+    Presence {
+        sequence: u32,
+        measurement: PresenceMeasurement,
+    },
+}
+
+/// Append-only JSON-lines spool with a size limit
+#[derive(Debug)]
+pub struct Spool {
+    path: PathBuf,
+    max_bytes: u64,
+    bytes: u64,
+    records: usize,
+    /// Records refused because the spool was full
+    pub dropped: u64,
+}
+
+impl Spool {
+    /// Open the spool at `path`, keeping records left by a previous run
+    pub fn open(path: impl AsRef<Path>, max_bytes: u64) -> Result<Self, RadarError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let (bytes, records) = match fs::read_to_string(&path) {
+            Ok(contents) => (contents.len() as u64, contents.lines().count()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (0, 0),
+            Err(e) => return Err(e.into()),
+        };
+        if records > 0 {
+            info!(
+                "📦 Spool {} holds {records} undelivered measurement(s) from a previous run",
+                path.display()
+            );
+        }
+
+        Ok(Self {
+            path,
+            max_bytes,
+            bytes,
+            records,
+            dropped: 0,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records == 0
+    }
+
+    /// Spool a measurement; it is dropped (and counted) once the size limit is reached
+    pub fn push(&mut self, record: &SpooledMeasurement) -> Result<(), RadarError> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        if self.bytes + line.len() as u64 > self.max_bytes {
+            if self.dropped == 0 {
+                warn!(
+                    "⚠️  Spool {} is full ({} bytes) - dropping measurements until sinks recover",
+                    self.path.display(),
+                    self.max_bytes
+                );
+            }
+            self.dropped += 1;
+            return Ok(());
+        }
+
+        if self.records == 0 {
+            warn!(
+                "⚠️  All data sinks failing - spooling measurements to {}",
+                self.path.display()
+            );
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        self.bytes += line.len() as u64;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Hand spooled records to `deliver` in order until it reports a failure
+    ///
+    /// Delivered records are removed from the spool; returns how many were replayed.
+    pub fn replay(
+        &mut self,
+        mut deliver: impl FnMut(&SpooledMeasurement) -> Result<bool, RadarError>,
+    ) -> Result<usize, RadarError> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+
+        let contents = fs::read_to_string(&self.path)?;
+        let mut lines = contents.lines();
+        let mut replayed = 0;
+        let mut remaining = String::new();
+        for line in lines.by_ref() {
+            let record = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(e) => {
+                    warn!("Discarding unreadable spool record: {e}");
+                    continue;
+                }
+            };
+            if !deliver(&record)? {
+                remaining.push_str(line);
+                remaining.push('\n');
+                break;
+            }
+            replayed += 1;
+        }
+        for line in lines {
+            remaining.push_str(line);
+            remaining.push('\n');
+        }
+
+        write_atomic(&self.path, remaining.as_bytes())?;
+        self.bytes = remaining.len() as u64;
+        self.records = remaining.lines().count();
+        if replayed > 0 {
+            info!(
+                "📦 Replayed {replayed} spooled measurement(s), {} left",
+                self.records
+            );
+        }
+        Ok(replayed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn presence(sequence: u32) -> SpooledMeasurement {
+        SpooledMeasurement::Presence {
+            sequence,
+            measurement: PresenceMeasurement {
+                presence_detected: true,
+                presence_distance: 1.0,
+                intra_presence_score: 1.0,
+                inter_presence_score: 1.0,
+                timestamp: Utc::now(),
+                config_generation: None,
+                raw: None,
+            },
+        }
+    }
+
+    fn sequence(record: &SpooledMeasurement) -> u32 {
+        match record {
+            SpooledMeasurement::Distance { sequence, .. }
+            | SpooledMeasurement::Presence { sequence, .. } => *sequence,
+        }
+    }
+
+    #[test]
+    fn test_spool_replays_in_order_until_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spool.jsonl");
+        let mut spool = Spool::open(&path, 1024).unwrap();
+        for n in 1..=3 {
+            spool.push(&presence(n)).unwrap();
+        }
+
+        // Sinks fail again after the first record
+        let mut seen = Vec::new();
+        let replayed = spool
+            .replay(|record| {
+                seen.push(sequence(record));
+                Ok(seen.len() < 2)
+            })
+            .unwrap();
+        assert_eq!((replayed, seen), (1, vec![1, 2]));
+
+        // Remaining records survive a restart and are bounded by the size limit
+        let mut spool = Spool::open(&path, 1024).unwrap();
+        while spool.dropped == 0 {
+            spool.push(&presence(99)).unwrap();
+        }
+        let mut seen = Vec::new();
+        spool
+            .replay(|record| {
+                seen.push(sequence(record));
+                Ok(true)
+            })
+            .unwrap();
+        assert_eq!(&seen[..2], [2, 3]);
+        assert!(spool.is_empty());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+    }
+}
@@ -0,0 +1,244 @@
+//! Cumulative device statistics kept across sessions
+//!
+//! Every run adds its measurement, detection, reset, flash and error counts to a small TOML
+//! file keyed by the module's bus and address. Counts are buffered in memory and merged into
+//! the file at most once a minute and on exit, so a long continuous run does not rewrite it
+//! on every measurement. The totals feed predictive maintenance: a module whose error or
+//! reset rate climbs is due for replacement.
+
+use crate::error::RadarError;
+use crate::journal::write_atomic;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::ops::AddAssign;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long counts may sit in memory before being merged into the file
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Event counters for one module
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counters {
+    pub measurements: u64,
+    /// Measurements with presence detected or a distance target found
+    pub detections: u64,
+    pub resets: u64,
+    pub flashes: u64,
+    /// Failed measurements
+    pub errors: u64,
+}
+
+impl AddAssign for Counters {
+    fn add_assign(&mut self, other: Self) {
+        self.measurements += other.measurements;
+        self.detections += other.detections;
+        self.resets += other.resets;
+        self.flashes += other.flashes;
+        self.errors += other.errors;
+    }
+}
+
+/// Lifetime statistics of one module
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceStats {
+    #[serde(flatten)]
+    pub counters: Counters,
+    pub first_seen: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+}
+
+impl DeviceStats {
+    fn new() -> Self {
+        let now = Utc::now();
+        Self {
+            counters: Counters::default(),
+            first_seen: now,
+            last_updated: now,
+        }
+    }
+}
+
+/// Contents of the statistics file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StatsFile {
+    #[serde(default)]
+    devices: BTreeMap<String, DeviceStats>,
+}
+
+/// Key identifying a module in the statistics file
+pub fn device_key(i2c_device: &str, i2c_address: u16) -> String {
+    format!("{i2c_device}@0x{i2c_address:02X}")
+}
+
+/// Statistics of every module recorded in `path`
+pub fn load(path: impl AsRef<Path>) -> Result<BTreeMap<String, DeviceStats>, RadarError> {
+    let path = path.as_ref();
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let file: StatsFile = toml::from_str(&contents).map_err(|e| {
+        RadarError::InvalidParameters(format!(
+            "Failed to parse statistics file {}: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(file.devices)
+}
+
+/// Add `counters` to the entry for `key`, holding a lock so concurrent runs do not lose counts
+fn merge(path: &Path, key: &str, counters: Counters) -> Result<(), RadarError> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let lock = File::create(path.with_extension("lock"))?;
+    // SAFETY: flock on a file descriptor we own; released when `lock` is closed
+    if unsafe { libc::flock(lock.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let mut devices = load(path)?;
+    let stats = devices
+        .entry(key.to_string())
+        .or_insert_with(DeviceStats::new);
+    stats.counters += counters;
+    stats.last_updated = Utc::now();
+
+    let contents = toml::to_string_pretty(&StatsFile { devices })
+        .map_err(|e| RadarError::InvalidParameters(e.to_string()))?;
+    write_atomic(path, contents.as_bytes())?;
+    Ok(())
+}
+
+/// Buffers this run's counts for one module and merges them into the statistics file
+#[derive(Debug)]
+pub struct StatsRecorder {
+    path: Option<PathBuf>,
+    key: String,
+    pending: Counters,
+    last_flush: Instant,
+}
+
+impl Default for StatsRecorder {
+    fn default() -> Self {
+        Self::new(None, "", 0)
+    }
+}
+
+impl StatsRecorder {
+    /// Recorder for the module at `i2c_device`/`i2c_address`; `path: None` disables it
+    pub fn new(path: Option<&str>, i2c_device: &str, i2c_address: u16) -> Self {
+        Self {
+            path: path.map(PathBuf::from),
+            key: device_key(i2c_device, i2c_address),
+            pending: Counters::default(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Count a successful measurement
+    pub fn measurement(&mut self, detected: bool) {
+        self.pending.measurements += 1;
+        self.pending.detections += u64::from(detected);
+        self.flush_if_due();
+    }
+
+    /// Count a failed measurement
+    pub fn error(&mut self) {
+        self.pending.errors += 1;
+        self.flush_if_due();
+    }
+
+    /// Count a module reset
+    pub fn reset(&mut self) {
+        self.pending.resets += 1;
+    }
+
+    /// Count a firmware flash
+    pub fn flash(&mut self) {
+        self.pending.flashes += 1;
+    }
+
+    /// Lifetime totals including counts not yet written
+    pub fn totals(&self) -> Result<DeviceStats, RadarError> {
+        let mut stats = match &self.path {
+            Some(path) => load(path)?.remove(&self.key),
+            None => None,
+        }
+        .unwrap_or_else(DeviceStats::new);
+        stats.counters += self.pending;
+        Ok(stats)
+    }
+
+    fn flush_if_due(&mut self) {
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    /// Merge buffered counts into the statistics file
+    pub fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        let Some(path) = &self.path else {
+            return;
+        };
+        if self.pending == Counters::default() {
+            return;
+        }
+        match merge(path, &self.key, self.pending) {
+            Ok(()) => {
+                debug!("Device statistics saved to {}", path.display());
+                self.pending = Counters::default();
+            }
+            Err(e) => warn!("Failed to update device statistics {}: {e}", path.display()),
+        }
+    }
+}
+
+impl Drop for StatsRecorder {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorders_accumulate_per_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.toml");
+        let path = path.to_str().unwrap();
+
+        for _ in 0..2 {
+            let mut run = StatsRecorder::new(Some(path), "/dev/i2c-2", 0x52);
+            run.measurement(true);
+            run.measurement(false);
+            run.error();
+        }
+        let mut other = StatsRecorder::new(Some(path), "/dev/i2c-1", 0x52);
+        other.reset();
+        other.flash();
+        assert_eq!(other.totals().unwrap().counters.flashes, 1);
+        drop(other);
+
+        let devices = load(path).unwrap();
+        assert_eq!(
+            devices["/dev/i2c-2@0x52"].counters,
+            Counters {
+                measurements: 4,
+                detections: 2,
+                errors: 2,
+                ..Counters::default()
+            }
+        );
+        assert_eq!(devices["/dev/i2c-1@0x52"].counters.resets, 1);
+    }
+}
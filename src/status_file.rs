@@ -0,0 +1,168 @@
+//! Health status file for continuous monitoring
+//!
+//! With `--status-file`, a continuous run keeps a small JSON document describing its health
+//! (last measurement time, radar state, counts, last error) up to date every
+//! `--status-interval`. The file is replaced atomically, so watchdogs and UIs can poll it
+//! without connecting to the measurement stream and never see a partial write. The final
+//! write records whether the run stopped cleanly or failed.
+
+use crate::error::RadarError;
+use crate::journal::write_atomic;
+use crate::radar::RadarState;
+use crate::session::SessionMetadata;
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Whether the run is still going
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunState {
+    Running,
+    Stopped,
+    Failed,
+}
+
+/// Contents of the status file
+#[derive(Debug, Clone, Serialize)]
+pub struct RunStatus {
+    pub pid: u32,
+    pub mode: String,
+    pub state: RunState,
+    pub radar_state: RadarState,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_measurement_at: Option<DateTime<Utc>>,
+    pub measurements: u32,
+    pub quality_failed: u32,
+    pub errors: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
+/// Keeps the status file of one continuous run current; does nothing without a path
+#[derive(Debug)]
+pub struct StatusReporter {
+    path: Option<PathBuf>,
+    interval: Duration,
+    last_write: Option<Instant>,
+    status: RunStatus,
+    finished: bool,
+}
+
+impl StatusReporter {
+    /// Reporter for a `mode` run writing to `path` at most every `interval`
+    pub fn new(path: Option<&str>, interval: Duration, mode: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            path: path.map(PathBuf::from),
+            interval,
+            last_write: None,
+            status: RunStatus {
+                pid: std::process::id(),
+                mode: mode.to_string(),
+                state: RunState::Running,
+                radar_state: RadarState::Disconnected,
+                started_at: now,
+                updated_at: now,
+                last_measurement_at: None,
+                measurements: 0,
+                quality_failed: 0,
+                errors: 0,
+                last_error: None,
+                stop_reason: None,
+            },
+            finished: false,
+        }
+    }
+
+    /// Record a completed measurement, writing the file if the interval has passed
+    pub fn measured(&mut self, radar_state: RadarState, session: &SessionMetadata) {
+        self.status.radar_state = radar_state;
+        self.status.last_measurement_at = Some(Utc::now());
+        self.status.measurements = session.measurements;
+        self.status.quality_failed = session.quality_failed;
+        if self
+            .last_write
+            .is_none_or(|last| last.elapsed() >= self.interval)
+        {
+            self.write();
+        }
+    }
+
+    /// Record a failed measurement
+    pub fn error(&mut self, error: &RadarError) {
+        self.status.errors += 1;
+        self.status.last_error = Some(error.to_string());
+    }
+
+    /// Record the end of the run
+    pub fn finish(&mut self, session: &SessionMetadata) {
+        self.status.state = RunState::Stopped;
+        self.status.stop_reason.clone_from(&session.stop_reason);
+        self.finished = true;
+        self.write();
+    }
+
+    fn write(&mut self) {
+        self.last_write = Some(Instant::now());
+        let Some(path) = &self.path else {
+            return;
+        };
+        self.status.updated_at = Utc::now();
+        let result = serde_json::to_vec_pretty(&self.status)
+            .map_err(std::io::Error::from)
+            .and_then(|contents| write_atomic(path, &contents));
+        if let Err(e) = result {
+            warn!("Failed to update status file {}: {e}", path.display());
+        }
+    }
+}
+
+impl Drop for StatusReporter {
+    /// A run that ends without [`StatusReporter::finish`] returned an error
+    fn drop(&mut self) {
+        if !self.finished {
+            self.status.state = RunState::Failed;
+            self.write();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_file_tracks_run_and_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("status.json");
+        let read = || -> serde_json::Value {
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap()
+        };
+
+        let mut session = SessionMetadata::new("presence", None, Duration::from_millis(100));
+        let mut reporter =
+            StatusReporter::new(path.to_str(), Duration::from_secs(3600), "presence");
+        session.measurements = 1;
+        reporter.measured(RadarState::Streaming, &session);
+        assert_eq!(read()["state"], "running");
+        assert_eq!(read()["radar_state"], "streaming");
+
+        // Within the interval the file is left alone
+        session.measurements = 2;
+        reporter.measured(RadarState::Streaming, &session);
+        assert_eq!(read()["measurements"], 1);
+
+        reporter.error(&RadarError::NotConnected);
+        drop(reporter);
+        let status = read();
+        assert_eq!(status["state"], "failed");
+        assert_eq!(status["measurements"], 2);
+        assert_eq!(status["errors"], 1);
+    }
+}
@@ -0,0 +1,188 @@
+//! OpenTelemetry traces and metrics (`otel` feature)
+//!
+//! Measurement cycles and firmware operations are exported as spans, together with a
+//! measurement latency histogram and an error counter, via OTLP/gRPC. Without the
+//! `otel` feature, or without a configured endpoint, every call here is a no-op so the
+//! instrumentation points cost nothing on devices that don't use it.
+
+#[cfg(not(feature = "otel"))]
+use crate::error::RadarError;
+
+/// Instrumentation scope name
+#[cfg(feature = "otel")]
+const SCOPE: &str = "xm125-radar-monitor";
+
+/// An in-flight instrumented operation (measurement cycle or firmware operation)
+pub struct Operation {
+    #[cfg(feature = "otel")]
+    span: opentelemetry::global::BoxedSpan,
+    #[cfg(feature = "otel")]
+    kind: &'static str,
+    #[cfg(feature = "otel")]
+    start: std::time::Instant,
+}
+
+/// Start a measurement cycle span (e.g. `"distance"`, `"presence"`)
+pub fn start_measurement(mode: &'static str) -> Operation {
+    start_operation("measurement", mode)
+}
+
+/// Start a firmware operation span (e.g. `"update"`, `"bootloader"`)
+pub fn start_firmware(operation: &'static str) -> Operation {
+    start_operation("firmware", operation)
+}
+
+#[cfg(not(feature = "otel"))]
+fn start_operation(_category: &'static str, _kind: &'static str) -> Operation {
+    Operation {}
+}
+
+#[cfg(not(feature = "otel"))]
+impl Operation {
+    /// Finish the operation, recording its latency and any error
+    pub fn finish<T>(self, _result: &Result<T, RadarError>) {}
+}
+
+/// Flushes and shuts down the exporters when dropped
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    tracer_provider: opentelemetry_sdk::trace::SdkTracerProvider,
+    #[cfg(feature = "otel")]
+    meter_provider: opentelemetry_sdk::metrics::SdkMeterProvider,
+}
+
+/// Initialize OTLP export to `endpoint` (no-op without the `otel` feature)
+#[cfg(not(feature = "otel"))]
+pub fn init(_endpoint: Option<&str>) -> Option<TelemetryGuard> {
+    None
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use super::{Operation, TelemetryGuard, SCOPE};
+    use crate::error::RadarError;
+    use log::{info, warn};
+    use opentelemetry::metrics::{Counter, Histogram};
+    use opentelemetry::trace::{Span, Status, Tracer};
+    use opentelemetry::{global, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    struct Instruments {
+        latency: Histogram<f64>,
+        errors: Counter<u64>,
+    }
+
+    fn instruments() -> &'static Instruments {
+        static INSTRUMENTS: OnceLock<Instruments> = OnceLock::new();
+        INSTRUMENTS.get_or_init(|| {
+            let meter = global::meter(SCOPE);
+            Instruments {
+                latency: meter
+                    .f64_histogram("xm125.operation.duration")
+                    .with_unit("ms")
+                    .with_description("Duration of measurement cycles and firmware operations")
+                    .build(),
+                errors: meter
+                    .u64_counter("xm125.errors")
+                    .with_description("Failed measurement cycles and firmware operations")
+                    .build(),
+            }
+        })
+    }
+
+    pub(super) fn start_operation(category: &'static str, kind: &'static str) -> Operation {
+        let mut span = global::tracer(SCOPE).start(format!("{category}.{kind}"));
+        span.set_attribute(KeyValue::new("xm125.category", category));
+        Operation {
+            span,
+            kind,
+            start: Instant::now(),
+        }
+    }
+
+    impl Operation {
+        /// Finish the operation, recording its latency and any error
+        pub fn finish<T>(mut self, result: &Result<T, RadarError>) {
+            let attributes = [KeyValue::new("xm125.operation", self.kind)];
+            let elapsed_ms = self.start.elapsed().as_secs_f64() * 1000.0;
+            let instruments = instruments();
+            instruments.latency.record(elapsed_ms, &attributes);
+
+            if let Err(e) = result {
+                instruments.errors.add(1, &attributes);
+                self.span.set_status(Status::error(e.to_string()));
+            } else {
+                self.span.set_status(Status::Ok);
+            }
+            self.span.end();
+        }
+    }
+
+    pub(super) fn init(endpoint: &str) -> Result<TelemetryGuard, String> {
+        let resource = Resource::builder()
+            .with_service_name(SCOPE)
+            .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+            .build();
+
+        let span_exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(metric_exporter)
+            .with_resource(resource)
+            .build();
+
+        global::set_tracer_provider(tracer_provider.clone());
+        global::set_meter_provider(meter_provider.clone());
+        info!("📡 OpenTelemetry export enabled: {endpoint}");
+
+        Ok(TelemetryGuard {
+            tracer_provider,
+            meter_provider,
+        })
+    }
+
+    impl Drop for TelemetryGuard {
+        fn drop(&mut self) {
+            if let Err(e) = self.tracer_provider.shutdown() {
+                warn!("Failed to flush OpenTelemetry traces: {e}");
+            }
+            if let Err(e) = self.meter_provider.shutdown() {
+                warn!("Failed to flush OpenTelemetry metrics: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(feature = "otel")]
+use otel::start_operation;
+
+/// Initialize OTLP export to `endpoint`; export is disabled when no endpoint is given
+#[cfg(feature = "otel")]
+pub fn init(endpoint: Option<&str>) -> Option<TelemetryGuard> {
+    let endpoint = endpoint?;
+    match otel::init(endpoint) {
+        Ok(guard) => Some(guard),
+        Err(e) => {
+            log::warn!("Failed to initialize OpenTelemetry export to {endpoint}: {e}");
+            None
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Hardware event timeline
+//!
+//! Every GPIO line the tool drives and every module reset it performs is recorded with a
+//! timestamp. Continuous monitoring emits the events in its output stream as they happen
+//! and lists them in the session sidecar, so gaps or jumps in measurement data can be
+//! matched to hardware resets during post-analysis.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Events kept until the next [`drain`]; older ones are dropped first
+const MAX_PENDING: usize = 1024;
+
+static PENDING: Mutex<VecDeque<HardwareEvent>> = Mutex::new(VecDeque::new());
+
+/// Module mode selected by a reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetMode {
+    Run,
+    Bootloader,
+}
+
+/// Something the tool did to the module's control lines
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HardwareEvent {
+    /// A GPIO output was driven
+    Gpio {
+        timestamp: DateTime<Utc>,
+        gpio: u32,
+        line: String,
+        value: u8,
+    },
+    /// The module was reset into run or bootloader mode
+    Reset {
+        timestamp: DateTime<Utc>,
+        mode: ResetMode,
+    },
+}
+
+impl HardwareEvent {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            HardwareEvent::Gpio { timestamp, .. } | HardwareEvent::Reset { timestamp, .. } => {
+                *timestamp
+            }
+        }
+    }
+}
+
+fn record(event: HardwareEvent) {
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    if pending.len() == MAX_PENDING {
+        pending.pop_front();
+    }
+    pending.push_back(event);
+}
+
+/// Record a GPIO output write
+pub fn gpio(gpio: u32, line: &str, high: bool) {
+    record(HardwareEvent::Gpio {
+        timestamp: Utc::now(),
+        gpio,
+        line: line.to_string(),
+        value: u8::from(high),
+    });
+}
+
+/// Record a module reset
+pub fn reset(mode: ResetMode) {
+    record(HardwareEvent::Reset {
+        timestamp: Utc::now(),
+        mode,
+    });
+}
+
+/// Events recorded since the last call, oldest first
+pub fn drain() -> Vec<HardwareEvent> {
+    PENDING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .drain(..)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_drain_in_order() {
+        reset(ResetMode::Bootloader);
+        gpio(124, "Reset (asserted)", false);
+        let events = drain();
+        assert!(matches!(
+            events.first(),
+            Some(HardwareEvent::Reset {
+                mode: ResetMode::Bootloader,
+                ..
+            })
+        ));
+        let json = serde_json::to_value(&events[1]).unwrap();
+        assert_eq!(json["event"], "gpio");
+        assert_eq!(json["gpio"], 124);
+        assert_eq!(json["value"], 0);
+    }
+}
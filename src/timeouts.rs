@@ -0,0 +1,108 @@
+//! Per-phase timeouts for device operations
+//!
+//! Connecting, detector configuration/calibration, measurements and firmware flashing have
+//! very different expected durations. Each phase gets its own limit, set from the global
+//! `--timeout` or a per-phase flag, and timeout errors name the phase that expired.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// Interval between busy/ready polls while waiting on the module
+pub const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Operation phase a timeout applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPhase {
+    Connect,
+    Calibration,
+    Measurement,
+    FirmwareFlash,
+}
+
+impl fmt::Display for TimeoutPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TimeoutPhase::Connect => "connect",
+            TimeoutPhase::Calibration => "calibration",
+            TimeoutPhase::Measurement => "measurement",
+            TimeoutPhase::FirmwareFlash => "firmware flash",
+        })
+    }
+}
+
+/// Timeout for each operation phase
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Timeouts {
+    /// Module ready and answering on I2C after a reset
+    pub connect: Duration,
+    /// Apply configuration and calibrate (detector busy)
+    pub calibration: Duration,
+    /// Single measurement command (detector busy)
+    pub measurement: Duration,
+    /// One firmware write, readback or erase
+    pub firmware_flash: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        // Calibration covers the slowest presence configuration sequence (reset, apply,
+        // start); distance calibration alone completes within 2s per Acconeer documentation
+        Self {
+            connect: Duration::from_secs(5),
+            calibration: Duration::from_secs(5),
+            measurement: Duration::from_secs(5),
+            firmware_flash: Duration::from_secs(120),
+        }
+    }
+}
+
+impl Timeouts {
+    /// Build from the global timeout and per-phase overrides (all in seconds)
+    ///
+    /// The global timeout covers the device response phases (connect, calibration,
+    /// measurement); flashing takes minutes and is only set by its own override.
+    pub fn from_overrides(
+        global: Option<u64>,
+        connect: Option<u64>,
+        calibration: Option<u64>,
+        measurement: Option<u64>,
+        firmware_flash: Option<u64>,
+    ) -> Self {
+        let defaults = Self::default();
+        let pick = |phase: Option<u64>, fallback: Option<u64>, default: Duration| {
+            phase.or(fallback).map_or(default, Duration::from_secs)
+        };
+        Self {
+            connect: pick(connect, global, defaults.connect),
+            calibration: pick(calibration, global, defaults.calibration),
+            measurement: pick(measurement, global, defaults.measurement),
+            firmware_flash: pick(firmware_flash, None, defaults.firmware_flash),
+        }
+    }
+
+    /// Timeout for `phase`
+    pub fn get(&self, phase: TimeoutPhase) -> Duration {
+        match phase {
+            TimeoutPhase::Connect => self.connect,
+            TimeoutPhase::Calibration => self.calibration,
+            TimeoutPhase::Measurement => self.measurement,
+            TimeoutPhase::FirmwareFlash => self.firmware_flash,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_overrides_global() {
+        let timeouts = Timeouts::from_overrides(Some(8), None, Some(3), None, None);
+        assert_eq!(timeouts.connect, Duration::from_secs(8));
+        assert_eq!(timeouts.calibration, Duration::from_secs(3));
+        assert_eq!(timeouts.measurement, Duration::from_secs(8));
+        // Flashing is not shortened by the device response timeout
+        assert_eq!(timeouts.firmware_flash, Timeouts::default().firmware_flash);
+    }
+}
@@ -0,0 +1,161 @@
+//! Host/module clock drift and sampling jitter estimation
+//!
+//! The XM125 increments its measure counter register once per radar frame. Correlating
+//! that counter with host monotonic timestamps gives the module's actual frame rate as
+//! seen by the host (and its drift from the configured rate), while the spread of host
+//! loop intervals shows when CPU load or scheduling is degrading sampling regularity.
+
+use log::warn;
+use serde::Serialize;
+use std::time::Instant;
+
+/// A host loop interval this much longer than the running mean is counted as late
+const LATE_FACTOR: f64 = 1.5;
+/// Samples required before late intervals are flagged
+const MIN_SAMPLES_FOR_FLAGGING: u32 = 5;
+/// Jitter (standard deviation) above this fraction of the mean interval is degraded
+const DEGRADED_JITTER_RATIO: f64 = 0.10;
+/// Late samples above this fraction of all samples is degraded
+const DEGRADED_LATE_RATIO: f64 = 0.05;
+
+/// Clock drift and jitter summary for a monitoring session
+#[derive(Debug, Clone, Serialize)]
+pub struct ClockDriftReport {
+    /// Number of host samples correlated with the device counter
+    pub samples: u32,
+    /// Mean host interval between measurements in milliseconds
+    pub host_interval_mean_ms: f64,
+    /// Standard deviation of the host interval in milliseconds
+    pub host_jitter_ms: f64,
+    /// Longest host interval seen in milliseconds
+    pub host_interval_max_ms: f64,
+    /// Host intervals flagged as late (scheduling delays)
+    pub late_samples: u32,
+    /// Device frames counted over the session
+    pub device_frames: u32,
+    /// Device frame rate measured against the host clock
+    pub device_rate_hz: f64,
+    /// Configured device frame rate, if the detector free-runs
+    pub nominal_rate_hz: Option<f64>,
+    /// Estimated drift of the device frame clock relative to the host in ppm
+    pub drift_ppm: Option<f64>,
+    /// True when host scheduling is degrading sampling regularity
+    pub degraded: bool,
+}
+
+/// Correlates device measure counter readings with host timestamps
+#[derive(Debug)]
+pub struct ClockDriftTracker {
+    nominal_rate_hz: Option<f64>,
+    first: Option<(Instant, u32)>,
+    last: Option<(Instant, u32)>,
+    device_frames: u32,
+    intervals: u32,
+    mean_ms: f64,
+    m2: f64,
+    max_ms: f64,
+    late_samples: u32,
+}
+
+impl ClockDriftTracker {
+    /// Create a tracker; `nominal_rate_hz` is the configured frame rate of free-running detectors
+    pub fn new(nominal_rate_hz: Option<f64>) -> Self {
+        Self {
+            nominal_rate_hz,
+            first: None,
+            last: None,
+            device_frames: 0,
+            intervals: 0,
+            mean_ms: 0.0,
+            m2: 0.0,
+            max_ms: 0.0,
+            late_samples: 0,
+        }
+    }
+
+    /// Record a device counter reading taken at `host_time`
+    pub fn record(&mut self, host_time: Instant, counter: u32) {
+        if let Some((last_time, last_counter)) = self.last {
+            let interval_ms = host_time.duration_since(last_time).as_secs_f64() * 1000.0;
+            self.device_frames = self
+                .device_frames
+                .saturating_add(counter.wrapping_sub(last_counter));
+
+            if self.intervals >= MIN_SAMPLES_FOR_FLAGGING
+                && interval_ms > self.mean_ms * LATE_FACTOR
+            {
+                self.late_samples += 1;
+                warn!(
+                    "⏱️  Host sampling delayed: {interval_ms:.1}ms vs {:.1}ms average (CPU load?)",
+                    self.mean_ms
+                );
+            }
+
+            // Welford's running mean/variance
+            self.intervals += 1;
+            let delta = interval_ms - self.mean_ms;
+            self.mean_ms += delta / f64::from(self.intervals);
+            self.m2 += delta * (interval_ms - self.mean_ms);
+            self.max_ms = self.max_ms.max(interval_ms);
+        } else {
+            self.first = Some((host_time, counter));
+        }
+        self.last = Some((host_time, counter));
+    }
+
+    /// Summarize drift and jitter, if at least two samples were recorded
+    pub fn report(&self) -> Option<ClockDriftReport> {
+        let ((first_time, _), (last_time, _)) = (self.first?, self.last?);
+        let elapsed_s = last_time.duration_since(first_time).as_secs_f64();
+        if self.intervals == 0 || elapsed_s <= 0.0 {
+            return None;
+        }
+
+        let device_rate_hz = f64::from(self.device_frames) / elapsed_s;
+        let drift_ppm = self
+            .nominal_rate_hz
+            .filter(|nominal| *nominal > 0.0)
+            .map(|nominal| (device_rate_hz / nominal - 1.0) * 1e6);
+        let jitter_ms = (self.m2 / f64::from(self.intervals)).sqrt();
+        let degraded = jitter_ms > self.mean_ms * DEGRADED_JITTER_RATIO
+            || f64::from(self.late_samples) > f64::from(self.intervals) * DEGRADED_LATE_RATIO;
+
+        Some(ClockDriftReport {
+            samples: self.intervals + 1,
+            host_interval_mean_ms: self.mean_ms,
+            host_jitter_ms: jitter_ms,
+            host_interval_max_ms: self.max_ms,
+            late_samples: self.late_samples,
+            device_frames: self.device_frames,
+            device_rate_hz,
+            nominal_rate_hz: self.nominal_rate_hz,
+            drift_ppm,
+            degraded,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_drift_and_late_samples() {
+        let mut tracker = ClockDriftTracker::new(Some(10.0));
+        let start = Instant::now();
+        let mut host_ms = 0u64;
+        for i in 0..20u32 {
+            // One 1s stall in an otherwise regular 100ms loop
+            host_ms += if i == 15 { 1000 } else { 100 };
+            tracker.record(start + Duration::from_millis(host_ms), i * 2);
+        }
+
+        let report = tracker.report().unwrap();
+        assert_eq!(report.samples, 20);
+        assert_eq!(report.device_frames, 38);
+        assert_eq!(report.late_samples, 1);
+        assert!(report.degraded);
+        assert!(report.drift_ppm.unwrap() > 0.0);
+    }
+}
@@ -0,0 +1,391 @@
+//! Upload of completed sessions
+//!
+//! Field data collection used to end with an `scp` of every CSV export and its metadata off
+//! each device. With `--upload`, a continuous run with `--save-to` or a labeled capture sends
+//! its files once it completes: the data file, the session summary (`<file>.meta.json` or the
+//! capture's `metadata.json`) and any `.sig` signature, followed by a `complete.json` marker
+//! listing every file with its size and SHA-256. The marker goes last, so a collector can
+//! treat a session as complete once the marker exists. Each transfer is retried with
+//! exponential backoff, and the local files are kept whether or not the upload succeeds.
+//!
+//! Objects are stored under `<destination>/<device id>/<session>/`. Transfers go through
+//! `curl`, so HTTPS and AWS signature V4 need nothing linked into the monitor:
+//!
+//! * `s3://BUCKET[/PREFIX]` is signed with `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+//!   `AWS_SESSION_TOKEN` for `AWS_REGION` (default `us-east-1`). `AWS_ENDPOINT_URL` selects
+//!   an S3-compatible store, addressed path-style.
+//! * `http://` and `https://` URLs receive a PUT per object, with `XM125_UPLOAD_TOKEN` as
+//!   bearer token when set.
+//!
+//! Credentials are handed to curl on its standard input, never on its command line.
+
+use crate::error::RadarError;
+use crate::journal::write_atomic;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Name of the completion marker object and local file suffix
+pub const MARKER: &str = "complete.json";
+
+/// Limit for a single transfer
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Longest wait between attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Where completed sessions are uploaded, from `--upload`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadDestination {
+    S3 { bucket: String, prefix: String },
+    Http { url: String },
+}
+
+/// Parse `s3://BUCKET[/PREFIX]` or an `http(s)://` URL
+pub fn parse_destination(s: &str) -> Result<UploadDestination, String> {
+    let s = s.trim();
+    if let Some(rest) = s.strip_prefix("s3://") {
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!(
+                "'{s}' names no bucket (expected s3://BUCKET[/PREFIX])"
+            ));
+        }
+        return Ok(UploadDestination::S3 {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        });
+    }
+    if s.starts_with("http://") || s.starts_with("https://") {
+        return Ok(UploadDestination::Http {
+            url: s.trim_end_matches('/').to_string(),
+        });
+    }
+    Err(format!(
+        "unsupported upload destination '{s}' (expected s3://BUCKET[/PREFIX] or an http(s):// URL)"
+    ))
+}
+
+impl UploadDestination {
+    /// URL of the object `key`
+    fn url(&self, key: &str) -> String {
+        let key = encode_key(key);
+        match self {
+            UploadDestination::Http { url } => format!("{url}/{key}"),
+            UploadDestination::S3 { bucket, prefix } => {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}/{key}", encode_key(prefix))
+                };
+                match std::env::var("AWS_ENDPOINT_URL") {
+                    Ok(endpoint) if !endpoint.is_empty() => {
+                        format!("{}/{bucket}/{key}", endpoint.trim_end_matches('/'))
+                    }
+                    _ => format!("https://{bucket}.s3.{}.amazonaws.com/{key}", region()),
+                }
+            }
+        }
+    }
+
+    /// curl configuration carrying the credentials
+    fn curl_config(&self) -> String {
+        let env = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let mut config = String::new();
+        match self {
+            UploadDestination::Http { .. } => {
+                if let Some(token) = env("XM125_UPLOAD_TOKEN") {
+                    config += &curl_option("header", &format!("Authorization: Bearer {token}"));
+                }
+            }
+            UploadDestination::S3 { .. } => {
+                config += &curl_option("aws-sigv4", &format!("aws:amz:{}:s3", region()));
+                let key_id = env("AWS_ACCESS_KEY_ID").unwrap_or_default();
+                let secret = env("AWS_SECRET_ACCESS_KEY").unwrap_or_default();
+                config += &curl_option("user", &format!("{key_id}:{secret}"));
+                if let Some(token) = env("AWS_SESSION_TOKEN") {
+                    config += &curl_option("header", &format!("x-amz-security-token: {token}"));
+                }
+            }
+        }
+        config
+    }
+}
+
+impl std::fmt::Display for UploadDestination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UploadDestination::Http { url } => write!(f, "{url}"),
+            UploadDestination::S3 { bucket, prefix } if prefix.is_empty() => {
+                write!(f, "s3://{bucket}")
+            }
+            UploadDestination::S3 { bucket, prefix } => write!(f, "s3://{bucket}/{prefix}"),
+        }
+    }
+}
+
+fn region() -> String {
+    std::env::var("AWS_REGION")
+        .ok()
+        .filter(|region| !region.is_empty())
+        .unwrap_or_else(|| "us-east-1".to_string())
+}
+
+/// Percent-encode an object key, keeping `/` separators
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// One `name = "value"` line of a curl config file
+fn curl_option(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("{name} = \"{value}\"\n")
+}
+
+/// Files of a completed session
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadSession {
+    /// Directory of the session under `<destination>/<device id>/`
+    pub name: String,
+    /// Files to upload, in order
+    pub files: Vec<PathBuf>,
+    /// Local path of the completion marker
+    pub marker: PathBuf,
+}
+
+impl UploadSession {
+    /// CSV export of a continuous run with its metadata sidecar and signatures
+    pub fn export(path: &str, finished_at: DateTime<Utc>) -> Self {
+        let stem = Path::new(path)
+            .file_stem()
+            .map_or_else(|| "session".into(), |stem| stem.to_string_lossy());
+        let files = [
+            path.to_string(),
+            crate::session::SessionMetadata::sidecar_path(path),
+            format!("{path}.sig"),
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|file| file.exists())
+        .collect();
+        Self {
+            name: format!("{stem}-{}", finished_at.format("%Y%m%dT%H%M%SZ")),
+            files,
+            marker: PathBuf::from(format!("{path}.{MARKER}")),
+        }
+    }
+
+    /// Labeled capture directory (`<dataset>/<label>/<capture>`)
+    pub fn capture(dir: &Path) -> Result<Self, RadarError> {
+        let marker = dir.join(MARKER);
+        let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        files.retain(|file| file.is_file() && *file != marker);
+        files.sort();
+        let component = |path: Option<&Path>| {
+            path.and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        Ok(Self {
+            name: format!("{}/{}", component(dir.parent()), component(Some(dir))),
+            files,
+            marker,
+        })
+    }
+}
+
+/// Uploaded file as listed in the completion marker
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadedFile {
+    pub name: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Contents of the completion marker
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionMarker {
+    pub device: String,
+    pub session: String,
+    pub completed_at: DateTime<Utc>,
+    pub files: Vec<UploadedFile>,
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn describe(path: &Path) -> Result<UploadedFile, RadarError> {
+    let mut file = std::fs::File::open(path)?;
+    let mut sha256 = Sha256::new();
+    let mut bytes = 0u64;
+    let mut buffer = [0u8; 8192];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        sha256.update(&buffer[..n]);
+        bytes += n as u64;
+    }
+    Ok(UploadedFile {
+        name: file_name(path),
+        bytes,
+        sha256: format!("{:x}", sha256.finalize()),
+    })
+}
+
+/// PUT `file` to `url` with curl
+async fn put(file: &Path, url: &str, config: &str) -> Result<(), String> {
+    let mut child = tokio::process::Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--config", "-"])
+        .arg("--max-time")
+        .arg(TRANSFER_TIMEOUT.as_secs().to_string())
+        .arg("--upload-file")
+        .arg(file)
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("cannot run curl: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(config.as_bytes())
+            .await
+            .map_err(|e| format!("cannot pass credentials to curl: {e}"))?;
+    }
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("curl failed: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Upload `file` as `key`, making `retries` further attempts after a failure
+async fn put_with_retry(
+    destination: &UploadDestination,
+    file: &Path,
+    key: &str,
+    retries: u32,
+) -> Result<(), RadarError> {
+    let url = destination.url(key);
+    let config = destination.curl_config();
+    let mut backoff = Duration::from_secs(1);
+    let mut attempt = 0;
+    loop {
+        match put(file, &url, &config).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                warn!("⚠️  Upload of {key} failed ({e}), retry {attempt}/{retries} in {backoff:?}");
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+            Err(e) => {
+                return Err(RadarError::Io(std::io::Error::other(format!(
+                    "upload of {key} to {destination} failed after {} attempts: {e}",
+                    attempt + 1
+                ))))
+            }
+        }
+    }
+}
+
+/// Upload the files of `session`, then write and upload its completion marker
+pub async fn upload(
+    destination: &UploadDestination,
+    session: &UploadSession,
+    retries: u32,
+) -> Result<(), RadarError> {
+    let device = crate::discovery::device_id();
+    let base = format!("{device}/{}", session.name);
+    info!(
+        "☁️  Uploading {} files to {destination}/{base}",
+        session.files.len()
+    );
+
+    let mut files = Vec::with_capacity(session.files.len());
+    for path in &session.files {
+        let described = describe(path)?;
+        put_with_retry(
+            destination,
+            path,
+            &format!("{base}/{}", described.name),
+            retries,
+        )
+        .await?;
+        files.push(described);
+    }
+
+    let marker = CompletionMarker {
+        device,
+        session: session.name.clone(),
+        completed_at: Utc::now(),
+        files,
+    };
+    write_atomic(
+        &session.marker,
+        serde_json::to_string_pretty(&marker)?.as_bytes(),
+    )?;
+    put_with_retry(
+        destination,
+        &session.marker,
+        &format!("{base}/{MARKER}"),
+        retries,
+    )
+    .await?;
+    info!("☁️  Upload complete: {destination}/{base}/{MARKER}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destinations_and_object_urls() {
+        let s3 = parse_destination("s3://field-data/xm125/").unwrap();
+        assert_eq!(
+            s3,
+            UploadDestination::S3 {
+                bucket: "field-data".to_string(),
+                prefix: "xm125".to_string()
+            }
+        );
+        assert_eq!(s3.to_string(), "s3://field-data/xm125");
+        let http = parse_destination("https://collector.local/upload/").unwrap();
+        assert_eq!(
+            http.url("node 1/run-20240501T120000Z/data.csv"),
+            "https://collector.local/upload/node%201/run-20240501T120000Z/data.csv"
+        );
+        assert!(parse_destination("s3://").is_err());
+        assert!(parse_destination("ftp://collector.local").is_err());
+
+        assert_eq!(
+            curl_option("header", r#"Authorization: Bearer a"b\c"#),
+            "header = \"Authorization: Bearer a\\\"b\\\\c\"\n"
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Version and build metadata reporting
+//!
+//! `--version` honours `--format` so that fleet inventory tooling can collect a
+//! structured report of exactly what is deployed on each device.
+
+use crate::cli::OutputFormat;
+use crate::firmware::FirmwareType;
+use crate::radar::protocol::protocol_for_firmware;
+use serde::Serialize;
+
+/// Firmware application supported by this build
+#[derive(Debug, Serialize)]
+pub struct SupportedApplication {
+    pub name: &'static str,
+    pub application_id: u32,
+    pub firmware_binary: &'static str,
+    /// Status and command registers of the application's register protocol
+    pub status_register: u16,
+    pub command_register: u16,
+}
+
+/// Version and build report
+#[derive(Debug, Serialize)]
+pub struct VersionInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_commit: &'static str,
+    pub build_date: &'static str,
+    pub arch: &'static str,
+    pub profile: &'static str,
+    pub features: Vec<&'static str>,
+    pub applications: Vec<SupportedApplication>,
+}
+
+impl VersionInfo {
+    /// Collect metadata for the running binary
+    pub fn current() -> Self {
+        let applications = [
+            FirmwareType::Distance,
+            FirmwareType::Presence,
+            FirmwareType::Breathing,
+        ]
+        .into_iter()
+        .map(|firmware| {
+            let protocol = protocol_for_firmware(firmware);
+            SupportedApplication {
+                name: firmware.display_name(),
+                application_id: firmware.application_id(),
+                firmware_binary: firmware.binary_filename(),
+                status_register: protocol.status_register(),
+                command_register: protocol.command_register(),
+            }
+        })
+        .collect();
+
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: env!("XM125_GIT_COMMIT"),
+            build_date: env!("XM125_BUILD_DATE"),
+            arch: std::env::consts::ARCH,
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            },
+            features: env!("XM125_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect(),
+            applications,
+        }
+    }
+}
+
+/// Print the version report in the requested output format
+pub fn print_version(format: &OutputFormat) -> Result<(), serde_json::Error> {
+    let info = VersionInfo::current();
+    let features = if info.features.is_empty() {
+        "none".to_string()
+    } else {
+        info.features.join(",")
+    };
+
+    match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&info)?);
+        }
+        OutputFormat::Csv => {
+            println!("name,version,git_commit,build_date,arch,profile,features,application_ids");
+            let app_ids: Vec<String> = info
+                .applications
+                .iter()
+                .map(|app| app.application_id.to_string())
+                .collect();
+            println!(
+                "{},{},{},{},{},{},{},{}",
+                info.name,
+                info.version,
+                info.git_commit,
+                info.build_date,
+                info.arch,
+                info.profile,
+                features.replace(',', ";"),
+                app_ids.join(";")
+            );
+        }
+        OutputFormat::Human => {
+            println!("{} v{}", info.name, info.version);
+            println!(
+                "Commit: {} | Built: {} | {} ({})",
+                info.git_commit, info.build_date, info.arch, info.profile
+            );
+            println!("Features: {features}");
+            println!("Supported firmware:");
+            for app in &info.applications {
+                println!(
+                    "  App ID {} - {} ({}, status reg {}, command reg {})",
+                    app.application_id,
+                    app.name,
+                    app.firmware_binary,
+                    app.status_register,
+                    app.command_register
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! Distance zones with hysteresis
+//!
+//! Consumers such as door controllers only care which zone a target is in (near, mid,
+//! far), not the raw distance. Zones are defined by ascending upper bounds; a target must
+//! move past a boundary by the hysteresis margin before the zone changes, so a target
+//! standing on a boundary does not produce a stream of flapping events.
+
+use crate::error::RadarError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A named distance band; the last zone is unbounded
+#[derive(Debug, Clone, PartialEq)]
+pub struct Zone {
+    pub name: String,
+    /// Exclusive upper bound in meters (None for the outermost zone)
+    pub upper_m: Option<f32>,
+}
+
+/// Ordered zone definitions
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneSet {
+    zones: Vec<Zone>,
+    hysteresis_m: f32,
+}
+
+impl ZoneSet {
+    /// Parse `name:upper,name:upper,...,name`, e.g. `near:0.5,mid:2.0,far`
+    pub fn parse(spec: &str, hysteresis_m: f32) -> Result<Self, RadarError> {
+        let invalid = |reason: String| RadarError::InvalidParameters(format!("--zones: {reason}"));
+        if hysteresis_m.is_nan() || hysteresis_m < 0.0 {
+            return Err(invalid(format!(
+                "hysteresis must be non-negative, got {hysteresis_m}"
+            )));
+        }
+
+        let parts: Vec<&str> = spec.split(',').map(str::trim).collect();
+        if parts.len() < 2 {
+            return Err(invalid("at least two zones are required".to_string()));
+        }
+
+        let mut zones: Vec<Zone> = Vec::with_capacity(parts.len());
+        for (i, part) in parts.iter().enumerate() {
+            let last = i == parts.len() - 1;
+            let (name, upper_m) = match (part.split_once(':'), last) {
+                (Some((name, upper)), false) => {
+                    let upper: f32 = upper
+                        .trim()
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid bound '{upper}' for zone {name}")))?;
+                    (name.trim(), Some(upper))
+                }
+                (None, true) => (*part, None),
+                (Some(_), true) => {
+                    return Err(invalid(format!(
+                        "last zone '{part}' must not have an upper bound"
+                    )))
+                }
+                (None, false) => {
+                    return Err(invalid(format!(
+                        "zone '{part}' needs an upper bound (name:meters)"
+                    )))
+                }
+            };
+
+            if name.is_empty() || zones.iter().any(|zone| zone.name == name) {
+                return Err(invalid(format!(
+                    "zone names must be unique and non-empty: '{name}'"
+                )));
+            }
+            let previous = zones.last().and_then(|zone| zone.upper_m).unwrap_or(0.0);
+            if let Some(upper) = upper_m {
+                if upper <= previous {
+                    return Err(invalid(format!(
+                        "bounds must be positive and ascending ({name}: {upper}m)"
+                    )));
+                }
+            }
+            zones.push(Zone {
+                name: name.to_string(),
+                upper_m,
+            });
+        }
+
+        Ok(Self {
+            zones,
+            hysteresis_m,
+        })
+    }
+
+    /// Zone index for `distance` ignoring hysteresis
+    fn classify(&self, distance: f32) -> usize {
+        self.zones
+            .iter()
+            .position(|zone| zone.upper_m.is_some_and(|upper| distance < upper))
+            .unwrap_or(self.zones.len() - 1)
+    }
+
+    /// True while `distance` is within zone `index` widened by the hysteresis margin
+    fn holds(&self, index: usize, distance: f32) -> bool {
+        let lower = match index {
+            0 => f32::NEG_INFINITY,
+            _ => self.zones[index - 1].upper_m.unwrap_or(0.0) - self.hysteresis_m,
+        };
+        let upper = self.zones[index]
+            .upper_m
+            .map_or(f32::INFINITY, |upper| upper + self.hysteresis_m);
+        distance >= lower && distance < upper
+    }
+}
+
+/// A change of zone
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoneEvent {
+    pub timestamp: DateTime<Utc>,
+    /// Previous zone (None for the first reading)
+    pub from: Option<String>,
+    pub to: String,
+    pub distance_m: f32,
+}
+
+/// Tracks the current zone and reports transitions
+#[derive(Debug, Clone)]
+pub struct ZoneTracker {
+    zones: ZoneSet,
+    current: Option<usize>,
+}
+
+impl ZoneTracker {
+    pub fn new(zones: ZoneSet) -> Self {
+        Self {
+            zones,
+            current: None,
+        }
+    }
+
+    /// Feed a distance reading; returns an event when the zone changes
+    pub fn update(&mut self, distance: f32) -> Option<ZoneEvent> {
+        if let Some(current) = self.current {
+            if self.zones.holds(current, distance) {
+                return None;
+            }
+        }
+
+        let next = self.zones.classify(distance);
+        let from = self.current.replace(next);
+        Some(ZoneEvent {
+            timestamp: Utc::now(),
+            from: from.map(|index| self.zones.zones[index].name.clone()),
+            to: self.zones.zones[next].name.clone(),
+            distance_m: distance,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions_respect_hysteresis() {
+        let zones = ZoneSet::parse("near:0.5,mid:2.0,far", 0.1).unwrap();
+        let mut tracker = ZoneTracker::new(zones);
+
+        let first = tracker.update(0.3).unwrap();
+        assert_eq!((first.from, first.to.as_str()), (None, "near"));
+        // Inside the margin around the 0.5m boundary: no flapping
+        assert!(tracker.update(0.55).is_none());
+        assert!(tracker.update(0.45).is_none());
+
+        let event = tracker.update(0.65).unwrap();
+        assert_eq!(
+            (event.from.as_deref(), event.to.as_str()),
+            (Some("near"), "mid")
+        );
+        assert!(tracker.update(0.45).is_none());
+        assert_eq!(tracker.update(2.5).unwrap().to, "far");
+
+        assert!(ZoneSet::parse("near:2.0,mid:0.5,far", 0.1).is_err());
+        assert!(ZoneSet::parse("near:0.5,far:2.0", 0.1).is_err());
+        assert!(ZoneSet::parse("near", 0.1).is_err());
+    }
+}
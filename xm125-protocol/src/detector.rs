@@ -0,0 +1,846 @@
+// Detector Protocol Module
+// Per-firmware register protocol definitions behind a shared trait
+
+#![allow(clippy::pedantic)]
+// The distance detector, presence detector and breathing reference application share the
+// register layout conventions (command register, status register, application ID) but differ
+// in status bit semantics, command codes and result word layout. Encoding those differences
+// here keeps them out of comments and ad-hoc masks scattered through the detectors.
+
+use crate::registers::{
+    BREATHING_REG_APP_STATE_ADDRESS, BREATHING_REG_APP_STATUS_ADDRESS,
+    BREATHING_REG_BREATHING_RATE_ADDRESS, BREATHING_REG_BREATHING_RESULT_ADDRESS,
+    BREATHING_REG_COMMAND_ADDRESS, BREATHING_REG_END_ADDRESS, BREATHING_REG_FRAME_RATE_ADDRESS,
+    BREATHING_REG_HIGHEST_BREATHING_RATE_ADDRESS, BREATHING_REG_HWAAS_ADDRESS,
+    BREATHING_REG_LOWEST_BREATHING_RATE_ADDRESS, BREATHING_REG_PROFILE_ADDRESS,
+    BREATHING_REG_START_ADDRESS, CMD_APPLY_CONFIG_AND_CALIBRATE, CMD_BREATHING_APPLY_CONFIGURATION,
+    CMD_BREATHING_RESET_MODULE, CMD_BREATHING_START_APP, CMD_BREATHING_STOP_APP,
+    CMD_PRESENCE_APPLY_CONFIGURATION, CMD_PRESENCE_RESET_MODULE, CMD_PRESENCE_START_DETECTOR,
+    CMD_PRESENCE_STOP_DETECTOR, CMD_RESET_MODULE, PRESENCE_REG_COMMAND_ADDRESS,
+    PRESENCE_REG_DETECTION_ON_GPIO_ADDRESS, PRESENCE_REG_DETECTOR_STATUS_ADDRESS,
+    PRESENCE_REG_END_ADDRESS, PRESENCE_REG_FRAME_RATE_ADDRESS,
+    PRESENCE_REG_INTER_DETECTION_ENABLED_ADDRESS, PRESENCE_REG_INTER_DETECTION_THRESHOLD_ADDRESS,
+    PRESENCE_REG_INTRA_DETECTION_ENABLED_ADDRESS, PRESENCE_REG_INTRA_DETECTION_THRESHOLD_ADDRESS,
+    PRESENCE_REG_START_ADDRESS, REG_COMMAND, REG_DETECTOR_STATUS, REG_DISTANCE_RESULT,
+    REG_END_CONFIG, REG_INTER_PRESENCE_SCORE, REG_INTRA_PRESENCE_SCORE, REG_MAX_PROFILE,
+    REG_MAX_STEP_LENGTH, REG_PEAK0_DISTANCE, REG_PEAK0_STRENGTH, REG_PRESENCE_DISTANCE,
+    REG_PRESENCE_RESULT, REG_START_CONFIG, REG_THRESHOLD_SENSITIVITY, STATUS_BUSY_MASK,
+    STATUS_ERROR_MASK,
+};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// XM125 application firmware, identified by its application ID register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Application {
+    Distance,
+    Presence,
+    Breathing,
+}
+
+impl Application {
+    /// Value of the application ID register for this firmware
+    pub const fn app_id(self) -> u32 {
+        match self {
+            Application::Distance => 1,
+            Application::Presence => 2,
+            Application::Breathing => 3,
+        }
+    }
+
+    /// Application for an ID read from the module (None if unknown)
+    pub const fn from_app_id(app_id: u32) -> Option<Self> {
+        match app_id {
+            1 => Some(Application::Distance),
+            2 => Some(Application::Presence),
+            3 => Some(Application::Breathing),
+            _ => None,
+        }
+    }
+}
+
+/// What a status flag reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagKind {
+    /// A startup step succeeded; all of them set means the detector is ready
+    Ok,
+    /// A step failed; only RESET MODULE is accepted while any is set
+    Error,
+    /// A command is in progress; only status reads are allowed
+    Busy,
+}
+
+/// A single named bit in a detector status register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusFlag {
+    pub mask: u32,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: FlagKind,
+}
+
+impl StatusFlag {
+    /// Bit number within the status word
+    pub const fn bit(&self) -> u8 {
+        self.mask.trailing_zeros() as u8
+    }
+
+    pub const fn is_error(&self) -> bool {
+        matches!(self.kind, FlagKind::Error)
+    }
+}
+
+impl core::fmt::Display for StatusFlag {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+const fn flag(
+    bit: u32,
+    kind: FlagKind,
+    name: &'static str,
+    description: &'static str,
+) -> StatusFlag {
+    StatusFlag {
+        mask: 1 << bit,
+        name,
+        description,
+        kind,
+    }
+}
+
+const fn ok_bit(bit: u32, name: &'static str, description: &'static str) -> StatusFlag {
+    flag(bit, FlagKind::Ok, name, description)
+}
+
+const fn err_bit(bit: u32, name: &'static str, description: &'static str) -> StatusFlag {
+    flag(bit, FlagKind::Error, name, description)
+}
+
+/// Flags every firmware sets in the upper bits of its status word
+const COMMON_STATUS_FLAGS: [StatusFlag; 2] = [
+    StatusFlag {
+        mask: STATUS_ERROR_MASK,
+        name: "DETECTOR_ERROR",
+        description: "The detector or application reported an error",
+        kind: FlagKind::Error,
+    },
+    StatusFlag {
+        mask: STATUS_BUSY_MASK,
+        name: "BUSY",
+        description: "A command is being executed",
+        kind: FlagKind::Busy,
+    },
+];
+
+/// Register description used for configuration/result tables
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegisterInfo {
+    pub address: u16,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+const fn reg(address: u16, name: &'static str, description: &'static str) -> RegisterInfo {
+    RegisterInfo {
+        address,
+        name,
+        description,
+    }
+}
+
+/// Decoded result word (register 16) - layout differs per firmware
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResultWord {
+    Distance {
+        num_distances: u8,
+        near_start_edge: bool,
+        calibration_needed: bool,
+        measure_distance_error: bool,
+        temperature: i16,
+    },
+    Presence {
+        presence_detected: bool,
+        presence_detected_sticky: bool,
+        detector_error: bool,
+        temperature: i16,
+    },
+    Breathing {
+        result_ready: bool,
+        result_ready_sticky: bool,
+        temperature: i16,
+    },
+}
+
+/// Temperature lives in the upper 16 bits of the result word for all firmwares
+fn result_temperature(raw: u32) -> i16 {
+    (raw >> 16) as u16 as i16
+}
+
+/// Shared interface for the XM125 firmware register protocols
+pub trait DetectorProtocol: Sync {
+    /// Human readable protocol name
+    fn name(&self) -> &'static str;
+
+    /// Firmware implementing this protocol
+    fn application(&self) -> Application;
+
+    /// Register holding the detector/application status word
+    fn status_register(&self) -> u16;
+
+    /// Register accepting command codes
+    fn command_register(&self) -> u16;
+
+    /// Status flags defined by this firmware, including the common error and busy bits
+    fn status_flags(&self) -> &'static [StatusFlag];
+
+    /// Command applying the written configuration
+    fn cmd_apply_configuration(&self) -> u32;
+
+    /// Command starting continuous operation, if the firmware has one
+    fn cmd_start(&self) -> Option<u32>;
+
+    /// Command stopping continuous operation, if the firmware has one
+    fn cmd_stop(&self) -> Option<u32>;
+
+    /// Command resetting the module so a new configuration can be written
+    fn cmd_reset_module(&self) -> u32;
+
+    /// Configuration registers written by the host
+    fn config_registers(&self) -> &'static [RegisterInfo];
+
+    /// Result registers read after a measurement
+    fn result_registers(&self) -> &'static [RegisterInfo];
+
+    /// Decode the result word (register 16)
+    fn parse_result(&self, raw: u32) -> ResultWord;
+
+    /// Busy bit set - only status reads are allowed
+    fn is_busy(&self, status: u32) -> bool {
+        self.status_flags()
+            .iter()
+            .any(|flag| flag.kind == FlagKind::Busy && status & flag.mask != 0)
+    }
+
+    /// Any error bit set - only RESET MODULE is allowed
+    fn has_error(&self, status: u32) -> bool {
+        self.status_flags()
+            .iter()
+            .any(|flag| flag.is_error() && status & flag.mask != 0)
+    }
+
+    /// All OK bits set - detector created, configured and ready
+    fn is_ready(&self, status: u32) -> bool {
+        self.status_flags()
+            .iter()
+            .filter(|flag| flag.kind == FlagKind::Ok)
+            .all(|flag| status & flag.mask != 0)
+    }
+
+    /// Flags set in `status`, in bit order
+    #[cfg(feature = "alloc")]
+    fn decode_status(&self, status: u32) -> Vec<StatusFlag> {
+        self.status_flags()
+            .iter()
+            .filter(|flag| status & flag.mask != 0)
+            .copied()
+            .collect()
+    }
+}
+
+/// Flags set in the `raw` status word of `application`
+#[cfg(feature = "alloc")]
+pub fn decode_status(application: impl Into<Application>, raw: u32) -> Vec<StatusFlag> {
+    protocol_for_application(application.into()).decode_status(raw)
+}
+
+/// Distance detector protocol (i2c_distance_detector.bin, application ID 1)
+pub struct DistanceProtocol;
+
+/// Presence detector protocol (i2c_presence_detector.bin, application ID 2)
+pub struct PresenceProtocol;
+
+/// Breathing reference application protocol (i2c_ref_app_breathing.bin, application ID 3)
+pub struct BreathingProtocol;
+
+const DISTANCE_STATUS_FLAGS: &[StatusFlag] = &[
+    ok_bit(0, "RSS_REGISTER_OK", "Radar system software registered"),
+    ok_bit(1, "CONFIG_CREATE_OK", "Detector configuration created"),
+    ok_bit(2, "SENSOR_CREATE_OK", "Sensor created"),
+    ok_bit(3, "DETECTOR_CREATE_OK", "Distance detector created"),
+    ok_bit(4, "DETECTOR_BUFFER_OK", "Detector buffer allocated"),
+    ok_bit(5, "SENSOR_BUFFER_OK", "Sensor buffer allocated"),
+    ok_bit(6, "CALIBRATION_BUFFER_OK", "Calibration buffer allocated"),
+    ok_bit(7, "CONFIG_APPLY_OK", "Configuration applied to the sensor"),
+    ok_bit(8, "SENSOR_CALIBRATE_OK", "Sensor calibrated"),
+    ok_bit(9, "DETECTOR_CALIBRATE_OK", "Detector calibrated"),
+    err_bit(
+        16,
+        "RSS_REGISTER_ERROR",
+        "Registering the radar system software failed",
+    ),
+    err_bit(
+        17,
+        "CONFIG_CREATE_ERROR",
+        "Creating the detector configuration failed",
+    ),
+    err_bit(18, "SENSOR_CREATE_ERROR", "Creating the sensor failed"),
+    err_bit(
+        19,
+        "DETECTOR_CREATE_ERROR",
+        "Creating the distance detector failed",
+    ),
+    err_bit(
+        20,
+        "DETECTOR_BUFFER_ERROR",
+        "Allocating the detector buffer failed",
+    ),
+    err_bit(
+        21,
+        "SENSOR_BUFFER_ERROR",
+        "Allocating the sensor buffer failed",
+    ),
+    err_bit(
+        22,
+        "CALIBRATION_BUFFER_ERROR",
+        "Allocating the calibration buffer failed",
+    ),
+    err_bit(
+        23,
+        "CONFIG_APPLY_ERROR",
+        "The sensor rejected the configuration",
+    ),
+    err_bit(24, "SENSOR_CALIBRATE_ERROR", "Sensor calibration failed"),
+    err_bit(
+        25,
+        "DETECTOR_CALIBRATE_ERROR",
+        "Detector calibration failed",
+    ),
+    COMMON_STATUS_FLAGS[0],
+    COMMON_STATUS_FLAGS[1],
+];
+
+const PRESENCE_STATUS_FLAGS: &[StatusFlag] = &[
+    ok_bit(0, "RSS_REGISTER_OK", "Radar system software registered"),
+    ok_bit(1, "CONFIG_CREATE_OK", "Detector configuration created"),
+    ok_bit(2, "SENSOR_CREATE_OK", "Sensor created"),
+    ok_bit(3, "SENSOR_CALIBRATE_OK", "Sensor calibrated"),
+    ok_bit(4, "DETECTOR_CREATE_OK", "Presence detector created"),
+    ok_bit(5, "DETECTOR_BUFFER_OK", "Detector buffer allocated"),
+    ok_bit(6, "SENSOR_BUFFER_OK", "Sensor buffer allocated"),
+    ok_bit(7, "CONFIG_APPLY_OK", "Configuration applied to the sensor"),
+    err_bit(
+        16,
+        "RSS_REGISTER_ERROR",
+        "Registering the radar system software failed",
+    ),
+    err_bit(
+        17,
+        "CONFIG_CREATE_ERROR",
+        "Creating the detector configuration failed",
+    ),
+    err_bit(18, "SENSOR_CREATE_ERROR", "Creating the sensor failed"),
+    err_bit(19, "SENSOR_CALIBRATE_ERROR", "Sensor calibration failed"),
+    err_bit(
+        20,
+        "DETECTOR_CREATE_ERROR",
+        "Creating the presence detector failed",
+    ),
+    err_bit(
+        21,
+        "DETECTOR_BUFFER_ERROR",
+        "Allocating the detector buffer failed",
+    ),
+    err_bit(
+        22,
+        "SENSOR_BUFFER_ERROR",
+        "Allocating the sensor buffer failed",
+    ),
+    err_bit(
+        23,
+        "CONFIG_APPLY_ERROR",
+        "The sensor rejected the configuration",
+    ),
+    COMMON_STATUS_FLAGS[0],
+    COMMON_STATUS_FLAGS[1],
+];
+
+const BREATHING_STATUS_FLAGS: &[StatusFlag] = &[
+    ok_bit(0, "RSS_REGISTER_OK", "Radar system software registered"),
+    ok_bit(1, "CONFIG_CREATE_OK", "Application configuration created"),
+    ok_bit(2, "SENSOR_CREATE_OK", "Sensor created"),
+    ok_bit(3, "SENSOR_CALIBRATE_OK", "Sensor calibrated"),
+    ok_bit(4, "APP_CREATE_OK", "Breathing application created"),
+    ok_bit(5, "SENSOR_BUFFER_OK", "Sensor buffer allocated"),
+    ok_bit(6, "CONFIG_APPLY_OK", "Configuration applied to the sensor"),
+    err_bit(
+        16,
+        "RSS_REGISTER_ERROR",
+        "Registering the radar system software failed",
+    ),
+    err_bit(
+        17,
+        "CONFIG_CREATE_ERROR",
+        "Creating the application configuration failed",
+    ),
+    err_bit(18, "SENSOR_CREATE_ERROR", "Creating the sensor failed"),
+    err_bit(19, "SENSOR_CALIBRATE_ERROR", "Sensor calibration failed"),
+    err_bit(
+        20,
+        "APP_CREATE_ERROR",
+        "Creating the breathing application failed",
+    ),
+    err_bit(
+        21,
+        "SENSOR_BUFFER_ERROR",
+        "Allocating the sensor buffer failed",
+    ),
+    err_bit(
+        22,
+        "CONFIG_APPLY_ERROR",
+        "The sensor rejected the configuration",
+    ),
+    COMMON_STATUS_FLAGS[0],
+    COMMON_STATUS_FLAGS[1],
+];
+
+const DISTANCE_CONFIG_REGISTERS: &[RegisterInfo] = &[
+    reg(REG_START_CONFIG, "Start", "Start of measured interval (mm)"),
+    reg(REG_END_CONFIG, "End", "End of measured interval (mm)"),
+    reg(
+        REG_MAX_STEP_LENGTH,
+        "Max Step Length",
+        "Maximum step length",
+    ),
+    reg(REG_MAX_PROFILE, "Max Profile", "Maximum profile setting"),
+    reg(
+        REG_THRESHOLD_SENSITIVITY,
+        "Threshold Sensitivity",
+        "Detection sensitivity (value * 1000)",
+    ),
+];
+
+const DISTANCE_RESULT_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        REG_DISTANCE_RESULT,
+        "Distance Result",
+        "Number of peaks, flags and temperature",
+    ),
+    reg(
+        REG_PEAK0_DISTANCE,
+        "Peak 0 Distance",
+        "Peak 0 distance (mm)",
+    ),
+    reg(
+        REG_PEAK0_STRENGTH,
+        "Peak 0 Strength",
+        "Peak 0 strength (dB * 1000)",
+    ),
+];
+
+const PRESENCE_CONFIG_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        PRESENCE_REG_INTRA_DETECTION_ENABLED_ADDRESS,
+        "Intra Detection",
+        "Fast motion detection enable (0/1)",
+    ),
+    reg(
+        PRESENCE_REG_INTER_DETECTION_ENABLED_ADDRESS,
+        "Inter Detection",
+        "Slow motion detection enable (0/1)",
+    ),
+    reg(
+        PRESENCE_REG_FRAME_RATE_ADDRESS,
+        "Frame Rate",
+        "Frame rate in mHz (value * 1000)",
+    ),
+    reg(
+        PRESENCE_REG_INTRA_DETECTION_THRESHOLD_ADDRESS,
+        "Intra Threshold",
+        "Fast motion threshold (value * 1000)",
+    ),
+    reg(
+        PRESENCE_REG_INTER_DETECTION_THRESHOLD_ADDRESS,
+        "Inter Threshold",
+        "Slow motion threshold (value * 1000)",
+    ),
+    reg(
+        PRESENCE_REG_START_ADDRESS,
+        "Start Point",
+        "Start distance (mm)",
+    ),
+    reg(PRESENCE_REG_END_ADDRESS, "End Point", "End distance (mm)"),
+    reg(
+        PRESENCE_REG_DETECTION_ON_GPIO_ADDRESS,
+        "Detection GPIO",
+        "Output detection on GPIO (0/1)",
+    ),
+];
+
+const PRESENCE_RESULT_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        REG_PRESENCE_RESULT,
+        "Presence Result",
+        "Presence flags and temperature",
+    ),
+    reg(
+        REG_PRESENCE_DISTANCE,
+        "Presence Distance",
+        "Distance to detected presence (mm)",
+    ),
+    reg(
+        REG_INTRA_PRESENCE_SCORE,
+        "Intra Score",
+        "Fast motion score (value * 1000)",
+    ),
+    reg(
+        REG_INTER_PRESENCE_SCORE,
+        "Inter Score",
+        "Slow motion score (value * 1000)",
+    ),
+];
+
+const BREATHING_CONFIG_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        BREATHING_REG_START_ADDRESS,
+        "Start",
+        "Start of measured interval (mm)",
+    ),
+    reg(
+        BREATHING_REG_END_ADDRESS,
+        "End",
+        "End of measured interval (mm)",
+    ),
+    reg(
+        BREATHING_REG_LOWEST_BREATHING_RATE_ADDRESS,
+        "Lowest Breathing Rate",
+        "Lowest breathing rate (bpm)",
+    ),
+    reg(
+        BREATHING_REG_HIGHEST_BREATHING_RATE_ADDRESS,
+        "Highest Breathing Rate",
+        "Highest breathing rate (bpm)",
+    ),
+    reg(
+        BREATHING_REG_FRAME_RATE_ADDRESS,
+        "Frame Rate",
+        "Frame rate in mHz (value * 1000)",
+    ),
+    reg(
+        BREATHING_REG_HWAAS_ADDRESS,
+        "HWAAS",
+        "Hardware accelerated average samples",
+    ),
+    reg(
+        BREATHING_REG_PROFILE_ADDRESS,
+        "Profile",
+        "Radar profile (1-5)",
+    ),
+];
+
+const BREATHING_RESULT_REGISTERS: &[RegisterInfo] = &[
+    reg(
+        BREATHING_REG_BREATHING_RESULT_ADDRESS,
+        "Breathing Result",
+        "Result ready flags and temperature",
+    ),
+    reg(
+        BREATHING_REG_BREATHING_RATE_ADDRESS,
+        "Breathing Rate",
+        "Breaths per minute (value * 1000)",
+    ),
+    reg(
+        BREATHING_REG_APP_STATE_ADDRESS,
+        "App State",
+        "Application state",
+    ),
+];
+
+impl DetectorProtocol for DistanceProtocol {
+    fn name(&self) -> &'static str {
+        "Distance Detector"
+    }
+
+    fn application(&self) -> Application {
+        Application::Distance
+    }
+
+    fn status_register(&self) -> u16 {
+        REG_DETECTOR_STATUS
+    }
+
+    fn command_register(&self) -> u16 {
+        REG_COMMAND
+    }
+
+    fn status_flags(&self) -> &'static [StatusFlag] {
+        DISTANCE_STATUS_FLAGS
+    }
+
+    fn cmd_apply_configuration(&self) -> u32 {
+        CMD_APPLY_CONFIG_AND_CALIBRATE
+    }
+
+    fn cmd_start(&self) -> Option<u32> {
+        None // Distance measurements are triggered one at a time
+    }
+
+    fn cmd_stop(&self) -> Option<u32> {
+        None
+    }
+
+    fn cmd_reset_module(&self) -> u32 {
+        CMD_RESET_MODULE
+    }
+
+    fn config_registers(&self) -> &'static [RegisterInfo] {
+        DISTANCE_CONFIG_REGISTERS
+    }
+
+    fn result_registers(&self) -> &'static [RegisterInfo] {
+        DISTANCE_RESULT_REGISTERS
+    }
+
+    fn parse_result(&self, raw: u32) -> ResultWord {
+        ResultWord::Distance {
+            num_distances: (raw & 0x0000_000F) as u8,
+            near_start_edge: raw & 0x0000_0100 != 0,
+            calibration_needed: raw & 0x0000_0200 != 0,
+            measure_distance_error: raw & 0x0000_0400 != 0,
+            temperature: result_temperature(raw),
+        }
+    }
+}
+
+impl DetectorProtocol for PresenceProtocol {
+    fn name(&self) -> &'static str {
+        "Presence Detector"
+    }
+
+    fn application(&self) -> Application {
+        Application::Presence
+    }
+
+    fn status_register(&self) -> u16 {
+        PRESENCE_REG_DETECTOR_STATUS_ADDRESS
+    }
+
+    fn command_register(&self) -> u16 {
+        PRESENCE_REG_COMMAND_ADDRESS
+    }
+
+    fn status_flags(&self) -> &'static [StatusFlag] {
+        PRESENCE_STATUS_FLAGS
+    }
+
+    fn cmd_apply_configuration(&self) -> u32 {
+        CMD_PRESENCE_APPLY_CONFIGURATION
+    }
+
+    fn cmd_start(&self) -> Option<u32> {
+        Some(CMD_PRESENCE_START_DETECTOR)
+    }
+
+    fn cmd_stop(&self) -> Option<u32> {
+        Some(CMD_PRESENCE_STOP_DETECTOR)
+    }
+
+    fn cmd_reset_module(&self) -> u32 {
+        CMD_PRESENCE_RESET_MODULE
+    }
+
+    fn config_registers(&self) -> &'static [RegisterInfo] {
+        PRESENCE_CONFIG_REGISTERS
+    }
+
+    fn result_registers(&self) -> &'static [RegisterInfo] {
+        PRESENCE_RESULT_REGISTERS
+    }
+
+    fn parse_result(&self, raw: u32) -> ResultWord {
+        ResultWord::Presence {
+            presence_detected: raw & 0x0000_0001 != 0,
+            presence_detected_sticky: raw & 0x0000_0002 != 0,
+            detector_error: raw & 0x0000_8000 != 0,
+            temperature: result_temperature(raw),
+        }
+    }
+}
+
+impl DetectorProtocol for BreathingProtocol {
+    fn name(&self) -> &'static str {
+        "Breathing Monitor"
+    }
+
+    fn application(&self) -> Application {
+        Application::Breathing
+    }
+
+    fn status_register(&self) -> u16 {
+        BREATHING_REG_APP_STATUS_ADDRESS
+    }
+
+    fn command_register(&self) -> u16 {
+        BREATHING_REG_COMMAND_ADDRESS
+    }
+
+    fn status_flags(&self) -> &'static [StatusFlag] {
+        BREATHING_STATUS_FLAGS
+    }
+
+    fn cmd_apply_configuration(&self) -> u32 {
+        CMD_BREATHING_APPLY_CONFIGURATION
+    }
+
+    fn cmd_start(&self) -> Option<u32> {
+        Some(CMD_BREATHING_START_APP)
+    }
+
+    fn cmd_stop(&self) -> Option<u32> {
+        Some(CMD_BREATHING_STOP_APP)
+    }
+
+    fn cmd_reset_module(&self) -> u32 {
+        CMD_BREATHING_RESET_MODULE
+    }
+
+    fn config_registers(&self) -> &'static [RegisterInfo] {
+        BREATHING_CONFIG_REGISTERS
+    }
+
+    fn result_registers(&self) -> &'static [RegisterInfo] {
+        BREATHING_RESULT_REGISTERS
+    }
+
+    fn parse_result(&self, raw: u32) -> ResultWord {
+        ResultWord::Breathing {
+            result_ready: raw & 0x0000_0001 != 0,
+            result_ready_sticky: raw & 0x0000_0002 != 0,
+            temperature: result_temperature(raw),
+        }
+    }
+}
+
+/// Protocol implemented by the given firmware
+pub fn protocol_for_application(application: Application) -> &'static dyn DetectorProtocol {
+    match application {
+        Application::Distance => &DistanceProtocol,
+        Application::Presence => &PresenceProtocol,
+        Application::Breathing => &BreathingProtocol,
+    }
+}
+
+/// Protocol for an application ID read from the module (None if unknown)
+pub fn protocol_for_app_id(app_id: u32) -> Option<&'static dyn DetectorProtocol> {
+    Application::from_app_id(app_id).map(protocol_for_application)
+}
+
+/// RSS (Radar System Software) version decoded from the version register
+///
+/// Register 0 packs the version as major (bits 31-16), minor (bits 15-8) and patch (bits 7-0).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RssVersion {
+    pub major: u16,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+impl RssVersion {
+    pub const fn new(major: u16, minor: u8, patch: u8) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Decode the raw version register value
+    pub fn from_register(raw: u32) -> Self {
+        Self {
+            major: (raw >> 16) as u16,
+            minor: ((raw >> 8) & 0xFF) as u8,
+            patch: (raw & 0xFF) as u8,
+        }
+    }
+}
+
+impl core::fmt::Display for RssVersion {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Oldest RSS release whose register maps cover everything this tool writes
+///
+/// Older presence firmware lacks configuration registers such as auto subsweeps (86) and
+/// silently ignores the writes, so detection runs with a configuration we never applied.
+pub const MIN_RSS_VERSION: RssVersion = RssVersion::new(1, 0, 0);
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_decode_status_per_firmware() {
+        use std::vec;
+
+        let names = |flags: vec::Vec<StatusFlag>| {
+            flags.iter().map(|flag| flag.name).collect::<vec::Vec<_>>()
+        };
+        assert_eq!(
+            names(DistanceProtocol.decode_status(1 << 3)),
+            vec!["DETECTOR_CREATE_OK"]
+        );
+        assert_eq!(
+            names(decode_status(Application::Presence, 1 << 3)),
+            vec!["SENSOR_CALIBRATE_OK"]
+        );
+        let flags = decode_status(
+            Application::Distance,
+            (1 << 23) | STATUS_ERROR_MASK | STATUS_BUSY_MASK,
+        );
+        assert_eq!(
+            names(flags.clone()),
+            vec!["CONFIG_APPLY_ERROR", "DETECTOR_ERROR", "BUSY"]
+        );
+        assert_eq!(flags[0].bit(), 23);
+        assert_eq!(
+            flags[0].description,
+            "The sensor rejected the configuration"
+        );
+        assert_eq!(flags[2].kind, FlagKind::Busy);
+    }
+
+    #[test]
+    fn test_protocol_for_app_id() {
+        assert_eq!(
+            protocol_for_app_id(2).map(|p| p.application()),
+            Some(Application::Presence)
+        );
+        assert!(protocol_for_app_id(0).is_none());
+    }
+
+    #[test]
+    fn test_rss_version_decode_and_order() {
+        let version = RssVersion::from_register(0x0001_0203);
+        assert_eq!(version, RssVersion::new(1, 2, 3));
+        assert_eq!(version.to_string(), "1.2.3");
+        assert!(RssVersion::new(0, 9, 9) < MIN_RSS_VERSION);
+        assert!(RssVersion::new(1, 0, 0) >= MIN_RSS_VERSION);
+    }
+
+    #[test]
+    fn test_ready_busy_and_error() {
+        assert!(PresenceProtocol.is_ready(0x0000_00FF));
+        assert!(!DistanceProtocol.is_ready(0x0000_00FF));
+        assert!(DistanceProtocol.is_ready(0x0000_03FF));
+        assert!(PresenceProtocol.is_busy(0x8000_0000));
+        assert!(PresenceProtocol.has_error(0x1000_0000));
+        assert!(DistanceProtocol.has_error(1 << 24));
+    }
+}
@@ -0,0 +1,23 @@
+//! Acconeer XM125 I2C register protocol
+//!
+//! Register addresses, command codes, status bits and result-word parsers for the
+//! distance detector, presence detector and breathing reference application firmwares.
+//! The crate is `no_std` and has no dependencies so the same definitions can be used by
+//! host tooling (`xm125-radar-monitor`) and by firmware-side Rust code. Disable the
+//! default `alloc` feature on targets without a global allocator.
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+pub mod detector;
+pub mod registers;
+
+#[cfg(feature = "alloc")]
+pub use detector::decode_status;
+pub use detector::{
+    protocol_for_app_id, protocol_for_application, Application, BreathingProtocol,
+    DetectorProtocol, DistanceProtocol, FlagKind, PresenceProtocol, RegisterInfo, ResultWord,
+    RssVersion, StatusFlag, MIN_RSS_VERSION,
+};
@@ -3,8 +3,6 @@
 #![allow(clippy::pedantic)]
 // Based on official Acconeer documentation: distance_reg_protocol.h, presence_reg_protocol.h
 
-use std::time::Duration;
-
 // XM125 I2C Register Addresses (from distance_reg_protocol.h)
 pub const REG_VERSION: u16 = 0; // DISTANCE_REG_VERSION_ADDRESS
 pub const REG_PROTOCOL_STATUS: u16 = 1; // DISTANCE_REG_PROTOCOL_STATUS_ADDRESS
@@ -13,6 +11,7 @@ pub const REG_DETECTOR_STATUS: u16 = 3; // DISTANCE_REG_DETECTOR_STATUS_ADDRESS
 pub const REG_DISTANCE_RESULT: u16 = 16; // DISTANCE_REG_DISTANCE_RESULT_ADDRESS
 pub const REG_PEAK0_DISTANCE: u16 = 17; // DISTANCE_REG_PEAK0_DISTANCE_ADDRESS
 pub const REG_PEAK0_STRENGTH: u16 = 27; // DISTANCE_REG_PEAK0_STRENGTH_ADDRESS
+pub const DISTANCE_MAX_PEAKS: usize = 10; // PEAK0-PEAK9, distances and strengths in consecutive registers
 
 // Distance detector configuration registers (from distance_reg_protocol.h)
 pub const REG_START_CONFIG: u16 = 64; // DISTANCE_REG_START_ADDRESS (0x40)
@@ -44,6 +43,8 @@ pub const PRESENCE_REG_START_ADDRESS: u16 = 82; // PRESENCE_REG_START_ADDRESS
 pub const PRESENCE_REG_END_ADDRESS: u16 = 83; // PRESENCE_REG_END_ADDRESS
 pub const PRESENCE_REG_INTRA_DETECTION_THRESHOLD_ADDRESS: u16 = 70;
 pub const PRESENCE_REG_INTER_DETECTION_THRESHOLD_ADDRESS: u16 = 71;
+pub const PRESENCE_REG_INTRA_DETECTION_ENABLED_ADDRESS: u16 = 67; // 0x0043 - Fast (intra-frame) motion detection enable
+pub const PRESENCE_REG_INTER_DETECTION_ENABLED_ADDRESS: u16 = 68; // 0x0044 - Slow (inter-frame) motion detection enable
 pub const PRESENCE_REG_FRAME_RATE_ADDRESS: u16 = 69;
 pub const PRESENCE_REG_AUTO_PROFILE_ADDRESS: u16 = 78; // 0x004E - Auto profile selection enable/disable
 pub const PRESENCE_REG_AUTO_STEP_LENGTH_ADDRESS: u16 = 79; // 0x004F - Auto step length enable/disable
@@ -53,6 +54,7 @@ pub const PRESENCE_REG_HWAAS_ADDRESS: u16 = 85; // 0x0055 - Hardware accelerated
 pub const PRESENCE_REG_AUTO_SUBSWEEPS_ADDRESS: u16 = 86; // 0x0056 - Automatic subsweeps enable/disable
 pub const PRESENCE_REG_SIGNAL_QUALITY_ADDRESS: u16 = 87; // 0x0057 - Signal quality threshold
 pub const PRESENCE_REG_MANUAL_SUBSWEEPS_ADDRESS: u16 = 88; // 0x0058 - Manual subsweeps (when auto disabled)
+pub const PRESENCE_REG_DETECTION_ON_GPIO_ADDRESS: u16 = 128; // 0x0080 - Drive MISC_GPIO0 high while presence is detected
 
 // Command codes for XM125 (from distance_reg_protocol.h)
 pub const CMD_APPLY_CONFIG_AND_CALIBRATE: u32 = 1; // DISTANCE_REG_COMMAND_ENUM_APPLY_CONFIG_AND_CALIBRATE
@@ -75,24 +77,8 @@ pub const CMD_PRESENCE_DISABLE_UART_LOGS: u32 = 33; // DISABLE UART LOGS - DEBUG
 pub const CMD_PRESENCE_LOG_CONFIGURATION: u32 = 34; // LOG CONFIGURATION - DEBUG: Print detector configuration to UART
 pub const CMD_PRESENCE_RESET_MODULE: u32 = 1381192737; // RESET MODULE - Reset module, needed to make a new configuration
 
-// Status flags (generic for backward compatibility)
-pub const STATUS_RSS_REGISTER_OK: u32 = 0x00000001;
-pub const STATUS_CONFIG_CREATE_OK: u32 = 0x00000002;
-pub const STATUS_SENSOR_CREATE_OK: u32 = 0x00000004;
-pub const STATUS_SENSOR_CALIBRATE_OK: u32 = 0x00000008;
-pub const STATUS_DETECTOR_CREATE_OK: u32 = 0x00000010;
-pub const STATUS_CONFIG_APPLY_OK: u32 = 0x00000080;
-pub const STATUS_BUSY: u32 = 0x80000000;
-pub const STATUS_ERROR: u32 = 0x10000000;
-
-// Legacy compatibility
-pub const STATUS_DETECTOR_READY: u32 = STATUS_DETECTOR_CREATE_OK;
-pub const STATUS_CALIBRATION_DONE: u32 = STATUS_SENSOR_CALIBRATE_OK;
-pub const STATUS_MEASUREMENT_READY: u32 = 0x04;
-
-// Timeout constants - based on Acconeer documentation
-pub const CALIBRATION_TIMEOUT: Duration = Duration::from_secs(2);
-pub const MEASUREMENT_TIMEOUT: Duration = Duration::from_secs(5);
+// The per-firmware OK and error bits, with descriptions, are the `StatusFlag` tables in
+// `detector`; decode them with `decode_status` rather than masking here.
 
 // Distance detector default values (from distance_reg_protocol.h)
 pub const DISTANCE_START_DEFAULT: u32 = 100; // 100mm = 0.1m
@@ -102,9 +88,47 @@ pub const DISTANCE_CLOSE_RANGE_LEAKAGE_CANCELLATION_DEFAULT: u32 = 1; // Enabled
 pub const DISTANCE_SIGNAL_QUALITY_DEFAULT: u32 = 15000; // Signal quality threshold
 pub const DISTANCE_MAX_PROFILE_DEFAULT: u32 = 5; // Profile 5
 pub const DISTANCE_THRESHOLD_METHOD_DEFAULT: u32 = 0; // CFAR method
+pub const DISTANCE_THRESHOLD_METHOD_RECORDED: u32 = 2; // DISTANCE_REG_THRESHOLD_METHOD_ENUM_RECORDED
 pub const DISTANCE_PEAK_SORTING_DEFAULT: u32 = 0; // Strongest peaks
 pub const DISTANCE_NUM_FRAMES_RECORDED_THRESHOLD_DEFAULT: u32 = 100; // Frames for threshold calculation
 pub const DISTANCE_FIXED_AMPLITUDE_THRESHOLD_VALUE_DEFAULT: u32 = 100000; // Fixed amplitude threshold
 pub const DISTANCE_THRESHOLD_SENSITIVITY_DEFAULT: u32 = 100; // 0.1 sensitivity (factor 1000)
 pub const DISTANCE_REFLECTOR_SHAPE_DEFAULT: u32 = 0; // Generic reflector
+pub const DISTANCE_REFLECTOR_SHAPE_PLANAR: u32 = 1; // Planar reflector (liquid surface, wall)
+pub const DISTANCE_SIGNAL_QUALITY_LIQUID: u32 = 20000; // Signal quality threshold for liquid surfaces
+pub const DISTANCE_THRESHOLD_SENSITIVITY_LIQUID: u32 = 50; // 0.05 sensitivity for liquid surfaces
 pub const DISTANCE_FIXED_STRENGTH_THRESHOLD_VALUE_DEFAULT: u32 = 0; // Fixed strength threshold
+
+// Breathing reference application registers (from ref_app_breathing_reg_protocol.h)
+// Only the rate estimate is exposed; the displacement time series stays on the module.
+pub const BREATHING_REG_APP_STATUS_ADDRESS: u16 = 3;
+pub const BREATHING_REG_BREATHING_RESULT_ADDRESS: u16 = 16;
+pub const BREATHING_REG_BREATHING_RATE_ADDRESS: u16 = 17; // Breaths per minute * 1000
+pub const BREATHING_REG_APP_STATE_ADDRESS: u16 = 18;
+pub const BREATHING_REG_START_ADDRESS: u16 = 64; // Start of measured interval in mm
+pub const BREATHING_REG_END_ADDRESS: u16 = 65; // End of measured interval in mm
+pub const BREATHING_REG_NUM_DISTANCES_TO_ANALYZE_ADDRESS: u16 = 66;
+pub const BREATHING_REG_DISTANCE_DETERMINATION_DURATION_S_ADDRESS: u16 = 67;
+pub const BREATHING_REG_USE_PRESENCE_PROCESSOR_ADDRESS: u16 = 68;
+pub const BREATHING_REG_LOWEST_BREATHING_RATE_ADDRESS: u16 = 69; // Breaths per minute
+pub const BREATHING_REG_HIGHEST_BREATHING_RATE_ADDRESS: u16 = 70; // Breaths per minute
+pub const BREATHING_REG_TIME_SERIES_LENGTH_S_ADDRESS: u16 = 71;
+pub const BREATHING_REG_FRAME_RATE_ADDRESS: u16 = 72; // Frame rate in mHz
+pub const BREATHING_REG_SWEEPS_PER_FRAME_ADDRESS: u16 = 73;
+pub const BREATHING_REG_HWAAS_ADDRESS: u16 = 74;
+pub const BREATHING_REG_PROFILE_ADDRESS: u16 = 75;
+pub const BREATHING_REG_INTRA_DETECTION_THRESHOLD_ADDRESS: u16 = 76;
+pub const BREATHING_REG_COMMAND_ADDRESS: u16 = 256;
+
+// Breathing reference application commands
+pub const CMD_BREATHING_APPLY_CONFIGURATION: u32 = 1;
+pub const CMD_BREATHING_START_APP: u32 = 2;
+pub const CMD_BREATHING_STOP_APP: u32 = 3;
+pub const CMD_BREATHING_RESET_MODULE: u32 = 1381192737;
+
+// Breathing application states (BREATHING_REG_APP_STATE)
+pub const BREATHING_APP_STATE_INIT: u32 = 0;
+pub const BREATHING_APP_STATE_NO_PRESENCE: u32 = 1;
+pub const BREATHING_APP_STATE_INTRA_PRESENCE: u32 = 2;
+pub const BREATHING_APP_STATE_DETERMINE_DISTANCE: u32 = 3;
+pub const BREATHING_APP_STATE_ESTIMATE_BREATHING_RATE: u32 = 4;